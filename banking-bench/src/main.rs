@@ -474,6 +474,7 @@ fn main() {
         None,
         bank_forks.clone(),
         &prioritization_fee_cache,
+        None,
     );
 
     // This is so that the signal_receiver does not go out of scope after the closure.
@@ -0,0 +1,123 @@
+//! Connection reuse by the producers under `--report-cache-stats`.
+//!
+//! The connection cache does not expose its hit/miss counters, so reuse is
+//! inferred from the `get_connection()` calls made by the producers: each
+//! endpoint can open at most `connection_pool_size` connections, every other
+//! call must have reused a pooled connection.
+
+use {
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::HashSet,
+        fmt,
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
+    },
+};
+
+#[derive(Default)]
+pub struct CacheStats {
+    get_connection_calls: AtomicU64,
+    endpoints: Mutex<HashSet<SocketAddr>>,
+}
+
+impl CacheStats {
+    pub fn record_get_connection(&self, addr: &SocketAddr) {
+        self.get_connection_calls.fetch_add(1, Ordering::Relaxed);
+        self.endpoints.lock().unwrap().insert(*addr);
+    }
+
+    pub fn summary(&self, connection_pool_size: usize) -> CacheStatsSummary {
+        CacheStatsSummary {
+            get_connection_calls: self.get_connection_calls.load(Ordering::Relaxed),
+            distinct_endpoints: self.endpoints.lock().unwrap().len(),
+            connection_pool_size,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStatsSummary {
+    pub get_connection_calls: u64,
+    pub distinct_endpoints: usize,
+    pub connection_pool_size: usize,
+}
+
+impl CacheStatsSummary {
+    /// Upper bound of the connections opened by the cache
+    pub fn max_connections(&self) -> u64 {
+        ((self.distinct_endpoints * self.connection_pool_size) as u64)
+            .min(self.get_connection_calls)
+    }
+
+    /// Lower bound of the `get_connection()` calls served by a pooled connection
+    pub fn min_reused(&self) -> u64 {
+        self.get_connection_calls - self.max_connections()
+    }
+}
+
+impl fmt::Display for CacheStatsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let min_reuse_percent = if self.get_connection_calls == 0 {
+            0.0
+        } else {
+            self.min_reused() as f64 * 100.0 / self.get_connection_calls as f64
+        };
+        write!(
+            f,
+            "Connection cache: {} get_connection calls to {} distinct endpoints, pool size {}, \
+             at most {} connections opened, at least {} calls ({min_reuse_percent:.2}%) reused \
+             a pooled connection",
+            self.get_connection_calls,
+            self.distinct_endpoints,
+            self.connection_pool_size,
+            self.max_connections(),
+            self.min_reused(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_stats_single_destination() {
+        let cache_stats = CacheStats::default();
+        let addr: SocketAddr = "127.0.0.1:8000".parse().unwrap();
+        for _ in 0..10 {
+            cache_stats.record_get_connection(&addr);
+        }
+
+        let summary = cache_stats.summary(1);
+        assert_eq!(
+            summary,
+            CacheStatsSummary {
+                get_connection_calls: 10,
+                distinct_endpoints: 1,
+                connection_pool_size: 1,
+            }
+        );
+        assert_eq!(summary.max_connections(), 1);
+        assert_eq!(summary.min_reused(), 9);
+        assert_eq!(
+            summary.to_string(),
+            "Connection cache: 10 get_connection calls to 1 distinct endpoints, pool size 1, \
+             at most 1 connections opened, at least 9 calls (90.00%) reused a pooled connection"
+        );
+
+        // a pool larger than the number of calls may not have reused anything
+        assert_eq!(cache_stats.summary(256).min_reused(), 0);
+    }
+
+    #[test]
+    fn test_cache_stats_empty() {
+        let summary = CacheStats::default().summary(1);
+        assert_eq!(summary.max_connections(), 0);
+        assert_eq!(summary.min_reused(), 0);
+        assert!(summary.to_string().contains("(0.00%)"));
+    }
+}
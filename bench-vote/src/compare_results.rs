@@ -0,0 +1,332 @@
+//! Comparison of the results of two runs, under `compare-results`.
+//!
+//! The results of a current run are compared to those of a baseline, both
+//! written by `--results-file`. Runs over different transports, producer
+//! counts or vote instructions are not comparable, unless forced. A metric
+//! regresses when it is worse than the baseline by more than its threshold,
+//! in percent of the baseline. The loss and the maximum latency are only
+//! reported, as a single outlier moves them.
+
+use {crate::results::RunResults, std::fmt::Write};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RegressionThresholds {
+    pub max_throughput_regression_pct: f64,
+    pub max_latency_regression_pct: f64,
+}
+
+/// The parameters of the runs that differ, making their results incomparable
+pub fn incompatibilities(baseline: &RunResults, current: &RunResults) -> Vec<String> {
+    let mut incompatibilities = vec![];
+    let mut check = |name: &str, baseline: String, current: String| {
+        if baseline != current {
+            incompatibilities.push(format!("{name}: {baseline} in the baseline, {current} now"));
+        }
+    };
+    let transport = |results: &RunResults| {
+        let parameters = &results.parameters;
+        format!(
+            "{}{}",
+            if parameters.use_quic { "QUIC" } else { "UDP" },
+            if parameters.use_connection_cache {
+                " through the connection cache"
+            } else {
+                ""
+            }
+        )
+    };
+    check("transport", transport(baseline), transport(current));
+    check(
+        "producers",
+        baseline.parameters.num_producers.to_string(),
+        current.parameters.num_producers.to_string(),
+    );
+    let vote_instruction = |results: &RunResults| {
+        results.vote_instruction.map_or_else(
+            || "an unknown vote instruction".to_string(),
+            |v| v.to_string(),
+        )
+    };
+    check(
+        "vote instruction",
+        vote_instruction(baseline),
+        vote_instruction(current),
+    );
+    incompatibilities
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    Throughput,
+    LossPct,
+    LatencyP50,
+    LatencyP90,
+    LatencyP99,
+    LatencyMax,
+}
+
+impl Metric {
+    const ALL: [Self; 6] = [
+        Self::Throughput,
+        Self::LossPct,
+        Self::LatencyP50,
+        Self::LatencyP90,
+        Self::LatencyP99,
+        Self::LatencyMax,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Throughput => "throughput (tx/s)",
+            Self::LossPct => "loss (%)",
+            Self::LatencyP50 => "send latency p50 (us)",
+            Self::LatencyP90 => "send latency p90 (us)",
+            Self::LatencyP99 => "send latency p99 (us)",
+            Self::LatencyMax => "send latency max (us)",
+        }
+    }
+
+    /// Value of the metric in `results`, `None` if it was not measured
+    fn value(&self, results: &RunResults) -> Option<f64> {
+        let latency = results.send_latency.as_ref();
+        match self {
+            Self::Throughput => results.throughput,
+            Self::LossPct => results.loss_pct(),
+            Self::LatencyP50 => latency.map(|latency| latency.p50_us as f64),
+            Self::LatencyP90 => latency.map(|latency| latency.p90_us as f64),
+            Self::LatencyP99 => latency.map(|latency| latency.p99_us as f64),
+            Self::LatencyMax => latency.map(|latency| latency.max_us as f64),
+        }
+    }
+
+    fn higher_is_better(&self) -> bool {
+        matches!(self, Self::Throughput)
+    }
+
+    /// Largest regression allowed, `None` if the metric is only reported
+    fn threshold_pct(&self, thresholds: &RegressionThresholds) -> Option<f64> {
+        match self {
+            Self::Throughput => Some(thresholds.max_throughput_regression_pct),
+            Self::LatencyP50 | Self::LatencyP90 | Self::LatencyP99 => {
+                Some(thresholds.max_latency_regression_pct)
+            }
+            Self::LossPct | Self::LatencyMax => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MetricComparison {
+    pub metric: Metric,
+    pub baseline: f64,
+    pub current: f64,
+}
+
+impl MetricComparison {
+    /// Change from the baseline, in percent of the baseline, `None` if the
+    /// baseline is zero
+    pub fn delta_pct(&self) -> Option<f64> {
+        (self.baseline != 0.0).then(|| (self.current - self.baseline) * 100.0 / self.baseline)
+    }
+
+    /// How much worse than the baseline the current run is, in percent of the
+    /// baseline, zero if it is not worse
+    pub fn regression_pct(&self) -> f64 {
+        let delta_pct = self.delta_pct().unwrap_or_default();
+        let regression_pct = if self.metric.higher_is_better() {
+            -delta_pct
+        } else {
+            delta_pct
+        };
+        regression_pct.max(0.0)
+    }
+
+    pub fn is_regression(&self, thresholds: &RegressionThresholds) -> bool {
+        self.metric
+            .threshold_pct(thresholds)
+            .is_some_and(|threshold_pct| self.regression_pct() > threshold_pct)
+    }
+}
+
+/// The metrics of two runs, those missing from either run left out
+#[derive(Debug, PartialEq)]
+pub struct Comparison {
+    pub metrics: Vec<MetricComparison>,
+}
+
+impl Comparison {
+    pub fn new(baseline: &RunResults, current: &RunResults) -> Self {
+        let metrics = Metric::ALL
+            .into_iter()
+            .filter_map(|metric| {
+                Some(MetricComparison {
+                    metric,
+                    baseline: metric.value(baseline)?,
+                    current: metric.value(current)?,
+                })
+            })
+            .collect();
+        Self { metrics }
+    }
+
+    pub fn regressions(&self, thresholds: &RegressionThresholds) -> Vec<&MetricComparison> {
+        self.metrics
+            .iter()
+            .filter(|metric| metric.is_regression(thresholds))
+            .collect()
+    }
+
+    /// Exit code of `compare-results`, nonzero if any metric regressed
+    pub fn exit_code(&self, thresholds: &RegressionThresholds) -> i32 {
+        if self.regressions(thresholds).is_empty() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// The comparison as a table, each regression marked
+    pub fn table(&self, thresholds: &RegressionThresholds) -> String {
+        let mut table = format!(
+            "{:<24} {:>14} {:>14} {:>10}\n",
+            "Metric", "Baseline", "Current", "Delta"
+        );
+        for metric in &self.metrics {
+            let delta = metric
+                .delta_pct()
+                .map_or_else(|| "-".to_string(), |delta_pct| format!("{delta_pct:+.2}%"));
+            let _ = writeln!(
+                table,
+                "{:<24} {:>14.2} {:>14.2} {:>10}{}",
+                metric.metric.name(),
+                metric.baseline,
+                metric.current,
+                delta,
+                if metric.is_regression(thresholds) {
+                    "  REGRESSION"
+                } else {
+                    ""
+                },
+            );
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::vote_instruction_variant::VoteInstructionVariant};
+
+    const BASELINE: &str = include_str!("../tests/fixtures/results_baseline.json");
+    const REGRESSED: &str = include_str!("../tests/fixtures/results_regressed.json");
+    const UNVERSIONED: &str = include_str!("../tests/fixtures/results_unversioned.json");
+
+    const THRESHOLDS: RegressionThresholds = RegressionThresholds {
+        max_throughput_regression_pct: 5.0,
+        max_latency_regression_pct: 10.0,
+    };
+
+    fn results(json: &str) -> RunResults {
+        RunResults::from_json(json).unwrap()
+    }
+
+    #[test]
+    fn test_compare_results() {
+        let baseline = results(BASELINE);
+        let comparison = Comparison::new(&baseline, &results(REGRESSED));
+        assert_eq!(
+            comparison
+                .metrics
+                .iter()
+                .map(|metric| (metric.metric, metric.baseline, metric.current))
+                .collect::<Vec<_>>(),
+            vec![
+                (Metric::Throughput, 100_000.0, 90_000.0),
+                (Metric::LossPct, 1.0, 2.0),
+                (Metric::LatencyP50, 40.0, 42.0),
+                (Metric::LatencyP90, 80.0, 96.0),
+                (Metric::LatencyP99, 200.0, 200.0),
+                (Metric::LatencyMax, 5_000.0, 20_000.0),
+            ]
+        );
+        assert_eq!(comparison.metrics[0].delta_pct(), Some(-10.0));
+        assert_eq!(comparison.metrics[0].regression_pct(), 10.0);
+        assert_eq!(comparison.metrics[3].regression_pct(), 20.0);
+        assert_eq!(comparison.metrics[4].regression_pct(), 0.0);
+
+        // the loss and max latency are reported but never regress
+        assert_eq!(
+            comparison
+                .regressions(&THRESHOLDS)
+                .iter()
+                .map(|metric| metric.metric)
+                .collect::<Vec<_>>(),
+            vec![Metric::Throughput, Metric::LatencyP90]
+        );
+        assert_eq!(comparison.exit_code(&THRESHOLDS), 1);
+        let table = comparison.table(&THRESHOLDS);
+        assert_eq!(table.matches("REGRESSION").count(), 2);
+        assert!(table.contains("-10.00%"));
+
+        let lenient = RegressionThresholds {
+            max_throughput_regression_pct: 10.0,
+            max_latency_regression_pct: 20.0,
+        };
+        assert_eq!(comparison.exit_code(&lenient), 0);
+
+        // an improvement is never a regression
+        let improved = Comparison::new(&results(REGRESSED), &baseline);
+        assert!(improved.regressions(&THRESHOLDS).is_empty());
+        assert_eq!(improved.exit_code(&THRESHOLDS), 0);
+    }
+
+    #[test]
+    fn test_compare_results_missing_metrics() {
+        // written before the results were versioned, by a client only run
+        let unversioned = results(UNVERSIONED);
+        assert_eq!(unversioned.version, 0);
+        assert_eq!(unversioned.send_latency, None);
+        assert_eq!(unversioned.loss_pct(), None);
+
+        let comparison = Comparison::new(&results(BASELINE), &unversioned);
+        assert_eq!(comparison.metrics.len(), 1);
+        assert_eq!(comparison.metrics[0].metric, Metric::Throughput);
+    }
+
+    #[test]
+    fn test_incompatibilities() {
+        let baseline = results(BASELINE);
+        assert!(incompatibilities(&baseline, &results(REGRESSED)).is_empty());
+
+        let mut current = results(REGRESSED);
+        current.parameters.use_quic = true;
+        current.parameters.num_producers = 8;
+        current.vote_instruction = Some(VoteInstructionVariant::TowerSync);
+        assert_eq!(
+            incompatibilities(&baseline, &current),
+            vec![
+                "transport: UDP in the baseline, QUIC now",
+                "producers: 4 in the baseline, 8 now",
+                "vote instruction: vote in the baseline, tower-sync now",
+            ]
+        );
+
+        // the number of transactions does not change what is measured
+        let mut current = results(REGRESSED);
+        current.parameters.transactions_per_producer *= 2;
+        assert!(incompatibilities(&baseline, &current).is_empty());
+
+        assert_eq!(
+            incompatibilities(&baseline, &results(UNVERSIONED)),
+            vec!["vote instruction: vote in the baseline, an unknown vote instruction now"]
+        );
+    }
+
+    #[test]
+    fn test_results_version() {
+        let newer = BASELINE.replacen("\"version\": 1", "\"version\": 2", 1);
+        assert!(RunResults::from_json(&newer)
+            .unwrap_err()
+            .contains("unsupported version 2"));
+    }
+}
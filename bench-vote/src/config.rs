@@ -0,0 +1,304 @@
+//! Defaults of the command-line flags under `--config`.
+//!
+//! The TOML file holds a key per flag, named after its long form. A flag
+//! passed on the command line overrides the value of the file. The values of
+//! the file are validated as if they had been passed on the command line.
+
+use {
+    crate::preset::Preset,
+    clap::{App, Arg, ArgMatches},
+    serde::Deserialize,
+    std::{
+        ffi::{OsStr, OsString},
+        fs,
+        path::Path,
+        process,
+    },
+};
+
+pub fn config_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("config")
+        .long("config")
+        .value_name("PATH")
+        .takes_value(true)
+        .help(
+            "Read the defaults of the other flags from this TOML file, with a key per flag named \
+             after its long form. Flags passed on the command line override the file",
+        )
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct BenchVoteConfig {
+    pub identity: Option<String>,
+    pub preset: Option<Preset>,
+    pub num_recv_sockets: Option<usize>,
+    pub num_producers: Option<u64>,
+    pub transactions_per_producer: Option<u64>,
+    pub use_connection_cache: Option<bool>,
+    pub use_quic: Option<bool>,
+    pub server_only: Option<bool>,
+    pub client_only: Option<bool>,
+    pub server_address: Option<String>,
+    pub control_port: Option<u16>,
+    pub control: Option<String>,
+    pub verbose: Option<bool>,
+    pub dscp: Option<u8>,
+    pub report_dscp: Option<bool>,
+    pub vote_instruction: Option<String>,
+    pub show_transaction_details: Option<bool>,
+    pub report_cache_stats: Option<bool>,
+    pub max_memory_mb: Option<u64>,
+    pub max_connections_per_peer: Option<usize>,
+    pub max_connections_per_ip_per_min: Option<u64>,
+    pub forward_to: Option<String>,
+    pub tee: Option<bool>,
+    pub forward_use_quic: Option<bool>,
+    pub forward_identity: Option<String>,
+    pub forward_queue_size: Option<usize>,
+    pub results_file: Option<String>,
+    pub samples_file: Option<String>,
+    pub rotate_identity_secs: Option<u64>,
+    pub rotate_identity_dir: Option<String>,
+    pub pause_schedule: Option<String>,
+    pub teardown_on_pause: Option<bool>,
+    pub fork_producers: Option<bool>,
+    pub seed: Option<u64>,
+    pub dump_workload: Option<String>,
+    pub replay_workload: Option<String>,
+}
+
+impl BenchVoteConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read config file {}: {err}", path.display()))?;
+        toml::from_str(&contents)
+            .map_err(|err| format!("invalid config file {}: {err}", path.display()))
+    }
+
+    /// The values of the file as command-line arguments, leaving out the
+    /// flags passed in `passed`
+    pub fn args(&self, passed: &[OsString]) -> Vec<OsString> {
+        let mut args = Args {
+            passed,
+            args: vec![],
+        };
+        args.value("identity", &self.identity);
+        args.value("preset", &self.preset);
+        args.value("num-recv-sockets", &self.num_recv_sockets);
+        args.value("num-producers", &self.num_producers);
+        args.value("transactions-per-producer", &self.transactions_per_producer);
+        args.switch("use-connection-cache", self.use_connection_cache);
+        args.value("use-quic", &self.use_quic);
+        args.switch("server-only", self.server_only);
+        args.switch("client-only", self.client_only);
+        args.value("server-address", &self.server_address);
+        args.value("control-port", &self.control_port);
+        args.value("control", &self.control);
+        args.switch("verbose", self.verbose);
+        args.value("dscp", &self.dscp);
+        args.switch("report-dscp", self.report_dscp);
+        args.value("vote-instruction", &self.vote_instruction);
+        args.switch("show-transaction-details", self.show_transaction_details);
+        args.switch("report-cache-stats", self.report_cache_stats);
+        args.value("max-memory-mb", &self.max_memory_mb);
+        args.value("max-connections-per-peer", &self.max_connections_per_peer);
+        args.value(
+            "max-connections-per-ip-per-min",
+            &self.max_connections_per_ip_per_min,
+        );
+        args.value("forward-to", &self.forward_to);
+        args.switch("tee", self.tee);
+        args.value("forward-use-quic", &self.forward_use_quic);
+        args.value("forward-identity", &self.forward_identity);
+        args.value("forward-queue-size", &self.forward_queue_size);
+        args.value("results-file", &self.results_file);
+        args.value("samples-file", &self.samples_file);
+        args.value("rotate-identity-secs", &self.rotate_identity_secs);
+        args.value("rotate-identity-dir", &self.rotate_identity_dir);
+        args.value("pause-schedule", &self.pause_schedule);
+        args.switch("teardown-on-pause", self.teardown_on_pause);
+        args.switch("fork-producers", self.fork_producers);
+        args.value("seed", &self.seed);
+        args.value("dump-workload", &self.dump_workload);
+        args.value("replay-workload", &self.replay_workload);
+        args.args
+    }
+}
+
+/// Short forms of the flags of the file that have one
+const SHORT_FLAGS: &[(&str, &str)] = &[("identity", "-i"), ("server-address", "-n")];
+
+/// Whether the flag `name` is in `args`, in its long or short form
+fn is_passed(args: &[OsString], name: &str) -> bool {
+    let long = format!("--{name}");
+    let short = SHORT_FLAGS
+        .iter()
+        .find(|(long_name, _)| *long_name == name)
+        .map(|(_, short)| *short);
+    args.iter().filter_map(|arg| arg.to_str()).any(|arg| {
+        arg == long
+            || arg
+                .strip_prefix(long.as_str())
+                .is_some_and(|rest| rest.starts_with('='))
+            || short.is_some_and(|short| arg.starts_with(short))
+    })
+}
+
+/// The value of `--config` in `args`
+fn config_path(args: &[OsString]) -> Option<&OsStr> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(OsString::as_os_str);
+        }
+        if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--config=")) {
+            return Some(OsStr::new(path));
+        }
+    }
+    None
+}
+
+struct Args<'p> {
+    passed: &'p [OsString],
+    args: Vec<OsString>,
+}
+
+impl Args<'_> {
+    fn value(&mut self, name: &str, value: &Option<impl ToString>) {
+        if let Some(value) = value {
+            if !is_passed(self.passed, name) {
+                self.args.push(format!("--{name}").into());
+                self.args.push(value.to_string().into());
+            }
+        }
+    }
+
+    fn switch(&mut self, name: &str, value: Option<bool>) {
+        if value == Some(true) && !is_passed(self.passed, name) {
+            self.args.push(format!("--{name}").into());
+        }
+    }
+}
+
+/// Parse `args` with `app`, taking the flags left out from the file of
+/// `--config`, if any. The file is merged into `args` before they are parsed,
+/// so that they are validated once, with the values of the file.
+pub fn get_matches_with_config<'a>(app: App<'a, '_>, args: Vec<OsString>) -> ArgMatches<'a> {
+    let config_args = match config_path(&args) {
+        Some(path) => BenchVoteConfig::load(Path::new(path))
+            .unwrap_or_else(|err| {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            })
+            .args(args.get(1..).unwrap_or_default()),
+        None => vec![],
+    };
+    // ahead of the arguments passed, which may end with a subcommand
+    let mut args = args.into_iter();
+    app.get_matches_from(args.next().into_iter().chain(config_args).chain(args))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::app, std::io::Write};
+
+    fn parse(args: &[&str]) -> ArgMatches<'static> {
+        get_matches_with_config(
+            app(),
+            std::iter::once("solana-bench-vote")
+                .chain(args.iter().copied())
+                .map(OsString::from)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_config_file() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        config_file
+            .write_all(
+                b"num-producers = 7\n\
+                  use-quic = true\n\
+                  verbose = true\n\
+                  server-only = false\n\
+                  vote-instruction = \"tower-sync\"\n\
+                  results-file = \"results.json\"\n",
+            )
+            .unwrap();
+        let config_path = config_file.path().to_str().unwrap();
+
+        // the values of the file are used for the flags left out
+        let matches = parse(&["--config", config_path]);
+        assert_eq!(matches.value_of("num-producers"), Some("7"));
+        assert_eq!(matches.value_of("use-quic"), Some("true"));
+        assert!(matches.is_present("verbose"));
+        assert!(!matches.is_present("server-only"));
+        assert_eq!(matches.value_of("vote-instruction"), Some("tower-sync"));
+        assert_eq!(matches.value_of("results-file"), Some("results.json"));
+        // and the flags missing from the file keep their defaults
+        assert_eq!(
+            matches.value_of("stall-threshold-ms"),
+            Some(crate::DEFAULT_STALL_THRESHOLD_MS)
+        );
+
+        // the flags passed override the file
+        let matches = parse(&[
+            "--config",
+            config_path,
+            "--num-producers",
+            "3",
+            "--use-quic",
+            "false",
+            "--verbose",
+        ]);
+        assert_eq!(matches.value_of("num-producers"), Some("3"));
+        assert_eq!(matches.value_of("use-quic"), Some("false"));
+        assert_eq!(matches.occurrences_of("verbose"), 1);
+        assert_eq!(matches.value_of("vote-instruction"), Some("tower-sync"));
+    }
+
+    #[test]
+    fn test_config_file_merged_before_parsing() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        config_file
+            .write_all(b"server-only = true\nserver-address = \"127.0.0.1:8000\"\n")
+            .unwrap();
+        let config_path = config_file.path().to_str().unwrap();
+
+        // the flags passed may rely on the file
+        let matches = parse(&[&format!("--config={config_path}"), "--control-port", "9000"]);
+        assert!(matches.is_present("server-only"));
+        assert_eq!(matches.value_of("control-port"), Some("9000"));
+
+        // a flag passed in its short form overrides the file
+        let matches = parse(&["--config", config_path, "-n", "127.0.0.1:9000"]);
+        assert_eq!(matches.value_of("server-address"), Some("127.0.0.1:9000"));
+
+        // the file applies ahead of a subcommand
+        let matches = parse(&["--config", config_path, "compare-results", "a", "b"]);
+        assert!(matches.is_present("server-only"));
+        assert!(matches.subcommand_matches("compare-results").is_some());
+    }
+
+    #[test]
+    fn test_config_file_errors() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        config_file.write_all(b"num-producer = 7\n").unwrap();
+        assert!(BenchVoteConfig::load(config_file.path()).is_err());
+        assert!(BenchVoteConfig::load(Path::new("unknownfile")).is_err());
+
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        config_file
+            .write_all(b"preset = \"latency\"\nmax-memory-mb = 512\n")
+            .unwrap();
+        assert_eq!(
+            BenchVoteConfig::load(config_file.path()).unwrap(),
+            BenchVoteConfig {
+                preset: Some(Preset::Latency),
+                max_memory_mb: Some(512),
+                ..BenchVoteConfig::default()
+            }
+        );
+    }
+}
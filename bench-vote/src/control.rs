@@ -0,0 +1,354 @@
+//! Control channel between a client and a server running as separate processes.
+//!
+//! The server, with `--control-port`, accepts TCP connections on which each
+//! side sends one JSON message per line. The client says hello with the
+//! transport it uses, the server answers with its readiness, bound data-plane
+//! addresses, transport and run id, and once the producers are done the client
+//! tells the server, which then prints its summary and exits.
+//!
+//! Every message carries the protocol version of its sender. Fields unknown to
+//! the receiver are ignored, so a newer peer stays compatible as long as it
+//! only adds fields.
+
+use {
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
+    std::{
+        fmt,
+        io::{self, BufRead, BufReader, ErrorKind, Write},
+        net::{SocketAddr, TcpListener, TcpStream},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread::{self, sleep, JoinHandle},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+pub const CONTROL_PROTOCOL_VERSION: u32 = 1;
+/// Oldest protocol version of a peer this side understands
+const MIN_CONTROL_PROTOCOL_VERSION: u32 = 1;
+
+const CONTROL_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const CONTROL_ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Time given to the packets still in flight once the client is done
+const CONTROL_DRAIN_TIME: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    Udp,
+    Quic,
+}
+
+impl Transport {
+    pub fn new(use_quic: bool) -> Self {
+        if use_quic {
+            Self::Quic
+        } else {
+            Self::Udp
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Udp => write!(f, "UDP"),
+            Self::Quic => write!(f, "QUIC"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Hello {
+        version: u32,
+        transport: Transport,
+    },
+    /// The producers are done, after sending `sent` transactions
+    Done {
+        version: u32,
+        sent: u64,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerHello {
+    pub version: u32,
+    pub ready: bool,
+    pub run_id: String,
+    pub transport: Transport,
+    /// Addresses the receivers are bound to, possibly unspecified
+    pub data_addresses: Vec<SocketAddr>,
+}
+
+impl ServerHello {
+    pub fn new(transport: Transport, data_addresses: Vec<SocketAddr>) -> Self {
+        let start = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            version: CONTROL_PROTOCOL_VERSION,
+            ready: true,
+            run_id: format!("{:x}-{}", start.as_millis(), std::process::id()),
+            transport,
+            data_addresses,
+        }
+    }
+
+    /// The data-plane address to send to, reached at the host of `control_addr`
+    /// if the server is bound to an unspecified address
+    pub fn data_address(&self, control_addr: SocketAddr) -> Option<SocketAddr> {
+        self.data_addresses.first().map(|addr| {
+            if addr.ip().is_unspecified() {
+                SocketAddr::new(control_addr.ip(), addr.port())
+            } else {
+                *addr
+            }
+        })
+    }
+}
+
+fn check_version(version: u32) -> io::Result<()> {
+    if version < MIN_CONTROL_PROTOCOL_VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "unsupported control protocol version {version}, at least \
+                 {MIN_CONTROL_PROTOCOL_VERSION} is required"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn send(stream: &mut impl Write, message: &impl Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    stream.flush()
+}
+
+fn receive<T: DeserializeOwned>(reader: &mut impl BufRead) -> io::Result<T> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "control connection closed",
+        ));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// The client side of an established control connection
+pub struct ControlClient {
+    stream: TcpStream,
+}
+
+impl ControlClient {
+    /// Say hello to the server at `addr`, failing if it is not ready or does
+    /// not receive `transport`
+    pub fn connect(addr: SocketAddr, transport: Transport) -> io::Result<(Self, ServerHello)> {
+        let mut stream = TcpStream::connect_timeout(&addr, CONTROL_CONNECT_TIMEOUT)?;
+        send(
+            &mut stream,
+            &ClientMessage::Hello {
+                version: CONTROL_PROTOCOL_VERSION,
+                transport,
+            },
+        )?;
+        let hello: ServerHello = receive(&mut BufReader::new(&stream))?;
+        check_version(hello.version)?;
+        if hello.transport != transport {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "the client sends over {transport} but the server receives {}, start both \
+                     with the same --use-quic",
+                    hello.transport
+                ),
+            ));
+        }
+        if !hello.ready {
+            return Err(io::Error::other("the server is not ready"));
+        }
+        Ok((Self { stream }, hello))
+    }
+
+    /// Tell the server the run is over, so that it reports and exits
+    pub fn done(mut self, sent: u64) -> io::Result<()> {
+        send(
+            &mut self.stream,
+            &ClientMessage::Done {
+                version: CONTROL_PROTOCOL_VERSION,
+                sent,
+            },
+        )
+    }
+}
+
+/// Answer the hello of a client, then wait for it to be done.
+/// Returns the number of transactions the client sent.
+fn serve_client(stream: TcpStream, hello: &ServerHello) -> io::Result<u64> {
+    let mut reader = BufReader::new(&stream);
+    let mut writer = &stream;
+    match receive(&mut reader)? {
+        ClientMessage::Hello { version, transport } => {
+            check_version(version)?;
+            send(&mut writer, hello)?;
+            if transport != hello.transport {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("rejected a client sending over {transport}"),
+                ));
+            }
+        }
+        message => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("expected a hello, got {message:?}"),
+            ))
+        }
+    }
+    match receive(&mut reader)? {
+        ClientMessage::Done { sent, .. } => Ok(sent),
+        message => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("expected the end of the run, got {message:?}"),
+        )),
+    }
+}
+
+/// Serve control connections on `listener` until a client signals the end of
+/// its run, then set `exit` once the packets in flight had time to arrive
+pub fn spawn_control_server(
+    listener: TcpListener,
+    hello: ServerHello,
+    exit: Arc<AtomicBool>,
+) -> io::Result<JoinHandle<()>> {
+    listener.set_nonblocking(true)?;
+    Ok(thread::spawn(move || {
+        while !exit.load(Ordering::Relaxed) {
+            let (stream, peer) = match listener.accept() {
+                Ok(connection) => connection,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    sleep(CONTROL_ACCEPT_POLL_INTERVAL);
+                    continue;
+                }
+                Err(err) => {
+                    eprintln!("Error: failed to accept a control connection: {err}");
+                    continue;
+                }
+            };
+            let served = stream
+                .set_nonblocking(false)
+                .and_then(|()| serve_client(stream, &hello));
+            match served {
+                Ok(sent) => {
+                    println!("Client {peer} is done after sending {sent} transactions");
+                    sleep(CONTROL_DRAIN_TIME);
+                    exit.store(true, Ordering::Relaxed);
+                }
+                Err(err) => eprintln!("Error: control connection from {peer} failed: {err}"),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::net::Ipv4Addr};
+
+    #[test]
+    fn test_messages_round_trip() {
+        let messages = [
+            ClientMessage::Hello {
+                version: CONTROL_PROTOCOL_VERSION,
+                transport: Transport::Quic,
+            },
+            ClientMessage::Done {
+                version: CONTROL_PROTOCOL_VERSION,
+                sent: 4_000_000,
+            },
+        ];
+        for message in messages {
+            let json = serde_json::to_string(&message).unwrap();
+            assert_eq!(
+                serde_json::from_str::<ClientMessage>(&json).unwrap(),
+                message
+            );
+        }
+
+        let hello = ServerHello::new(Transport::Udp, vec!["0.0.0.0:8000".parse().unwrap()]);
+        let json = serde_json::to_string(&hello).unwrap();
+        assert_eq!(serde_json::from_str::<ServerHello>(&json).unwrap(), hello);
+    }
+
+    #[test]
+    fn test_messages_forward_compatible() {
+        // a newer peer adding fields
+        let hello: ServerHello = serde_json::from_str(
+            r#"{"version":2,"ready":true,"run_id":"r","transport":"quic",
+                "data_addresses":["10.0.0.1:8000"],"num_receivers":4}"#,
+        )
+        .unwrap();
+        assert_eq!(hello.version, 2);
+        assert!(check_version(hello.version).is_ok());
+        assert_eq!(hello.transport, Transport::Quic);
+
+        let message: ClientMessage =
+            serde_json::from_str(r#"{"type":"done","version":2,"sent":1,"dropped":0}"#).unwrap();
+        assert_eq!(
+            message,
+            ClientMessage::Done {
+                version: 2,
+                sent: 1
+            }
+        );
+
+        assert!(check_version(0).is_err());
+    }
+
+    #[test]
+    fn test_data_address() {
+        let control_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let hello = ServerHello::new(Transport::Udp, vec!["0.0.0.0:8000".parse().unwrap()]);
+        assert_eq!(
+            hello.data_address(control_addr),
+            Some("10.0.0.1:8000".parse().unwrap())
+        );
+        let hello = ServerHello::new(Transport::Udp, vec!["10.0.0.2:8000".parse().unwrap()]);
+        assert_eq!(
+            hello.data_address(control_addr),
+            Some("10.0.0.2:8000".parse().unwrap())
+        );
+        assert_eq!(
+            ServerHello::new(Transport::Udp, vec![]).data_address(control_addr),
+            None
+        );
+    }
+
+    #[test]
+    fn test_handshake() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let control_addr = listener.local_addr().unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let hello = ServerHello::new(Transport::Udp, vec!["0.0.0.0:8000".parse().unwrap()]);
+        let server = spawn_control_server(listener, hello.clone(), exit.clone()).unwrap();
+
+        // a client over the wrong transport is turned away
+        let err = ControlClient::connect(control_addr, Transport::Quic)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(!exit.load(Ordering::Relaxed));
+
+        let (client, server_hello) = ControlClient::connect(control_addr, Transport::Udp).unwrap();
+        assert_eq!(server_hello, hello);
+        client.done(42).unwrap();
+        server.join().unwrap();
+        assert!(exit.load(Ordering::Relaxed));
+    }
+}
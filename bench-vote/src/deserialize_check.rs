@@ -0,0 +1,78 @@
+//! `--verify-deserialize`: check that the received packets hold transactions.
+//!
+//! The sink only counts the packets it receives, so bytes mangled on the way
+//! would go unnoticed. Under this flag each payload is deserialized as a
+//! `Transaction`, and the payloads that fail to are counted.
+
+use {
+    solana_sdk::transaction::Transaction,
+    std::{
+        fmt,
+        sync::atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// Number of received payloads checked and of those that failed to deserialize
+#[derive(Default)]
+pub struct DeserializeCheck {
+    checked: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl DeserializeCheck {
+    /// Returns whether `payload` deserializes as a `Transaction`
+    pub fn check(&self, payload: &[u8]) -> bool {
+        let valid = bincode::deserialize::<Transaction>(payload).is_ok();
+        self.checked.fetch_add(1, Ordering::Relaxed);
+        if !valid {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+        valid
+    }
+
+    pub fn checked(&self) -> u64 {
+        self.checked.load(Ordering::Relaxed)
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Display for DeserializeCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} received packets failed to deserialize as a transaction",
+            self.failed(),
+            self.checked(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{vote_instruction_variant::VoteInstructionVariant, workload::WorkloadStream},
+    };
+
+    #[test]
+    fn test_deserialize_check() {
+        let deserialize_check = DeserializeCheck::default();
+        let valid =
+            WorkloadStream::new(0, 0, VoteInstructionVariant::TowerSync).next_transaction(0);
+        assert!(deserialize_check.check(&valid));
+        assert!(!deserialize_check.check(&[0xff; 64]));
+        assert_eq!(deserialize_check.checked(), 2);
+        assert_eq!(deserialize_check.failed(), 1);
+        assert_eq!(
+            deserialize_check.to_string(),
+            "1 of 2 received packets failed to deserialize as a transaction"
+        );
+
+        // a transaction cut short is a failure as well
+        assert!(!deserialize_check.check(&valid[..valid.len() - 1]));
+        assert_eq!(deserialize_check.failed(), 2);
+    }
+}
@@ -0,0 +1,266 @@
+//! DSCP (Differentiated Services Code Point) marking of vote packets.
+//!
+//! The DSCP occupies the upper six bits of the IPv4 TOS byte / IPv6 traffic
+//! class; the lower two bits are used for ECN.
+
+use std::{
+    io, mem,
+    net::UdpSocket,
+    sync::atomic::{AtomicU64, Ordering},
+};
+#[cfg(target_os = "linux")]
+use {
+    libc::{c_int, c_void, socklen_t},
+    std::{os::unix::io::AsRawFd, ptr},
+};
+
+pub const MAX_DSCP: u8 = 63;
+const NUM_DSCP_VALUES: usize = MAX_DSCP as usize + 1;
+
+pub fn parse_dscp(value: &str) -> Result<u8, String> {
+    let dscp = value
+        .parse::<u8>()
+        .map_err(|err| format!("invalid DSCP value {value}: {err}"))?;
+    if dscp > MAX_DSCP {
+        return Err(format!(
+            "invalid DSCP value {dscp}: must be between 0 and {MAX_DSCP}"
+        ));
+    }
+    Ok(dscp)
+}
+
+pub fn tos_from_dscp(dscp: u8) -> u8 {
+    dscp << 2
+}
+
+pub fn dscp_from_tos(tos: u8) -> u8 {
+    tos >> 2
+}
+
+/// Number of received packets per observed DSCP
+pub struct DscpCounts {
+    counts: [AtomicU64; NUM_DSCP_VALUES],
+    // packets for which the platform did not report a TOS byte
+    unknown: AtomicU64,
+}
+
+impl Default for DscpCounts {
+    fn default() -> Self {
+        Self {
+            counts: std::array::from_fn(|_| AtomicU64::default()),
+            unknown: AtomicU64::default(),
+        }
+    }
+}
+
+impl DscpCounts {
+    pub fn record(&self, tos: Option<u8>) {
+        match tos {
+            Some(tos) => &self.counts[usize::from(dscp_from_tos(tos))],
+            None => &self.unknown,
+        }
+        .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// (dscp, count) for every DSCP that has been observed at least once
+    pub fn observed(&self) -> Vec<(u8, u64)> {
+        (0..=MAX_DSCP)
+            .zip(&self.counts)
+            .map(|(dscp, count)| (dscp, count.load(Ordering::Relaxed)))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    pub fn unknown(&self) -> u64 {
+        self.unknown.load(Ordering::Relaxed)
+    }
+
+    /// Memory held by the counters, in bytes
+    pub fn memory_estimate(&self) -> usize {
+        mem::size_of::<Self>()
+    }
+
+    pub fn report(&self) {
+        for (dscp, count) in self.observed() {
+            println!("Received packets with DSCP {dscp}: {count}");
+        }
+        let unknown = self.unknown();
+        if unknown > 0 {
+            println!("Received packets without a reported DSCP: {unknown}");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_socket_option(
+    socket: &UdpSocket,
+    level: c_int,
+    name: c_int,
+    value: c_int,
+) -> io::Result<()> {
+    // SAFETY: the fd is owned by `socket`, which outlives the call, and the
+    // option value points to a c_int of the length passed
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &value as *const c_int as *const c_void,
+            mem::size_of::<c_int>() as socklen_t,
+        )
+    };
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Mark every packet sent through `socket` with `dscp`
+#[cfg(target_os = "linux")]
+pub fn set_dscp(socket: &UdpSocket, dscp: u8) -> io::Result<()> {
+    let tos = c_int::from(tos_from_dscp(dscp));
+    if socket.local_addr()?.is_ipv4() {
+        set_socket_option(socket, libc::IPPROTO_IP, libc::IP_TOS, tos)
+    } else {
+        set_socket_option(socket, libc::IPPROTO_IPV6, libc::IPV6_TCLASS, tos)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_dscp(_socket: &UdpSocket, _dscp: u8) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "DSCP marking is only supported on linux",
+    ))
+}
+
+/// Ask the kernel to report the TOS byte of packets received on `socket`
+#[cfg(target_os = "linux")]
+pub fn enable_recv_tos(socket: &UdpSocket) -> io::Result<()> {
+    if socket.local_addr()?.is_ipv4() {
+        set_socket_option(socket, libc::IPPROTO_IP, libc::IP_RECVTOS, 1)
+    } else {
+        set_socket_option(socket, libc::IPPROTO_IPV6, libc::IPV6_RECVTCLASS, 1)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn enable_recv_tos(_socket: &UdpSocket) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "reporting received DSCP is only supported on linux",
+    ))
+}
+
+/// Receive a single packet into `buf`, returning its size and TOS byte if the
+/// kernel reported one (see `enable_recv_tos()`)
+#[cfg(target_os = "linux")]
+pub fn recv_with_tos(socket: &UdpSocket, buf: &mut [u8]) -> io::Result<(usize, Option<u8>)> {
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut c_void,
+        iov_len: buf.len(),
+    };
+    // room for a single IP_TOS or IPV6_TCLASS control message, aligned for cmsghdr
+    let mut control = [0u64; 8];
+    // SAFETY: msghdr is a plain C struct, for which all zeroes is a valid
+    // (empty) value
+    let mut hdr: libc::msghdr = unsafe { mem::zeroed() };
+    hdr.msg_iov = &mut iov;
+    hdr.msg_iovlen = 1;
+    hdr.msg_control = control.as_mut_ptr() as *mut c_void;
+    hdr.msg_controllen = mem::size_of_val(&control) as _;
+
+    // SAFETY: the fd is owned by `socket`, and `iov` and `control`, which `hdr`
+    // points to, outlive the call with the lengths set above
+    let nrecv = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut hdr, 0) };
+    if nrecv < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut tos = None;
+    // SAFETY: the control buffer was filled in by recvmsg() above and
+    // CMSG_NXTHDR() stops at msg_controllen
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&hdr);
+        while !cmsg.is_null() {
+            let data = libc::CMSG_DATA(cmsg);
+            match ((*cmsg).cmsg_level, (*cmsg).cmsg_type) {
+                (libc::IPPROTO_IP, libc::IP_TOS) => tos = Some(*data),
+                (libc::IPPROTO_IPV6, libc::IPV6_TCLASS) => {
+                    tos = Some(ptr::read_unaligned(data as *const c_int) as u8)
+                }
+                _ => {}
+            }
+            cmsg = libc::CMSG_NXTHDR(&hdr, cmsg);
+        }
+    }
+    Ok((nrecv as usize, tos))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn recv_with_tos(socket: &UdpSocket, buf: &mut [u8]) -> io::Result<(usize, Option<u8>)> {
+    socket.recv(buf).map(|nrecv| (nrecv, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_net_utils::bind_to_localhost};
+
+    #[test]
+    fn test_parse_dscp() {
+        assert_eq!(parse_dscp("0"), Ok(0));
+        assert_eq!(parse_dscp("46"), Ok(46));
+        assert_eq!(parse_dscp("63"), Ok(63));
+        assert!(parse_dscp("64").is_err());
+        assert!(parse_dscp("255").is_err());
+        assert!(parse_dscp("256").is_err());
+        assert!(parse_dscp("-1").is_err());
+        assert!(parse_dscp("ef").is_err());
+        assert!(parse_dscp("").is_err());
+    }
+
+    #[test]
+    fn test_tos_dscp_conversion() {
+        // expedited forwarding
+        assert_eq!(tos_from_dscp(46), 0xb8);
+        assert_eq!(dscp_from_tos(0xb8), 46);
+        // ECN bits don't affect the DSCP
+        assert_eq!(dscp_from_tos(0xbb), 46);
+        for dscp in 0..=MAX_DSCP {
+            assert_eq!(dscp_from_tos(tos_from_dscp(dscp)), dscp);
+        }
+    }
+
+    #[test]
+    fn test_dscp_counts() {
+        let counts = DscpCounts::default();
+        assert!(counts.observed().is_empty());
+
+        counts.record(Some(tos_from_dscp(46)));
+        counts.record(Some(tos_from_dscp(46) | 0x01));
+        counts.record(Some(0));
+        counts.record(Some(tos_from_dscp(MAX_DSCP)));
+        counts.record(None);
+
+        assert_eq!(counts.observed(), vec![(0, 1), (46, 2), (MAX_DSCP, 1)]);
+        assert_eq!(counts.unknown(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_dscp_round_trip() {
+        let receiver = bind_to_localhost().unwrap();
+        enable_recv_tos(&receiver).unwrap();
+        let sender = bind_to_localhost().unwrap();
+        set_dscp(&sender, 46).unwrap();
+        sender
+            .send_to(b"vote", receiver.local_addr().unwrap())
+            .unwrap();
+
+        let mut buf = [0u8; 16];
+        let (nrecv, tos) = recv_with_tos(&receiver, &mut buf).unwrap();
+        assert_eq!(&buf[..nrecv], b"vote");
+        assert_eq!(tos.map(dscp_from_tos), Some(46));
+    }
+}
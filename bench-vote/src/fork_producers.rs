@@ -0,0 +1,364 @@
+//! Producers run as child processes under `--fork-producers`.
+//!
+//! Each child re-executes bench-vote as a client of the server with a single
+//! producer, so that it sends through a connection cache of its own rather
+//! than the one shared by the producer threads. A child writes its
+//! `ProducerSummary` as JSON to a pipe inherited from the parent, which
+//! aggregates the summaries once the children exited.
+
+#[cfg(unix)]
+use std::{
+    env, mem,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::process::CommandExt,
+    },
+    process::Command,
+};
+use {
+    crate::cache_stats::CacheStatsSummary,
+    clap::ArgMatches,
+    serde::{Deserialize, Serialize},
+    std::{
+        ffi::OsString,
+        fmt,
+        fs::File,
+        io::{self, Read},
+        net::SocketAddr,
+        process::Child,
+    },
+};
+
+/// Results of a child producer, written to the parent on exit
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProducerSummary {
+    pub sent: u64,
+    pub failed: u64,
+    pub elapsed_secs: f64,
+    /// Reuse of the connection cache of the child, under `--report-cache-stats`
+    pub cache_stats: Option<CacheStatsSummary>,
+}
+
+/// Results of all the child producers
+#[derive(Debug, PartialEq)]
+pub struct ForkedProducersSummary {
+    pub num_producers: u64,
+    /// Children that exited with a summary
+    pub num_reported: u64,
+    pub sent: u64,
+    pub failed: u64,
+    /// Run time of the slowest child
+    pub max_elapsed_secs: f64,
+    /// `get_connection()` calls and upper bound of the connections opened,
+    /// summed over the connection caches of the children
+    pub cache_stats: Option<(u64, u64)>,
+}
+
+impl ForkedProducersSummary {
+    /// Aggregate the `summaries` received from `num_producers` children,
+    /// some of which may have exited without one
+    pub fn aggregate(num_producers: u64, summaries: &[ProducerSummary]) -> Self {
+        let cache_stats = summaries
+            .iter()
+            .filter_map(|summary| summary.cache_stats.as_ref())
+            .map(|cache_stats| {
+                (
+                    cache_stats.get_connection_calls,
+                    cache_stats.max_connections(),
+                )
+            })
+            .reduce(|(calls, connections), (other_calls, other_connections)| {
+                (calls + other_calls, connections + other_connections)
+            });
+        Self {
+            num_producers,
+            num_reported: summaries.len() as u64,
+            sent: summaries.iter().map(|summary| summary.sent).sum(),
+            failed: summaries.iter().map(|summary| summary.failed).sum(),
+            max_elapsed_secs: summaries
+                .iter()
+                .map(|summary| summary.elapsed_secs)
+                .fold(0.0, f64::max),
+            cache_stats,
+        }
+    }
+}
+
+impl fmt::Display for ForkedProducersSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Forked producers: {} of {} processes reported, {} transactions sent, {} failed, \
+             slowest process done in {:.3}s",
+            self.num_reported, self.num_producers, self.sent, self.failed, self.max_elapsed_secs,
+        )?;
+        if let Some((get_connection_calls, max_connections)) = self.cache_stats {
+            write!(
+                f,
+                ", {get_connection_calls} get_connection calls across the connection caches, \
+                 at most {max_connections} connections opened"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Arguments of a child producer sending `transactions` to `destination`,
+/// along with the flags of `matches` that apply to a producer
+pub fn producer_args(
+    matches: &ArgMatches,
+    destination: SocketAddr,
+    use_quic: bool,
+    use_connection_cache: bool,
+    transactions: u64,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "--client-only".into(),
+        "--server-address".into(),
+        destination.to_string().into(),
+        "--num-producers".into(),
+        "1".into(),
+        "--transactions-per-producer".into(),
+        transactions.to_string().into(),
+        "--use-quic".into(),
+        use_quic.to_string().into(),
+    ];
+    if use_connection_cache {
+        args.push("--use-connection-cache".into());
+    }
+    for name in ["identity", "vote-instruction"] {
+        if let Some(value) = matches.value_of_os(name) {
+            args.push(format!("--{name}").into());
+            args.push(value.to_os_string());
+        }
+    }
+    for name in ["verbose", "report-cache-stats"] {
+        if matches.is_present(name) {
+            args.push(format!("--{name}").into());
+        }
+    }
+    args
+}
+
+/// A running child producer, and the read end of its summary pipe
+pub struct ForkedProducer {
+    child: Child,
+    summary: File,
+}
+
+impl ForkedProducer {
+    /// Wait for the child to exit and read its summary
+    pub fn wait(mut self) -> io::Result<ProducerSummary> {
+        let mut summary = String::new();
+        self.summary.read_to_string(&mut summary)?;
+        let status = self.child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("producer exited with {status}")));
+        }
+        serde_json::from_str(&summary).map_err(io::Error::from)
+    }
+}
+
+#[cfg(unix)]
+fn set_cloexec(fd: RawFd, cloexec: bool) -> io::Result<()> {
+    let flags = if cloexec { libc::FD_CLOEXEC } else { 0 };
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, flags) } < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// A pipe whose ends are closed on exec, as the (read, write) ends
+#[cfg(unix)]
+fn pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let (read, write) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+    set_cloexec(read.as_raw_fd(), true)?;
+    set_cloexec(write.as_raw_fd(), true)?;
+    Ok((read, write))
+}
+
+/// Re-execute the current binary with `args`, handing it the write end of a
+/// pipe to send its summary through
+#[cfg(unix)]
+pub fn spawn_forked_producer(args: &[OsString]) -> io::Result<ForkedProducer> {
+    let (read, write) = pipe()?;
+    let write_fd = write.as_raw_fd();
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .args(args)
+        .arg("--producer-child")
+        .arg(write_fd.to_string());
+    // only this child inherits the write end, so that the read end reaches
+    // EOF once it exited
+    unsafe {
+        command.pre_exec(move || set_cloexec(write_fd, false));
+    }
+    let child = command.spawn()?;
+    drop(write);
+    Ok(ForkedProducer {
+        child,
+        summary: File::from(read),
+    })
+}
+
+#[cfg(not(unix))]
+pub fn spawn_forked_producer(_args: &[OsString]) -> io::Result<ForkedProducer> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "forked producers are only supported on unix",
+    ))
+}
+
+/// Check that `fd`, as passed with `--producer-child`, is the pipe inherited
+/// from the parent rather than a standard stream or a closed fd
+#[cfg(unix)]
+fn check_summary_fd(fd: RawFd) -> io::Result<()> {
+    if fd <= libc::STDERR_FILENO {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("summary fd {fd} is not an inherited pipe"),
+        ));
+    }
+    let mut stat = mem::MaybeUninit::<libc::stat>::uninit();
+    // SAFETY: `stat` is valid for writes of a libc::stat
+    if unsafe { libc::fstat(fd, stat.as_mut_ptr()) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fstat() succeeded, so it initialized `stat`
+    let stat = unsafe { stat.assume_init() };
+    if stat.st_mode & libc::S_IFMT != libc::S_IFIFO {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("summary fd {fd} is not a pipe"),
+        ));
+    }
+    Ok(())
+}
+
+/// Write `summary` to the pipe inherited from the parent as `fd`
+///
+/// The fd is owned by the caller until it is checked to be a pipe; from then
+/// on it is owned, and closed, by this function. It must thus be called at
+/// most once per fd, with nothing else using the fd.
+#[cfg(unix)]
+pub fn write_summary(fd: RawFd, summary: &ProducerSummary) -> io::Result<()> {
+    check_summary_fd(fd)?;
+    // SAFETY: the fd is an open pipe, which the parent handed to this process
+    // for the summary only, so nothing else owns or closes it
+    let file = unsafe { File::from_raw_fd(fd) };
+    serde_json::to_writer(file, summary).map_err(io::Error::from)
+}
+
+#[cfg(not(unix))]
+pub fn write_summary(_fd: i32, _summary: &ProducerSummary) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "forked producers are only supported on unix",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(sent: u64, failed: u64, elapsed_secs: f64) -> ProducerSummary {
+        ProducerSummary {
+            sent,
+            failed,
+            elapsed_secs,
+            cache_stats: None,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_producer_summaries() {
+        let summaries = [
+            summary(1000, 0, 2.5),
+            summary(990, 10, 4.0),
+            summary(1000, 0, 3.0),
+        ];
+        let aggregate = ForkedProducersSummary::aggregate(3, &summaries);
+        assert_eq!(
+            aggregate,
+            ForkedProducersSummary {
+                num_producers: 3,
+                num_reported: 3,
+                sent: 2990,
+                failed: 10,
+                max_elapsed_secs: 4.0,
+                cache_stats: None,
+            }
+        );
+        assert_eq!(
+            aggregate.to_string(),
+            "Forked producers: 3 of 3 processes reported, 2990 transactions sent, 10 failed, \
+             slowest process done in 4.000s"
+        );
+
+        // a child that exited without a summary is not counted
+        let aggregate = ForkedProducersSummary::aggregate(4, &summaries[..2]);
+        assert_eq!(aggregate.num_reported, 2);
+        assert_eq!(aggregate.sent, 1990);
+        assert_eq!(aggregate.max_elapsed_secs, 4.0);
+
+        let aggregate = ForkedProducersSummary::aggregate(2, &[]);
+        assert_eq!(aggregate.sent, 0);
+        assert_eq!(aggregate.max_elapsed_secs, 0.0);
+    }
+
+    #[test]
+    fn test_aggregate_cache_stats() {
+        // each child opens the connections of its own cache
+        let summaries: Vec<_> = (0..4)
+            .map(|_| ProducerSummary {
+                cache_stats: Some(CacheStatsSummary {
+                    get_connection_calls: 100,
+                    distinct_endpoints: 1,
+                    connection_pool_size: 2,
+                }),
+                ..summary(100, 0, 1.0)
+            })
+            .collect();
+        let aggregate = ForkedProducersSummary::aggregate(4, &summaries);
+        assert_eq!(aggregate.cache_stats, Some((400, 8)));
+        assert!(aggregate.to_string().ends_with(
+            ", 400 get_connection calls across the connection caches, at most 8 connections \
+             opened"
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_summary_pipe() {
+        use std::os::fd::IntoRawFd;
+
+        let (read, write) = pipe().unwrap();
+        let sent = ProducerSummary {
+            cache_stats: Some(CacheStatsSummary {
+                get_connection_calls: 10,
+                distinct_endpoints: 1,
+                connection_pool_size: 1,
+            }),
+            ..summary(9, 1, 0.5)
+        };
+        write_summary(write.into_raw_fd(), &sent).unwrap();
+
+        // anything but an open pipe is left to the caller
+        assert!(write_summary(libc::STDOUT_FILENO, &sent).is_err());
+        assert!(write_summary(i32::MAX, &sent).is_err());
+        let not_a_pipe = File::open("/dev/null").unwrap();
+        assert!(write_summary(not_a_pipe.as_raw_fd(), &sent).is_err());
+
+        let mut received = String::new();
+        File::from(read).read_to_string(&mut received).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ProducerSummary>(&received).unwrap(),
+            sent
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! Forwarding of the received packets under `--forward-to`.
+//!
+//! A node that is not the leader forwards the votes it receives onward. The
+//! sinks push the payload of every received packet into a bounded queue, and
+//! the forwarder re-sends them to the downstream address over its own
+//! transporter. When the downstream is slower than ingestion, the queue drops
+//! its oldest packets so that what is forwarded stays fresh.
+//!
+//! The latency measured is the time a packet spent between the sink and the
+//! completion of its send, the delay added by the forwarding path.
+
+use {
+    crate::transporter::Transporter,
+    std::{
+        collections::VecDeque,
+        fmt,
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, Condvar, Mutex,
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+pub const DEFAULT_FORWARD_QUEUE_SIZE: usize = 10_000;
+const FORWARD_RECEIVE_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+pub struct ForwardStats {
+    enqueued: AtomicU64,
+    dropped: AtomicU64,
+    forwarded: AtomicU64,
+    send_errors: AtomicU64,
+    total_latency_us: AtomicU64,
+    max_latency_us: AtomicU64,
+}
+
+impl ForwardStats {
+    /// Number of packets handed to the forwarder by the sinks
+    pub fn enqueued(&self) -> u64 {
+        self.enqueued.load(Ordering::Relaxed)
+    }
+
+    fn record_forwarded(&self, latency: Duration) {
+        let latency_us = latency.as_micros() as u64;
+        self.forwarded.fetch_add(1, Ordering::Relaxed);
+        self.total_latency_us
+            .fetch_add(latency_us, Ordering::Relaxed);
+        self.max_latency_us.fetch_max(latency_us, Ordering::Relaxed);
+    }
+
+    pub fn summary(&self, elapsed: Duration) -> ForwardSummary {
+        ForwardSummary {
+            enqueued: self.enqueued(),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            forwarded: self.forwarded.load(Ordering::Relaxed),
+            send_errors: self.send_errors.load(Ordering::Relaxed),
+            total_latency: Duration::from_micros(self.total_latency_us.load(Ordering::Relaxed)),
+            max_latency: Duration::from_micros(self.max_latency_us.load(Ordering::Relaxed)),
+            elapsed,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ForwardSummary {
+    pub enqueued: u64,
+    /// Packets dropped from the queue because the downstream was too slow
+    pub dropped: u64,
+    pub forwarded: u64,
+    pub send_errors: u64,
+    pub total_latency: Duration,
+    pub max_latency: Duration,
+    pub elapsed: Duration,
+}
+
+impl ForwardSummary {
+    pub fn throughput(&self) -> f64 {
+        let elapsed = self.elapsed.as_secs_f64();
+        if elapsed == 0.0 {
+            0.0
+        } else {
+            self.forwarded as f64 / elapsed
+        }
+    }
+
+    pub fn average_latency(&self) -> Duration {
+        if self.forwarded == 0 {
+            Duration::ZERO
+        } else {
+            self.total_latency / self.forwarded as u32
+        }
+    }
+}
+
+impl fmt::Display for ForwardSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Forwarding: {} packets received, {} forwarded ({:.2}/s), {} dropped by the queue, \
+             {} send errors, added latency {:?} on average, {:?} at most",
+            self.enqueued,
+            self.forwarded,
+            self.throughput(),
+            self.dropped,
+            self.send_errors,
+            self.average_latency(),
+            self.max_latency,
+        )
+    }
+}
+
+struct QueuedPacket {
+    payload: Vec<u8>,
+    received: Instant,
+}
+
+/// Bounded queue between the sinks and the forwarder, dropping its oldest
+/// packets when full
+pub struct ForwardQueue {
+    packets: Mutex<VecDeque<QueuedPacket>>,
+    not_empty: Condvar,
+    capacity: usize,
+    stats: ForwardStats,
+}
+
+impl ForwardQueue {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            packets: Mutex::new(VecDeque::with_capacity(capacity)),
+            not_empty: Condvar::new(),
+            capacity,
+            stats: ForwardStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> &ForwardStats {
+        &self.stats
+    }
+
+    pub fn push(&self, payload: Vec<u8>) {
+        let mut packets = self.packets.lock().unwrap();
+        if packets.len() == self.capacity {
+            packets.pop_front();
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        packets.push_back(QueuedPacket {
+            payload,
+            received: Instant::now(),
+        });
+        self.stats.enqueued.fetch_add(1, Ordering::Relaxed);
+        drop(packets);
+        self.not_empty.notify_one();
+    }
+
+    fn pop_timeout(&self, timeout: Duration) -> Option<QueuedPacket> {
+        let packets = self.packets.lock().unwrap();
+        let (mut packets, _) = self
+            .not_empty
+            .wait_timeout_while(packets, timeout, |packets| packets.is_empty())
+            .unwrap();
+        packets.pop_front()
+    }
+}
+
+/// Forward the packets of `queue` to `destination` until `exit` is set
+pub fn spawn_forwarder(
+    exit: Arc<AtomicBool>,
+    queue: Arc<ForwardQueue>,
+    transporter: Transporter,
+    destination: SocketAddr,
+    verbose: bool,
+) -> JoinHandle<()> {
+    thread::Builder::new()
+        .name("solBenVoteFwd".to_string())
+        .spawn(move || {
+            while !exit.load(Ordering::Relaxed) {
+                let Some(packet) = queue.pop_timeout(FORWARD_RECEIVE_TIMEOUT) else {
+                    continue;
+                };
+                match transporter.send(&destination, &packet.payload, None) {
+                    Ok(()) => queue.stats.record_forwarded(packet.received.elapsed()),
+                    Err(err) => {
+                        queue.stats.send_errors.fetch_add(1, Ordering::Relaxed);
+                        if verbose {
+                            println!("Error forwarding packet {err:?}");
+                        }
+                    }
+                }
+            }
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::net::{Ipv4Addr, UdpSocket},
+    };
+
+    #[test]
+    fn test_forward_queue_drops_oldest() {
+        let queue = ForwardQueue::new(2);
+        for payload in [[1u8], [2], [3]] {
+            queue.push(payload.to_vec());
+        }
+        assert_eq!(queue.stats().enqueued(), 3);
+        assert_eq!(queue.stats().dropped.load(Ordering::Relaxed), 1);
+
+        let timeout = Duration::from_millis(10);
+        assert_eq!(queue.pop_timeout(timeout).unwrap().payload, [2]);
+        assert_eq!(queue.pop_timeout(timeout).unwrap().payload, [3]);
+        assert!(queue.pop_timeout(timeout).is_none());
+    }
+
+    #[test]
+    fn test_forwarder() {
+        let downstream = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        downstream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let queue = Arc::new(ForwardQueue::new(DEFAULT_FORWARD_QUEUE_SIZE));
+        let forwarder = spawn_forwarder(
+            exit.clone(),
+            queue.clone(),
+            Transporter::DirectSocket(Arc::new(socket)),
+            downstream.local_addr().unwrap(),
+            false,
+        );
+
+        for payload in [b"vote 1", b"vote 2"] {
+            queue.push(payload.to_vec());
+        }
+        let mut buf = [0u8; 64];
+        for expected in [b"vote 1", b"vote 2"] {
+            let len = downstream.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], expected);
+        }
+        exit.store(true, Ordering::Relaxed);
+        forwarder.join().unwrap();
+
+        let summary = queue.stats().summary(Duration::from_secs(1));
+        assert_eq!(summary.enqueued, 2);
+        assert_eq!(summary.forwarded, 2);
+        assert_eq!(summary.dropped, 0);
+        assert_eq!(summary.send_errors, 0);
+        assert!(summary.max_latency >= summary.average_latency());
+        assert_eq!(summary.throughput(), 2.0);
+    }
+
+    #[test]
+    fn test_forward_summary_display() {
+        let summary = ForwardSummary {
+            enqueued: 10,
+            dropped: 2,
+            forwarded: 8,
+            send_errors: 0,
+            total_latency: Duration::from_micros(800),
+            max_latency: Duration::from_micros(300),
+            elapsed: Duration::from_secs(2),
+        };
+        assert_eq!(
+            summary.to_string(),
+            "Forwarding: 10 packets received, 8 forwarded (4.00/s), 2 dropped by the queue, 0 \
+             send errors, added latency 100µs on average, 300µs at most"
+        );
+    }
+}
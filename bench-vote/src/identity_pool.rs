@@ -0,0 +1,171 @@
+//! Pool of QUIC client identities under `--num-identities`.
+//!
+//! Each identity of the pool has a connection cache of its own, and the
+//! producers send each transaction through the next one in turn, so that the
+//! server sees as many distinct peers. The identities are staked in the
+//! `StakedNodes` of the run, which the server of a run with both a server and
+//! a client shares, so that it treats them as staked peers. A server run with
+//! `--server-only` stakes the identities of `--identity-pool-dir`, to be
+//! taken by the client from the same keypair files.
+
+use {
+    crate::{identity_rotation::read_keypairs_dir, transporter::QuicParams},
+    solana_sdk::{
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+    },
+    solana_streamer::streamer::StakedNodes,
+    std::{
+        collections::HashMap,
+        path::Path,
+        sync::{Arc, RwLock},
+    },
+};
+
+/// Stake of each identity, the same as the single identity of a run without
+/// a pool
+pub const IDENTITY_STAKE: u64 = 1024;
+
+pub struct IdentityPool {
+    keypairs: Vec<Keypair>,
+}
+
+impl IdentityPool {
+    pub fn generate(num_identities: usize) -> Self {
+        Self {
+            keypairs: (0..num_identities).map(|_| Keypair::new()).collect(),
+        }
+    }
+
+    /// The first `num_identities` keypair files of `dir`, in file name order
+    pub fn from_dir(dir: &Path, num_identities: usize) -> Result<Self, String> {
+        let mut keypairs = read_keypairs_dir(dir)?;
+        if keypairs.len() < num_identities {
+            return Err(format!(
+                "{} has {} keypair files, fewer than the {num_identities} identities",
+                dir.display(),
+                keypairs.len()
+            ));
+        }
+        keypairs.truncate(num_identities);
+        Ok(Self { keypairs })
+    }
+
+    pub fn pubkeys(&self) -> impl Iterator<Item = Pubkey> + '_ {
+        self.keypairs.iter().map(Keypair::pubkey)
+    }
+
+    /// Stakes of the identities, each staked with `IDENTITY_STAKE`
+    pub fn stakes(&self) -> HashMap<Pubkey, u64> {
+        self.pubkeys()
+            .map(|pubkey| (pubkey, IDENTITY_STAKE))
+            .collect()
+    }
+
+    /// Params of the QUIC endpoint of `identity_keypair`, whose staked nodes
+    /// hold the identities of the pool along with its own
+    pub fn quic_params(&self, identity_keypair: Keypair) -> QuicParams {
+        let mut stakes = self.stakes();
+        stakes.insert(identity_keypair.pubkey(), IDENTITY_STAKE);
+        let staked_nodes = Arc::new(RwLock::new(StakedNodes::new(
+            Arc::new(stakes),
+            HashMap::<Pubkey, u64>::default(), // overrides
+        )));
+        QuicParams {
+            identity_keypair,
+            staked_nodes,
+        }
+    }
+
+    /// Params of the QUIC client of each identity, sharing `staked_nodes`
+    pub fn client_params(&self, staked_nodes: &Arc<RwLock<StakedNodes>>) -> Vec<QuicParams> {
+        self.keypairs
+            .iter()
+            .map(|keypair| QuicParams {
+                identity_keypair: keypair.insecure_clone(),
+                staked_nodes: staked_nodes.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::transporter::{Transporter, TransporterPool},
+        solana_sdk::signature::write_keypair_file,
+        std::{collections::HashSet, net::UdpSocket},
+    };
+
+    #[test]
+    fn test_identity_pool_stakes() {
+        let pool = IdentityPool::generate(5);
+        let pubkeys: HashSet<_> = pool.pubkeys().collect();
+        assert_eq!(pubkeys.len(), 5);
+
+        let identity = Keypair::new();
+        let identity_pubkey = identity.pubkey();
+        let quic_params = pool.quic_params(identity);
+        let staked_nodes = quic_params.staked_nodes.read().unwrap();
+        for pubkey in pubkeys.iter().chain([&identity_pubkey]) {
+            assert_eq!(staked_nodes.get_node_stake(pubkey), Some(IDENTITY_STAKE));
+        }
+        assert_eq!(staked_nodes.total_stake(), 6 * IDENTITY_STAKE);
+        drop(staked_nodes);
+
+        let client_params = pool.client_params(&quic_params.staked_nodes);
+        assert_eq!(
+            client_params
+                .iter()
+                .map(|params| params.identity_keypair.pubkey())
+                .collect::<HashSet<_>>(),
+            pubkeys
+        );
+        assert!(client_params
+            .iter()
+            .all(|params| Arc::ptr_eq(&params.staked_nodes, &quic_params.staked_nodes)));
+    }
+
+    #[test]
+    fn test_identity_pool_from_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let keypairs: Vec<_> = (0..3).map(|_| Keypair::new()).collect();
+        for (index, keypair) in keypairs.iter().enumerate() {
+            write_keypair_file(keypair, dir.path().join(format!("{index}.json"))).unwrap();
+        }
+
+        let pool = IdentityPool::from_dir(dir.path(), 2).unwrap();
+        assert_eq!(
+            pool.pubkeys().collect::<Vec<_>>(),
+            vec![keypairs[0].pubkey(), keypairs[1].pubkey()]
+        );
+        assert!(IdentityPool::from_dir(dir.path(), 4)
+            .err()
+            .unwrap()
+            .contains("fewer than the 4 identities"));
+    }
+
+    #[test]
+    fn test_transporter_pool_round_robin() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let destination = receiver.local_addr().unwrap();
+        let sockets: Vec<_> = (0..3)
+            .map(|_| Arc::new(UdpSocket::bind("127.0.0.1:0").unwrap()))
+            .collect();
+        let pool = Transporter::Pool(Arc::new(TransporterPool::new(
+            sockets
+                .iter()
+                .cloned()
+                .map(Transporter::DirectSocket)
+                .collect(),
+        )));
+
+        let mut buffer = [0; 8];
+        for index in 0..6 {
+            pool.send(&destination, &[index], None).unwrap();
+            let (_, from) = receiver.recv_from(&mut buffer).unwrap();
+            assert_eq!(from, sockets[index as usize % 3].local_addr().unwrap());
+        }
+    }
+}
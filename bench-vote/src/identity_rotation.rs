@@ -0,0 +1,443 @@
+//! Periodic rotation of the QUIC client identity under `--rotate-identity-secs`.
+//!
+//! Every interval, a connection cache with a new identity is swapped into the
+//! transporter shared by the producers, so that the server sees the client
+//! re-authenticate on fresh connections. The producers look the transporter
+//! up on each send and carry on with their own counters across a rotation.
+//! The replaced cache closes its connections once the last in-flight send
+//! holding it is done.
+//!
+//! Send errors are attributed to the window following a rotation, or to the
+//! steady state past it, to show whether the server mishandles the
+//! re-authentication.
+
+use {
+    crate::transporter::{QuicParams, Transporter},
+    solana_sdk::signature::{read_keypair_file, Keypair},
+    std::{
+        fmt, fs,
+        path::Path,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+            Arc, Mutex, RwLock,
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+/// Sends within this long after a rotation are attributed to the rotation
+pub const ROTATION_ERROR_WINDOW: Duration = Duration::from_secs(2);
+
+/// Granularity at which the rotation thread checks `exit` between rotations
+const ROTATION_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Transporter shared by the producers, which can be replaced while they send
+pub struct SharedTransporter {
+    current: RwLock<Arc<Transporter>>,
+}
+
+impl SharedTransporter {
+    pub fn new(transporter: Transporter) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(transporter)),
+        }
+    }
+
+    /// The current transporter, to be held for a single send
+    pub fn load(&self) -> Arc<Transporter> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Replace the current transporter, returning the replaced one
+    pub fn swap(&self, transporter: Transporter) -> Arc<Transporter> {
+        std::mem::replace(&mut *self.current.write().unwrap(), Arc::new(transporter))
+    }
+}
+
+/// Where the identities of the rotations come from
+pub enum IdentitySource {
+    Generated,
+    /// The keypair files of a directory, in file name order, cycled through
+    Directory {
+        keypairs: Vec<Keypair>,
+        next: usize,
+    },
+}
+
+impl IdentitySource {
+    pub fn from_dir(dir: &Path) -> Result<Self, String> {
+        let keypairs = read_keypairs_dir(dir)?;
+        Ok(Self::Directory { keypairs, next: 0 })
+    }
+
+    pub fn next_identity(&mut self) -> Keypair {
+        match self {
+            Self::Generated => Keypair::new(),
+            Self::Directory { keypairs, next } => {
+                let keypair = keypairs[*next].insecure_clone();
+                *next = (*next + 1) % keypairs.len();
+                keypair
+            }
+        }
+    }
+}
+
+/// The keypair files of `dir`, in file name order, failing if there is none
+pub fn read_keypairs_dir(dir: &Path) -> Result<Vec<Keypair>, String> {
+    let mut paths = fs::read_dir(dir)
+        .map_err(|err| format!("failed to read the directory {}: {err}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to read the directory {}: {err}", dir.display()))?;
+    paths.retain(|path| {
+        path.extension()
+            .is_some_and(|extension| extension == "json")
+    });
+    paths.sort();
+    if paths.is_empty() {
+        return Err(format!("no keypair file in {}", dir.display()));
+    }
+    paths
+        .iter()
+        .map(|path| {
+            read_keypair_file(path)
+                .map_err(|err| format!("failed to read keypair {}: {err}", path.display()))
+        })
+        .collect()
+}
+
+/// Whether a send made `since_rotation` after the last rotation, if any,
+/// falls in the window attributed to that rotation
+pub fn in_rotation_window(since_rotation: Option<Duration>) -> bool {
+    since_rotation.is_some_and(|since_rotation| since_rotation < ROTATION_ERROR_WINDOW)
+}
+
+#[derive(Default)]
+struct SendCounts {
+    sent: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl SendCounts {
+    fn record(&self, ok: bool) {
+        if ok {
+            self.sent.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn load(&self) -> (u64, u64) {
+        (
+            self.sent.load(Ordering::Relaxed),
+            self.errors.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Rotations, and the sends of the producers around them
+pub struct RotationTracker {
+    start: Instant,
+    /// Time of the last rotation, in nanoseconds since `start`, plus one so
+    /// that zero means no rotation yet
+    last_rotation: AtomicU64,
+    rotations: AtomicUsize,
+    rotation_window: SendCounts,
+    steady_state: SendCounts,
+    /// Total transactions sent when each identity epoch started
+    epoch_starts: Mutex<Vec<u64>>,
+}
+
+impl RotationTracker {
+    pub fn new(start: Instant) -> Self {
+        Self {
+            start,
+            last_rotation: AtomicU64::new(0),
+            rotations: AtomicUsize::new(0),
+            rotation_window: SendCounts::default(),
+            steady_state: SendCounts::default(),
+            epoch_starts: Mutex::new(vec![0]),
+        }
+    }
+
+    fn since_rotation(&self, now: Instant) -> Option<Duration> {
+        let last_rotation = self.last_rotation.load(Ordering::Relaxed).checked_sub(1)?;
+        Some(now.saturating_duration_since(self.start + Duration::from_nanos(last_rotation)))
+    }
+
+    pub fn record_send(&self, ok: bool, now: Instant) {
+        if in_rotation_window(self.since_rotation(now)) {
+            self.rotation_window.record(ok);
+        } else {
+            self.steady_state.record(ok);
+        }
+    }
+
+    pub fn record_rotation(&self, now: Instant) {
+        let (window_sent, _) = self.rotation_window.load();
+        let (steady_sent, _) = self.steady_state.load();
+        self.epoch_starts
+            .lock()
+            .unwrap()
+            .push(window_sent + steady_sent);
+        let since_start = now.saturating_duration_since(self.start).as_nanos() as u64;
+        self.last_rotation.store(since_start + 1, Ordering::Relaxed);
+        self.rotations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn summary(&self) -> RotationSummary {
+        let (window_sent, window_errors) = self.rotation_window.load();
+        let (steady_sent, steady_errors) = self.steady_state.load();
+        let total_sent = window_sent + steady_sent;
+        let epoch_starts = self.epoch_starts.lock().unwrap();
+        let sent_per_epoch = epoch_starts
+            .iter()
+            .zip(epoch_starts.iter().skip(1).chain([&total_sent]))
+            .map(|(start, end)| end.saturating_sub(*start))
+            .collect();
+        RotationSummary {
+            rotations: self.rotations.load(Ordering::Relaxed),
+            sent_per_epoch,
+            window_sent,
+            window_errors,
+            steady_sent,
+            steady_errors,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RotationSummary {
+    pub rotations: usize,
+    /// Transactions sent under each identity, in rotation order
+    pub sent_per_epoch: Vec<u64>,
+    pub window_sent: u64,
+    pub window_errors: u64,
+    pub steady_sent: u64,
+    pub steady_errors: u64,
+}
+
+fn error_percent(sent: u64, errors: u64) -> f64 {
+    let total = sent + errors;
+    if total == 0 {
+        0.0
+    } else {
+        errors as f64 * 100.0 / total as f64
+    }
+}
+
+impl fmt::Display for RotationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Identity rotation: {} rotations, transactions sent per identity: {:?}",
+            self.rotations, self.sent_per_epoch
+        )?;
+        write!(
+            f,
+            "Send errors: {} ({:.2}%) within {}s of a rotation, {} ({:.2}%) in steady state",
+            self.window_errors,
+            error_percent(self.window_sent, self.window_errors),
+            ROTATION_ERROR_WINDOW.as_secs(),
+            self.steady_errors,
+            error_percent(self.steady_sent, self.steady_errors),
+        )
+    }
+}
+
+/// Every `interval` until `exit` is set, swap a QUIC connection cache with
+/// the next identity of `identities` into `transporter`
+pub fn spawn_identity_rotation(
+    exit: Arc<AtomicBool>,
+    interval: Duration,
+    mut identities: IdentitySource,
+    transporter: Arc<SharedTransporter>,
+    tracker: Arc<RotationTracker>,
+) -> JoinHandle<()> {
+    thread::Builder::new()
+        .name("solBenVoteRotId".to_string())
+        .spawn(move || {
+            let mut next_rotation = Instant::now() + interval;
+            while !exit.load(Ordering::Relaxed) {
+                if Instant::now() < next_rotation {
+                    thread::sleep(ROTATION_POLL_INTERVAL);
+                    continue;
+                }
+                next_rotation += interval;
+                let quic_params = QuicParams::new(identities.next_identity());
+                let rotated = match Transporter::new(
+                    "connection_cache_vote_quic",
+                    false,
+                    Some(&quic_params),
+                    None,
+                ) {
+                    Ok(rotated) => rotated,
+                    Err(err) => {
+                        eprintln!("Error: failed to rotate the identity: {err}");
+                        continue;
+                    }
+                };
+                // the replaced cache is dropped, closing its connections,
+                // once no producer holds it anymore
+                drop(transporter.swap(rotated));
+                tracker.record_rotation(Instant::now());
+            }
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{signature::write_keypair_file, signer::Signer},
+        std::{net::UdpSocket, sync::Weak},
+    };
+
+    fn direct_socket() -> Transporter {
+        Transporter::DirectSocket(Arc::new(UdpSocket::bind("127.0.0.1:0").unwrap()))
+    }
+
+    fn local_addr(transporter: &Transporter) -> std::net::SocketAddr {
+        match transporter {
+            Transporter::DirectSocket(socket) => socket.local_addr().unwrap(),
+            Transporter::Cache(_) | Transporter::Pool(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_shared_transporter_swap() {
+        let shared = SharedTransporter::new(direct_socket());
+        let first = shared.load();
+        let first_addr = local_addr(&first);
+        let weak_first: Weak<Transporter> = Arc::downgrade(&first);
+
+        let replaced = shared.swap(direct_socket());
+        assert_eq!(local_addr(&replaced), first_addr);
+        assert_ne!(local_addr(&shared.load()), first_addr);
+
+        // the replaced transporter lives as long as a sender holds it
+        drop(replaced);
+        assert!(weak_first.upgrade().is_some());
+        drop(first);
+        assert!(weak_first.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_shared_transporter_swap_keeps_counters() {
+        let shared = Arc::new(SharedTransporter::new(direct_socket()));
+        let tracker = Arc::new(RotationTracker::new(Instant::now()));
+        let num_producers: u64 = 4;
+        let transactions_per_producer: u64 = 10_000;
+        let num_rotations = 100;
+        let producers: Vec<_> = (0..num_producers)
+            .map(|_| {
+                let shared = shared.clone();
+                let tracker = tracker.clone();
+                thread::spawn(move || {
+                    let mut sequence = 0;
+                    for _ in 0..transactions_per_producer {
+                        let _transporter = shared.load();
+                        sequence += 1;
+                        tracker.record_send(true, Instant::now());
+                    }
+                    sequence
+                })
+            })
+            .collect();
+        for _ in 0..num_rotations {
+            shared.swap(direct_socket());
+            tracker.record_rotation(Instant::now());
+        }
+        for producer in producers {
+            assert_eq!(producer.join().unwrap(), transactions_per_producer);
+        }
+
+        // the sends of all the producers, merged across the identity epochs
+        // they fell in, add up to every transaction sent
+        let summary = tracker.summary();
+        assert_eq!(summary.rotations, num_rotations);
+        assert_eq!(summary.sent_per_epoch.len(), num_rotations + 1);
+        assert_eq!(
+            summary.sent_per_epoch.iter().sum::<u64>(),
+            num_producers * transactions_per_producer
+        );
+        assert_eq!(
+            summary.window_sent + summary.steady_sent,
+            num_producers * transactions_per_producer
+        );
+        assert_eq!(summary.window_errors + summary.steady_errors, 0);
+    }
+
+    #[test]
+    fn test_in_rotation_window() {
+        assert!(!in_rotation_window(None));
+        assert!(in_rotation_window(Some(Duration::ZERO)));
+        assert!(in_rotation_window(Some(
+            ROTATION_ERROR_WINDOW - Duration::from_millis(1)
+        )));
+        assert!(!in_rotation_window(Some(ROTATION_ERROR_WINDOW)));
+    }
+
+    #[test]
+    fn test_rotation_tracker_attribution() {
+        let start = Instant::now();
+        let tracker = RotationTracker::new(start);
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        // before any rotation, all sends are steady state
+        tracker.record_send(true, at(0));
+        tracker.record_send(false, at(1));
+        tracker.record_send(true, at(3));
+
+        tracker.record_rotation(at(5));
+        tracker.record_send(false, at(5));
+        tracker.record_send(false, at(6));
+        tracker.record_send(true, at(6));
+        tracker.record_send(true, at(7));
+        tracker.record_send(true, at(8));
+
+        tracker.record_rotation(at(10));
+        tracker.record_send(true, at(11));
+
+        assert_eq!(
+            tracker.summary(),
+            RotationSummary {
+                rotations: 2,
+                sent_per_epoch: vec![2, 3, 1],
+                window_sent: 2,
+                window_errors: 2,
+                steady_sent: 4,
+                steady_errors: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_identity_source_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let keypairs: Vec<_> = (0..3).map(|_| Keypair::new()).collect();
+        for (nth, keypair) in keypairs.iter().enumerate() {
+            write_keypair_file(keypair, dir.path().join(format!("identity-{nth}.json"))).unwrap();
+        }
+        fs::write(dir.path().join("README"), "not a keypair").unwrap();
+
+        let mut identities = IdentitySource::from_dir(dir.path()).unwrap();
+        let pubkeys: Vec<_> = (0..4)
+            .map(|_| identities.next_identity().pubkey())
+            .collect();
+        assert_eq!(
+            pubkeys,
+            [
+                keypairs[0].pubkey(),
+                keypairs[1].pubkey(),
+                keypairs[2].pubkey(),
+                keypairs[0].pubkey()
+            ]
+        );
+
+        let empty_dir = tempfile::TempDir::new().unwrap();
+        assert!(IdentitySource::from_dir(empty_dir.path()).is_err());
+    }
+}
@@ -1,29 +1,91 @@
 #![allow(clippy::arithmetic_side_effects)]
 
+mod cache_stats;
+mod compare_results;
+mod config;
+mod control;
+mod deserialize_check;
+mod dscp;
+mod fork_producers;
+mod forwarder;
+mod identity_pool;
+mod identity_rotation;
+mod memory_watchdog;
+mod pause_schedule;
+mod pipeline;
+mod preset;
+mod results;
+mod samples;
+mod send_latency;
+mod sink_channel;
+mod stall_watchdog;
+mod transaction_details;
+mod transporter;
+mod udp_drops;
+mod vote_instruction_variant;
+mod workload;
+
 use {
-    clap::{crate_description, crate_name, value_t, value_t_or_exit, App, Arg},
-    crossbeam_channel::unbounded,
-    solana_clap_utils::{input_parsers::keypair_of, input_validators::is_keypair_or_ask_keyword},
-    solana_client::connection_cache::ConnectionCache,
-    solana_connection_cache::client_connection::ClientConnection,
-    solana_net_utils::{bind_to_unspecified, SocketConfig},
-    solana_sdk::{
-        hash::Hash, message::Message, pubkey::Pubkey, signature::Keypair, signer::Signer,
-        transaction::Transaction,
+    crate::{
+        cache_stats::CacheStats,
+        compare_results::{incompatibilities, Comparison, RegressionThresholds},
+        config::{config_arg, get_matches_with_config},
+        control::{spawn_control_server, ControlClient, ServerHello, Transport},
+        deserialize_check::DeserializeCheck,
+        dscp::DscpCounts,
+        fork_producers::{
+            producer_args, spawn_forked_producer, write_summary, ForkedProducersSummary,
+            ProducerSummary,
+        },
+        forwarder::{spawn_forwarder, ForwardQueue, DEFAULT_FORWARD_QUEUE_SIZE},
+        identity_pool::IdentityPool,
+        identity_rotation::{
+            spawn_identity_rotation, IdentitySource, RotationTracker, SharedTransporter,
+        },
+        memory_watchdog::{spawn_memory_watchdog, ComponentEstimate, EstimateFn},
+        pause_schedule::{spawn_pause_controller, PauseSchedule, PauseTracker, TransporterFactory},
+        pipeline::{run_pipeline, PipelineStats, DEFAULT_PIPELINE_CAPACITY},
+        preset::{workload_args, workload_params},
+        results::{RunResults, RESULTS_VERSION},
+        samples::{spawn_sampler, SystemClock},
+        send_latency::SendLatencies,
+        sink_channel::{
+            sink_channel, spawn_depth_sampler, spawn_dropping_relay, ChannelDrops,
+            SinkChannelReport,
+        },
+        stall_watchdog::{spawn_stall_watchdog, ProducerProgresses, PROGRESS_INTERVAL},
+        transaction_details::{TransactionDetails, MAX_TRANSACTION_DETAILS},
+        transporter::{
+            QuicParams, Transporter, QUIC_CONNECTION_POOL_SIZE, UDP_CONNECTION_POOL_SIZE,
+        },
+        udp_drops::{UdpCounters, UdpDropsReport},
+        vote_instruction_variant::VoteInstructionVariant,
+        workload::{WorkloadManifest, WorkloadStream},
+    },
+    clap::{
+        crate_description, crate_name, value_t, value_t_or_exit, App, Arg, ArgMatches, SubCommand,
     },
+    solana_clap_utils::{
+        input_parsers::keypair_of,
+        input_validators::{is_keypair_or_ask_keyword, is_parsable},
+    },
+    solana_net_utils::SocketConfig,
+    solana_sdk::{hash::Hash, quic::QUIC_MAX_TIMEOUT, signature::Keypair},
     solana_streamer::{
-        packet::PacketBatchRecycler,
+        packet::{PacketBatchRecycler, PACKET_DATA_SIZE},
         quic::{spawn_server_multi, QuicServerParams},
-        streamer::{receiver, PacketBatchReceiver, StakedNodes, StreamerReceiveStats},
+        streamer::{receiver, PacketBatchReceiver, StreamerReceiveStats},
     },
-    solana_vote_program::{vote_instruction, vote_state::Vote},
     std::{
-        cmp::max,
-        collections::HashMap,
-        net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+        env, fmt,
+        io::{self, ErrorKind},
+        net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, UdpSocket},
+        num::NonZeroUsize,
+        path::{Path, PathBuf},
+        process,
         sync::{
             atomic::{AtomicBool, AtomicUsize, Ordering},
-            Arc, RwLock,
+            Arc,
         },
         thread::{self, spawn, JoinHandle, Result},
         time::{Duration, Instant, SystemTime},
@@ -34,34 +96,203 @@ const SINK_REPORT_INTERVAL: Duration = Duration::from_secs(5);
 const SINK_RECEIVE_TIMEOUT: Duration = Duration::from_secs(1);
 const SOCKET_RECEIVE_TIMEOUT: Duration = Duration::from_secs(1);
 const COALESCE_TIME: Duration = Duration::from_millis(1);
+const DEFAULT_MAX_CONNECTIONS_PER_PEER: &str = "1024";
+const DEFAULT_MAX_CONNECTIONS_PER_IP_PER_MIN: &str = "1024";
+const DEFAULT_STALL_THRESHOLD_MS: &str = "1000";
+const DEFAULT_MAX_THROUGHPUT_REGRESSION_PCT: &str = "5";
+const DEFAULT_MAX_LATENCY_REGRESSION_PCT: &str = "10";
+/// Flags of the workload, taken from the manifest under `--replay-workload`
+const REPLAYED_WORKLOAD_FLAGS: &[&str] = &[
+    "preset",
+    "num-recv-sockets",
+    "num-producers",
+    "transactions-per-producer",
+    "use-connection-cache",
+    "use-quic",
+    "vote-instruction",
+];
+
+/// Delay of a sink simulating a slow consumer, under `--sink-delay-us`
+#[derive(Clone, Copy)]
+struct SinkDelay {
+    delay: Duration,
+    /// The delay is applied once per `every` batches
+    every: u64,
+}
 
+/// Count the received packets, or hand them to the forwarder if
+/// `forward_queue` is set, counting them as well if `tee` is. Their payloads
+/// are checked to deserialize as transactions if `deserialize_check` is set
 fn sink(
     exit: Arc<AtomicBool>,
     received_size: Arc<AtomicUsize>,
     receiver: PacketBatchReceiver,
+    forward_queue: Option<Arc<ForwardQueue>>,
+    deserialize_check: Option<Arc<DeserializeCheck>>,
+    sink_delay: Option<SinkDelay>,
+    tee: bool,
     verbose: bool,
 ) -> JoinHandle<()> {
     spawn(move || {
         let mut last_report = Instant::now();
+        let mut num_batches = 0u64;
         while !exit.load(Ordering::Relaxed) {
             if let Ok(packet_batch) = receiver.recv_timeout(SINK_RECEIVE_TIMEOUT) {
-                received_size.fetch_add(packet_batch.len(), Ordering::Relaxed);
+                if let Some(deserialize_check) = &deserialize_check {
+                    packet_batch
+                        .iter()
+                        .filter_map(|packet| packet.data(..))
+                        .for_each(|payload| {
+                            deserialize_check.check(payload);
+                        });
+                }
+                if let Some(forward_queue) = &forward_queue {
+                    packet_batch
+                        .iter()
+                        .filter_map(|packet| packet.data(..))
+                        .for_each(|payload| forward_queue.push(payload.to_vec()));
+                }
+                if forward_queue.is_none() || tee {
+                    received_size.fetch_add(packet_batch.len(), Ordering::Relaxed);
+                }
+                num_batches += 1;
+                if let Some(sink_delay) = sink_delay {
+                    if num_batches % sink_delay.every == 0 {
+                        thread::sleep(sink_delay.delay);
+                    }
+                }
             }
 
-            let count = received_size.load(Ordering::Relaxed);
-
             if verbose && last_report.elapsed() > SINK_REPORT_INTERVAL {
-                println!("Received txns count: {count}");
+                let count = received_size.load(Ordering::Relaxed);
+                let depth = receiver.len();
+                println!("Received txns count: {count}, channel depth: {depth} batches");
                 last_report = Instant::now();
             }
         }
     })
 }
 
-const TRANSACTIONS_PER_THREAD: u64 = 1_000_000; // Number of transactions per thread
+/// Receive packets directly from `socket`, recording the DSCP each one arrived with.
+/// Used in place of the streamer receiver when `--report-dscp` is set.
+fn dscp_receiver(
+    exit: Arc<AtomicBool>,
+    received_size: Arc<AtomicUsize>,
+    socket: UdpSocket,
+    dscp_counts: Arc<DscpCounts>,
+    verbose: bool,
+) -> JoinHandle<()> {
+    thread::Builder::new()
+        .name("solRcvrBenVote".to_string())
+        .spawn(move || {
+            let mut buf = [0u8; PACKET_DATA_SIZE];
+            let mut last_report = Instant::now();
+            while !exit.load(Ordering::Relaxed) {
+                match dscp::recv_with_tos(&socket, &mut buf) {
+                    Ok((_nrecv, tos)) => {
+                        received_size.fetch_add(1, Ordering::Relaxed);
+                        dscp_counts.record(tos);
+                    }
+                    Err(err)
+                        if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                    Err(err) => println!("Error receiving packet {err:?}"),
+                }
 
-fn main() -> Result<()> {
-    let matches = App::new(crate_name!())
+                if verbose && last_report.elapsed() > SINK_REPORT_INTERVAL {
+                    let count = received_size.load(Ordering::Relaxed);
+                    println!("Received txns count: {count}");
+                    dscp_counts.report();
+                    last_report = Instant::now();
+                }
+            }
+        })
+        .unwrap()
+}
+
+fn quic_connection_limit_args<'a, 'b>() -> [Arg<'a, 'b>; 2] {
+    [
+        Arg::with_name("max-connections-per-peer")
+            .long("max-connections-per-peer")
+            .value_name("NUM")
+            .takes_value(true)
+            .default_value(DEFAULT_MAX_CONNECTIONS_PER_PEER)
+            .validator(is_parsable::<usize>)
+            .help("Maximum number of concurrent QUIC connections the server accepts from a peer"),
+        Arg::with_name("max-connections-per-ip-per-min")
+            .long("max-connections-per-ip-per-min")
+            .value_name("NUM")
+            .takes_value(true)
+            .default_value(DEFAULT_MAX_CONNECTIONS_PER_IP_PER_MIN)
+            .validator(is_parsable::<u64>)
+            .help(
+                "Maximum number of QUIC connections the server accepts from an IP address \
+                   per minute",
+            ),
+    ]
+}
+
+fn quic_server_params(matches: &ArgMatches) -> QuicServerParams {
+    QuicServerParams {
+        max_connections_per_ipaddr_per_min: value_t_or_exit!(
+            matches,
+            "max-connections-per-ip-per-min",
+            u64
+        ),
+        max_connections_per_peer: value_t_or_exit!(matches, "max-connections-per-peer", usize),
+        ..Default::default()
+    }
+}
+
+/// Sides of the benchmark run by this process
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RunMode {
+    ServerAndClient,
+    ServerOnly,
+    ClientOnly,
+}
+
+/// Invalid combination of the client and server flags
+#[derive(Debug, PartialEq, Eq)]
+enum RunModeError {
+    ServerOnlyAndClientOnly,
+    ClientWithoutDestination,
+}
+
+impl fmt::Display for RunModeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ServerOnlyAndClientOnly => f.write_str(
+                "--server-only and --client-only cannot be set together, leave both out to run \
+                 the server and the client in this process",
+            ),
+            Self::ClientWithoutDestination => f.write_str(
+                "--client-only requires a destination: set --server-address to the address of \
+                 the server, or --control to its control port to be told the address",
+            ),
+        }
+    }
+}
+
+fn run_mode(matches: &ArgMatches) -> std::result::Result<RunMode, RunModeError> {
+    match (
+        matches.is_present("server-only"),
+        matches.is_present("client-only"),
+    ) {
+        (true, true) => Err(RunModeError::ServerOnlyAndClientOnly),
+        (true, false) => Ok(RunMode::ServerOnly),
+        (false, true) => {
+            if matches.is_present("server-address") || matches.is_present("control") {
+                Ok(RunMode::ClientOnly)
+            } else {
+                Err(RunModeError::ClientWithoutDestination)
+            }
+        }
+        (false, false) => Ok(RunMode::ServerAndClient),
+    }
+}
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new(crate_name!())
         .about(crate_description!())
         .version(solana_version::version!())
         .arg(
@@ -73,20 +304,7 @@ fn main() -> Result<()> {
                 .validator(is_keypair_or_ask_keyword)
                 .help("Identity keypair for the QUIC endpoint when '--use-quic' is set true. If it is not specified a dynamic key is created."),
         )
-        .arg(
-            Arg::with_name("num-recv-sockets")
-                .long("num-recv-sockets")
-                .value_name("NUM")
-                .takes_value(true)
-                .help("Use NUM receive sockets"),
-        )
-        .arg(
-            Arg::with_name("num-producers")
-                .long("num-producers")
-                .value_name("NUM")
-                .takes_value(true)
-                .help("Use this many producer threads."),
-        )
+        .args(&workload_args())
         .arg(
             Arg::with_name("server-only")
                 .long("server-only")
@@ -97,8 +315,7 @@ fn main() -> Result<()> {
             Arg::with_name("client-only")
                 .long("client-only")
                 .takes_value(false)
-                .requires("server-address")
-                .help("Run the bench tool as a client only."),
+                .help("Run the bench tool as a client only. Requires --server-address or --control."),
         )
         .arg(
             Arg::with_name("server-address")
@@ -110,10 +327,25 @@ fn main() -> Result<()> {
                 .help("The destination streamer address to which the client will send transactions to"),
         )
         .arg(
-            Arg::with_name("use-connection-cache")
-                .long("use-connection-cache")
-                .takes_value(false)
-                .help("Use this many producer threads."),
+            Arg::with_name("control-port")
+                .long("control-port")
+                .value_name("PORT")
+                .takes_value(true)
+                .requires("server-only")
+                .validator(is_parsable::<u16>)
+                .help("Answer the handshake of a client started with --control on this TCP port, \
+                       and exit once that client is done instead of waiting for ^C"),
+        )
+        .arg(
+            Arg::with_name("control")
+                .long("control")
+                .value_name("HOST:PORT")
+                .takes_value(true)
+                .requires("client-only")
+                .validator(|arg| solana_net_utils::is_host_port(arg.to_string()))
+                .help("Handshake with the server on its --control-port before producing, \
+                       checking that both use the same transport, and signal it the end of the \
+                       run. The server tells its address unless --server-address is set"),
         )
         .arg(
             Arg::with_name("verbose")
@@ -122,28 +354,605 @@ fn main() -> Result<()> {
                 .help("Show verbose messages."),
         )
         .arg(
-            Arg::with_name("use-quic")
-                .long("use-quic")
+            Arg::with_name("dscp")
+                .long("dscp")
+                .value_name("VALUE")
+                .takes_value(true)
+                .validator(|arg| dscp::parse_dscp(&arg).map(|_| ()))
+                .help("Mark sent packets with this DSCP (0-63). Only supported for the direct UDP socket transport on linux."),
+        )
+        .arg(
+            Arg::with_name("report-dscp")
+                .long("report-dscp")
+                .takes_value(false)
+                .help("Count received packets per observed DSCP, to detect remarking along the path. \
+                       Bypasses the streamer receiver. Only supported for UDP on linux."),
+        )
+        .arg(
+            Arg::with_name("vote-instruction")
+                .long("vote-instruction")
+                .value_name("VARIANT")
+                .takes_value(true)
+                .default_value("vote")
+                .possible_values(VoteInstructionVariant::NAMES)
+                .help("Vote instruction sent by the producers"),
+        )
+        .arg(
+            Arg::with_name("show-transaction-details")
+                .long("show-transaction-details")
+                .takes_value(false)
+                .help("Print the signature, fee payer, vote slots, recent blockhash and size of \
+                       the first 100 sent transactions. Only effective with --num-producers 1"),
+        )
+        .arg(
+            Arg::with_name("report-cache-stats")
+                .long("report-cache-stats")
+                .takes_value(false)
+                .help("At the end of the run, report how often the producers reused pooled \
+                       connections. Only effective with the connection cache transports"),
+        )
+        .arg(
+            Arg::with_name("max-memory-mb")
+                .long("max-memory-mb")
+                .value_name("MB")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Bound the memory used by the server. When the process RSS exceeds this, \
+                       the run is aborted. Only enforced on linux"),
+        )
+        .args(&quic_connection_limit_args())
+        .arg(
+            Arg::with_name("forward-to")
+                .long("forward-to")
+                .value_name("HOST:PORT")
+                .takes_value(true)
+                .conflicts_with_all(&["client-only", "report-dscp"])
+                .validator(|arg| solana_net_utils::is_host_port(arg.to_string()))
+                .help("Instead of consuming the received packets, re-send them to this downstream \
+                       address as a node that is not the leader forwards votes, reporting the \
+                       forwarding throughput and added latency"),
+        )
+        .arg(
+            Arg::with_name("tee")
+                .long("tee")
+                .takes_value(false)
+                .requires("forward-to")
+                .help("Count the forwarded packets as received too"),
+        )
+        .arg(
+            Arg::with_name("sink-delay-us")
+                .long("sink-delay-us")
+                .value_name("MICROS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .conflicts_with_all(&["client-only", "report-dscp"])
+                .help("Have the sinks sleep MICROS microseconds after receiving a batch, to \
+                       simulate a consumer slower than the network"),
+        )
+        .arg(
+            Arg::with_name("sink-delay-every")
+                .long("sink-delay-every")
+                .value_name("BATCHES")
+                .takes_value(true)
+                .default_value("1")
+                .requires("sink-delay-us")
+                .validator(is_parsable::<u64>)
+                .help("Apply the delay of --sink-delay-us once every BATCHES batches"),
+        )
+        .arg(
+            Arg::with_name("channel-capacity")
+                .long("channel-capacity")
+                .value_name("BATCHES")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .conflicts_with_all(&["client-only", "report-dscp"])
+                .help("Bound the channels between the receivers and the sinks to BATCHES \
+                       batches, the receivers blocking while they are full. The channels are \
+                       unbounded by default"),
+        )
+        .arg(
+            Arg::with_name("drop-on-full-channel")
+                .long("drop-on-full-channel")
+                .takes_value(false)
+                .requires("channel-capacity")
+                .help("Drop the batches finding a channel of --channel-capacity full instead \
+                       of blocking the receivers, reporting them apart from the packets never \
+                       received"),
+        )
+        .arg(
+            Arg::with_name("forward-use-quic")
+                .long("forward-use-quic")
                 .value_name("Boolean")
                 .takes_value(true)
-                .default_value("false")
-                .help("Controls if to use QUIC for sending/receiving vote transactions."),
+                .requires("forward-to")
+                .help("Controls if to use QUIC for forwarding the received packets \
+                       [default: false]"),
+        )
+        .arg(
+            Arg::with_name("forward-identity")
+                .long("forward-identity")
+                .value_name("KEYPAIR")
+                .takes_value(true)
+                .requires("forward-to")
+                .validator(is_keypair_or_ask_keyword)
+                .help("Identity keypair of the forwarder's QUIC endpoint. If it is not specified a \
+                       dynamic key is created."),
+        )
+        .arg(
+            Arg::with_name("forward-queue-size")
+                .long("forward-queue-size")
+                .value_name("NUM")
+                .takes_value(true)
+                .requires("forward-to")
+                .validator(is_parsable::<usize>)
+                .help("Number of packets waiting to be forwarded beyond which the oldest are \
+                       dropped [default: 10000]"),
+        )
+        .arg(
+            Arg::with_name("results-file")
+                .long("results-file")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("Write the parameters and results of the run to this file as JSON"),
+        )
+        .arg(
+            Arg::with_name("samples-file")
+                .long("samples-file")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("Every 5 seconds, append the counts of received and sent transactions to \
+                       this file as a `timestamp,received,sent` line"),
+        )
+        .arg(
+            Arg::with_name("rotate-identity-secs")
+                .long("rotate-identity-secs")
+                .value_name("SECS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Every SECS seconds, switch the producers to a QUIC connection cache with \
+                       a new identity, reporting the send errors following each rotation. \
+                       Requires --use-quic true"),
+        )
+        .arg(
+            Arg::with_name("rotate-identity-dir")
+                .long("rotate-identity-dir")
+                .value_name("DIR")
+                .takes_value(true)
+                .requires("rotate-identity-secs")
+                .help("Step through the keypair files of this directory on each rotation, \
+                       instead of generating a new identity"),
+        )
+        .arg(
+            Arg::with_name("num-identities")
+                .long("num-identities")
+                .value_name("N")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .conflicts_with_all(&["rotate-identity-secs", "fork-producers", "dscp"])
+                .help("Have the producers send each transaction through the next of a pool of \
+                       N QUIC client identities in turn, each staked in the staked nodes of the \
+                       run. With --server-only, stake the identities of --identity-pool-dir \
+                       instead, for a client run with the same pool. Requires --use-quic true"),
+        )
+        .arg(
+            Arg::with_name("identity-pool-dir")
+                .long("identity-pool-dir")
+                .value_name("DIR")
+                .takes_value(true)
+                .requires("num-identities")
+                .help("Take the identities of the pool from the first N keypair files of this \
+                       directory, instead of generating them. Required with --server-only"),
+        )
+        .arg(
+            Arg::with_name("pause-schedule")
+                .long("pause-schedule")
+                .value_name("ON_SECS:OFF_SECS[:REPEATS]")
+                .takes_value(true)
+                .conflicts_with("server-only")
+                .validator(|arg| arg.parse::<PauseSchedule>().map(|_| ()))
+                .help("Have the producers send for ON_SECS seconds then fully stop for OFF_SECS \
+                       seconds, REPEATS times or until they are done, reporting the transactions \
+                       sent and received in each cycle and the time from each resume to the \
+                       first packet received"),
+        )
+        .arg(
+            Arg::with_name("teardown-on-pause")
+                .long("teardown-on-pause")
+                .takes_value(false)
+                .requires("pause-schedule")
+                .conflicts_with("rotate-identity-secs")
+                .help("Close the connections of the producers on each pause, so that they \
+                       reconnect on resume"),
+        )
+        .arg(
+            Arg::with_name("fork-producers")
+                .long("fork-producers")
+                .takes_value(false)
+                .conflicts_with_all(&[
+                    "server-only",
+                    "show-transaction-details",
+                    "samples-file",
+                    "rotate-identity-secs",
+                    "pause-schedule",
+                    "seed",
+                    "dump-workload",
+                    "replay-workload",
+                    "pipeline",
+                ])
+                .help("Run each producer as a child process with a connection cache of its own \
+                       instead of a thread, reporting the results aggregated over the children. \
+                       Only supported on unix"),
+        )
+        .arg(
+            Arg::with_name("producer-child")
+                .long("producer-child")
+                .value_name("FD")
+                .takes_value(true)
+                .hidden(true)
+                .requires("client-only")
+                .conflicts_with("fork-producers")
+                .validator(is_parsable::<i32>)
+                .help("Run as a child of --fork-producers, writing the summary of the run to \
+                       this file descriptor"),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Derive the transactions of the producers from this seed, so that another \
+                       run with the same seed and workload sends the same bytes. Prints a hash of \
+                       the transactions of each producer at the end of the run"),
+        )
+        .arg(
+            Arg::with_name("dump-workload")
+                .long("dump-workload")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("Write the seed, the parameters and the producer streams of the workload \
+                       to this file, for --replay-workload. A seed is picked unless --seed is set"),
+        )
+        .arg(
+            Arg::with_name("replay-workload")
+                .long("replay-workload")
+                .value_name("PATH")
+                .takes_value(true)
+                .conflicts_with_all(&["seed", "dump-workload"])
+                .help("Send the transactions of the workload written by --dump-workload to this \
+                       file, byte for byte. The workload flags are taken from the file"),
+        )
+        .arg(
+            Arg::with_name("pipeline")
+                .long("pipeline")
+                .takes_value(false)
+                .help("Split each producer into a thread serializing the transactions and a \
+                       thread sending them, and report whether the serialization or the sends \
+                       limit the rate of each producer"),
+        )
+        .arg(
+            Arg::with_name("stall-threshold-ms")
+                .long("stall-threshold-ms")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .default_value(DEFAULT_STALL_THRESHOLD_MS)
+                .validator(is_parsable::<u64>)
+                .help("Warn about the producers that made no progress in their send loop for \
+                       longer than this, and report the number of stalls of each producer"),
+        )
+        .arg(
+            Arg::with_name("verify-deserialize")
+                .long("verify-deserialize")
+                .takes_value(false)
+                .conflicts_with_all(&["client-only", "report-dscp"])
+                .help("Check that each received packet deserializes as a transaction, failing \
+                       the run if any does not"),
+        )
+        .arg(config_arg())
+        .subcommand(
+            SubCommand::with_name("compare-results")
+                .about("Compare the results of a run to those of a baseline, both written by \
+                        --results-file, failing if a metric regressed beyond its threshold")
+                .arg(
+                    Arg::with_name("baseline")
+                        .value_name("BASELINE")
+                        .required(true)
+                        .index(1)
+                        .help("Results of the baseline run"),
+                )
+                .arg(
+                    Arg::with_name("current")
+                        .value_name("CURRENT")
+                        .required(true)
+                        .index(2)
+                        .help("Results of the run compared to the baseline"),
+                )
+                .arg(
+                    Arg::with_name("max-throughput-regression-pct")
+                        .long("max-throughput-regression-pct")
+                        .value_name("PERCENT")
+                        .takes_value(true)
+                        .default_value(DEFAULT_MAX_THROUGHPUT_REGRESSION_PCT)
+                        .validator(is_parsable::<f64>)
+                        .help("Largest drop of the throughput allowed, in percent of the \
+                               baseline"),
+                )
+                .arg(
+                    Arg::with_name("max-latency-regression-pct")
+                        .long("max-latency-regression-pct")
+                        .value_name("PERCENT")
+                        .takes_value(true)
+                        .default_value(DEFAULT_MAX_LATENCY_REGRESSION_PCT)
+                        .validator(is_parsable::<f64>)
+                        .help("Largest increase of the p50, p90 and p99 send latencies allowed, \
+                               in percent of the baseline"),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .long("force")
+                        .takes_value(false)
+                        .help("Compare runs of different transports, producer counts or vote \
+                               instructions"),
+                ),
+        )
+}
 
-    solana_logger::setup();
+/// Compare the results files of `compare-results`, returning the exit code
+fn compare_results(matches: &ArgMatches) -> i32 {
+    let read = |name| {
+        RunResults::read(Path::new(matches.value_of(name).unwrap())).unwrap_or_else(|err| {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        })
+    };
+    let (baseline, current) = (read("baseline"), read("current"));
 
-    let mut num_sockets = 1usize;
-    if let Some(n) = matches.value_of("num-recv-sockets") {
-        num_sockets = max(num_sockets, n.to_string().parse().expect("integer"));
+    let incompatibilities = incompatibilities(&baseline, &current);
+    if !incompatibilities.is_empty() {
+        let severity = if matches.is_present("force") {
+            "Warning"
+        } else {
+            "Error"
+        };
+        for incompatibility in &incompatibilities {
+            eprintln!("{severity}: the runs differ in {incompatibility}");
+        }
+        if !matches.is_present("force") {
+            eprintln!("Error: the runs are not comparable, use --force to compare them anyway");
+            return 1;
+        }
+    }
+
+    let thresholds = RegressionThresholds {
+        max_throughput_regression_pct: value_t_or_exit!(
+            matches,
+            "max-throughput-regression-pct",
+            f64
+        ),
+        max_latency_regression_pct: value_t_or_exit!(matches, "max-latency-regression-pct", f64),
+    };
+    let comparison = Comparison::new(&baseline, &current);
+    print!("{}", comparison.table(&thresholds));
+    let regressions = comparison.regressions(&thresholds);
+    if !regressions.is_empty() {
+        eprintln!(
+            "Error: {} metrics regressed beyond their threshold",
+            regressions.len()
+        );
     }
+    comparison.exit_code(&thresholds)
+}
+
+fn main() -> Result<()> {
+    let matches = get_matches_with_config(app(), env::args_os().collect());
+    if let Some(matches) = matches.subcommand_matches("compare-results") {
+        process::exit(compare_results(matches));
+    }
+
+    solana_logger::setup();
 
-    let vote_use_quic = value_t_or_exit!(matches, "use-quic", bool);
-    let num_producers: u64 = value_t!(matches, "num-producers", u64).unwrap_or(4);
-    let use_connection_cache = matches.is_present("use-connection-cache");
-    let server_only = matches.is_present("server-only");
-    let client_only = matches.is_present("client-only");
+    let available_parallelism = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let replay_workload = value_t!(matches, "replay-workload", PathBuf).ok();
+    let workload = if let Some(replay_workload) = &replay_workload {
+        if let Some(flag) = REPLAYED_WORKLOAD_FLAGS
+            .iter()
+            .find(|flag| matches.occurrences_of(flag) > 0)
+        {
+            eprintln!(
+                "Error: --{flag} cannot be set with --replay-workload, which replays the \
+                       workload of the manifest"
+            );
+            process::exit(1);
+        }
+        let workload = WorkloadManifest::read(replay_workload).unwrap_or_else(|err| {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        });
+        if workload.tool_version != solana_version::version!() {
+            eprintln!(
+                "Warning: the workload was dumped by bench-vote {}, the transactions may differ",
+                workload.tool_version
+            );
+        }
+        workload
+    } else {
+        WorkloadManifest::new(
+            value_t!(matches, "seed", u64).unwrap_or_else(|_| rand::random()),
+            workload_params(&matches, available_parallelism),
+            value_t_or_exit!(matches, "vote-instruction", VoteInstructionVariant),
+        )
+    };
+    if let Some(dump_workload) = value_t!(matches, "dump-workload", PathBuf).ok() {
+        if let Err(err) = workload.write(&dump_workload) {
+            eprintln!(
+                "Error: failed to write the workload to {}: {err}",
+                dump_workload.display()
+            );
+            process::exit(1);
+        }
+        println!(
+            "Workload with seed {} written to {}",
+            workload.seed,
+            dump_workload.display()
+        );
+    }
+    let reproducible = matches.is_present("seed")
+        || matches.is_present("dump-workload")
+        || replay_workload.is_some();
+    let params = workload.parameters.clone();
+    println!("Parameters: {params}");
+    let num_sockets = params.num_recv_sockets;
+    let vote_use_quic = params.use_quic;
+    let num_producers = params.num_producers;
+    let transactions_per_producer = params.transactions_per_producer;
+    let use_connection_cache = params.use_connection_cache;
+    let mode = run_mode(&matches).unwrap_or_else(|err| {
+        eprintln!("Error: {err}");
+        process::exit(1);
+    });
+    let server_only = mode == RunMode::ServerOnly;
+    let client_only = mode == RunMode::ClientOnly;
     let verbose = matches.is_present("verbose");
+    let pipeline = matches.is_present("pipeline");
+    let deserialize_check = matches
+        .is_present("verify-deserialize")
+        .then(|| Arc::new(DeserializeCheck::default()));
+    let dscp = matches
+        .value_of("dscp")
+        .map(|value| dscp::parse_dscp(value).unwrap());
+    let report_dscp = matches.is_present("report-dscp");
+    let max_memory_mb = value_t!(matches, "max-memory-mb", u64).ok();
+    let stall_threshold_ms = value_t_or_exit!(matches, "stall-threshold-ms", u64);
+    let results_file = value_t!(matches, "results-file", PathBuf).ok();
+    let samples_file = value_t!(matches, "samples-file", PathBuf).ok();
+    let rotate_identity_interval = value_t!(matches, "rotate-identity-secs", u64)
+        .ok()
+        .map(Duration::from_secs);
+    if rotate_identity_interval.is_some() && (server_only || !vote_use_quic) {
+        eprintln!("Error: --rotate-identity-secs requires --use-quic true and a client");
+        process::exit(1);
+    }
+    if rotate_identity_interval.is_some_and(|interval| interval.is_zero()) {
+        eprintln!("Error: --rotate-identity-secs must be greater than 0");
+        process::exit(1);
+    }
+    let identities = rotate_identity_interval.map(|_| {
+        matches
+            .value_of("rotate-identity-dir")
+            .map_or(Ok(IdentitySource::Generated), |dir| {
+                IdentitySource::from_dir(Path::new(dir))
+            })
+            .unwrap_or_else(|err| {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            })
+    });
+    let num_identities = value_t!(matches, "num-identities", usize).ok();
+    if num_identities.is_some() && !vote_use_quic {
+        eprintln!("Error: --num-identities requires --use-quic true");
+        process::exit(1);
+    }
+    if num_identities == Some(0) {
+        eprintln!("Error: --num-identities must be greater than 0");
+        process::exit(1);
+    }
+    // a generated pool would be unknown to the client of the run
+    if num_identities.is_some() && server_only && !matches.is_present("identity-pool-dir") {
+        eprintln!("Error: --num-identities with --server-only requires --identity-pool-dir");
+        process::exit(1);
+    }
+    let identity_pool = num_identities.map(|num_identities| {
+        let identity_pool = matches
+            .value_of("identity-pool-dir")
+            .map_or_else(
+                || Ok(IdentityPool::generate(num_identities)),
+                |dir| IdentityPool::from_dir(Path::new(dir), num_identities),
+            )
+            .unwrap_or_else(|err| {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            });
+        Arc::new(identity_pool)
+    });
+    let pause_schedule = value_t!(matches, "pause-schedule", PauseSchedule).ok();
+    let teardown_on_pause = matches.is_present("teardown-on-pause");
+    if let Some(pause_schedule) = &pause_schedule {
+        if vote_use_quic && !teardown_on_pause && pause_schedule.off <= QUIC_MAX_TIMEOUT {
+            eprintln!(
+                "Warning: the pauses of --pause-schedule do not cross the QUIC idle timeout of \
+                 {}s, the connections may stay open across them",
+                QUIC_MAX_TIMEOUT.as_secs()
+            );
+        }
+    }
+    let fork_producers = matches.is_present("fork-producers");
+    if fork_producers && !cfg!(unix) {
+        eprintln!("Error: --fork-producers is only supported on unix");
+        process::exit(1);
+    }
+    let producer_child = value_t!(matches, "producer-child", i32).ok();
+    let mut show_transaction_details = matches.is_present("show-transaction-details");
+    if show_transaction_details && num_producers != 1 {
+        eprintln!("Warning: --show-transaction-details is ignored without --num-producers 1");
+        show_transaction_details = false;
+    }
+    let mut report_cache_stats = matches.is_present("report-cache-stats");
+    if report_cache_stats && !(use_connection_cache || vote_use_quic) {
+        eprintln!(
+            "Warning: --report-cache-stats is ignored without --use-connection-cache or \
+             --use-quic true"
+        );
+        report_cache_stats = false;
+    }
+
+    if dscp.is_some() && !server_only && (use_connection_cache || vote_use_quic) {
+        eprintln!(
+            "Error: --dscp cannot be applied to the connection cache transports, whose sockets \
+             are not accessible. Use the direct UDP socket transport instead."
+        );
+        process::exit(1);
+    }
+    if report_dscp && !client_only && vote_use_quic {
+        eprintln!("Error: --report-dscp is not supported with --use-quic");
+        process::exit(1);
+    }
+
+    let sink_delay = value_t!(matches, "sink-delay-us", u64)
+        .ok()
+        .map(|delay_us| SinkDelay {
+            delay: Duration::from_micros(delay_us),
+            every: value_t_or_exit!(matches, "sink-delay-every", u64),
+        });
+    if sink_delay.is_some_and(|sink_delay| sink_delay.every == 0) {
+        eprintln!("Error: --sink-delay-every must be greater than 0");
+        process::exit(1);
+    }
+    let channel_capacity = value_t!(matches, "channel-capacity", usize).ok();
+    if channel_capacity == Some(0) {
+        eprintln!("Error: --channel-capacity must be greater than 0");
+        process::exit(1);
+    }
+    let channel_drops = matches
+        .is_present("drop-on-full-channel")
+        .then(|| Arc::new(ChannelDrops::default()));
+
+    let forward_to = matches.value_of("forward-to").map(|addr| {
+        solana_net_utils::parse_host_port(addr).expect("Expecting a valid forward address")
+    });
+    let tee = matches.is_present("tee");
+    let forward_use_quic = matches.is_present("forward-use-quic")
+        && value_t_or_exit!(matches, "forward-use-quic", bool);
+    let forward_queue_size =
+        value_t!(matches, "forward-queue-size", usize).unwrap_or(DEFAULT_FORWARD_QUEUE_SIZE);
+    if forward_queue_size == 0 {
+        eprintln!("Error: --forward-queue-size must be greater than 0");
+        process::exit(1);
+    }
+
+    let control_port = value_t!(matches, "control-port", u16).ok();
+    let control = matches.value_of("control").map(|addr| {
+        solana_net_utils::parse_host_port(addr).expect("Expecting a valid control address")
+    });
 
     let destination = matches.is_present("server-address").then(|| {
         let addr = matches
@@ -160,26 +969,17 @@ fn main() -> Result<()> {
             println!("--identity is not specified when --use-quic is on. Will generate a key dynamically.");
             Some(Keypair::new())
         }).unwrap();
-
-        let stake: u64 = 1024;
-        let total_stake: u64 = 1024;
-
-        let stakes = HashMap::from([
-            (identity_keypair.pubkey(), stake),
-            (Pubkey::new_unique(), total_stake.saturating_sub(stake)),
-        ]);
-        let staked_nodes: Arc<RwLock<StakedNodes>> = Arc::new(RwLock::new(StakedNodes::new(
-            Arc::new(stakes),
-            HashMap::<Pubkey, u64>::default(), // overrides
-        )));
-
-        QuicParams {
-            identity_keypair,
-            staked_nodes
+        match &identity_pool {
+            Some(identity_pool) => identity_pool.quic_params(identity_keypair),
+            None => QuicParams::new(identity_keypair),
         }
     });
 
-    let (exit, read_threads, sink_threads, destination) = if !client_only {
+    let dscp_counts = Arc::new(DscpCounts::default());
+    let received_size = Arc::new(AtomicUsize::new(0));
+    let mut forward_queue = None;
+
+    let (exit, read_threads, sink_threads, depth_sampler, destination) = if !client_only {
         let exit = Arc::new(AtomicBool::new(false));
 
         let mut read_channels = Vec::new();
@@ -194,14 +994,26 @@ fn main() -> Result<()> {
         )
         .unwrap();
         let stats = Arc::new(StreamerReceiveStats::new("bench-vote-test"));
+        // the sender for the receivers and the receiver for a sink, relayed
+        // under --drop-on-full-channel
+        let new_channel = |read_threads: &mut Vec<JoinHandle<()>>| {
+            let (sender, receiver) = sink_channel(channel_capacity);
+            let Some(channel_drops) = &channel_drops else {
+                return (sender, receiver);
+            };
+            let (ingress_sender, ingress) = sink_channel(None);
+            read_threads.push(spawn_dropping_relay(
+                exit.clone(),
+                ingress,
+                sender,
+                channel_drops.clone(),
+            ));
+            (ingress_sender, receiver)
+        };
 
         if let Some(quic_params) = &quic_params {
-            let quic_server_params = QuicServerParams {
-                max_connections_per_ipaddr_per_min: 1024,
-                max_connections_per_peer: 1024,
-                ..Default::default()
-            };
-            let (s_reader, r_reader) = unbounded();
+            let quic_server_params = quic_server_params(&matches);
+            let (s_reader, r_reader) = new_channel(&mut read_threads);
             read_channels.push(r_reader);
 
             let server = spawn_server_multi(
@@ -216,11 +1028,26 @@ fn main() -> Result<()> {
             )
             .unwrap();
             read_threads.push(server.thread);
+        } else if report_dscp {
+            for read in read_sockets {
+                read.set_read_timeout(Some(SOCKET_RECEIVE_TIMEOUT)).unwrap();
+                dscp::enable_recv_tos(&read).unwrap_or_else(|err| {
+                    eprintln!("Error: failed to enable DSCP reporting: {err}");
+                    process::exit(1);
+                });
+                read_threads.push(dscp_receiver(
+                    exit.clone(),
+                    received_size.clone(),
+                    read,
+                    dscp_counts.clone(),
+                    verbose,
+                ));
+            }
         } else {
             for read in read_sockets {
                 read.set_read_timeout(Some(SOCKET_RECEIVE_TIMEOUT)).unwrap();
 
-                let (s_reader, r_reader) = unbounded();
+                let (s_reader, r_reader) = new_channel(&mut read_threads);
                 read_channels.push(r_reader);
                 read_threads.push(receiver(
                     "solRcvrBenVote".to_string(),
@@ -237,49 +1064,325 @@ fn main() -> Result<()> {
             }
         }
 
-        let received_size = Arc::new(AtomicUsize::new(0));
+        if let Some(max_memory_mb) = max_memory_mb {
+            let mut estimates: Vec<EstimateFn> = vec![];
+            if report_dscp {
+                let dscp_counts = dscp_counts.clone();
+                estimates.push(Box::new(move || ComponentEstimate {
+                    name: "dscp counts",
+                    bytes: dscp_counts.memory_estimate(),
+                }));
+            }
+            read_threads.push(spawn_memory_watchdog(
+                exit.clone(),
+                max_memory_mb,
+                estimates,
+            ));
+        }
+
+        if let Some(forward_to) = forward_to {
+            let forward_quic_params = forward_use_quic.then(|| {
+                QuicParams::new(
+                    keypair_of(&matches, "forward-identity").unwrap_or_else(Keypair::new),
+                )
+            });
+            let transporter = Transporter::new(
+                "connection_cache_vote_forward",
+                false,
+                forward_quic_params.as_ref(),
+                None,
+            )
+            .unwrap_or_else(|err| {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            });
+            let queue = Arc::new(ForwardQueue::new(forward_queue_size));
+            println!("Forwarding received packets to {forward_to:?}");
+            read_threads.push(spawn_forwarder(
+                exit.clone(),
+                queue.clone(),
+                transporter,
+                forward_to,
+                verbose,
+            ));
+            forward_queue = Some(queue);
+        }
+
+        let depth_sampler =
+            spawn_depth_sampler(exit.clone(), read_channels.clone(), SINK_REPORT_INTERVAL);
         let sink_threads: Vec<_> = read_channels
             .into_iter()
-            .map(|r_reader| sink(exit.clone(), received_size.clone(), r_reader, verbose))
+            .map(|r_reader| {
+                sink(
+                    exit.clone(),
+                    received_size.clone(),
+                    r_reader,
+                    forward_queue.clone(),
+                    deserialize_check.clone(),
+                    sink_delay,
+                    tee,
+                    verbose,
+                )
+            })
             .collect();
 
         let destination = SocketAddr::new(ip_addr, port);
         println!("Running server at {destination:?}");
+
+        if let Some(control_port) = control_port {
+            let hello = ServerHello::new(Transport::new(vote_use_quic), vec![destination]);
+            println!(
+                "Control channel on port {control_port}, run id {}",
+                hello.run_id
+            );
+            let control_server = TcpListener::bind((ip_addr, control_port))
+                .and_then(|listener| spawn_control_server(listener, hello, exit.clone()))
+                .unwrap_or_else(|err| {
+                    eprintln!("Error: failed to start the control channel: {err}");
+                    process::exit(1);
+                });
+            read_threads.push(control_server);
+        }
         (
             Some(exit),
             Some(read_threads),
             Some(sink_threads),
-            destination,
+            Some(depth_sampler),
+            Some(destination),
         )
     } else {
-        (None, None, None, destination.unwrap())
+        (None, None, None, None, destination)
     };
 
+    let control_client = control.map(|control| {
+        let (control_client, hello) =
+            ControlClient::connect(control, Transport::new(vote_use_quic)).unwrap_or_else(|err| {
+                eprintln!("Error: control handshake with {control} failed: {err}");
+                process::exit(1);
+            });
+        println!("Server at {control} is ready, run id {}", hello.run_id);
+        (control_client, hello.data_address(control))
+    });
+    let destination = destination
+        .or_else(|| control_client.as_ref().and_then(|(_, addr)| *addr))
+        .unwrap_or_else(|| {
+            eprintln!("Error: the server did not tell its address, set --server-address");
+            process::exit(1);
+        });
+
+    let udp_counters_start = (!client_only).then(|| UdpCounters::snapshot(destination.port()));
+
     let start = SystemTime::now();
 
-    let producer_threads = (!server_only).then(|| {
+    let cache_stats = report_cache_stats.then(|| Arc::new(CacheStats::default()));
+    let sent_count = Arc::new(AtomicUsize::new(0));
+    let sampler_exit = Arc::new(AtomicBool::new(false));
+    let sampler = samples_file.map(|samples_file| {
+        spawn_sampler(
+            sampler_exit.clone(),
+            &samples_file,
+            SINK_REPORT_INTERVAL,
+            received_size.clone(),
+            sent_count.clone(),
+            SystemClock::default(),
+        )
+        .unwrap_or_else(|err| {
+            eprintln!(
+                "Error: failed to create the samples file {}: {err}",
+                samples_file.display()
+            );
+            process::exit(1);
+        })
+    });
+    let transporter_name = if quic_params.is_some() {
+        "connection_cache_vote_quic"
+    } else {
+        "connection_cache_vote_udp"
+    };
+    let transporter = (!server_only && !fork_producers).then(|| {
+        let transporter = match (&identity_pool, &quic_params) {
+            // the identities of the pool share the staked nodes of the run
+            (Some(identity_pool), Some(quic_params)) => Transporter::new_pool(
+                transporter_name,
+                &identity_pool.client_params(&quic_params.staked_nodes),
+            ),
+            _ => Transporter::new(
+                transporter_name,
+                use_connection_cache,
+                quic_params.as_ref(),
+                dscp,
+            ),
+        }
+        .unwrap_or_else(|err| {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        });
+        Arc::new(SharedTransporter::new(transporter))
+    });
+    let rotation_exit = Arc::new(AtomicBool::new(false));
+    let rotation = rotate_identity_interval
+        .zip(identities)
+        .zip(transporter.clone())
+        .map(|((interval, identities), transporter)| {
+            let tracker = Arc::new(RotationTracker::new(Instant::now()));
+            let rotation_thread = spawn_identity_rotation(
+                rotation_exit.clone(),
+                interval,
+                identities,
+                transporter,
+                tracker.clone(),
+            );
+            (tracker, rotation_thread)
+        });
+    let pause_exit = Arc::new(AtomicBool::new(false));
+    let received_count = (!client_only).then(|| received_size.clone());
+    let pause = pause_schedule
+        .zip(transporter.clone())
+        .map(|(pause_schedule, transporter)| {
+            let tracker = Arc::new(PauseTracker::new(
+                Instant::now(),
+                received_count
+                    .as_ref()
+                    .map(|received| received.load(Ordering::Relaxed) as u64),
+            ));
+            let teardown = teardown_on_pause.then(|| {
+                let identity = quic_params
+                    .as_ref()
+                    .map(|quic_params| quic_params.identity_keypair.insecure_clone());
+                let identity_pool = identity_pool.clone().zip(
+                    quic_params
+                        .as_ref()
+                        .map(|quic_params| quic_params.staked_nodes.clone()),
+                );
+                let new_transporter: TransporterFactory = Box::new(move || {
+                    if let Some((identity_pool, staked_nodes)) = &identity_pool {
+                        return Transporter::new_pool(
+                            transporter_name,
+                            &identity_pool.client_params(staked_nodes),
+                        );
+                    }
+                    let quic_params = identity
+                        .as_ref()
+                        .map(|identity| QuicParams::new(identity.insecure_clone()));
+                    Transporter::new(
+                        transporter_name,
+                        use_connection_cache,
+                        quic_params.as_ref(),
+                        dscp,
+                    )
+                });
+                (transporter, new_transporter)
+            });
+            let pause_thread = spawn_pause_controller(
+                pause_exit.clone(),
+                pause_schedule,
+                tracker.clone(),
+                received_count.clone(),
+                teardown,
+            );
+            (tracker, pause_thread)
+        });
+    let stall_exit = Arc::new(AtomicBool::new(false));
+    let producer_progresses = Arc::new(ProducerProgresses::new(workload.streams.len()));
+    let stall_watchdog = transporter.is_some().then(|| {
+        spawn_stall_watchdog(
+            stall_exit.clone(),
+            producer_progresses.clone(),
+            stall_threshold_ms,
+        )
+    });
+    let producer_threads = transporter.map(|transporter| {
         producer(
             destination,
-            num_producers,
-            use_connection_cache,
+            &workload,
+            transporter,
             verbose,
-            quic_params,
+            show_transaction_details,
+            pipeline,
+            cache_stats.clone(),
+            sent_count.clone(),
+            rotation.as_ref().map(|(tracker, _)| tracker.clone()),
+            pause.as_ref().map(|(tracker, _)| tracker.clone()),
+            producer_progresses.clone(),
         )
     });
 
-    producer_threads
+    let forked_producers = fork_producers.then(|| {
+        println!("Running {num_producers} producer processes against {destination:?}");
+        let args = producer_args(
+            &matches,
+            destination,
+            vote_use_quic,
+            use_connection_cache,
+            transactions_per_producer,
+        );
+        (0..num_producers)
+            .map(|_| spawn_forked_producer(&args))
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap_or_else(|err| {
+                eprintln!("Error: failed to start a producer process: {err}");
+                process::exit(1);
+            })
+    });
+
+    let mut send_latencies = SendLatencies::default();
+    let (workload_hashes, pipeline_stats): (Vec<_>, Vec<_>) = producer_threads
         .into_iter()
         .flatten()
-        .try_for_each(JoinHandle::join)?;
+        .map(JoinHandle::join)
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .map(|(hash, pipeline_stats, producer_latencies)| {
+            send_latencies.merge(&producer_latencies);
+            (hash, pipeline_stats)
+        })
+        .unzip();
+    let forked_summary = forked_producers.map(|forked_producers| {
+        let summaries: Vec<_> = forked_producers
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, forked_producer)| {
+                forked_producer
+                    .wait()
+                    .map_err(|err| eprintln!("Warning: producer process {index} failed: {err}"))
+                    .ok()
+            })
+            .collect();
+        ForkedProducersSummary::aggregate(num_producers, &summaries)
+    });
+
+    stall_exit.store(true, Ordering::Relaxed);
+    if let Some(stall_watchdog) = stall_watchdog {
+        stall_watchdog.join()?;
+        println!("{}", producer_progresses.summary());
+    }
+
+    rotation_exit.store(true, Ordering::Relaxed);
+    if let Some((tracker, rotation_thread)) = rotation {
+        rotation_thread.join()?;
+        println!("{}", tracker.summary());
+    }
+    pause_exit.store(true, Ordering::Relaxed);
+    let pause_tracker = pause
+        .map(|(tracker, pause_thread)| pause_thread.join().map(|()| tracker))
+        .transpose()?;
 
     if !server_only {
         if let Some(exit) = exit {
             exit.store(true, Ordering::Relaxed);
         }
+    } else if let Some(control_port) = control_port {
+        println!("The server stops once the client is done, signaled on port {control_port}");
     } else {
         println!("To stop the server, please press ^C");
     }
 
+    if let Some((control_client, _)) = control_client {
+        if let Err(err) = control_client.done(transactions_per_producer * num_producers) {
+            eprintln!("Warning: failed to signal the end of the run to the server: {err}");
+        }
+    }
+
     read_threads
         .into_iter()
         .flatten()
@@ -288,129 +1391,373 @@ fn main() -> Result<()> {
         .into_iter()
         .flatten()
         .try_for_each(JoinHandle::join)?;
+    let channel_depth = depth_sampler
+        .map(JoinHandle::join)
+        .transpose()?
+        .unwrap_or_default();
 
-    if !(server_only) {
-        let elapsed = start.elapsed().unwrap();
-        let ftime = elapsed.as_nanos() as f64 / 1_000_000_000.0;
-        let fcount = (TRANSACTIONS_PER_THREAD * num_producers) as f64;
+    sampler_exit.store(true, Ordering::Relaxed);
+    if let Some(Err(err)) = sampler.map(|sampler| sampler.join()).transpose()? {
+        eprintln!("Warning: failed to write the samples file: {err}");
+    }
 
+    if report_dscp && !client_only {
+        dscp_counts.report();
+    }
+
+    if let Some(pause_tracker) = pause_tracker {
+        let received = received_count.map(|received| received.load(Ordering::Relaxed) as u64);
+        println!("{}", pause_tracker.summary(received));
+    }
+
+    if let Some(forward_queue) = &forward_queue {
+        println!(
+            "{}",
+            forward_queue
+                .stats()
+                .summary(start.elapsed().unwrap_or_default())
+        );
+    }
+
+    // without --tee, the received packets were only counted by the forwarder
+    let num_received = match &forward_queue {
+        Some(forward_queue) if !tee => forward_queue.stats().enqueued() as usize,
+        _ => received_size.load(Ordering::Relaxed),
+    };
+
+    if let Some(udp_counters_start) = udp_counters_start {
+        let report = UdpDropsReport::new(
+            udp_counters_start,
+            UdpCounters::snapshot(destination.port()),
+            num_received,
+        );
+        println!("{report}");
+    }
+
+    // the transactions sent successfully, by the producer threads or the children
+    let num_sent = (!server_only).then(|| match &forked_summary {
+        Some(forked_summary) => forked_summary.sent,
+        None => sent_count.load(Ordering::Relaxed) as u64,
+    });
+
+    if !client_only && (sink_delay.is_some() || channel_capacity.is_some()) {
+        let report = SinkChannelReport {
+            capacity: channel_capacity,
+            depth: channel_depth,
+            dropped_on_full: channel_drops
+                .as_ref()
+                .map(|drops| (drops.batches(), drops.packets())),
+            sent: num_sent,
+            received: num_received as u64,
+        };
+        println!("{report}");
+    }
+
+    let cache_stats_summary =
+        cache_stats
+            .filter(|_| !server_only && !fork_producers)
+            .map(|cache_stats| {
+                let connection_pool_size = if vote_use_quic {
+                    QUIC_CONNECTION_POOL_SIZE
+                } else {
+                    UDP_CONNECTION_POOL_SIZE
+                };
+                cache_stats.summary(connection_pool_size)
+            });
+    if let Some(cache_stats_summary) = &cache_stats_summary {
+        println!("{cache_stats_summary}");
+    }
+    if let Some(forked_summary) = &forked_summary {
+        println!("{forked_summary}");
+    }
+
+    let elapsed = start.elapsed().unwrap();
+    let ftime = elapsed.as_nanos() as f64 / 1_000_000_000.0;
+    let fcount = (transactions_per_producer * num_producers) as f64;
+    if !(server_only) {
         println!(
             "Performance: {:?}/s, count: {fcount}, time in second: {ftime}",
             fcount / ftime
         );
     }
-    Ok(())
-}
 
-#[derive(Clone)]
-enum Transporter {
-    Cache(Arc<ConnectionCache>),
-    DirectSocket(Arc<UdpSocket>),
-}
+    let send_latency = send_latencies.percentiles();
+    if let Some(send_latency) = &send_latency {
+        println!("{send_latency}");
+    }
 
-struct QuicParams {
-    identity_keypair: Keypair,
-    staked_nodes: Arc<RwLock<StakedNodes>>,
+    for (stream, stats) in workload.streams.iter().zip(&pipeline_stats) {
+        if let Some(stats) = stats {
+            println!("Pipeline of producer {stream}: {stats}");
+        }
+    }
+
+    if reproducible {
+        for (stream, hash) in workload.streams.iter().zip(&workload_hashes) {
+            println!("Workload hash of stream {stream}: {hash}");
+        }
+    }
+
+    if let Some(summary_fd) = producer_child {
+        let sent = sent_count.load(Ordering::Relaxed) as u64;
+        let summary = ProducerSummary {
+            sent,
+            failed: transactions_per_producer * num_producers - sent,
+            elapsed_secs: ftime,
+            cache_stats: cache_stats_summary,
+        };
+        if let Err(err) = write_summary(summary_fd, &summary) {
+            eprintln!("Error: failed to write the producer summary: {err}");
+            process::exit(1);
+        }
+    }
+
+    if let Some(results_file) = results_file {
+        let results = RunResults {
+            version: RESULTS_VERSION,
+            parameters: params,
+            vote_instruction: Some(workload.vote_instruction),
+            sent: num_sent,
+            received: (!client_only).then_some(num_received as u64),
+            elapsed_secs: ftime,
+            throughput: (!server_only).then_some(fcount / ftime),
+            send_latency,
+        };
+        if let Err(err) = results.write(&results_file) {
+            eprintln!(
+                "Error: failed to write the results to {}: {err}",
+                results_file.display()
+            );
+            process::exit(1);
+        }
+    }
+
+    if let Some(deserialize_check) = &deserialize_check {
+        if deserialize_check.failed() > 0 {
+            eprintln!("Error: {deserialize_check}");
+            process::exit(1);
+        }
+        println!("{deserialize_check}");
+    }
+    Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn producer(
     sock: SocketAddr,
-    num_producers: u64,
-    use_connection_cache: bool,
+    workload: &WorkloadManifest,
+    transporter: Arc<SharedTransporter>,
     verbose: bool,
-    quic_params: Option<QuicParams>,
-) -> Vec<JoinHandle<()>> {
+    show_transaction_details: bool,
+    pipeline: bool,
+    cache_stats: Option<Arc<CacheStats>>,
+    sent_count: Arc<AtomicUsize>,
+    rotation_tracker: Option<Arc<RotationTracker>>,
+    pause_tracker: Option<Arc<PauseTracker>>,
+    progresses: Arc<ProducerProgresses>,
+) -> Vec<JoinHandle<(Hash, Option<PipelineStats>, SendLatencies)>> {
     println!("Running clients against {sock:?}");
-    let transporter = if use_connection_cache || quic_params.is_some() {
-        if let Some(quic_params) = &quic_params {
-            Transporter::Cache(Arc::new(ConnectionCache::new_with_client_options(
-                "connection_cache_vote_quic",
-                256,  // connection_pool_size
-                None, // client_endpoint
-                Some((
-                    &quic_params.identity_keypair,
-                    IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
-                )),
-                Some((
-                    &quic_params.staked_nodes,
-                    &quic_params.identity_keypair.pubkey(),
-                )),
-            )))
-        } else {
-            Transporter::Cache(Arc::new(ConnectionCache::with_udp(
-                "connection_cache_vote_udp",
-                1, // connection_pool_size
-            )))
-        }
-    } else {
-        Transporter::DirectSocket(Arc::new(bind_to_unspecified().unwrap()))
-    };
 
     let mut handles = vec![];
 
     let current_slot: u64 = 0;
+    let transactions_per_producer = workload.parameters.transactions_per_producer;
 
-    let identity_keypair = Keypair::new(); // Replace with loaded keypair
-
-    for _i in 0..num_producers {
+    for (index, stream) in workload.streams.iter().enumerate() {
+        let mut workload_stream =
+            WorkloadStream::new(workload.seed, *stream, workload.vote_instruction);
         let transporter = transporter.clone();
-        let identity_keypair = identity_keypair.insecure_clone();
+        let cache_stats = cache_stats.clone();
+        let sent_count = sent_count.clone();
+        let rotation_tracker = rotation_tracker.clone();
+        let pause_tracker = pause_tracker.clone();
+        let progresses = progresses.clone();
         handles.push(thread::spawn(move || {
-            // Generate and send transactions
-            for _j in 0..TRANSACTIONS_PER_THREAD {
-                // Create a vote instruction
-                let vote = Vote {
-                    slots: vec![current_slot], // Voting for the current slot
-                    hash: Hash::new_unique(),
-                    timestamp: None, // Optional timestamp
-                };
-
-                let vote_instruction = vote_instruction::vote(
-                    &identity_keypair.pubkey(),
-                    &identity_keypair.pubkey(),
-                    vote,
-                );
-
-                // Build the transaction
-                let message = Message::new(&[vote_instruction], Some(&identity_keypair.pubkey()));
-
-                let recent_blockhash = Hash::new_unique();
-                let transaction = Transaction::new(&[&identity_keypair], message, recent_blockhash);
-
-                let serialized_transaction = bincode::serialize(&transaction).unwrap();
-
-                match &transporter {
-                    Transporter::Cache(cache) => {
-                        let connection = cache.get_connection(&sock);
+            let mut num_sent = 0;
+            let mut send_latencies = SendLatencies::default();
+            progresses.record_progress(index, num_sent);
+            let mut send = |serialized_transaction: &[u8]| {
+                if show_transaction_details && num_sent < MAX_TRANSACTION_DETAILS as u64 {
+                    match TransactionDetails::from_serialized(serialized_transaction) {
+                        Ok(details) => println!("{details}"),
+                        Err(err) => println!("Error decoding sent transaction {err:?}"),
+                    }
+                }
+                num_sent += 1;
 
-                        match connection.send_data(&serialized_transaction) {
-                            Ok(_) => {
-                                if verbose {
-                                    println!("Sent transaction successfully");
-                                }
-                            }
-                            Err(ex) => {
-                                println!("Error sending transaction {ex:?}");
-                            }
+                if let Some(pause_tracker) = &pause_tracker {
+                    pause_tracker.wait_while_paused();
+                }
+                // looked up on each send, to pick up the rotated identities
+                let send_start = Instant::now();
+                let result =
+                    transporter
+                        .load()
+                        .send(&sock, serialized_transaction, cache_stats.as_deref());
+                send_latencies.record(send_start.elapsed());
+                if let Some(rotation_tracker) = &rotation_tracker {
+                    rotation_tracker.record_send(result.is_ok(), Instant::now());
+                }
+                if let Some(pause_tracker) = &pause_tracker {
+                    pause_tracker.record_send(result.is_ok());
+                }
+                match result {
+                    Ok(()) => {
+                        sent_count.fetch_add(1, Ordering::Relaxed);
+                        if verbose {
+                            println!("Sent transaction successfully to {sock:?}");
                         }
                     }
-                    Transporter::DirectSocket(socket) => {
-                        match socket.send_to(&serialized_transaction, sock) {
-                            Ok(_) => {
-                                if verbose {
-                                    println!(
-                                        "Sent transaction via direct socket successfully {sock:?}"
-                                    );
-                                }
-                            }
-                            Err(ex) => {
-                                println!("Error sending transaction {ex:?}");
-                            }
-                        }
+                    Err(ex) => {
+                        println!("Error sending transaction {ex:?}");
                     }
                 }
-            }
+                if num_sent % PROGRESS_INTERVAL == 0 {
+                    progresses.record_progress(index, num_sent);
+                }
+            };
+
+            // Generate and send transactions
+            let pipeline_stats = if pipeline {
+                Some(run_pipeline(
+                    DEFAULT_PIPELINE_CAPACITY,
+                    transactions_per_producer,
+                    |buffer| workload_stream.next_transaction_into(current_slot, buffer),
+                    send,
+                ))
+            } else {
+                for _ in 0..transactions_per_producer {
+                    send(&workload_stream.next_transaction(current_slot));
+                }
+                None
+            };
+            progresses.record_finished(index, num_sent);
+            (workload_stream.hash(), pipeline_stats, send_latencies)
         }));
     }
     handles
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_quic_server_params(args: &[&str]) -> QuicServerParams {
+        let matches = App::new("test")
+            .args(&quic_connection_limit_args())
+            .get_matches_from(std::iter::once("test").chain(args.iter().copied()));
+        quic_server_params(&matches)
+    }
+
+    fn parse_run_mode(args: &[&str]) -> std::result::Result<RunMode, RunModeError> {
+        let matches = app()
+            .get_matches_from_safe(std::iter::once("test").chain(args.iter().copied()))
+            .unwrap();
+        run_mode(&matches)
+    }
+
+    #[test]
+    fn test_run_mode() {
+        assert_eq!(parse_run_mode(&[]), Ok(RunMode::ServerAndClient));
+        assert_eq!(parse_run_mode(&["--server-only"]), Ok(RunMode::ServerOnly));
+        assert_eq!(
+            parse_run_mode(&["--client-only", "--server-address", "127.0.0.1:8000"]),
+            Ok(RunMode::ClientOnly)
+        );
+        assert_eq!(
+            parse_run_mode(&["--client-only", "--control", "127.0.0.1:8001"]),
+            Ok(RunMode::ClientOnly)
+        );
+    }
+
+    #[test]
+    fn test_run_mode_errors() {
+        assert_eq!(
+            parse_run_mode(&["--server-only", "--client-only"]),
+            Err(RunModeError::ServerOnlyAndClientOnly)
+        );
+        assert_eq!(
+            parse_run_mode(&[
+                "--server-only",
+                "--client-only",
+                "--server-address",
+                "127.0.0.1:8000",
+            ]),
+            Err(RunModeError::ServerOnlyAndClientOnly)
+        );
+        assert_eq!(
+            parse_run_mode(&["--client-only"]),
+            Err(RunModeError::ClientWithoutDestination)
+        );
+        assert!(RunModeError::ClientWithoutDestination
+            .to_string()
+            .contains("--server-address"));
+    }
+
+    #[test]
+    fn test_identity_pool_args() {
+        let parse = |args: &[&str]| {
+            app().get_matches_from_safe(std::iter::once("test").chain(args.iter().copied()))
+        };
+        let pool_args = ["--use-quic", "true", "--num-identities", "4"];
+
+        // the server stakes the pool the client takes from the same directory
+        let matches = parse(
+            &[
+                &pool_args[..],
+                &["--server-only", "--identity-pool-dir", "pool"],
+            ]
+            .concat(),
+        )
+        .unwrap();
+        assert_eq!(value_t!(matches, "num-identities", usize).unwrap(), 4);
+        assert_eq!(matches.value_of("identity-pool-dir"), Some("pool"));
+
+        // the identities of the pool are sent through the QUIC connection
+        // caches, whose sockets cannot be marked
+        assert!(parse(&[&pool_args[..], &["--dscp", "46"]].concat()).is_err());
+        assert!(parse(&["--identity-pool-dir", "pool"]).is_err());
+    }
+
+    #[test]
+    fn test_forward_args() {
+        let parse = |args: &[&str]| {
+            app().get_matches_from_safe(std::iter::once("test").chain(args.iter().copied()))
+        };
+        assert!(parse(&[]).is_ok());
+        assert!(parse(&[
+            "--forward-to",
+            "127.0.0.1:8002",
+            "--forward-use-quic",
+            "true"
+        ])
+        .is_ok());
+        assert!(parse(&["--forward-use-quic", "true"]).is_err());
+        assert!(parse(&["--forward-use-quic", "false"]).is_err());
+    }
+
+    #[test]
+    fn test_quic_server_params() {
+        let params = parse_quic_server_params(&[]);
+        assert_eq!(params.max_connections_per_peer, 1024);
+        assert_eq!(params.max_connections_per_ipaddr_per_min, 1024);
+
+        let params = parse_quic_server_params(&[
+            "--max-connections-per-peer",
+            "2",
+            "--max-connections-per-ip-per-min",
+            "8",
+        ]);
+        assert_eq!(params.max_connections_per_peer, 2);
+        assert_eq!(params.max_connections_per_ipaddr_per_min, 8);
+        assert_eq!(
+            params.max_unstaked_connections,
+            QuicServerParams::default().max_unstaked_connections
+        );
+
+        assert!(App::new("test")
+            .args(&quic_connection_limit_args())
+            .get_matches_from_safe(["test", "--max-connections-per-peer", "many"])
+            .is_err());
+    }
+}
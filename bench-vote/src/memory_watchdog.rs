@@ -0,0 +1,119 @@
+//! Keeps long runs within `--max-memory-mb`.
+//!
+//! A watchdog thread samples the process RSS and aborts the run once it
+//! exceeds the limit, naming the largest of the components reporting an
+//! estimate of their memory. The benchmark holds no state it could release
+//! instead, so the watchdog does not try to degrade it first.
+
+use std::{
+    fs, process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, sleep, JoinHandle},
+    time::{Duration, Instant},
+};
+
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_secs(5);
+const WATCHDOG_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Memory held by one of the stats structures, as reported by its `memory_estimate()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentEstimate {
+    pub name: &'static str,
+    pub bytes: usize,
+}
+
+pub type EstimateFn = Box<dyn Fn() -> ComponentEstimate + Send>;
+
+/// The component holding the most memory
+pub fn dominant_component(estimates: &[ComponentEstimate]) -> Option<&ComponentEstimate> {
+    estimates.iter().max_by_key(|estimate| estimate.bytes)
+}
+
+fn parse_vm_rss_bytes(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kb * 1024)
+}
+
+/// Resident set size of this process, if the platform reports it
+pub fn process_rss_bytes() -> Option<u64> {
+    if cfg!(target_os = "linux") {
+        parse_vm_rss_bytes(&fs::read_to_string("/proc/self/status").ok()?)
+    } else {
+        None
+    }
+}
+
+pub fn spawn_memory_watchdog(
+    exit: Arc<AtomicBool>,
+    max_memory_mb: u64,
+    estimates: Vec<EstimateFn>,
+) -> JoinHandle<()> {
+    let max_bytes = max_memory_mb * 1024 * 1024;
+    thread::Builder::new()
+        .name("solBenVoteMemWd".to_string())
+        .spawn(move || {
+            let mut last_sample = Instant::now();
+            while !exit.load(Ordering::Relaxed) {
+                sleep(WATCHDOG_POLL_INTERVAL);
+                if last_sample.elapsed() < WATCHDOG_INTERVAL {
+                    continue;
+                }
+                last_sample = Instant::now();
+
+                let Some(rss_bytes) = process_rss_bytes() else {
+                    println!(
+                        "Warning: process RSS is not available, --max-memory-mb is not enforced"
+                    );
+                    return;
+                };
+                if rss_bytes <= max_bytes {
+                    continue;
+                }
+
+                let estimates: Vec<_> = estimates.iter().map(|estimate| estimate()).collect();
+                let dominant = dominant_component(&estimates)
+                    .map(|estimate| format!("{} ({} bytes)", estimate.name, estimate.bytes))
+                    .unwrap_or_else(|| "unknown".to_string());
+                println!(
+                    "Memory usage {} MB exceeds --max-memory-mb {max_memory_mb}, largest \
+                     component: {dominant}; aborting",
+                    rss_bytes / (1024 * 1024),
+                );
+                eprintln!("Error: memory limit of {max_memory_mb} MB exceeded");
+                process::exit(1);
+            }
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dominant_component() {
+        let estimates = [
+            ComponentEstimate {
+                name: "small",
+                bytes: 1,
+            },
+            ComponentEstimate {
+                name: "large",
+                bytes: 10,
+            },
+        ];
+        assert_eq!(dominant_component(&estimates).unwrap().name, "large");
+        assert!(dominant_component(&[]).is_none());
+    }
+
+    #[test]
+    fn test_parse_vm_rss_bytes() {
+        let status = "Name:\tbench-vote\nVmPeak:\t  20000 kB\nVmRSS:\t   1234 kB\nThreads:\t4\n";
+        assert_eq!(parse_vm_rss_bytes(status), Some(1234 * 1024));
+        assert_eq!(parse_vm_rss_bytes("Name:\tbench-vote\n"), None);
+    }
+}
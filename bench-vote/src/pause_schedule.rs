@@ -0,0 +1,477 @@
+//! Fault injection pausing the producers under `--pause-schedule`.
+//!
+//! The producers send for ON seconds, then fully stop for OFF seconds, over
+//! and over, to reproduce receivers misbehaving after an idle period. Their
+//! connections are kept across a pause, unless `--teardown-on-pause` swaps a
+//! new transporter in when it starts so that each resume reconnects.
+//!
+//! The sends of the producers are broken out per cycle, a cycle being a
+//! sending phase and the pause following it. In combined mode, so are the
+//! received packets, along with the time from each resume to the first packet
+//! received past it.
+
+use {
+    crate::{identity_rotation::SharedTransporter, transporter::Transporter},
+    std::{
+        fmt,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+/// Granularity of the phases, and of the time to the first received packet
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The `ON_SECS:OFF_SECS[:REPEATS]` of `--pause-schedule`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PauseSchedule {
+    pub on: Duration,
+    pub off: Duration,
+    /// Number of pauses, after which the producers send until they are done.
+    /// Repeat until then if unset.
+    pub repeats: Option<u64>,
+}
+
+impl FromStr for PauseSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<_> = s.split(':').collect();
+        let (on, off, repeats) = match fields.as_slice() {
+            [on, off] => (*on, *off, None),
+            [on, off, repeats] => (*on, *off, Some(*repeats)),
+            _ => return Err(format!("expected ON_SECS:OFF_SECS[:REPEATS], found {s}")),
+        };
+        let positive = |name: &str, value: &str| match value.parse::<u64>() {
+            Ok(value) if value > 0 => Ok(value),
+            _ => Err(format!("{name} must be a positive integer, found {value}")),
+        };
+        Ok(Self {
+            on: Duration::from_secs(positive("ON_SECS", on)?),
+            off: Duration::from_secs(positive("OFF_SECS", off)?),
+            repeats: repeats
+                .map(|repeats| positive("REPEATS", repeats))
+                .transpose()?,
+        })
+    }
+}
+
+/// Phase of the schedule, with the index of its cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Sending(usize),
+    Paused(usize),
+}
+
+/// State machine stepping through the phases of a schedule
+pub struct PauseCycle {
+    schedule: PauseSchedule,
+    phase: Phase,
+    /// Deadline of the current phase, unset for the last sending phase
+    phase_end: Option<Instant>,
+}
+
+impl PauseCycle {
+    pub fn new(schedule: PauseSchedule, start: Instant) -> Self {
+        Self {
+            schedule,
+            phase: Phase::Sending(0),
+            phase_end: Some(start + schedule.on),
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Move to the next phase if the current one is over at `now`, returning
+    /// the new phase. The deadlines are fixed from the start so that the
+    /// phases do not drift with the polling.
+    pub fn advance(&mut self, now: Instant) -> Option<Phase> {
+        let phase_end = self.phase_end.filter(|phase_end| now >= *phase_end)?;
+        self.phase = match self.phase {
+            Phase::Sending(cycle) => {
+                self.phase_end = Some(phase_end + self.schedule.off);
+                Phase::Paused(cycle)
+            }
+            Phase::Paused(cycle) => {
+                let cycle = cycle + 1;
+                self.phase_end = (self.schedule.repeats != Some(cycle as u64))
+                    .then(|| phase_end + self.schedule.on);
+                Phase::Sending(cycle)
+            }
+        };
+        Some(self.phase)
+    }
+}
+
+struct CycleStart {
+    resumed_at: Instant,
+    sent: u64,
+    errors: u64,
+    received: Option<u64>,
+    first_received: Option<Duration>,
+    /// The transporter was torn down during the pause before the cycle
+    torn_down: bool,
+}
+
+/// Whether the producers are paused, and their sends in each cycle
+pub struct PauseTracker {
+    paused: AtomicBool,
+    sent: AtomicU64,
+    errors: AtomicU64,
+    cycles: Mutex<Vec<CycleStart>>,
+}
+
+impl PauseTracker {
+    pub fn new(start: Instant, received: Option<u64>) -> Self {
+        Self {
+            paused: AtomicBool::new(false),
+            sent: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            cycles: Mutex::new(vec![CycleStart {
+                resumed_at: start,
+                sent: 0,
+                errors: 0,
+                received,
+                first_received: None,
+                torn_down: false,
+            }]),
+        }
+    }
+
+    /// Block the calling producer for as long as the producers are paused
+    pub fn wait_while_paused(&self) {
+        while self.paused.load(Ordering::Relaxed) {
+            thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+    }
+
+    pub fn record_send(&self, ok: bool) {
+        if ok {
+            self.sent.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Start the next cycle at `now`, with `received` packets received so far
+    pub fn resume(&self, now: Instant, received: Option<u64>, torn_down: bool) {
+        self.cycles.lock().unwrap().push(CycleStart {
+            resumed_at: now,
+            sent: self.sent.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            received,
+            first_received: None,
+            torn_down,
+        });
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Record the time to the first packet received in the current cycle if
+    /// `received` went past the count at its start
+    pub fn record_received(&self, now: Instant, received: u64) {
+        let mut cycles = self.cycles.lock().unwrap();
+        let cycle = cycles.last_mut().unwrap();
+        if cycle.first_received.is_none()
+            && cycle.received.is_some_and(|at_resume| received > at_resume)
+        {
+            cycle.first_received = Some(now.saturating_duration_since(cycle.resumed_at));
+        }
+    }
+
+    /// Counts of each cycle, `received` being the packets received by the
+    /// end of the run in combined mode
+    pub fn summary(&self, received: Option<u64>) -> PauseSummary {
+        let cycles = self.cycles.lock().unwrap();
+        let end = CycleStart {
+            resumed_at: Instant::now(),
+            sent: self.sent.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            received,
+            first_received: None,
+            torn_down: false,
+        };
+        let cycles = cycles
+            .iter()
+            .zip(cycles.iter().skip(1).chain([&end]))
+            .map(|(start, next)| CycleSummary {
+                sent: next.sent - start.sent,
+                errors: next.errors - start.errors,
+                received: start
+                    .received
+                    .zip(next.received)
+                    .map(|(start, next)| next.saturating_sub(start)),
+                first_received: start.first_received,
+                torn_down: start.torn_down,
+            })
+            .collect();
+        PauseSummary { cycles }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CycleSummary {
+    pub sent: u64,
+    pub errors: u64,
+    /// Packets received in the cycle, in combined mode
+    pub received: Option<u64>,
+    /// Time from the start of the cycle to the first packet received in it
+    pub first_received: Option<Duration>,
+    /// The cycle resumed on a new transporter, torn down during the pause
+    /// before it under `--teardown-on-pause`. Reconnects of a kept
+    /// transporter, e.g. past an idle timeout, are not observed.
+    pub torn_down: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PauseSummary {
+    pub cycles: Vec<CycleSummary>,
+}
+
+impl PauseSummary {
+    pub fn teardowns(&self) -> usize {
+        self.cycles.iter().filter(|cycle| cycle.torn_down).count()
+    }
+}
+
+impl fmt::Display for PauseSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Pause schedule: {} cycles, {} resumed on a torn down transporter",
+            self.cycles.len(),
+            self.teardowns()
+        )?;
+        for (nth, cycle) in self.cycles.iter().enumerate() {
+            write!(
+                f,
+                "\n  cycle {nth}: sent {} ({} errors)",
+                cycle.sent, cycle.errors
+            )?;
+            if let Some(received) = cycle.received {
+                write!(f, ", received {received}")?;
+                match cycle.first_received {
+                    Some(first_received) => write!(
+                        f,
+                        ", first packet received {}ms after resume",
+                        first_received.as_millis()
+                    )?,
+                    None => write!(f, ", no packet received after resume")?,
+                }
+            }
+            if cycle.torn_down {
+                write!(f, ", transporter torn down")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the transporter swapped in when a pause starts under
+/// `--teardown-on-pause`
+pub type TransporterFactory = Box<dyn Fn() -> Result<Transporter, String> + Send>;
+
+/// Step through the phases of `schedule` until `exit` is set, pausing and
+/// resuming the producers through `tracker`. `received` is the count of
+/// received packets in combined mode.
+pub fn spawn_pause_controller(
+    exit: Arc<AtomicBool>,
+    schedule: PauseSchedule,
+    tracker: Arc<PauseTracker>,
+    received: Option<Arc<AtomicUsize>>,
+    teardown: Option<(Arc<SharedTransporter>, TransporterFactory)>,
+) -> JoinHandle<()> {
+    thread::Builder::new()
+        .name("solBenVotePause".to_string())
+        .spawn(move || {
+            let load_received = || {
+                received
+                    .as_ref()
+                    .map(|received| received.load(Ordering::Relaxed) as u64)
+            };
+            let mut cycle = PauseCycle::new(schedule, Instant::now());
+            let mut torn_down = false;
+            while !exit.load(Ordering::Relaxed) {
+                let now = Instant::now();
+                while let Some(phase) = cycle.advance(now) {
+                    match phase {
+                        Phase::Paused(_) => {
+                            tracker.pause();
+                            if let Some((transporter, new_transporter)) = &teardown {
+                                match new_transporter() {
+                                    // the replaced transporter closes its
+                                    // connections once no producer holds it
+                                    Ok(new) => {
+                                        drop(transporter.swap(new));
+                                        torn_down = true;
+                                    }
+                                    Err(err) => {
+                                        eprintln!(
+                                            "Error: failed to tear down the transporter: {err}"
+                                        )
+                                    }
+                                }
+                            }
+                        }
+                        Phase::Sending(_) => {
+                            tracker.resume(now, load_received(), torn_down);
+                            torn_down = false;
+                        }
+                    }
+                }
+                if let Some(received) = load_received() {
+                    tracker.record_received(now, received);
+                }
+                thread::sleep(PAUSE_POLL_INTERVAL);
+            }
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_schedule_from_str() {
+        assert_eq!(
+            "10:5".parse(),
+            Ok(PauseSchedule {
+                on: Duration::from_secs(10),
+                off: Duration::from_secs(5),
+                repeats: None,
+            })
+        );
+        assert_eq!(
+            "1:90:3".parse(),
+            Ok(PauseSchedule {
+                on: Duration::from_secs(1),
+                off: Duration::from_secs(90),
+                repeats: Some(3),
+            })
+        );
+        for invalid in [
+            "", "10", "10:5:3:1", "0:5", "10:0", "10:5:0", "10:five", "-1:5",
+        ] {
+            assert!(invalid.parse::<PauseSchedule>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_pause_cycle_transitions() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut cycle = PauseCycle::new("10:5:2".parse().unwrap(), start);
+        assert_eq!(cycle.phase(), Phase::Sending(0));
+        assert_eq!(cycle.advance(at(9)), None);
+        assert_eq!(cycle.advance(at(10)), Some(Phase::Paused(0)));
+        assert_eq!(cycle.advance(at(14)), None);
+        assert_eq!(cycle.advance(at(15)), Some(Phase::Sending(1)));
+        // the deadlines hold even if a transition is seen late
+        assert_eq!(cycle.advance(at(27)), Some(Phase::Paused(1)));
+        assert_eq!(cycle.advance(at(29)), None);
+        assert_eq!(cycle.advance(at(30)), Some(Phase::Sending(2)));
+        // after the last pause, the producers send until they are done
+        assert_eq!(cycle.advance(at(1_000)), None);
+        assert_eq!(cycle.phase(), Phase::Sending(2));
+    }
+
+    #[test]
+    fn test_pause_cycle_catches_up() {
+        let start = Instant::now();
+        let mut cycle = PauseCycle::new("2:1".parse().unwrap(), start);
+        let now = start + Duration::from_secs(7);
+        let phases: Vec<_> = std::iter::from_fn(|| cycle.advance(now)).collect();
+        assert_eq!(
+            phases,
+            [
+                Phase::Paused(0),
+                Phase::Sending(1),
+                Phase::Paused(1),
+                Phase::Sending(2),
+                Phase::Paused(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pause_tracker() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let tracker = PauseTracker::new(start, Some(0));
+        tracker.record_send(true);
+        tracker.record_send(true);
+        tracker.record_received(at(3), 2);
+        tracker.pause();
+
+        tracker.resume(at(100), Some(2), true);
+        tracker.record_send(false);
+        tracker.record_send(true);
+        tracker.record_received(at(102), 2);
+        tracker.record_received(at(120), 3);
+        tracker.record_received(at(130), 4);
+
+        let summary = tracker.summary(Some(5));
+        assert_eq!(
+            summary,
+            PauseSummary {
+                cycles: vec![
+                    CycleSummary {
+                        sent: 2,
+                        errors: 0,
+                        received: Some(2),
+                        first_received: Some(Duration::from_millis(3)),
+                        torn_down: false,
+                    },
+                    CycleSummary {
+                        sent: 1,
+                        errors: 1,
+                        received: Some(3),
+                        first_received: Some(Duration::from_millis(20)),
+                        torn_down: true,
+                    },
+                ],
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            "Pause schedule: 2 cycles, 1 resumed on a torn down transporter\n  \
+             cycle 0: sent 2 (0 errors), received 2, first packet received 3ms after resume\n  \
+             cycle 1: sent 1 (1 errors), received 3, first packet received 20ms after resume, \
+             transporter torn down"
+        );
+    }
+
+    #[test]
+    fn test_pause_tracker_blocks_producers() {
+        let tracker = Arc::new(PauseTracker::new(Instant::now(), None));
+        tracker.pause();
+        let producer = {
+            let tracker = tracker.clone();
+            thread::spawn(move || {
+                tracker.wait_while_paused();
+                tracker.record_send(true);
+            })
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(tracker.summary(None).cycles[0].sent, 0);
+
+        tracker.resume(Instant::now(), None, false);
+        producer.join().unwrap();
+        let summary = tracker.summary(None);
+        assert_eq!(summary.cycles.len(), 2);
+        assert_eq!(summary.cycles[1].sent, 1);
+        assert_eq!(summary.cycles[1].received, None);
+        assert!(!summary.to_string().contains("received"));
+    }
+}
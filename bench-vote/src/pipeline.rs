@@ -0,0 +1,283 @@
+//! `--pipeline`: a producer split into a serializer and a sender thread.
+//!
+//! The serializer fills byte buffers with serialized transactions and passes
+//! them to the sender over a bounded channel. Sent buffers go back to the
+//! serializer over a return channel, so that the same few buffers are reused
+//! for the whole run.
+//!
+//! The time each side spends blocked on the channel tells which one limits
+//! the rate: a serializer waiting on a full channel is held back by the
+//! sender, a sender waiting on an empty channel by the serializer.
+
+use {
+    crossbeam_channel::{bounded, TryRecvError, TrySendError},
+    std::{
+        fmt, thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// Buffers in flight between the serializer and the sender of a producer
+pub const DEFAULT_PIPELINE_CAPACITY: usize = 256;
+
+/// Share of the run below which neither side is considered blocked
+const BLOCKED_THRESHOLD: f64 = 0.05;
+
+/// Side limiting the rate of a pipelined producer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bottleneck {
+    /// The channel was mostly full, the serializer waiting on the sender
+    Sender,
+    /// The channel was mostly empty, the sender waiting on the serializer
+    Serializer,
+    /// Neither side waited on the other for long
+    Balanced,
+}
+
+impl fmt::Display for Bottleneck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Sender => "sender-bound",
+            Self::Serializer => "serializer-bound",
+            Self::Balanced => "balanced",
+        })
+    }
+}
+
+/// Occupancy of the channel of a pipelined producer over its run
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PipelineStats {
+    pub capacity: usize,
+    pub elapsed: Duration,
+    /// Time the serializer spent blocked on a full channel
+    pub time_full: Duration,
+    /// Time the sender spent blocked on an empty channel
+    pub time_empty: Duration,
+    /// Buffers in the channel, summed over each buffer received
+    pub occupancy_sum: u64,
+    pub buffers: u64,
+    /// Buffers allocated, at most `capacity` + 2 as they are reused
+    pub allocated: usize,
+}
+
+impl PipelineStats {
+    fn share(&self, duration: Duration) -> f64 {
+        if self.elapsed.is_zero() {
+            0.0
+        } else {
+            duration.as_secs_f64() / self.elapsed.as_secs_f64()
+        }
+    }
+
+    pub fn mean_occupancy(&self) -> f64 {
+        if self.buffers == 0 {
+            0.0
+        } else {
+            self.occupancy_sum as f64 / self.buffers as f64
+        }
+    }
+
+    pub fn bottleneck(&self) -> Bottleneck {
+        let full = self.share(self.time_full);
+        let empty = self.share(self.time_empty);
+        if full < BLOCKED_THRESHOLD && empty < BLOCKED_THRESHOLD {
+            Bottleneck::Balanced
+        } else if full >= empty {
+            Bottleneck::Sender
+        } else {
+            Bottleneck::Serializer
+        }
+    }
+}
+
+impl fmt::Display for PipelineStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: channel full {:.1}% and empty {:.1}% of {:.3}s, mean occupancy {:.1}/{}",
+            self.bottleneck(),
+            self.share(self.time_full) * 100.0,
+            self.share(self.time_empty) * 100.0,
+            self.elapsed.as_secs_f64(),
+            self.mean_occupancy(),
+            self.capacity,
+        )
+    }
+}
+
+/// Run `count` iterations of `serialize`, filling a cleared buffer, on a
+/// thread of its own, and of `send` on the buffers it filled, in order, on
+/// the calling thread
+pub fn run_pipeline(
+    capacity: usize,
+    count: u64,
+    mut serialize: impl FnMut(&mut Vec<u8>) + Send,
+    mut send: impl FnMut(&[u8]),
+) -> PipelineStats {
+    let (filled_sender, filled_receiver) = bounded::<Vec<u8>>(capacity);
+    let (free_sender, free_receiver) = bounded::<Vec<u8>>(capacity);
+    let start = Instant::now();
+
+    let (time_full, allocated, mut stats) = thread::scope(|scope| {
+        let serializer = thread::Builder::new()
+            .name("solBenchVoteSer".to_string())
+            .spawn_scoped(scope, move || {
+                let mut time_full = Duration::ZERO;
+                let mut allocated = 0;
+                for _ in 0..count {
+                    // a buffer is allocated only when none was returned yet,
+                    // so that at most `capacity` + 2 exist: those in the
+                    // channel and the one held by each side
+                    let mut buffer = free_receiver.try_recv().unwrap_or_else(|_| {
+                        allocated += 1;
+                        Vec::new()
+                    });
+                    buffer.clear();
+                    serialize(&mut buffer);
+                    if let Err(TrySendError::Full(buffer)) = filled_sender.try_send(buffer) {
+                        let blocked = Instant::now();
+                        if filled_sender.send(buffer).is_err() {
+                            break;
+                        }
+                        time_full += blocked.elapsed();
+                    }
+                }
+                (time_full, allocated)
+            })
+            .unwrap();
+
+        let mut stats = PipelineStats {
+            capacity,
+            ..PipelineStats::default()
+        };
+        loop {
+            let buffer = match filled_receiver.try_recv() {
+                Ok(buffer) => buffer,
+                Err(TryRecvError::Empty) => {
+                    let blocked = Instant::now();
+                    let Ok(buffer) = filled_receiver.recv() else {
+                        break;
+                    };
+                    stats.time_empty += blocked.elapsed();
+                    buffer
+                }
+                Err(TryRecvError::Disconnected) => break,
+            };
+            stats.occupancy_sum += filled_receiver.len() as u64 + 1;
+            stats.buffers += 1;
+            send(&buffer);
+            // dropped once the serializer is done
+            let _ = free_sender.try_send(buffer);
+        }
+        let (time_full, allocated) = serializer.join().unwrap();
+        (time_full, allocated, stats)
+    });
+
+    stats.elapsed = start.elapsed();
+    stats.time_full = time_full;
+    stats.allocated = allocated;
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{vote_instruction_variant::VoteInstructionVariant, workload::WorkloadStream},
+    };
+
+    fn stats(time_full_ms: u64, time_empty_ms: u64) -> PipelineStats {
+        PipelineStats {
+            capacity: 4,
+            elapsed: Duration::from_millis(1_000),
+            time_full: Duration::from_millis(time_full_ms),
+            time_empty: Duration::from_millis(time_empty_ms),
+            ..PipelineStats::default()
+        }
+    }
+
+    #[test]
+    fn test_buffer_reuse() {
+        // the buffers get shorter and longer, a buffer not cleared before
+        // being reused would keep the tail of its previous packet
+        let packet = |i: u64| vec![i as u8; (i % 7 + 1) as usize * 100];
+        let mut next = 0;
+        let mut received = vec![];
+        let stats = run_pipeline(
+            2,
+            1_000,
+            |buffer| {
+                buffer.extend_from_slice(&packet(next));
+                next += 1;
+            },
+            |buffer| received.push(buffer.to_vec()),
+        );
+        assert_eq!(received, (0..1_000).map(packet).collect::<Vec<_>>());
+        assert_eq!(stats.buffers, 1_000);
+        assert!(stats.allocated <= 2 + 2, "{}", stats.allocated);
+
+        // the transactions of a stream are sent unchanged
+        let mut workload_stream = WorkloadStream::new(7, 0, VoteInstructionVariant::TowerSync);
+        let mut expected = WorkloadStream::new(7, 0, VoteInstructionVariant::TowerSync);
+        let mut received = vec![];
+        run_pipeline(
+            1,
+            50,
+            |buffer| workload_stream.next_transaction_into(0, buffer),
+            |buffer| received.push(buffer.to_vec()),
+        );
+        for transaction in received {
+            assert_eq!(transaction, expected.next_transaction(0));
+        }
+        assert_eq!(workload_stream.hash(), expected.hash());
+    }
+
+    #[test]
+    fn test_occupancy_accounting() {
+        // a slow sender keeps the channel full
+        let stats = run_pipeline(
+            4,
+            40,
+            |buffer| buffer.push(0),
+            |_| thread::sleep(Duration::from_millis(2)),
+        );
+        assert_eq!(stats.bottleneck(), Bottleneck::Sender, "{stats}");
+        assert!(stats.time_full > stats.time_empty, "{stats}");
+        assert!(stats.mean_occupancy() > 2.0, "{stats}");
+
+        // a slow serializer keeps it empty
+        let stats = run_pipeline(
+            4,
+            40,
+            |buffer| {
+                thread::sleep(Duration::from_millis(2));
+                buffer.push(0);
+            },
+            |_| (),
+        );
+        assert_eq!(stats.bottleneck(), Bottleneck::Serializer, "{stats}");
+        assert!(stats.time_empty > stats.time_full, "{stats}");
+        assert!(stats.mean_occupancy() < 2.0, "{stats}");
+    }
+
+    #[test]
+    fn test_bottleneck() {
+        assert_eq!(stats(600, 10).bottleneck(), Bottleneck::Sender);
+        assert_eq!(stats(10, 600).bottleneck(), Bottleneck::Serializer);
+        assert_eq!(stats(49, 49).bottleneck(), Bottleneck::Balanced);
+        assert_eq!(stats(50, 0).bottleneck(), Bottleneck::Sender);
+        assert_eq!(PipelineStats::default().bottleneck(), Bottleneck::Balanced);
+        assert_eq!(PipelineStats::default().mean_occupancy(), 0.0);
+
+        let summary = PipelineStats {
+            occupancy_sum: 30,
+            buffers: 10,
+            ..stats(600, 10)
+        }
+        .to_string();
+        assert_eq!(
+            summary,
+            "sender-bound: channel full 60.0% and empty 1.0% of 1.000s, mean occupancy 3.0/4"
+        );
+    }
+}
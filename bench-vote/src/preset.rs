@@ -0,0 +1,357 @@
+//! Workload presets under `--preset`.
+//!
+//! A preset expands into a coherent set of workload parameters, scaled from
+//! the available parallelism where it matters, so that a run does not pit a
+//! single producer against a 64-core box or many producers against a single
+//! UDP socket. Any workload flag passed explicitly overrides the value of the
+//! preset.
+
+use {
+    clap::{value_t, Arg, ArgMatches},
+    serde::{Deserialize, Serialize},
+    solana_clap_utils::input_validators::is_parsable,
+    std::{fmt, str::FromStr},
+};
+
+pub const DEFAULT_NUM_PRODUCERS: u64 = 4;
+pub const DEFAULT_TRANSACTIONS_PER_PRODUCER: u64 = 1_000_000;
+const SMOKE_TRANSACTIONS_PER_PRODUCER: u64 = 5_000;
+const LATENCY_TRANSACTIONS_PER_PRODUCER: u64 = 100_000;
+const MAX_UDP_PRODUCERS: u64 = 32;
+const MAX_UDP_RECV_SOCKETS: usize = 8;
+const MAX_QUIC_PRODUCERS: u64 = 16;
+const MAX_QUIC_RECV_SOCKETS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// A few thousand transactions from a single producer
+    Smoke,
+    ThroughputUdp,
+    ThroughputQuic,
+    /// A single producer over QUIC, so that the receive path is not loaded
+    Latency,
+}
+
+impl Preset {
+    pub const NAMES: &'static [&'static str] =
+        &["smoke", "throughput-udp", "throughput-quic", "latency"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Smoke => "smoke",
+            Self::ThroughputUdp => "throughput-udp",
+            Self::ThroughputQuic => "throughput-quic",
+            Self::Latency => "latency",
+        }
+    }
+
+    /// Parameters of the preset on a host with `available_parallelism` cores
+    pub fn params(&self, available_parallelism: usize) -> WorkloadParams {
+        let parallelism = available_parallelism.max(1);
+        match self {
+            Self::Smoke => WorkloadParams {
+                preset: Some(*self),
+                num_producers: 1,
+                transactions_per_producer: SMOKE_TRANSACTIONS_PER_PRODUCER,
+                ..WorkloadParams::default()
+            },
+            Self::ThroughputUdp => {
+                // half of the cores produce, the other half receive over
+                // enough sockets for the producers not to contend on one
+                let num_producers = (parallelism as u64 / 2).clamp(1, MAX_UDP_PRODUCERS);
+                WorkloadParams {
+                    preset: Some(*self),
+                    num_producers,
+                    num_recv_sockets: (num_producers as usize / 2).clamp(1, MAX_UDP_RECV_SOCKETS),
+                    ..WorkloadParams::default()
+                }
+            }
+            Self::ThroughputQuic => WorkloadParams {
+                preset: Some(*self),
+                num_producers: (parallelism as u64 / 2).clamp(1, MAX_QUIC_PRODUCERS),
+                num_recv_sockets: (parallelism / 8).clamp(1, MAX_QUIC_RECV_SOCKETS),
+                use_quic: true,
+                ..WorkloadParams::default()
+            },
+            Self::Latency => WorkloadParams {
+                preset: Some(*self),
+                num_producers: 1,
+                transactions_per_producer: LATENCY_TRANSACTIONS_PER_PRODUCER,
+                use_quic: true,
+                ..WorkloadParams::default()
+            },
+        }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "smoke" => Ok(Self::Smoke),
+            "throughput-udp" => Ok(Self::ThroughputUdp),
+            "throughput-quic" => Ok(Self::ThroughputQuic),
+            "latency" => Ok(Self::Latency),
+            _ => Err(format!("unknown preset {s}")),
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Effective workload parameters of a run
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkloadParams {
+    pub preset: Option<Preset>,
+    pub num_producers: u64,
+    pub num_recv_sockets: usize,
+    pub transactions_per_producer: u64,
+    pub use_quic: bool,
+    pub use_connection_cache: bool,
+}
+
+impl Default for WorkloadParams {
+    fn default() -> Self {
+        Self {
+            preset: None,
+            num_producers: DEFAULT_NUM_PRODUCERS,
+            num_recv_sockets: 1,
+            transactions_per_producer: DEFAULT_TRANSACTIONS_PER_PRODUCER,
+            use_quic: false,
+            use_connection_cache: false,
+        }
+    }
+}
+
+impl fmt::Display for WorkloadParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} producers sending {} transactions each over {}{}, {} receive sockets",
+            self.num_producers,
+            self.transactions_per_producer,
+            if self.use_quic { "QUIC" } else { "UDP" },
+            if self.use_connection_cache {
+                " through the connection cache"
+            } else {
+                ""
+            },
+            self.num_recv_sockets,
+        )?;
+        if let Some(preset) = self.preset {
+            write!(f, " (preset {preset})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Arguments of the workload parameters, which override the preset
+pub fn workload_args<'a, 'b>() -> [Arg<'a, 'b>; 6] {
+    [
+        Arg::with_name("preset")
+            .long("preset")
+            .value_name("PRESET")
+            .takes_value(true)
+            .possible_values(Preset::NAMES)
+            .help(
+                "Start from the workload parameters of a common scenario, scaled from the \
+                 available parallelism. Explicitly passed workload flags override the preset",
+            ),
+        Arg::with_name("num-recv-sockets")
+            .long("num-recv-sockets")
+            .value_name("NUM")
+            .takes_value(true)
+            .validator(is_parsable::<usize>)
+            .help("Use NUM receive sockets"),
+        Arg::with_name("num-producers")
+            .long("num-producers")
+            .value_name("NUM")
+            .takes_value(true)
+            .validator(is_parsable::<u64>)
+            .help("Use this many producer threads."),
+        Arg::with_name("transactions-per-producer")
+            .long("transactions-per-producer")
+            .value_name("NUM")
+            .takes_value(true)
+            .validator(is_parsable::<u64>)
+            .help("Number of transactions sent by each producer [default: 1000000]"),
+        Arg::with_name("use-connection-cache")
+            .long("use-connection-cache")
+            .takes_value(false)
+            .help("Use this many producer threads."),
+        Arg::with_name("use-quic")
+            .long("use-quic")
+            .value_name("Boolean")
+            .takes_value(true)
+            .default_value("false")
+            .validator(is_parsable::<bool>)
+            .help("Controls if to use QUIC for sending/receiving vote transactions."),
+    ]
+}
+
+/// Expand the preset of `matches`, if any, on a host with
+/// `available_parallelism` cores, and override it with the workload flags
+/// passed explicitly
+pub fn workload_params(matches: &ArgMatches, available_parallelism: usize) -> WorkloadParams {
+    let preset = value_t!(matches, "preset", Preset).ok();
+    let defaults = preset.map_or_else(WorkloadParams::default, |preset| {
+        preset.params(available_parallelism)
+    });
+    // `--use-quic` has a default value, which must not override the preset
+    let use_quic = if matches.occurrences_of("use-quic") > 0 {
+        value_t!(matches, "use-quic", bool).unwrap_or(defaults.use_quic)
+    } else {
+        defaults.use_quic
+    };
+    WorkloadParams {
+        preset,
+        num_producers: value_t!(matches, "num-producers", u64).unwrap_or(defaults.num_producers),
+        num_recv_sockets: value_t!(matches, "num-recv-sockets", usize)
+            .map(|num_recv_sockets| num_recv_sockets.max(1))
+            .unwrap_or(defaults.num_recv_sockets),
+        transactions_per_producer: value_t!(matches, "transactions-per-producer", u64)
+            .unwrap_or(defaults.transactions_per_producer),
+        use_quic,
+        use_connection_cache: matches.is_present("use-connection-cache")
+            || defaults.use_connection_cache,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, clap::App};
+
+    fn parse(args: &[&str], available_parallelism: usize) -> WorkloadParams {
+        let matches = App::new("test")
+            .args(&workload_args())
+            .get_matches_from(std::iter::once("test").chain(args.iter().copied()));
+        workload_params(&matches, available_parallelism)
+    }
+
+    #[test]
+    fn test_no_preset() {
+        assert_eq!(parse(&[], 64), WorkloadParams::default());
+        assert_eq!(
+            parse(&["--num-recv-sockets", "0", "--use-quic", "true"], 64),
+            WorkloadParams {
+                use_quic: true,
+                ..WorkloadParams::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_preset_smoke() {
+        for available_parallelism in [1, 64] {
+            assert_eq!(
+                parse(&["--preset", "smoke"], available_parallelism),
+                WorkloadParams {
+                    preset: Some(Preset::Smoke),
+                    num_producers: 1,
+                    num_recv_sockets: 1,
+                    transactions_per_producer: 5_000,
+                    use_quic: false,
+                    use_connection_cache: false,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_preset_throughput_udp() {
+        let params = parse(&["--preset", "throughput-udp"], 16);
+        assert_eq!(params.preset, Some(Preset::ThroughputUdp));
+        assert_eq!(params.num_producers, 8);
+        assert_eq!(params.num_recv_sockets, 4);
+        assert!(!params.use_quic);
+
+        let params = parse(&["--preset", "throughput-udp"], 1);
+        assert_eq!((params.num_producers, params.num_recv_sockets), (1, 1));
+        let params = parse(&["--preset", "throughput-udp"], 256);
+        assert_eq!((params.num_producers, params.num_recv_sockets), (32, 8));
+    }
+
+    #[test]
+    fn test_preset_throughput_quic() {
+        let params = parse(&["--preset", "throughput-quic"], 16);
+        assert_eq!(params.preset, Some(Preset::ThroughputQuic));
+        assert_eq!(params.num_producers, 8);
+        assert_eq!(params.num_recv_sockets, 2);
+        assert!(params.use_quic);
+
+        let params = parse(&["--preset", "throughput-quic"], 2);
+        assert_eq!((params.num_producers, params.num_recv_sockets), (1, 1));
+        let params = parse(&["--preset", "throughput-quic"], 256);
+        assert_eq!((params.num_producers, params.num_recv_sockets), (16, 4));
+    }
+
+    #[test]
+    fn test_preset_latency() {
+        assert_eq!(
+            parse(&["--preset", "latency"], 64),
+            WorkloadParams {
+                preset: Some(Preset::Latency),
+                num_producers: 1,
+                num_recv_sockets: 1,
+                transactions_per_producer: 100_000,
+                use_quic: true,
+                use_connection_cache: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_explicit_flags_override_preset() {
+        let params = parse(
+            &[
+                "--preset",
+                "throughput-quic",
+                "--num-producers",
+                "3",
+                "--num-recv-sockets",
+                "5",
+                "--transactions-per-producer",
+                "7",
+                "--use-quic",
+                "false",
+                "--use-connection-cache",
+            ],
+            16,
+        );
+        assert_eq!(
+            params,
+            WorkloadParams {
+                preset: Some(Preset::ThroughputQuic),
+                num_producers: 3,
+                num_recv_sockets: 5,
+                transactions_per_producer: 7,
+                use_quic: false,
+                use_connection_cache: true,
+            }
+        );
+
+        // flags left out keep the value of the preset
+        let params = parse(&["--preset", "latency", "--num-producers", "2"], 16);
+        assert_eq!(params.num_producers, 2);
+        assert!(params.use_quic);
+        assert_eq!(params.transactions_per_producer, 100_000);
+    }
+
+    #[test]
+    fn test_preset_names() {
+        for name in Preset::NAMES {
+            assert_eq!(name.parse::<Preset>().unwrap().name(), *name);
+        }
+        assert!("fast".parse::<Preset>().is_err());
+        assert_eq!(
+            serde_json::to_string(&Preset::ThroughputQuic).unwrap(),
+            "\"throughput-quic\""
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Results of a run, written as JSON under `--results-file`.
+
+use {
+    crate::{
+        preset::WorkloadParams, send_latency::LatencyPercentiles,
+        vote_instruction_variant::VoteInstructionVariant,
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        fs::{self, File},
+        io,
+        path::Path,
+    },
+};
+
+/// Version of the results format, bumped on incompatible changes. Results
+/// written before the format was versioned read as version 0.
+pub const RESULTS_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunResults {
+    #[serde(default)]
+    pub version: u32,
+    pub parameters: WorkloadParams,
+    /// Vote instruction of the transactions, which sets their size
+    #[serde(default)]
+    pub vote_instruction: Option<VoteInstructionVariant>,
+    /// Transactions the producers sent successfully, unless the run was server
+    /// only
+    pub sent: Option<u64>,
+    /// Packets received by the server, unless the run was client only
+    pub received: Option<u64>,
+    pub elapsed_secs: f64,
+    /// Sent transactions per second, unless the run was server only
+    pub throughput: Option<f64>,
+    /// Latency of the sends, unless the run was server only or the producers
+    /// were forked
+    #[serde(default)]
+    pub send_latency: Option<LatencyPercentiles>,
+}
+
+impl RunResults {
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self).map_err(io::Error::from)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        Self::from_json(&contents).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let results: Self =
+            serde_json::from_str(json).map_err(|err| format!("invalid results: {err}"))?;
+        if results.version > RESULTS_VERSION {
+            return Err(format!(
+                "unsupported version {} of the results, expected at most {RESULTS_VERSION}",
+                results.version
+            ));
+        }
+        Ok(results)
+    }
+
+    /// Percentage of the sent transactions the server did not receive, if
+    /// the run had both a client and a server
+    pub fn loss_pct(&self) -> Option<f64> {
+        let (sent, received) = (self.sent?, self.received?);
+        (sent > 0).then(|| sent.saturating_sub(received) as f64 * 100.0 / sent as f64)
+    }
+}
@@ -0,0 +1,175 @@
+//! Throughput samples under `--samples-file`.
+//!
+//! Every report interval, the counts of packets received by the server and
+//! transactions sent by the producers so far are appended to the file as a
+//! `timestamp,received,sent` line, the timestamp being in milliseconds since
+//! the UNIX epoch.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Granularity at which the sampler checks `exit` between samples
+const SAMPLER_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The time the sampler runs on
+pub trait Clock: Send + 'static {
+    /// Monotonic time elapsed since the clock was created
+    fn elapsed(&self) -> Duration;
+    /// Wall-clock time since the UNIX epoch
+    fn timestamp(&self) -> Duration;
+    fn sleep(&mut self, duration: Duration);
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn timestamp(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// Append a sample of `received` and `sent` to the file at `path` every
+/// `interval` of `clock` until `exit` is set
+pub fn spawn_sampler(
+    exit: Arc<AtomicBool>,
+    path: &Path,
+    interval: Duration,
+    received: Arc<AtomicUsize>,
+    sent: Arc<AtomicUsize>,
+    mut clock: impl Clock,
+) -> io::Result<JoinHandle<io::Result<()>>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    thread::Builder::new()
+        .name("solBenVoteSmpl".to_string())
+        .spawn(move || {
+            let start = clock.elapsed();
+            let mut next_sample = 1;
+            while !exit.load(Ordering::Relaxed) {
+                // sample on fixed deadlines so that the samples do not drift
+                if clock.elapsed() - start < interval * next_sample {
+                    clock.sleep(SAMPLER_POLL_INTERVAL);
+                    continue;
+                }
+                next_sample += 1;
+                let timestamp = clock.timestamp().as_millis();
+                writeln!(
+                    writer,
+                    "{timestamp},{},{}",
+                    received.load(Ordering::Relaxed),
+                    sent.load(Ordering::Relaxed)
+                )?;
+                writer.flush()?;
+            }
+            Ok(())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::fs};
+
+    const EPOCH_OFFSET: Duration = Duration::from_secs(1_700_000_000);
+
+    /// A clock advanced by the sampler sleeping on it, which counts a
+    /// received and two sent transactions per sleep and sets `exit` once
+    /// `run_for` elapsed
+    struct TestClock {
+        elapsed: Duration,
+        run_for: Duration,
+        exit: Arc<AtomicBool>,
+        received: Arc<AtomicUsize>,
+        sent: Arc<AtomicUsize>,
+    }
+
+    impl Clock for TestClock {
+        fn elapsed(&self) -> Duration {
+            self.elapsed
+        }
+
+        fn timestamp(&self) -> Duration {
+            EPOCH_OFFSET + self.elapsed
+        }
+
+        fn sleep(&mut self, duration: Duration) {
+            self.elapsed += duration;
+            self.received.fetch_add(1, Ordering::Relaxed);
+            self.sent.fetch_add(2, Ordering::Relaxed);
+            if self.elapsed >= self.run_for {
+                self.exit.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sampler() {
+        let samples_file = tempfile::NamedTempFile::new().unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let received = Arc::new(AtomicUsize::new(0));
+        let sent = Arc::new(AtomicUsize::new(0));
+        let interval = Duration::from_millis(100);
+        let clock = TestClock {
+            elapsed: Duration::ZERO,
+            run_for: interval * 5 + interval / 2,
+            exit: exit.clone(),
+            received: received.clone(),
+            sent: sent.clone(),
+        };
+        let sampler =
+            spawn_sampler(exit, samples_file.path(), interval, received, sent, clock).unwrap();
+        sampler.join().unwrap().unwrap();
+
+        let samples = fs::read_to_string(samples_file.path()).unwrap();
+        let samples: Vec<(u128, usize, usize)> = samples
+            .lines()
+            .map(|line| {
+                let fields: Vec<_> = line.split(',').collect();
+                assert_eq!(fields.len(), 3, "{line}");
+                (
+                    fields[0].parse().unwrap(),
+                    fields[1].parse().unwrap(),
+                    fields[2].parse().unwrap(),
+                )
+            })
+            .collect();
+        // a sample per interval, with the counts of the 10 polls per interval
+        let expected: Vec<_> = (1..=5)
+            .map(|i| {
+                (
+                    (EPOCH_OFFSET + interval * i).as_millis(),
+                    10 * i as usize,
+                    20 * i as usize,
+                )
+            })
+            .collect();
+        assert_eq!(samples, expected);
+    }
+}
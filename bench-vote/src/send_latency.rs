@@ -0,0 +1,156 @@
+//! Latency of the sends of the producers.
+//!
+//! Each send is timed from the call to the transporter to its return, and
+//! counted in a log-linear histogram of its producer, the histograms of the
+//! producers being merged once they are done. The buckets
+//! split each power of two in `SUB_BUCKETS`, so a percentile is the lower
+//! bound of its bucket, at most 1/8 below the actual latency.
+
+use {
+    serde::{Deserialize, Serialize},
+    std::{fmt, time::Duration},
+};
+
+const SUB_BUCKET_BITS: u32 = 3;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const NUM_BUCKETS: usize = (u64::BITS - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS;
+
+/// Bucket of a latency of `value_us`
+fn bucket(value_us: u64) -> usize {
+    if value_us < SUB_BUCKETS as u64 {
+        return value_us as usize;
+    }
+    let exponent = u64::BITS - 1 - value_us.leading_zeros();
+    let sub_bucket = (value_us >> (exponent - SUB_BUCKET_BITS)) as usize & (SUB_BUCKETS - 1);
+    (exponent - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS + sub_bucket
+}
+
+/// Smallest latency counted in `bucket`
+fn bucket_lower_bound(bucket: usize) -> u64 {
+    if bucket < SUB_BUCKETS {
+        return bucket as u64;
+    }
+    let exponent = (bucket / SUB_BUCKETS) as u32 + SUB_BUCKET_BITS - 1;
+    let sub_bucket = (bucket % SUB_BUCKETS) as u64;
+    (1 << exponent) | (sub_bucket << (exponent - SUB_BUCKET_BITS))
+}
+
+/// Latencies of the sends of a producer, or of several once merged
+pub struct SendLatencies {
+    buckets: Vec<u64>,
+    max_us: u64,
+}
+
+impl Default for SendLatencies {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; NUM_BUCKETS],
+            max_us: 0,
+        }
+    }
+}
+
+impl SendLatencies {
+    pub fn record(&mut self, latency: Duration) {
+        let latency_us = latency.as_micros() as u64;
+        self.buckets[bucket(latency_us)] += 1;
+        self.max_us = self.max_us.max(latency_us);
+    }
+
+    /// Add the latencies recorded by `other`, e.g. by another producer
+    pub fn merge(&mut self, other: &Self) {
+        for (count, other_count) in self.buckets.iter_mut().zip(&other.buckets) {
+            *count += other_count;
+        }
+        self.max_us = self.max_us.max(other.max_us);
+    }
+
+    /// Percentiles of the latencies recorded, `None` if none was
+    pub fn percentiles(&self) -> Option<LatencyPercentiles> {
+        let total: u64 = self.buckets.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let percentile = |percent: u64| {
+            let rank = (total * percent).div_ceil(100).max(1);
+            let mut seen = 0;
+            self.buckets
+                .iter()
+                .position(|count| {
+                    seen += count;
+                    seen >= rank
+                })
+                .map_or(0, bucket_lower_bound)
+        };
+        Some(LatencyPercentiles {
+            p50_us: percentile(50),
+            p90_us: percentile(90),
+            p99_us: percentile(99),
+            max_us: self.max_us,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+impl fmt::Display for LatencyPercentiles {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Send latency: p50 {}us, p90 {}us, p99 {}us, max {}us",
+            self.p50_us, self.p90_us, self.p99_us, self.max_us
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets() {
+        for bucket_index in 0..NUM_BUCKETS {
+            let lower_bound = bucket_lower_bound(bucket_index);
+            assert_eq!(bucket(lower_bound), bucket_index);
+            if bucket_index > 0 {
+                assert_eq!(bucket(lower_bound - 1), bucket_index - 1);
+            }
+        }
+        assert_eq!(bucket(u64::MAX), NUM_BUCKETS - 1);
+        // within 1/8 of the latency
+        assert_eq!(bucket_lower_bound(bucket(1_000)), 960);
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut latencies = SendLatencies::default();
+        assert_eq!(latencies.percentiles(), None);
+
+        // recorded by two producers
+        let mut other_latencies = SendLatencies::default();
+        for latency_us in 1..=100 {
+            if latency_us % 2 == 0 {
+                latencies.record(Duration::from_micros(latency_us));
+            } else {
+                other_latencies.record(Duration::from_micros(latency_us));
+            }
+        }
+        other_latencies.record(Duration::from_millis(5));
+        latencies.merge(&other_latencies);
+        assert_eq!(
+            latencies.percentiles(),
+            Some(LatencyPercentiles {
+                p50_us: 48,
+                p90_us: 88,
+                p99_us: 96,
+                max_us: 5_000,
+            })
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Channel between the receivers of the server and the sinks, and the depth
+//! it reaches, under `--channel-capacity`.
+//!
+//! By default the channel is unbounded, so that a sink slower than the
+//! network, e.g. under `--sink-delay-us`, lets it grow without bound. With a
+//! capacity the channel is bounded and the receivers block while it is full,
+//! pushing back on the transport, whose drops show up as packets never
+//! received. Under `--drop-on-full-channel` the receivers send to a relay
+//! instead, which drops and counts the batches finding the channel full.
+//!
+//! The depth of the channels is sampled every report interval by a thread of
+//! its own, so that a sink stalled under `--sink-delay-us` does not skip the
+//! samples taken while the channel is the fullest.
+
+use {
+    crossbeam_channel::{bounded, unbounded, RecvTimeoutError, TrySendError},
+    solana_streamer::{
+        packet::PacketBatch,
+        streamer::{PacketBatchReceiver, PacketBatchSender},
+    },
+    std::{
+        fmt,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc,
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+const RELAY_RECEIVE_TIMEOUT: Duration = Duration::from_millis(100);
+const DEPTH_SAMPLER_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A channel of `capacity` batches, unbounded if `None`
+pub fn sink_channel(capacity: Option<usize>) -> (PacketBatchSender, PacketBatchReceiver) {
+    match capacity {
+        Some(capacity) => bounded(capacity),
+        None => unbounded(),
+    }
+}
+
+/// Batches and packets dropped because the channel was full
+#[derive(Default)]
+pub struct ChannelDrops {
+    batches: AtomicU64,
+    packets: AtomicU64,
+}
+
+impl ChannelDrops {
+    fn record(&self, packet_batch: &PacketBatch) {
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.packets
+            .fetch_add(packet_batch.len() as u64, Ordering::Relaxed);
+    }
+
+    pub fn batches(&self) -> u64 {
+        self.batches.load(Ordering::Relaxed)
+    }
+
+    pub fn packets(&self) -> u64 {
+        self.packets.load(Ordering::Relaxed)
+    }
+}
+
+/// Relay the batches of `ingress` to `sender`, dropping those which find it
+/// full into `drops`
+pub fn spawn_dropping_relay(
+    exit: Arc<AtomicBool>,
+    ingress: PacketBatchReceiver,
+    sender: PacketBatchSender,
+    drops: Arc<ChannelDrops>,
+) -> JoinHandle<()> {
+    thread::Builder::new()
+        .name("solBenVoteRelay".to_string())
+        .spawn(move || {
+            while !exit.load(Ordering::Relaxed) {
+                let packet_batch = match ingress.recv_timeout(RELAY_RECEIVE_TIMEOUT) {
+                    Ok(packet_batch) => packet_batch,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                match sender.try_send(packet_batch) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(packet_batch)) => drops.record(&packet_batch),
+                    Err(TrySendError::Disconnected(_)) => break,
+                }
+            }
+        })
+        .unwrap()
+}
+
+/// Depths of a channel, sampled over a run
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChannelDepth {
+    pub samples: u64,
+    total: u64,
+    pub max: usize,
+    /// Samples finding the channel at its capacity
+    pub full: u64,
+}
+
+impl ChannelDepth {
+    pub fn record(&mut self, depth: usize, capacity: Option<usize>) {
+        self.samples += 1;
+        self.total += depth as u64;
+        self.max = self.max.max(depth);
+        if capacity.is_some_and(|capacity| depth >= capacity) {
+            self.full += 1;
+        }
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        (self.samples > 0).then(|| self.total as f64 / self.samples as f64)
+    }
+}
+
+/// Sample the depth of each of `receivers` every `interval` until `exit`,
+/// returning the samples of all of them together
+pub fn spawn_depth_sampler(
+    exit: Arc<AtomicBool>,
+    receivers: Vec<PacketBatchReceiver>,
+    interval: Duration,
+) -> JoinHandle<ChannelDepth> {
+    thread::Builder::new()
+        .name("solBenVoteDepth".to_string())
+        .spawn(move || {
+            let start = Instant::now();
+            let mut next_sample = 1;
+            let mut channel_depth = ChannelDepth::default();
+            while !exit.load(Ordering::Relaxed) {
+                // sample on fixed deadlines so that the samples do not drift
+                if start.elapsed() < interval * next_sample {
+                    thread::sleep(DEPTH_SAMPLER_POLL_INTERVAL);
+                    continue;
+                }
+                next_sample += 1;
+                for receiver in &receivers {
+                    channel_depth.record(receiver.len(), receiver.capacity());
+                }
+            }
+            channel_depth
+        })
+        .unwrap()
+}
+
+/// The channels of the sinks over a run, and where the packets which did not
+/// reach the sinks were lost
+pub struct SinkChannelReport {
+    pub capacity: Option<usize>,
+    pub depth: ChannelDepth,
+    /// Batches and packets dropped by the relay, under
+    /// `--drop-on-full-channel`
+    pub dropped_on_full: Option<(u64, u64)>,
+    /// Packets the client sent successfully, unless the run was server only
+    pub sent: Option<u64>,
+    pub received: u64,
+}
+
+impl SinkChannelReport {
+    /// Packets sent which neither reached a sink nor were dropped on a full
+    /// channel, i.e. lost by the transport or the receivers
+    pub fn never_received(&self) -> Option<u64> {
+        self.sent.map(|sent| {
+            sent.saturating_sub(self.received).saturating_sub(
+                self.dropped_on_full
+                    .map_or(0, |(_batches, packets)| packets),
+            )
+        })
+    }
+}
+
+impl fmt::Display for SinkChannelReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.capacity {
+            Some(capacity) => write!(f, "Sink channels: capacity {capacity} batches, ")?,
+            None => write!(f, "Sink channels: unbounded, ")?,
+        }
+        match self.depth.average() {
+            Some(average) => write!(
+                f,
+                "depth {average:.1} batches on average, {} at most, full in {} of {} samples",
+                self.depth.max, self.depth.full, self.depth.samples
+            )?,
+            None => write!(f, "depth not sampled")?,
+        }
+        match self.dropped_on_full {
+            Some((batches, packets)) => write!(
+                f,
+                "\nDropped on a full channel: {packets} packets in {batches} batches"
+            )?,
+            None => write!(f, "\nDropped on a full channel: none, the receivers block")?,
+        }
+        match self.never_received() {
+            Some(never_received) => write!(f, "\nNever received: {never_received} packets"),
+            None => write!(f, "\nNever received: unknown without a client"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_streamer::packet::Packet};
+
+    #[test]
+    fn test_sink_channel_capacity() {
+        let (sender, receiver) = sink_channel(Some(2));
+        assert_eq!(sender.capacity(), Some(2));
+        assert_eq!(receiver.capacity(), Some(2));
+        for _ in 0..2 {
+            sender.try_send(PacketBatch::default()).unwrap();
+        }
+        assert!(sender
+            .try_send(PacketBatch::default())
+            .unwrap_err()
+            .is_full());
+
+        let (sender, _receiver) = sink_channel(None);
+        assert_eq!(sender.capacity(), None);
+        for _ in 0..100 {
+            sender.try_send(PacketBatch::default()).unwrap();
+        }
+        assert_eq!(sender.len(), 100);
+    }
+
+    #[test]
+    fn test_dropping_relay() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let (ingress_sender, ingress) = sink_channel(None);
+        let (sender, receiver) = sink_channel(Some(1));
+        let drops = Arc::new(ChannelDrops::default());
+        for num_packets in 1..=3 {
+            ingress_sender
+                .send(PacketBatch::new(vec![Packet::default(); num_packets]))
+                .unwrap();
+        }
+        drop(ingress_sender);
+        let relay = spawn_dropping_relay(exit, ingress, sender, drops.clone());
+        relay.join().unwrap();
+
+        // the first batch fills the channel, the others are dropped
+        assert_eq!(receiver.try_recv().unwrap().len(), 1);
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(drops.batches(), 2);
+        assert_eq!(drops.packets(), 5);
+    }
+
+    #[test]
+    fn test_channel_depth() {
+        let mut depth = ChannelDepth::default();
+        assert_eq!(depth.average(), None);
+        for sample in [0, 4, 8, 8] {
+            depth.record(sample, Some(8));
+        }
+        assert_eq!(depth.samples, 4);
+        assert_eq!(depth.average(), Some(5.0));
+        assert_eq!(depth.max, 8);
+        assert_eq!(depth.full, 2);
+
+        // an unbounded channel is never full
+        let mut unbounded = ChannelDepth::default();
+        unbounded.record(1_000, None);
+        assert_eq!(unbounded.full, 0);
+    }
+
+    #[test]
+    fn test_depth_sampler() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = sink_channel(Some(4));
+        for _ in 0..3 {
+            sender.send(PacketBatch::default()).unwrap();
+        }
+        // nothing receives from the channel, as with a stalled sink
+        let sampler = spawn_depth_sampler(exit.clone(), vec![receiver], Duration::from_millis(20));
+        thread::sleep(Duration::from_millis(100));
+        sender.send(PacketBatch::default()).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        exit.store(true, Ordering::Relaxed);
+        let depth = sampler.join().unwrap();
+
+        assert!(depth.samples >= 2);
+        assert_eq!(depth.max, 4);
+        assert!(depth.full >= 1);
+        assert!(depth.full < depth.samples);
+    }
+
+    #[test]
+    fn test_sink_channel_report() {
+        let report = SinkChannelReport {
+            capacity: Some(8),
+            depth: ChannelDepth::default(),
+            dropped_on_full: Some((3, 30)),
+            sent: Some(100),
+            received: 60,
+        };
+        assert_eq!(report.never_received(), Some(10));
+        assert_eq!(
+            report.to_string(),
+            "Sink channels: capacity 8 batches, depth not sampled\nDropped on a full channel: \
+             30 packets in 3 batches\nNever received: 10 packets"
+        );
+
+        let report = SinkChannelReport {
+            capacity: None,
+            dropped_on_full: None,
+            sent: None,
+            ..report
+        };
+        assert_eq!(report.never_received(), None);
+        assert!(report.to_string().ends_with("unknown without a client"));
+    }
+}
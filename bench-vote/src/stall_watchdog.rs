@@ -0,0 +1,305 @@
+//! Detects producers stalled in their send loop.
+//!
+//! A producer blocked in a send for long drags the aggregate rate down
+//! without telling which one or when. Each producer stores the time of its
+//! latest progress every `PROGRESS_INTERVAL` sends, and a watchdog thread
+//! scans these every `STALL_SCAN_INTERVAL`, warning about the producers that
+//! made no progress for longer than `--stall-threshold-ms`. The producers
+//! only ever store to their own atomics, so that the send path is not slowed
+//! down by the watchdog.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, sleep, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Sends between two updates of the progress of a producer
+pub const PROGRESS_INTERVAL: u64 = 16;
+const STALL_SCAN_INTERVAL: Duration = Duration::from_millis(500);
+const STALL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress of one producer, in milliseconds since the start of the run
+#[derive(Default)]
+struct ProducerProgress {
+    last_progress_ms: AtomicU64,
+    sent: AtomicU64,
+    finished: AtomicBool,
+    finished_ms: AtomicU64,
+    /// Written by the watchdog only
+    stalls: AtomicU64,
+}
+
+/// Progress of every producer of the run
+pub struct ProducerProgresses {
+    start: Instant,
+    producers: Vec<ProducerProgress>,
+}
+
+impl ProducerProgresses {
+    pub fn new(num_producers: usize) -> Self {
+        Self {
+            start: Instant::now(),
+            producers: (0..num_producers)
+                .map(|_| ProducerProgress::default())
+                .collect(),
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    /// Record that `producer` has sent `sent` transactions so far
+    pub fn record_progress(&self, producer: usize, sent: u64) {
+        let progress = &self.producers[producer];
+        progress.sent.store(sent, Ordering::Relaxed);
+        progress
+            .last_progress_ms
+            .store(self.now_ms(), Ordering::Relaxed);
+    }
+
+    /// Record that `producer` is done, having sent `sent` transactions
+    pub fn record_finished(&self, producer: usize, sent: u64) {
+        let progress = &self.producers[producer];
+        progress.sent.store(sent, Ordering::Relaxed);
+        progress.finished_ms.store(self.now_ms(), Ordering::Relaxed);
+        progress.finished.store(true, Ordering::Relaxed);
+    }
+
+    /// The time of the latest progress of each producer, `None` for the
+    /// finished ones
+    fn last_progress_ms(&self) -> Vec<Option<u64>> {
+        self.producers
+            .iter()
+            .map(|progress| {
+                (!progress.finished.load(Ordering::Relaxed))
+                    .then(|| progress.last_progress_ms.load(Ordering::Relaxed))
+            })
+            .collect()
+    }
+
+    /// Sent count, active time and stalls of each producer
+    pub fn summary(&self) -> ProducerProgressSummary {
+        let now_ms = self.now_ms();
+        ProducerProgressSummary(
+            self.producers
+                .iter()
+                .map(|progress| {
+                    let active_ms = if progress.finished.load(Ordering::Relaxed) {
+                        progress.finished_ms.load(Ordering::Relaxed)
+                    } else {
+                        now_ms
+                    };
+                    (
+                        progress.sent.load(Ordering::Relaxed),
+                        Duration::from_millis(active_ms),
+                        progress.stalls.load(Ordering::Relaxed),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+/// `(sent, active time, stalls)` of each producer
+pub struct ProducerProgressSummary(Vec<(u64, Duration, u64)>);
+
+impl fmt::Display for ProducerProgressSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (producer, (sent, active, stalls)) in self.0.iter().enumerate() {
+            if producer > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "Producer {producer}: sent {sent} in {:.3}s, stalled {stalls} times",
+                active.as_secs_f64(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A change in the state of a producer found by a scan
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StallEvent {
+    /// The producer has made no progress for `stalled_ms`
+    Stalled { producer: usize, stalled_ms: u64 },
+    /// The producer progressed again, after `stalled_ms` without progress
+    Resumed { producer: usize, stalled_ms: u64 },
+}
+
+impl fmt::Display for StallEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Stalled {
+                producer,
+                stalled_ms,
+            } => write!(
+                f,
+                "producer {producer} has made no progress for {stalled_ms} ms"
+            ),
+            Self::Resumed {
+                producer,
+                stalled_ms,
+            } => write!(
+                f,
+                "producer {producer} resumed after {stalled_ms} ms without progress"
+            ),
+        }
+    }
+}
+
+/// Compares the progress of the producers from one scan to the next
+pub struct StallScanner {
+    threshold_ms: u64,
+    /// The last progress of each stalled producer, when it was found stalled
+    stalled_since: Vec<Option<u64>>,
+}
+
+impl StallScanner {
+    pub fn new(num_producers: usize, threshold_ms: u64) -> Self {
+        Self {
+            threshold_ms,
+            stalled_since: vec![None; num_producers],
+        }
+    }
+
+    /// Find the producers stalled, or resumed, since the previous scan, given
+    /// the last progress of each producer, `None` once it finished. A stall
+    /// is reported once, when it exceeds the threshold.
+    pub fn scan(&mut self, now_ms: u64, last_progress_ms: &[Option<u64>]) -> Vec<StallEvent> {
+        let mut events = vec![];
+        for (producer, (last_progress_ms, stalled_since)) in last_progress_ms
+            .iter()
+            .zip(self.stalled_since.iter_mut())
+            .enumerate()
+        {
+            match (*last_progress_ms, *stalled_since) {
+                (Some(last_progress_ms), Some(since)) if last_progress_ms > since => {
+                    events.push(StallEvent::Resumed {
+                        producer,
+                        stalled_ms: last_progress_ms - since,
+                    });
+                    *stalled_since = None;
+                }
+                (Some(last_progress_ms), None)
+                    if now_ms.saturating_sub(last_progress_ms) > self.threshold_ms =>
+                {
+                    events.push(StallEvent::Stalled {
+                        producer,
+                        stalled_ms: now_ms - last_progress_ms,
+                    });
+                    *stalled_since = Some(last_progress_ms);
+                }
+                (None, _) => *stalled_since = None,
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+pub fn spawn_stall_watchdog(
+    exit: Arc<AtomicBool>,
+    progresses: Arc<ProducerProgresses>,
+    threshold_ms: u64,
+) -> JoinHandle<()> {
+    thread::Builder::new()
+        .name("solBenVoteStall".to_string())
+        .spawn(move || {
+            let mut scanner = StallScanner::new(progresses.producers.len(), threshold_ms);
+            let mut last_scan = Instant::now();
+            while !exit.load(Ordering::Relaxed) {
+                sleep(STALL_POLL_INTERVAL);
+                if last_scan.elapsed() < STALL_SCAN_INTERVAL {
+                    continue;
+                }
+                last_scan = Instant::now();
+
+                let events = scanner.scan(progresses.now_ms(), &progresses.last_progress_ms());
+                for event in events {
+                    if let StallEvent::Stalled { producer, .. } = event {
+                        progresses.producers[producer]
+                            .stalls
+                            .fetch_add(1, Ordering::Relaxed);
+                    }
+                    eprintln!("Warning: {event}");
+                }
+            }
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stall_scanner() {
+        let mut scanner = StallScanner::new(3, 1000);
+        assert!(scanner.scan(500, &[Some(0), Some(400), Some(0)]).is_empty());
+        // exactly at the threshold is not a stall yet
+        assert!(scanner
+            .scan(1000, &[Some(0), Some(900), Some(900)])
+            .is_empty());
+
+        assert_eq!(
+            scanner.scan(1500, &[Some(0), Some(1400), Some(1400)]),
+            [StallEvent::Stalled {
+                producer: 0,
+                stalled_ms: 1500,
+            }]
+        );
+        // reported once however long it lasts
+        assert!(scanner
+            .scan(4000, &[Some(0), Some(3900), Some(3900)])
+            .is_empty());
+
+        assert_eq!(
+            scanner.scan(4500, &[Some(4200), Some(2000), Some(4400)]),
+            [
+                StallEvent::Resumed {
+                    producer: 0,
+                    stalled_ms: 4200,
+                },
+                StallEvent::Stalled {
+                    producer: 1,
+                    stalled_ms: 2500,
+                },
+            ]
+        );
+        // a finished producer is neither stalled nor resumed
+        assert!(scanner.scan(9000, &[None, None, None]).is_empty());
+        assert_eq!(
+            StallEvent::Stalled {
+                producer: 1,
+                stalled_ms: 2500,
+            }
+            .to_string(),
+            "producer 1 has made no progress for 2500 ms"
+        );
+    }
+
+    #[test]
+    fn test_producer_progresses() {
+        let progresses = ProducerProgresses::new(2);
+        progresses.record_progress(0, PROGRESS_INTERVAL);
+        progresses.record_finished(1, 5);
+        assert_eq!(progresses.last_progress_ms().len(), 2);
+        assert!(progresses.last_progress_ms()[0].is_some());
+        assert_eq!(progresses.last_progress_ms()[1], None);
+        progresses.producers[0].stalls.store(2, Ordering::Relaxed);
+
+        let summary = progresses.summary();
+        assert_eq!(summary.0[0].0, PROGRESS_INTERVAL);
+        assert_eq!(summary.0[0].2, 2);
+        assert_eq!(summary.0[1].0, 5);
+        assert!(summary.to_string().contains("Producer 1: sent 5 in "));
+    }
+}
@@ -0,0 +1,136 @@
+//! Dump of the individual transactions sent by the producers, to debug
+//! transactions that are not being received.
+
+use {
+    solana_sdk::{
+        clock::Slot, hash::Hash, pubkey::Pubkey, signature::Signature, transaction::Transaction,
+    },
+    solana_vote_program::vote_instruction::VoteInstruction,
+    std::fmt,
+};
+
+/// Details are printed for at most this many transactions
+pub const MAX_TRANSACTION_DETAILS: usize = 100;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TransactionDetails {
+    pub signature: Signature,
+    pub fee_payer: Pubkey,
+    pub vote_slots: Vec<Slot>,
+    pub recent_blockhash: Hash,
+    pub serialized_size_bytes: usize,
+}
+
+impl TransactionDetails {
+    /// Decode the details from the bytes that are sent over the wire
+    pub fn from_serialized(serialized_transaction: &[u8]) -> bincode::Result<Self> {
+        let transaction: Transaction = bincode::deserialize(serialized_transaction)?;
+        let vote_slots = transaction
+            .message
+            .instructions
+            .iter()
+            .filter(|instruction| {
+                transaction
+                    .message
+                    .account_keys
+                    .get(usize::from(instruction.program_id_index))
+                    == Some(&solana_vote_program::id())
+            })
+            .filter_map(|instruction| bincode::deserialize(&instruction.data).ok())
+            .flat_map(|instruction| vote_slots(&instruction))
+            .collect();
+        Ok(Self {
+            signature: transaction.signatures.first().copied().unwrap_or_default(),
+            fee_payer: transaction
+                .message
+                .account_keys
+                .first()
+                .copied()
+                .unwrap_or_default(),
+            vote_slots,
+            recent_blockhash: transaction.message.recent_blockhash,
+            serialized_size_bytes: serialized_transaction.len(),
+        })
+    }
+}
+
+fn vote_slots(instruction: &VoteInstruction) -> Vec<Slot> {
+    match instruction {
+        VoteInstruction::Vote(vote) | VoteInstruction::VoteSwitch(vote, _) => vote.slots.clone(),
+        VoteInstruction::UpdateVoteState(vote_state_update)
+        | VoteInstruction::UpdateVoteStateSwitch(vote_state_update, _)
+        | VoteInstruction::CompactUpdateVoteState(vote_state_update)
+        | VoteInstruction::CompactUpdateVoteStateSwitch(vote_state_update, _) => {
+            vote_state_update.slots()
+        }
+        VoteInstruction::TowerSync(tower_sync)
+        | VoteInstruction::TowerSyncSwitch(tower_sync, _) => tower_sync.slots(),
+        _ => vec![],
+    }
+}
+
+// Formatted by hand rather than with `Debug` so that the output stays stable
+impl fmt::Display for TransactionDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "signature={} fee_payer={} vote_slots=[{}] recent_blockhash={} \
+             serialized_size_bytes={}",
+            self.signature,
+            self.fee_payer,
+            self.vote_slots
+                .iter()
+                .map(Slot::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            self.recent_blockhash,
+            self.serialized_size_bytes,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::vote_instruction_variant::VoteInstructionVariant,
+        solana_sdk::{
+            message::Message,
+            signature::{keypair_from_seed, Signer},
+        },
+    };
+
+    const GOLDEN: &str = include_str!("../tests/fixtures/transaction_details.golden");
+
+    #[test]
+    fn test_transaction_details_golden() {
+        let identity_keypair = keypair_from_seed(&[7; 32]).unwrap();
+        let details: Vec<_> = [0, 42]
+            .into_iter()
+            .map(|slot| {
+                let vote_instruction = VoteInstructionVariant::Vote.instruction(
+                    &identity_keypair.pubkey(),
+                    slot,
+                    Hash::new_from_array([2; 32]),
+                    Hash::default(),
+                );
+                let message = Message::new(&[vote_instruction], Some(&identity_keypair.pubkey()));
+                let transaction =
+                    Transaction::new(&[&identity_keypair], message, Hash::new_from_array([3; 32]));
+                let serialized_transaction = bincode::serialize(&transaction).unwrap();
+                let details = TransactionDetails::from_serialized(&serialized_transaction).unwrap();
+                assert_eq!(details.signature, transaction.signatures[0]);
+                assert_eq!(details.fee_payer, identity_keypair.pubkey());
+                assert_eq!(details.vote_slots, vec![slot]);
+                details.to_string()
+            })
+            .collect();
+
+        assert_eq!(details.join("\n"), GOLDEN.trim_end());
+    }
+
+    #[test]
+    fn test_transaction_details_invalid() {
+        assert!(TransactionDetails::from_serialized(&[1, 2, 3]).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Transports shared by the producers and the forwarder
+
+use {
+    crate::{cache_stats::CacheStats, dscp},
+    solana_client::connection_cache::ConnectionCache,
+    solana_connection_cache::client_connection::ClientConnection,
+    solana_net_utils::bind_to_unspecified,
+    solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer, transport},
+    solana_streamer::streamer::StakedNodes,
+    std::{
+        collections::HashMap,
+        net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, RwLock,
+        },
+    },
+};
+
+pub const QUIC_CONNECTION_POOL_SIZE: usize = 256;
+pub const UDP_CONNECTION_POOL_SIZE: usize = 1;
+
+pub struct QuicParams {
+    pub identity_keypair: Keypair,
+    pub staked_nodes: Arc<RwLock<StakedNodes>>,
+}
+
+impl QuicParams {
+    /// Params of a QUIC endpoint staked with all of the stake
+    pub fn new(identity_keypair: Keypair) -> Self {
+        let stake: u64 = 1024;
+        let total_stake: u64 = 1024;
+
+        let stakes = HashMap::from([
+            (identity_keypair.pubkey(), stake),
+            (Pubkey::new_unique(), total_stake.saturating_sub(stake)),
+        ]);
+        let staked_nodes: Arc<RwLock<StakedNodes>> = Arc::new(RwLock::new(StakedNodes::new(
+            Arc::new(stakes),
+            HashMap::<Pubkey, u64>::default(), // overrides
+        )));
+
+        Self {
+            identity_keypair,
+            staked_nodes,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum Transporter {
+    Cache(Arc<ConnectionCache>),
+    DirectSocket(Arc<UdpSocket>),
+    /// Transporters of an identity pool, see `Transporter::new_pool`
+    Pool(Arc<TransporterPool>),
+}
+
+/// Transporters each send goes through in turn
+pub struct TransporterPool {
+    transporters: Vec<Transporter>,
+    next: AtomicUsize,
+}
+
+impl TransporterPool {
+    pub fn new(transporters: Vec<Transporter>) -> Self {
+        assert!(!transporters.is_empty());
+        Self {
+            transporters,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    fn next(&self) -> &Transporter {
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        &self.transporters[next % self.transporters.len()]
+    }
+}
+
+impl Transporter {
+    /// A connection cache named `name`, over QUIC if `quic_params` is set or
+    /// over UDP if `use_connection_cache` is, else a plain UDP socket marked
+    /// with `dscp`
+    pub fn new(
+        name: &'static str,
+        use_connection_cache: bool,
+        quic_params: Option<&QuicParams>,
+        dscp: Option<u8>,
+    ) -> Result<Self, String> {
+        if let Some(quic_params) = quic_params {
+            return Ok(Self::Cache(Arc::new(
+                ConnectionCache::new_with_client_options(
+                    name,
+                    QUIC_CONNECTION_POOL_SIZE,
+                    None, // client_endpoint
+                    Some((
+                        &quic_params.identity_keypair,
+                        IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+                    )),
+                    Some((
+                        &quic_params.staked_nodes,
+                        &quic_params.identity_keypair.pubkey(),
+                    )),
+                ),
+            )));
+        }
+        if use_connection_cache {
+            return Ok(Self::Cache(Arc::new(ConnectionCache::with_udp(
+                name,
+                UDP_CONNECTION_POOL_SIZE,
+            ))));
+        }
+        let socket =
+            bind_to_unspecified().map_err(|err| format!("failed to bind a UDP socket: {err}"))?;
+        if let Some(dscp) = dscp {
+            dscp::set_dscp(&socket, dscp)
+                .map_err(|err| format!("failed to set DSCP {dscp} on the client socket: {err}"))?;
+        }
+        Ok(Self::DirectSocket(Arc::new(socket)))
+    }
+
+    /// QUIC connection caches named `name`, one for each of `quic_params`,
+    /// the sends going through each of them in turn
+    pub fn new_pool(name: &'static str, quic_params: &[QuicParams]) -> Result<Self, String> {
+        let transporters = quic_params
+            .iter()
+            .map(|quic_params| Self::new(name, false, Some(quic_params), None))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::Pool(Arc::new(TransporterPool::new(transporters))))
+    }
+
+    /// Send `payload` to `destination`, recording the connection lookups of a
+    /// connection cache in `cache_stats`
+    pub fn send(
+        &self,
+        destination: &SocketAddr,
+        payload: &[u8],
+        cache_stats: Option<&CacheStats>,
+    ) -> transport::Result<()> {
+        match self {
+            Self::Cache(cache) => {
+                let connection = cache.get_connection(destination);
+                if let Some(cache_stats) = cache_stats {
+                    cache_stats.record_get_connection(destination);
+                }
+                connection.send_data(payload)
+            }
+            Self::DirectSocket(socket) => {
+                socket.send_to(payload, destination)?;
+                Ok(())
+            }
+            Self::Pool(pool) => pool.next().send(destination, payload, cache_stats),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Packets dropped by the kernel before the benchmark could receive them.
+//!
+//! On Linux the system-wide UDP counters of /proc/net/snmp and the per-socket
+//! drop counters of /proc/net/udp and /proc/net/udp6 are snapshotted when the
+//! server starts and when the run ends. Reading them is best-effort: when they
+//! are unavailable the report says so and the benchmark is unaffected.
+
+use std::{fmt, fs};
+
+const PROC_NET_SNMP: &str = "/proc/net/snmp";
+const PROC_NET_UDP: [&str; 2] = ["/proc/net/udp", "/proc/net/udp6"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UdpCounters {
+    /// Datagrams the kernel failed to deliver, system-wide; includes `rcvbuf_errors`
+    pub in_errors: u64,
+    /// Datagrams dropped because a socket receive buffer was full, system-wide
+    pub rcvbuf_errors: u64,
+    /// Drops of the sockets bound to the benchmark port, if accessible
+    pub socket_drops: Option<u64>,
+}
+
+/// Parse the `InErrors` and `RcvbufErrors` counters of the `Udp:` lines of /proc/net/snmp
+pub fn parse_snmp_udp(snmp: &str) -> Option<(u64, u64)> {
+    let mut udp_lines = snmp.lines().filter(|line| line.starts_with("Udp:"));
+    let names = udp_lines.next()?.split_whitespace();
+    let values = udp_lines.next()?.split_whitespace();
+    let counters: Vec<_> = names.zip(values).skip(1).collect();
+    let counter = |name| {
+        counters
+            .iter()
+            .find(|(counter_name, _)| *counter_name == name)
+            .and_then(|(_, value)| value.parse::<u64>().ok())
+    };
+    Some((counter("InErrors")?, counter("RcvbufErrors")?))
+}
+
+/// Sum the `drops` column of the sockets of /proc/net/udp (or udp6) whose
+/// local port is `port`
+pub fn parse_socket_drops(udp: &str, port: u16) -> Option<u64> {
+    let mut lines = udp.lines();
+    // the header names do not line up with the columns of the entries, but
+    // `drops` is the last column of both
+    if lines.next()?.split_whitespace().last()? != "drops" {
+        return None;
+    }
+    let mut drops = 0;
+    for line in lines {
+        let columns: Vec<_> = line.split_whitespace().collect();
+        let local_port = columns
+            .get(1)
+            .and_then(|local_address| local_address.rsplit_once(':'))
+            .and_then(|(_, local_port)| u16::from_str_radix(local_port, 16).ok())?;
+        if local_port == port {
+            drops += columns.last()?.parse::<u64>().ok()?;
+        }
+    }
+    Some(drops)
+}
+
+impl UdpCounters {
+    /// Read the counters for the sockets bound to `port`.
+    /// Returns None when /proc/net/snmp cannot be read or parsed.
+    pub fn snapshot(port: u16) -> Option<Self> {
+        if !cfg!(target_os = "linux") {
+            return None;
+        }
+        let (in_errors, rcvbuf_errors) = parse_snmp_udp(&fs::read_to_string(PROC_NET_SNMP).ok()?)?;
+        // udp6 is missing when IPv6 is disabled
+        let socket_drops = PROC_NET_UDP
+            .iter()
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .map(|udp| parse_socket_drops(&udp, port))
+            .collect::<Option<Vec<_>>>()
+            .filter(|drops| !drops.is_empty())
+            .map(|drops| drops.into_iter().sum());
+        Some(Self {
+            in_errors,
+            rcvbuf_errors,
+            socket_drops,
+        })
+    }
+
+    /// Counters accumulated between `self` and `later`
+    pub fn delta(&self, later: &Self) -> Self {
+        Self {
+            in_errors: later.in_errors.saturating_sub(self.in_errors),
+            rcvbuf_errors: later.rcvbuf_errors.saturating_sub(self.rcvbuf_errors),
+            socket_drops: self
+                .socket_drops
+                .zip(later.socket_drops)
+                .map(|(before, after)| after.saturating_sub(before)),
+        }
+    }
+}
+
+/// Kernel drops during a run, next to the packets received by the application
+pub struct UdpDropsReport {
+    /// None when the counters could not be read at the start or end of the run
+    pub delta: Option<UdpCounters>,
+    pub received: usize,
+}
+
+impl UdpDropsReport {
+    pub fn new(start: Option<UdpCounters>, end: Option<UdpCounters>, received: usize) -> Self {
+        Self {
+            delta: start.zip(end).map(|(start, end)| start.delta(&end)),
+            received,
+        }
+    }
+
+    /// Packets lost by the transport: the drops of the benchmark sockets when
+    /// accessible, otherwise the system-wide UDP receive errors
+    pub fn transport_loss(&self) -> Option<u64> {
+        self.delta
+            .map(|delta| delta.socket_drops.unwrap_or(delta.in_errors))
+    }
+}
+
+impl fmt::Display for UdpDropsReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (Some(delta), Some(transport_loss)) = (self.delta, self.transport_loss()) else {
+            let reason = if cfg!(target_os = "linux") {
+                format!("{PROC_NET_SNMP} could not be read")
+            } else {
+                "not supported on this platform".to_string()
+            };
+            return write!(f, "Kernel UDP drops: measurement unavailable, {reason}");
+        };
+        let socket_drops = delta
+            .socket_drops
+            .map(|drops| drops.to_string())
+            .unwrap_or_else(|| "unavailable".to_string());
+        let sent = transport_loss + self.received as u64;
+        let loss_percent = if sent == 0 {
+            0.0
+        } else {
+            transport_loss as f64 * 100.0 / sent as f64
+        };
+        write!(
+            f,
+            "Kernel UDP drops: InErrors {}, RcvbufErrors {}, socket drops {socket_drops}; \
+             received by the application: {}; transport loss estimate: {transport_loss} \
+             packets ({loss_percent:.2}%)",
+            delta.in_errors, delta.rcvbuf_errors, self.received,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNMP: &str = "\
+Ip: Forwarding DefaultTTL InReceives InHdrErrors InAddrErrors ForwDatagrams InUnknownProtos InDiscards InDelivers OutRequests OutDiscards OutNoRoutes ReasmTimeout ReasmReqds ReasmOKs ReasmFails FragOKs FragFails FragCreates OutTransmits
+Ip: 1 64 1234567 0 12 0 0 0 1234500 987654 40 2 0 0 0 0 0 0 0 987654
+Icmp: InMsgs InErrors InCsumErrors InDestUnreachs
+Icmp: 40 1 0 39
+Udp: InDatagrams NoPorts InErrors OutDatagrams RcvbufErrors SndbufErrors InCsumErrors IgnoredMulti MemErrors
+Udp: 5000123 17 4242 4000000 4200 0 42 3 0
+UdpLite: InDatagrams NoPorts InErrors OutDatagrams RcvbufErrors SndbufErrors InCsumErrors IgnoredMulti MemErrors
+UdpLite: 0 0 99 0 99 0 0 0 0
+";
+
+    const UDP: &str = "   \
+   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  583: 00000000:1F90 00000000:0000 07 00000000:00000000 00:00000000 00000000  1000        0 41234 2 0000000000000000 120
+  584: 00000000:1F90 00000000:0000 07 00000000:00034000 00:00000000 00000000  1000        0 41235 2 0000000000000000 7
+  923: 3500007F:0035 00000000:0000 07 00000000:00000000 00:00000000 00000000   101        0 18345 2 0000000000000000 5
+";
+
+    #[test]
+    fn test_parse_snmp_udp() {
+        assert_eq!(parse_snmp_udp(SNMP), Some((4242, 4200)));
+        assert_eq!(parse_snmp_udp("Ip: Forwarding\nIp: 1\n"), None);
+        assert_eq!(parse_snmp_udp(""), None);
+    }
+
+    #[test]
+    fn test_parse_socket_drops() {
+        // 0x1F90 is port 8080
+        assert_eq!(parse_socket_drops(UDP, 8080), Some(127));
+        assert_eq!(parse_socket_drops(UDP, 53), Some(5));
+        assert_eq!(parse_socket_drops(UDP, 8000), Some(0));
+        assert_eq!(parse_socket_drops("", 8080), None);
+        assert_eq!(
+            parse_socket_drops("sl local_address drops\n0: garbage 1\n", 8080),
+            None
+        );
+    }
+
+    #[test]
+    fn test_udp_drops_report() {
+        let start = UdpCounters {
+            in_errors: 100,
+            rcvbuf_errors: 90,
+            socket_drops: Some(10),
+        };
+        let end = UdpCounters {
+            in_errors: 400,
+            rcvbuf_errors: 380,
+            socket_drops: Some(260),
+        };
+        let report = UdpDropsReport::new(Some(start), Some(end), 750);
+        assert_eq!(report.transport_loss(), Some(250));
+        assert_eq!(
+            report.to_string(),
+            "Kernel UDP drops: InErrors 300, RcvbufErrors 290, socket drops 250; received by \
+             the application: 750; transport loss estimate: 250 packets (25.00%)"
+        );
+
+        // without per-socket counters, the system-wide errors are the estimate
+        let end = UdpCounters {
+            socket_drops: None,
+            ..end
+        };
+        let report = UdpDropsReport::new(Some(start), Some(end), 700);
+        assert_eq!(report.transport_loss(), Some(300));
+
+        let report = UdpDropsReport::new(Some(start), None, 700);
+        assert_eq!(report.transport_loss(), None);
+        assert!(report.to_string().contains("measurement unavailable"));
+    }
+}
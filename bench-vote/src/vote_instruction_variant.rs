@@ -0,0 +1,163 @@
+//! Selection of the vote instruction sent by the producers.
+//!
+//! The variants serialize to different sizes, which lets the benchmark
+//! exercise the receive path with packets closer to what validators send.
+
+use {
+    serde::{Deserialize, Serialize},
+    solana_sdk::{clock::Slot, hash::Hash, instruction::Instruction, pubkey::Pubkey},
+    solana_vote_program::{
+        vote_instruction,
+        vote_state::{TowerSync, Vote, VoteStateUpdate},
+    },
+    std::{fmt, str::FromStr},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VoteInstructionVariant {
+    #[default]
+    Vote,
+    UpdateVoteState,
+    CompactUpdateVoteState,
+    TowerSync,
+}
+
+impl VoteInstructionVariant {
+    pub const NAMES: &'static [&'static str] = &[
+        "vote",
+        "update-vote-state",
+        "compact-update-vote-state",
+        "tower-sync",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Vote => "vote",
+            Self::UpdateVoteState => "update-vote-state",
+            Self::CompactUpdateVoteState => "compact-update-vote-state",
+            Self::TowerSync => "tower-sync",
+        }
+    }
+
+    /// Build a vote for `slot` signed by `authority` for the vote account
+    /// `authority`. Only tower sync votes carry a `block_id`
+    pub fn instruction(
+        &self,
+        authority: &Pubkey,
+        slot: Slot,
+        hash: Hash,
+        block_id: Hash,
+    ) -> Instruction {
+        match self {
+            Self::Vote => {
+                let vote = Vote {
+                    slots: vec![slot],
+                    hash,
+                    timestamp: None,
+                };
+                vote_instruction::vote(authority, authority, vote)
+            }
+            Self::UpdateVoteState => {
+                let mut vote_state_update = VoteStateUpdate::from(vec![(slot, 1)]);
+                vote_state_update.hash = hash;
+                vote_instruction::update_vote_state(authority, authority, vote_state_update)
+            }
+            Self::CompactUpdateVoteState => {
+                let mut vote_state_update = VoteStateUpdate::from(vec![(slot, 1)]);
+                vote_state_update.hash = hash;
+                vote_instruction::compact_update_vote_state(authority, authority, vote_state_update)
+            }
+            Self::TowerSync => {
+                let mut tower_sync = TowerSync::from(vec![(slot, 1)]);
+                tower_sync.hash = hash;
+                tower_sync.block_id = block_id;
+                vote_instruction::tower_sync(authority, authority, tower_sync)
+            }
+        }
+    }
+}
+
+impl FromStr for VoteInstructionVariant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vote" => Ok(Self::Vote),
+            "update-vote-state" => Ok(Self::UpdateVoteState),
+            "compact-update-vote-state" => Ok(Self::CompactUpdateVoteState),
+            "tower-sync" => Ok(Self::TowerSync),
+            _ => Err(format!("unknown vote instruction {s}")),
+        }
+    }
+}
+
+impl fmt::Display for VoteInstructionVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_vote_program::vote_instruction::VoteInstruction};
+
+    fn discriminant(instruction: &Instruction) -> u32 {
+        u32::from_le_bytes(instruction.data[..4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for name in VoteInstructionVariant::NAMES {
+            let variant = name.parse::<VoteInstructionVariant>().unwrap();
+            assert_eq!(variant.name(), *name);
+        }
+        assert!("legacy".parse::<VoteInstructionVariant>().is_err());
+        // serialized under the same names
+        for variant in VoteInstructionVariant::NAMES
+            .iter()
+            .map(|name| name.parse::<VoteInstructionVariant>().unwrap())
+        {
+            assert_eq!(
+                serde_json::to_string(&variant).unwrap(),
+                format!("\"{variant}\"")
+            );
+        }
+        assert_eq!(
+            VoteInstructionVariant::default(),
+            VoteInstructionVariant::Vote
+        );
+    }
+
+    #[test]
+    fn test_instruction_discriminant() {
+        let authority = Pubkey::new_unique();
+        for (variant, expected_discriminant) in [
+            (VoteInstructionVariant::Vote, 2),
+            (VoteInstructionVariant::UpdateVoteState, 8),
+            (VoteInstructionVariant::CompactUpdateVoteState, 12),
+            (VoteInstructionVariant::TowerSync, 14),
+        ] {
+            let instruction =
+                variant.instruction(&authority, 42, Hash::new_unique(), Hash::new_unique());
+            assert_eq!(instruction.program_id, solana_vote_program::id());
+            assert_eq!(discriminant(&instruction), expected_discriminant);
+
+            let decoded: VoteInstruction = bincode::deserialize(&instruction.data).unwrap();
+            let matches = match variant {
+                VoteInstructionVariant::Vote => matches!(decoded, VoteInstruction::Vote(_)),
+                VoteInstructionVariant::UpdateVoteState => {
+                    matches!(decoded, VoteInstruction::UpdateVoteState(_))
+                }
+                VoteInstructionVariant::CompactUpdateVoteState => {
+                    matches!(decoded, VoteInstruction::CompactUpdateVoteState(_))
+                }
+                VoteInstructionVariant::TowerSync => {
+                    matches!(decoded, VoteInstruction::TowerSync(_))
+                }
+            };
+            assert!(matches, "{variant} decoded as {decoded:?}");
+            assert_eq!(decoded.last_voted_slot(), Some(42));
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Workload of the producers, reproducible from a seed.
+//!
+//! Every input of the transactions of a producer, its fee payer, the vote
+//! hashes and block ids and the recent blockhashes, is drawn from a ChaCha
+//! stream of its own, keyed by the seed of the run and the stream id of the
+//! producer. The same seed and stream ids give the same serialized
+//! transactions on any host.
+//!
+//! `--dump-workload` writes the seed, stream ids and parameters of a run to a
+//! manifest, which `--replay-workload` reads to send the same transactions
+//! again. Each producer chains the hashes of the transactions it serialized,
+//! so that two runs can be compared from the hashes they print.
+
+use {
+    crate::{preset::WorkloadParams, vote_instruction_variant::VoteInstructionVariant},
+    rand::{Rng, SeedableRng},
+    rand_chacha::ChaCha8Rng,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        clock::Slot,
+        hash::{hashv, Hash},
+        message::Message,
+        signature::{keypair_from_seed, Keypair},
+        signer::Signer,
+        transaction::Transaction,
+    },
+    std::{
+        fs::{self, File},
+        io,
+        path::Path,
+    },
+};
+
+/// Version of the manifest format, bumped on incompatible changes
+pub const WORKLOAD_MANIFEST_VERSION: u32 = 1;
+
+/// Everything needed to regenerate the transactions of a run
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadManifest {
+    pub version: u32,
+    /// Version of bench-vote that wrote the manifest
+    pub tool_version: String,
+    pub seed: u64,
+    /// Stream id of each producer
+    pub streams: Vec<u64>,
+    pub parameters: WorkloadParams,
+    pub vote_instruction: VoteInstructionVariant,
+}
+
+impl WorkloadManifest {
+    pub fn new(
+        seed: u64,
+        parameters: WorkloadParams,
+        vote_instruction: VoteInstructionVariant,
+    ) -> Self {
+        Self {
+            version: WORKLOAD_MANIFEST_VERSION,
+            tool_version: solana_version::version!().to_string(),
+            seed,
+            streams: (0..parameters.num_producers).collect(),
+            parameters,
+            vote_instruction,
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self).map_err(io::Error::from)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        let manifest: Self = serde_json::from_str(&contents)
+            .map_err(|err| format!("invalid workload manifest {}: {err}", path.display()))?;
+        if manifest.version != WORKLOAD_MANIFEST_VERSION {
+            return Err(format!(
+                "unsupported version {} of workload manifest {}, expected {}",
+                manifest.version,
+                path.display(),
+                WORKLOAD_MANIFEST_VERSION
+            ));
+        }
+        if manifest.streams.len() as u64 != manifest.parameters.num_producers {
+            return Err(format!(
+                "workload manifest {} has {} streams for {} producers",
+                path.display(),
+                manifest.streams.len(),
+                manifest.parameters.num_producers
+            ));
+        }
+        Ok(manifest)
+    }
+}
+
+/// Running hash of a sequence of serialized transactions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HashChain(Hash);
+
+impl HashChain {
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.0 = hashv(&[self.0.as_ref(), bytes]);
+    }
+
+    pub fn hash(&self) -> Hash {
+        self.0
+    }
+}
+
+/// Transactions of a producer, drawn from its stream
+pub struct WorkloadStream {
+    rng: ChaCha8Rng,
+    identity_keypair: Keypair,
+    vote_instruction: VoteInstructionVariant,
+    hash_chain: HashChain,
+}
+
+impl WorkloadStream {
+    pub fn new(seed: u64, stream: u64, vote_instruction: VoteInstructionVariant) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(stream);
+        let identity_keypair = keypair_from_seed(&rng.gen::<[u8; 32]>()).unwrap();
+        Self {
+            rng,
+            identity_keypair,
+            vote_instruction,
+            hash_chain: HashChain::default(),
+        }
+    }
+
+    /// The next vote transaction for `slot`, serialized
+    pub fn next_transaction(&mut self, slot: Slot) -> Vec<u8> {
+        let mut serialized_transaction = vec![];
+        self.next_transaction_into(slot, &mut serialized_transaction);
+        serialized_transaction
+    }
+
+    /// Serialize the next vote transaction for `slot` into the cleared
+    /// `buffer`, reusing its allocation
+    pub fn next_transaction_into(&mut self, slot: Slot, buffer: &mut Vec<u8>) {
+        let authority = self.identity_keypair.pubkey();
+        let vote_instruction = self.vote_instruction.instruction(
+            &authority,
+            slot,
+            Hash::new_from_array(self.rng.gen()),
+            Hash::new_from_array(self.rng.gen()),
+        );
+        let message = Message::new(&[vote_instruction], Some(&authority));
+        let recent_blockhash = Hash::new_from_array(self.rng.gen());
+        let transaction = Transaction::new(&[&self.identity_keypair], message, recent_blockhash);
+        buffer.clear();
+        bincode::serialize_into(&mut *buffer, &transaction).unwrap();
+        self.hash_chain.push(buffer);
+    }
+
+    /// Hash chain of the transactions drawn so far
+    pub fn hash(&self) -> Hash {
+        self.hash_chain.hash()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(seed: u64) -> WorkloadManifest {
+        WorkloadManifest::new(
+            seed,
+            WorkloadParams {
+                num_producers: 4,
+                transactions_per_producer: 20,
+                ..WorkloadParams::default()
+            },
+            VoteInstructionVariant::TowerSync,
+        )
+    }
+
+    /// The hash of each producer of a run of `manifest`
+    fn run(manifest: &WorkloadManifest) -> Vec<Hash> {
+        manifest
+            .streams
+            .iter()
+            .map(|stream| {
+                let mut workload_stream =
+                    WorkloadStream::new(manifest.seed, *stream, manifest.vote_instruction);
+                for _ in 0..manifest.parameters.transactions_per_producer {
+                    workload_stream.next_transaction(0);
+                }
+                workload_stream.hash()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hash_chain() {
+        let mut hash_chain = HashChain::default();
+        assert_eq!(hash_chain.hash(), Hash::default());
+        hash_chain.push(b"first");
+        hash_chain.push(b"second");
+        assert_eq!(
+            hash_chain.hash(),
+            hashv(&[
+                hashv(&[Hash::default().as_ref(), b"first"]).as_ref(),
+                b"second"
+            ])
+        );
+
+        // the order of the transactions matters
+        let mut swapped = HashChain::default();
+        swapped.push(b"second");
+        swapped.push(b"first");
+        assert_ne!(swapped, hash_chain);
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let manifest = manifest(42);
+        assert_eq!(manifest.streams, [0, 1, 2, 3]);
+        let file = tempfile::NamedTempFile::new().unwrap();
+        manifest.write(file.path()).unwrap();
+        assert_eq!(WorkloadManifest::read(file.path()).unwrap(), manifest);
+
+        let unsupported = WorkloadManifest {
+            version: WORKLOAD_MANIFEST_VERSION + 1,
+            ..manifest.clone()
+        };
+        unsupported.write(file.path()).unwrap();
+        assert!(WorkloadManifest::read(file.path())
+            .unwrap_err()
+            .contains("unsupported version"));
+
+        let missing_stream = WorkloadManifest {
+            streams: vec![0, 1, 2],
+            ..manifest
+        };
+        missing_stream.write(file.path()).unwrap();
+        assert!(WorkloadManifest::read(file.path())
+            .unwrap_err()
+            .contains("3 streams for 4 producers"));
+    }
+
+    #[test]
+    fn test_replay_from_manifest() {
+        let manifest = manifest(42);
+        let hashes = run(&manifest);
+        assert_eq!(run(&manifest), hashes);
+
+        // each producer sends a stream of its own
+        for (i, hash) in hashes.iter().enumerate() {
+            assert!(!hashes[i + 1..].contains(hash));
+        }
+        assert_ne!(run(&self::manifest(43)), hashes);
+
+        // the same transactions, not only the same hash
+        let mut first = WorkloadStream::new(42, 1, VoteInstructionVariant::Vote);
+        let mut second = WorkloadStream::new(42, 1, VoteInstructionVariant::Vote);
+        for slot in 0..10 {
+            assert_eq!(first.next_transaction(slot), second.next_transaction(slot));
+        }
+    }
+}
@@ -0,0 +1,72 @@
+#![feature(test)]
+
+extern crate test;
+
+use {
+    solana_core::repair::repair_response::{
+        decompress_repair_response, repair_response_packet_compressed,
+        repair_response_packet_from_bytes,
+    },
+    solana_ledger::shred::{Shred, ShredFlags},
+    solana_sdk::signature::Keypair,
+    std::net::{IpAddr, Ipv4Addr, SocketAddr},
+    test::{black_box, Bencher},
+};
+
+// Compressing repair responses trades serve-side CPU for bandwidth. Shreds
+// of real entries are close to incompressible, so both a padded shred and
+// one of random data are measured.
+
+const NONCE: u32 = 9;
+
+fn shred_payload(data: &[u8]) -> Vec<u8> {
+    let mut shred = Shred::new_from_data(
+        0xdead_c0de,
+        0xc0de,
+        0xdead,
+        data,
+        ShredFlags::LAST_SHRED_IN_SLOT,
+        0,
+        0,
+        0xc0de,
+    );
+    shred.sign(&Keypair::new());
+    shred.into_payload().to_vec()
+}
+
+fn dest() -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080)
+}
+
+fn random_data() -> Vec<u8> {
+    (0..1000).map(|_| rand::random()).collect()
+}
+
+#[bench]
+fn bench_repair_response_packet(bencher: &mut Bencher) {
+    let payload = shred_payload(&[1u8; 1000]);
+    let dest = dest();
+    bencher.iter(|| repair_response_packet_from_bytes(black_box(&payload), &dest, NONCE));
+}
+
+#[bench]
+fn bench_repair_response_packet_compressed(bencher: &mut Bencher) {
+    let payload = shred_payload(&[1u8; 1000]);
+    let dest = dest();
+    bencher.iter(|| repair_response_packet_compressed(black_box(&payload), &dest, NONCE));
+}
+
+#[bench]
+fn bench_repair_response_packet_compressed_random(bencher: &mut Bencher) {
+    let payload = shred_payload(&random_data());
+    let dest = dest();
+    bencher.iter(|| repair_response_packet_compressed(black_box(&payload), &dest, NONCE));
+}
+
+#[bench]
+fn bench_decompress_repair_response(bencher: &mut Bencher) {
+    let payload = shred_payload(&[1u8; 1000]);
+    let packet = repair_response_packet_compressed(&payload, &dest(), NONCE).unwrap();
+    let packet = packet.data(..).unwrap();
+    bencher.iter(|| decompress_repair_response(black_box(packet)));
+}
@@ -1,9 +1,12 @@
 use {
     crate::{
+        banking_stage::{EstimateRequestSender, SharedAdmissionConfig, SharedSchedulerState},
         cluster_slots_service::cluster_slots::ClusterSlots,
         repair::{outstanding_requests::OutstandingRequests, serve_repair::ShredRepairType},
+        replay_stage::SharedVotingIdentity,
     },
     solana_gossip::cluster_info::ClusterInfo,
+    solana_ledger::blockstore::Blockstore,
     solana_runtime::bank_forks::BankForks,
     solana_sdk::{pubkey::Pubkey, quic::NotifyKeyUpdate},
     std::{
@@ -23,4 +26,9 @@ pub struct AdminRpcRequestMetadataPostInit {
     pub repair_socket: Arc<UdpSocket>,
     pub outstanding_repair_requests: Arc<RwLock<OutstandingRequests<ShredRepairType>>>,
     pub cluster_slots: Arc<ClusterSlots>,
+    pub blockstore: Arc<Blockstore>,
+    pub scheduler_state: SharedSchedulerState,
+    pub estimate_request_sender: EstimateRequestSender,
+    pub admission_config: SharedAdmissionConfig,
+    pub voting_identity: SharedVotingIdentity,
 }
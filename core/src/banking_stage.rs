@@ -4,6 +4,13 @@
 
 #[cfg(feature = "dev-context-only-utils")]
 use qualifier_attr::qualifiers;
+pub use transaction_scheduler::{
+    in_flight_tracker::ThreadLoad,
+    scheduler::SchedulingSummary,
+    scheduler_state::{SchedulerStateSnapshot, SharedSchedulerState},
+    scheduling_estimate::{EstimateRequest, EstimateRequestSender, SchedulingEstimate},
+    transaction_state_container::{AdmissionConfig, FloorAction, SharedAdmissionConfig},
+};
 use {
     self::{
         committer::Committer,
@@ -55,6 +62,7 @@ use {
         receive_and_buffer::{
             ReceiveAndBuffer, SanitizedTransactionReceiveAndBuffer, TransactionViewReceiveAndBuffer,
         },
+        scheduling_estimate::EstimateRequestReceiver,
         transaction_state_container::TransactionStateContainer,
     },
 };
@@ -78,7 +86,7 @@ mod packet_filter;
 mod packet_receiver;
 mod read_write_account_set;
 mod scheduler_messages;
-mod transaction_scheduler;
+pub mod transaction_scheduler;
 
 // proc_macro_hygiene needs to be stabilzied to use qualifier_attr...
 // error[E0658]: non-inline modules in proc macro input are unstable
@@ -300,6 +308,9 @@ pub struct BatchedTransactionErrorDetails {
 /// Stores the stage's thread handle and output receiver.
 pub struct BankingStage {
     bank_thread_hdls: Vec<JoinHandle<()>>,
+    scheduler_state: SharedSchedulerState,
+    admission_config: SharedAdmissionConfig,
+    estimate_request_sender: EstimateRequestSender,
 }
 
 pub trait LikeClusterInfo: Send + Sync + 'static + Clone {
@@ -320,6 +331,8 @@ impl LikeClusterInfo for Arc<ClusterInfo> {
 
 impl BankingStage {
     /// Create the stage using `bank`. Exit when `verified_receiver` is dropped.
+    /// Transactions not scheduled within `transaction_ttl` of being received
+    /// are dropped.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         block_production_method: BlockProductionMethod,
@@ -334,6 +347,7 @@ impl BankingStage {
         log_messages_bytes_limit: Option<usize>,
         bank_forks: Arc<RwLock<BankForks>>,
         prioritization_fee_cache: &Arc<PrioritizationFeeCache>,
+        transaction_ttl: Option<Duration>,
     ) -> Self {
         Self::new_num_threads(
             block_production_method,
@@ -349,6 +363,7 @@ impl BankingStage {
             log_messages_bytes_limit,
             bank_forks,
             prioritization_fee_cache,
+            transaction_ttl,
         )
     }
 
@@ -367,6 +382,7 @@ impl BankingStage {
         log_messages_bytes_limit: Option<usize>,
         bank_forks: Arc<RwLock<BankForks>>,
         prioritization_fee_cache: &Arc<PrioritizationFeeCache>,
+        transaction_ttl: Option<Duration>,
     ) -> Self {
         match block_production_method {
             BlockProductionMethod::CentralScheduler
@@ -389,6 +405,7 @@ impl BankingStage {
                     log_messages_bytes_limit,
                     bank_forks,
                     prioritization_fee_cache,
+                    transaction_ttl,
                 )
             }
         }
@@ -409,6 +426,7 @@ impl BankingStage {
         log_messages_bytes_limit: Option<usize>,
         bank_forks: Arc<RwLock<BankForks>>,
         prioritization_fee_cache: &Arc<PrioritizationFeeCache>,
+        transaction_ttl: Option<Duration>,
     ) -> Self {
         assert!(num_threads >= MIN_TOTAL_THREADS);
         // Keeps track of extraneous vote transactions for the vote threads
@@ -427,6 +445,9 @@ impl BankingStage {
 
         // + 1 for the central scheduler thread
         let mut bank_thread_hdls = Vec::with_capacity(num_threads as usize + 1);
+        let scheduler_state = SharedSchedulerState::default();
+        let admission_config = SharedAdmissionConfig::default();
+        let (estimate_request_sender, estimate_request_receiver) = unbounded();
 
         // Spawn legacy voting threads first: 1 gossip, 1 tpu
         for (id, packet_receiver, vote_source) in [
@@ -453,7 +474,8 @@ impl BankingStage {
                 let receive_and_buffer = SanitizedTransactionReceiveAndBuffer::new(
                     PacketDeserializer::new(non_vote_receiver),
                     bank_forks.clone(),
-                );
+                )
+                .with_transaction_ttl(transaction_ttl);
                 Self::spawn_scheduler_and_workers(
                     &mut bank_thread_hdls,
                     receive_and_buffer,
@@ -464,12 +486,16 @@ impl BankingStage {
                     num_threads,
                     log_messages_bytes_limit,
                     bank_forks,
+                    scheduler_state.clone(),
+                    admission_config.clone(),
+                    estimate_request_receiver,
                 );
             }
             TransactionStructure::View => {
                 let receive_and_buffer = TransactionViewReceiveAndBuffer {
                     receiver: non_vote_receiver,
                     bank_forks: bank_forks.clone(),
+                    transaction_ttl,
                 };
                 Self::spawn_scheduler_and_workers(
                     &mut bank_thread_hdls,
@@ -481,11 +507,37 @@ impl BankingStage {
                     num_threads,
                     log_messages_bytes_limit,
                     bank_forks,
+                    scheduler_state.clone(),
+                    admission_config.clone(),
+                    estimate_request_receiver,
                 );
             }
         }
 
-        Self { bank_thread_hdls }
+        Self {
+            bank_thread_hdls,
+            scheduler_state,
+            admission_config,
+            estimate_request_sender,
+        }
+    }
+
+    /// Latest snapshot of the central scheduler state, updated by the
+    /// scheduler thread.
+    pub fn scheduler_state(&self) -> SharedSchedulerState {
+        self.scheduler_state.clone()
+    }
+
+    /// Admission floor of the central scheduler container, applied by the
+    /// scheduler thread once updated.
+    pub fn admission_config(&self) -> SharedAdmissionConfig {
+        self.admission_config.clone()
+    }
+
+    /// Channel the central scheduler thread answers scheduling estimate
+    /// requests on, between scheduling passes.
+    pub fn estimate_request_sender(&self) -> EstimateRequestSender {
+        self.estimate_request_sender.clone()
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -499,6 +551,9 @@ impl BankingStage {
         num_threads: u32,
         log_messages_bytes_limit: Option<usize>,
         bank_forks: Arc<RwLock<BankForks>>,
+        scheduler_state: SharedSchedulerState,
+        admission_config: SharedAdmissionConfig,
+        estimate_request_receiver: EstimateRequestReceiver,
     ) {
         // Create channels for communication between scheduler and workers
         let num_workers = (num_threads).saturating_sub(NUM_VOTE_PROCESSING_THREADS);
@@ -549,7 +604,10 @@ impl BankingStage {
                                 bank_forks,
                                 $scheduler,
                                 worker_metrics,
-                            );
+                            )
+                            .with_scheduler_state(scheduler_state)
+                            .with_admission_config(admission_config)
+                            .with_estimate_request_receiver(estimate_request_receiver);
 
                             match scheduler_controller.run() {
                                 Ok(_) => {}
@@ -841,6 +899,7 @@ mod tests {
             None,
             bank_forks,
             &Arc::new(PrioritizationFeeCache::new(0u64)),
+            None,
         );
         drop(non_vote_sender);
         drop(tpu_vote_sender);
@@ -898,6 +957,7 @@ mod tests {
             None,
             bank_forks,
             &Arc::new(PrioritizationFeeCache::new(0u64)),
+            None,
         );
         trace!("sending bank");
         drop(non_vote_sender);
@@ -981,6 +1041,7 @@ mod tests {
             None,
             bank_forks.clone(), // keep a local-copy of bank-forks so worker threads do not lose weak access to bank-forks
             &Arc::new(PrioritizationFeeCache::new(0u64)),
+            None,
         );
 
         // fund another account so we can send 2 good transactions in a single batch.
@@ -1151,6 +1212,7 @@ mod tests {
                 None,
                 bank_forks,
                 &Arc::new(PrioritizationFeeCache::new(0u64)),
+                None,
             );
 
             // wait for banking_stage to eat the packets
@@ -1339,6 +1401,7 @@ mod tests {
             None,
             bank_forks,
             &Arc::new(PrioritizationFeeCache::new(0u64)),
+            None,
         );
 
         let keypairs = (0..100).map(|_| Keypair::new()).collect_vec();
@@ -1,10 +1,12 @@
 use {
     super::{
+        committer::CommitTransactionDetails,
         consumer::{Consumer, ExecuteAndCommitTransactionsOutput, ProcessTransactionBatchOutput},
         leader_slot_timing_metrics::LeaderExecuteAndCommitTimings,
         scheduler_messages::{ConsumeWork, FinishedConsumeWork},
     },
     crossbeam_channel::{Receiver, RecvError, SendError, Sender},
+    solana_cost_model::cost_model::CostModel,
     solana_measure::measure_us,
     solana_poh::leader_bank_notifier::LeaderBankNotifier,
     solana_runtime::bank::Bank,
@@ -121,11 +123,23 @@ impl<Tx: TransactionWithMeta> ConsumeWorker<Tx> {
         self.metrics.update_for_consume(&output);
         self.metrics.has_data.store(true, Ordering::Relaxed);
 
+        let ExecuteAndCommitTransactionsOutput {
+            retryable_transaction_indexes,
+            retryable_transaction_reasons,
+            commit_transactions_result,
+            ..
+        } = output.execute_and_commit_transactions_output;
+        let actual_cus_used = commit_transactions_result
+            .iter()
+            .flat_map(|details| work.transactions.iter().zip(details))
+            .map(|(transaction, details)| actual_cost(bank, transaction, details))
+            .sum();
         self.consumed_sender.send(FinishedConsumeWork {
             work,
-            retryable_indexes: output
-                .execute_and_commit_transactions_output
-                .retryable_transaction_indexes,
+            retryable_indexes: retryable_transaction_indexes,
+            retryable_reasons: retryable_transaction_reasons,
+            retry_all: false,
+            actual_cus_used,
         })?;
         Ok(())
     }
@@ -152,8 +166,7 @@ impl<Tx: TransactionWithMeta> ConsumeWorker<Tx> {
 
     /// Send transactions back to scheduler as retryable.
     fn retry(&self, work: ConsumeWork<Tx>) -> Result<(), ConsumeWorkerError<Tx>> {
-        let retryable_indexes: Vec<_> = (0..work.transactions.len()).collect();
-        let num_retryable = retryable_indexes.len();
+        let num_retryable = work.transactions.len();
         self.metrics
             .count_metrics
             .retryable_transaction_count
@@ -165,7 +178,10 @@ impl<Tx: TransactionWithMeta> ConsumeWorker<Tx> {
         self.metrics.has_data.store(true, Ordering::Relaxed);
         self.consumed_sender.send(FinishedConsumeWork {
             work,
-            retryable_indexes,
+            retryable_indexes: vec![],
+            retryable_reasons: vec![],
+            retry_all: true,
+            actual_cus_used: 0,
         })?;
         Ok(())
     }
@@ -177,6 +193,34 @@ fn try_drain_iter<T>(work: T, receiver: &Receiver<T>) -> impl Iterator<Item = T>
     std::iter::once(work).chain(receiver.try_iter())
 }
 
+/// Cost of `transaction` as it was committed, comparable to the cost the
+/// scheduler reserved for it: the estimated cost with the execution and
+/// loaded accounts data size costs replaced by the actual ones, as the cost
+/// tracker does. Transactions that were not committed cost nothing.
+fn actual_cost(
+    bank: &Bank,
+    transaction: &impl TransactionWithMeta,
+    details: &CommitTransactionDetails,
+) -> u64 {
+    let CommitTransactionDetails::Committed {
+        compute_units,
+        loaded_accounts_data_size,
+    } = details
+    else {
+        return 0;
+    };
+    let estimated_cost = CostModel::calculate_cost(transaction, &bank.feature_set);
+    estimated_cost
+        .sum()
+        .saturating_sub(estimated_cost.programs_execution_cost())
+        .saturating_sub(estimated_cost.loaded_accounts_data_size_cost())
+        .saturating_add(*compute_units)
+        .saturating_add(CostModel::calculate_loaded_accounts_data_size_cost(
+            *loaded_accounts_data_size,
+            &bank.feature_set,
+        ))
+}
+
 /// Metrics tracking number of packets processed by the consume worker.
 /// These are atomic, and intended to be reported by the scheduling thread
 /// since the consume worker thread is sleeping unless there is work to be
@@ -737,7 +781,7 @@ mod tests {
         crate::banking_stage::{
             committer::Committer,
             qos_service::QosService,
-            scheduler_messages::{MaxAge, TransactionBatchId},
+            scheduler_messages::{MaxAge, RetryReason, TransactionBatchId},
             tests::{create_slow_genesis_config, sanitize_transactions, simulate_poh},
         },
         crossbeam_channel::unbounded,
@@ -903,7 +947,9 @@ mod tests {
         assert_eq!(consumed.work.batch_id, bid);
         assert_eq!(consumed.work.ids, vec![id]);
         assert_eq!(consumed.work.max_ages, vec![max_age]);
-        assert_eq!(consumed.retryable_indexes, vec![0]);
+        assert!(consumed.retry_all);
+        assert_eq!(consumed.actual_cus_used, 0);
+        assert_eq!(consumed.retryable_indexes, Vec::<usize>::new());
 
         drop(test_frame);
         let _ = worker_thread.join().unwrap();
@@ -947,12 +993,20 @@ mod tests {
             transactions,
             max_ages: vec![max_age],
         };
+        let estimated_cost = CostModel::calculate_cost(&work.transactions[0], &bank.feature_set);
+        let fixed_cost = estimated_cost.sum()
+            - estimated_cost.programs_execution_cost()
+            - estimated_cost.loaded_accounts_data_size_cost();
         consume_sender.send(work).unwrap();
         let consumed = consumed_receiver.recv().unwrap();
         assert_eq!(consumed.work.batch_id, bid);
         assert_eq!(consumed.work.ids, vec![id]);
         assert_eq!(consumed.work.max_ages, vec![max_age]);
         assert_eq!(consumed.retryable_indexes, Vec::<usize>::new());
+        // the committed transfer costs its signature and write locks, as
+        // reserved, along with the CUs it consumed
+        assert!(consumed.actual_cus_used > fixed_cost);
+        assert!(consumed.actual_cus_used <= estimated_cost.sum());
 
         drop(test_frame);
         let _ = worker_thread.join().unwrap();
@@ -1005,6 +1059,7 @@ mod tests {
         assert_eq!(consumed.work.ids, vec![id1, id2]);
         assert_eq!(consumed.work.max_ages, vec![max_age, max_age]);
         assert_eq!(consumed.retryable_indexes, vec![1]); // id2 is retryable since lock conflict
+        assert_eq!(consumed.retryable_reasons, vec![RetryReason::AccountInUse]);
 
         drop(test_frame);
         let _ = worker_thread.join().unwrap();
@@ -7,7 +7,7 @@ use {
         },
         leader_slot_timing_metrics::LeaderExecuteAndCommitTimings,
         qos_service::QosService,
-        scheduler_messages::MaxAge,
+        scheduler_messages::{MaxAge, RetryReason},
         unprocessed_transaction_storage::{ConsumeScannerPayload, UnprocessedTransactionStorage},
         BankingStageStats,
     },
@@ -65,6 +65,8 @@ pub struct ExecuteAndCommitTransactionsOutput {
     // Transactions that either were not executed, or were executed and failed to be committed due
     // to the block ending.
     pub(crate) retryable_transaction_indexes: Vec<usize>,
+    // `RetryReason` for each entry of `retryable_transaction_indexes`.
+    pub(crate) retryable_transaction_reasons: Vec<RetryReason>,
     // A result that indicates whether transactions were successfully
     // committed into the Poh stream.
     pub commit_transactions_result: Result<Vec<CommitTransactionDetails>, PohRecorderError>,
@@ -585,7 +587,10 @@ impl Consumer {
             min_max.into_option().unwrap_or_default();
 
         let mut error_counters = TransactionErrorMetrics::default();
-        let mut retryable_transaction_indexes: Vec<_> = batch
+        let (mut retryable_transaction_indexes, mut retryable_transaction_reasons): (
+            Vec<_>,
+            Vec<_>,
+        ) = batch
             .lock_results()
             .iter()
             .enumerate()
@@ -593,23 +598,23 @@ impl Consumer {
                 // following are retryable errors
                 Err(TransactionError::AccountInUse) => {
                     error_counters.account_in_use += 1;
-                    Some(index)
+                    Some((index, RetryReason::AccountInUse))
                 }
                 Err(TransactionError::WouldExceedMaxBlockCostLimit) => {
                     error_counters.would_exceed_max_block_cost_limit += 1;
-                    Some(index)
+                    Some((index, RetryReason::BlockLimit))
                 }
                 Err(TransactionError::WouldExceedMaxVoteCostLimit) => {
                     error_counters.would_exceed_max_vote_cost_limit += 1;
-                    Some(index)
+                    Some((index, RetryReason::BlockLimit))
                 }
                 Err(TransactionError::WouldExceedMaxAccountCostLimit) => {
                     error_counters.would_exceed_max_account_cost_limit += 1;
-                    Some(index)
+                    Some((index, RetryReason::BlockLimit))
                 }
                 Err(TransactionError::WouldExceedAccountDataBlockLimit) => {
                     error_counters.would_exceed_account_data_block_limit += 1;
-                    Some(index)
+                    Some((index, RetryReason::BlockLimit))
                 }
                 // following are non-retryable errors
                 Err(TransactionError::TooManyAccountLocks) => {
@@ -619,7 +624,7 @@ impl Consumer {
                 Err(_) => None,
                 Ok(_) => None,
             })
-            .collect();
+            .unzip();
 
         let (load_and_execute_transactions_output, load_execute_us) = measure_us!(bank
             .load_and_execute_transactions(
@@ -690,10 +695,13 @@ impl Consumer {
             retryable_transaction_indexes.extend(processing_results.iter().enumerate().filter_map(
                 |(index, processing_result)| processing_result.was_processed().then_some(index),
             ));
+            retryable_transaction_reasons
+                .resize(retryable_transaction_indexes.len(), RetryReason::Other);
 
             return ExecuteAndCommitTransactionsOutput {
                 transaction_counts,
                 retryable_transaction_indexes,
+                retryable_transaction_reasons,
                 commit_transactions_result: Err(recorder_err),
                 execute_and_commit_timings,
                 error_counters,
@@ -744,6 +752,7 @@ impl Consumer {
         ExecuteAndCommitTransactionsOutput {
             transaction_counts,
             retryable_transaction_indexes,
+            retryable_transaction_reasons,
             commit_transactions_result: Ok(commit_transaction_statuses),
             execute_and_commit_timings,
             error_counters,
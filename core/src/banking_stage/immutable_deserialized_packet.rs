@@ -21,7 +21,7 @@ use {
     solana_svm_transaction::{
         instruction::SVMInstruction, message_address_table_lookup::SVMMessageAddressTableLookup,
     },
-    std::{cmp::Ordering, collections::HashSet, mem::size_of},
+    std::{cmp::Ordering, collections::HashSet, mem::size_of, time::Instant},
     thiserror::Error,
 };
 
@@ -58,6 +58,9 @@ pub struct ImmutableDeserializedPacket {
     is_simple_vote: bool,
     compute_unit_price: u64,
     compute_unit_limit: u32,
+    /// Instant after which the transaction is worthless and should be dropped
+    /// rather than scheduled
+    deadline: Option<Instant>,
 }
 
 impl ImmutableDeserializedPacket {
@@ -94,9 +97,16 @@ impl ImmutableDeserializedPacket {
             is_simple_vote,
             compute_unit_price,
             compute_unit_limit,
+            deadline: None,
         })
     }
 
+    /// Attach a deadline, for sources whose transactions are time-sensitive
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
     pub fn original_packet(&self) -> &Packet {
         &self.original_packet
     }
@@ -121,6 +131,10 @@ impl ImmutableDeserializedPacket {
         u64::from(self.compute_unit_limit)
     }
 
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
     // This function deserializes packets into transactions, computes the blake3 hash of transaction
     // messages.
     // Additionally, this returns the minimum deactivation slot of the resolved addresses.
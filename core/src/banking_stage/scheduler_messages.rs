@@ -1,5 +1,5 @@
 use {
-    solana_sdk::clock::{Epoch, Slot},
+    solana_sdk::clock::{Epoch, Slot, MAX_PROCESSING_AGE},
     std::fmt::Display,
 };
 
@@ -32,6 +32,21 @@ impl MaxAge {
         sanitized_epoch: Epoch::MAX,
         alt_invalidation_slot: Slot::MAX,
     };
+
+    /// Whether the transaction can no longer be processed at `current_slot`,
+    /// with a recent blockhash `current_blockhash_age` blockhashes old:
+    /// either its blockhash is past `MAX_PROCESSING_AGE`, or its resolved
+    /// address lookups are past their guaranteed validity.
+    pub fn is_expired(&self, current_slot: Slot, current_blockhash_age: u64) -> bool {
+        current_blockhash_age > MAX_PROCESSING_AGE as u64
+            || self.slots_remaining(current_slot).is_none()
+    }
+
+    /// Number of slots after `current_slot` for which the resolved address
+    /// lookups are guaranteed valid, or `None` once past them.
+    pub fn slots_remaining(&self, current_slot: Slot) -> Option<u64> {
+        self.alt_invalidation_slot.checked_sub(current_slot)
+    }
 }
 
 /// Message: [Scheduler -> Worker]
@@ -43,9 +58,63 @@ pub struct ConsumeWork<Tx> {
     pub max_ages: Vec<MaxAge>,
 }
 
+/// Why a transaction was returned to the scheduler as retryable.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RetryReason {
+    /// An account was locked by another batch; the transaction can be
+    /// retried as soon as the conflicting batch completes.
+    AccountInUse,
+    /// The transaction would exceed a block-wide cost limit; it cannot be
+    /// included until the next slot.
+    BlockLimit,
+    /// Any other retryable failure, e.g. the bank ended before the
+    /// transaction could be recorded.
+    Other,
+}
+
 /// Message: [Worker -> Scheduler]
 /// Processed transactions.
 pub struct FinishedConsumeWork<Tx> {
     pub work: ConsumeWork<Tx>,
     pub retryable_indexes: Vec<usize>,
+    /// `RetryReason` for each entry of `retryable_indexes`.
+    pub retryable_reasons: Vec<RetryReason>,
+    /// The whole batch is retryable with `RetryReason::Other`, e.g. because
+    /// the bank ended before it was processed. `retryable_indexes` is then
+    /// left empty rather than listing every index.
+    pub retry_all: bool,
+    /// Cost of the committed transactions of the batch, with their actual
+    /// execution and loaded accounts data size costs, comparable to the CUs
+    /// the scheduler reserved for the batch.
+    pub actual_cus_used: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_age_expiry() {
+        let max_age = MaxAge {
+            sanitized_epoch: 10,
+            alt_invalidation_slot: 100,
+        };
+
+        // one under, exactly at, and one over the invalidation slot
+        assert_eq!(max_age.slots_remaining(99), Some(1));
+        assert!(!max_age.is_expired(99, 0));
+        assert_eq!(max_age.slots_remaining(100), Some(0));
+        assert!(!max_age.is_expired(100, 0));
+        assert_eq!(max_age.slots_remaining(101), None);
+        assert!(max_age.is_expired(101, 0));
+
+        // one under, exactly at, and one over the max blockhash age
+        let max_blockhash_age = MAX_PROCESSING_AGE as u64;
+        assert!(!max_age.is_expired(99, max_blockhash_age - 1));
+        assert!(!max_age.is_expired(99, max_blockhash_age));
+        assert!(max_age.is_expired(99, max_blockhash_age + 1));
+
+        assert_eq!(MaxAge::MAX.slots_remaining(Slot::MAX), Some(0));
+        assert!(!MaxAge::MAX.is_expired(Slot::MAX, 0));
+    }
 }
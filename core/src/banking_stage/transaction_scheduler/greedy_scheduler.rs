@@ -1,20 +1,31 @@
 use {
     super::{
-        in_flight_tracker::InFlightTracker,
+        in_flight_tracker::{InFlightTracker, ThreadLoad},
         prio_graph_scheduler::{
             Batches, PrioGraphScheduler, TransactionSchedulingError, TransactionSchedulingInfo,
         },
-        scheduler::{PreLockFilterAction, Scheduler, SchedulingSummary},
+        retry_tracker::{RetryTracker, DEFAULT_RETRY_THRESHOLD},
+        scheduler::{CompletedCus, PreLockFilterAction, RetryCounts, Scheduler, SchedulingSummary},
         scheduler_error::SchedulerError,
+        scheduler_state::SchedulerStateSnapshot,
+        scheduling_estimate::{
+            self, EstimateRequest, SchedulingEstimate, DEFAULT_MAX_ESTIMATE_EXAMINED_TRANSACTIONS,
+        },
+        stats::SchedulerConfig,
+        summary_history::SummaryHistory,
         thread_aware_account_locks::{ThreadAwareAccountLocks, ThreadId, ThreadSet, TryLockError},
         transaction_priority_id::TransactionPriorityId,
         transaction_state::{SanitizedTransactionTTL, TransactionState},
         transaction_state_container::StateContainer,
+        writable_account_mask::WritableAccountMask,
     },
     crate::banking_stage::{
         consumer::TARGET_NUM_TRANSACTIONS_PER_BATCH,
         read_write_account_set::ReadWriteAccountSet,
-        scheduler_messages::{ConsumeWork, FinishedConsumeWork, TransactionBatchId},
+        scheduler_messages::{
+            ConsumeWork, FinishedConsumeWork, MaxAge, RetryReason, TransactionBatchId,
+            TransactionId,
+        },
         transaction_scheduler::thread_aware_account_locks::MAX_THREADS,
     },
     crossbeam_channel::{Receiver, Sender, TryRecvError},
@@ -22,12 +33,26 @@ use {
     solana_cost_model::block_cost_limits::MAX_BLOCK_UNITS,
     solana_runtime_transaction::transaction_with_meta::TransactionWithMeta,
     solana_sdk::saturating_add_assign,
+    std::{
+        collections::{HashSet, VecDeque},
+        time::Instant,
+    },
 };
 
-pub(crate) struct GreedySchedulerConfig {
+/// Configuration of the `GreedyScheduler`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct GreedySchedulerConfig {
     pub target_scheduled_cus: u64,
     pub max_scanned_transactions_per_scheduling_pass: usize,
     pub target_transactions_per_batch: usize,
+    /// Transactions retried more than this many times are reported by
+    /// `transactions_over_retry_threshold()`.
+    pub retry_threshold: usize,
+    /// Number of recent scheduling summaries retained for `recent_summaries()`.
+    /// Zero disables retention.
+    pub summary_history_size: usize,
 }
 
 impl Default for GreedySchedulerConfig {
@@ -36,6 +61,8 @@ impl Default for GreedySchedulerConfig {
             target_scheduled_cus: MAX_BLOCK_UNITS / 4,
             max_scanned_transactions_per_scheduling_pass: 100_000,
             target_transactions_per_batch: TARGET_NUM_TRANSACTIONS_PER_BATCH,
+            retry_threshold: DEFAULT_RETRY_THRESHOLD,
+            summary_history_size: 0,
         }
     }
 }
@@ -48,6 +75,12 @@ pub struct GreedyScheduler<Tx: TransactionWithMeta> {
     account_locks: ThreadAwareAccountLocks,
     consume_work_senders: Vec<Sender<ConsumeWork<Tx>>>,
     finished_consume_work_receiver: Receiver<FinishedConsumeWork<Tx>>,
+    retry_tracker: RetryTracker,
+    summary_history: SummaryHistory,
+    /// Transactions held back until the next slot because they would exceed a block limit.
+    deferred: Vec<TransactionPriorityId>,
+    /// CUs of the batches completed since the last `take_completed_cus()`.
+    completed_cus: CompletedCus,
     working_account_set: ReadWriteAccountSet,
     unschedulables: Vec<TransactionPriorityId>,
     config: GreedySchedulerConfig,
@@ -70,6 +103,10 @@ impl<Tx: TransactionWithMeta> GreedyScheduler<Tx> {
             account_locks: ThreadAwareAccountLocks::new(num_threads),
             consume_work_senders,
             finished_consume_work_receiver,
+            retry_tracker: RetryTracker::new(config.retry_threshold),
+            summary_history: SummaryHistory::new(config.summary_history_size),
+            deferred: Vec::new(),
+            completed_cus: CompletedCus::default(),
             working_account_set: ReadWriteAccountSet::default(),
             unschedulables: Vec::with_capacity(config.max_scanned_transactions_per_scheduling_pass),
             config,
@@ -81,7 +118,7 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
     fn schedule<S: StateContainer<Tx>>(
         &mut self,
         container: &mut S,
-        _pre_graph_filter: impl Fn(&[&Tx], &mut [bool]),
+        _pre_graph_filter: impl Fn(&[&Tx], &[MaxAge], &mut [bool]),
         pre_lock_filter: impl Fn(&TransactionState<Tx>) -> PreLockFilterAction,
     ) -> Result<SchedulingSummary, SchedulerError> {
         let num_threads = self.consume_work_senders.len();
@@ -95,7 +132,7 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
             }
         }
         if schedulable_threads.is_empty() {
-            return Ok(SchedulingSummary::default());
+            return Ok(self.summary_history.record(SchedulingSummary::default()));
         }
 
         // Track metrics on filter.
@@ -103,6 +140,7 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
         let mut num_scheduled: usize = 0;
         let mut num_sent: usize = 0;
         let mut num_unschedulable: usize = 0;
+        let mut num_deadline_expired: usize = 0;
 
         let mut batches = Batches::new(num_threads, self.config.target_transactions_per_batch);
         while num_scanned < self.config.max_scanned_transactions_per_scheduling_pass
@@ -135,11 +173,13 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
             match try_schedule_transaction(
                 transaction_state,
                 &pre_lock_filter,
+                Instant::now,
                 &mut self.account_locks,
                 schedulable_threads,
                 |thread_set| {
                     PrioGraphScheduler::<Tx>::select_thread(
                         thread_set,
+                        None,
                         &batches.total_cus,
                         self.in_flight_tracker.cus_in_flight_per_thread(),
                         &batches.transactions,
@@ -152,11 +192,16 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
                     num_unschedulable += 1;
                     self.unschedulables.push(id);
                 }
+                Err(TransactionSchedulingError::DeadlineExpired) => {
+                    saturating_add_assign!(num_deadline_expired, 1);
+                    container.remove_by_id(id.id);
+                }
                 Ok(TransactionSchedulingInfo {
                     thread_id,
                     transaction,
                     max_age,
                     cost,
+                    writable_mask,
                 }) => {
                     assert!(
                         self.working_account_set.take_locks(&transaction),
@@ -166,6 +211,7 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
                     batches.transactions[thread_id].push(transaction);
                     batches.ids[thread_id].push(id.id);
                     batches.max_ages[thread_id].push(max_age);
+                    batches.writable_masks[thread_id].push(writable_mask);
                     saturating_add_assign!(batches.total_cus[thread_id], cost);
 
                     // If target batch size is reached, send all the batches
@@ -199,41 +245,101 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
         // Push unschedulables back into the queue
         container.push_ids_into_queue(self.unschedulables.drain(..));
 
-        Ok(SchedulingSummary {
+        Ok(self.summary_history.record(SchedulingSummary {
             num_scheduled,
             num_unschedulable,
             num_filtered_out: 0,
             filter_time_us: 0,
-        })
+            num_unschedulable_priority_inclusions: 0,
+            num_force_evictions: 0,
+            num_priority_inversions: 0,
+            num_deadline_expired,
+            graph_insertion_time_us: 0,
+        }))
     }
 
     /// Receive completed batches of transactions without blocking.
-    /// Returns (num_transactions, num_retryable_transactions) on success.
+    /// Returns (num_transactions, retry_counts) on success.
     fn receive_completed(
         &mut self,
         container: &mut impl StateContainer<Tx>,
-    ) -> Result<(usize, usize), SchedulerError> {
+    ) -> Result<(usize, RetryCounts), SchedulerError> {
         let mut total_num_transactions: usize = 0;
-        let mut total_num_retryable: usize = 0;
+        let mut total_retry_counts = RetryCounts::default();
         loop {
-            let (num_transactions, num_retryable) = self.try_receive_completed(container)?;
+            let (num_transactions, retry_counts) = self.try_receive_completed(container)?;
             if num_transactions == 0 {
                 break;
             }
             saturating_add_assign!(total_num_transactions, num_transactions);
-            saturating_add_assign!(total_num_retryable, num_retryable);
+            total_retry_counts.accumulate(&retry_counts);
         }
-        Ok((total_num_transactions, total_num_retryable))
+        // Forget transactions that have since been dropped from the container
+        self.retry_tracker
+            .retain(|id| container.get_mut_transaction_state(id).is_some());
+        Ok((total_num_transactions, total_retry_counts))
+    }
+
+    fn take_deferred(&mut self) -> Vec<TransactionPriorityId> {
+        std::mem::take(&mut self.deferred)
+    }
+
+    fn flush_held_batches(&mut self) -> Result<usize, SchedulerError> {
+        // batches are always sent by the pass scheduling them
+        Ok(0)
+    }
+
+    fn take_completed_cus(&mut self) -> CompletedCus {
+        std::mem::take(&mut self.completed_cus)
+    }
+
+    fn transactions_over_retry_threshold(&self) -> &HashSet<TransactionId> {
+        self.retry_tracker.over_threshold()
+    }
+
+    fn recent_summaries(&self) -> &VecDeque<SchedulingSummary> {
+        self.summary_history.summaries()
+    }
+
+    fn thread_loads(&self) -> Vec<ThreadLoad> {
+        self.in_flight_tracker.thread_loads()
+    }
+
+    fn state_snapshot(&self, container: &impl StateContainer<Tx>) -> SchedulerStateSnapshot {
+        SchedulerStateSnapshot::new(
+            container,
+            self.in_flight_tracker.thread_loads(),
+            self.account_locks.num_locked_accounts(),
+            self.summary_history.last(),
+        )
+    }
+
+    fn config(&self) -> SchedulerConfig {
+        SchedulerConfig::Greedy(self.config.clone())
+    }
+
+    fn estimate(
+        &self,
+        container: &impl StateContainer<Tx>,
+        request: &EstimateRequest,
+    ) -> SchedulingEstimate {
+        scheduling_estimate::estimate(
+            request,
+            &self.account_locks,
+            self.in_flight_tracker.cus_in_flight_per_thread(),
+            container,
+            DEFAULT_MAX_ESTIMATE_EXAMINED_TRANSACTIONS,
+        )
     }
 }
 
 impl<Tx: TransactionWithMeta> GreedyScheduler<Tx> {
     /// Receive completed batches of transactions.
-    /// Returns `Ok((num_transactions, num_retryable))` if a batch was received, `Ok((0, 0))` if no batch was received.
+    /// Returns `Ok((num_transactions, retry_counts))` if a batch was received, `Ok((0, RetryCounts::default()))` if no batch was received.
     fn try_receive_completed(
         &mut self,
         container: &mut impl StateContainer<Tx>,
-    ) -> Result<(usize, usize), SchedulerError> {
+    ) -> Result<(usize, RetryCounts), SchedulerError> {
         match self.finished_consume_work_receiver.try_recv() {
             Ok(FinishedConsumeWork {
                 work:
@@ -244,37 +350,60 @@ impl<Tx: TransactionWithMeta> GreedyScheduler<Tx> {
                         max_ages,
                     },
                 retryable_indexes,
+                retryable_reasons,
+                retry_all,
+                actual_cus_used,
             }) => {
                 let num_transactions = ids.len();
-                let num_retryable = retryable_indexes.len();
-
-                // Free the locks
-                self.complete_batch(batch_id, &transactions);
-
-                // Retryable transactions should be inserted back into the container
-                let mut retryable_iter = retryable_indexes.into_iter().peekable();
+                let mut retry_counts = RetryCounts::default();
+                debug_assert_eq!(retryable_indexes.len(), retryable_reasons.len());
+                debug_assert!(!retry_all || retryable_indexes.is_empty());
+
+                // Free the locks, and credit the CUs the batch did not use
+                let reserved_cus = self.complete_batch(batch_id, &transactions, actual_cus_used);
+                self.completed_cus.record(reserved_cus, actual_cus_used);
+
+                // Retryable transactions should be inserted back into the container,
+                // unless they hit a block limit, in which case they wait for the next slot
+                let mut retryable_iter = retryable_indexes
+                    .into_iter()
+                    .zip(retryable_reasons)
+                    .peekable();
                 for (index, (id, transaction, max_age)) in
                     izip!(ids, transactions, max_ages).enumerate()
                 {
-                    if let Some(retryable_index) = retryable_iter.peek() {
-                        if *retryable_index == index {
-                            container.retry_transaction(
-                                id,
-                                SanitizedTransactionTTL {
-                                    transaction,
-                                    max_age,
-                                },
-                            );
-                            retryable_iter.next();
-                            continue;
+                    let retry_reason = if retry_all {
+                        Some(RetryReason::Other)
+                    } else {
+                        retryable_iter
+                            .next_if(|(retryable_index, _)| *retryable_index == index)
+                            .map(|(_, reason)| reason)
+                    };
+                    if let Some(reason) = retry_reason {
+                        retry_counts.record(reason);
+                        self.retry_tracker
+                            .record_retry(id, transaction.message_hash());
+                        let transaction_ttl = SanitizedTransactionTTL {
+                            transaction,
+                            max_age,
+                        };
+                        match reason {
+                            RetryReason::BlockLimit => self
+                                .deferred
+                                .push(container.defer_transaction(id, transaction_ttl)),
+                            RetryReason::AccountInUse | RetryReason::Other => {
+                                container.retry_transaction(id, transaction_ttl)
+                            }
                         }
+                        continue;
                     }
-                    container.remove_by_id(id);
+                    self.retry_tracker.remove(id);
+                    container.remove_completed(id, transaction.signature());
                 }
 
-                Ok((num_transactions, num_retryable))
+                Ok((num_transactions, retry_counts))
             }
-            Err(TryRecvError::Empty) => Ok((0, 0)),
+            Err(TryRecvError::Empty) => Ok((0, RetryCounts::default())),
             Err(TryRecvError::Disconnected) => Err(SchedulerError::DisconnectedRecvChannel(
                 "finished consume work",
             )),
@@ -283,21 +412,29 @@ impl<Tx: TransactionWithMeta> GreedyScheduler<Tx> {
 
     /// Mark a given `TransactionBatchId` as completed.
     /// This will update the internal tracking, including account locks.
-    fn complete_batch(&mut self, batch_id: TransactionBatchId, transactions: &[Tx]) {
-        let thread_id = self.in_flight_tracker.complete_batch(batch_id);
-        for transaction in transactions {
+    /// The CUs the batch reserved and did not use are credited to the
+    /// batches still in flight on its thread.
+    /// Returns the CUs reserved by the batch.
+    fn complete_batch(
+        &mut self,
+        batch_id: TransactionBatchId,
+        transactions: &[Tx],
+        actual_cus_used: u64,
+    ) -> u64 {
+        let (thread_id, reserved_cus, writable_masks) = self
+            .in_flight_tracker
+            .complete_batch(batch_id, actual_cus_used);
+        for (transaction, writable_mask) in transactions.iter().zip(&writable_masks) {
             let account_keys = transaction.account_keys();
-            let write_account_locks = account_keys
-                .iter()
-                .enumerate()
-                .filter_map(|(index, key)| transaction.is_writable(index).then_some(key));
-            let read_account_locks = account_keys
-                .iter()
-                .enumerate()
-                .filter_map(|(index, key)| (!transaction.is_writable(index)).then_some(key));
-            self.account_locks
-                .unlock_accounts(write_account_locks, read_account_locks, thread_id);
+            self.account_locks.unlock_accounts(
+                writable_mask.write_keys(account_keys.iter()),
+                writable_mask.read_keys(account_keys.iter()),
+                thread_id,
+            );
         }
+        self.in_flight_tracker
+            .recycle_writable_masks(writable_masks);
+        reserved_cus
     }
 
     /// Send all batches of transactions to the worker threads.
@@ -319,12 +456,18 @@ impl<Tx: TransactionWithMeta> GreedyScheduler<Tx> {
             return Ok(0);
         }
 
-        let (ids, transactions, max_ages, total_cus) =
-            batches.take_batch(thread_index, self.config.target_transactions_per_batch);
-
-        let batch_id = self
+        let writable_masks_buffer = self
             .in_flight_tracker
-            .track_batch(ids.len(), total_cus, thread_index);
+            .take_writable_masks_buffer(self.config.target_transactions_per_batch);
+        let (ids, transactions, max_ages, writable_masks, total_cus) = batches.take_batch(
+            thread_index,
+            self.config.target_transactions_per_batch,
+            writable_masks_buffer,
+        );
+
+        let batch_id =
+            self.in_flight_tracker
+                .track_batch(ids.len(), total_cus, thread_index, writable_masks);
 
         let num_scheduled = ids.len();
         let work = ConsumeWork {
@@ -344,6 +487,7 @@ impl<Tx: TransactionWithMeta> GreedyScheduler<Tx> {
 fn try_schedule_transaction<Tx: TransactionWithMeta>(
     transaction_state: &mut TransactionState<Tx>,
     pre_lock_filter: impl Fn(&TransactionState<Tx>) -> PreLockFilterAction,
+    clock: impl Fn() -> Instant,
     account_locks: &mut ThreadAwareAccountLocks,
     schedulable_threads: ThreadSet,
     thread_selector: impl Fn(ThreadSet) -> ThreadId,
@@ -352,17 +496,16 @@ fn try_schedule_transaction<Tx: TransactionWithMeta>(
         PreLockFilterAction::AttemptToSchedule => {}
     }
 
+    if transaction_state.deadline().is_some() && transaction_state.is_deadline_expired(clock()) {
+        return Err(TransactionSchedulingError::DeadlineExpired);
+    }
+
     // Schedule the transaction if it can be.
     let transaction = &transaction_state.transaction_ttl().transaction;
     let account_keys = transaction.account_keys();
-    let write_account_locks = account_keys
-        .iter()
-        .enumerate()
-        .filter_map(|(index, key)| transaction.is_writable(index).then_some(key));
-    let read_account_locks = account_keys
-        .iter()
-        .enumerate()
-        .filter_map(|(index, key)| (!transaction.is_writable(index)).then_some(key));
+    let writable_mask = WritableAccountMask::new(transaction);
+    let write_account_locks = writable_mask.write_keys(account_keys.iter());
+    let read_account_locks = writable_mask.read_keys(account_keys.iter());
 
     let thread_id = match account_locks.try_lock_accounts(
         write_account_locks,
@@ -387,6 +530,7 @@ fn try_schedule_transaction<Tx: TransactionWithMeta>(
         transaction: sanitized_transaction_ttl.transaction,
         max_age: sanitized_transaction_ttl.max_age,
         cost,
+        writable_mask,
     })
 }
 
@@ -485,6 +629,7 @@ mod test {
                 transaction_ttl,
                 compute_unit_price,
                 TEST_TRANSACTION_COST,
+                None,
             );
         }
 
@@ -508,6 +653,7 @@ mod test {
 
     fn test_pre_graph_filter(
         _txs: &[&RuntimeTransaction<SanitizedTransaction>],
+        _max_ages: &[MaxAge],
         results: &mut [bool],
     ) {
         results.fill(true);
@@ -1,21 +1,47 @@
 use {
-    super::{batch_id_generator::BatchIdGenerator, thread_aware_account_locks::ThreadId},
+    super::{
+        batch_id_generator::BatchIdGenerator, thread_aware_account_locks::ThreadId,
+        writable_account_mask::WritableAccountMask,
+    },
     crate::banking_stage::scheduler_messages::TransactionBatchId,
     std::collections::HashMap,
 };
 
+/// Load of a worker thread at a point in time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadLoad {
+    /// CUs of the transactions in flight on the thread, less those they are
+    /// expected to reserve and not use.
+    pub in_flight_cus: u64,
+    /// Number of transactions in flight on the thread.
+    pub in_flight_count: usize,
+}
+
 /// Tracks the number of transactions that are in flight for each thread.
+///
+/// The CUs in flight on a thread are those reserved by its batches, less the
+/// CUs the last batch completed on the thread reserved and did not use: the
+/// batches still in flight are expected to over-reserve as much. The credit
+/// is replaced, not accumulated, on each completion and never exceeds the
+/// CUs reserved in flight, so the CUs in flight never go below zero and the
+/// budgets are never raised past their configured values.
 pub struct InFlightTracker {
     num_in_flight_per_thread: Vec<usize>,
     cus_in_flight_per_thread: Vec<u64>,
+    reserved_cus_per_thread: Vec<u64>,
+    unused_cus_per_thread: Vec<u64>,
     batches: HashMap<TransactionBatchId, BatchEntry>,
     batch_id_generator: BatchIdGenerator,
+    /// Emptied writable mask buffers of completed batches, reused by the
+    /// next batches.
+    free_writable_masks: Vec<Vec<WritableAccountMask>>,
 }
 
 struct BatchEntry {
     thread_id: ThreadId,
     num_transactions: usize,
     total_cus: u64,
+    writable_masks: Vec<WritableAccountMask>,
 }
 
 impl InFlightTracker {
@@ -23,8 +49,11 @@ impl InFlightTracker {
         Self {
             num_in_flight_per_thread: vec![0; num_threads],
             cus_in_flight_per_thread: vec![0; num_threads],
+            reserved_cus_per_thread: vec![0; num_threads],
+            unused_cus_per_thread: vec![0; num_threads],
             batches: HashMap::new(),
             batch_id_generator: BatchIdGenerator::default(),
+            free_writable_masks: Vec::new(),
         }
     }
 
@@ -38,7 +67,20 @@ impl InFlightTracker {
         &self.cus_in_flight_per_thread
     }
 
-    /// Tracks number of transactions and CUs in-flight for the `thread_id`.
+    /// Returns a snapshot of the load of each thread, by thread id.
+    pub fn thread_loads(&self) -> Vec<ThreadLoad> {
+        self.cus_in_flight_per_thread
+            .iter()
+            .zip(&self.num_in_flight_per_thread)
+            .map(|(&in_flight_cus, &in_flight_count)| ThreadLoad {
+                in_flight_cus,
+                in_flight_count,
+            })
+            .collect()
+    }
+
+    /// Tracks number of transactions and CUs in-flight for the `thread_id`,
+    /// along with the writable masks of the transactions of the batch.
     /// Returns a `TransactionBatchId` that can be used to stop tracking the batch
     /// when it is complete.
     pub fn track_batch(
@@ -46,41 +88,74 @@ impl InFlightTracker {
         num_transactions: usize,
         total_cus: u64,
         thread_id: ThreadId,
+        writable_masks: Vec<WritableAccountMask>,
     ) -> TransactionBatchId {
         let batch_id = self.batch_id_generator.next();
         self.num_in_flight_per_thread[thread_id] += num_transactions;
-        self.cus_in_flight_per_thread[thread_id] += total_cus;
+        self.reserved_cus_per_thread[thread_id] += total_cus;
+        self.update_cus_in_flight(thread_id);
         self.batches.insert(
             batch_id,
             BatchEntry {
                 thread_id,
                 num_transactions,
                 total_cus,
+                writable_masks,
             },
         );
 
         batch_id
     }
 
-    /// Stop tracking the batch with given `batch_id`.
+    /// Stop tracking the batch with given `batch_id`, which used
+    /// `actual_cus` of the CUs it reserved.
     /// Removes the number of transactions for the scheduled thread.
-    /// Returns the thread id that the batch was scheduled on.
+    /// Returns the thread id that the batch was scheduled on, the CUs it
+    /// reserved, and the writable masks of its transactions.
     ///
     /// # Panics
     /// Panics if the batch id does not exist in the tracker.
-    pub fn complete_batch(&mut self, batch_id: TransactionBatchId) -> ThreadId {
+    pub fn complete_batch(
+        &mut self,
+        batch_id: TransactionBatchId,
+        actual_cus: u64,
+    ) -> (ThreadId, u64, Vec<WritableAccountMask>) {
         let Some(BatchEntry {
             thread_id,
             num_transactions,
             total_cus,
+            writable_masks,
         }) = self.batches.remove(&batch_id)
         else {
             panic!("batch id {batch_id} is not being tracked");
         };
         self.num_in_flight_per_thread[thread_id] -= num_transactions;
-        self.cus_in_flight_per_thread[thread_id] -= total_cus;
+        self.reserved_cus_per_thread[thread_id] -= total_cus;
+        self.unused_cus_per_thread[thread_id] = total_cus.saturating_sub(actual_cus);
+        self.update_cus_in_flight(thread_id);
+
+        (thread_id, total_cus, writable_masks)
+    }
 
-        thread_id
+    /// A buffer for the writable masks of a new batch, that of a completed
+    /// batch if any was recycled.
+    pub fn take_writable_masks_buffer(&mut self, capacity: usize) -> Vec<WritableAccountMask> {
+        self.free_writable_masks
+            .pop()
+            .unwrap_or_else(|| Vec::with_capacity(capacity))
+    }
+
+    /// Keep the writable masks returned by `complete_batch()` for reuse by
+    /// `take_writable_masks_buffer()`.
+    pub fn recycle_writable_masks(&mut self, mut writable_masks: Vec<WritableAccountMask>) {
+        writable_masks.clear();
+        self.free_writable_masks.push(writable_masks);
+    }
+
+    fn update_cus_in_flight(&mut self, thread_id: ThreadId) {
+        let reserved_cus = self.reserved_cus_per_thread[thread_id];
+        self.cus_in_flight_per_thread[thread_id] =
+            reserved_cus - self.unused_cus_per_thread[thread_id].min(reserved_cus);
     }
 }
 
@@ -92,7 +167,7 @@ mod tests {
     #[should_panic(expected = "is not being tracked")]
     fn test_in_flight_tracker_untracked_batch() {
         let mut in_flight_tracker = InFlightTracker::new(2);
-        in_flight_tracker.complete_batch(TransactionBatchId::new(5));
+        in_flight_tracker.complete_batch(TransactionBatchId::new(5), 0);
     }
 
     #[test]
@@ -100,24 +175,84 @@ mod tests {
         let mut in_flight_tracker = InFlightTracker::new(2);
 
         // Add a batch with 2 transactions, 10 kCUs to thread 0.
-        let batch_id_0 = in_flight_tracker.track_batch(2, 10_000, 0);
+        let batch_id_0 =
+            in_flight_tracker.track_batch(2, 10_000, 0, vec![WritableAccountMask::default(); 2]);
         assert_eq!(in_flight_tracker.num_in_flight_per_thread(), &[2, 0]);
         assert_eq!(in_flight_tracker.cus_in_flight_per_thread(), &[10_000, 0]);
 
         // Add a batch with 1 transaction, 15 kCUs to thread 1.
-        let batch_id_1 = in_flight_tracker.track_batch(1, 15_000, 1);
+        let batch_id_1 =
+            in_flight_tracker.track_batch(1, 15_000, 1, vec![WritableAccountMask::default()]);
         assert_eq!(in_flight_tracker.num_in_flight_per_thread(), &[2, 1]);
         assert_eq!(
             in_flight_tracker.cus_in_flight_per_thread(),
             &[10_000, 15_000]
         );
 
-        in_flight_tracker.complete_batch(batch_id_0);
+        assert_eq!(
+            in_flight_tracker.thread_loads(),
+            [
+                ThreadLoad {
+                    in_flight_cus: 10_000,
+                    in_flight_count: 2,
+                },
+                ThreadLoad {
+                    in_flight_cus: 15_000,
+                    in_flight_count: 1,
+                },
+            ]
+        );
+
+        let (thread_id, total_cus, writable_masks) =
+            in_flight_tracker.complete_batch(batch_id_0, 10_000);
+        assert_eq!((thread_id, total_cus, writable_masks.len()), (0, 10_000, 2));
         assert_eq!(in_flight_tracker.num_in_flight_per_thread(), &[0, 1]);
         assert_eq!(in_flight_tracker.cus_in_flight_per_thread(), &[0, 15_000]);
 
-        in_flight_tracker.complete_batch(batch_id_1);
+        in_flight_tracker.complete_batch(batch_id_1, 15_000);
         assert_eq!(in_flight_tracker.num_in_flight_per_thread(), &[0, 0]);
         assert_eq!(in_flight_tracker.cus_in_flight_per_thread(), &[0, 0]);
     }
+
+    #[test]
+    fn test_in_flight_tracker_unused_cus() {
+        let mut in_flight_tracker = InFlightTracker::new(1);
+        let batch_ids: Vec<_> = (0..3)
+            .map(|_| in_flight_tracker.track_batch(1, 10_000, 0, vec![]))
+            .collect();
+        assert_eq!(in_flight_tracker.cus_in_flight_per_thread(), &[30_000]);
+
+        // the batches still in flight are expected to use as little
+        in_flight_tracker.complete_batch(batch_ids[0], 4_000);
+        assert_eq!(in_flight_tracker.cus_in_flight_per_thread(), &[14_000]);
+
+        // the credit is replaced rather than added to
+        in_flight_tracker.complete_batch(batch_ids[1], 8_000);
+        assert_eq!(in_flight_tracker.cus_in_flight_per_thread(), &[8_000]);
+
+        // and never exceeds the CUs reserved in flight
+        let batch_id = in_flight_tracker.track_batch(1, 1_000, 0, vec![]);
+        in_flight_tracker.complete_batch(batch_ids[2], 0);
+        assert_eq!(in_flight_tracker.cus_in_flight_per_thread(), &[0]);
+        in_flight_tracker.complete_batch(batch_id, 1_000);
+        assert_eq!(in_flight_tracker.cus_in_flight_per_thread(), &[0]);
+    }
+
+    #[test]
+    fn test_in_flight_tracker_recycles_writable_masks() {
+        let mut in_flight_tracker = InFlightTracker::new(1);
+        let mut writable_masks = in_flight_tracker.take_writable_masks_buffer(4);
+        writable_masks.extend([WritableAccountMask::default(); 4]);
+        let batch_id = in_flight_tracker.track_batch(4, 10_000, 0, writable_masks);
+
+        let (_, _, writable_masks) = in_flight_tracker.complete_batch(batch_id, 10_000);
+        let buffer = writable_masks.as_ptr();
+        in_flight_tracker.recycle_writable_masks(writable_masks);
+
+        // the next batch reuses the buffer, emptied
+        let writable_masks = in_flight_tracker.take_writable_masks_buffer(4);
+        assert!(writable_masks.is_empty());
+        assert_eq!(writable_masks.as_ptr(), buffer);
+        assert!(in_flight_tracker.take_writable_masks_buffer(4).capacity() >= 4);
+    }
 }
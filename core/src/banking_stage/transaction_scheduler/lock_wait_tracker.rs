@@ -0,0 +1,268 @@
+use {
+    super::thread_aware_account_locks::{ThreadAwareAccountLocks, ThreadId},
+    crate::banking_stage::{
+        read_write_account_set::ReadWriteAccountSet, scheduler_messages::TransactionId,
+    },
+    solana_pubkey::Pubkey,
+    solana_svm_transaction::svm_message::SVMMessage,
+    std::collections::{HashMap, HashSet},
+};
+
+/// Default `max_lock_wait_passes`, never force-evicting.
+pub(crate) const DEFAULT_MAX_LOCK_WAIT_PASSES: usize = usize::MAX;
+
+/// In-flight transactions whose account locks were released early to unblock
+/// a starving transaction. Their locks must not be released again when their
+/// batch completes.
+pub(crate) type ForceEvictSet = HashSet<TransactionId>;
+
+/// Account locks held by an in-flight transaction.
+struct InFlightLocks {
+    thread_id: ThreadId,
+    priority: u64,
+    write_accounts: Vec<Pubkey>,
+    read_accounts: Vec<Pubkey>,
+}
+
+/// Accounts reserved by a starving transaction against lower-priority work.
+struct Reservation {
+    priority: u64,
+    locks: ReadWriteAccountSet,
+}
+
+/// Counts how many scheduling passes each transaction has been unschedulable
+/// due to account lock conflicts, so that a transaction blocked by
+/// lower-priority in-flight transactions does not wait indefinitely.
+///
+/// Once a transaction has waited `max_lock_wait_passes` passes, the
+/// lower-priority in-flight transactions holding conflicting locks are
+/// force-evicted: their locks are released so that the waiting transaction
+/// can be scheduled. The evicted transactions remain with the workers, which
+/// return them as retryable if they hit an account lock conflict, re-inserting
+/// them into the container. Until it is scheduled, the accounts of the waiting
+/// transaction are reserved: lower-priority transactions conflicting with them
+/// are left unschedulable, so they cannot take the released locks first.
+pub(crate) struct LockWaitTracker {
+    max_lock_wait_passes: usize,
+    wait_passes: HashMap<TransactionId, usize>,
+    in_flight: HashMap<TransactionId, InFlightLocks>,
+    force_evicted: ForceEvictSet,
+    reservations: HashMap<TransactionId, Reservation>,
+}
+
+impl LockWaitTracker {
+    pub(crate) fn new(max_lock_wait_passes: usize) -> Self {
+        Self {
+            max_lock_wait_passes,
+            wait_passes: HashMap::new(),
+            in_flight: HashMap::new(),
+            force_evicted: ForceEvictSet::new(),
+            reservations: HashMap::new(),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.max_lock_wait_passes != DEFAULT_MAX_LOCK_WAIT_PASSES
+    }
+
+    /// Record that `id` could not be scheduled during this pass.
+    pub(crate) fn record_wait(&mut self, id: TransactionId) {
+        if self.is_enabled() {
+            let passes = self.wait_passes.entry(id).or_default();
+            *passes = passes.saturating_add(1);
+        }
+    }
+
+    /// Whether `id` has waited long enough to force-evict the transactions blocking it.
+    pub(crate) fn should_force_evict(&self, id: TransactionId) -> bool {
+        self.is_enabled()
+            && self.wait_passes.get(&id).copied().unwrap_or_default() >= self.max_lock_wait_passes
+    }
+
+    /// Record the locks taken by `id`, scheduled onto `thread_id`.
+    pub(crate) fn track_scheduled(
+        &mut self,
+        id: TransactionId,
+        thread_id: ThreadId,
+        priority: u64,
+        transaction: &impl SVMMessage,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.wait_passes.remove(&id);
+        self.reservations.remove(&id);
+        let (write_accounts, read_accounts) = transaction
+            .account_keys()
+            .iter()
+            .enumerate()
+            .partition::<Vec<_>, _>(|(index, _)| transaction.is_writable(*index));
+        self.in_flight.insert(
+            id,
+            InFlightLocks {
+                thread_id,
+                priority,
+                write_accounts: write_accounts.into_iter().map(|(_, key)| *key).collect(),
+                read_accounts: read_accounts.into_iter().map(|(_, key)| *key).collect(),
+            },
+        );
+    }
+
+    /// Release the locks of the in-flight transactions with a lower priority
+    /// than `priority` that conflict with `transaction`, and reserve the
+    /// accounts of `transaction`, identified by `id`, until it is scheduled.
+    /// Returns the number of transactions evicted.
+    pub(crate) fn force_evict_blocking(
+        &mut self,
+        id: TransactionId,
+        transaction: &impl SVMMessage,
+        priority: u64,
+        account_locks: &mut ThreadAwareAccountLocks,
+    ) -> usize {
+        let account_keys = transaction.account_keys();
+        let conflicts = |locks: &InFlightLocks| {
+            account_keys.iter().enumerate().any(|(index, key)| {
+                locks.write_accounts.contains(key)
+                    || (transaction.is_writable(index) && locks.read_accounts.contains(key))
+            })
+        };
+        let evicted: Vec<_> = self
+            .in_flight
+            .iter()
+            .filter(|(_, locks)| locks.priority < priority && conflicts(locks))
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in &evicted {
+            let locks = self.in_flight.remove(id).unwrap();
+            account_locks.unlock_accounts(
+                locks.write_accounts.iter(),
+                locks.read_accounts.iter(),
+                locks.thread_id,
+            );
+            self.force_evicted.insert(*id);
+        }
+
+        let mut locks = ReadWriteAccountSet::default();
+        locks.take_locks(transaction);
+        self.reservations
+            .insert(id, Reservation { priority, locks });
+        evicted.len()
+    }
+
+    /// Whether `transaction` conflicts with the accounts reserved by a
+    /// transaction of higher priority than `priority`.
+    pub(crate) fn is_reserved(&self, transaction: &impl SVMMessage, priority: u64) -> bool {
+        self.reservations.values().any(|reservation| {
+            reservation.priority > priority && !reservation.locks.check_locks(transaction)
+        })
+    }
+
+    /// Stop tracking the locks of a completed transaction.
+    /// Returns true if its locks were already released by a force-eviction.
+    pub(crate) fn complete(&mut self, id: TransactionId) -> bool {
+        self.in_flight.remove(&id);
+        self.force_evicted.remove(&id)
+    }
+
+    /// Stop tracking the waits and reservations of all transactions for which
+    /// `f` returns false.
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(TransactionId) -> bool) {
+        self.wait_passes.retain(|id, _| f(*id));
+        self.reservations.retain(|id, _| f(*id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::banking_stage::transaction_scheduler::thread_aware_account_locks::ThreadSet,
+        solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
+        solana_sdk::{
+            hash::Hash,
+            message::Message,
+            signature::Keypair,
+            signer::Signer,
+            system_instruction,
+            transaction::{SanitizedTransaction, Transaction},
+        },
+    };
+
+    fn transfer(from: &Keypair, to: &Pubkey) -> RuntimeTransaction<SanitizedTransaction> {
+        let ixs = vec![system_instruction::transfer(&from.pubkey(), to, 1)];
+        let message = Message::new(&ixs, Some(&from.pubkey()));
+        let tx = Transaction::new(&[from], message, Hash::default());
+        RuntimeTransaction::from_transaction_for_tests(tx)
+    }
+
+    fn lock(
+        account_locks: &mut ThreadAwareAccountLocks,
+        transaction: &impl SVMMessage,
+        thread_id: ThreadId,
+    ) {
+        let account_keys = transaction.account_keys();
+        account_locks
+            .try_lock_accounts(
+                account_keys
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, key)| transaction.is_writable(index).then_some(key)),
+                account_keys
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, key)| (!transaction.is_writable(index)).then_some(key)),
+                ThreadSet::only(thread_id),
+                |_| thread_id,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut tracker = LockWaitTracker::new(DEFAULT_MAX_LOCK_WAIT_PASSES);
+        tracker.record_wait(0);
+        assert!(!tracker.should_force_evict(0));
+    }
+
+    #[test]
+    fn test_force_evict_blocking() {
+        let mut tracker = LockWaitTracker::new(1);
+        let mut account_locks = ThreadAwareAccountLocks::new(2);
+        let pubkey = Pubkey::new_unique();
+
+        let low = transfer(&Keypair::new(), &pubkey);
+        lock(&mut account_locks, &low, 0);
+        tracker.track_scheduled(0, 0, 1, &low);
+        let unrelated = transfer(&Keypair::new(), &Pubkey::new_unique());
+        lock(&mut account_locks, &unrelated, 1);
+        tracker.track_scheduled(1, 1, 1, &unrelated);
+
+        let high = transfer(&Keypair::new(), &pubkey);
+        assert!(!tracker.should_force_evict(2));
+        tracker.record_wait(2);
+        assert!(tracker.should_force_evict(2));
+
+        // Only lower-priority conflicting transactions are evicted.
+        assert_eq!(
+            tracker.force_evict_blocking(2, &high, 1, &mut account_locks),
+            0
+        );
+        assert_eq!(
+            tracker.force_evict_blocking(2, &high, 2, &mut account_locks),
+            1
+        );
+
+        // Its accounts are reserved against lower-priority work until scheduled.
+        let other = transfer(&Keypair::new(), &pubkey);
+        assert!(tracker.is_reserved(&other, 1));
+        assert!(!tracker.is_reserved(&other, 2));
+        assert!(!tracker.is_reserved(&unrelated, 1));
+        lock(&mut account_locks, &high, 1);
+        tracker.track_scheduled(2, 1, 2, &high);
+        assert!(!tracker.is_reserved(&other, 1));
+
+        assert!(tracker.complete(0));
+        assert!(!tracker.complete(1));
+    }
+}
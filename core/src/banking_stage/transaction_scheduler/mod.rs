@@ -1,13 +1,24 @@
 mod batch_id_generator;
 pub(crate) mod greedy_scheduler;
-mod in_flight_tracker;
+pub(crate) mod in_flight_tracker;
+mod lock_wait_tracker;
 pub(crate) mod prio_graph_scheduler;
+mod readonly_fanin;
 pub(crate) mod receive_and_buffer;
+mod recently_completed;
+mod retry_tracker;
 pub(crate) mod scheduler;
 pub(crate) mod scheduler_controller;
 pub(crate) mod scheduler_error;
 mod scheduler_metrics;
+pub(crate) mod scheduler_state;
+pub(crate) mod scheduling_estimate;
+pub mod stats;
+mod summary_history;
 mod thread_aware_account_locks;
+mod transaction_id_allocator;
 mod transaction_priority_id;
+mod transaction_priority_queue;
 mod transaction_state;
 pub(crate) mod transaction_state_container;
+mod writable_account_mask;
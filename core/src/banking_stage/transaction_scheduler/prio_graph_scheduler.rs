@@ -1,16 +1,28 @@
 use {
     super::{
-        in_flight_tracker::InFlightTracker,
-        scheduler::{PreLockFilterAction, Scheduler},
+        in_flight_tracker::{InFlightTracker, ThreadLoad},
+        lock_wait_tracker::{LockWaitTracker, DEFAULT_MAX_LOCK_WAIT_PASSES},
+        readonly_fanin::{ReadonlyFanin, DEFAULT_READONLY_FANIN_THRESHOLD},
+        retry_tracker::{RetryTracker, DEFAULT_RETRY_THRESHOLD},
+        scheduler::{CompletedCus, PreLockFilterAction, RetryCounts, Scheduler},
         scheduler_error::SchedulerError,
+        scheduler_state::SchedulerStateSnapshot,
+        scheduling_estimate::{
+            self, EstimateRequest, SchedulingEstimate, DEFAULT_MAX_ESTIMATE_EXAMINED_TRANSACTIONS,
+        },
+        stats::SchedulerConfig,
+        summary_history::SummaryHistory,
         thread_aware_account_locks::{ThreadAwareAccountLocks, ThreadId, ThreadSet, TryLockError},
+        transaction_id_allocator::TransactionIdAllocator,
         transaction_state::SanitizedTransactionTTL,
+        writable_account_mask::WritableAccountMask,
     },
     crate::banking_stage::{
         consumer::TARGET_NUM_TRANSACTIONS_PER_BATCH,
         read_write_account_set::ReadWriteAccountSet,
         scheduler_messages::{
-            ConsumeWork, FinishedConsumeWork, MaxAge, TransactionBatchId, TransactionId,
+            ConsumeWork, FinishedConsumeWork, MaxAge, RetryReason, TransactionBatchId,
+            TransactionId,
         },
         transaction_scheduler::{
             scheduler::SchedulingSummary, transaction_priority_id::TransactionPriorityId,
@@ -20,11 +32,17 @@ use {
     crossbeam_channel::{Receiver, Sender, TryRecvError},
     itertools::izip,
     prio_graph::{AccessKind, GraphNode, PrioGraph},
+    rand::{Rng, SeedableRng},
+    rand_chacha::ChaCha8Rng,
     solana_cost_model::block_cost_limits::MAX_BLOCK_UNITS,
     solana_measure::measure_us,
     solana_runtime_transaction::transaction_with_meta::TransactionWithMeta,
     solana_sdk::{pubkey::Pubkey, saturating_add_assign},
     solana_svm_transaction::svm_message::SVMMessage,
+    std::{
+        collections::{HashMap, HashSet, VecDeque},
+        time::{Duration, Instant},
+    },
 };
 
 #[inline(always)]
@@ -35,6 +53,13 @@ fn passthrough_priority(
     *id
 }
 
+/// A transaction to be scheduled ahead of the container, with its max age and cost.
+pub(crate) type PriorityInclusion<Tx> = (Tx, MaxAge, u64);
+
+/// Priority inclusions are not in the container, so they are given ids counting
+/// up from here, well clear of the container's slab indices.
+const FIRST_PRIORITY_INCLUSION_ID: TransactionId = TransactionId::MAX / 2;
+
 type SchedulerPrioGraph = PrioGraph<
     TransactionPriorityId,
     Pubkey,
@@ -42,11 +67,62 @@ type SchedulerPrioGraph = PrioGraph<
     fn(&TransactionPriorityId, &GraphNode<TransactionPriorityId>) -> TransactionPriorityId,
 >;
 
-pub(crate) struct PrioGraphSchedulerConfig {
+/// Configuration of the `PrioGraphScheduler`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct PrioGraphSchedulerConfig {
+    /// CUs in flight across all threads, less the CUs the batches in flight
+    /// are expected to reserve and not use.
     pub max_scheduled_cus: u64,
     pub max_scanned_transactions_per_scheduling_pass: usize,
     pub look_ahead_window_size: usize,
     pub target_transactions_per_batch: usize,
+    /// Transactions retried more than this many times are reported by
+    /// `transactions_over_retry_threshold()`.
+    pub retry_threshold: usize,
+    /// Number of recent scheduling summaries retained for `recent_summaries()`.
+    /// Zero disables retention.
+    pub summary_history_size: usize,
+    /// Once a transaction has been unschedulable for this many passes, the
+    /// lower-priority in-flight transactions blocking it are force-evicted,
+    /// and its accounts are reserved against lower-priority work until it is
+    /// scheduled.
+    pub max_lock_wait_passes: usize,
+    /// Accounts read, and not written, by more than this many transactions
+    /// in the look-ahead window have their read edges left out of the
+    /// prio-graph. Read locks are still taken on them.
+    pub readonly_fanin_threshold: usize,
+    /// Batches below the target size are held back for up to this long at
+    /// the end of a pass, to be topped up by the following passes, instead of
+    /// being sent right away. `None` disables holding. Held batches are sent
+    /// when the working bank changes.
+    pub batch_coalesce: Option<Duration>,
+    /// Prefer scheduling a transaction on the thread already holding the
+    /// most locks on its most locked account, so that transactions sharing
+    /// a hot account keep its locks on one thread.
+    pub sticky_account_affinity: bool,
+    /// Number of queued transactions examined for conflicts by `estimate()`.
+    pub max_estimate_examined_transactions: usize,
+    /// Send at most one batch per thread per pass, at the end of the pass,
+    /// instead of sending a batch as soon as it reaches the target size.
+    /// Batches may then grow past `target_transactions_per_batch`.
+    pub single_batch_per_thread: bool,
+    /// Order retried transactions ahead of the fresh ones of the same
+    /// priority, so that they are reconsidered first on the next pass.
+    pub retryables_first: bool,
+    /// Always select the lowest schedulable thread id, ignoring the load of
+    /// the threads and `sticky_account_affinity`, so that benchmarks place
+    /// transactions reproducibly.
+    pub lowest_thread_id_selection: bool,
+    /// Select among the schedulable threads at random, weighted towards the
+    /// threads with the fewest CUs queued, instead of always the least loaded
+    /// one. A thread preferred by `sticky_account_affinity` still wins.
+    pub weighted_random_thread_selection: bool,
+    /// Seed of the RNG every randomized policy draws from, so that a run can
+    /// be reproduced. When `None`, a seed is generated, logged, and reported
+    /// in its place by `Scheduler::config()`.
+    pub rng_seed: Option<u64>,
 }
 
 impl Default for PrioGraphSchedulerConfig {
@@ -56,34 +132,284 @@ impl Default for PrioGraphSchedulerConfig {
             max_scanned_transactions_per_scheduling_pass: 1000,
             look_ahead_window_size: 256,
             target_transactions_per_batch: TARGET_NUM_TRANSACTIONS_PER_BATCH,
+            retry_threshold: DEFAULT_RETRY_THRESHOLD,
+            summary_history_size: 0,
+            max_lock_wait_passes: DEFAULT_MAX_LOCK_WAIT_PASSES,
+            readonly_fanin_threshold: DEFAULT_READONLY_FANIN_THRESHOLD,
+            batch_coalesce: None,
+            sticky_account_affinity: false,
+            max_estimate_examined_transactions: DEFAULT_MAX_ESTIMATE_EXAMINED_TRANSACTIONS,
+            single_batch_per_thread: false,
+            retryables_first: false,
+            lowest_thread_id_selection: false,
+            weighted_random_thread_selection: false,
+            rng_seed: None,
+        }
+    }
+}
+
+impl PrioGraphSchedulerConfig {
+    /// Schedule transactions one at a time, strictly in priority order, for
+    /// deterministic execution. Must be used with a single worker thread, see
+    /// `PrioGraphScheduler::strict_priority_mode`.
+    #[cfg(test)]
+    pub(crate) fn strict_priority_mode() -> Self {
+        Self {
+            // Only the top of the container is ever in the prio-graph, so
+            // that no transaction is reordered around another.
+            look_ahead_window_size: 1,
+            // Each transaction is sent on its own, as soon as it is scheduled.
+            target_transactions_per_batch: 1,
+            batch_coalesce: None,
+            sticky_account_affinity: false,
+            single_batch_per_thread: false,
+            ..Self::default()
         }
     }
 }
 
+/// Observer of the individual decisions made during a scheduling pass, e.g.
+/// for monitoring or logging. Callbacks are invoked on the scheduler thread
+/// and should be cheap.
+pub(crate) trait SchedulingEventListener<Tx> {
+    /// `tx_id` was scheduled onto `thread_id` with a cost of `cus`.
+    fn on_scheduled(&self, tx_id: TransactionId, thread_id: ThreadId, cus: u64);
+    /// `tx_id` could not be scheduled during this pass.
+    fn on_unschedulable(&self, tx_id: TransactionId, reason: &TransactionSchedulingError);
+    /// `tx_id` was dropped by the pre-graph filter.
+    fn on_filtered(&self, tx_id: TransactionId);
+    /// `tx_id` was dropped because its deadline passed.
+    fn on_deadline_expired(&self, tx_id: TransactionId);
+}
+
+type EventListener<Tx> = dyn SchedulingEventListener<Tx> + Send;
+type Clock = dyn Fn() -> Instant + Send;
+
 pub(crate) struct PrioGraphScheduler<Tx> {
     in_flight_tracker: InFlightTracker,
     account_locks: ThreadAwareAccountLocks,
     consume_work_senders: Vec<Sender<ConsumeWork<Tx>>>,
     finished_consume_work_receiver: Receiver<FinishedConsumeWork<Tx>>,
+    retry_tracker: RetryTracker,
+    summary_history: SummaryHistory,
+    lock_wait_tracker: LockWaitTracker,
+    /// Transactions held back until the next slot because they would exceed a block limit.
+    deferred: Vec<TransactionPriorityId>,
+    /// CUs of the batches completed since the last `take_completed_cus()`.
+    completed_cus: CompletedCus,
+    /// Transactions to schedule at the top of the next pass.
+    priority_inclusions: Vec<PriorityInclusion<Tx>>,
+    /// Priority inclusions that could not be scheduled, until taken by the controller.
+    unscheduled_priority_inclusions: Vec<PriorityInclusion<Tx>>,
+    /// Cost of each in-flight priority inclusion, by its id.
+    in_flight_priority_inclusions: HashMap<TransactionId, u64>,
+    priority_inclusion_ids: TransactionIdAllocator,
+    /// Batches held back by `batch_coalesce`, carried over to the next pass.
+    held_batches: Option<Batches<Tx>>,
+    /// When the batch of each thread was first held back.
+    held_since: Vec<Option<Instant>>,
     prio_graph: SchedulerPrioGraph,
     config: PrioGraphSchedulerConfig,
+    scheduling_event_listener: Option<Box<EventListener<Tx>>>,
+    /// Source of the current time, against which deadlines are checked.
+    clock: Box<Clock>,
+    /// Source of the random draws of the randomized policies, seeded from
+    /// `config.rng_seed`.
+    rng: ChaCha8Rng,
 }
 
 impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
     pub(crate) fn new(
         consume_work_senders: Vec<Sender<ConsumeWork<Tx>>>,
         finished_consume_work_receiver: Receiver<FinishedConsumeWork<Tx>>,
-        config: PrioGraphSchedulerConfig,
+        mut config: PrioGraphSchedulerConfig,
     ) -> Self {
         let num_threads = consume_work_senders.len();
+        let rng_seed = *config.rng_seed.get_or_insert_with(|| {
+            let rng_seed = rand::thread_rng().gen();
+            info!("prio-graph scheduler rng seed: {rng_seed}");
+            rng_seed
+        });
         Self {
             in_flight_tracker: InFlightTracker::new(num_threads),
             account_locks: ThreadAwareAccountLocks::new(num_threads),
             consume_work_senders,
             finished_consume_work_receiver,
+            retry_tracker: RetryTracker::new(config.retry_threshold),
+            summary_history: SummaryHistory::new(config.summary_history_size),
+            lock_wait_tracker: LockWaitTracker::new(config.max_lock_wait_passes),
+            deferred: Vec::new(),
+            completed_cus: CompletedCus::default(),
+            priority_inclusions: Vec::new(),
+            unscheduled_priority_inclusions: Vec::new(),
+            in_flight_priority_inclusions: HashMap::new(),
+            priority_inclusion_ids: TransactionIdAllocator::new(FIRST_PRIORITY_INCLUSION_ID),
+            held_batches: None,
+            held_since: vec![None; num_threads],
             prio_graph: PrioGraph::new(passthrough_priority),
             config,
+            scheduling_event_listener: None,
+            clock: Box::new(Instant::now),
+            rng: ChaCha8Rng::seed_from_u64(rng_seed),
+        }
+    }
+
+    /// Scheduler sending every transaction in its own batch to the single
+    /// worker of `consume_work_sender`, strictly in priority order.
+    #[cfg(test)]
+    pub(crate) fn strict_priority_mode(
+        consume_work_sender: Sender<ConsumeWork<Tx>>,
+        finished_consume_work_receiver: Receiver<FinishedConsumeWork<Tx>>,
+    ) -> Self {
+        Self::new(
+            vec![consume_work_sender],
+            finished_consume_work_receiver,
+            PrioGraphSchedulerConfig::strict_priority_mode(),
+        )
+    }
+
+    #[cfg(test)]
+    fn with_clock(mut self, clock: Box<Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Notify `listener` of every scheduling decision.
+    pub(crate) fn with_scheduling_event_listener(
+        mut self,
+        listener: Box<EventListener<Tx>>,
+    ) -> Self {
+        self.scheduling_event_listener = Some(listener);
+        self
+    }
+
+    /// Schedule `transactions` at the top of the first batches of the next
+    /// `schedule()` pass, ahead of any container transactions.
+    /// They bypass the container and the pre-graph filter, but still take
+    /// account locks and count against the per-thread CU budgets. Inclusions
+    /// returned as retryable by the workers are included again in a later pass.
+    pub(crate) fn set_priority_inclusions(&mut self, transactions: Vec<PriorityInclusion<Tx>>) {
+        self.priority_inclusions.extend(transactions);
+    }
+
+    /// Priority inclusions that could not be scheduled, as counted by
+    /// `SchedulingSummary::num_unschedulable_priority_inclusions`.
+    pub(crate) fn take_unscheduled_priority_inclusions(&mut self) -> Vec<PriorityInclusion<Tx>> {
+        std::mem::take(&mut self.unscheduled_priority_inclusions)
+    }
+
+    /// Resume a batch that was in flight on `thread_id`, such as one
+    /// recovered after a restart: take the locks of its transactions, track
+    /// their costs so that the following passes see its conflicts, and send it
+    /// to the thread again.
+    /// Like priority inclusions, the transactions of the batch bypass the
+    /// container and are given ids of their own; those returned as retryable
+    /// are included again in a later pass.
+    ///
+    /// If a transaction of the batch conflicts with the locks held by another
+    /// thread, or the priority inclusion ids are exhausted, no lock is taken
+    /// and the batch is left to the next pass as priority inclusions instead.
+    /// Returns whether the batch was resumed on `thread_id`.
+    pub(crate) fn resume_in_flight_batch(
+        &mut self,
+        thread_id: ThreadId,
+        batch: Vec<PriorityInclusion<Tx>>,
+    ) -> Result<bool, SchedulerError> {
+        let mut writable_masks = self
+            .in_flight_tracker
+            .take_writable_masks_buffer(batch.len());
+        for (transaction, _max_age, _cost) in &batch {
+            let account_keys = transaction.account_keys();
+            let writable_mask = WritableAccountMask::new(transaction);
+            let locked = self.account_locks.try_lock_accounts(
+                writable_mask.write_keys(account_keys.iter()),
+                writable_mask.read_keys(account_keys.iter()),
+                ThreadSet::only(thread_id),
+                |_| thread_id,
+            );
+            if locked.is_err() {
+                self.release_resumed_batch(thread_id, batch, writable_masks);
+                return Ok(false);
+            }
+            writable_masks.push(writable_mask);
+        }
+        let Some(ids) = self.allocate_priority_inclusion_ids(&batch) else {
+            self.release_resumed_batch(thread_id, batch, writable_masks);
+            return Ok(false);
+        };
+
+        let total_cus = batch
+            .iter()
+            .fold(0u64, |total_cus, (_transaction, _max_age, cost)| {
+                total_cus.saturating_add(*cost)
+            });
+        let (transactions, max_ages) = batch
+            .into_iter()
+            .map(|(transaction, max_age, _cost)| (transaction, max_age))
+            .unzip();
+        let batch_id =
+            self.in_flight_tracker
+                .track_batch(ids.len(), total_cus, thread_id, writable_masks);
+        let work = ConsumeWork {
+            batch_id,
+            ids,
+            transactions,
+            max_ages,
+        };
+        self.consume_work_senders[thread_id]
+            .send(work)
+            .map_err(|_| SchedulerError::DisconnectedSendChannel("consume work sender"))?;
+
+        Ok(true)
+    }
+
+    /// Release the locks taken for the transactions of `batch` which
+    /// `writable_masks` holds the masks of, leaving it to the next pass as
+    /// priority inclusions.
+    fn release_resumed_batch(
+        &mut self,
+        thread_id: ThreadId,
+        batch: Vec<PriorityInclusion<Tx>>,
+        writable_masks: Vec<WritableAccountMask>,
+    ) {
+        for ((transaction, _max_age, _cost), writable_mask) in batch.iter().zip(&writable_masks) {
+            let account_keys = transaction.account_keys();
+            self.account_locks.unlock_accounts(
+                writable_mask.write_keys(account_keys.iter()),
+                writable_mask.read_keys(account_keys.iter()),
+                thread_id,
+            );
+        }
+        self.in_flight_tracker
+            .recycle_writable_masks(writable_masks);
+        self.priority_inclusions.extend(batch);
+    }
+
+    /// Allocate an id to each transaction of `batch`, tracking its cost as an
+    /// in-flight priority inclusion. Returns `None`, allocating none of them,
+    /// if the ids are exhausted.
+    fn allocate_priority_inclusion_ids(
+        &mut self,
+        batch: &[PriorityInclusion<Tx>],
+    ) -> Option<Vec<TransactionId>> {
+        let mut ids = Vec::with_capacity(batch.len());
+        for (_transaction, _max_age, cost) in batch {
+            let in_flight_priority_inclusions = &self.in_flight_priority_inclusions;
+            let Some(id) = self
+                .priority_inclusion_ids
+                .next(in_flight_priority_inclusions.len(), |id| {
+                    in_flight_priority_inclusions.contains_key(&id)
+                })
+            else {
+                for id in &ids {
+                    self.in_flight_priority_inclusions.remove(id);
+                }
+                return None;
+            };
+            self.in_flight_priority_inclusions.insert(id, *cost);
+            ids.push(id);
         }
+        Some(ids)
     }
 }
 
@@ -92,9 +418,9 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
     /// consumed by the worker threads. Returns summary of scheduling, or an
     /// error.
     /// `pre_graph_filter` is used to filter out transactions that should be
-    /// skipped and dropped before insertion to the prio-graph. This fn should
-    /// set `false` for transactions that should be dropped, and `true`
-    /// otherwise.
+    /// skipped and dropped before insertion to the prio-graph. It is passed
+    /// the transactions along with their `MaxAge`s. This fn should set `false`
+    /// for transactions that should be dropped, and `true` otherwise.
     /// `pre_lock_filter` is used to filter out transactions after they have
     /// made it to the top of the prio-graph, and immediately before locks are
     /// checked and taken. This fn should return `true` for transactions that
@@ -107,7 +433,7 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
     fn schedule<S: StateContainer<Tx>>(
         &mut self,
         container: &mut S,
-        pre_graph_filter: impl Fn(&[&Tx], &mut [bool]),
+        pre_graph_filter: impl Fn(&[&Tx], &[MaxAge], &mut [bool]),
         pre_lock_filter: impl Fn(&TransactionState<Tx>) -> PreLockFilterAction,
     ) -> Result<SchedulingSummary, SchedulerError> {
         let num_threads = self.consume_work_senders.len();
@@ -120,15 +446,24 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
             }
         }
         if schedulable_threads.is_empty() {
-            return Ok(SchedulingSummary {
+            return Ok(self.summary_history.record(SchedulingSummary {
                 num_scheduled: 0,
                 num_unschedulable: 0,
                 num_filtered_out: 0,
                 filter_time_us: 0,
-            });
+                num_unschedulable_priority_inclusions: 0,
+                num_force_evictions: 0,
+                num_priority_inversions: 0,
+                num_deadline_expired: 0,
+                graph_insertion_time_us: 0,
+            }));
         }
 
-        let mut batches = Batches::new(num_threads, self.config.target_transactions_per_batch);
+        // Batches held back by the previous pass are topped up first.
+        let mut batches = self.held_batches.take().unwrap_or_else(|| {
+            Batches::new(num_threads, self.config.target_transactions_per_batch)
+        });
+        let num_previously_held = batches.num_transactions();
         // Some transactions may be unschedulable due to multi-thread conflicts.
         // These transactions cannot be scheduled until some conflicting work is completed.
         // However, the scheduler should not allow other transactions that conflict with
@@ -136,19 +471,36 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
         let mut unschedulable_ids = Vec::new();
         let mut blocking_locks = ReadWriteAccountSet::default();
 
+        // Priority inclusions go to the top of the first batches.
+        let (mut num_scheduled, mut num_sent, num_unschedulable_priority_inclusions) = self
+            .schedule_priority_inclusions(
+                &mut batches,
+                &mut blocking_locks,
+                &mut schedulable_threads,
+                max_cu_per_thread,
+                &pre_lock_filter,
+            )?;
+
         // Track metrics on filter.
         let mut num_filtered_out: usize = 0;
+        let mut num_deadline_expired: usize = 0;
         let mut total_filter_time_us: u64 = 0;
+        let mut total_graph_insertion_time_us: u64 = 0;
+
+        let mut readonly_fanin = ReadonlyFanin::new(self.config.readonly_fanin_threshold);
 
         let mut window_budget = self.config.look_ahead_window_size;
         let mut chunked_pops = |container: &mut S,
                                 prio_graph: &mut PrioGraph<_, _, _, _>,
-                                window_budget: &mut usize| {
+                                window_budget: &mut usize,
+                                events: Option<&EventListener<Tx>>,
+                                now: Instant| {
             while *window_budget > 0 {
                 const MAX_FILTER_CHUNK_SIZE: usize = 128;
                 let mut filter_array = [true; MAX_FILTER_CHUNK_SIZE];
                 let mut ids = Vec::with_capacity(MAX_FILTER_CHUNK_SIZE);
                 let mut txs = Vec::with_capacity(MAX_FILTER_CHUNK_SIZE);
+                let mut max_ages = Vec::with_capacity(MAX_FILTER_CHUNK_SIZE);
 
                 let chunk_size = (*window_budget).min(MAX_FILTER_CHUNK_SIZE);
                 for _ in 0..chunk_size {
@@ -159,30 +511,58 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
                     }
                 }
                 *window_budget = window_budget.saturating_sub(chunk_size);
+                let num_popped = ids.len();
+
+                // Drop transactions whose deadline has passed, before filtering.
+                ids.retain(|id| {
+                    let expired = container
+                        .get_mut_transaction_state(id.id)
+                        .unwrap()
+                        .is_deadline_expired(now);
+                    if expired {
+                        saturating_add_assign!(num_deadline_expired, 1);
+                        container.remove_by_id(id.id);
+                        if let Some(events) = events {
+                            events.on_deadline_expired(id.id);
+                        }
+                    }
+                    !expired
+                });
 
                 ids.iter().for_each(|id| {
                     let transaction = container.get_transaction_ttl(id.id).unwrap();
                     txs.push(&transaction.transaction);
+                    max_ages.push(transaction.max_age);
                 });
 
-                let (_, filter_us) =
-                    measure_us!(pre_graph_filter(&txs, &mut filter_array[..chunk_size]));
+                let (_, filter_us) = measure_us!(pre_graph_filter(
+                    &txs,
+                    &max_ages,
+                    &mut filter_array[..chunk_size]
+                ));
                 saturating_add_assign!(total_filter_time_us, filter_us);
 
                 for (id, filter_result) in ids.iter().zip(&filter_array[..chunk_size]) {
                     if *filter_result {
                         let transaction = container.get_transaction_ttl(id.id).unwrap();
-                        prio_graph.insert_transaction(
-                            *id,
-                            Self::get_transaction_account_access(transaction),
-                        );
+                        let (_, insertion_us) = measure_us!({
+                            readonly_fanin.observe(&transaction.transaction);
+                            prio_graph.insert_transaction(
+                                *id,
+                                Self::get_transaction_account_access(transaction, &readonly_fanin),
+                            );
+                        });
+                        saturating_add_assign!(total_graph_insertion_time_us, insertion_us);
                     } else {
                         saturating_add_assign!(num_filtered_out, 1);
                         container.remove_by_id(id.id);
+                        if let Some(events) = events {
+                            events.on_filtered(id.id);
+                        }
                     }
                 }
 
-                if ids.len() != chunk_size {
+                if num_popped != chunk_size {
                     break;
                 }
             }
@@ -190,15 +570,23 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
 
         // Create the initial look-ahead window.
         // Check transactions against filter, remove from container if it fails.
-        chunked_pops(container, &mut self.prio_graph, &mut window_budget);
+        chunked_pops(
+            container,
+            &mut self.prio_graph,
+            &mut window_budget,
+            self.scheduling_event_listener.as_deref(),
+            (self.clock)(),
+        );
 
         let mut unblock_this_batch = Vec::with_capacity(
             self.consume_work_senders.len() * self.config.target_transactions_per_batch,
         );
         let mut num_scanned: usize = 0;
-        let mut num_scheduled: usize = 0;
-        let mut num_sent: usize = 0;
         let mut num_unschedulable: usize = 0;
+        let mut num_force_evictions: usize = 0;
+        let mut num_priority_inversions: usize = 0;
+        // Highest priority of the transactions found unschedulable so far.
+        let mut max_unschedulable_priority = None;
         while num_scanned < self.config.max_scanned_transactions_per_scheduling_pass {
             // If nothing is in the main-queue of the `PrioGraph` then there's nothing left to schedule.
             if self.prio_graph.is_empty() {
@@ -215,43 +603,114 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
                     panic!("transaction state must exist")
                 };
 
-                let maybe_schedule_info = try_schedule_transaction(
-                    transaction_state,
-                    &pre_lock_filter,
-                    &mut blocking_locks,
-                    &mut self.account_locks,
-                    num_threads,
-                    |thread_set| {
-                        Self::select_thread(
-                            thread_set,
-                            &batches.total_cus,
-                            self.in_flight_tracker.cus_in_flight_per_thread(),
-                            &batches.transactions,
-                            self.in_flight_tracker.num_in_flight_per_thread(),
+                // Release the locks of lower-priority transactions this one
+                // has been waiting on for too long, and reserve its accounts.
+                if self.lock_wait_tracker.should_force_evict(id.id) {
+                    saturating_add_assign!(
+                        num_force_evictions,
+                        self.lock_wait_tracker.force_evict_blocking(
+                            id.id,
+                            &transaction_state.transaction_ttl().transaction,
+                            id.priority,
+                            &mut self.account_locks,
                         )
-                    },
-                );
+                    );
+                }
+
+                // Leave the accounts reserved by a starving higher-priority
+                // transaction to it.
+                let transaction = &transaction_state.transaction_ttl().transaction;
+                let maybe_schedule_info =
+                    if self.lock_wait_tracker.is_reserved(transaction, id.priority) {
+                        blocking_locks.take_locks(transaction);
+                        Err(TransactionSchedulingError::UnschedulableConflicts)
+                    } else {
+                        try_schedule_transaction(
+                            transaction_state,
+                            &pre_lock_filter,
+                            &self.clock,
+                            &mut blocking_locks,
+                            &mut self.account_locks,
+                            num_threads,
+                            self.config.sticky_account_affinity,
+                            |thread_set, sticky_thread| {
+                                if self.config.lowest_thread_id_selection {
+                                    return Self::select_lowest_thread(thread_set);
+                                }
+                                let preferred_thread = sticky_thread
+                                    .filter(|thread_id| schedulable_threads.contains(*thread_id));
+                                if self.config.weighted_random_thread_selection {
+                                    return Self::select_weighted_random_thread(
+                                        thread_set,
+                                        preferred_thread,
+                                        &batches.total_cus,
+                                        self.in_flight_tracker.cus_in_flight_per_thread(),
+                                        &mut self.rng,
+                                    );
+                                }
+                                Self::select_thread(
+                                    thread_set,
+                                    preferred_thread,
+                                    &batches.total_cus,
+                                    self.in_flight_tracker.cus_in_flight_per_thread(),
+                                    &batches.transactions,
+                                    self.in_flight_tracker.num_in_flight_per_thread(),
+                                )
+                            },
+                        )
+                    };
 
                 match maybe_schedule_info {
-                    Err(TransactionSchedulingError::UnschedulableConflicts)
-                    | Err(TransactionSchedulingError::UnschedulableThread) => {
+                    Err(
+                        reason @ (TransactionSchedulingError::UnschedulableConflicts
+                        | TransactionSchedulingError::UnschedulableThread),
+                    ) => {
                         unschedulable_ids.push(id);
                         saturating_add_assign!(num_unschedulable, 1);
+                        max_unschedulable_priority =
+                            max_unschedulable_priority.max(Some(id.priority));
+                        self.lock_wait_tracker.record_wait(id.id);
+                        if let Some(listener) = &self.scheduling_event_listener {
+                            listener.on_unschedulable(id.id, &reason);
+                        }
+                    }
+                    Err(TransactionSchedulingError::DeadlineExpired) => {
+                        saturating_add_assign!(num_deadline_expired, 1);
+                        container.remove_by_id(id.id);
+                        if let Some(listener) = &self.scheduling_event_listener {
+                            listener.on_deadline_expired(id.id);
+                        }
                     }
                     Ok(TransactionSchedulingInfo {
                         thread_id,
                         transaction,
                         max_age,
                         cost,
+                        writable_mask,
                     }) => {
                         saturating_add_assign!(num_scheduled, 1);
+                        if max_unschedulable_priority.is_some_and(|max| id.priority < max) {
+                            saturating_add_assign!(num_priority_inversions, 1);
+                        }
+                        if let Some(listener) = &self.scheduling_event_listener {
+                            listener.on_scheduled(id.id, thread_id, cost);
+                        }
+                        self.lock_wait_tracker.track_scheduled(
+                            id.id,
+                            thread_id,
+                            id.priority,
+                            &transaction,
+                        );
                         batches.transactions[thread_id].push(transaction);
                         batches.ids[thread_id].push(id.id);
                         batches.max_ages[thread_id].push(max_age);
+                        batches.writable_masks[thread_id].push(writable_mask);
                         saturating_add_assign!(batches.total_cus[thread_id], cost);
 
                         // If target batch size is reached, send only this batch.
-                        if batches.ids[thread_id].len() >= self.config.target_transactions_per_batch
+                        if !self.config.single_batch_per_thread
+                            && batches.ids[thread_id].len()
+                                >= self.config.target_transactions_per_batch
                         {
                             saturating_add_assign!(
                                 num_sent,
@@ -278,12 +737,21 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
                 }
             }
 
-            // Send all non-empty batches
-            saturating_add_assign!(num_sent, self.send_batches(&mut batches)?);
+            // Send all non-empty batches, unless held back to be coalesced or
+            // until the end of the pass
+            if !self.config.single_batch_per_thread {
+                saturating_add_assign!(num_sent, self.send_or_hold_batches(&mut batches)?);
+            }
 
             // Refresh window budget and do chunked pops
             saturating_add_assign!(window_budget, unblock_this_batch.len());
-            chunked_pops(container, &mut self.prio_graph, &mut window_budget);
+            chunked_pops(
+                container,
+                &mut self.prio_graph,
+                &mut window_budget,
+                self.scheduling_event_listener.as_deref(),
+                (self.clock)(),
+            );
 
             // Unblock all transactions that were blocked by the transactions that were just sent.
             for id in unblock_this_batch.drain(..) {
@@ -291,8 +759,12 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
             }
         }
 
-        // Send batches for any remaining transactions
-        saturating_add_assign!(num_sent, self.send_batches(&mut batches)?);
+        // Send batches for any remaining transactions, unless held back to be coalesced
+        saturating_add_assign!(num_sent, self.send_or_hold_batches(&mut batches)?);
+        let num_held = batches.num_transactions();
+        if num_held > 0 {
+            self.held_batches = Some(batches);
+        }
 
         // Push unschedulable ids back into the container
         container.push_ids_into_queue(unschedulable_ids.into_iter());
@@ -308,45 +780,110 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
         self.prio_graph.clear();
 
         assert_eq!(
-            num_scheduled, num_sent,
+            num_previously_held + num_scheduled,
+            num_sent + num_held,
             "number of scheduled and sent transactions must match"
         );
 
-        Ok(SchedulingSummary {
+        Ok(self.summary_history.record(SchedulingSummary {
             num_scheduled,
             num_unschedulable,
             num_filtered_out,
             filter_time_us: total_filter_time_us,
-        })
+            num_unschedulable_priority_inclusions,
+            num_force_evictions,
+            num_priority_inversions,
+            num_deadline_expired,
+            graph_insertion_time_us: total_graph_insertion_time_us,
+        }))
     }
 
     /// Receive completed batches of transactions without blocking.
-    /// Returns (num_transactions, num_retryable_transactions) on success.
+    /// Returns (num_transactions, retry_counts) on success.
     fn receive_completed(
         &mut self,
         container: &mut impl StateContainer<Tx>,
-    ) -> Result<(usize, usize), SchedulerError> {
+    ) -> Result<(usize, RetryCounts), SchedulerError> {
         let mut total_num_transactions: usize = 0;
-        let mut total_num_retryable: usize = 0;
+        let mut total_retry_counts = RetryCounts::default();
         loop {
-            let (num_transactions, num_retryable) = self.try_receive_completed(container)?;
+            let (num_transactions, retry_counts) = self.try_receive_completed(container)?;
             if num_transactions == 0 {
                 break;
             }
             saturating_add_assign!(total_num_transactions, num_transactions);
-            saturating_add_assign!(total_num_retryable, num_retryable);
+            total_retry_counts.accumulate(&retry_counts);
         }
-        Ok((total_num_transactions, total_num_retryable))
+        // Forget transactions that have since been dropped from the container
+        self.retry_tracker
+            .retain(|id| container.get_mut_transaction_state(id).is_some());
+        self.lock_wait_tracker
+            .retain(|id| container.get_mut_transaction_state(id).is_some());
+        Ok((total_num_transactions, total_retry_counts))
+    }
+
+    fn take_deferred(&mut self) -> Vec<TransactionPriorityId> {
+        std::mem::take(&mut self.deferred)
+    }
+
+    fn flush_held_batches(&mut self) -> Result<usize, SchedulerError> {
+        let Some(mut batches) = self.held_batches.take() else {
+            return Ok(0);
+        };
+        self.send_batches(&mut batches)
+    }
+
+    fn take_completed_cus(&mut self) -> CompletedCus {
+        std::mem::take(&mut self.completed_cus)
+    }
+
+    fn transactions_over_retry_threshold(&self) -> &HashSet<TransactionId> {
+        self.retry_tracker.over_threshold()
+    }
+
+    fn recent_summaries(&self) -> &VecDeque<SchedulingSummary> {
+        self.summary_history.summaries()
+    }
+
+    fn thread_loads(&self) -> Vec<ThreadLoad> {
+        self.in_flight_tracker.thread_loads()
+    }
+
+    fn state_snapshot(&self, container: &impl StateContainer<Tx>) -> SchedulerStateSnapshot {
+        SchedulerStateSnapshot::new(
+            container,
+            self.in_flight_tracker.thread_loads(),
+            self.account_locks.num_locked_accounts(),
+            self.summary_history.last(),
+        )
+    }
+
+    fn config(&self) -> SchedulerConfig {
+        SchedulerConfig::PrioGraph(self.config.clone())
+    }
+
+    fn estimate(
+        &self,
+        container: &impl StateContainer<Tx>,
+        request: &EstimateRequest,
+    ) -> SchedulingEstimate {
+        scheduling_estimate::estimate(
+            request,
+            &self.account_locks,
+            self.in_flight_tracker.cus_in_flight_per_thread(),
+            container,
+            self.config.max_estimate_examined_transactions,
+        )
     }
 }
 
 impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
     /// Receive completed batches of transactions.
-    /// Returns `Ok((num_transactions, num_retryable))` if a batch was received, `Ok((0, 0))` if no batch was received.
+    /// Returns `Ok((num_transactions, retry_counts))` if a batch was received, `Ok((0, RetryCounts::default()))` if no batch was received.
     fn try_receive_completed(
         &mut self,
         container: &mut impl StateContainer<Tx>,
-    ) -> Result<(usize, usize), SchedulerError> {
+    ) -> Result<(usize, RetryCounts), SchedulerError> {
         match self.finished_consume_work_receiver.try_recv() {
             Ok(FinishedConsumeWork {
                 work:
@@ -357,60 +894,235 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
                         max_ages,
                     },
                 retryable_indexes,
+                retryable_reasons,
+                retry_all,
+                actual_cus_used,
             }) => {
                 let num_transactions = ids.len();
-                let num_retryable = retryable_indexes.len();
-
-                // Free the locks
-                self.complete_batch(batch_id, &transactions);
-
-                // Retryable transactions should be inserted back into the container
-                let mut retryable_iter = retryable_indexes.into_iter().peekable();
+                let mut retry_counts = RetryCounts::default();
+                debug_assert_eq!(retryable_indexes.len(), retryable_reasons.len());
+                debug_assert!(!retry_all || retryable_indexes.is_empty());
+
+                // Free the locks, and credit the CUs the batch did not use
+                let reserved_cus =
+                    self.complete_batch(batch_id, &ids, &transactions, actual_cus_used);
+                self.completed_cus.record(reserved_cus, actual_cus_used);
+
+                // Retryable transactions should be inserted back into the container,
+                // unless they hit a block limit, in which case they wait for the next slot
+                let mut retryable_iter = retryable_indexes
+                    .into_iter()
+                    .zip(retryable_reasons)
+                    .peekable();
                 for (index, (id, transaction, max_age)) in
                     izip!(ids, transactions, max_ages).enumerate()
                 {
-                    if let Some(retryable_index) = retryable_iter.peek() {
-                        if *retryable_index == index {
-                            container.retry_transaction(
-                                id,
-                                SanitizedTransactionTTL {
-                                    transaction,
-                                    max_age,
-                                },
-                            );
-                            retryable_iter.next();
-                            continue;
+                    let retry_reason = if retry_all {
+                        Some(RetryReason::Other)
+                    } else {
+                        retryable_iter
+                            .next_if(|(retryable_index, _)| *retryable_index == index)
+                            .map(|(_, reason)| reason)
+                    };
+
+                    // Priority inclusions are not in the container, retry them
+                    // at the top of the next pass instead.
+                    if let Some(cost) = self.in_flight_priority_inclusions.remove(&id) {
+                        if let Some(reason) = retry_reason {
+                            retry_counts.record(reason);
+                            self.priority_inclusions.push((transaction, max_age, cost));
+                        }
+                        continue;
+                    }
+
+                    if let Some(reason) = retry_reason {
+                        retry_counts.record(reason);
+                        self.retry_tracker
+                            .record_retry(id, transaction.message_hash());
+                        let transaction_ttl = SanitizedTransactionTTL {
+                            transaction,
+                            max_age,
+                        };
+                        let mut priority_id = container.defer_transaction(id, transaction_ttl);
+                        if self.config.retryables_first {
+                            priority_id = priority_id.retried();
+                        }
+                        match reason {
+                            RetryReason::BlockLimit => self.deferred.push(priority_id),
+                            RetryReason::AccountInUse | RetryReason::Other => {
+                                container.push_ids_into_queue(std::iter::once(priority_id));
+                            }
                         }
+                        continue;
                     }
-                    container.remove_by_id(id);
+                    self.retry_tracker.remove(id);
+                    container.remove_completed(id, transaction.signature());
                 }
 
-                Ok((num_transactions, num_retryable))
+                Ok((num_transactions, retry_counts))
             }
-            Err(TryRecvError::Empty) => Ok((0, 0)),
+            Err(TryRecvError::Empty) => Ok((0, RetryCounts::default())),
             Err(TryRecvError::Disconnected) => Err(SchedulerError::DisconnectedRecvChannel(
                 "finished consume work",
             )),
         }
     }
 
+    /// Schedule the pending priority inclusions into `batches`, ahead of any
+    /// container transactions.
+    /// Returns (num_scheduled, num_sent, num_unschedulable).
+    fn schedule_priority_inclusions(
+        &mut self,
+        batches: &mut Batches<Tx>,
+        blocking_locks: &mut ReadWriteAccountSet,
+        schedulable_threads: &mut ThreadSet,
+        max_cu_per_thread: u64,
+        pre_lock_filter: &impl Fn(&TransactionState<Tx>) -> PreLockFilterAction,
+    ) -> Result<(usize, usize, usize), SchedulerError> {
+        let num_threads = self.consume_work_senders.len();
+        let mut num_scheduled: usize = 0;
+        let mut num_sent: usize = 0;
+        let mut num_unschedulable: usize = 0;
+        for (transaction, max_age, cost) in std::mem::take(&mut self.priority_inclusions) {
+            if schedulable_threads.is_empty() {
+                saturating_add_assign!(num_unschedulable, 1);
+                self.unscheduled_priority_inclusions
+                    .push((transaction, max_age, cost));
+                continue;
+            }
+
+            // allocated upfront, so that running out of ids takes no lock
+            let in_flight_priority_inclusions = &self.in_flight_priority_inclusions;
+            let Some(id) = self
+                .priority_inclusion_ids
+                .next(in_flight_priority_inclusions.len(), |id| {
+                    in_flight_priority_inclusions.contains_key(&id)
+                })
+            else {
+                saturating_add_assign!(num_unschedulable, 1);
+                self.unscheduled_priority_inclusions
+                    .push((transaction, max_age, cost));
+                continue;
+            };
+
+            let mut transaction_state = TransactionState::new(
+                SanitizedTransactionTTL {
+                    transaction,
+                    max_age,
+                },
+                u64::MAX,
+                cost,
+                None,
+            );
+            let maybe_schedule_info = try_schedule_transaction(
+                &mut transaction_state,
+                pre_lock_filter,
+                &self.clock,
+                blocking_locks,
+                &mut self.account_locks,
+                num_threads,
+                self.config.sticky_account_affinity,
+                |thread_set, sticky_thread| {
+                    if self.config.lowest_thread_id_selection {
+                        return Self::select_lowest_thread(thread_set);
+                    }
+                    let preferred_thread =
+                        sticky_thread.filter(|thread_id| schedulable_threads.contains(*thread_id));
+                    if self.config.weighted_random_thread_selection {
+                        return Self::select_weighted_random_thread(
+                            thread_set,
+                            preferred_thread,
+                            &batches.total_cus,
+                            self.in_flight_tracker.cus_in_flight_per_thread(),
+                            &mut self.rng,
+                        );
+                    }
+                    Self::select_thread(
+                        thread_set,
+                        preferred_thread,
+                        &batches.total_cus,
+                        self.in_flight_tracker.cus_in_flight_per_thread(),
+                        &batches.transactions,
+                        self.in_flight_tracker.num_in_flight_per_thread(),
+                    )
+                },
+            );
+
+            match maybe_schedule_info {
+                Err(_) => {
+                    saturating_add_assign!(num_unschedulable, 1);
+                    let SanitizedTransactionTTL {
+                        transaction,
+                        max_age,
+                    } = transaction_state.transition_to_pending();
+                    self.unscheduled_priority_inclusions
+                        .push((transaction, max_age, cost));
+                }
+                Ok(TransactionSchedulingInfo {
+                    thread_id,
+                    transaction,
+                    max_age,
+                    cost,
+                    writable_mask,
+                }) => {
+                    self.in_flight_priority_inclusions.insert(id, cost);
+
+                    saturating_add_assign!(num_scheduled, 1);
+                    batches.transactions[thread_id].push(transaction);
+                    batches.ids[thread_id].push(id);
+                    batches.max_ages[thread_id].push(max_age);
+                    batches.writable_masks[thread_id].push(writable_mask);
+                    saturating_add_assign!(batches.total_cus[thread_id], cost);
+
+                    if !self.config.single_batch_per_thread
+                        && batches.ids[thread_id].len() >= self.config.target_transactions_per_batch
+                    {
+                        saturating_add_assign!(num_sent, self.send_batch(batches, thread_id)?);
+                    }
+
+                    if self.in_flight_tracker.cus_in_flight_per_thread()[thread_id]
+                        + batches.total_cus[thread_id]
+                        >= max_cu_per_thread
+                    {
+                        schedulable_threads.remove(thread_id);
+                    }
+                }
+            }
+        }
+
+        Ok((num_scheduled, num_sent, num_unschedulable))
+    }
+
     /// Mark a given `TransactionBatchId` as completed.
     /// This will update the internal tracking, including account locks.
-    fn complete_batch(&mut self, batch_id: TransactionBatchId, transactions: &[Tx]) {
-        let thread_id = self.in_flight_tracker.complete_batch(batch_id);
-        for transaction in transactions {
+    /// The CUs the batch reserved and did not use are credited to the
+    /// batches still in flight on its thread.
+    /// Transactions that were force-evicted have already released their locks.
+    /// Returns the CUs reserved by the batch.
+    fn complete_batch(
+        &mut self,
+        batch_id: TransactionBatchId,
+        ids: &[TransactionId],
+        transactions: &[Tx],
+        actual_cus_used: u64,
+    ) -> u64 {
+        let (thread_id, reserved_cus, writable_masks) = self
+            .in_flight_tracker
+            .complete_batch(batch_id, actual_cus_used);
+        for (id, transaction, writable_mask) in izip!(ids, transactions, &writable_masks) {
+            if self.lock_wait_tracker.complete(*id) {
+                continue;
+            }
             let account_keys = transaction.account_keys();
-            let write_account_locks = account_keys
-                .iter()
-                .enumerate()
-                .filter_map(|(index, key)| transaction.is_writable(index).then_some(key));
-            let read_account_locks = account_keys
-                .iter()
-                .enumerate()
-                .filter_map(|(index, key)| (!transaction.is_writable(index)).then_some(key));
-            self.account_locks
-                .unlock_accounts(write_account_locks, read_account_locks, thread_id);
+            self.account_locks.unlock_accounts(
+                writable_mask.write_keys(account_keys.iter()),
+                writable_mask.read_keys(account_keys.iter()),
+                thread_id,
+            );
         }
+        self.in_flight_tracker
+            .recycle_writable_masks(writable_masks);
+        reserved_cus
     }
 
     /// Send all batches of transactions to the worker threads.
@@ -421,6 +1133,29 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
             .sum()
     }
 
+    /// Send all batches of transactions to the worker threads, except those
+    /// below the target size that `batch_coalesce` allows to be held back.
+    /// Returns the number of transactions sent.
+    fn send_or_hold_batches(&mut self, batches: &mut Batches<Tx>) -> Result<usize, SchedulerError> {
+        let Some(batch_coalesce) = self.config.batch_coalesce else {
+            return self.send_batches(batches);
+        };
+
+        let now = Instant::now();
+        let mut num_sent = 0;
+        for thread_index in 0..self.consume_work_senders.len() {
+            let batch_len = batches.ids[thread_index].len();
+            if batch_len > 0 && batch_len < self.config.target_transactions_per_batch {
+                let held_since = self.held_since[thread_index].get_or_insert(now);
+                if now.duration_since(*held_since) < batch_coalesce {
+                    continue;
+                }
+            }
+            saturating_add_assign!(num_sent, self.send_batch(batches, thread_index)?);
+        }
+        Ok(num_sent)
+    }
+
     /// Send a batch of transactions to the given thread's `ConsumeWork` channel.
     /// Returns the number of transactions sent.
     fn send_batch(
@@ -432,12 +1167,19 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
             return Ok(0);
         }
 
-        let (ids, transactions, max_ages, total_cus) =
-            batches.take_batch(thread_index, self.config.target_transactions_per_batch);
-
-        let batch_id = self
+        let writable_masks_buffer = self
             .in_flight_tracker
-            .track_batch(ids.len(), total_cus, thread_index);
+            .take_writable_masks_buffer(self.config.target_transactions_per_batch);
+        let (ids, transactions, max_ages, writable_masks, total_cus) = batches.take_batch(
+            thread_index,
+            self.config.target_transactions_per_batch,
+            writable_masks_buffer,
+        );
+        self.held_since[thread_index] = None;
+
+        let batch_id =
+            self.in_flight_tracker
+                .track_batch(ids.len(), total_cus, thread_index, writable_masks);
 
         let num_scheduled = ids.len();
         let work = ConsumeWork {
@@ -457,18 +1199,27 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
     /// of work queued up.
     /// Currently, "work" is just defined as the number of transactions.
     ///
-    /// If the `chain_thread` is available, this thread will be selected, regardless of
+    /// If the `preferred_thread` is available, this thread will be selected, regardless of
     /// load-balancing.
     ///
+    /// Ties are broken deterministically: among threads with the same queued CUs
+    /// and transactions, the lowest thread id wins.
+    ///
     /// Panics if the `thread_set` is empty. This should never happen, see comment
     /// on `ThreadAwareAccountLocks::try_lock_accounts`.
     pub(crate) fn select_thread(
         thread_set: ThreadSet,
+        preferred_thread: Option<ThreadId>,
         batch_cus_per_thread: &[u64],
         in_flight_cus_per_thread: &[u64],
         batches_per_thread: &[Vec<Tx>],
         in_flight_per_thread: &[usize],
     ) -> ThreadId {
+        if let Some(thread_id) =
+            preferred_thread.filter(|thread_id| thread_set.contains(*thread_id))
+        {
+            return thread_id;
+        }
         thread_set
             .contained_threads_iter()
             .map(|thread_id| {
@@ -478,25 +1229,80 @@ impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
                     batches_per_thread[thread_id].len() + in_flight_per_thread[thread_id],
                 )
             })
-            .min_by(|a, b| a.1.cmp(&b.1).then_with(|| a.2.cmp(&b.2)))
+            .min_by(|a, b| {
+                a.1.cmp(&b.1)
+                    .then_with(|| a.2.cmp(&b.2))
+                    .then_with(|| a.0.cmp(&b.0))
+            })
             .map(|(thread_id, _, _)| thread_id)
             .unwrap()
     }
 
-    /// Gets accessed accounts (resources) for use in `PrioGraph`.
-    fn get_transaction_account_access(
-        transaction: &SanitizedTransactionTTL<impl SVMMessage>,
-    ) -> impl Iterator<Item = (Pubkey, AccessKind)> + '_ {
+    /// Select the lowest thread id of `thread_set`, see
+    /// `PrioGraphSchedulerConfig::lowest_thread_id_selection`.
+    ///
+    /// Panics if the `thread_set` is empty.
+    fn select_lowest_thread(thread_set: ThreadSet) -> ThreadId {
+        thread_set.contained_threads_iter().min().unwrap()
+    }
+
+    /// Select a thread of `thread_set` at random, see
+    /// `PrioGraphSchedulerConfig::weighted_random_thread_selection`. Each
+    /// thread is weighted by how many fewer CUs it has queued than the most
+    /// loaded one, plus one so that every thread may be drawn.
+    ///
+    /// Panics if the `thread_set` is empty.
+    fn select_weighted_random_thread(
+        thread_set: ThreadSet,
+        preferred_thread: Option<ThreadId>,
+        batch_cus_per_thread: &[u64],
+        in_flight_cus_per_thread: &[u64],
+        rng: &mut impl Rng,
+    ) -> ThreadId {
+        if let Some(thread_id) =
+            preferred_thread.filter(|thread_id| thread_set.contains(*thread_id))
+        {
+            return thread_id;
+        }
+        let loads: Vec<_> = thread_set
+            .contained_threads_iter()
+            .map(|thread_id| {
+                (
+                    thread_id,
+                    batch_cus_per_thread[thread_id] + in_flight_cus_per_thread[thread_id],
+                )
+            })
+            .collect();
+        let max_load = loads.iter().map(|(_, load)| *load).max().unwrap();
+        let weight = |load: u64| u128::from(max_load - load) + 1;
+        let mut draw = rng.gen_range(0..loads.iter().map(|(_, load)| weight(*load)).sum::<u128>());
+        for (thread_id, load) in &loads {
+            if draw < weight(*load) {
+                return *thread_id;
+            }
+            draw -= weight(*load);
+        }
+        unreachable!("the draw is below the sum of the weights")
+    }
+
+    /// Gets accessed accounts (resources) for use in `PrioGraph`, leaving out
+    /// the reads of accounts pruned by `readonly_fanin`.
+    fn get_transaction_account_access<'a>(
+        transaction: &'a SanitizedTransactionTTL<impl SVMMessage>,
+        readonly_fanin: &'a ReadonlyFanin,
+    ) -> impl Iterator<Item = (Pubkey, AccessKind)> + 'a {
         let message = &transaction.transaction;
         message
             .account_keys()
             .iter()
             .enumerate()
-            .map(|(index, key)| {
+            .filter_map(|(index, key)| {
                 if message.is_writable(index) {
-                    (*key, AccessKind::Write)
+                    Some((*key, AccessKind::Write))
+                } else if readonly_fanin.is_pruned(key) {
+                    None
                 } else {
-                    (*key, AccessKind::Read)
+                    Some((*key, AccessKind::Read))
                 }
             })
     }
@@ -506,6 +1312,7 @@ pub(crate) struct Batches<Tx> {
     pub ids: Vec<Vec<TransactionId>>,
     pub transactions: Vec<Vec<Tx>>,
     pub max_ages: Vec<Vec<MaxAge>>,
+    pub writable_masks: Vec<Vec<WritableAccountMask>>,
     pub total_cus: Vec<u64>,
 }
 
@@ -518,15 +1325,32 @@ impl<Tx> Batches<Tx> {
                 .map(|_| Vec::with_capacity(target_num_transactions_per_batch))
                 .collect(),
             max_ages: vec![Vec::with_capacity(target_num_transactions_per_batch); num_threads],
+            writable_masks: vec![
+                Vec::with_capacity(target_num_transactions_per_batch);
+                num_threads
+            ],
             total_cus: vec![0; num_threads],
         }
     }
 
+    pub(crate) fn num_transactions(&self) -> usize {
+        self.ids.iter().map(Vec::len).sum()
+    }
+
+    /// Take the batch of `thread_id`, leaving empty buffers in its place,
+    /// `writable_masks_buffer` for the writable masks.
     pub(crate) fn take_batch(
         &mut self,
         thread_id: ThreadId,
         target_num_transactions_per_batch: usize,
-    ) -> (Vec<TransactionId>, Vec<Tx>, Vec<MaxAge>, u64) {
+        writable_masks_buffer: Vec<WritableAccountMask>,
+    ) -> (
+        Vec<TransactionId>,
+        Vec<Tx>,
+        Vec<MaxAge>,
+        Vec<WritableAccountMask>,
+        u64,
+    ) {
         (
             core::mem::replace(
                 &mut self.ids[thread_id],
@@ -540,6 +1364,7 @@ impl<Tx> Batches<Tx> {
                 &mut self.max_ages[thread_id],
                 Vec::with_capacity(target_num_transactions_per_batch),
             ),
+            core::mem::replace(&mut self.writable_masks[thread_id], writable_masks_buffer),
             core::mem::replace(&mut self.total_cus[thread_id], 0),
         )
     }
@@ -551,29 +1376,42 @@ pub(crate) struct TransactionSchedulingInfo<Tx> {
     pub transaction: Tx,
     pub max_age: MaxAge,
     pub cost: u64,
+    /// The writable account keys locked for the transaction, to release the
+    /// same locks when its batch completes.
+    pub writable_mask: WritableAccountMask,
 }
 
 /// Error type for reasons a transaction could not be scheduled.
+#[derive(Debug, PartialEq, Eq)]
 pub(crate) enum TransactionSchedulingError {
     /// Transaction cannot be scheduled due to conflicts, or
     /// higher priority conflicting transactions are unschedulable.
     UnschedulableConflicts,
     /// Thread is not allowed to be scheduled on at this time.
     UnschedulableThread,
+    /// The deadline of the transaction has passed.
+    DeadlineExpired,
 }
 
 fn try_schedule_transaction<Tx: TransactionWithMeta>(
     transaction_state: &mut TransactionState<Tx>,
     pre_lock_filter: impl Fn(&TransactionState<Tx>) -> PreLockFilterAction,
+    clock: impl Fn() -> Instant,
     blocking_locks: &mut ReadWriteAccountSet,
     account_locks: &mut ThreadAwareAccountLocks,
     num_threads: usize,
-    thread_selector: impl Fn(ThreadSet) -> ThreadId,
+    sticky_account_affinity: bool,
+    mut thread_selector: impl FnMut(ThreadSet, Option<ThreadId>) -> ThreadId,
 ) -> Result<TransactionSchedulingInfo<Tx>, TransactionSchedulingError> {
     match pre_lock_filter(transaction_state) {
         PreLockFilterAction::AttemptToSchedule => {}
     }
 
+    // The clock is only read for transactions with a deadline.
+    if transaction_state.deadline().is_some() && transaction_state.is_deadline_expired(clock()) {
+        return Err(TransactionSchedulingError::DeadlineExpired);
+    }
+
     // Check if this transaction conflicts with any blocked transactions
     let transaction = &transaction_state.transaction_ttl().transaction;
     if !blocking_locks.check_locks(transaction) {
@@ -583,20 +1421,26 @@ fn try_schedule_transaction<Tx: TransactionWithMeta>(
 
     // Schedule the transaction if it can be.
     let account_keys = transaction.account_keys();
-    let write_account_locks = account_keys
-        .iter()
-        .enumerate()
-        .filter_map(|(index, key)| transaction.is_writable(index).then_some(key));
-    let read_account_locks = account_keys
-        .iter()
-        .enumerate()
-        .filter_map(|(index, key)| (!transaction.is_writable(index)).then_some(key));
+    let writable_mask = WritableAccountMask::new(transaction);
+    let write_account_locks = writable_mask.write_keys(account_keys.iter());
+    let read_account_locks = writable_mask.read_keys(account_keys.iter());
+    // Programs are shared by too many transactions to be worth sticking to.
+    let sticky_thread = sticky_account_affinity
+        .then(|| {
+            account_locks.dominant_account_thread(
+                account_keys
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, key)| (!transaction.is_invoked(index)).then_some(key)),
+            )
+        })
+        .flatten();
 
     let thread_id = match account_locks.try_lock_accounts(
         write_account_locks,
         read_account_locks,
         ThreadSet::any(num_threads),
-        thread_selector,
+        |thread_set| thread_selector(thread_set, sticky_thread),
     ) {
         Ok(thread_id) => thread_id,
         Err(TryLockError::MultipleConflicts) => {
@@ -617,6 +1461,7 @@ fn try_schedule_transaction<Tx: TransactionWithMeta>(
         transaction: sanitized_transaction_ttl.transaction,
         max_age: sanitized_transaction_ttl.max_age,
         cost,
+        writable_mask,
     })
 }
 
@@ -631,16 +1476,26 @@ mod tests {
         solana_sdk::{
             compute_budget::ComputeBudgetInstruction,
             hash::Hash,
+            instruction::{AccountMeta, Instruction},
             message::Message,
             pubkey::Pubkey,
             signature::Keypair,
             signer::Signer,
-            system_instruction,
+            system_instruction, system_program,
             transaction::{SanitizedTransaction, Transaction},
         },
-        std::borrow::Borrow,
+        std::{
+            borrow::Borrow,
+            cmp::Reverse,
+            sync::{
+                atomic::{AtomicU64, AtomicUsize, Ordering},
+                Arc, Mutex,
+            },
+        },
     };
 
+    const TEST_TRANSACTION_COST: u64 = 5000;
+
     #[allow(clippy::type_complexity)]
     fn create_test_frame(
         num_threads: usize,
@@ -720,11 +1575,11 @@ mod tests {
                 transaction,
                 max_age: MaxAge::MAX,
             };
-            const TEST_TRANSACTION_COST: u64 = 5000;
             container.insert_new_transaction(
                 transaction_ttl,
                 compute_unit_price,
                 TEST_TRANSACTION_COST,
+                None,
             );
         }
 
@@ -748,6 +1603,7 @@ mod tests {
 
     fn test_pre_graph_filter(
         _txs: &[&RuntimeTransaction<SanitizedTransaction>],
+        _max_ages: &[MaxAge],
         results: &mut [bool],
     ) {
         results.fill(true);
@@ -771,6 +1627,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_schedule_duplicate_once() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(1);
+        let mut container = create_container([(&Keypair::new(), &[Pubkey::new_unique()], 1, 3)]);
+
+        // the same transaction received twice, with differing priorities
+        let keypair = Keypair::new();
+        let to_pubkey = Pubkey::new_unique();
+        for priority in [1, 5] {
+            container.insert_new_transaction(
+                SanitizedTransactionTTL {
+                    transaction: prioritized_tranfers(&keypair, [to_pubkey], 1, 1),
+                    max_age: MaxAge::MAX,
+                },
+                priority,
+                TEST_TRANSACTION_COST,
+                None,
+            );
+        }
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        // the duplicate is scheduled once, ahead of the transaction of
+        // priority 3, with the higher of its priorities
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![1, 0]]);
+        assert_eq!(
+            container.get_mut_transaction_state(1).unwrap().priority(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_schedule_strict_priority_mode() {
+        let (consume_work_sender, consume_work_receiver) = unbounded();
+        let (_finished_work_sender, finished_work_receiver) = unbounded();
+        let mut scheduler =
+            PrioGraphScheduler::strict_priority_mode(consume_work_sender, finished_work_receiver);
+
+        // a mix of conflicting and non-conflicting transactions, inserted out
+        // of priority order
+        let hot_pubkey = Pubkey::new_unique();
+        let priorities = [3, 9, 1, 7, 5, 8, 2, 6, 4, 10];
+        let mut container = create_container(priorities.iter().map(|&priority| {
+            let to_pubkey = if priority % 3 == 0 {
+                hot_pubkey
+            } else {
+                Pubkey::new_unique()
+            };
+            (Keypair::new(), [to_pubkey], 1, priority)
+        }));
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, priorities.len());
+        assert_eq!(scheduling_summary.num_unschedulable, 0);
+
+        // one transaction per batch, in descending priority
+        let expected_ids = priorities
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_, &priority)| Reverse(priority))
+            .map(|(id, _)| vec![id])
+            .collect_vec();
+        assert_eq!(collect_work(&consume_work_receiver).1, expected_ids);
+    }
+
     #[test]
     fn test_schedule_single_threaded_no_conflicts() {
         let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(1);
@@ -830,22 +1755,328 @@ mod tests {
     }
 
     #[test]
-    fn test_schedule_simple_thread_selection() {
+    fn test_schedule_single_batch_per_thread() {
         let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(2);
-        let mut container =
-            create_container((0..4).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, i)));
+        scheduler.config.single_batch_per_thread = true;
+        let mut container = create_container(
+            (0..4 * TARGET_NUM_TRANSACTIONS_PER_BATCH)
+                .map(|i| (Keypair::new(), [Pubkey::new_unique()], i as u64, 1)),
+        );
 
         let scheduling_summary = scheduler
             .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
             .unwrap();
-        assert_eq!(scheduling_summary.num_scheduled, 4);
-        assert_eq!(scheduling_summary.num_unschedulable, 0);
-        assert_eq!(collect_work(&work_receivers[0]).1, [vec![3, 1]]);
-        assert_eq!(collect_work(&work_receivers[1]).1, [vec![2, 0]]);
-    }
-
-    #[test]
-    fn test_schedule_priority_guard() {
+        assert_eq!(
+            scheduling_summary.num_scheduled,
+            4 * TARGET_NUM_TRANSACTIONS_PER_BATCH
+        );
+
+        // each thread overflows the target, yet gets a single batch
+        for work_receiver in &work_receivers {
+            let work_counts: Vec<_> = work_receiver
+                .try_iter()
+                .map(|work| work.ids.len())
+                .collect();
+            assert_eq!(work_counts, [2 * TARGET_NUM_TRANSACTIONS_PER_BATCH]);
+        }
+
+        // conflicting transactions land in the same batch too
+        let pubkey = Pubkey::new_unique();
+        let mut container = create_container([
+            (&Keypair::new(), &[pubkey], 1, 1),
+            (&Keypair::new(), &[pubkey], 1, 2),
+        ]);
+        scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        let batches: Vec<_> = work_receivers
+            .iter()
+            .flat_map(|work_receiver| collect_work(work_receiver).1)
+            .collect();
+        assert_eq!(batches, [vec![1, 0]]);
+    }
+
+    #[test]
+    fn test_schedule_batch_coalesce() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(1);
+        scheduler.config.batch_coalesce = Some(Duration::from_secs(3600));
+        let mut container = create_container([(&Keypair::new(), &[Pubkey::new_unique()], 1, 1)]);
+
+        // a batch below the target size is held rather than sent
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+        assert!(collect_work(&work_receivers[0]).1.is_empty());
+
+        // and topped up by the following passes
+        let transaction = prioritized_tranfers(&Keypair::new(), [Pubkey::new_unique()], 1, 2);
+        container.insert_new_transaction(
+            SanitizedTransactionTTL {
+                transaction,
+                max_age: MaxAge::MAX,
+            },
+            2,
+            TEST_TRANSACTION_COST,
+            None,
+        );
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+        assert!(collect_work(&work_receivers[0]).1.is_empty());
+
+        // until the coalesce delay is over
+        scheduler.config.batch_coalesce = Some(Duration::ZERO);
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 0);
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_flush_held_batches() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(1);
+        scheduler.config.batch_coalesce = Some(Duration::from_secs(3600));
+        let mut container = create_container([(&Keypair::new(), &[Pubkey::new_unique()], 1, 1)]);
+        assert_eq!(scheduler.flush_held_batches().unwrap(), 0);
+
+        scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert!(collect_work(&work_receivers[0]).1.is_empty());
+
+        // the held batch is sent as the working bank changes, whatever the delay
+        assert_eq!(scheduler.flush_held_batches().unwrap(), 1);
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![0]]);
+        assert_eq!(scheduler.flush_held_batches().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_schedule_bulk_inserted() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(2);
+        let mut container = TransactionStateContainer::with_capacity(100 * 1024);
+        let ids = container.insert_new_transactions((0..2_000).map(|i| {
+            let transaction = prioritized_tranfers(&Keypair::new(), [Pubkey::new_unique()], 1, i);
+            let transaction_ttl = SanitizedTransactionTTL {
+                transaction,
+                max_age: MaxAge::MAX,
+            };
+            (transaction_ttl, i, 1)
+        }));
+        assert_eq!(ids.len(), 2_000);
+
+        let mut scheduled = HashSet::new();
+        while !container.is_empty() {
+            let scheduling_summary = scheduler
+                .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+                .unwrap();
+            assert!(scheduling_summary.num_scheduled > 0);
+            for work_receiver in &work_receivers {
+                scheduled.extend(collect_work(work_receiver).1.into_iter().flatten());
+            }
+        }
+        assert_eq!(scheduled, ids.into_iter().collect());
+    }
+
+    #[test]
+    fn test_schedule_simple_thread_selection() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(2);
+        let mut container =
+            create_container((0..4).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, i)));
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 4);
+        assert_eq!(scheduling_summary.num_unschedulable, 0);
+        assert_eq!(collect_work(&work_receivers[0]).1, [vec![3, 1]]);
+        assert_eq!(collect_work(&work_receivers[1]).1, [vec![2, 0]]);
+    }
+
+    #[test]
+    fn test_select_thread_tie_break() {
+        type Tx = RuntimeTransaction<SanitizedTransaction>;
+        let batches: Vec<Vec<Tx>> = (0..4).map(|_| vec![]).collect();
+        let in_flight = [0; 4];
+
+        // a full tie goes to the lowest thread id
+        let no_cus = [0; 4];
+        assert_eq!(
+            PrioGraphScheduler::<Tx>::select_thread(
+                ThreadSet::any(4),
+                None,
+                &no_cus,
+                &no_cus,
+                &batches,
+                &in_flight,
+            ),
+            0
+        );
+
+        // threads 1 and 3 tie on the least load, in a set without thread 0
+        let mut thread_set = ThreadSet::any(4);
+        thread_set.remove(0);
+        let batch_cus = [0, 5, 7, 5];
+        assert_eq!(
+            PrioGraphScheduler::<Tx>::select_thread(
+                thread_set, None, &batch_cus, &no_cus, &batches, &in_flight,
+            ),
+            1
+        );
+        thread_set.remove(1);
+        assert_eq!(
+            PrioGraphScheduler::<Tx>::select_thread(
+                thread_set, None, &batch_cus, &no_cus, &batches, &in_flight,
+            ),
+            3
+        );
+    }
+
+    #[test]
+    fn test_lowest_thread_id_selection() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(2);
+        scheduler.config.lowest_thread_id_selection = true;
+        let mut container =
+            create_container((0..4).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, i)));
+
+        // unlike `test_schedule_simple_thread_selection`, nothing is balanced
+        // onto the second thread
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 4);
+        assert_eq!(collect_work(&work_receivers[0]).1, [vec![3, 2, 1, 0]]);
+        assert!(collect_work(&work_receivers[1]).1.is_empty());
+    }
+
+    #[test]
+    fn test_weighted_random_thread_selection_seed() {
+        let thread_assignments = |rng_seed| {
+            let (consume_work_senders, work_receivers): (Vec<_>, Vec<_>) =
+                (0..4).map(|_| unbounded()).unzip();
+            let (_finished_work_sender, finished_work_receiver) = unbounded();
+            let mut scheduler = PrioGraphScheduler::new(
+                consume_work_senders,
+                finished_work_receiver,
+                PrioGraphSchedulerConfig {
+                    weighted_random_thread_selection: true,
+                    rng_seed: Some(rng_seed),
+                    ..PrioGraphSchedulerConfig::default()
+                },
+            );
+            assert_eq!(
+                scheduler.config(),
+                SchedulerConfig::PrioGraph(PrioGraphSchedulerConfig {
+                    weighted_random_thread_selection: true,
+                    rng_seed: Some(rng_seed),
+                    ..PrioGraphSchedulerConfig::default()
+                })
+            );
+            let mut container =
+                create_container((0..64).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, i)));
+            let scheduling_summary = scheduler
+                .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+                .unwrap();
+            assert_eq!(scheduling_summary.num_scheduled, 64);
+            work_receivers
+                .iter()
+                .map(|work_receiver| collect_work(work_receiver).1)
+                .collect::<Vec<_>>()
+        };
+
+        // the same seed and input reproduce the same assignments
+        assert_eq!(thread_assignments(42), thread_assignments(42));
+        assert_ne!(thread_assignments(42), thread_assignments(43));
+
+        // a generated seed is reported in place of the missing one
+        let (scheduler, _work_receivers, _finished_work_sender) = create_test_frame(1);
+        let SchedulerConfig::PrioGraph(config) = scheduler.config() else {
+            panic!("prio-graph scheduler reports a prio-graph config");
+        };
+        assert!(config.rng_seed.is_some());
+    }
+
+    #[test]
+    fn test_thread_loads() {
+        let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(2);
+        let mut container =
+            create_container((0..4).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, i)));
+        assert_eq!(scheduler.thread_loads(), [ThreadLoad::default(); 2]);
+
+        scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        let (_, thread_0_ids) = collect_work(&work_receivers[0]);
+        let (thread_1_work, thread_1_ids) = collect_work(&work_receivers[1]);
+        assert_eq!(thread_0_ids, [vec![3, 1]]);
+        assert_eq!(thread_1_ids, [vec![2, 0]]);
+        let busy = ThreadLoad {
+            in_flight_cus: 2 * TEST_TRANSACTION_COST,
+            in_flight_count: 2,
+        };
+        assert_eq!(scheduler.thread_loads(), [busy, busy]);
+
+        // completing the batch of thread 1 only frees thread 1
+        finished_work_sender
+            .send(FinishedConsumeWork {
+                work: thread_1_work.into_iter().next().unwrap(),
+                retryable_indexes: vec![],
+                retryable_reasons: vec![],
+                retry_all: false,
+                actual_cus_used: 0,
+            })
+            .unwrap();
+        scheduler.receive_completed(&mut container).unwrap();
+        assert_eq!(scheduler.thread_loads(), [busy, ThreadLoad::default()]);
+    }
+
+    #[test]
+    fn test_state_snapshot() {
+        let (mut scheduler, _work_receivers, _finished_work_sender) = create_test_frame(2);
+        let mut container =
+            create_container((0..4).map(|i| (Keypair::new(), [Pubkey::new_unique()], 1, i)));
+        assert_eq!(
+            scheduler.state_snapshot(&container).last_summary,
+            SchedulingSummary::default()
+        );
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        // two transactions received after the pass wait in the queue
+        for priority in [1, 2] {
+            let transaction =
+                prioritized_tranfers(&Keypair::new(), [Pubkey::new_unique()], 1, priority);
+            container.insert_new_transaction(
+                SanitizedTransactionTTL {
+                    transaction,
+                    max_age: MaxAge::MAX,
+                },
+                priority,
+                TEST_TRANSACTION_COST,
+                None,
+            );
+        }
+
+        let snapshot = scheduler.state_snapshot(&container);
+        assert_eq!(snapshot.queue_depth, 2);
+        assert_eq!(snapshot.queued_cus, 2 * TEST_TRANSACTION_COST);
+        let busy = ThreadLoad {
+            in_flight_cus: 2 * TEST_TRANSACTION_COST,
+            in_flight_count: 2,
+        };
+        assert_eq!(snapshot.thread_loads, [busy, busy]);
+        // the payer and recipient of each transaction, with the system and
+        // compute budget programs shared by all of them
+        assert_eq!(snapshot.num_locked_accounts, 4 * 2 + 2);
+        assert!(snapshot.oldest_transaction_age_us.is_some());
+        assert_eq!(snapshot.last_summary, scheduling_summary);
+    }
+
+    #[test]
+    fn test_schedule_priority_guard() {
         let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(2);
         // intentionally shorten the look-ahead window to cause unschedulable conflicts
         scheduler.config.look_ahead_window_size = 2;
@@ -897,6 +2128,9 @@ mod tests {
             .send(FinishedConsumeWork {
                 work: thread_0_work.into_iter().next().unwrap(),
                 retryable_indexes: vec![],
+                retryable_reasons: vec![],
+                retry_all: false,
+                actual_cus_used: 0,
             })
             .unwrap();
         scheduler.receive_completed(&mut container).unwrap();
@@ -909,6 +2143,1057 @@ mod tests {
         assert_eq!(collect_work(&work_receivers[1]).1, [vec![4], vec![5]]);
     }
 
+    #[test]
+    fn test_schedule_priority_inversions() {
+        let (mut scheduler, _work_receivers, _finished_work_sender) = create_test_frame(2);
+        // intentionally shorten the look-ahead window to cause unschedulable conflicts
+        scheduler.config.look_ahead_window_size = 2;
+
+        let accounts = (0..6).map(|_| Keypair::new()).collect_vec();
+        let mut container = create_container([
+            (&accounts[0], &[accounts[1].pubkey()], 1, 4),
+            (&accounts[2], &[accounts[3].pubkey()], 1, 3),
+            (&accounts[1], &[accounts[2].pubkey()], 1, 2),
+            (&accounts[4], &[accounts[5].pubkey()], 1, 1),
+        ]);
+
+        // [0] and [1] are scheduled onto different threads, making [2]
+        // unschedulable. [3] does not conflict with anything and is scheduled
+        // ahead of the higher-priority [2].
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+        assert_eq!(scheduling_summary.num_unschedulable, 1);
+        assert_eq!(scheduling_summary.num_priority_inversions, 1);
+    }
+
+    fn prioritized_read(
+        from_keypair: &Keypair,
+        account: &Pubkey,
+        priority: u64,
+    ) -> RuntimeTransaction<SanitizedTransaction> {
+        let ixs = vec![
+            Instruction::new_with_bytes(
+                system_program::id(),
+                &[],
+                vec![AccountMeta::new_readonly(*account, false)],
+            ),
+            ComputeBudgetInstruction::set_compute_unit_price(priority),
+        ];
+        let message = Message::new(&ixs, Some(&from_keypair.pubkey()));
+        let tx = Transaction::new(&[from_keypair], message, Hash::default());
+        RuntimeTransaction::from_transaction_for_tests(tx)
+    }
+
+    /// Readers of `hot_account` on top of `create_container()`, prioritized
+    /// above the transfers.
+    fn create_container_with_readers(
+        hot_account: &Pubkey,
+        num_readers: u64,
+        tx_infos: impl IntoIterator<
+            Item = (
+                impl Borrow<Keypair>,
+                impl IntoIterator<Item = impl Borrow<Pubkey>>,
+                u64,
+                u64,
+            ),
+        >,
+    ) -> TransactionStateContainer<RuntimeTransaction<SanitizedTransaction>> {
+        let mut container = create_container(tx_infos);
+        for index in 0..num_readers {
+            let priority = 1000 - index;
+            container.insert_new_transaction(
+                SanitizedTransactionTTL {
+                    transaction: prioritized_read(&Keypair::new(), hot_account, priority),
+                    max_age: MaxAge::MAX,
+                },
+                priority,
+                TEST_TRANSACTION_COST,
+                None,
+            );
+        }
+        container
+    }
+
+    #[test]
+    fn test_readonly_fanin_pruning_keeps_outcomes() {
+        let hot_account = Pubkey::new_unique();
+        let payers = (0..4).map(|_| Keypair::new()).collect_vec();
+        let tx_infos = [
+            (&payers[0], vec![hot_account], 1, 5),
+            (&payers[1], vec![Pubkey::new_unique()], 1, 4),
+            (&payers[2], vec![payers[1].pubkey()], 1, 3),
+            (&payers[3], vec![hot_account], 1, 2),
+        ];
+
+        let outcome = |readonly_fanin_threshold| {
+            let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(2);
+            scheduler.config.readonly_fanin_threshold = readonly_fanin_threshold;
+            let mut container = create_container_with_readers(&hot_account, 6, tx_infos.clone());
+            let scheduling_summary = scheduler
+                .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+                .unwrap();
+            (
+                scheduling_summary.num_scheduled,
+                scheduling_summary.num_unschedulable,
+                work_receivers
+                    .iter()
+                    .map(|receiver| collect_work(receiver).1)
+                    .collect_vec(),
+            )
+        };
+
+        // The system and compute budget programs, and `hot_account` until it
+        // is written, are pruned with a threshold of 1.
+        assert_eq!(outcome(DEFAULT_READONLY_FANIN_THRESHOLD), outcome(1));
+    }
+
+    #[test]
+    fn test_readonly_fanin_writer_serialized_by_locks() {
+        let (mut scheduler, _work_receivers, _finished_work_sender) = create_test_frame(2);
+        scheduler.config.readonly_fanin_threshold = 1;
+
+        let hot_account = Pubkey::new_unique();
+        let mut container =
+            create_container_with_readers(&hot_account, 4, [(Keypair::new(), [hot_account], 1, 1)]);
+
+        // The readers are spread over both threads, so the writer cannot take
+        // its write lock even though most readers have no edge to it.
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 4);
+        assert_eq!(scheduling_summary.num_unschedulable, 1);
+    }
+
+    #[derive(Default)]
+    struct MockSchedulingEventListener {
+        num_scheduled: AtomicUsize,
+        scheduled_cus: AtomicU64,
+        num_unschedulable: AtomicUsize,
+        num_filtered: AtomicUsize,
+        deadline_expired: Mutex<Vec<TransactionId>>,
+    }
+
+    impl SchedulingEventListener<RuntimeTransaction<SanitizedTransaction>>
+        for Arc<MockSchedulingEventListener>
+    {
+        fn on_scheduled(&self, _tx_id: TransactionId, _thread_id: ThreadId, cus: u64) {
+            self.num_scheduled.fetch_add(1, Ordering::Relaxed);
+            self.scheduled_cus.fetch_add(cus, Ordering::Relaxed);
+        }
+
+        fn on_unschedulable(&self, _tx_id: TransactionId, _reason: &TransactionSchedulingError) {
+            self.num_unschedulable.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_filtered(&self, _tx_id: TransactionId) {
+            self.num_filtered.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_deadline_expired(&self, tx_id: TransactionId) {
+            self.deadline_expired.lock().unwrap().push(tx_id);
+        }
+    }
+
+    #[test]
+    fn test_sticky_account_affinity() {
+        let hot_account = Pubkey::new_unique();
+        let scheduled_per_thread = |sticky_account_affinity| {
+            let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(2);
+            scheduler.config.sticky_account_affinity = sticky_account_affinity;
+            let mut container = create_container_with_readers(
+                &hot_account,
+                6,
+                Vec::<(Keypair, [Pubkey; 1], u64, u64)>::new(),
+            );
+            let scheduling_summary = scheduler
+                .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+                .unwrap();
+            assert_eq!(scheduling_summary.num_scheduled, 6);
+            work_receivers
+                .iter()
+                .map(|receiver| collect_work(receiver).1.concat().len())
+                .collect_vec()
+        };
+
+        // The readers are load-balanced across the threads by default, and
+        // all follow the read locks of the first one with the affinity.
+        assert_eq!(scheduled_per_thread(false), [3, 3]);
+        assert_eq!(scheduled_per_thread(true), [6, 0]);
+    }
+
+    #[test]
+    fn test_scheduling_event_listener() {
+        let listener = Arc::new(MockSchedulingEventListener::default());
+        let (scheduler, _work_receivers, _finished_work_sender) = create_test_frame(2);
+        let mut scheduler = scheduler.with_scheduling_event_listener(Box::new(listener.clone()));
+        // shorten the look-ahead window to cause unschedulable conflicts,
+        // see `test_schedule_priority_guard`
+        scheduler.config.look_ahead_window_size = 2;
+
+        let accounts = (0..8).map(|_| Keypair::new()).collect_vec();
+        let filtered_payer = Keypair::new();
+        let mut container = create_container([
+            (&accounts[0], &[accounts[1].pubkey()], 1, 6),
+            (&accounts[2], &[accounts[3].pubkey()], 1, 5),
+            (&accounts[4], &[accounts[5].pubkey()], 1, 4),
+            (&accounts[6], &[accounts[7].pubkey()], 1, 3),
+            (&accounts[1], &[accounts[2].pubkey()], 1, 2),
+            (&accounts[2], &[accounts[3].pubkey()], 1, 1),
+            (&filtered_payer, &[Pubkey::new_unique()], 1, 0),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(
+                &mut container,
+                |txs, results| {
+                    for (tx, result) in txs.iter().zip(results.iter_mut()) {
+                        *result = *tx.fee_payer() != filtered_payer.pubkey();
+                    }
+                },
+                test_pre_lock_filter,
+            )
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 4);
+        assert_eq!(scheduling_summary.num_unschedulable, 2);
+        assert_eq!(scheduling_summary.num_filtered_out, 1);
+
+        assert_eq!(
+            listener.num_scheduled.load(Ordering::Relaxed),
+            scheduling_summary.num_scheduled
+        );
+        assert_eq!(
+            listener.scheduled_cus.load(Ordering::Relaxed),
+            scheduling_summary.num_scheduled as u64 * TEST_TRANSACTION_COST
+        );
+        assert_eq!(
+            listener.num_unschedulable.load(Ordering::Relaxed),
+            scheduling_summary.num_unschedulable
+        );
+        assert_eq!(
+            listener.num_filtered.load(Ordering::Relaxed),
+            scheduling_summary.num_filtered_out
+        );
+    }
+
+    #[test]
+    fn test_deadlines() {
+        let listener = Arc::new(MockSchedulingEventListener::default());
+        let start = Instant::now();
+        let elapsed_ms = Arc::new(AtomicU64::new(0));
+        let (scheduler, work_receivers, _finished_work_sender) = create_test_frame(1);
+        let mut scheduler = scheduler
+            .with_scheduling_event_listener(Box::new(listener.clone()))
+            .with_clock(Box::new({
+                let elapsed_ms = elapsed_ms.clone();
+                move || start + Duration::from_millis(elapsed_ms.load(Ordering::Relaxed))
+            }));
+
+        // (priority, deadline), inserted with ids 0..4
+        let mut container = TransactionStateContainer::with_capacity(16);
+        for (priority, deadline) in [
+            (3, None),
+            (4, Some(start)),
+            (2, Some(start + Duration::from_millis(10))),
+            (1, Some(start + Duration::from_secs(3600))),
+        ] {
+            let transaction =
+                prioritized_tranfers(&Keypair::new(), [Pubkey::new_unique()], 1, priority);
+            container.insert_new_transaction(
+                SanitizedTransactionTTL {
+                    transaction,
+                    max_age: MaxAge::MAX,
+                },
+                priority,
+                TEST_TRANSACTION_COST,
+                deadline,
+            );
+        }
+
+        // time passes between the insertion into the prio-graph and the
+        // lock acquisition
+        let pre_locked = Mutex::new(vec![]);
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, |state| {
+                pre_locked.lock().unwrap().push(state.priority());
+                elapsed_ms.store(20, Ordering::Relaxed);
+                PreLockFilterAction::AttemptToSchedule
+            })
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        assert_eq!(scheduling_summary.num_deadline_expired, 2);
+
+        // the expired transaction was dropped before reaching the lock
+        // acquisition, the near one right before it, and priority ordering
+        // was not affected by the deadlines
+        assert_eq!(*pre_locked.lock().unwrap(), [3, 2, 1]);
+        assert_eq!(*listener.deadline_expired.lock().unwrap(), [1, 2]);
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![0, 3]]);
+        assert!(container.get_mut_transaction_state(1).is_none());
+        assert!(container.get_mut_transaction_state(2).is_none());
+    }
+
+    #[test]
+    fn test_retry_threshold() {
+        let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(1);
+        scheduler.retry_tracker = RetryTracker::new(2);
+        let mut container = create_container([(Keypair::new(), [Pubkey::new_unique()], 1, 1)]);
+
+        let mut retry_once = |container: &mut TransactionStateContainer<_>| {
+            scheduler
+                .schedule(container, test_pre_graph_filter, test_pre_lock_filter)
+                .unwrap();
+            let work = work_receivers[0].try_recv().unwrap();
+            let id = work.ids[0];
+            finished_work_sender
+                .send(FinishedConsumeWork {
+                    work,
+                    retryable_indexes: vec![0],
+                    retryable_reasons: vec![RetryReason::AccountInUse],
+                    retry_all: false,
+                    actual_cus_used: 0,
+                })
+                .unwrap();
+            let (num_transactions, retry_counts) = scheduler.receive_completed(container).unwrap();
+            assert_eq!(num_transactions, 1);
+            assert_eq!(retry_counts.account_in_use, 1);
+            id
+        };
+        retry_once(&mut container);
+        retry_once(&mut container);
+        assert!(scheduler.transactions_over_retry_threshold().is_empty());
+        let id = retry_once(&mut container);
+        assert_eq!(
+            scheduler.transactions_over_retry_threshold(),
+            &HashSet::from([id])
+        );
+
+        // Once the transaction completes it is no longer tracked
+        scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        finished_work_sender
+            .send(FinishedConsumeWork {
+                work: work_receivers[0].try_recv().unwrap(),
+                retryable_indexes: vec![],
+                retryable_reasons: vec![],
+                retry_all: false,
+                actual_cus_used: 0,
+            })
+            .unwrap();
+        assert_eq!(
+            scheduler.receive_completed(&mut container).unwrap(),
+            (1, RetryCounts::default())
+        );
+        assert!(scheduler.transactions_over_retry_threshold().is_empty());
+    }
+
+    fn priority_inclusion(
+        from_keypair: &Keypair,
+        to_pubkeys: impl IntoIterator<Item = impl Borrow<Pubkey>>,
+    ) -> PriorityInclusion<RuntimeTransaction<SanitizedTransaction>> {
+        (
+            prioritized_tranfers(from_keypair, to_pubkeys, 1, 0),
+            MaxAge::MAX,
+            TEST_TRANSACTION_COST,
+        )
+    }
+
+    #[test]
+    fn test_priority_inclusions_scheduled_first() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(1);
+        let mut container = create_container([
+            (&Keypair::new(), &[Pubkey::new_unique()], 1, 100),
+            (&Keypair::new(), &[Pubkey::new_unique()], 1, 200),
+        ]);
+        let inclusion = priority_inclusion(&Keypair::new(), [Pubkey::new_unique()]);
+        let inclusion_signature = *inclusion.0.signature();
+        scheduler.set_priority_inclusions(vec![inclusion]);
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+        assert_eq!(scheduling_summary.num_unschedulable_priority_inclusions, 0);
+
+        let (work, ids) = collect_work(&work_receivers[0]);
+        assert_eq!(ids, vec![vec![FIRST_PRIORITY_INCLUSION_ID, 1, 0]]);
+        assert_eq!(*work[0].transactions[0].signature(), inclusion_signature);
+        assert_eq!(
+            scheduler.in_flight_tracker.cus_in_flight_per_thread()[0],
+            3 * TEST_TRANSACTION_COST
+        );
+
+        // Inclusions are only scheduled once
+        let mut container = create_container([(&Keypair::new(), &[Pubkey::new_unique()], 1, 1)]);
+        scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![0]]);
+    }
+
+    #[test]
+    fn test_priority_inclusions_conflicts() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(2);
+
+        // A conflicting pair is serialized onto the same thread, in order.
+        let pubkey = Pubkey::new_unique();
+        scheduler.set_priority_inclusions(vec![
+            priority_inclusion(&Keypair::new(), [pubkey]),
+            priority_inclusion(&Keypair::new(), [pubkey]),
+        ]);
+        let mut container = TransactionStateContainer::with_capacity(1024);
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        assert_eq!(
+            collect_work(&work_receivers[0]).1,
+            vec![vec![
+                FIRST_PRIORITY_INCLUSION_ID,
+                FIRST_PRIORITY_INCLUSION_ID + 1
+            ]]
+        );
+        assert!(collect_work(&work_receivers[1]).1.is_empty());
+
+        // An inclusion conflicting with work in flight on both threads is handed back.
+        let other_pubkey = Pubkey::new_unique();
+        let mut container = create_container([(&Keypair::new(), &[other_pubkey], 1, 1)]);
+        scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(collect_work(&work_receivers[1]).1, vec![vec![0]]);
+
+        scheduler.set_priority_inclusions(vec![priority_inclusion(
+            &Keypair::new(),
+            [pubkey, other_pubkey],
+        )]);
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 0);
+        assert_eq!(scheduling_summary.num_unschedulable_priority_inclusions, 1);
+        assert_eq!(scheduler.take_unscheduled_priority_inclusions().len(), 1);
+        assert!(scheduler.take_unscheduled_priority_inclusions().is_empty());
+    }
+
+    #[test]
+    fn test_force_eviction_after_max_lock_wait_passes() {
+        const MAX_LOCK_WAIT_PASSES: usize = 2;
+        let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(2);
+        scheduler.lock_wait_tracker = LockWaitTracker::new(MAX_LOCK_WAIT_PASSES);
+
+        // Low-priority transactions hold write locks on both threads.
+        let accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut container = create_container(
+            accounts
+                .iter()
+                .map(|account| (Keypair::new(), [*account], 1, 1)),
+        );
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        let low_priority_work = work_receivers
+            .iter()
+            .flat_map(|receiver| collect_work(receiver).0)
+            .collect_vec();
+        assert_eq!(low_priority_work.len(), 2);
+
+        // A high-priority transaction needing both accounts waits, then evicts them.
+        container.insert_new_transaction(
+            SanitizedTransactionTTL {
+                transaction: prioritized_tranfers(&Keypair::new(), accounts, 1, 10),
+                max_age: MaxAge::MAX,
+            },
+            10,
+            TEST_TRANSACTION_COST,
+            None,
+        );
+        // It is scheduled within `MAX_LOCK_WAIT_PASSES + 1` passes, without
+        // any in-flight work completing.
+        let mut num_passes = 0;
+        let mut num_force_evictions = 0;
+        loop {
+            num_passes += 1;
+            assert!(num_passes <= MAX_LOCK_WAIT_PASSES + 1);
+            let scheduling_summary = scheduler
+                .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+                .unwrap();
+            num_force_evictions += scheduling_summary.num_force_evictions;
+            if scheduling_summary.num_scheduled == 1 {
+                break;
+            }
+            assert_eq!(scheduling_summary.num_unschedulable, 1);
+        }
+        assert_eq!(num_force_evictions, 2);
+
+        // Completing the evicted transactions does not release their locks again.
+        for work in low_priority_work {
+            finished_work_sender
+                .send(FinishedConsumeWork {
+                    work,
+                    retryable_indexes: vec![],
+                    retryable_reasons: vec![],
+                    retry_all: false,
+                    actual_cus_used: 0,
+                })
+                .unwrap();
+        }
+        scheduler.receive_completed(&mut container).unwrap();
+        let mut other_container = create_container([(&Keypair::new(), &[accounts[0]], 1, 1)]);
+        let high_priority_thread = (0..2)
+            .find(|thread_id| !collect_work(&work_receivers[*thread_id]).1.is_empty())
+            .unwrap();
+        scheduler
+            .schedule(
+                &mut other_container,
+                test_pre_graph_filter,
+                test_pre_lock_filter,
+            )
+            .unwrap();
+        assert_eq!(
+            collect_work(&work_receivers[high_priority_thread]).1,
+            vec![vec![0]]
+        );
+    }
+
+    #[test]
+    fn test_recent_summaries() {
+        let (mut scheduler, _work_receivers, _finished_work_sender) = create_test_frame(1);
+        assert!(scheduler.recent_summaries().is_empty());
+        scheduler.summary_history = SummaryHistory::new(2);
+
+        for num_transactions in 1..=4 {
+            let mut container = create_container(
+                (0..num_transactions).map(|_| (Keypair::new(), [Pubkey::new_unique()], 1, 1)),
+            );
+            let summary = scheduler
+                .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+                .unwrap();
+            assert_eq!(summary.num_scheduled, num_transactions);
+        }
+
+        assert_eq!(
+            scheduler
+                .recent_summaries()
+                .iter()
+                .map(|summary| summary.num_scheduled)
+                .collect_vec(),
+            vec![3, 4]
+        );
+    }
+
+    #[test]
+    fn test_block_limit_retries_are_deferred() {
+        let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(1);
+        let mut container = create_container([
+            (Keypair::new(), [Pubkey::new_unique()], 1, 3),
+            (Keypair::new(), [Pubkey::new_unique()], 1, 2),
+            (Keypair::new(), [Pubkey::new_unique()], 1, 1),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+        let (mut work, ids) = collect_work(&work_receivers[0]);
+        assert_eq!(ids, [vec![0, 1, 2]]);
+
+        // 0 hit a lock conflict, 1 hit the block limit, 2 failed for another reason
+        finished_work_sender
+            .send(FinishedConsumeWork {
+                work: work.remove(0),
+                retryable_indexes: vec![0, 1, 2],
+                retryable_reasons: vec![
+                    RetryReason::AccountInUse,
+                    RetryReason::BlockLimit,
+                    RetryReason::Other,
+                ],
+                retry_all: false,
+                actual_cus_used: 0,
+            })
+            .unwrap();
+        let (num_transactions, retry_counts) = scheduler.receive_completed(&mut container).unwrap();
+        assert_eq!(num_transactions, 3);
+        assert_eq!(
+            retry_counts,
+            RetryCounts {
+                account_in_use: 1,
+                block_limit: 1,
+                other: 1,
+            }
+        );
+
+        // The block-limited transaction is not rescheduled in the same slot
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        assert_eq!(collect_work(&work_receivers[0]).1, [vec![0, 2]]);
+
+        // It is handed back to be re-queued on the next slot
+        let deferred = scheduler.take_deferred();
+        assert_eq!(deferred.iter().map(|id| id.id).collect::<Vec<_>>(), vec![1]);
+        assert!(scheduler.take_deferred().is_empty());
+        container.push_ids_into_queue(deferred.into_iter());
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+        assert_eq!(collect_work(&work_receivers[0]).1, [vec![1]]);
+    }
+
+    #[test]
+    fn test_retryables_first() {
+        let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(1);
+        scheduler.config.retryables_first = true;
+        let mut container = create_container([(Keypair::new(), [Pubkey::new_unique()], 1, 5)]);
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+        let (mut work, ids) = collect_work(&work_receivers[0]);
+        assert_eq!(ids, [vec![0]]);
+
+        // a fresh transaction of the same priority arrives while 0 is retried
+        let transaction = prioritized_tranfers(&Keypair::new(), [Pubkey::new_unique()], 1, 5);
+        container.insert_new_transaction(
+            SanitizedTransactionTTL {
+                transaction,
+                max_age: MaxAge::MAX,
+            },
+            5,
+            TEST_TRANSACTION_COST,
+            None,
+        );
+        finished_work_sender
+            .send(FinishedConsumeWork {
+                work: work.remove(0),
+                retryable_indexes: vec![0],
+                retryable_reasons: vec![RetryReason::AccountInUse],
+                retry_all: false,
+                actual_cus_used: 0,
+            })
+            .unwrap();
+        scheduler.receive_completed(&mut container).unwrap();
+
+        // the retried transaction goes first, despite its lower id
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        assert_eq!(collect_work(&work_receivers[0]).1, [vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_unused_cus_credited() {
+        let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(1);
+        scheduler.config.max_scheduled_cus = 4 * TEST_TRANSACTION_COST;
+        scheduler.config.target_transactions_per_batch = 2;
+        let mut container = create_container(
+            (0..16).map(|priority| (Keypair::new(), [Pubkey::new_unique()], 1, priority)),
+        );
+        let complete = |work, actual_cus_used| {
+            finished_work_sender
+                .send(FinishedConsumeWork {
+                    work,
+                    retryable_indexes: vec![],
+                    retryable_reasons: vec![],
+                    retry_all: false,
+                    actual_cus_used,
+                })
+                .unwrap();
+        };
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 4);
+        let mut works = work_receivers[0].try_iter();
+        let (first, second) = (works.next().unwrap(), works.next().unwrap());
+        drop(works);
+
+        // the first batch only used half of the CUs it reserved, and so is
+        // the second batch expected to
+        complete(first, TEST_TRANSACTION_COST);
+        scheduler.receive_completed(&mut container).unwrap();
+        assert_eq!(
+            scheduler.take_completed_cus(),
+            CompletedCus {
+                reserved: 2 * TEST_TRANSACTION_COST,
+                actual: TEST_TRANSACTION_COST,
+            }
+        );
+        assert_eq!(
+            scheduler.in_flight_tracker.cus_in_flight_per_thread(),
+            &[TEST_TRANSACTION_COST]
+        );
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+
+        // the credit is replaced once the second batch used all it reserved,
+        // rather than being counted again
+        complete(second, 2 * TEST_TRANSACTION_COST);
+        scheduler.receive_completed(&mut container).unwrap();
+        assert_eq!(
+            scheduler.in_flight_tracker.cus_in_flight_per_thread(),
+            &[3 * TEST_TRANSACTION_COST]
+        );
+    }
+
+    #[test]
+    fn test_retry_all_requeues_batch() {
+        let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(1);
+        let mut container = create_container([
+            (Keypair::new(), [Pubkey::new_unique()], 1, 3),
+            (Keypair::new(), [Pubkey::new_unique()], 1, 2),
+            (Keypair::new(), [Pubkey::new_unique()], 1, 1),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+        let (mut work, ids) = collect_work(&work_receivers[0]);
+        assert_eq!(ids, [vec![0, 1, 2]]);
+        assert!(container.is_empty());
+
+        // the bank ended before the batch was processed
+        finished_work_sender
+            .send(FinishedConsumeWork {
+                work: work.remove(0),
+                retryable_indexes: vec![],
+                retryable_reasons: vec![],
+                retry_all: true,
+                actual_cus_used: 0,
+            })
+            .unwrap();
+        let (num_transactions, retry_counts) = scheduler.receive_completed(&mut container).unwrap();
+        assert_eq!(num_transactions, 3);
+        assert_eq!(
+            retry_counts,
+            RetryCounts {
+                other: 3,
+                ..RetryCounts::default()
+            }
+        );
+        assert!(!container.is_empty());
+        for id in 0..3 {
+            assert!(container.get_mut_transaction_state(id).is_some());
+        }
+
+        // the whole batch is scheduled again, in priority order
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+        assert_eq!(collect_work(&work_receivers[0]).1, [vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn test_completed_batches_release_locks() {
+        let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(2);
+        let shared = Pubkey::new_unique();
+        let payer = Keypair::new();
+        let mut container = create_container([
+            (&payer, vec![shared, Pubkey::new_unique()], 1, 3),
+            (&Keypair::new(), vec![Pubkey::new_unique()], 1, 2),
+            (&Keypair::new(), vec![shared], 1, 1),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+        assert!(scheduler.account_locks.num_locked_accounts() > 0);
+        let work: Vec<_> = work_receivers
+            .iter()
+            .flat_map(|receiver| receiver.try_iter())
+            .collect();
+
+        // every batch completes normally
+        for work in work {
+            finished_work_sender
+                .send(FinishedConsumeWork {
+                    work,
+                    retryable_indexes: vec![],
+                    retryable_reasons: vec![],
+                    retry_all: false,
+                    actual_cus_used: 0,
+                })
+                .unwrap();
+        }
+        assert_eq!(scheduler.receive_completed(&mut container).unwrap().0, 3);
+        assert_eq!(scheduler.account_locks.num_locked_accounts(), 0);
+
+        // a conflicting transaction is scheduled on any thread once the locks are released
+        let mut container = create_container([
+            (&payer, vec![shared, Pubkey::new_unique()], 1, 3),
+            (&Keypair::new(), vec![shared], 1, 1),
+        ]);
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        let (mut work, ids) = collect_work(&work_receivers[0]);
+        assert_eq!(ids, [vec![0], vec![1]]);
+
+        // the locks of retried transactions are released as well
+        for (work, retryable_indexes) in work.drain(..).zip([vec![0], vec![]]) {
+            finished_work_sender
+                .send(FinishedConsumeWork {
+                    work,
+                    retryable_reasons: vec![RetryReason::AccountInUse; retryable_indexes.len()],
+                    retryable_indexes,
+                    retry_all: false,
+                    actual_cus_used: 0,
+                })
+                .unwrap();
+        }
+        let (num_transactions, retry_counts) = scheduler.receive_completed(&mut container).unwrap();
+        assert_eq!(num_transactions, 2);
+        assert_eq!(retry_counts.account_in_use, 1);
+        assert_eq!(scheduler.account_locks.num_locked_accounts(), 0);
+
+        // and the retried transaction takes its locks again
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+        assert_eq!(
+            work_receivers
+                .iter()
+                .flat_map(|receiver| receiver.try_iter())
+                .map(|work| work.ids)
+                .collect::<Vec<_>>(),
+            [vec![0]]
+        );
+        assert!(scheduler.account_locks.num_locked_accounts() > 0);
+    }
+
+    #[test]
+    fn test_resume_in_flight_batch() {
+        let (mut scheduler, work_receivers, finished_work_sender) = create_test_frame(2);
+        let recovered_accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        // a batch recovered on each thread, write-locking an account each
+        for (thread_id, account) in recovered_accounts.iter().enumerate() {
+            let batch = vec![priority_inclusion(&Keypair::new(), [account])];
+            assert!(scheduler.resume_in_flight_batch(thread_id, batch).unwrap());
+        }
+        assert_eq!(
+            scheduler.in_flight_tracker.cus_in_flight_per_thread(),
+            [TEST_TRANSACTION_COST, TEST_TRANSACTION_COST]
+        );
+        let recovered_work: Vec<_> = work_receivers
+            .iter()
+            .flat_map(|receiver| receiver.try_iter())
+            .collect();
+        assert_eq!(
+            recovered_work
+                .iter()
+                .map(|work| work.ids.clone())
+                .collect::<Vec<_>>(),
+            [
+                vec![FIRST_PRIORITY_INCLUSION_ID],
+                vec![FIRST_PRIORITY_INCLUSION_ID + 1]
+            ]
+        );
+
+        // a batch conflicting with both is left to the next pass without
+        // taking any lock
+        let num_locked_accounts = scheduler.account_locks.num_locked_accounts();
+        let conflicting = vec![
+            priority_inclusion(&Keypair::new(), [Pubkey::new_unique()]),
+            priority_inclusion(&Keypair::new(), [recovered_accounts[1]]),
+        ];
+        assert!(!scheduler.resume_in_flight_batch(0, conflicting).unwrap());
+        assert_eq!(
+            scheduler.account_locks.num_locked_accounts(),
+            num_locked_accounts
+        );
+        assert!(work_receivers[0].is_empty());
+
+        // a fresh transaction writing both accounts is unschedulable, one
+        // writing a single account goes to the thread holding it, along with
+        // the conflicting inclusion
+        let mut container = create_container([
+            (&Keypair::new(), recovered_accounts.to_vec(), 1, 2),
+            (&Keypair::new(), vec![recovered_accounts[1]], 1, 1),
+        ]);
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+        assert_eq!(scheduling_summary.num_unschedulable, 1);
+        let (work_0, ids_0) = collect_work(&work_receivers[0]);
+        let (work_1, ids_1) = collect_work(&work_receivers[1]);
+        assert_eq!(ids_0, [vec![FIRST_PRIORITY_INCLUSION_ID + 2]]);
+        assert_eq!(ids_1, [vec![FIRST_PRIORITY_INCLUSION_ID + 3, 1]]);
+
+        // completing the recovered batches releases their locks
+        for work in recovered_work.into_iter().chain(work_0).chain(work_1) {
+            finished_work_sender
+                .send(FinishedConsumeWork {
+                    work,
+                    retryable_indexes: vec![],
+                    retryable_reasons: vec![],
+                    retry_all: false,
+                    actual_cus_used: 0,
+                })
+                .unwrap();
+        }
+        assert_eq!(scheduler.receive_completed(&mut container).unwrap().0, 5);
+        assert_eq!(scheduler.account_locks.num_locked_accounts(), 0);
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+    }
+
+    #[test]
+    fn test_priority_inclusion_ids_exhausted() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(1);
+        // the only id left is in flight
+        scheduler.priority_inclusion_ids = TransactionIdAllocator::new(TransactionId::MAX);
+        scheduler
+            .in_flight_priority_inclusions
+            .insert(TransactionId::MAX, TEST_TRANSACTION_COST);
+
+        // a resumed batch is left to the next pass without taking any lock
+        let batch = vec![priority_inclusion(&Keypair::new(), [Pubkey::new_unique()])];
+        assert!(!scheduler.resume_in_flight_batch(0, batch).unwrap());
+        assert_eq!(scheduler.account_locks.num_locked_accounts(), 0);
+        assert!(work_receivers[0].is_empty());
+
+        // where it is handed back as unschedulable
+        let mut container = TransactionStateContainer::with_capacity(1024);
+        let scheduling_summary = scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 0);
+        assert_eq!(scheduling_summary.num_unschedulable_priority_inclusions, 1);
+        assert_eq!(scheduler.take_unscheduled_priority_inclusions().len(), 1);
+        assert_eq!(scheduler.account_locks.num_locked_accounts(), 0);
+        assert!(work_receivers[0].is_empty());
+    }
+
+    #[test]
+    fn test_estimate() {
+        let (mut scheduler, _work_receivers, _finished_work_sender) = create_test_frame(2);
+        let max_cu_per_thread = MAX_BLOCK_UNITS / 2;
+        let in_flight_headroom = max_cu_per_thread - TEST_TRANSACTION_COST;
+
+        // leave one transaction in flight on each thread, write-locking an account
+        let locked = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut container = create_container([
+            (&Keypair::new(), &[locked[0]], 1, 2),
+            (&Keypair::new(), &[locked[1]], 1, 1),
+        ]);
+        scheduler
+            .schedule(&mut container, test_pre_graph_filter, test_pre_lock_filter)
+            .unwrap();
+        assert_eq!(
+            scheduler.in_flight_tracker.num_in_flight_per_thread(),
+            [1, 1]
+        );
+
+        // and queue two transactions writing another account
+        let queued = Pubkey::new_unique();
+        for priority in [7, 3] {
+            let transaction_ttl = SanitizedTransactionTTL {
+                transaction: prioritized_tranfers(&Keypair::new(), [queued], 1, priority),
+                max_age: MaxAge::MAX,
+            };
+            container.insert_new_transaction(
+                transaction_ttl,
+                priority,
+                TEST_TRANSACTION_COST,
+                None,
+            );
+        }
+
+        let request = |write_accounts: &[Pubkey], read_accounts: &[Pubkey], cost| EstimateRequest {
+            write_accounts: write_accounts.to_vec(),
+            read_accounts: read_accounts.to_vec(),
+            cost,
+        };
+        let queued_conflicts = SchedulingEstimate {
+            would_schedule_now: true,
+            min_conflicting_priority: Some(3),
+            num_conflicting: 2,
+            cu_headroom: in_flight_headroom,
+            truncated: false,
+        };
+        assert_eq!(
+            scheduler.estimate(&container, &request(&[queued], &[], TEST_TRANSACTION_COST)),
+            queued_conflicts
+        );
+        assert_eq!(
+            scheduler.estimate(&container, &request(&[], &[queued], TEST_TRANSACTION_COST)),
+            queued_conflicts
+        );
+        // the system program is read, and not written, by the queued transactions
+        assert_eq!(
+            scheduler.estimate(
+                &container,
+                &request(&[], &[system_program::id()], TEST_TRANSACTION_COST)
+            ),
+            SchedulingEstimate {
+                would_schedule_now: true,
+                cu_headroom: in_flight_headroom,
+                ..SchedulingEstimate::default()
+            }
+        );
+
+        // an account locked on a single thread
+        assert_eq!(
+            scheduler.estimate(
+                &container,
+                &request(&[locked[0]], &[], TEST_TRANSACTION_COST)
+            ),
+            SchedulingEstimate {
+                would_schedule_now: true,
+                cu_headroom: in_flight_headroom,
+                ..SchedulingEstimate::default()
+            }
+        );
+        // accounts locked on different threads
+        assert_eq!(
+            scheduler.estimate(&container, &request(&locked, &[], TEST_TRANSACTION_COST)),
+            SchedulingEstimate::default()
+        );
+        // a cost over the headroom of every thread
+        assert_eq!(
+            scheduler.estimate(
+                &container,
+                &request(&[Pubkey::new_unique()], &[], max_cu_per_thread)
+            ),
+            SchedulingEstimate {
+                would_schedule_now: false,
+                cu_headroom: in_flight_headroom,
+                ..SchedulingEstimate::default()
+            }
+        );
+
+        // the examined transactions are capped, highest priority first
+        scheduler.config.max_estimate_examined_transactions = 1;
+        let estimate =
+            scheduler.estimate(&container, &request(&[queued], &[], TEST_TRANSACTION_COST));
+        assert!(estimate.truncated);
+        assert_eq!(estimate.num_conflicting, 1);
+        assert_eq!(estimate.min_conflicting_priority, Some(7));
+
+        // estimating leaves the queue untouched
+        assert_eq!(container.queued_ids().count(), 2);
+    }
+
     #[test]
     fn test_schedule_over_full_container() {
         let (mut scheduler, _work_receivers, _finished_work_sender) = create_test_frame(1);
@@ -0,0 +1,122 @@
+use {
+    solana_pubkey::Pubkey,
+    solana_svm_transaction::svm_message::SVMMessage,
+    std::collections::{HashMap, HashSet},
+};
+
+/// Default `readonly_fanin_threshold`, never pruning.
+pub(crate) const DEFAULT_READONLY_FANIN_THRESHOLD: usize = usize::MAX;
+
+/// Tracks, over a single scheduling pass, how many transactions inserted into
+/// the prio-graph read each account, so that the read edges of accounts with a
+/// very wide read-only fan-in can be left out of the graph.
+///
+/// Reads never conflict with reads, so those edges only matter to a later
+/// writer of the account. Once an account has been written during the pass it
+/// is no longer pruned, and readers inserted before the threshold was crossed
+/// keep their edges; any other conflict with a writer is still caught by
+/// `ThreadAwareAccountLocks`, where read locks are always taken.
+pub(crate) struct ReadonlyFanin {
+    threshold: usize,
+    reads: HashMap<Pubkey, usize>,
+    written: HashSet<Pubkey>,
+}
+
+impl ReadonlyFanin {
+    pub(crate) fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            reads: HashMap::new(),
+            written: HashSet::new(),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.threshold != DEFAULT_READONLY_FANIN_THRESHOLD
+    }
+
+    /// Record the accounts accessed by a transaction about to be inserted
+    /// into the prio-graph.
+    pub(crate) fn observe(&mut self, transaction: &impl SVMMessage) {
+        if !self.is_enabled() {
+            return;
+        }
+        for (index, key) in transaction.account_keys().iter().enumerate() {
+            if transaction.is_writable(index) {
+                self.written.insert(*key);
+            } else {
+                let reads = self.reads.entry(*key).or_default();
+                *reads = reads.saturating_add(1);
+            }
+        }
+    }
+
+    /// Whether read accesses of `key` should be left out of the prio-graph.
+    pub(crate) fn is_pruned(&self, key: &Pubkey) -> bool {
+        self.is_enabled()
+            && !self.written.contains(key)
+            && self.reads.get(key).copied().unwrap_or_default() > self.threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
+        solana_sdk::{
+            hash::Hash,
+            instruction::{AccountMeta, Instruction},
+            message::Message,
+            signature::Keypair,
+            signer::Signer,
+            system_instruction, system_program,
+            transaction::{SanitizedTransaction, Transaction},
+        },
+    };
+
+    fn read(payer: &Keypair, account: &Pubkey) -> RuntimeTransaction<SanitizedTransaction> {
+        let ix = Instruction::new_with_bytes(
+            system_program::id(),
+            &[],
+            vec![AccountMeta::new_readonly(*account, false)],
+        );
+        let message = Message::new(&[ix], Some(&payer.pubkey()));
+        let tx = Transaction::new(&[payer], message, Hash::default());
+        RuntimeTransaction::from_transaction_for_tests(tx)
+    }
+
+    fn write(payer: &Keypair, account: &Pubkey) -> RuntimeTransaction<SanitizedTransaction> {
+        let ixs = vec![system_instruction::transfer(&payer.pubkey(), account, 1)];
+        let message = Message::new(&ixs, Some(&payer.pubkey()));
+        let tx = Transaction::new(&[payer], message, Hash::default());
+        RuntimeTransaction::from_transaction_for_tests(tx)
+    }
+
+    #[test]
+    fn test_disabled() {
+        let account = Pubkey::new_unique();
+        let mut fanin = ReadonlyFanin::new(DEFAULT_READONLY_FANIN_THRESHOLD);
+        for _ in 0..4 {
+            fanin.observe(&read(&Keypair::new(), &account));
+        }
+        assert!(!fanin.is_pruned(&account));
+    }
+
+    #[test]
+    fn test_pruned_until_written() {
+        let account = Pubkey::new_unique();
+        let mut fanin = ReadonlyFanin::new(2);
+
+        fanin.observe(&read(&Keypair::new(), &account));
+        fanin.observe(&read(&Keypair::new(), &account));
+        assert!(!fanin.is_pruned(&account));
+        fanin.observe(&read(&Keypair::new(), &account));
+        assert!(fanin.is_pruned(&account));
+        // the system program is read by every transaction above
+        assert!(fanin.is_pruned(&system_program::id()));
+
+        fanin.observe(&write(&Keypair::new(), &account));
+        assert!(!fanin.is_pruned(&account));
+    }
+}
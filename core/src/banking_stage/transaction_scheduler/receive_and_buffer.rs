@@ -4,8 +4,8 @@ use {
         transaction_priority_id::TransactionPriorityId,
         transaction_state::TransactionState,
         transaction_state_container::{
-            SharedBytes, StateContainer, TransactionViewState, TransactionViewStateContainer,
-            EXTRA_CAPACITY,
+            InsertOutcome, SharedBytes, StateContainer, TransactionViewState,
+            TransactionViewStateContainer, EXTRA_CAPACITY,
         },
     },
     crate::banking_stage::{
@@ -66,6 +66,9 @@ pub(crate) struct SanitizedTransactionReceiveAndBuffer {
     /// Packet/Transaction ingress.
     packet_receiver: PacketDeserializer,
     bank_forks: Arc<RwLock<BankForks>>,
+    /// Time after which received transactions that were not scheduled are
+    /// dropped, see `TransactionState::deadline()`.
+    transaction_ttl: Option<Duration>,
 }
 
 impl ReceiveAndBuffer for SanitizedTransactionReceiveAndBuffer {
@@ -95,11 +98,15 @@ impl ReceiveAndBuffer for SanitizedTransactionReceiveAndBuffer {
             BufferedPacketsDecision::ForwardAndHold => (MAX_PACKET_RECEIVE_TIME, true),
         };
 
+        let deadline = self.transaction_ttl.map(|ttl| Instant::now() + ttl);
         let (received_packet_results, receive_time_us) = measure_us!(self
             .packet_receiver
             .receive_packets(recv_timeout, MAX_RECEIVE_PACKETS, |packet| {
                 packet.check_excessive_precompiles()?;
-                Ok(packet)
+                Ok(match deadline {
+                    Some(deadline) => packet.with_deadline(deadline),
+                    None => packet,
+                })
             }));
 
         timing_metrics.update(|timing_metrics| {
@@ -147,9 +154,17 @@ impl SanitizedTransactionReceiveAndBuffer {
         Self {
             packet_receiver,
             bank_forks,
+            transaction_ttl: None,
         }
     }
 
+    /// Drop the transactions not scheduled within `transaction_ttl` of being
+    /// received, if any.
+    pub fn with_transaction_ttl(mut self, transaction_ttl: Option<Duration>) -> Self {
+        self.transaction_ttl = transaction_ttl;
+        self
+    }
+
     fn buffer_packets(
         &mut self,
         container: &mut TransactionStateContainer<RuntimeTransaction<SanitizedTransaction>>,
@@ -176,6 +191,7 @@ impl SanitizedTransactionReceiveAndBuffer {
 
         let mut transactions = ArrayVec::<_, CHUNK_SIZE>::new();
         let mut max_ages = ArrayVec::<_, CHUNK_SIZE>::new();
+        let mut deadlines = ArrayVec::<_, CHUNK_SIZE>::new();
         let mut fee_budget_limits_vec = ArrayVec::<_, CHUNK_SIZE>::new();
 
         let mut error_counts = TransactionErrorMetrics::default();
@@ -184,33 +200,38 @@ impl SanitizedTransactionReceiveAndBuffer {
             chunk
                 .iter()
                 .filter_map(|packet| {
-                    packet.build_sanitized_transaction(
-                        vote_only,
-                        root_bank.as_ref(),
-                        root_bank.get_reserved_account_keys(),
-                    )
+                    packet
+                        .build_sanitized_transaction(
+                            vote_only,
+                            root_bank.as_ref(),
+                            root_bank.get_reserved_account_keys(),
+                        )
+                        .map(|(tx, deactivation_slot)| (tx, deactivation_slot, packet.deadline()))
                 })
                 .inspect(|_| saturating_add_assign!(post_sanitization_count, 1))
-                .filter(|(tx, _deactivation_slot)| {
+                .filter(|(tx, _deactivation_slot, _deadline)| {
                     validate_account_locks(
                         tx.message().account_keys(),
                         transaction_account_lock_limit,
                     )
                     .is_ok()
                 })
-                .filter_map(|(tx, deactivation_slot)| {
+                .filter_map(|(tx, deactivation_slot, deadline)| {
                     tx.compute_budget_instruction_details()
                         .sanitize_and_convert_to_compute_budget_limits(&working_bank.feature_set)
-                        .map(|compute_budget| (tx, deactivation_slot, compute_budget.into()))
+                        .map(|compute_budget| {
+                            (tx, deactivation_slot, deadline, compute_budget.into())
+                        })
                         .ok()
                 })
-                .for_each(|(tx, deactivation_slot, fee_budget_limits)| {
+                .for_each(|(tx, deactivation_slot, deadline, fee_budget_limits)| {
                     transactions.push(tx);
                     max_ages.push(calculate_max_age(
                         sanitized_epoch,
                         deactivation_slot,
                         alt_resolved_slot,
                     ));
+                    deadlines.push(deadline);
                     fee_budget_limits_vec.push(fee_budget_limits);
                 });
 
@@ -224,16 +245,21 @@ impl SanitizedTransactionReceiveAndBuffer {
 
             let mut post_transaction_check_count: usize = 0;
             let mut num_dropped_on_capacity: usize = 0;
+            let mut num_dropped_on_duplicate: usize = 0;
+            let mut num_dropped_on_recently_completed: usize = 0;
             let mut num_buffered: usize = 0;
-            for (((transaction, max_age), fee_budget_limits), _check_result) in transactions
-                .drain(..)
-                .zip(max_ages.drain(..))
-                .zip(fee_budget_limits_vec.drain(..))
-                .zip(check_results)
-                .filter(|(_, check_result)| check_result.is_ok())
-                .filter(|(((tx, _), _), _)| {
-                    Consumer::check_fee_payer_unlocked(&working_bank, tx, &mut error_counts).is_ok()
-                })
+            for ((((transaction, max_age), deadline), fee_budget_limits), _check_result) in
+                transactions
+                    .drain(..)
+                    .zip(max_ages.drain(..))
+                    .zip(deadlines.drain(..))
+                    .zip(fee_budget_limits_vec.drain(..))
+                    .zip(check_results)
+                    .filter(|(_, check_result)| check_result.is_ok())
+                    .filter(|((((tx, _), _), _), _)| {
+                        Consumer::check_fee_payer_unlocked(&working_bank, tx, &mut error_counts)
+                            .is_ok()
+                    })
             {
                 saturating_add_assign!(post_transaction_check_count, 1);
 
@@ -244,10 +270,21 @@ impl SanitizedTransactionReceiveAndBuffer {
                     max_age,
                 };
 
-                if container.insert_new_transaction(transaction_ttl, priority, cost) {
-                    saturating_add_assign!(num_dropped_on_capacity, 1);
+                match container.insert_new_transaction(transaction_ttl, priority, cost, deadline) {
+                    InsertOutcome::Inserted => saturating_add_assign!(num_buffered, 1),
+                    InsertOutcome::InsertedDroppingLowest => {
+                        saturating_add_assign!(num_buffered, 1);
+                        saturating_add_assign!(num_dropped_on_capacity, 1);
+                    }
+                    InsertOutcome::Duplicate => {
+                        saturating_add_assign!(num_dropped_on_duplicate, 1)
+                    }
+                    InsertOutcome::RecentlyCompleted => {
+                        saturating_add_assign!(num_dropped_on_recently_completed, 1)
+                    }
+                    // counted by the container, see `take_admission_counts()`
+                    InsertOutcome::BelowAdmissionFloor => {}
                 }
-                saturating_add_assign!(num_buffered, 1);
             }
 
             // Update metrics for transactions that were dropped.
@@ -262,6 +299,14 @@ impl SanitizedTransactionReceiveAndBuffer {
                     count_metrics.num_dropped_on_capacity,
                     num_dropped_on_capacity
                 );
+                saturating_add_assign!(
+                    count_metrics.num_dropped_on_duplicate,
+                    num_dropped_on_duplicate
+                );
+                saturating_add_assign!(
+                    count_metrics.num_dropped_on_recently_completed,
+                    num_dropped_on_recently_completed
+                );
                 saturating_add_assign!(count_metrics.num_buffered, num_buffered);
                 saturating_add_assign!(
                     count_metrics.num_dropped_on_sanitization,
@@ -283,6 +328,9 @@ impl SanitizedTransactionReceiveAndBuffer {
 pub(crate) struct TransactionViewReceiveAndBuffer {
     pub receiver: BankingPacketReceiver,
     pub bank_forks: Arc<RwLock<BankForks>>,
+    /// Time after which received transactions that were not scheduled are
+    /// dropped, see `TransactionState::deadline()`.
+    pub transaction_ttl: Option<Duration>,
 }
 
 impl ReceiveAndBuffer for TransactionViewReceiveAndBuffer {
@@ -385,6 +433,7 @@ impl TransactionViewReceiveAndBuffer {
         }
 
         let start = Instant::now();
+        let deadline = self.transaction_ttl.map(|ttl| start + ttl);
         // Sanitize packets, generate IDs, and insert into the container.
         let alt_resolved_slot = root_bank.slot();
         let sanitized_epoch = root_bank.epoch();
@@ -395,6 +444,8 @@ impl TransactionViewReceiveAndBuffer {
         let mut num_dropped_on_status_age_checks = 0usize;
         let mut num_dropped_on_capacity = 0usize;
         let mut num_dropped_on_receive = 0usize;
+        let mut num_dropped_on_duplicate = 0usize;
+        let mut num_dropped_on_recently_completed = 0usize;
 
         // Create temporary batches of transactions to be age-checked.
         let mut transaction_priority_ids = ArrayVec::<_, EXTRA_CAPACITY>::new();
@@ -473,6 +524,7 @@ impl TransactionViewReceiveAndBuffer {
                             alt_resolved_slot,
                             sanitized_epoch,
                             transaction_account_lock_limit,
+                            deadline,
                         ) {
                             Ok(state) => {
                                 num_buffered += 1;
@@ -485,10 +537,28 @@ impl TransactionViewReceiveAndBuffer {
                         }
                     })
                 {
-                    let priority = container
+                    let state = container
                         .get_mut_transaction_state(transaction_id)
-                        .expect("transaction must exist")
-                        .priority();
+                        .expect("transaction must exist");
+                    let priority = state.priority();
+                    let signature = *state.transaction_ttl().transaction.signature();
+                    if !container.insert_signature(transaction_id, signature, priority) {
+                        num_buffered -= 1;
+                        num_dropped_on_duplicate += 1;
+                        container.remove_by_id(transaction_id);
+                        continue;
+                    }
+                    if container.is_recently_completed(&signature) {
+                        num_buffered -= 1;
+                        num_dropped_on_recently_completed += 1;
+                        container.remove_by_id(transaction_id);
+                        continue;
+                    }
+                    if !container.admit(priority) {
+                        num_buffered -= 1;
+                        container.remove_by_id(transaction_id);
+                        continue;
+                    }
                     transaction_priority_ids
                         .push(TransactionPriorityId::new(priority, transaction_id));
 
@@ -519,6 +589,14 @@ impl TransactionViewReceiveAndBuffer {
                 num_dropped_on_capacity
             );
             saturating_add_assign!(count_metrics.num_dropped_on_receive, num_dropped_on_receive);
+            saturating_add_assign!(
+                count_metrics.num_dropped_on_duplicate,
+                num_dropped_on_duplicate
+            );
+            saturating_add_assign!(
+                count_metrics.num_dropped_on_recently_completed,
+                num_dropped_on_recently_completed
+            );
         });
 
         num_received
@@ -531,6 +609,7 @@ impl TransactionViewReceiveAndBuffer {
         alt_resolved_slot: Slot,
         sanitized_epoch: Epoch,
         transaction_account_lock_limit: usize,
+        deadline: Option<Instant>,
     ) -> Result<TransactionViewState, ()> {
         // Parsing and basic sanitization checks
         let Ok(view) = SanitizedTransactionView::try_new_sanitized(bytes) else {
@@ -602,6 +681,7 @@ impl TransactionViewReceiveAndBuffer {
             },
             priority,
             cost,
+            deadline,
         ))
     }
 }
@@ -0,0 +1,147 @@
+use {
+    solana_sdk::signature::Signature,
+    std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+    },
+};
+
+/// Default number of completed signatures remembered.
+pub(crate) const DEFAULT_RECENTLY_COMPLETED_CAPACITY: usize = 100_000;
+/// Default time a completed signature is remembered for.
+pub(crate) const DEFAULT_RECENTLY_COMPLETED_WINDOW: Duration = Duration::from_millis(2_000);
+
+/// Signatures of the transactions that recently completed, so that a client
+/// re-submitting a transaction before seeing it confirmed does not cost a
+/// schedule and execute cycle ending in `AlreadyProcessed`.
+///
+/// The signatures are held in a ring of fixed capacity, the oldest being
+/// overwritten once it is full, and indexed by a map holding at most as many
+/// entries. Both are allocated for the full capacity upfront, so that
+/// recording a completion never reallocates. A signature is only reported
+/// within `window` of its completion, with a millisecond resolution.
+pub(crate) struct RecentlyCompleted {
+    capacity: usize,
+    window_ms: u64,
+    start: Instant,
+    /// Signatures and their sequence number, in completion order, wrapping
+    /// around at `next`.
+    ring: Vec<(Signature, u64)>,
+    next: usize,
+    /// Sequence number and completion time of each signature in `ring`.
+    /// A signature completed twice is indexed by its latest entry.
+    signature_to_completion: HashMap<Signature, (u64, u64)>,
+    next_seq: u64,
+}
+
+impl RecentlyCompleted {
+    pub(crate) fn new(capacity: usize, window: Duration) -> Self {
+        Self {
+            capacity,
+            window_ms: window.as_millis() as u64,
+            start: Instant::now(),
+            ring: Vec::with_capacity(capacity),
+            next: 0,
+            signature_to_completion: HashMap::with_capacity(capacity),
+            next_seq: 0,
+        }
+    }
+
+    /// Remember that the transaction of `signature` completed.
+    pub(crate) fn record(&mut self, signature: Signature) {
+        if self.capacity == 0 {
+            return;
+        }
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        if self.ring.len() < self.capacity {
+            self.ring.push((signature, seq));
+        } else {
+            let (evicted, evicted_seq) =
+                std::mem::replace(&mut self.ring[self.next], (signature, seq));
+            if self
+                .signature_to_completion
+                .get(&evicted)
+                .is_some_and(|(latest_seq, _)| *latest_seq == evicted_seq)
+            {
+                self.signature_to_completion.remove(&evicted);
+            }
+        }
+        self.next = (self.next + 1) % self.capacity;
+        self.signature_to_completion
+            .insert(signature, (seq, self.now_ms()));
+    }
+
+    /// Whether the transaction of `signature` completed within the window.
+    pub(crate) fn contains(&self, signature: &Signature) -> bool {
+        self.signature_to_completion
+            .get(signature)
+            .is_some_and(|(_, completed_ms)| {
+                self.now_ms().saturating_sub(*completed_ms) < self.window_ms
+            })
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    /// Move the clock forward by `by`, as if that much time had passed.
+    #[cfg(test)]
+    pub(crate) fn advance_clock(&mut self, by: Duration) {
+        self.start = self.start.checked_sub(by).expect("clock underflow");
+    }
+}
+
+impl Default for RecentlyCompleted {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_RECENTLY_COMPLETED_CAPACITY,
+            DEFAULT_RECENTLY_COMPLETED_WINDOW,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recently_completed_window() {
+        let mut recently_completed = RecentlyCompleted::new(4, Duration::from_millis(100));
+        let signature = Signature::new_unique();
+        assert!(!recently_completed.contains(&signature));
+        recently_completed.record(signature);
+        assert!(recently_completed.contains(&signature));
+
+        recently_completed.advance_clock(Duration::from_millis(100));
+        assert!(!recently_completed.contains(&signature));
+
+        // completing again restarts the window
+        recently_completed.record(signature);
+        assert!(recently_completed.contains(&signature));
+    }
+
+    #[test]
+    fn test_recently_completed_capacity() {
+        let mut recently_completed = RecentlyCompleted::new(4, Duration::from_secs(60));
+        let signatures: Vec<_> = (0..6).map(|_| Signature::new_unique()).collect();
+        for signature in &signatures {
+            recently_completed.record(*signature);
+        }
+        assert_eq!(recently_completed.ring.len(), 4);
+        assert_eq!(recently_completed.signature_to_completion.len(), 4);
+        assert!(!recently_completed.contains(&signatures[0]));
+        assert!(!recently_completed.contains(&signatures[1]));
+        assert!(signatures[2..]
+            .iter()
+            .all(|signature| recently_completed.contains(signature)));
+
+        // overwriting the older entry of a signature completed twice keeps
+        // the signature
+        recently_completed.record(signatures[3]);
+        assert!(!recently_completed.contains(&signatures[2]));
+        recently_completed.record(Signature::new_unique());
+        assert!(recently_completed.contains(&signatures[3]));
+        assert_eq!(recently_completed.signature_to_completion.len(), 4);
+    }
+}
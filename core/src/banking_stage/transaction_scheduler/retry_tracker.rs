@@ -0,0 +1,111 @@
+use {
+    crate::banking_stage::scheduler_messages::TransactionId,
+    solana_sdk::hash::Hash,
+    std::collections::{HashMap, HashSet},
+};
+
+/// Default number of times a transaction may be retried before it is
+/// reported as over the retry threshold.
+pub(crate) const DEFAULT_RETRY_THRESHOLD: usize = 32;
+
+/// Tracks how many times each in-flight transaction has been returned as
+/// retryable by the workers, so that transactions which retry forever can
+/// be detected.
+///
+/// `TransactionId`s are reused by the container once a transaction is
+/// removed, so each counter also remembers the message hash of the
+/// transaction it belongs to and restarts when the id is reused.
+pub(crate) struct RetryTracker {
+    threshold: usize,
+    retry_counts: HashMap<TransactionId, (Hash, usize)>,
+    over_threshold: HashSet<TransactionId>,
+}
+
+impl RetryTracker {
+    pub(crate) fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            retry_counts: HashMap::new(),
+            over_threshold: HashSet::new(),
+        }
+    }
+
+    /// Record a retry of the transaction `id`.
+    /// Returns the number of times it has been retried, including this one.
+    pub(crate) fn record_retry(&mut self, id: TransactionId, message_hash: &Hash) -> usize {
+        let (hash, count) = self.retry_counts.entry(id).or_insert((*message_hash, 0));
+        if hash != message_hash {
+            *hash = *message_hash;
+            *count = 0;
+            self.over_threshold.remove(&id);
+        }
+        *count = count.saturating_add(1);
+        if *count > self.threshold {
+            self.over_threshold.insert(id);
+        }
+        *count
+    }
+
+    /// Stop tracking a transaction that is no longer retried.
+    pub(crate) fn remove(&mut self, id: TransactionId) {
+        if self.retry_counts.remove(&id).is_some() {
+            self.over_threshold.remove(&id);
+        }
+    }
+
+    /// Stop tracking all transactions for which `f` returns false.
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(TransactionId) -> bool) {
+        self.retry_counts.retain(|id, _| f(*id));
+        let retry_counts = &self.retry_counts;
+        self.over_threshold
+            .retain(|id| retry_counts.contains_key(id));
+    }
+
+    /// Transactions that have been retried more than the threshold.
+    pub(crate) fn over_threshold(&self) -> &HashSet<TransactionId> {
+        &self.over_threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_retry() {
+        let mut tracker = RetryTracker::new(2);
+        let hash = Hash::new_unique();
+        assert_eq!(tracker.record_retry(0, &hash), 1);
+        assert_eq!(tracker.record_retry(0, &hash), 2);
+        assert!(tracker.over_threshold().is_empty());
+        assert_eq!(tracker.record_retry(0, &hash), 3);
+        assert_eq!(tracker.over_threshold(), &HashSet::from([0]));
+
+        tracker.remove(0);
+        assert!(tracker.over_threshold().is_empty());
+        assert_eq!(tracker.record_retry(0, &hash), 1);
+    }
+
+    #[test]
+    fn test_reused_id() {
+        let mut tracker = RetryTracker::new(1);
+        let hash = Hash::new_unique();
+        tracker.record_retry(3, &hash);
+        tracker.record_retry(3, &hash);
+        assert_eq!(tracker.over_threshold(), &HashSet::from([3]));
+
+        // a different transaction now occupies id 3
+        assert_eq!(tracker.record_retry(3, &Hash::new_unique()), 1);
+        assert!(tracker.over_threshold().is_empty());
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut tracker = RetryTracker::new(0);
+        for id in 0..4 {
+            tracker.record_retry(id, &Hash::new_unique());
+        }
+        tracker.retain(|id| id % 2 == 0);
+        assert_eq!(tracker.over_threshold(), &HashSet::from([0, 2]));
+    }
+}
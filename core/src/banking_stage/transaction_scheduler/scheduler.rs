@@ -1,9 +1,18 @@
 use {
     super::{
-        scheduler_error::SchedulerError, transaction_state::TransactionState,
+        in_flight_tracker::ThreadLoad,
+        scheduler_error::SchedulerError,
+        scheduler_state::SchedulerStateSnapshot,
+        scheduling_estimate::{EstimateRequest, SchedulingEstimate},
+        stats::SchedulerConfig,
+        transaction_priority_id::TransactionPriorityId,
+        transaction_state::TransactionState,
         transaction_state_container::StateContainer,
     },
+    crate::banking_stage::scheduler_messages::{MaxAge, RetryReason, TransactionId},
     solana_runtime_transaction::transaction_with_meta::TransactionWithMeta,
+    solana_sdk::saturating_add_assign,
+    std::collections::{HashSet, VecDeque},
 };
 
 pub(crate) trait Scheduler<Tx: TransactionWithMeta> {
@@ -13,16 +22,59 @@ pub(crate) trait Scheduler<Tx: TransactionWithMeta> {
     fn schedule<S: StateContainer<Tx>>(
         &mut self,
         container: &mut S,
-        pre_graph_filter: impl Fn(&[&Tx], &mut [bool]),
+        pre_graph_filter: impl Fn(&[&Tx], &[MaxAge], &mut [bool]),
         pre_lock_filter: impl Fn(&TransactionState<Tx>) -> PreLockFilterAction,
     ) -> Result<SchedulingSummary, SchedulerError>;
 
     /// Receive completed batches of transactions without blocking.
-    /// Returns (num_transactions, num_retryable_transactions) on success.
+    /// Returns (num_transactions, retry_counts) on success.
     fn receive_completed(
         &mut self,
         container: &mut impl StateContainer<Tx>,
-    ) -> Result<(usize, usize), SchedulerError>;
+    ) -> Result<(usize, RetryCounts), SchedulerError>;
+
+    /// Take the transactions that were retried because they would exceed a
+    /// block limit. These are held out of the queue for the rest of the slot
+    /// and should be pushed back into the container's queue on the next one.
+    fn take_deferred(&mut self) -> Vec<TransactionPriorityId>;
+
+    /// Send the batches held back to be coalesced with later passes, so that
+    /// they do not wait across a change of the working bank.
+    /// Returns the number of transactions sent.
+    fn flush_held_batches(&mut self) -> Result<usize, SchedulerError>;
+
+    /// Take the CUs reserved and used by the batches completed since the last
+    /// call.
+    fn take_completed_cus(&mut self) -> CompletedCus;
+
+    /// Transactions that have been returned as retryable more times than the
+    /// configured retry threshold and are still waiting to be processed.
+    fn transactions_over_retry_threshold(&self) -> &HashSet<TransactionId>;
+
+    /// Summaries of the most recent scheduling passes, oldest first.
+    /// Empty unless the scheduler was configured to retain them.
+    fn recent_summaries(&self) -> &VecDeque<SchedulingSummary>;
+
+    /// Snapshot of the CUs and transactions in flight on each worker thread,
+    /// by thread id.
+    fn thread_loads(&self) -> Vec<ThreadLoad>;
+
+    /// Snapshot of the state of the scheduler and of `container`, from
+    /// counters already tracked, without iterating over transactions.
+    fn state_snapshot(&self, container: &impl StateContainer<Tx>) -> SchedulerStateSnapshot;
+
+    /// The configuration the scheduler was created with.
+    fn config(&self) -> SchedulerConfig;
+
+    /// Estimate how a transaction accessing the accounts of `request` would
+    /// be treated by the next pass, from the current account locks, in-flight
+    /// CUs and the transactions queued in `container`. Does not modify any
+    /// state.
+    fn estimate(
+        &self,
+        container: &impl StateContainer<Tx>,
+        request: &EstimateRequest,
+    ) -> SchedulingEstimate;
 }
 
 /// Action to be taken by pre-lock filter.
@@ -32,8 +84,10 @@ pub(crate) enum PreLockFilterAction {
 }
 
 /// Metrics from scheduling transactions.
-#[derive(Default, Debug, PartialEq, Eq)]
-pub(crate) struct SchedulingSummary {
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct SchedulingSummary {
     /// Number of transactions scheduled.
     pub num_scheduled: usize,
     /// Number of transactions that were not scheduled due to conflicts.
@@ -42,4 +96,68 @@ pub(crate) struct SchedulingSummary {
     pub num_filtered_out: usize,
     /// Time spent filtering transactions
     pub filter_time_us: u64,
+    /// Number of priority inclusions that could not be scheduled, see
+    /// `PrioGraphScheduler::set_priority_inclusions()`.
+    pub num_unschedulable_priority_inclusions: usize,
+    /// Number of in-flight transactions whose locks were force-released to
+    /// unblock a transaction that waited more than `max_lock_wait_passes`.
+    pub num_force_evictions: usize,
+    /// Number of transactions scheduled while a higher-priority transaction
+    /// was unschedulable earlier in the same pass.
+    pub num_priority_inversions: usize,
+    /// Number of transactions dropped because their deadline passed.
+    pub num_deadline_expired: usize,
+    /// Time spent inserting transactions into the prio-graph
+    pub graph_insertion_time_us: u64,
+}
+
+/// CUs of the batches completed by the workers.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub(crate) struct CompletedCus {
+    /// CUs reserved against the per-thread budgets when scheduling.
+    pub reserved: u64,
+    /// Cost of the committed transactions with their actual execution and
+    /// loaded accounts data size costs, as reported by the workers.
+    pub actual: u64,
+}
+
+impl CompletedCus {
+    pub fn record(&mut self, reserved: u64, actual: u64) {
+        saturating_add_assign!(self.reserved, reserved);
+        saturating_add_assign!(self.actual, actual);
+    }
+}
+
+/// Number of retryable transactions received from workers, by `RetryReason`.
+#[derive(Default, Debug, PartialEq, Eq)]
+pub(crate) struct RetryCounts {
+    /// Transactions retried due to an account lock conflict.
+    pub account_in_use: usize,
+    /// Transactions deferred to the next slot due to a block limit.
+    pub block_limit: usize,
+    /// Transactions retried for any other reason.
+    pub other: usize,
+}
+
+impl RetryCounts {
+    pub fn record(&mut self, reason: RetryReason) {
+        let count = match reason {
+            RetryReason::AccountInUse => &mut self.account_in_use,
+            RetryReason::BlockLimit => &mut self.block_limit,
+            RetryReason::Other => &mut self.other,
+        };
+        *count = count.saturating_add(1);
+    }
+
+    pub fn accumulate(&mut self, other: &Self) {
+        saturating_add_assign!(self.account_in_use, other.account_in_use);
+        saturating_add_assign!(self.block_limit, other.block_limit);
+        saturating_add_assign!(self.other, other.other);
+    }
+
+    pub fn total(&self) -> usize {
+        self.account_in_use
+            .saturating_add(self.block_limit)
+            .saturating_add(self.other)
+    }
 }
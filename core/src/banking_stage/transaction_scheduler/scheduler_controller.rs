@@ -3,23 +3,36 @@
 
 use {
     super::{
+        prio_graph_scheduler::{PrioGraphScheduler, PriorityInclusion},
         receive_and_buffer::ReceiveAndBuffer,
-        scheduler::{PreLockFilterAction, Scheduler},
+        scheduler::{PreLockFilterAction, Scheduler, SchedulingSummary},
         scheduler_error::SchedulerError,
         scheduler_metrics::{
             SchedulerCountMetrics, SchedulerLeaderDetectionMetrics, SchedulerTimingMetrics,
         },
+        scheduler_state::SharedSchedulerState,
+        scheduling_estimate::EstimateRequestReceiver,
+        stats::SchedulerStatsSnapshot,
+        thread_aware_account_locks::ThreadId,
     },
     crate::banking_stage::{
         consume_worker::ConsumeWorkerMetrics,
         consumer::Consumer,
         decision_maker::{BufferedPacketsDecision, DecisionMaker},
-        transaction_scheduler::transaction_state_container::StateContainer,
+        scheduler_messages::MaxAge,
+        transaction_scheduler::transaction_state_container::{
+            SharedAdmissionConfig, StateContainer, EXTRA_CAPACITY,
+        },
         TOTAL_BUFFERED_PACKETS,
     },
     solana_measure::measure_us,
     solana_runtime::{bank::Bank, bank_forks::BankForks},
-    solana_sdk::{self, clock::MAX_PROCESSING_AGE, saturating_add_assign},
+    solana_sdk::{
+        self,
+        clock::{Slot, MAX_PROCESSING_AGE},
+        saturating_add_assign,
+        timing::AtomicInterval,
+    },
     solana_svm::transaction_error_metrics::TransactionErrorMetrics,
     std::sync::{Arc, RwLock},
 };
@@ -49,8 +62,27 @@ where
     timing_metrics: SchedulerTimingMetrics,
     /// Metric report handles for the worker threads.
     worker_metrics: Vec<Arc<ConsumeWorkerMetrics>>,
+    /// Leader slot seen on the previous iteration. Transactions deferred by
+    /// the scheduler are pushed back into the queue when this changes.
+    last_leader_slot: Option<Slot>,
+    /// Requests for scheduling estimates, answered between scheduling passes.
+    estimate_request_receiver: Option<EstimateRequestReceiver>,
+    /// Latest state snapshot, updated every
+    /// `SCHEDULER_STATE_PUBLISH_INTERVAL_MS`.
+    scheduler_state: Option<SharedSchedulerState>,
+    scheduler_state_interval: AtomicInterval,
+    /// Admission floor of the container, applied on every iteration.
+    admission_config: Option<SharedAdmissionConfig>,
 }
 
+/// Estimate requests answered per iteration of the scheduler loop, so that a
+/// flood of requests cannot stall scheduling.
+const MAX_ESTIMATE_REQUESTS_PER_ITERATION: usize = 16;
+
+/// Interval at which the scheduler state snapshot is refreshed, so that its
+/// readers do not contend for its lock with every iteration of the loop.
+const SCHEDULER_STATE_PUBLISH_INTERVAL_MS: u64 = 100;
+
 impl<R, S> SchedulerController<R, S>
 where
     R: ReceiveAndBuffer,
@@ -73,9 +105,39 @@ where
             count_metrics: SchedulerCountMetrics::default(),
             timing_metrics: SchedulerTimingMetrics::default(),
             worker_metrics,
+            last_leader_slot: None,
+            estimate_request_receiver: None,
+            scheduler_state: None,
+            scheduler_state_interval: AtomicInterval::default(),
+            admission_config: None,
         }
     }
 
+    /// Answer the scheduling estimate requests received on `receiver`, see
+    /// `Scheduler::estimate()`.
+    pub(crate) fn with_estimate_request_receiver(
+        mut self,
+        receiver: EstimateRequestReceiver,
+    ) -> Self {
+        self.estimate_request_receiver = Some(receiver);
+        self
+    }
+
+    /// Publish a snapshot of the scheduler state to `scheduler_state` every
+    /// `SCHEDULER_STATE_PUBLISH_INTERVAL_MS`, see
+    /// `Scheduler::state_snapshot()`.
+    pub(crate) fn with_scheduler_state(mut self, scheduler_state: SharedSchedulerState) -> Self {
+        self.scheduler_state = Some(scheduler_state);
+        self
+    }
+
+    /// Apply the latest admission floor set in `admission_config` to the
+    /// container on every iteration, see `AdmissionConfig`.
+    pub(crate) fn with_admission_config(mut self, admission_config: SharedAdmissionConfig) -> Self {
+        self.admission_config = Some(admission_config);
+        self
+    }
+
     pub fn run(mut self) -> Result<(), SchedulerError> {
         loop {
             // BufferedPacketsDecision is shared with legacy BankingStage, which will forward
@@ -100,12 +162,19 @@ where
                 .maybe_report_and_reset_slot(new_leader_slot);
             self.timing_metrics
                 .maybe_report_and_reset_slot(new_leader_slot);
+            if new_leader_slot != self.last_leader_slot {
+                self.scheduler.flush_held_batches()?;
+                self.requeue_deferred();
+                self.last_leader_slot = new_leader_slot;
+            }
 
             self.receive_completed()?;
+            self.answer_estimate_requests();
             self.process_transactions(&decision)?;
             if self.receive_and_buffer_packets(&decision).is_err() {
                 break;
             }
+            self.publish_scheduler_state();
             // Report metrics only if there is data.
             // Reset intervals when appropriate, regardless of report.
             let should_report = self.count_metrics.interval_has_data();
@@ -134,9 +203,10 @@ where
             BufferedPacketsDecision::Consume(bank_start) => {
                 let (scheduling_summary, schedule_time_us) = measure_us!(self.scheduler.schedule(
                     &mut self.container,
-                    |txs, results| {
+                    |txs, max_ages, results| {
                         Self::pre_graph_filter(
                             txs,
+                            max_ages,
                             results,
                             &bank_start.working_bank,
                             MAX_PROCESSING_AGE,
@@ -158,6 +228,18 @@ where
                         count_metrics.num_schedule_filtered_out,
                         scheduling_summary.num_filtered_out
                     );
+                    saturating_add_assign!(
+                        count_metrics.num_force_evictions,
+                        scheduling_summary.num_force_evictions
+                    );
+                    saturating_add_assign!(
+                        count_metrics.num_priority_inversions,
+                        scheduling_summary.num_priority_inversions
+                    );
+                    saturating_add_assign!(
+                        count_metrics.num_deadline_expired,
+                        scheduling_summary.num_deadline_expired
+                    );
                 });
 
                 self.timing_metrics.update(|timing_metrics| {
@@ -165,6 +247,10 @@ where
                         timing_metrics.schedule_filter_time_us,
                         scheduling_summary.filter_time_us
                     );
+                    saturating_add_assign!(
+                        timing_metrics.schedule_graph_insertion_time_us,
+                        scheduling_summary.graph_insertion_time_us
+                    );
                     saturating_add_assign!(timing_metrics.schedule_time_us, schedule_time_us);
                 });
             }
@@ -188,25 +274,43 @@ where
 
     fn pre_graph_filter(
         transactions: &[&R::Transaction],
+        max_ages: &[MaxAge],
         results: &mut [bool],
         bank: &Bank,
         max_age: usize,
     ) {
-        let lock_results = vec![Ok(()); transactions.len()];
+        // Drop the transactions whose blockhash or address lookups have
+        // expired before running the bank checks on the rest. Blockhashes not
+        // in the queue, such as durable nonces, are left to the bank checks.
+        let current_slot = bank.slot();
+        let (unexpired_indexes, unexpired_transactions): (Vec<_>, Vec<_>) = transactions
+            .iter()
+            .zip(max_ages)
+            .enumerate()
+            .filter(|(_, (tx, tx_max_age))| {
+                !bank
+                    .get_hash_age(tx.recent_blockhash())
+                    .is_some_and(|blockhash_age| tx_max_age.is_expired(current_slot, blockhash_age))
+            })
+            .map(|(index, (tx, _))| (index, *tx))
+            .unzip();
+        results.fill(false);
+
+        let lock_results = vec![Ok(()); unexpired_transactions.len()];
         let mut error_counters = TransactionErrorMetrics::default();
         let check_results = bank.check_transactions::<R::Transaction>(
-            transactions,
+            &unexpired_transactions,
             &lock_results,
             max_age,
             &mut error_counters,
         );
 
-        for ((check_result, tx), result) in check_results
+        for ((check_result, tx), index) in check_results
             .into_iter()
-            .zip(transactions)
-            .zip(results.iter_mut())
+            .zip(&unexpired_transactions)
+            .zip(unexpired_indexes)
         {
-            *result = check_result
+            results[index] = check_result
                 .and_then(|_| Consumer::check_fee_payer_unlocked(bank, *tx, &mut error_counters))
                 .is_ok();
         }
@@ -292,12 +396,28 @@ where
 
     /// Receives completed transactions from the workers and updates metrics.
     fn receive_completed(&mut self) -> Result<(), SchedulerError> {
-        let ((num_transactions, num_retryable), receive_completed_time_us) =
+        let ((num_transactions, retry_counts), receive_completed_time_us) =
             measure_us!(self.scheduler.receive_completed(&mut self.container)?);
+        let num_over_retry_threshold = self.scheduler.transactions_over_retry_threshold().len();
+        let completed_cus = self.scheduler.take_completed_cus();
 
         self.count_metrics.update(|count_metrics| {
             saturating_add_assign!(count_metrics.num_finished, num_transactions);
-            saturating_add_assign!(count_metrics.num_retryable, num_retryable);
+            saturating_add_assign!(count_metrics.num_retryable, retry_counts.total());
+            saturating_add_assign!(
+                count_metrics.num_retryable_account_in_use,
+                retry_counts.account_in_use
+            );
+            saturating_add_assign!(
+                count_metrics.num_retryable_block_limit,
+                retry_counts.block_limit
+            );
+            saturating_add_assign!(count_metrics.num_retryable_other, retry_counts.other);
+            count_metrics.max_over_retry_threshold = count_metrics
+                .max_over_retry_threshold
+                .max(num_over_retry_threshold);
+            saturating_add_assign!(count_metrics.reserved_cus, completed_cus.reserved);
+            saturating_add_assign!(count_metrics.actual_cus, completed_cus.actual);
         });
         self.timing_metrics.update(|timing_metrics| {
             saturating_add_assign!(
@@ -309,17 +429,116 @@ where
         Ok(())
     }
 
+    /// Publish a snapshot of the scheduler state, at most once per
+    /// `SCHEDULER_STATE_PUBLISH_INTERVAL_MS`.
+    fn publish_scheduler_state(&self) {
+        if let Some(scheduler_state) = &self.scheduler_state {
+            if !self
+                .scheduler_state_interval
+                .should_update(SCHEDULER_STATE_PUBLISH_INTERVAL_MS)
+            {
+                return;
+            }
+            let mut snapshot = self.scheduler.state_snapshot(&self.container);
+            snapshot.stats = Some(self.stats_snapshot(snapshot.last_summary));
+            *scheduler_state.write().unwrap() = Some(snapshot);
+        }
+    }
+
+    /// Metrics and configuration of the scheduler, with `last_summary` the
+    /// summary of the most recent scheduling pass.
+    fn stats_snapshot(&self, last_summary: SchedulingSummary) -> SchedulerStatsSnapshot {
+        SchedulerStatsSnapshot::new(
+            last_summary,
+            self.timing_metrics.totals(),
+            self.scheduler.config(),
+        )
+    }
+
+    /// Answer pending estimate requests. Requesters that stopped waiting for
+    /// their estimate are ignored.
+    fn answer_estimate_requests(&mut self) {
+        let Some(receiver) = &self.estimate_request_receiver else {
+            return;
+        };
+        for (request, response_sender) in receiver
+            .try_iter()
+            .take(MAX_ESTIMATE_REQUESTS_PER_ITERATION)
+        {
+            let _ = response_sender.send(self.scheduler.estimate(&self.container, &request));
+        }
+    }
+
+    /// Push transactions the scheduler deferred during the previous slot back
+    /// into the queue.
+    fn requeue_deferred(&mut self) {
+        let deferred = self.scheduler.take_deferred();
+        if deferred.is_empty() {
+            return;
+        }
+
+        let mut num_dropped_on_capacity: usize = 0;
+        for chunk in deferred.chunks(EXTRA_CAPACITY) {
+            let num_dropped = self.container.push_ids_into_queue(chunk.iter().copied());
+            saturating_add_assign!(num_dropped_on_capacity, num_dropped);
+        }
+
+        self.count_metrics.update(|count_metrics| {
+            saturating_add_assign!(count_metrics.num_deferred_requeued, deferred.len());
+            saturating_add_assign!(
+                count_metrics.num_dropped_on_capacity,
+                num_dropped_on_capacity
+            );
+        });
+    }
+
     /// Returns whether the packet receiver is still connected.
     fn receive_and_buffer_packets(
         &mut self,
         decision: &BufferedPacketsDecision,
     ) -> Result<usize, ()> {
-        self.receive_and_buffer.receive_and_buffer_packets(
+        if let Some(admission_config) = &self.admission_config {
+            self.container
+                .set_admission_config(*admission_config.read().unwrap());
+        }
+        let result = self.receive_and_buffer.receive_and_buffer_packets(
             &mut self.container,
             &mut self.timing_metrics,
             &mut self.count_metrics,
             decision,
-        )
+        );
+        let admission_counts = self.container.take_admission_counts();
+        self.count_metrics.update(|count_metrics| {
+            saturating_add_assign!(
+                count_metrics.num_dropped_below_admission_floor,
+                admission_counts.num_rejected
+            );
+            saturating_add_assign!(
+                count_metrics.num_admission_evictions,
+                admission_counts.num_evicted
+            );
+        });
+        result
+    }
+}
+
+impl<R> SchedulerController<R, PrioGraphScheduler<R::Transaction>>
+where
+    R: ReceiveAndBuffer,
+{
+    /// Resume the batches that were in flight on each thread, e.g. recovered
+    /// after a restart, before the first scheduling pass, so that it sees
+    /// their conflicts. See `PrioGraphScheduler::resume_in_flight_batch()`.
+    pub(crate) fn with_in_flight_batches(
+        mut self,
+        batches: Vec<(ThreadId, Vec<PriorityInclusion<R::Transaction>>)>,
+    ) -> Result<Self, SchedulerError> {
+        for (thread_id, batch) in batches {
+            if !self.scheduler.resume_in_flight_batch(thread_id, batch)? {
+                warn!("In-flight batch conflicts with another thread, rescheduling it");
+            }
+        }
+        Ok(self)
     }
 }
 
@@ -330,11 +549,14 @@ mod tests {
         crate::banking_stage::{
             consumer::TARGET_NUM_TRANSACTIONS_PER_BATCH,
             packet_deserializer::PacketDeserializer,
-            scheduler_messages::{ConsumeWork, FinishedConsumeWork, TransactionBatchId},
+            scheduler_messages::{
+                ConsumeWork, FinishedConsumeWork, MaxAge, RetryReason, TransactionBatchId,
+            },
             tests::create_slow_genesis_config,
             transaction_scheduler::{
-                prio_graph_scheduler::{PrioGraphScheduler, PrioGraphSchedulerConfig},
+                prio_graph_scheduler::PrioGraphSchedulerConfig,
                 receive_and_buffer::SanitizedTransactionReceiveAndBuffer,
+                scheduling_estimate::EstimateRequest,
             },
             TransactionViewReceiveAndBuffer,
         },
@@ -348,13 +570,18 @@ mod tests {
         solana_perf::packet::{to_packet_batches, PacketBatch, NUM_PACKETS},
         solana_poh::poh_recorder::{PohRecorder, Record, WorkingBankEntry},
         solana_runtime::bank::Bank,
-        solana_runtime_transaction::transaction_meta::StaticMeta,
+        solana_runtime_transaction::{
+            runtime_transaction::RuntimeTransaction, transaction_meta::StaticMeta,
+        },
         solana_sdk::{
             compute_budget::ComputeBudgetInstruction, fee_calculator::FeeRateGovernor, hash::Hash,
             message::Message, poh_config::PohConfig, pubkey::Pubkey, signature::Keypair,
             signer::Signer, system_instruction, system_transaction, transaction::Transaction,
         },
-        std::sync::{atomic::AtomicBool, Arc, RwLock},
+        std::{
+            sync::{atomic::AtomicBool, Arc, RwLock},
+            time::Duration,
+        },
         tempfile::TempDir,
         test_case::test_case,
     };
@@ -392,6 +619,7 @@ mod tests {
         TransactionViewReceiveAndBuffer {
             receiver,
             bank_forks,
+            transaction_ttl: None,
         }
     }
 
@@ -542,6 +770,9 @@ mod tests {
                     max_ages: vec![],
                 },
                 retryable_indexes: vec![],
+                retryable_reasons: vec![],
+                retry_all: false,
+                actual_cus_used: 0,
             })
             .unwrap();
 
@@ -608,6 +839,70 @@ mod tests {
         assert_eq!(message_hashes, vec![&tx2_hash, &tx1_hash]);
     }
 
+    #[test]
+    fn test_schedule_consume_after_in_flight_batches() {
+        let (test_frame, scheduler_controller) =
+            create_test_frame(2, test_create_sanitized_transaction_receive_and_buffer);
+        let TestFrame {
+            bank,
+            mint_keypair,
+            poh_recorder,
+            banking_packet_sender,
+            consume_work_receivers,
+            ..
+        } = &test_frame;
+
+        poh_recorder
+            .write()
+            .unwrap()
+            .set_bank_for_test(bank.clone());
+
+        // a batch in flight on thread 1 writes the account
+        let account = Pubkey::new_unique();
+        let in_flight = create_and_fund_prioritized_transfer(
+            bank,
+            mint_keypair,
+            &Keypair::new(),
+            &account,
+            1,
+            1000,
+            bank.last_blockhash(),
+        );
+        let in_flight_hash = in_flight.message().hash();
+        let mut scheduler_controller = scheduler_controller
+            .with_in_flight_batches(vec![(
+                1,
+                vec![(
+                    RuntimeTransaction::from_transaction_for_tests(in_flight),
+                    MaxAge::MAX,
+                    10_000,
+                )],
+            )])
+            .unwrap();
+        let consume_work = consume_work_receivers[1].try_recv().unwrap();
+        assert_eq!(consume_work.transactions[0].message_hash(), &in_flight_hash);
+
+        // so a fresh transaction writing it goes to the same thread
+        let tx = create_and_fund_prioritized_transfer(
+            bank,
+            mint_keypair,
+            &Keypair::new(),
+            &account,
+            1,
+            2000,
+            bank.last_blockhash(),
+        );
+        let tx_hash = tx.message().hash();
+        banking_packet_sender
+            .send(to_banking_packet_batch(&[tx]))
+            .unwrap();
+
+        test_receive_then_schedule(&mut scheduler_controller);
+        assert!(consume_work_receivers[0].is_empty());
+        let consume_work = consume_work_receivers[1].try_recv().unwrap();
+        assert_eq!(consume_work.transactions[0].message_hash(), &tx_hash);
+    }
+
     #[test_case(test_create_sanitized_transaction_receive_and_buffer; "Sdk")]
     #[test_case(test_create_transaction_view_receive_and_buffer; "View")]
     fn test_schedule_consume_single_threaded_conflict<R: ReceiveAndBuffer>(
@@ -875,6 +1170,9 @@ mod tests {
             .send(FinishedConsumeWork {
                 work: consume_work,
                 retryable_indexes: vec![1],
+                retryable_reasons: vec![RetryReason::AccountInUse],
+                retry_all: false,
+                actual_cus_used: 0,
             })
             .unwrap();
 
@@ -890,4 +1188,212 @@ mod tests {
             .collect_vec();
         assert_eq!(message_hashes, vec![&tx1_hash]);
     }
+
+    #[test_case(test_create_sanitized_transaction_receive_and_buffer; "Sdk")]
+    #[test_case(test_create_transaction_view_receive_and_buffer; "View")]
+    fn test_reject_recently_completed<R: ReceiveAndBuffer>(
+        create_receive_and_buffer: impl FnOnce(BankingPacketReceiver, Arc<RwLock<BankForks>>) -> R,
+    ) {
+        let (test_frame, mut scheduler_controller) =
+            create_test_frame(1, create_receive_and_buffer);
+        let TestFrame {
+            bank,
+            mint_keypair,
+            poh_recorder,
+            banking_packet_sender,
+            consume_work_receivers,
+            finished_consume_work_sender,
+            ..
+        } = &test_frame;
+
+        poh_recorder
+            .write()
+            .unwrap()
+            .set_bank_for_test(bank.clone());
+
+        let tx = create_and_fund_prioritized_transfer(
+            bank,
+            mint_keypair,
+            &Keypair::new(),
+            &Pubkey::new_unique(),
+            1,
+            1000,
+            bank.last_blockhash(),
+        );
+        let txs = vec![tx];
+        banking_packet_sender
+            .send(to_banking_packet_batch(&txs))
+            .unwrap();
+        test_receive_then_schedule(&mut scheduler_controller);
+        let consume_work = consume_work_receivers[0].try_recv().unwrap();
+        assert_eq!(consume_work.ids.len(), 1);
+
+        // Complete the transaction, then re-submit it before the client could
+        // see it confirmed
+        finished_consume_work_sender
+            .send(FinishedConsumeWork {
+                work: consume_work,
+                retryable_indexes: vec![],
+                retryable_reasons: vec![],
+                retry_all: false,
+                actual_cus_used: 0,
+            })
+            .unwrap();
+        banking_packet_sender
+            .send(to_banking_packet_batch(&txs))
+            .unwrap();
+        test_receive_then_schedule(&mut scheduler_controller);
+        assert!(consume_work_receivers[0].try_recv().is_err());
+        assert!(scheduler_controller.container.is_empty());
+        let metrics = scheduler_controller.count_metrics.slot_metrics();
+        assert_eq!(metrics.num_dropped_on_recently_completed, 1);
+        assert_eq!(metrics.num_dropped_on_duplicate, 0);
+        assert_eq!(metrics.num_buffered, 1);
+    }
+
+    fn test_create_sanitized_transaction_receive_and_buffer_expiring(
+        receiver: BankingPacketReceiver,
+        bank_forks: Arc<RwLock<BankForks>>,
+    ) -> SanitizedTransactionReceiveAndBuffer {
+        test_create_sanitized_transaction_receive_and_buffer(receiver, bank_forks)
+            .with_transaction_ttl(Some(Duration::ZERO))
+    }
+
+    fn test_create_transaction_view_receive_and_buffer_expiring(
+        receiver: BankingPacketReceiver,
+        bank_forks: Arc<RwLock<BankForks>>,
+    ) -> TransactionViewReceiveAndBuffer {
+        TransactionViewReceiveAndBuffer {
+            transaction_ttl: Some(Duration::ZERO),
+            ..test_create_transaction_view_receive_and_buffer(receiver, bank_forks)
+        }
+    }
+
+    #[test_case(test_create_sanitized_transaction_receive_and_buffer_expiring; "Sdk")]
+    #[test_case(test_create_transaction_view_receive_and_buffer_expiring; "View")]
+    fn test_transaction_ttl<R: ReceiveAndBuffer>(
+        create_receive_and_buffer: impl FnOnce(BankingPacketReceiver, Arc<RwLock<BankForks>>) -> R,
+    ) {
+        let (test_frame, mut scheduler_controller) =
+            create_test_frame(1, create_receive_and_buffer);
+        let TestFrame {
+            bank,
+            mint_keypair,
+            poh_recorder,
+            banking_packet_sender,
+            consume_work_receivers,
+            ..
+        } = &test_frame;
+
+        poh_recorder
+            .write()
+            .unwrap()
+            .set_bank_for_test(bank.clone());
+
+        // the deadline is set on receipt, and has passed by the time the
+        // transaction could be scheduled
+        let tx = create_and_fund_prioritized_transfer(
+            bank,
+            mint_keypair,
+            &Keypair::new(),
+            &Pubkey::new_unique(),
+            1,
+            1000,
+            bank.last_blockhash(),
+        );
+        banking_packet_sender
+            .send(to_banking_packet_batch(&[tx]))
+            .unwrap();
+        test_receive_then_schedule(&mut scheduler_controller);
+        assert!(consume_work_receivers[0].try_recv().is_err());
+        assert!(scheduler_controller.container.is_empty());
+        let metrics = scheduler_controller.count_metrics.slot_metrics();
+        assert_eq!(metrics.num_buffered, 1);
+        assert_eq!(metrics.num_deadline_expired, 1);
+    }
+
+    #[test_case(test_create_sanitized_transaction_receive_and_buffer; "Sdk")]
+    #[test_case(test_create_transaction_view_receive_and_buffer; "View")]
+    fn test_answer_estimate_requests<R: ReceiveAndBuffer>(
+        create_receive_and_buffer: impl FnOnce(BankingPacketReceiver, Arc<RwLock<BankForks>>) -> R,
+    ) {
+        let (estimate_request_sender, estimate_request_receiver) = unbounded();
+        let (test_frame, scheduler_controller) = create_test_frame(1, create_receive_and_buffer);
+        let mut scheduler_controller =
+            scheduler_controller.with_estimate_request_receiver(estimate_request_receiver);
+        let TestFrame {
+            bank,
+            mint_keypair,
+            poh_recorder,
+            banking_packet_sender,
+            consume_work_receivers,
+            ..
+        } = &test_frame;
+
+        poh_recorder
+            .write()
+            .unwrap()
+            .set_bank_for_test(bank.clone());
+
+        let to_pubkey = Pubkey::new_unique();
+        let tx = create_and_fund_prioritized_transfer(
+            bank,
+            mint_keypair,
+            &Keypair::new(),
+            &to_pubkey,
+            1,
+            1000,
+            bank.last_blockhash(),
+        );
+        banking_packet_sender
+            .send(to_banking_packet_batch(&[tx]))
+            .unwrap();
+        let decision = scheduler_controller
+            .decision_maker
+            .make_consume_or_forward_decision();
+        while scheduler_controller
+            .receive_and_buffer_packets(&decision)
+            .map(|n| n > 0)
+            .unwrap_or_default()
+        {}
+
+        let request_estimate = |scheduler_controller: &mut SchedulerController<
+            R,
+            PrioGraphScheduler<R::Transaction>,
+        >| {
+            let (response_sender, response_receiver) = unbounded();
+            let request = EstimateRequest {
+                write_accounts: vec![to_pubkey],
+                ..EstimateRequest::default()
+            };
+            estimate_request_sender
+                .send((request, response_sender))
+                .unwrap();
+            scheduler_controller.answer_estimate_requests();
+            response_receiver.try_recv().unwrap()
+        };
+
+        // the buffered transfer conflicts with the candidate
+        let estimate = request_estimate(&mut scheduler_controller);
+        assert!(estimate.would_schedule_now);
+        assert_eq!(estimate.num_conflicting, 1);
+        assert!(estimate.min_conflicting_priority.is_some());
+
+        // once scheduled, it is no longer queued
+        test_receive_then_schedule(&mut scheduler_controller);
+        assert_eq!(consume_work_receivers[0].try_recv().unwrap().ids.len(), 1);
+        let estimate = request_estimate(&mut scheduler_controller);
+        assert!(estimate.would_schedule_now);
+        assert_eq!(estimate.num_conflicting, 0);
+        assert_eq!(estimate.min_conflicting_priority, None);
+
+        // requesters that stopped waiting are ignored
+        let (response_sender, response_receiver) = unbounded();
+        drop(response_receiver);
+        estimate_request_sender
+            .send((EstimateRequest::default(), response_sender))
+            .unwrap();
+        scheduler_controller.answer_estimate_requests();
+        assert!(estimate_request_sender.is_empty());
+    }
 }
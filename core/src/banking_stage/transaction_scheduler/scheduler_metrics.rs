@@ -28,6 +28,11 @@ impl SchedulerCountMetrics {
     pub fn interval_has_data(&self) -> bool {
         self.interval.metrics.has_data()
     }
+
+    #[cfg(test)]
+    pub(crate) fn slot_metrics(&self) -> &SchedulerCountMetricsInner {
+        &self.slot.metrics
+    }
 }
 
 #[derive(Default)]
@@ -55,10 +60,33 @@ pub struct SchedulerCountMetricsInner {
     pub num_unschedulable: usize,
     /// Number of transactions that were filtered out during scheduling.
     pub num_schedule_filtered_out: usize,
+    /// Number of in-flight transactions whose locks were force-released to
+    /// unblock a long-waiting transaction.
+    pub num_force_evictions: usize,
+    /// Number of transactions scheduled ahead of a higher-priority
+    /// unschedulable transaction.
+    pub num_priority_inversions: usize,
+    /// Number of transactions dropped during scheduling because their
+    /// deadline passed.
+    pub num_deadline_expired: usize,
     /// Number of completed transactions received from workers.
     pub num_finished: usize,
     /// Number of transactions that were retryable.
     pub num_retryable: usize,
+    /// Number of retryable transactions that hit an account lock conflict.
+    pub num_retryable_account_in_use: usize,
+    /// Number of retryable transactions deferred to the next slot due to a block limit.
+    pub num_retryable_block_limit: usize,
+    /// Number of transactions that were retryable for any other reason.
+    pub num_retryable_other: usize,
+    /// Number of deferred transactions pushed back into the queue on a new slot.
+    pub num_deferred_requeued: usize,
+    /// Max number of transactions retried more than the retry threshold.
+    pub max_over_retry_threshold: usize,
+    /// CUs reserved by the completed batches when they were scheduled.
+    pub reserved_cus: u64,
+    /// CUs actually consumed by the completed batches.
+    pub actual_cus: u64,
 
     /// Number of transactions that were immediately dropped on receive.
     pub num_dropped_on_receive: usize,
@@ -75,6 +103,18 @@ pub struct SchedulerCountMetricsInner {
     pub num_dropped_on_age_and_status: usize,
     /// Number of transactions that were dropped due to exceeded capacity.
     pub num_dropped_on_capacity: usize,
+    /// Number of transactions that were dropped because a transaction with
+    /// the same signature was already in the container.
+    pub num_dropped_on_duplicate: usize,
+    /// Number of transactions that were dropped because a transaction with
+    /// the same signature recently completed.
+    pub num_dropped_on_recently_completed: usize,
+    /// Number of transactions that were rejected below the admission floor
+    /// of the container.
+    pub num_dropped_below_admission_floor: usize,
+    /// Number of queued transactions that were evicted by the admission floor
+    /// of the container.
+    pub num_admission_evictions: usize,
     /// Min prioritization fees in the transaction container
     pub min_prioritization_fees: u64,
     /// Max prioritization fees in the transaction container
@@ -120,8 +160,34 @@ impl SchedulerCountMetricsInner {
                 self.num_schedule_filtered_out,
                 i64
             ),
+            ("num_force_evictions", self.num_force_evictions, i64),
+            (
+                "num_priority_inversions",
+                self.num_priority_inversions,
+                i64
+            ),
+            ("num_deadline_expired", self.num_deadline_expired, i64),
             ("num_finished", self.num_finished, i64),
             ("num_retryable", self.num_retryable, i64),
+            (
+                "num_retryable_account_in_use",
+                self.num_retryable_account_in_use,
+                i64
+            ),
+            (
+                "num_retryable_block_limit",
+                self.num_retryable_block_limit,
+                i64
+            ),
+            ("num_retryable_other", self.num_retryable_other, i64),
+            ("num_deferred_requeued", self.num_deferred_requeued, i64),
+            (
+                "max_over_retry_threshold",
+                self.max_over_retry_threshold,
+                i64
+            ),
+            ("reserved_cus", self.reserved_cus, i64),
+            ("actual_cus", self.actual_cus, i64),
             ("num_dropped_on_receive", self.num_dropped_on_receive, i64),
             (
                 "num_dropped_on_sanitization",
@@ -145,6 +211,26 @@ impl SchedulerCountMetricsInner {
                 i64
             ),
             ("num_dropped_on_capacity", self.num_dropped_on_capacity, i64),
+            (
+                "num_dropped_on_duplicate",
+                self.num_dropped_on_duplicate,
+                i64
+            ),
+            (
+                "num_dropped_on_recently_completed",
+                self.num_dropped_on_recently_completed,
+                i64
+            ),
+            (
+                "num_dropped_below_admission_floor",
+                self.num_dropped_below_admission_floor,
+                i64
+            ),
+            (
+                "num_admission_evictions",
+                self.num_admission_evictions,
+                i64
+            ),
             ("min_priority", self.get_min_priority(), i64),
             ("max_priority", self.get_max_priority(), i64)
         );
@@ -160,8 +246,18 @@ impl SchedulerCountMetricsInner {
             || self.num_scheduled != 0
             || self.num_unschedulable != 0
             || self.num_schedule_filtered_out != 0
+            || self.num_force_evictions != 0
+            || self.num_priority_inversions != 0
+            || self.num_deadline_expired != 0
             || self.num_finished != 0
             || self.num_retryable != 0
+            || self.num_retryable_account_in_use != 0
+            || self.num_retryable_block_limit != 0
+            || self.num_retryable_other != 0
+            || self.num_deferred_requeued != 0
+            || self.max_over_retry_threshold != 0
+            || self.reserved_cus != 0
+            || self.actual_cus != 0
             || self.num_dropped_on_receive != 0
             || self.num_dropped_on_sanitization != 0
             || self.num_dropped_on_validate_locks != 0
@@ -169,6 +265,10 @@ impl SchedulerCountMetricsInner {
             || self.num_dropped_on_clear != 0
             || self.num_dropped_on_age_and_status != 0
             || self.num_dropped_on_capacity != 0
+            || self.num_dropped_on_duplicate != 0
+            || self.num_dropped_on_recently_completed != 0
+            || self.num_dropped_below_admission_floor != 0
+            || self.num_admission_evictions != 0
     }
 
     fn reset(&mut self) {
@@ -177,8 +277,18 @@ impl SchedulerCountMetricsInner {
         self.num_scheduled = 0;
         self.num_unschedulable = 0;
         self.num_schedule_filtered_out = 0;
+        self.num_force_evictions = 0;
+        self.num_priority_inversions = 0;
+        self.num_deadline_expired = 0;
         self.num_finished = 0;
         self.num_retryable = 0;
+        self.num_retryable_account_in_use = 0;
+        self.num_retryable_block_limit = 0;
+        self.num_retryable_other = 0;
+        self.num_deferred_requeued = 0;
+        self.max_over_retry_threshold = 0;
+        self.reserved_cus = 0;
+        self.actual_cus = 0;
         self.num_dropped_on_receive = 0;
         self.num_dropped_on_sanitization = 0;
         self.num_dropped_on_validate_locks = 0;
@@ -186,6 +296,10 @@ impl SchedulerCountMetricsInner {
         self.num_dropped_on_clear = 0;
         self.num_dropped_on_age_and_status = 0;
         self.num_dropped_on_capacity = 0;
+        self.num_dropped_on_duplicate = 0;
+        self.num_dropped_on_recently_completed = 0;
+        self.num_dropped_below_admission_floor = 0;
+        self.num_admission_evictions = 0;
         self.min_prioritization_fees = u64::MAX;
         self.max_prioritization_fees = 0;
     }
@@ -225,12 +339,20 @@ impl SchedulerCountMetricsInner {
 pub struct SchedulerTimingMetrics {
     interval: IntervalSchedulerTimingMetrics,
     slot: SlotSchedulerTimingMetrics,
+    /// Timings accumulated since the scheduler started, never reset.
+    total: SchedulerTimings,
 }
 
 impl SchedulerTimingMetrics {
-    pub fn update(&mut self, update: impl Fn(&mut SchedulerTimingMetricsInner)) {
+    pub fn update(&mut self, update: impl Fn(&mut SchedulerTimings)) {
         update(&mut self.interval.metrics);
         update(&mut self.slot.metrics);
+        update(&mut self.total);
+    }
+
+    /// Timings accumulated since the scheduler started.
+    pub fn totals(&self) -> SchedulerTimings {
+        self.total
     }
 
     pub fn maybe_report_and_reset_slot(&mut self, slot: Option<Slot>) {
@@ -245,17 +367,20 @@ impl SchedulerTimingMetrics {
 #[derive(Default)]
 struct IntervalSchedulerTimingMetrics {
     interval: AtomicInterval,
-    metrics: SchedulerTimingMetricsInner,
+    metrics: SchedulerTimings,
 }
 
 #[derive(Default)]
 struct SlotSchedulerTimingMetrics {
     slot: Option<Slot>,
-    metrics: SchedulerTimingMetricsInner,
+    metrics: SchedulerTimings,
 }
 
-#[derive(Default)]
-pub struct SchedulerTimingMetricsInner {
+/// Time spent in each section of the scheduler loop, in microseconds.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct SchedulerTimings {
     /// Time spent making processing decisions.
     pub decision_time_us: u64,
     /// Time spent receiving packets.
@@ -264,6 +389,8 @@ pub struct SchedulerTimingMetricsInner {
     pub buffer_time_us: u64,
     /// Time spent filtering transactions during scheduling.
     pub schedule_filter_time_us: u64,
+    /// Time spent inserting transactions into the prio-graph during scheduling.
+    pub schedule_graph_insertion_time_us: u64,
     /// Time spent scheduling transactions.
     pub schedule_time_us: u64,
     /// Time spent clearing transactions from the container.
@@ -300,7 +427,7 @@ impl SlotSchedulerTimingMetrics {
     }
 }
 
-impl SchedulerTimingMetricsInner {
+impl SchedulerTimings {
     fn report(&self, name: &'static str, slot: Option<Slot>) {
         let mut datapoint = create_datapoint!(
             @point name,
@@ -308,6 +435,11 @@ impl SchedulerTimingMetricsInner {
             ("receive_time_us", self.receive_time_us, i64),
             ("buffer_time_us", self.buffer_time_us, i64),
             ("schedule_filter_time_us", self.schedule_filter_time_us, i64),
+            (
+                "schedule_graph_insertion_time_us",
+                self.schedule_graph_insertion_time_us,
+                i64
+            ),
             ("schedule_time_us", self.schedule_time_us, i64),
             ("clear_time_us", self.clear_time_us, i64),
             ("clean_time_us", self.clean_time_us, i64),
@@ -328,6 +460,7 @@ impl SchedulerTimingMetricsInner {
         self.receive_time_us = 0;
         self.buffer_time_us = 0;
         self.schedule_filter_time_us = 0;
+        self.schedule_graph_insertion_time_us = 0;
         self.schedule_time_us = 0;
         self.clear_time_us = 0;
         self.clean_time_us = 0;
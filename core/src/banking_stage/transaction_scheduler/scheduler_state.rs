@@ -0,0 +1,56 @@
+//! Point-in-time state of the central scheduler, served by the admin RPC
+//! `schedulerState` method.
+
+use {
+    super::{
+        in_flight_tracker::ThreadLoad, scheduler::SchedulingSummary, stats::SchedulerStatsSnapshot,
+        transaction_state_container::StateContainer,
+    },
+    solana_runtime_transaction::transaction_with_meta::TransactionWithMeta,
+    std::sync::{Arc, RwLock},
+};
+
+/// Latest snapshot of the scheduler state, refreshed periodically by the
+/// scheduler thread. `None` until the first refresh.
+pub type SharedSchedulerState = Arc<RwLock<Option<SchedulerStateSnapshot>>>;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchedulerStateSnapshot {
+    /// Number of transactions waiting in the queue.
+    pub queue_depth: usize,
+    /// Total cost of the transactions waiting in the queue.
+    pub queued_cus: u64,
+    /// Load of each worker thread, by thread id.
+    pub thread_loads: Vec<ThreadLoad>,
+    /// Number of accounts locked by in-flight transactions.
+    pub num_locked_accounts: usize,
+    /// Time since the oldest transaction held by the container, queued or
+    /// in flight, was received.
+    pub oldest_transaction_age_us: Option<u64>,
+    /// Summary of the most recent scheduling pass.
+    pub last_summary: SchedulingSummary,
+    /// Metrics and configuration of the scheduler, for external tooling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<SchedulerStatsSnapshot>,
+}
+
+impl SchedulerStateSnapshot {
+    pub(crate) fn new<Tx: TransactionWithMeta>(
+        container: &impl StateContainer<Tx>,
+        thread_loads: Vec<ThreadLoad>,
+        num_locked_accounts: usize,
+        last_summary: SchedulingSummary,
+    ) -> Self {
+        Self {
+            queue_depth: container.queue_len(),
+            queued_cus: container.queued_cus(),
+            thread_loads,
+            num_locked_accounts,
+            oldest_transaction_age_us: container
+                .oldest_insert_time()
+                .map(|insert_time| insert_time.elapsed().as_micros() as u64),
+            last_summary,
+            stats: None,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Read-only estimates of how the scheduler would treat a candidate
+//! transaction, answered from its current state rather than from recent
+//! blocks, e.g. for RPC priority fee suggestions.
+
+use {
+    super::{
+        thread_aware_account_locks::{ThreadAwareAccountLocks, ThreadSet},
+        transaction_state_container::StateContainer,
+    },
+    crossbeam_channel::{Receiver, Sender},
+    solana_cost_model::block_cost_limits::MAX_BLOCK_UNITS,
+    solana_runtime_transaction::transaction_with_meta::TransactionWithMeta,
+    solana_sdk::pubkey::Pubkey,
+    solana_svm_transaction::svm_message::SVMMessage,
+};
+
+/// Default number of queued transactions examined for conflicts by a single
+/// estimate, bounding the time the scheduler thread spends answering it.
+pub(crate) const DEFAULT_MAX_ESTIMATE_EXAMINED_TRANSACTIONS: usize = 4096;
+
+/// CUs the headroom of a thread is measured against, split evenly across
+/// threads. The same budget is used whichever scheduler answers, so that
+/// estimates do not depend on the scheduler configuration.
+const ESTIMATE_SCHEDULED_CUS: u64 = MAX_BLOCK_UNITS;
+
+/// Requests are sent to the scheduler thread along with the channel the
+/// estimate is sent back on.
+pub type EstimateRequestSender = Sender<(EstimateRequest, Sender<SchedulingEstimate>)>;
+pub(crate) type EstimateRequestReceiver = Receiver<(EstimateRequest, Sender<SchedulingEstimate>)>;
+
+/// Account access list and cost of the candidate transaction.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EstimateRequest {
+    pub write_accounts: Vec<Pubkey>,
+    pub read_accounts: Vec<Pubkey>,
+    pub cost: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchedulingEstimate {
+    /// The accounts can be locked on a thread with enough CU headroom for
+    /// the cost of the transaction.
+    pub would_schedule_now: bool,
+    /// Lowest priority among the queued transactions conflicting with the
+    /// candidate, `None` if none was found.
+    pub min_conflicting_priority: Option<u64>,
+    /// Number of queued transactions found conflicting with the candidate.
+    pub num_conflicting: usize,
+    /// CUs left on the least loaded thread the accounts can be locked on.
+    /// Zero if the accounts cannot be locked on any thread.
+    pub cu_headroom: u64,
+    /// The examination cap was reached before the whole queue was examined,
+    /// so conflicting transactions may have been missed.
+    pub truncated: bool,
+}
+
+/// Estimate how a transaction accessing the accounts of `request` would be
+/// treated by the next scheduling pass, given the current `account_locks`,
+/// the CUs in flight on each thread and the transactions queued in
+/// `container`. At most `max_examined` queued transactions are examined,
+/// highest priority first.
+pub(crate) fn estimate<Tx: TransactionWithMeta>(
+    request: &EstimateRequest,
+    account_locks: &ThreadAwareAccountLocks,
+    cus_in_flight_per_thread: &[u64],
+    container: &impl StateContainer<Tx>,
+    max_examined: usize,
+) -> SchedulingEstimate {
+    let max_cu_per_thread = ESTIMATE_SCHEDULED_CUS / cus_in_flight_per_thread.len() as u64;
+    let lockable_threads = account_locks
+        .accounts_schedulable_threads(request.write_accounts.iter(), request.read_accounts.iter())
+        .unwrap_or(ThreadSet::none());
+    let cu_headroom = lockable_threads
+        .contained_threads_iter()
+        .map(|thread_id| max_cu_per_thread.saturating_sub(cus_in_flight_per_thread[thread_id]))
+        .max()
+        .unwrap_or(0);
+
+    let mut estimate = SchedulingEstimate {
+        would_schedule_now: !lockable_threads.is_empty() && cu_headroom >= request.cost,
+        cu_headroom,
+        ..SchedulingEstimate::default()
+    };
+    let mut queued_ids: Vec<_> = container.queued_ids().copied().collect();
+    if queued_ids.len() > max_examined {
+        queued_ids.select_nth_unstable_by(max_examined, |a, b| b.cmp(a));
+        queued_ids.truncate(max_examined);
+        estimate.truncated = true;
+    }
+    for priority_id in queued_ids {
+        let Some(transaction_ttl) = container.get_transaction_ttl(priority_id.id) else {
+            continue;
+        };
+        if conflicts(request, &transaction_ttl.transaction) {
+            estimate.num_conflicting += 1;
+            estimate.min_conflicting_priority = Some(
+                estimate
+                    .min_conflicting_priority
+                    .map_or(priority_id.priority, |min| min.min(priority_id.priority)),
+            );
+        }
+    }
+
+    estimate
+}
+
+/// Returns true if `message` writes an account accessed by `request`, or
+/// reads an account written by it.
+fn conflicts(request: &EstimateRequest, message: &impl SVMMessage) -> bool {
+    message
+        .account_keys()
+        .iter()
+        .enumerate()
+        .any(|(index, key)| {
+            request.write_accounts.contains(key)
+                || (message.is_writable(index) && request.read_accounts.contains(key))
+        })
+}
@@ -0,0 +1,187 @@
+//! Metrics and configuration of the central scheduler, for tooling outside of
+//! the validator, e.g. block-packing analysis.
+//!
+//! These types are serialized by the admin RPC `schedulerState` method. Their
+//! fields may be added to over time, so the structs are `#[non_exhaustive]`
+//! and missing fields deserialize to their defaults. Renaming or removing a
+//! field, or changing its meaning, bumps `SCHEDULER_STATS_VERSION`.
+
+pub use super::{
+    greedy_scheduler::GreedySchedulerConfig, prio_graph_scheduler::PrioGraphSchedulerConfig,
+    scheduler::SchedulingSummary, scheduler_metrics::SchedulerTimings,
+};
+
+/// Version of the format of `SchedulerStatsSnapshot`.
+pub const SCHEDULER_STATS_VERSION: u32 = 1;
+
+/// Configuration of the running scheduler, tagged by the kind of scheduler.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "scheduler", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SchedulerConfig {
+    PrioGraph(PrioGraphSchedulerConfig),
+    Greedy(GreedySchedulerConfig),
+}
+
+/// Metrics and configuration of the scheduler at a point in time.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SchedulerStatsSnapshot {
+    /// `SCHEDULER_STATS_VERSION` of the scheduler that took the snapshot.
+    pub version: u32,
+    /// Summary of the most recent scheduling pass.
+    pub last_summary: SchedulingSummary,
+    /// Time spent in each section of the scheduler loop since it started.
+    pub timings: SchedulerTimings,
+    pub config: SchedulerConfig,
+}
+
+impl SchedulerStatsSnapshot {
+    pub(crate) fn new(
+        last_summary: SchedulingSummary,
+        timings: SchedulerTimings,
+        config: SchedulerConfig,
+    ) -> Self {
+        Self {
+            version: SCHEDULER_STATS_VERSION,
+            last_summary,
+            timings,
+            config,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json, std::time::Duration};
+
+    fn snapshot() -> SchedulerStatsSnapshot {
+        SchedulerStatsSnapshot::new(
+            SchedulingSummary {
+                num_scheduled: 10,
+                num_unschedulable: 2,
+                num_filtered_out: 1,
+                filter_time_us: 30,
+                num_unschedulable_priority_inclusions: 0,
+                num_force_evictions: 1,
+                num_priority_inversions: 3,
+                num_deadline_expired: 4,
+                graph_insertion_time_us: 50,
+            },
+            SchedulerTimings {
+                decision_time_us: 1,
+                receive_time_us: 2,
+                buffer_time_us: 3,
+                schedule_filter_time_us: 4,
+                schedule_graph_insertion_time_us: 5,
+                schedule_time_us: 6,
+                clear_time_us: 7,
+                clean_time_us: 8,
+                receive_completed_time_us: 9,
+            },
+            SchedulerConfig::PrioGraph(PrioGraphSchedulerConfig {
+                max_scheduled_cus: 48_000_000,
+                max_scanned_transactions_per_scheduling_pass: 1000,
+                look_ahead_window_size: 256,
+                target_transactions_per_batch: 64,
+                retry_threshold: 5,
+                summary_history_size: 0,
+                max_lock_wait_passes: 8,
+                readonly_fanin_threshold: 32,
+                batch_coalesce: Some(Duration::from_micros(500)),
+                sticky_account_affinity: true,
+                max_estimate_examined_transactions: 512,
+                single_batch_per_thread: false,
+                retryables_first: false,
+                lowest_thread_id_selection: false,
+                weighted_random_thread_selection: false,
+                rng_seed: Some(42),
+            }),
+        )
+    }
+
+    #[test]
+    fn test_scheduler_stats_snapshot_round_trip() {
+        let snapshot = snapshot();
+        let serialized = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SchedulerStatsSnapshot>(&serialized).unwrap(),
+            snapshot
+        );
+
+        let greedy = SchedulerStatsSnapshot {
+            config: SchedulerConfig::Greedy(GreedySchedulerConfig::default()),
+            ..snapshot
+        };
+        let serialized = serde_json::to_value(&greedy).unwrap();
+        assert_eq!(serialized["config"]["scheduler"], "greedy");
+        assert_eq!(
+            serde_json::from_value::<SchedulerStatsSnapshot>(serialized).unwrap(),
+            greedy
+        );
+    }
+
+    /// Renaming or removing any of these fields breaks the tools reading the
+    /// snapshots, and requires bumping `SCHEDULER_STATS_VERSION`.
+    #[test]
+    fn test_scheduler_stats_snapshot_fields() {
+        let pinned = json!({
+            "version": 1,
+            "last_summary": {
+                "num_scheduled": 10,
+                "num_unschedulable": 2,
+                "num_filtered_out": 1,
+                "filter_time_us": 30,
+                "num_unschedulable_priority_inclusions": 0,
+                "num_force_evictions": 1,
+                "num_priority_inversions": 3,
+                "num_deadline_expired": 4,
+                "graph_insertion_time_us": 50,
+            },
+            "timings": {
+                "decision_time_us": 1,
+                "receive_time_us": 2,
+                "buffer_time_us": 3,
+                "schedule_filter_time_us": 4,
+                "schedule_graph_insertion_time_us": 5,
+                "schedule_time_us": 6,
+                "clear_time_us": 7,
+                "clean_time_us": 8,
+                "receive_completed_time_us": 9,
+            },
+            "config": {
+                "scheduler": "prio_graph",
+                "max_scheduled_cus": 48_000_000,
+                "max_scanned_transactions_per_scheduling_pass": 1000,
+                "look_ahead_window_size": 256,
+                "target_transactions_per_batch": 64,
+                "retry_threshold": 5,
+                "summary_history_size": 0,
+                "max_lock_wait_passes": 8,
+                "readonly_fanin_threshold": 32,
+                "batch_coalesce": { "secs": 0, "nanos": 500_000 },
+                "sticky_account_affinity": true,
+                "max_estimate_examined_transactions": 512,
+                "single_batch_per_thread": false,
+                "retryables_first": false,
+                "lowest_thread_id_selection": false,
+                "weighted_random_thread_selection": false,
+                "rng_seed": 42,
+            },
+        });
+        assert_eq!(serde_json::to_value(snapshot()).unwrap(), pinned);
+        assert_eq!(
+            serde_json::from_value::<SchedulerStatsSnapshot>(pinned).unwrap(),
+            snapshot()
+        );
+
+        // fields added later read as their defaults from older snapshots
+        let mut older = serde_json::to_value(snapshot()).unwrap();
+        older["last_summary"]
+            .as_object_mut()
+            .unwrap()
+            .remove("num_deadline_expired");
+        let older = serde_json::from_value::<SchedulerStatsSnapshot>(older).unwrap();
+        assert_eq!(older.last_summary.num_deadline_expired, 0);
+    }
+}
@@ -0,0 +1,80 @@
+use {super::scheduler::SchedulingSummary, std::collections::VecDeque};
+
+/// Ring buffer of the summaries of the most recent scheduling passes, for
+/// spotting trends without external logging.
+pub(crate) struct SummaryHistory {
+    capacity: usize,
+    summaries: VecDeque<SchedulingSummary>,
+    /// Most recent summary, kept even when the history is disabled.
+    last: SchedulingSummary,
+}
+
+impl SummaryHistory {
+    /// A `capacity` of zero disables the history.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            summaries: VecDeque::with_capacity(capacity),
+            last: SchedulingSummary::default(),
+        }
+    }
+
+    /// Retain `summary`, evicting the oldest one if full. Returns `summary`.
+    pub(crate) fn record(&mut self, summary: SchedulingSummary) -> SchedulingSummary {
+        self.last = summary;
+        if self.capacity > 0 {
+            if self.summaries.len() == self.capacity {
+                self.summaries.pop_front();
+            }
+            self.summaries.push_back(summary);
+        }
+        summary
+    }
+
+    /// Retained summaries, oldest first.
+    pub(crate) fn summaries(&self) -> &VecDeque<SchedulingSummary> {
+        &self.summaries
+    }
+
+    /// Most recent summary, or the default one before the first pass.
+    pub(crate) fn last(&self) -> SchedulingSummary {
+        self.last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(num_scheduled: usize) -> SchedulingSummary {
+        SchedulingSummary {
+            num_scheduled,
+            ..SchedulingSummary::default()
+        }
+    }
+
+    #[test]
+    fn test_summary_history() {
+        let mut history = SummaryHistory::new(3);
+        for num_scheduled in 0..5 {
+            assert_eq!(
+                history.record(summary(num_scheduled)),
+                summary(num_scheduled)
+            );
+        }
+        assert_eq!(
+            history.summaries(),
+            &VecDeque::from([summary(2), summary(3), summary(4)])
+        );
+        assert_eq!(history.last(), summary(4));
+    }
+
+    #[test]
+    fn test_summary_history_disabled() {
+        let mut history = SummaryHistory::new(0);
+        assert_eq!(history.last(), SchedulingSummary::default());
+        history.record(summary(1));
+        assert!(history.summaries().is_empty());
+        assert_eq!(history.last(), summary(1));
+    }
+}
@@ -76,6 +76,11 @@ impl ThreadAwareAccountLocks {
         }
     }
 
+    /// Number of accounts with at least one lock held.
+    pub(crate) fn num_locked_accounts(&self) -> usize {
+        self.locks.len()
+    }
+
     /// Returns the `ThreadId` if the accounts are able to be locked
     /// for the given thread, otherwise `None` is returned.
     /// `allowed_threads` is a set of threads that the caller restricts locking to.
@@ -119,8 +124,46 @@ impl ThreadAwareAccountLocks {
         }
     }
 
+    /// Returns the thread holding the most locks on the most locked of the
+    /// given accounts, or `None` if none of them is locked.
+    pub(crate) fn dominant_account_thread<'a>(
+        &self,
+        accounts: impl Iterator<Item = &'a Pubkey>,
+    ) -> Option<ThreadId> {
+        accounts
+            .filter_map(|account| self.locks.get(account))
+            .map(|locks| {
+                let (read_thread, num_read_locks) = locks
+                    .read_locks
+                    .as_ref()
+                    .map(|read_locks| {
+                        let thread_id = read_locks
+                            .thread_set
+                            .contained_threads_iter()
+                            .max_by_key(|thread_id| read_locks.lock_counts[*thread_id])
+                            .unwrap();
+                        let num_locks: LockCount = read_locks
+                            .thread_set
+                            .contained_threads_iter()
+                            .map(|thread_id| read_locks.lock_counts[thread_id])
+                            .sum();
+                        (Some(thread_id), num_locks)
+                    })
+                    .unwrap_or((None, 0));
+                match &locks.write_locks {
+                    Some(write_locks) => (
+                        write_locks.lock_count + num_read_locks,
+                        write_locks.thread_id,
+                    ),
+                    None => (num_read_locks, read_thread.unwrap()),
+                }
+            })
+            .max_by_key(|(num_locks, _)| *num_locks)
+            .map(|(_, thread_id)| thread_id)
+    }
+
     /// Returns `ThreadSet` that the given accounts can be scheduled on.
-    fn accounts_schedulable_threads<'a>(
+    pub(crate) fn accounts_schedulable_threads<'a>(
         &self,
         write_account_locks: impl Iterator<Item = &'a Pubkey>,
         read_account_locks: impl Iterator<Item = &'a Pubkey>,
@@ -738,6 +781,35 @@ mod tests {
         assert!(locks.locks.is_empty());
     }
 
+    #[test]
+    fn test_dominant_account_thread() {
+        let pk1 = Pubkey::new_unique();
+        let pk2 = Pubkey::new_unique();
+        let pk3 = Pubkey::new_unique();
+        let mut locks = ThreadAwareAccountLocks::new(TEST_NUM_THREADS);
+        assert_eq!(
+            locks.dominant_account_thread([&pk1, &pk2].into_iter()),
+            None
+        );
+
+        locks.read_lock_account(&pk1, 1);
+        locks.read_lock_account(&pk1, 2);
+        locks.read_lock_account(&pk1, 2);
+        assert_eq!(locks.dominant_account_thread([&pk1].into_iter()), Some(2));
+
+        locks.write_lock_account(&pk2, 3);
+        assert_eq!(
+            locks.dominant_account_thread([&pk1, &pk2, &pk3].into_iter()),
+            Some(2)
+        );
+        locks.write_lock_account(&pk2, 3);
+        locks.write_lock_account(&pk2, 3);
+        assert_eq!(
+            locks.dominant_account_thread([&pk1, &pk2, &pk3].into_iter()),
+            Some(3)
+        );
+    }
+
     #[test]
     #[should_panic(expected = "thread_id must be < num_threads")]
     fn test_lock_accounts_invalid_thread() {
@@ -0,0 +1,138 @@
+use {crate::banking_stage::scheduler_messages::TransactionId, log::*};
+
+/// `is_overflowing_soon()` is true once the next id is within this distance
+/// of `TransactionId::MAX`.
+const OVERFLOW_WARNING_DISTANCE: TransactionId = 1_000_000;
+
+/// Allocates `TransactionId`s for transactions that are not in the container
+/// by incrementing a counter from `first_id`.
+///
+/// Incrementing would eventually overflow on a long-running validator, so
+/// when the counter reaches `TransactionId::MAX` allocation restarts from
+/// `first_id`, skipping any id that is still in use. Once every id from
+/// `first_id` on is in use, allocation fails.
+pub(crate) struct TransactionIdAllocator {
+    first_id: TransactionId,
+    next_id: TransactionId,
+}
+
+impl TransactionIdAllocator {
+    pub(crate) fn new(first_id: TransactionId) -> Self {
+        Self {
+            first_id,
+            next_id: first_id,
+        }
+    }
+
+    /// Allocate the next id for which `in_use` returns false, or `None` if
+    /// every id from `first_id` to `TransactionId::MAX` is in use.
+    /// `num_in_use` is the number of ids for which `in_use` returns true.
+    pub(crate) fn next(
+        &mut self,
+        num_in_use: usize,
+        in_use: impl Fn(TransactionId) -> bool,
+    ) -> Option<TransactionId> {
+        // one of any `num_in_use + 1` distinct ids is free, so the ids tried
+        // repeat, all being in use, only once every id is
+        for _ in 0..=num_in_use {
+            let was_overflowing_soon = self.is_overflowing_soon();
+            let id = self.next_id;
+            self.next_id = match id.checked_add(1) {
+                Some(next_id) => next_id,
+                None => {
+                    warn!(
+                        "TransactionId allocation overflowed, restarting from {}",
+                        self.first_id
+                    );
+                    self.first_id
+                }
+            };
+            if !was_overflowing_soon && self.is_overflowing_soon() {
+                warn!(
+                    "TransactionId allocation will overflow after {} more ids",
+                    TransactionId::MAX - self.next_id
+                );
+            }
+            if !in_use(id) {
+                return Some(id);
+            }
+        }
+        None
+    }
+
+    /// Whether the counter is about to overflow and restart from `first_id`.
+    pub(crate) fn is_overflowing_soon(&self) -> bool {
+        TransactionId::MAX - self.next_id < OVERFLOW_WARNING_DISTANCE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::collections::HashSet};
+
+    #[test]
+    fn test_is_overflowing_soon() {
+        assert!(!TransactionIdAllocator::new(0).is_overflowing_soon());
+
+        let mut allocator =
+            TransactionIdAllocator::new(TransactionId::MAX - OVERFLOW_WARNING_DISTANCE);
+        assert!(!allocator.is_overflowing_soon());
+        allocator.next(0, |_| false);
+        assert!(allocator.is_overflowing_soon());
+
+        // after the overflow, allocation is back at the start
+        let mut allocator = TransactionIdAllocator::new(0);
+        allocator.next_id = TransactionId::MAX;
+        assert!(allocator.is_overflowing_soon());
+        assert_eq!(allocator.next(0, |_| false), Some(TransactionId::MAX));
+        assert!(!allocator.is_overflowing_soon());
+        assert_eq!(allocator.next(0, |_| false), Some(0));
+    }
+
+    #[test]
+    fn test_overflow_skips_ids_in_use() {
+        let first_id = TransactionId::MAX - 4;
+        let mut allocator = TransactionIdAllocator::new(first_id);
+        let mut in_use = HashSet::new();
+        for _ in 0..5 {
+            let id = allocator
+                .next(in_use.len(), |id| in_use.contains(&id))
+                .unwrap();
+            in_use.insert(id);
+        }
+        assert_eq!(in_use, (first_id..=TransactionId::MAX).collect());
+
+        // the ids allocated before the overflow are still in use, except one
+        in_use.remove(&(first_id + 2));
+        let id = allocator
+            .next(in_use.len(), |id| in_use.contains(&id))
+            .unwrap();
+        assert_eq!(id, first_id + 2);
+        in_use.insert(id);
+
+        // ids are never reused while in use
+        in_use.remove(&first_id);
+        assert_eq!(
+            allocator.next(in_use.len(), |id| in_use.contains(&id)),
+            Some(first_id)
+        );
+    }
+
+    #[test]
+    fn test_exhausted() {
+        let first_id = TransactionId::MAX - 2;
+        let mut allocator = TransactionIdAllocator::new(first_id);
+        let in_use: HashSet<_> = (first_id..=TransactionId::MAX).collect();
+        assert_eq!(
+            allocator.next(in_use.len(), |id| in_use.contains(&id)),
+            None
+        );
+
+        // allocation resumes once an id is released
+        let in_use: HashSet<_> = (first_id..TransactionId::MAX).collect();
+        assert_eq!(
+            allocator.next(in_use.len(), |id| in_use.contains(&id)),
+            Some(TransactionId::MAX)
+        );
+    }
+}
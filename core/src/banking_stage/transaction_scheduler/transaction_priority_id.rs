@@ -8,12 +8,28 @@ use {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct TransactionPriorityId {
     pub(crate) priority: u64,
+    /// Set on retried transactions to order them ahead of the others of the
+    /// same priority.
+    pub(crate) retried: bool,
     pub(crate) id: TransactionId,
 }
 
 impl TransactionPriorityId {
     pub(crate) fn new(priority: u64, id: TransactionId) -> Self {
-        Self { priority, id }
+        Self {
+            priority,
+            retried: false,
+            id,
+        }
+    }
+
+    /// The same id, ordered ahead of the transactions of the same priority
+    /// that were not retried.
+    pub(crate) fn retried(self) -> Self {
+        Self {
+            retried: true,
+            ..self
+        }
     }
 }
 
@@ -55,6 +71,15 @@ mod tests {
             assert!(id2 >= id1);
         }
 
+        // Equal priority then retried first, whatever the id
+        {
+            let id1 = TransactionPriorityId::new(1, 2);
+            let id2 = TransactionPriorityId::new(1, 1).retried();
+            assert!(id1 < id2);
+            assert!(id2 > id1);
+            assert!(id2 < TransactionPriorityId::new(2, 0));
+        }
+
         // Equal priority and id
         {
             let id1 = TransactionPriorityId::new(1, 1);
@@ -0,0 +1,290 @@
+use {
+    super::transaction_priority_id::TransactionPriorityId,
+    crate::banking_stage::scheduler_messages::TransactionId,
+};
+
+/// Position of ids that are not in a heap.
+const NOT_QUEUED: usize = usize::MAX;
+
+/// A double-ended priority queue of `TransactionPriorityId`s, indexed by
+/// `TransactionId` so that the priority of a queued id can be updated in
+/// O(log n).
+///
+/// The ids are held in a max-heap and a min-heap, each tracking the position
+/// of every id, so that an id popped from one heap is removed from the other
+/// without searching it.
+/// Ids are expected to be below the capacity the queue is created with, the
+/// position tables growing otherwise.
+pub(crate) struct TransactionPriorityQueue {
+    max_heap: IndexedHeap<true>,
+    min_heap: IndexedHeap<false>,
+}
+
+impl TransactionPriorityQueue {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            max_heap: IndexedHeap::with_capacity(capacity),
+            min_heap: IndexedHeap::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.max_heap.heap.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.max_heap.heap.is_empty()
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.max_heap.heap.capacity()
+    }
+
+    /// Push `priority_id`, whose id must not be queued already.
+    pub(crate) fn push(&mut self, priority_id: TransactionPriorityId) {
+        self.max_heap.push(priority_id);
+        self.min_heap.push(priority_id);
+    }
+
+    pub(crate) fn peek_max(&self) -> Option<&TransactionPriorityId> {
+        self.max_heap.heap.first()
+    }
+
+    pub(crate) fn peek_min(&self) -> Option<&TransactionPriorityId> {
+        self.min_heap.heap.first()
+    }
+
+    pub(crate) fn pop_max(&mut self) -> Option<TransactionPriorityId> {
+        let id = self.max_heap.heap.first()?.id;
+        let priority_id = self.max_heap.remove(id);
+        self.min_heap.remove(priority_id.id);
+        Some(priority_id)
+    }
+
+    pub(crate) fn pop_min(&mut self) -> Option<TransactionPriorityId> {
+        let id = self.min_heap.heap.first()?.id;
+        let priority_id = self.min_heap.remove(id);
+        self.max_heap.remove(priority_id.id);
+        Some(priority_id)
+    }
+
+    /// Iterate over the queued ids, in no particular order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &TransactionPriorityId> {
+        self.max_heap.heap.iter()
+    }
+
+    /// Set the priority of the queued `id` to `priority`. Returns false if
+    /// `id` is not queued.
+    pub(crate) fn update_priority(&mut self, id: TransactionId, priority: u64) -> bool {
+        if !self.max_heap.contains(id) {
+            return false;
+        }
+        self.max_heap.update_priority(id, priority);
+        self.min_heap.update_priority(id, priority);
+        true
+    }
+
+    /// Remove the ids for which `predicate` returns false, rebuilding the
+    /// heaps in a single pass.
+    pub(crate) fn retain(&mut self, mut predicate: impl FnMut(&TransactionPriorityId) -> bool) {
+        self.max_heap.clear_positions();
+        self.max_heap
+            .heap
+            .retain(|priority_id| predicate(priority_id));
+        self.min_heap.clear_positions();
+        self.min_heap.heap.clear();
+        self.min_heap.heap.extend_from_slice(&self.max_heap.heap);
+        self.max_heap.heapify();
+        self.min_heap.heapify();
+    }
+}
+
+/// A binary heap of `TransactionPriorityId`s, with the highest one first if
+/// `MAX`, the lowest one otherwise.
+struct IndexedHeap<const MAX: bool> {
+    heap: Vec<TransactionPriorityId>,
+    /// Position in `heap` of each id, indexed by id.
+    positions: Vec<usize>,
+}
+
+impl<const MAX: bool> IndexedHeap<MAX> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            heap: Vec::with_capacity(capacity),
+            positions: vec![NOT_QUEUED; capacity],
+        }
+    }
+
+    fn contains(&self, id: TransactionId) -> bool {
+        self.positions
+            .get(id)
+            .is_some_and(|&position| position != NOT_QUEUED)
+    }
+
+    /// Whether `a` goes before `b`.
+    fn precedes(a: &TransactionPriorityId, b: &TransactionPriorityId) -> bool {
+        if MAX {
+            a > b
+        } else {
+            a < b
+        }
+    }
+
+    fn push(&mut self, priority_id: TransactionPriorityId) {
+        if priority_id.id >= self.positions.len() {
+            self.positions.resize(priority_id.id + 1, NOT_QUEUED);
+        }
+        debug_assert!(!self.contains(priority_id.id));
+        self.heap.push(priority_id);
+        self.set_position(self.heap.len() - 1);
+        self.sift_up(self.heap.len() - 1);
+    }
+
+    /// Remove the queued `id`.
+    fn remove(&mut self, id: TransactionId) -> TransactionPriorityId {
+        let position = std::mem::replace(&mut self.positions[id], NOT_QUEUED);
+        let priority_id = self.heap.swap_remove(position);
+        if position < self.heap.len() {
+            self.set_position(position);
+            self.restore(position);
+        }
+        priority_id
+    }
+
+    fn update_priority(&mut self, id: TransactionId, priority: u64) {
+        let position = self.positions[id];
+        self.heap[position].priority = priority;
+        self.restore(position);
+    }
+
+    fn clear_positions(&mut self) {
+        for priority_id in &self.heap {
+            self.positions[priority_id.id] = NOT_QUEUED;
+        }
+    }
+
+    /// Order `heap` and record the positions of its ids.
+    fn heapify(&mut self) {
+        for position in 0..self.heap.len() {
+            self.set_position(position);
+        }
+        for position in (0..self.heap.len() / 2).rev() {
+            self.sift_down(position);
+        }
+    }
+
+    /// Move the id at `position`, whose ordering changed, to its place.
+    fn restore(&mut self, position: usize) {
+        let position = self.sift_up(position);
+        self.sift_down(position);
+    }
+
+    fn sift_up(&mut self, mut position: usize) -> usize {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if !Self::precedes(&self.heap[position], &self.heap[parent]) {
+                break;
+            }
+            self.swap(position, parent);
+            position = parent;
+        }
+        position
+    }
+
+    fn sift_down(&mut self, mut position: usize) {
+        loop {
+            let mut first = position;
+            for child in [2 * position + 1, 2 * position + 2] {
+                if child < self.heap.len() && Self::precedes(&self.heap[child], &self.heap[first]) {
+                    first = child;
+                }
+            }
+            if first == position {
+                break;
+            }
+            self.swap(position, first);
+            position = first;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.set_position(a);
+        self.set_position(b);
+    }
+
+    fn set_position(&mut self, position: usize) {
+        self.positions[self.heap[position].id] = position;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pop_in_priority_order() {
+        let mut queue = TransactionPriorityQueue::with_capacity(8);
+        for (id, priority) in [5, 1, 7, 3, 9, 2].into_iter().enumerate() {
+            queue.push(TransactionPriorityId::new(priority, id));
+        }
+        assert_eq!(queue.len(), 6);
+        assert_eq!(queue.peek_max().unwrap().priority, 9);
+        assert_eq!(queue.peek_min().unwrap().priority, 1);
+
+        assert_eq!(queue.pop_max().unwrap().priority, 9);
+        assert_eq!(queue.pop_min().unwrap().priority, 1);
+        assert_eq!(queue.pop_max().unwrap().priority, 7);
+        assert_eq!(queue.pop_min().unwrap().priority, 2);
+        assert_eq!(queue.pop_min().unwrap().priority, 3);
+        assert_eq!(queue.pop_max().unwrap().priority, 5);
+        assert!(queue.is_empty());
+        assert!(queue.pop_max().is_none());
+        assert!(queue.pop_min().is_none());
+    }
+
+    #[test]
+    fn test_update_priority() {
+        let mut queue = TransactionPriorityQueue::with_capacity(8);
+        for id in 0..5 {
+            queue.push(TransactionPriorityId::new(id as u64, id));
+        }
+
+        // the lowest becomes the highest
+        assert!(queue.update_priority(0, 10));
+        assert_eq!(
+            *queue.peek_max().unwrap(),
+            TransactionPriorityId::new(10, 0)
+        );
+        assert_eq!(*queue.peek_min().unwrap(), TransactionPriorityId::new(1, 1));
+
+        let popped = queue.pop_max().unwrap();
+        assert_eq!(popped, TransactionPriorityId::new(10, 0));
+        assert!(!queue.update_priority(popped.id, 20));
+
+        let priorities: Vec<_> = std::iter::from_fn(|| queue.pop_min())
+            .map(|priority_id| priority_id.priority)
+            .collect();
+        assert_eq!(priorities, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_retain() {
+        let capacity = 16;
+        let mut queue = TransactionPriorityQueue::with_capacity(capacity);
+        for id in 0..10 {
+            queue.push(TransactionPriorityId::new(id as u64, id));
+        }
+        queue.retain(|priority_id| priority_id.id % 2 == 0);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.capacity(), capacity);
+        assert!(!queue.update_priority(3, 100));
+
+        // removed ids may be pushed again
+        queue.push(TransactionPriorityId::new(100, 3));
+        let priorities: Vec<_> = std::iter::from_fn(|| queue.pop_max())
+            .map(|priority_id| priority_id.priority)
+            .collect();
+        assert_eq!(priorities, [100, 8, 6, 4, 2, 0]);
+    }
+}
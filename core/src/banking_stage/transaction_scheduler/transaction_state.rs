@@ -1,4 +1,4 @@
-use crate::banking_stage::scheduler_messages::MaxAge;
+use {crate::banking_stage::scheduler_messages::MaxAge, std::time::Instant};
 
 /// Simple wrapper type to tie a sanitized transaction to max age slot.
 pub(crate) struct SanitizedTransactionTTL<Tx> {
@@ -25,6 +25,9 @@ pub(crate) struct SanitizedTransactionTTL<Tx> {
 ///   internal `SanitizedTransaction` is moved out of the `TransactionState` and sent
 ///   to the appropriate thread for processing. This is done to avoid cloning the
 ///  `SanitizedTransaction`.
+///
+/// A transaction may carry a deadline, after which it is dropped by the
+///   scheduler rather than scheduled. The deadline has no effect on priority.
 #[allow(clippy::large_enum_variant)]
 pub(crate) enum TransactionState<Tx> {
     /// The transaction is available for scheduling.
@@ -32,9 +35,14 @@ pub(crate) enum TransactionState<Tx> {
         transaction_ttl: SanitizedTransactionTTL<Tx>,
         priority: u64,
         cost: u64,
+        deadline: Option<Instant>,
     },
     /// The transaction is currently scheduled or being processed.
-    Pending { priority: u64, cost: u64 },
+    Pending {
+        priority: u64,
+        cost: u64,
+        deadline: Option<Instant>,
+    },
     /// Only used during transition.
     Transitioning,
 }
@@ -45,11 +53,13 @@ impl<Tx> TransactionState<Tx> {
         transaction_ttl: SanitizedTransactionTTL<Tx>,
         priority: u64,
         cost: u64,
+        deadline: Option<Instant>,
     ) -> Self {
         Self::Unprocessed {
             transaction_ttl,
             priority,
             cost,
+            deadline,
         }
     }
 
@@ -64,6 +74,20 @@ impl<Tx> TransactionState<Tx> {
         }
     }
 
+    /// Raise the priority of an `Unprocessed` transaction. The caller is
+    /// responsible for updating its id in the priority queue, if any.
+    ///
+    /// # Panics
+    /// This method will panic if the transaction is not in the `Unprocessed`
+    /// state.
+    pub(crate) fn set_priority(&mut self, new_priority: u64) {
+        match self {
+            Self::Unprocessed { priority, .. } => *priority = new_priority,
+            Self::Pending { .. } => panic!("transaction is pending"),
+            Self::Transitioning => unreachable!(),
+        }
+    }
+
     /// Return the cost of the transaction.
     pub(crate) fn cost(&self) -> u64 {
         match self {
@@ -73,6 +97,20 @@ impl<Tx> TransactionState<Tx> {
         }
     }
 
+    /// Return the instant after which the transaction should no longer be scheduled.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        match self {
+            Self::Unprocessed { deadline, .. } => *deadline,
+            Self::Pending { deadline, .. } => *deadline,
+            Self::Transitioning => unreachable!(),
+        }
+    }
+
+    /// Return true if the transaction has a deadline that has passed at `now`.
+    pub(crate) fn is_deadline_expired(&self, now: Instant) -> bool {
+        self.deadline().is_some_and(|deadline| deadline <= now)
+    }
+
     /// Intended to be called when a transaction is scheduled. This method will
     /// transition the transaction from `Unprocessed` to `Pending` and return the
     /// `SanitizedTransactionTTL` for processing.
@@ -86,8 +124,13 @@ impl<Tx> TransactionState<Tx> {
                 transaction_ttl,
                 priority,
                 cost,
+                deadline,
             } => {
-                *self = TransactionState::Pending { priority, cost };
+                *self = TransactionState::Pending {
+                    priority,
+                    cost,
+                    deadline,
+                };
                 transaction_ttl
             }
             TransactionState::Pending { .. } => {
@@ -109,11 +152,16 @@ impl<Tx> TransactionState<Tx> {
     ) {
         match self.take() {
             TransactionState::Unprocessed { .. } => panic!("already unprocessed"),
-            TransactionState::Pending { priority, cost } => {
+            TransactionState::Pending {
+                priority,
+                cost,
+                deadline,
+            } => {
                 *self = Self::Unprocessed {
                     transaction_ttl,
                     priority,
                     cost,
+                    deadline,
                 }
             }
             Self::Transitioning => unreachable!(),
@@ -155,10 +203,12 @@ mod tests {
             system_instruction,
             transaction::{SanitizedTransaction, Transaction},
         },
+        std::time::Duration,
     };
 
     fn create_transaction_state(
         compute_unit_price: u64,
+        deadline: Option<Instant>,
     ) -> TransactionState<RuntimeTransaction<SanitizedTransaction>> {
         let from_keypair = Keypair::new();
         let ixs = vec![
@@ -173,20 +223,25 @@ mod tests {
             max_age: MaxAge::MAX,
         };
         const TEST_TRANSACTION_COST: u64 = 5000;
-        TransactionState::new(transaction_ttl, compute_unit_price, TEST_TRANSACTION_COST)
+        TransactionState::new(
+            transaction_ttl,
+            compute_unit_price,
+            TEST_TRANSACTION_COST,
+            deadline,
+        )
     }
 
     #[test]
     #[should_panic(expected = "already pending")]
     fn test_transition_to_pending_panic() {
-        let mut transaction_state = create_transaction_state(0);
+        let mut transaction_state = create_transaction_state(0, None);
         transaction_state.transition_to_pending();
         transaction_state.transition_to_pending(); // invalid transition
     }
 
     #[test]
     fn test_transition_to_pending() {
-        let mut transaction_state = create_transaction_state(0);
+        let mut transaction_state = create_transaction_state(0, None);
         assert!(matches!(
             transaction_state,
             TransactionState::Unprocessed { .. }
@@ -201,7 +256,7 @@ mod tests {
     #[test]
     #[should_panic(expected = "already unprocessed")]
     fn test_transition_to_unprocessed_panic() {
-        let mut transaction_state = create_transaction_state(0);
+        let mut transaction_state = create_transaction_state(0, None);
 
         // Manually clone `SanitizedTransactionTTL`
         let SanitizedTransactionTTL {
@@ -217,7 +272,7 @@ mod tests {
 
     #[test]
     fn test_transition_to_unprocessed() {
-        let mut transaction_state = create_transaction_state(0);
+        let mut transaction_state = create_transaction_state(0, None);
         assert!(matches!(
             transaction_state,
             TransactionState::Unprocessed { .. }
@@ -237,7 +292,7 @@ mod tests {
     #[test]
     fn test_priority() {
         let priority = 15;
-        let mut transaction_state = create_transaction_state(priority);
+        let mut transaction_state = create_transaction_state(priority, None);
         assert_eq!(transaction_state.priority(), priority);
 
         // ensure compute unit price is not lost through state transitions
@@ -250,7 +305,7 @@ mod tests {
     #[test]
     #[should_panic(expected = "transaction is pending")]
     fn test_transaction_ttl_panic() {
-        let mut transaction_state = create_transaction_state(0);
+        let mut transaction_state = create_transaction_state(0, None);
         let transaction_ttl = transaction_state.transaction_ttl();
         assert!(matches!(
             transaction_state,
@@ -268,7 +323,7 @@ mod tests {
 
     #[test]
     fn test_transaction_ttl() {
-        let mut transaction_state = create_transaction_state(0);
+        let mut transaction_state = create_transaction_state(0, None);
         let transaction_ttl = transaction_state.transaction_ttl();
         assert!(matches!(
             transaction_state,
@@ -291,4 +346,22 @@ mod tests {
         ));
         assert_eq!(transaction_ttl.max_age, MaxAge::MAX);
     }
+
+    #[test]
+    fn test_deadline() {
+        let now = Instant::now();
+        let deadline = now + Duration::from_millis(100);
+        let mut transaction_state = create_transaction_state(0, Some(deadline));
+        assert!(!transaction_state.is_deadline_expired(now));
+        assert!(transaction_state.is_deadline_expired(deadline));
+
+        // ensure the deadline is not lost through state transitions
+        let transaction_ttl = transaction_state.transition_to_pending();
+        assert_eq!(transaction_state.deadline(), Some(deadline));
+        transaction_state.transition_to_unprocessed(transaction_ttl);
+        assert_eq!(transaction_state.deadline(), Some(deadline));
+
+        let transaction_state = create_transaction_state(0, None);
+        assert!(!transaction_state.is_deadline_expired(deadline));
+    }
 }
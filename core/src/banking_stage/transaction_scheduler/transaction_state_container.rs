@@ -1,18 +1,23 @@
 use {
     super::{
+        recently_completed::RecentlyCompleted,
         transaction_priority_id::TransactionPriorityId,
+        transaction_priority_queue::TransactionPriorityQueue,
         transaction_state::{SanitizedTransactionTTL, TransactionState},
     },
     crate::banking_stage::scheduler_messages::TransactionId,
     agave_transaction_view::resolved_transaction_view::ResolvedTransactionView,
     itertools::MinMaxResult,
-    min_max_heap::MinMaxHeap,
     slab::{Slab, VacantEntry},
     solana_runtime_transaction::{
         runtime_transaction::RuntimeTransaction, transaction_with_meta::TransactionWithMeta,
     },
-    solana_sdk::packet::PACKET_DATA_SIZE,
-    std::sync::Arc,
+    solana_sdk::{packet::PACKET_DATA_SIZE, saturating_add_assign, signature::Signature},
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::{Arc, RwLock},
+        time::Instant,
+    },
 };
 
 /// This structure will hold `TransactionState` for the entirety of a
@@ -40,10 +45,127 @@ use {
 ///
 /// The container maintains a fixed capacity. If the queue is full when pushing
 /// a new transaction, the lowest priority transaction will be dropped.
+///
+/// Transactions inserted with `insert_new_transaction` are deduplicated by
+/// signature, so that a transaction received over several forwarding paths
+/// is scheduled once. Transactions that recently completed are rejected as
+/// well, see [`InsertOutcome::RecentlyCompleted`].
+///
+/// An [`AdmissionConfig`] additionally keeps low priority transactions out of
+/// a container filling up, before it reaches its capacity.
 pub(crate) struct TransactionStateContainer<Tx: TransactionWithMeta> {
     capacity: usize,
-    priority_queue: MinMaxHeap<TransactionPriorityId>,
+    priority_queue: TransactionPriorityQueue,
     id_to_transaction_state: Slab<TransactionState<Tx>>,
+    signature_to_id: HashMap<Signature, TransactionId>,
+    /// Signature of each transaction in `signature_to_id`, indexed by id.
+    /// Pending transactions do not hold their transaction, so the signature
+    /// cannot be read back from the state on removal.
+    id_to_signature: Vec<Option<Signature>>,
+    /// Total cost of the transactions in `priority_queue`.
+    queued_cus: u64,
+    /// When each transaction in `id_to_transaction_state` was inserted,
+    /// indexed by id.
+    id_to_insert_time: Vec<Option<Instant>>,
+    /// Insertion times and ids, oldest first. Entries of removed transactions
+    /// are popped once they reach the front, so that the front is always the
+    /// oldest transaction held, and are compacted away once the deque holds
+    /// twice as many entries as transactions may be held.
+    insert_times: VecDeque<(Instant, TransactionId)>,
+    admission_config: Option<AdmissionConfig>,
+    admission_counts: AdmissionCounts,
+    /// Signatures of the transactions removed by `remove_completed`.
+    recently_completed: RecentlyCompleted,
+}
+
+/// What happens to a new transaction once the container fill is above
+/// [`AdmissionConfig::floor_watermark`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FloorAction {
+    /// Transactions below the lowest queued priority are rejected, the others
+    /// are inserted.
+    Reject,
+    /// Transactions not above the lowest queued priority are rejected, the
+    /// others replace the lowest queued transaction, keeping the fill at the
+    /// watermark.
+    EvictAndReplace,
+}
+
+/// Admission floor of new transactions, based on the fill of the container.
+/// Fills are fractions of the capacity of the container.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdmissionConfig {
+    /// Fill above which new transactions are admitted only if their priority
+    /// is not below that of the lowest queued transaction.
+    pub floor_watermark: f64,
+    pub floor_action: FloorAction,
+    /// Fill above which the lowest `eviction_fraction` of the queued
+    /// transactions, by priority, are evicted to make room.
+    pub eviction_watermark: f64,
+    pub eviction_fraction: f64,
+}
+
+impl AdmissionConfig {
+    /// Check that the watermarks are non-negative fills, the floor one not
+    /// above the eviction one, and that the eviction fraction is a fraction.
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, watermark) in [
+            ("floor watermark", self.floor_watermark),
+            ("eviction watermark", self.eviction_watermark),
+        ] {
+            if !watermark.is_finite() || watermark < 0.0 {
+                return Err(format!("{name} {watermark} is not a non-negative fill"));
+            }
+        }
+        if self.floor_watermark > self.eviction_watermark {
+            return Err(format!(
+                "floor watermark {} is above eviction watermark {}",
+                self.floor_watermark, self.eviction_watermark
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.eviction_fraction) {
+            return Err(format!(
+                "eviction fraction {} is not between 0 and 1",
+                self.eviction_fraction
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Admission config shared with the scheduler controller, which applies the
+/// latest one to its container on every iteration. `None` disables the floor.
+pub type SharedAdmissionConfig = Arc<RwLock<Option<AdmissionConfig>>>;
+
+/// Transactions kept out of the container by its [`AdmissionConfig`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct AdmissionCounts {
+    /// New transactions rejected below the admission floor.
+    pub num_rejected: usize,
+    /// Queued transactions evicted to admit a new one, or above the eviction
+    /// watermark.
+    pub num_evicted: usize,
+}
+
+/// Outcome of [`TransactionStateContainer::insert_new_transaction`].
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum InsertOutcome {
+    Inserted,
+    /// The transaction was inserted, and the lowest priority transaction was
+    /// dropped due to capacity limits.
+    InsertedDroppingLowest,
+    /// A transaction with the same signature is already in the container, and
+    /// the transaction was not inserted. The priority of the queued copy is
+    /// raised to the priority of the duplicate if lower.
+    Duplicate,
+    /// The container is above its admission floor watermark, and the
+    /// transaction was not inserted, see [`AdmissionConfig`].
+    BelowAdmissionFloor,
+    /// A transaction with the same signature completed recently, and the
+    /// re-submission was not inserted, as it would fail with
+    /// `AlreadyProcessed`.
+    RecentlyCompleted,
 }
 
 pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
@@ -56,6 +178,10 @@ pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
     /// Get the top transaction id in the priority queue.
     fn pop(&mut self) -> Option<TransactionPriorityId>;
 
+    /// Iterate over the ids in the priority queue without removing them, in
+    /// no particular order.
+    fn queued_ids(&self) -> impl Iterator<Item = &TransactionPriorityId>;
+
     /// Get mutable transaction state by id.
     fn get_mut_transaction_state(&mut self, id: TransactionId)
         -> Option<&mut TransactionState<Tx>>;
@@ -71,12 +197,24 @@ pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
         transaction_id: TransactionId,
         transaction_ttl: SanitizedTransactionTTL<Tx>,
     ) {
+        let priority_id = self.defer_transaction(transaction_id, transaction_ttl);
+        self.push_ids_into_queue(std::iter::once(priority_id));
+    }
+
+    /// Defers a transaction - transitions it to `Unprocessed` state without
+    /// inserting it back into the queue. The returned id should be pushed
+    /// into the queue once the transaction may be scheduled again.
+    fn defer_transaction(
+        &mut self,
+        transaction_id: TransactionId,
+        transaction_ttl: SanitizedTransactionTTL<Tx>,
+    ) -> TransactionPriorityId {
         let transaction_state = self
             .get_mut_transaction_state(transaction_id)
             .expect("transaction must exist");
         let priority_id = TransactionPriorityId::new(transaction_state.priority(), transaction_id);
         transaction_state.transition_to_unprocessed(transaction_ttl);
-        self.push_ids_into_queue(std::iter::once(priority_id));
+        priority_id
     }
 
     /// Pushes transaction ids into the priority queue. If the queue if full,
@@ -93,7 +231,27 @@ pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
     /// Remove transaction by id.
     fn remove_by_id(&mut self, id: TransactionId);
 
+    /// Remove a transaction that completed by id, remembering its `signature`
+    /// so that re-submissions are rejected for a while.
+    fn remove_completed(&mut self, id: TransactionId, signature: &Signature);
+
     fn get_min_max_priority(&self) -> MinMaxResult<u64>;
+
+    /// Number of transactions in the priority queue.
+    fn queue_len(&self) -> usize;
+
+    /// Total cost of the transactions in the priority queue.
+    fn queued_cus(&self) -> u64;
+
+    /// When the oldest transaction held, queued or pending, was inserted.
+    fn oldest_insert_time(&self) -> Option<Instant>;
+
+    /// Set the admission floor applied to new transactions, `None` disabling
+    /// it.
+    fn set_admission_config(&mut self, admission_config: Option<AdmissionConfig>);
+
+    /// Transactions kept out by the admission floor since the last call.
+    fn take_admission_counts(&mut self) -> AdmissionCounts;
 }
 
 // Extra capacity is added because some additional space is needed when
@@ -102,10 +260,22 @@ pub(crate) const EXTRA_CAPACITY: usize = 64;
 
 impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<Tx> {
     fn with_capacity(capacity: usize) -> Self {
+        let id_to_transaction_state = Slab::with_capacity(capacity + EXTRA_CAPACITY);
+        let id_to_signature = vec![None; id_to_transaction_state.capacity()];
+        let id_to_insert_time = vec![None; id_to_transaction_state.capacity()];
+        let insert_times = VecDeque::with_capacity(2 * id_to_insert_time.len());
         Self {
             capacity,
-            priority_queue: MinMaxHeap::with_capacity(capacity + EXTRA_CAPACITY),
-            id_to_transaction_state: Slab::with_capacity(capacity + EXTRA_CAPACITY),
+            priority_queue: TransactionPriorityQueue::with_capacity(capacity + EXTRA_CAPACITY),
+            id_to_transaction_state,
+            signature_to_id: HashMap::with_capacity(capacity + EXTRA_CAPACITY),
+            id_to_signature,
+            queued_cus: 0,
+            id_to_insert_time,
+            insert_times,
+            admission_config: None,
+            admission_counts: AdmissionCounts::default(),
+            recently_completed: RecentlyCompleted::default(),
         }
     }
 
@@ -114,7 +284,15 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
     }
 
     fn pop(&mut self) -> Option<TransactionPriorityId> {
-        self.priority_queue.pop_max()
+        let priority_id = self.priority_queue.pop_max()?;
+        self.queued_cus = self
+            .queued_cus
+            .saturating_sub(self.id_to_transaction_state[priority_id.id].cost());
+        Some(priority_id)
+    }
+
+    fn queued_ids(&self) -> impl Iterator<Item = &TransactionPriorityId> {
+        self.priority_queue.iter()
     }
 
     fn get_mut_transaction_state(
@@ -135,6 +313,7 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
         priority_ids: impl Iterator<Item = TransactionPriorityId>,
     ) -> usize {
         for id in priority_ids {
+            saturating_add_assign!(self.queued_cus, self.id_to_transaction_state[id.id].cost());
             self.priority_queue.push(id);
         }
 
@@ -149,7 +328,10 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
 
         for _ in 0..num_dropped {
             let priority_id = self.priority_queue.pop_min().expect("queue is not empty");
-            self.id_to_transaction_state.remove(priority_id.id);
+            let state = self.id_to_transaction_state.remove(priority_id.id);
+            self.queued_cus = self.queued_cus.saturating_sub(state.cost());
+            self.remove_signature(priority_id.id);
+            self.remove_insert_time(priority_id.id);
         }
 
         num_dropped
@@ -157,6 +339,13 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
 
     fn remove_by_id(&mut self, id: TransactionId) {
         self.id_to_transaction_state.remove(id);
+        self.remove_signature(id);
+        self.remove_insert_time(id);
+    }
+
+    fn remove_completed(&mut self, id: TransactionId, signature: &Signature) {
+        self.recently_completed.record(*signature);
+        self.remove_by_id(id);
     }
 
     fn get_min_max_priority(&self) -> MinMaxResult<u64> {
@@ -168,25 +357,279 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
             None => MinMaxResult::NoElements,
         }
     }
+
+    fn queue_len(&self) -> usize {
+        self.priority_queue.len()
+    }
+
+    fn queued_cus(&self) -> u64 {
+        self.queued_cus
+    }
+
+    fn oldest_insert_time(&self) -> Option<Instant> {
+        self.insert_times
+            .front()
+            .map(|(insert_time, _)| *insert_time)
+    }
+
+    fn set_admission_config(&mut self, admission_config: Option<AdmissionConfig>) {
+        self.admission_config = admission_config;
+    }
+
+    fn take_admission_counts(&mut self) -> AdmissionCounts {
+        std::mem::take(&mut self.admission_counts)
+    }
 }
 
 impl<Tx: TransactionWithMeta> TransactionStateContainer<Tx> {
     /// Insert a new transaction into the container's queues and maps.
-    /// Returns `true` if a packet was dropped due to capacity limits.
+    /// A transaction with a `deadline` is dropped by the scheduler, rather
+    /// than scheduled, once the deadline has passed.
+    /// A transaction whose signature is already in the container, or
+    /// recently completed, is not inserted, see [`InsertOutcome::Duplicate`]
+    /// and [`InsertOutcome::RecentlyCompleted`].
     pub(crate) fn insert_new_transaction(
         &mut self,
         transaction_ttl: SanitizedTransactionTTL<Tx>,
         priority: u64,
         cost: u64,
-    ) -> bool {
+        deadline: Option<Instant>,
+    ) -> InsertOutcome {
+        let signature = *transaction_ttl.transaction.signature();
+        if let Some(&existing_id) = self.signature_to_id.get(&signature) {
+            self.raise_queued_priority(existing_id, priority);
+            return InsertOutcome::Duplicate;
+        }
+        if self.recently_completed.contains(&signature) {
+            return InsertOutcome::RecentlyCompleted;
+        }
+        if !self.admit(priority) {
+            return InsertOutcome::BelowAdmissionFloor;
+        }
+
+        let priority_id = self.insert_state(transaction_ttl, priority, cost, deadline);
+        if self.push_ids_into_queue(std::iter::once(priority_id)) > 0 {
+            InsertOutcome::InsertedDroppingLowest
+        } else {
+            InsertOutcome::Inserted
+        }
+    }
+
+    /// Insert a batch of new transactions, given as `(transaction_ttl,
+    /// priority, cost)`, as [`Self::insert_new_transaction`] does without a
+    /// deadline. The ids are pushed into the queue [`EXTRA_CAPACITY`] at a
+    /// time, so that the queue never reallocates and the lowest priority
+    /// transactions are dropped once per chunk rather than on every insertion.
+    /// Returns the ids of the transactions of the batch still held once it is
+    /// inserted, in insertion order. Duplicates, recently completed
+    /// transactions, transactions below the admission floor and those dropped
+    /// for capacity are left out.
+    pub(crate) fn insert_new_transactions(
+        &mut self,
+        transactions: impl IntoIterator<Item = (SanitizedTransactionTTL<Tx>, u64, u64)>,
+    ) -> Vec<TransactionId> {
+        let transactions = transactions.into_iter();
+        let mut inserted = Vec::with_capacity(transactions.size_hint().0.min(self.capacity));
+        let mut chunk = Vec::with_capacity(EXTRA_CAPACITY);
+        for (transaction_ttl, priority, cost) in transactions {
+            let signature = *transaction_ttl.transaction.signature();
+            if let Some(&existing_id) = self.signature_to_id.get(&signature) {
+                // the existing transaction may be in the chunk, not queued yet
+                self.push_ids_into_queue(chunk.drain(..));
+                self.raise_queued_priority(existing_id, priority);
+                continue;
+            }
+            if self.recently_completed.contains(&signature) {
+                continue;
+            }
+            if !self.admit(priority) {
+                continue;
+            }
+
+            let priority_id = self.insert_state(transaction_ttl, priority, cost, None);
+            chunk.push(priority_id);
+            inserted.push((priority_id.id, signature));
+            if chunk.len() == EXTRA_CAPACITY {
+                self.push_ids_into_queue(chunk.drain(..));
+            }
+        }
+        self.push_ids_into_queue(chunk.drain(..));
+
+        // the ids of dropped transactions may have been reused by the batch
+        inserted
+            .into_iter()
+            .filter(|(id, signature)| self.id_to_signature[*id] == Some(*signature))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Insert the state of a new transaction into the maps, without pushing
+    /// its id into the queue.
+    fn insert_state(
+        &mut self,
+        transaction_ttl: SanitizedTransactionTTL<Tx>,
+        priority: u64,
+        cost: u64,
+        deadline: Option<Instant>,
+    ) -> TransactionPriorityId {
+        let signature = *transaction_ttl.transaction.signature();
         let priority_id = {
             let entry = self.get_vacant_map_entry();
             let transaction_id = entry.key();
-            entry.insert(TransactionState::new(transaction_ttl, priority, cost));
+            entry.insert(TransactionState::new(
+                transaction_ttl,
+                priority,
+                cost,
+                deadline,
+            ));
             TransactionPriorityId::new(priority, transaction_id)
         };
+        self.signature_to_id.insert(signature, priority_id.id);
+        self.id_to_signature[priority_id.id] = Some(signature);
+        self.record_insert_time(priority_id.id);
+        priority_id
+    }
 
-        self.push_ids_into_queue(std::iter::once(priority_id)) > 0
+    /// Apply the admission floor to a new transaction of `priority`, evicting
+    /// queued transactions as configured. Returns whether it may be inserted.
+    pub(crate) fn admit(&mut self, priority: u64) -> bool {
+        let Some(admission_config) = self.admission_config else {
+            return true;
+        };
+        let fill = |container: &Self| {
+            container.id_to_transaction_state.len() as f64 / container.capacity as f64
+        };
+
+        if fill(self) > admission_config.eviction_watermark {
+            let num_evicted = (self.priority_queue.len() as f64
+                * admission_config.eviction_fraction)
+                .ceil() as usize;
+            self.evict_lowest(num_evicted);
+        }
+
+        if fill(self) <= admission_config.floor_watermark {
+            return true;
+        }
+        // Pending transactions are not in the queue, there is no floor
+        // without queued transactions.
+        let Some(floor) = self.priority_queue.peek_min().map(|min| min.priority) else {
+            return true;
+        };
+        let admitted = match admission_config.floor_action {
+            FloorAction::Reject => priority >= floor,
+            FloorAction::EvictAndReplace => {
+                let admitted = priority > floor;
+                if admitted {
+                    self.evict_lowest(1);
+                }
+                admitted
+            }
+        };
+        if !admitted {
+            saturating_add_assign!(self.admission_counts.num_rejected, 1);
+        }
+        admitted
+    }
+
+    /// Evict the `num` lowest priority queued transactions.
+    fn evict_lowest(&mut self, num: usize) {
+        for _ in 0..num {
+            let Some(priority_id) = self.priority_queue.pop_min() else {
+                break;
+            };
+            self.queued_cus = self
+                .queued_cus
+                .saturating_sub(self.id_to_transaction_state[priority_id.id].cost());
+            self.remove_by_id(priority_id.id);
+            saturating_add_assign!(self.admission_counts.num_evicted, 1);
+        }
+    }
+
+    /// Raise the priority of the transaction `id` to `priority` if it is
+    /// queued with a lower priority. Pending or deferred transactions, which
+    /// are not in the queue, are left untouched.
+    fn raise_queued_priority(&mut self, id: TransactionId, priority: u64) {
+        if self.id_to_transaction_state[id].priority() < priority
+            && self.priority_queue.update_priority(id, priority)
+        {
+            self.id_to_transaction_state[id].set_priority(priority);
+        }
+    }
+
+    /// Record the `signature` of the transaction `id`, unless a transaction
+    /// with the same signature is held, in which case its queued priority is
+    /// raised to `priority` if lower. Returns whether it was recorded.
+    fn insert_signature(&mut self, id: TransactionId, signature: Signature, priority: u64) -> bool {
+        if let Some(&existing_id) = self.signature_to_id.get(&signature) {
+            self.raise_queued_priority(existing_id, priority);
+            return false;
+        }
+        self.signature_to_id.insert(signature, id);
+        self.id_to_signature[id] = Some(signature);
+        true
+    }
+
+    fn remove_signature(&mut self, id: TransactionId) {
+        if let Some(signature) = self.id_to_signature[id].take() {
+            self.signature_to_id.remove(&signature);
+        }
+    }
+
+    fn record_insert_time(&mut self, id: TransactionId) {
+        if self.insert_times.len() >= 2 * self.id_to_insert_time.len() {
+            // At most half the entries are held transactions, so that the
+            // deque is compacted at most once per that many insertions.
+            let id_to_insert_time = &self.id_to_insert_time;
+            self.insert_times
+                .retain(|(insert_time, id)| id_to_insert_time[*id] == Some(*insert_time));
+        }
+        let now = Instant::now();
+        self.id_to_insert_time[id] = Some(now);
+        self.insert_times.push_back((now, id));
+    }
+
+    fn remove_insert_time(&mut self, id: TransactionId) {
+        self.id_to_insert_time[id] = None;
+        self.pop_removed_insert_times();
+    }
+
+    fn pop_removed_insert_times(&mut self) {
+        while let Some(&(insert_time, id)) = self.insert_times.front() {
+            if self.id_to_insert_time[id] == Some(insert_time) {
+                break;
+            }
+            self.insert_times.pop_front();
+        }
+    }
+
+    /// Remove all queued transactions for which `predicate` returns false,
+    /// rebuilding the queue in a single pass.
+    /// `Pending` transactions are not in the queue, and are left untouched.
+    /// Returns the number of transactions removed.
+    pub(crate) fn retain<F>(&mut self, predicate: F) -> usize
+    where
+        F: Fn(&TransactionState<Tx>) -> bool,
+    {
+        let num_queued = self.priority_queue.len();
+        let mut queued_cus: u64 = 0;
+        self.priority_queue.retain(|priority_id| {
+            let state = &self.id_to_transaction_state[priority_id.id];
+            let retain = predicate(state);
+            if retain {
+                saturating_add_assign!(queued_cus, state.cost());
+            } else {
+                self.id_to_transaction_state.remove(priority_id.id);
+                if let Some(signature) = self.id_to_signature[priority_id.id].take() {
+                    self.signature_to_id.remove(&signature);
+                }
+                self.id_to_insert_time[priority_id.id] = None;
+            }
+            retain
+        });
+        self.queued_cus = queued_cus;
+        self.pop_removed_insert_times();
+
+        num_queued - self.priority_queue.len()
     }
 
     fn get_vacant_map_entry(&mut self) -> VacantEntry<TransactionState<Tx>> {
@@ -208,8 +651,33 @@ pub struct TransactionViewStateContainer {
 }
 
 impl TransactionViewStateContainer {
+    /// See [`TransactionStateContainer::admit`].
+    pub(crate) fn admit(&mut self, priority: u64) -> bool {
+        self.inner.admit(priority)
+    }
+
+    /// Deduplicate the transaction `id`, inserted with
+    /// [`Self::try_insert_map_only_with_data`], by its `signature`. Returns
+    /// false if a transaction with the same signature is already held, see
+    /// [`InsertOutcome::Duplicate`], in which case `id` should be removed.
+    pub(crate) fn insert_signature(
+        &mut self,
+        id: TransactionId,
+        signature: Signature,
+        priority: u64,
+    ) -> bool {
+        self.inner.insert_signature(id, signature, priority)
+    }
+
+    /// Whether the transaction of `signature` recently completed, see
+    /// [`InsertOutcome::RecentlyCompleted`].
+    pub(crate) fn is_recently_completed(&self, signature: &Signature) -> bool {
+        self.inner.recently_completed.contains(signature)
+    }
+
     /// Insert into the map, but NOT into the priority queue.
-    /// Returns the id of the transaction if it was inserted.
+    /// Returns the id of the transaction if it was inserted. The signature of
+    /// the transaction is not recorded until [`Self::insert_signature`].
     pub(crate) fn try_insert_map_only_with_data(
         &mut self,
         data: &[u8],
@@ -242,6 +710,7 @@ impl TransactionViewStateContainer {
         // Attempt to insert the transaction.
         if let Ok(state) = f(Arc::clone(bytes_entry)) {
             vacant_entry.insert(state);
+            self.inner.record_insert_time(transaction_id);
             Some(transaction_id)
         } else {
             None
@@ -272,6 +741,11 @@ impl StateContainer<RuntimeTransactionView> for TransactionViewStateContainer {
         self.inner.pop()
     }
 
+    #[inline]
+    fn queued_ids(&self) -> impl Iterator<Item = &TransactionPriorityId> {
+        self.inner.queued_ids()
+    }
+
     #[inline]
     fn get_mut_transaction_state(
         &mut self,
@@ -301,17 +775,50 @@ impl StateContainer<RuntimeTransactionView> for TransactionViewStateContainer {
         self.inner.remove_by_id(id);
     }
 
+    #[inline]
+    fn remove_completed(&mut self, id: TransactionId, signature: &Signature) {
+        self.inner.remove_completed(id, signature);
+    }
+
     #[inline]
     fn get_min_max_priority(&self) -> MinMaxResult<u64> {
         self.inner.get_min_max_priority()
     }
+
+    #[inline]
+    fn queue_len(&self) -> usize {
+        self.inner.queue_len()
+    }
+
+    #[inline]
+    fn queued_cus(&self) -> u64 {
+        self.inner.queued_cus()
+    }
+
+    #[inline]
+    fn oldest_insert_time(&self) -> Option<Instant> {
+        self.inner.oldest_insert_time()
+    }
+
+    #[inline]
+    fn set_admission_config(&mut self, admission_config: Option<AdmissionConfig>) {
+        self.inner.set_admission_config(admission_config);
+    }
+
+    #[inline]
+    fn take_admission_counts(&mut self) -> AdmissionCounts {
+        self.inner.take_admission_counts()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use {
         super::*,
-        crate::banking_stage::scheduler_messages::MaxAge,
+        crate::banking_stage::{
+            scheduler_messages::MaxAge,
+            transaction_scheduler::recently_completed::DEFAULT_RECENTLY_COMPLETED_WINDOW,
+        },
         agave_transaction_view::transaction_view::SanitizedTransactionView,
         solana_perf::packet::Packet,
         solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
@@ -360,7 +867,7 @@ mod tests {
     ) {
         for priority in 0..num as u64 {
             let (transaction_ttl, priority, cost) = test_transaction(priority);
-            container.insert_new_transaction(transaction_ttl, priority, cost);
+            container.insert_new_transaction(transaction_ttl, priority, cost, None);
         }
     }
 
@@ -391,6 +898,449 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_retain() {
+        let mut container = TransactionStateContainer::with_capacity(10);
+        push_to_container(&mut container, 10);
+
+        // Popped transactions are pending, and not subject to `retain`.
+        let pending_id = container.pop().unwrap();
+        container
+            .get_mut_transaction_state(pending_id.id)
+            .unwrap()
+            .transition_to_pending();
+
+        assert_eq!(container.retain(|state| state.priority() % 2 == 0), 4);
+        assert_eq!(container.priority_queue.len(), 5);
+        assert_eq!(container.id_to_transaction_state.len(), 6);
+        assert!(container.get_mut_transaction_state(pending_id.id).is_some());
+
+        let mut retained = vec![];
+        while let Some(priority_id) = container.pop() {
+            let state = container.get_mut_transaction_state(priority_id.id).unwrap();
+            assert_eq!(state.priority() % 2, 0);
+            retained.push(priority_id.priority);
+        }
+        assert_eq!(retained, [8, 6, 4, 2, 0]);
+
+        assert_eq!(container.retain(|_| false), 0);
+    }
+
+    #[test]
+    fn test_queue_stats() {
+        let mut container = TransactionStateContainer::with_capacity(10);
+        assert_eq!(container.queue_len(), 0);
+        assert_eq!(container.queued_cus(), 0);
+        assert_eq!(container.oldest_insert_time(), None);
+
+        push_to_container(&mut container, 3);
+        assert_eq!(container.queue_len(), 3);
+        assert_eq!(container.queued_cus(), 15_000);
+        let first_insert_time = container.id_to_insert_time[0];
+        assert!(first_insert_time.is_some());
+        assert_eq!(container.oldest_insert_time(), first_insert_time);
+
+        // pending transactions do not count as queued, but are still held
+        let pending_id = container.pop().unwrap();
+        assert_eq!(pending_id.id, 2);
+        assert_eq!(container.queue_len(), 2);
+        assert_eq!(container.queued_cus(), 10_000);
+        container.remove_by_id(pending_id.id);
+        assert_eq!(container.oldest_insert_time(), first_insert_time);
+
+        // removing the oldest transaction moves on to the next one
+        assert_eq!(container.retain(|state| state.priority() != 0), 1);
+        assert_eq!(container.queue_len(), 1);
+        assert_eq!(container.queued_cus(), 5_000);
+        assert_eq!(
+            container.oldest_insert_time(),
+            container.id_to_insert_time[1]
+        );
+
+        assert_eq!(container.retain(|_| false), 1);
+        assert_eq!(container.queued_cus(), 0);
+        assert_eq!(container.oldest_insert_time(), None);
+    }
+
+    #[test]
+    fn test_insert_times_bounded() {
+        let mut container = TransactionStateContainer::with_capacity(10);
+        push_to_container(&mut container, 1);
+        let first_insert_time = container.id_to_insert_time[0];
+
+        // transactions removed behind a long lived one leave entries behind,
+        // until the deque is compacted
+        let max_insert_times = 2 * container.id_to_insert_time.len();
+        let insert_times_capacity = container.insert_times.capacity();
+        for _ in 0..4 * max_insert_times {
+            assert_eq!(insert(&mut container, 100), InsertOutcome::Inserted);
+            let top = container.pop().unwrap();
+            container.remove_by_id(top.id);
+            assert!(container.insert_times.len() <= max_insert_times);
+        }
+        assert_eq!(container.insert_times.capacity(), insert_times_capacity);
+        assert_eq!(container.oldest_insert_time(), first_insert_time);
+    }
+
+    fn insert(
+        container: &mut TransactionStateContainer<RuntimeTransaction<SanitizedTransaction>>,
+        priority: u64,
+    ) -> InsertOutcome {
+        let (transaction_ttl, priority, cost) = test_transaction(priority);
+        container.insert_new_transaction(transaction_ttl, priority, cost, None)
+    }
+
+    fn admission_config(floor_action: FloorAction) -> AdmissionConfig {
+        AdmissionConfig {
+            floor_watermark: 0.8,
+            floor_action,
+            eviction_watermark: 0.95,
+            eviction_fraction: 0.25,
+        }
+    }
+
+    #[test]
+    fn test_admission_config_validate() {
+        let config = admission_config(FloorAction::Reject);
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(
+            AdmissionConfig {
+                floor_watermark: 0.9,
+                eviction_watermark: 0.9,
+                ..config
+            }
+            .validate(),
+            Ok(())
+        );
+
+        assert_eq!(
+            AdmissionConfig {
+                floor_watermark: 0.96,
+                ..config
+            }
+            .validate(),
+            Err("floor watermark 0.96 is above eviction watermark 0.95".to_string())
+        );
+        for invalid in [
+            AdmissionConfig {
+                floor_watermark: -0.1,
+                ..config
+            },
+            AdmissionConfig {
+                eviction_watermark: f64::NAN,
+                ..config
+            },
+            AdmissionConfig {
+                eviction_fraction: 1.5,
+                ..config
+            },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_admission_floor_evict_and_replace() {
+        let mut container = TransactionStateContainer::with_capacity(10);
+        container.set_admission_config(Some(admission_config(FloorAction::EvictAndReplace)));
+
+        // filled up to the floor watermark with low priority transactions
+        for _ in 0..8 {
+            assert_eq!(insert(&mut container, 1), InsertOutcome::Inserted);
+        }
+        assert_eq!(insert(&mut container, 1), InsertOutcome::Inserted);
+        assert_eq!(
+            container.take_admission_counts(),
+            AdmissionCounts::default()
+        );
+
+        // past it, a high priority transaction replaces a low priority one
+        assert_eq!(insert(&mut container, 100), InsertOutcome::Inserted);
+        assert_eq!(container.id_to_transaction_state.len(), 9);
+        assert_eq!(
+            container.get_min_max_priority(),
+            MinMaxResult::MinMax(1, 100)
+        );
+        assert_eq!(
+            container.take_admission_counts(),
+            AdmissionCounts {
+                num_rejected: 0,
+                num_evicted: 1
+            }
+        );
+
+        // while a low priority one is rejected
+        assert_eq!(
+            insert(&mut container, 1),
+            InsertOutcome::BelowAdmissionFloor
+        );
+        assert_eq!(
+            insert(&mut container, 0),
+            InsertOutcome::BelowAdmissionFloor
+        );
+        assert_eq!(container.id_to_transaction_state.len(), 9);
+        assert_eq!(container.queue_len(), 9);
+        assert_eq!(container.queued_cus(), 9 * 5000);
+        assert_eq!(
+            container.take_admission_counts(),
+            AdmissionCounts {
+                num_rejected: 2,
+                num_evicted: 0
+            }
+        );
+
+        // without a floor, the container fills up to its capacity
+        container.set_admission_config(None);
+        assert_eq!(insert(&mut container, 0), InsertOutcome::Inserted);
+        assert_eq!(
+            insert(&mut container, 0),
+            InsertOutcome::InsertedDroppingLowest
+        );
+        assert_eq!(
+            container.take_admission_counts(),
+            AdmissionCounts::default()
+        );
+    }
+
+    #[test]
+    fn test_admission_floor_reject() {
+        let mut container = TransactionStateContainer::with_capacity(10);
+        container.set_admission_config(Some(admission_config(FloorAction::Reject)));
+        for priority in 1..=9 {
+            assert_eq!(insert(&mut container, priority), InsertOutcome::Inserted);
+        }
+
+        // past the floor watermark, only transactions below the floor are
+        // rejected, the others are inserted without evicting any
+        assert_eq!(
+            insert(&mut container, 0),
+            InsertOutcome::BelowAdmissionFloor
+        );
+        assert_eq!(insert(&mut container, 1), InsertOutcome::Inserted);
+        assert_eq!(container.id_to_transaction_state.len(), 10);
+        assert_eq!(
+            container.take_admission_counts(),
+            AdmissionCounts {
+                num_rejected: 1,
+                num_evicted: 0
+            }
+        );
+
+        // past the eviction watermark, the lowest quarter of the queue is
+        // evicted to make room
+        assert_eq!(insert(&mut container, 50), InsertOutcome::Inserted);
+        assert_eq!(container.id_to_transaction_state.len(), 8);
+        assert_eq!(
+            container.get_min_max_priority(),
+            MinMaxResult::MinMax(3, 50)
+        );
+        assert_eq!(
+            container.take_admission_counts(),
+            AdmissionCounts {
+                num_rejected: 0,
+                num_evicted: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_insert_duplicate_signature() {
+        let mut container = TransactionStateContainer::with_capacity(10);
+        let (transaction_ttl, _, cost) = test_transaction(1);
+        let duplicate = |transaction_ttl: &SanitizedTransactionTTL<_>| SanitizedTransactionTTL {
+            transaction: RuntimeTransaction::from_transaction_for_tests(
+                transaction_ttl
+                    .transaction
+                    .to_versioned_transaction()
+                    .into_legacy_transaction()
+                    .unwrap(),
+            ),
+            max_age: MaxAge::MAX,
+        };
+
+        // a duplicate of a lower priority is rejected
+        let copy = duplicate(&transaction_ttl);
+        assert_eq!(
+            container.insert_new_transaction(transaction_ttl, 5, cost, None),
+            InsertOutcome::Inserted
+        );
+        assert_eq!(
+            container.insert_new_transaction(duplicate(&copy), 3, cost, None),
+            InsertOutcome::Duplicate
+        );
+        assert_eq!(container.priority_queue.len(), 1);
+        assert_eq!(container.id_to_transaction_state.len(), 1);
+
+        // a duplicate of a higher priority raises the priority of the copy
+        push_to_container(&mut container, 3);
+        assert_eq!(
+            container.insert_new_transaction(duplicate(&copy), 8, cost, None),
+            InsertOutcome::Duplicate
+        );
+        assert_eq!(container.priority_queue.len(), 4);
+        assert_eq!(container.signature_to_id.len(), 4);
+        let top = container.pop().unwrap();
+        assert_eq!(top.priority, 8);
+        let state = container.get_mut_transaction_state(top.id).unwrap();
+        assert_eq!(state.priority(), 8);
+        assert_eq!(
+            state.transition_to_pending().transaction.signature(),
+            copy.transaction.signature()
+        );
+
+        // a duplicate of a pending transaction is rejected as well
+        assert_eq!(
+            container.insert_new_transaction(duplicate(&copy), 10, cost, None),
+            InsertOutcome::Duplicate
+        );
+        assert_eq!(
+            container
+                .get_mut_transaction_state(top.id)
+                .unwrap()
+                .priority(),
+            8
+        );
+
+        // once completed, the signature may be inserted again
+        container.remove_by_id(top.id);
+        assert_eq!(container.signature_to_id.len(), 3);
+        assert_eq!(
+            container.insert_new_transaction(duplicate(&copy), 2, cost, None),
+            InsertOutcome::Inserted
+        );
+        assert_eq!(container.signature_to_id.len(), 4);
+    }
+
+    #[test]
+    fn test_insert_recently_completed() {
+        let mut container = TransactionStateContainer::with_capacity(10);
+        let (transaction_ttl, priority, cost) = test_transaction(1);
+        let transaction = transaction_ttl
+            .transaction
+            .to_versioned_transaction()
+            .into_legacy_transaction()
+            .unwrap();
+        let resubmit = || SanitizedTransactionTTL {
+            transaction: RuntimeTransaction::from_transaction_for_tests(transaction.clone()),
+            max_age: MaxAge::MAX,
+        };
+
+        assert_eq!(
+            container.insert_new_transaction(transaction_ttl, priority, cost, None),
+            InsertOutcome::Inserted
+        );
+        let priority_id = container.pop().unwrap();
+        let signature = *container
+            .get_mut_transaction_state(priority_id.id)
+            .unwrap()
+            .transition_to_pending()
+            .transaction
+            .signature();
+        container.remove_completed(priority_id.id, &signature);
+
+        // rejected within the window, in both the single and batch paths
+        assert_eq!(
+            container.insert_new_transaction(resubmit(), priority, cost, None),
+            InsertOutcome::RecentlyCompleted
+        );
+        assert!(container
+            .insert_new_transactions([(resubmit(), priority, cost)])
+            .is_empty());
+        assert!(container.is_empty());
+
+        // accepted again once past it
+        container
+            .recently_completed
+            .advance_clock(DEFAULT_RECENTLY_COMPLETED_WINDOW);
+        assert_eq!(
+            container.insert_new_transaction(resubmit(), priority, cost, None),
+            InsertOutcome::Inserted
+        );
+    }
+
+    #[test]
+    fn test_insert_new_transactions() {
+        let capacity = 1_000;
+        let mut container = TransactionStateContainer::with_capacity(capacity);
+        let queue_capacity = container.priority_queue.capacity();
+
+        // more than the capacity, in a single batch
+        let batch: Vec<_> = (0..1_500).map(test_transaction).collect();
+        let signatures: Vec<_> = batch
+            .iter()
+            .map(|(transaction_ttl, _, _)| *transaction_ttl.transaction.signature())
+            .collect();
+        let ids = container.insert_new_transactions(batch);
+        assert_eq!(ids.len(), capacity);
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), capacity);
+        assert_eq!(container.priority_queue.capacity(), queue_capacity);
+        assert_eq!(container.queue_len(), capacity);
+        assert_eq!(container.queued_cus(), capacity as u64 * 5000);
+
+        // the lowest priorities were dropped, and every id held is returned
+        for (id, signature) in ids.iter().zip(&signatures[500..]) {
+            assert_eq!(
+                container
+                    .get_transaction_ttl(*id)
+                    .unwrap()
+                    .transaction
+                    .signature(),
+                signature
+            );
+        }
+        let mut drained = vec![];
+        while let Some(priority_id) = container.pop() {
+            drained.push(priority_id.id);
+        }
+        assert_eq!(drained.len(), capacity);
+        assert_eq!(
+            drained.into_iter().collect::<HashSet<_>>(),
+            ids.into_iter().collect::<HashSet<_>>()
+        );
+        assert_eq!(container.queued_cus(), 0);
+    }
+
+    #[test]
+    fn test_insert_new_transactions_duplicates() {
+        let mut container = TransactionStateContainer::with_capacity(10);
+        let (transaction_ttl, _, cost) = test_transaction(1);
+        let duplicate = SanitizedTransactionTTL {
+            transaction: RuntimeTransaction::from_transaction_for_tests(
+                transaction_ttl
+                    .transaction
+                    .to_versioned_transaction()
+                    .into_legacy_transaction()
+                    .unwrap(),
+            ),
+            max_age: MaxAge::MAX,
+        };
+
+        // a duplicate within the batch raises the priority of the first copy
+        let ids = container.insert_new_transactions([
+            (transaction_ttl, 2, cost),
+            test_transaction(3),
+            (duplicate, 7, cost),
+        ]);
+        assert_eq!(ids.len(), 2);
+        assert_eq!(container.queue_len(), 2);
+        let top = container.pop().unwrap();
+        assert_eq!((top.id, top.priority), (ids[0], 7));
+    }
+
+    #[test]
+    fn test_signatures_removed_on_drop() {
+        let mut container = TransactionStateContainer::with_capacity(2);
+        push_to_container(&mut container, 5);
+        assert_eq!(container.signature_to_id.len(), 2);
+
+        assert_eq!(container.retain(|state| state.priority() == 4), 1);
+        assert_eq!(container.signature_to_id.len(), 1);
+        assert_eq!(
+            container.id_to_signature.iter().flatten().count(),
+            container.signature_to_id.len()
+        );
+    }
+
     #[test]
     fn test_get_mut_transaction_state() {
         let mut container = TransactionStateContainer::with_capacity(5);
@@ -405,35 +1355,40 @@ mod tests {
             .is_none());
     }
 
+    fn view_transaction_state(
+        data: SharedBytes,
+        priority: u64,
+        cost: u64,
+    ) -> Result<TransactionViewState, ()> {
+        let view = SanitizedTransactionView::try_new_sanitized(data).unwrap();
+        let view = RuntimeTransaction::<SanitizedTransactionView<_>>::try_from(
+            view,
+            MessageHash::Compute,
+            None,
+        )
+        .unwrap();
+        let view = RuntimeTransaction::<ResolvedTransactionView<_>>::try_from(
+            view,
+            None,
+            &HashSet::default(),
+        )
+        .unwrap();
+
+        Ok(TransactionState::new(
+            SanitizedTransactionTTL {
+                transaction: view,
+                max_age: MaxAge::MAX,
+            },
+            priority,
+            cost,
+            None,
+        ))
+    }
+
     #[test]
     fn test_view_push_ids_to_queue() {
         let mut container = TransactionViewStateContainer::with_capacity(2);
-
-        let reserved_addresses = HashSet::default();
-        let packet_parser = |data, priority, cost| {
-            let view = SanitizedTransactionView::try_new_sanitized(data).unwrap();
-            let view = RuntimeTransaction::<SanitizedTransactionView<_>>::try_from(
-                view,
-                MessageHash::Compute,
-                None,
-            )
-            .unwrap();
-            let view = RuntimeTransaction::<ResolvedTransactionView<_>>::try_from(
-                view,
-                None,
-                &reserved_addresses,
-            )
-            .unwrap();
-
-            Ok(TransactionState::new(
-                SanitizedTransactionTTL {
-                    transaction: view,
-                    max_age: MaxAge::MAX,
-                },
-                priority,
-                cost,
-            ))
-        };
+        let packet_parser = view_transaction_state;
 
         // Push 2 transactions into the queue so buffer is full.
         for priority in [4, 5] {
@@ -493,4 +1448,45 @@ mod tests {
         );
         assert!(container.pop().is_none());
     }
+
+    #[test]
+    fn test_view_insert_signature() {
+        let mut container = TransactionViewStateContainer::with_capacity(4);
+        let (transaction_ttl, _, cost) = test_transaction(1);
+        let packet =
+            Packet::from_data(None, transaction_ttl.transaction.to_versioned_transaction())
+                .unwrap();
+        let signature = *transaction_ttl.transaction.signature();
+
+        let id = container
+            .try_insert_map_only_with_data(packet.data(..).unwrap(), |data| {
+                view_transaction_state(data, 1, cost)
+            })
+            .unwrap();
+        assert!(container.insert_signature(id, signature, 1));
+        container.push_ids_into_queue(std::iter::once(TransactionPriorityId::new(1, id)));
+        assert_eq!(container.inner.signature_to_id.get(&signature), Some(&id));
+
+        // a copy of a higher priority is not held, but raises the priority
+        let duplicate_id = container
+            .try_insert_map_only_with_data(packet.data(..).unwrap(), |data| {
+                view_transaction_state(data, 3, cost)
+            })
+            .unwrap();
+        assert!(!container.insert_signature(duplicate_id, signature, 3));
+        container.remove_by_id(duplicate_id);
+        assert_eq!(container.inner.signature_to_id.get(&signature), Some(&id));
+        assert_eq!(container.queue_len(), 1);
+        assert_eq!(container.pop().unwrap(), TransactionPriorityId::new(3, id));
+
+        // once removed, the signature may be inserted again
+        container.remove_by_id(id);
+        assert!(container.inner.signature_to_id.is_empty());
+        let id = container
+            .try_insert_map_only_with_data(packet.data(..).unwrap(), |data| {
+                view_transaction_state(data, 1, cost)
+            })
+            .unwrap();
+        assert!(container.insert_signature(id, signature, 1));
+    }
 }
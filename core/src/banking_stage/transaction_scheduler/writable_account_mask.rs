@@ -0,0 +1,153 @@
+use {solana_pubkey::Pubkey, solana_svm_transaction::svm_message::SVMMessage};
+
+/// Maximum number of account keys a transaction can reference, static and
+/// loaded from lookup tables alike.
+const MAX_ACCOUNT_KEYS: usize = 256;
+
+/// Which account keys of a transaction are writable, computed once when the
+/// transaction is scheduled. The locks taken then are released on completion
+/// from the same partition, without calling `is_writable()` per key again.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct WritableAccountMask([u64; MAX_ACCOUNT_KEYS / 64]);
+
+impl WritableAccountMask {
+    pub(crate) fn new(transaction: &impl SVMMessage) -> Self {
+        let mut mask = Self::default();
+        let num_account_keys = transaction.account_keys().len();
+        debug_assert!(num_account_keys <= MAX_ACCOUNT_KEYS);
+        for index in 0..num_account_keys.min(MAX_ACCOUNT_KEYS) {
+            if transaction.is_writable(index) {
+                mask.0[index / 64] |= 1 << (index % 64);
+            }
+        }
+        mask
+    }
+
+    pub(crate) fn is_writable(&self, index: usize) -> bool {
+        index < MAX_ACCOUNT_KEYS && self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// The writable keys among `account_keys`, the keys of the transaction
+    /// the mask was computed for.
+    pub(crate) fn write_keys<'a>(
+        self,
+        account_keys: impl Iterator<Item = &'a Pubkey> + Clone,
+    ) -> impl Iterator<Item = &'a Pubkey> + Clone {
+        account_keys
+            .enumerate()
+            .filter_map(move |(index, key)| self.is_writable(index).then_some(key))
+    }
+
+    /// The readonly keys among `account_keys`, the keys of the transaction
+    /// the mask was computed for.
+    pub(crate) fn read_keys<'a>(
+        self,
+        account_keys: impl Iterator<Item = &'a Pubkey> + Clone,
+    ) -> impl Iterator<Item = &'a Pubkey> + Clone {
+        account_keys
+            .enumerate()
+            .filter_map(move |(index, key)| (!self.is_writable(index)).then_some(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::banking_stage::transaction_scheduler::thread_aware_account_locks::{
+            ThreadAwareAccountLocks, ThreadSet,
+        },
+        solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
+        solana_sdk::{
+            message::Message,
+            signature::Keypair,
+            signer::Signer,
+            system_instruction,
+            transaction::{SanitizedTransaction, Transaction},
+        },
+    };
+
+    fn transfers(num_recipients: usize) -> RuntimeTransaction<SanitizedTransaction> {
+        let payer = Keypair::new();
+        let recipients: Vec<_> = (0..num_recipients)
+            .map(|_| (Pubkey::new_unique(), 1))
+            .collect();
+        let message = Message::new(
+            &system_instruction::transfer_many(&payer.pubkey(), &recipients),
+            Some(&payer.pubkey()),
+        );
+        RuntimeTransaction::from_transaction_for_tests(Transaction::new_unsigned(message))
+    }
+
+    #[test]
+    fn test_writable_account_mask() {
+        let transaction = transfers(4);
+        let mask = WritableAccountMask::new(&transaction);
+        let account_keys = transaction.account_keys();
+        // the payer and the recipients are written, the system program read
+        assert_eq!(
+            mask.write_keys(account_keys.iter()).collect::<Vec<_>>(),
+            account_keys.iter().take(5).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            mask.read_keys(account_keys.iter()).collect::<Vec<_>>(),
+            account_keys.iter().skip(5).collect::<Vec<_>>()
+        );
+        for index in 0..account_keys.len() {
+            assert_eq!(mask.is_writable(index), transaction.is_writable(index));
+        }
+        assert!(!mask.is_writable(account_keys.len()));
+        assert!(!mask.is_writable(MAX_ACCOUNT_KEYS));
+
+        // keys past the first word of the mask
+        let transaction = transfers(100);
+        let mask = WritableAccountMask::new(&transaction);
+        for index in 0..transaction.account_keys().len() {
+            assert_eq!(mask.is_writable(index), transaction.is_writable(index));
+        }
+    }
+
+    #[test]
+    fn test_writable_account_mask_batch_completion() {
+        const BATCH_SIZE: usize = 64;
+        const NUM_RECIPIENTS: usize = 16;
+        let batch: Vec<_> = (0..BATCH_SIZE).map(|_| transfers(NUM_RECIPIENTS)).collect();
+        let masks: Vec<_> = batch.iter().map(WritableAccountMask::new).collect();
+        let mut account_locks = ThreadAwareAccountLocks::new(1);
+
+        // locks taken deriving the partition from each transaction
+        for transaction in &batch {
+            let account_keys = transaction.account_keys();
+            account_locks
+                .try_lock_accounts(
+                    account_keys
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(index, key)| transaction.is_writable(index).then_some(key)),
+                    account_keys.iter().enumerate().filter_map(|(index, key)| {
+                        (!transaction.is_writable(index)).then_some(key)
+                    }),
+                    ThreadSet::any(1),
+                    |_| 0,
+                )
+                .unwrap();
+        }
+        // the payers and recipients are written, the system program read by all
+        assert_eq!(
+            account_locks.num_locked_accounts(),
+            BATCH_SIZE * (1 + NUM_RECIPIENTS) + 1
+        );
+
+        // are released from the partition computed at scheduling time, which
+        // would panic on a key locked in the other mode
+        for (transaction, mask) in batch.iter().zip(&masks) {
+            let account_keys = transaction.account_keys();
+            account_locks.unlock_accounts(
+                mask.write_keys(account_keys.iter()),
+                mask.read_keys(account_keys.iter()),
+                0,
+            );
+        }
+        assert_eq!(account_locks.num_locked_accounts(), 0);
+    }
+}
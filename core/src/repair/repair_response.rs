@@ -1,12 +1,40 @@
 use {
     solana_ledger::{
         blockstore::Blockstore,
-        shred::{Nonce, SIZE_OF_NONCE},
+        shred::{Nonce, Shred, SIZE_OF_NONCE},
+    },
+    solana_sdk::{
+        clock::Slot,
+        packet::{Packet, PACKET_DATA_SIZE},
     },
-    solana_sdk::{clock::Slot, packet::Packet},
     std::{io, net::SocketAddr},
 };
 
+/// Leads the payload of a repair response whose shred is lz4 compressed, see
+/// `repair_response_packet_compressed`. The packet meta is not sent over the
+/// wire, so the requester can only tell from the payload.
+pub const COMPRESSED_REPAIR_RESPONSE_MAGIC: [u8; 4] = *b"RLZ4";
+
+/// Metadata of the shred served in a repair response, so that the caller can
+/// act on it without reading the shred again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShredMeta {
+    pub index: u32,
+    pub is_last_in_slot: bool,
+    pub is_coding: bool,
+}
+
+impl ShredMeta {
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let shred = Shred::new_from_serialized_shred(bytes.to_vec()).ok()?;
+        Some(Self {
+            index: shred.index(),
+            is_last_in_slot: shred.last_in_slot(),
+            is_coding: shred.is_code(),
+        })
+    }
+}
+
 pub fn repair_response_packet(
     blockstore: &Blockstore,
     slot: Slot,
@@ -14,14 +42,56 @@ pub fn repair_response_packet(
     dest: &SocketAddr,
     nonce: Nonce,
 ) -> Option<Packet> {
-    let shred = blockstore
-        .get_data_shred(slot, shred_index)
-        .expect("Blockstore could not get data shred");
+    repair_response_packet_from_blockstores([blockstore], slot, shred_index, dest, nonce)
+}
+
+/// Like `repair_response_packet`, but looks the shred up in each of
+/// `blockstores` in turn, e.g. a hot store of recent shreds followed by a cold
+/// store of older ones, and serves it from the first one that has it.
+pub fn repair_response_packet_from_blockstores<'a>(
+    blockstores: impl IntoIterator<Item = &'a Blockstore>,
+    slot: Slot,
+    shred_index: u64,
+    dest: &SocketAddr,
+    nonce: Nonce,
+) -> Option<Packet> {
+    let shred = blockstores.into_iter().find_map(|blockstore| {
+        blockstore
+            .get_data_shred(slot, shred_index)
+            .expect("Blockstore could not get data shred")
+    });
     shred
         .map(|shred| repair_response_packet_from_bytes(shred, dest, nonce))
         .unwrap_or(None)
 }
 
+/// Like `repair_response_packet`, but also returns the metadata of the shred.
+pub fn repair_response_packet_with_meta(
+    blockstore: &Blockstore,
+    slot: Slot,
+    shred_index: u64,
+    dest: &SocketAddr,
+    nonce: Nonce,
+) -> Option<(Packet, ShredMeta)> {
+    let shred = blockstore
+        .get_data_shred(slot, shred_index)
+        .expect("Blockstore could not get data shred")?;
+    repair_response_packet_from_bytes_with_meta(shred, dest, nonce)
+}
+
+/// Like `repair_response_packet_from_bytes`, but also returns the metadata of
+/// the shred. Returns `None` if `bytes` is not a valid shred.
+pub fn repair_response_packet_from_bytes_with_meta(
+    bytes: impl AsRef<[u8]>,
+    dest: &SocketAddr,
+    nonce: Nonce,
+) -> Option<(Packet, ShredMeta)> {
+    let bytes = bytes.as_ref();
+    let meta = ShredMeta::from_bytes(bytes)?;
+    let packet = repair_response_packet_from_bytes(bytes, dest, nonce)?;
+    Some((packet, meta))
+}
+
 pub fn repair_response_packet_from_bytes(
     bytes: impl AsRef<[u8]>,
     dest: &SocketAddr,
@@ -41,12 +111,51 @@ pub fn repair_response_packet_from_bytes(
     Some(packet)
 }
 
+/// Like `repair_response_packet_from_bytes`, but with the shred lz4
+/// compressed behind `COMPRESSED_REPAIR_RESPONSE_MAGIC`, for links where
+/// bandwidth is costly. Shreds are mostly incompressible, so the plain
+/// response is returned if compression does not make it smaller.
+pub fn repair_response_packet_compressed(
+    bytes: impl AsRef<[u8]>,
+    dest: &SocketAddr,
+    nonce: Nonce,
+) -> Option<Packet> {
+    let bytes = bytes.as_ref();
+    let compressed = match lz4::block::compress(bytes, None, /*prepend_size:*/ false) {
+        Ok(compressed)
+            if COMPRESSED_REPAIR_RESPONSE_MAGIC.len() + compressed.len() < bytes.len() =>
+        {
+            compressed
+        }
+        _ => return repair_response_packet_from_bytes(bytes, dest, nonce),
+    };
+    let mut payload = Vec::with_capacity(COMPRESSED_REPAIR_RESPONSE_MAGIC.len() + compressed.len());
+    payload.extend_from_slice(&COMPRESSED_REPAIR_RESPONSE_MAGIC);
+    payload.extend_from_slice(&compressed);
+    repair_response_packet_from_bytes(payload, dest, nonce)
+}
+
+/// Decompresses a repair response built by `repair_response_packet_compressed`
+/// into the shred followed by the nonce, as in an uncompressed response.
+/// Returns `None` if `payload` is not a compressed response or is corrupt.
+pub fn decompress_repair_response(payload: &[u8]) -> Option<Vec<u8>> {
+    let compressed = payload.strip_prefix(&COMPRESSED_REPAIR_RESPONSE_MAGIC)?;
+    let (compressed, nonce) = compressed.split_at(compressed.len().checked_sub(SIZE_OF_NONCE)?);
+    // The size is bounded by a packet rather than read from the payload, so
+    // that a corrupt response cannot make us allocate more.
+    let mut shred = lz4::block::decompress(compressed, Some(PACKET_DATA_SIZE as i32)).ok()?;
+    shred.extend_from_slice(nonce);
+    Some(shred)
+}
+
 #[cfg(test)]
 mod test {
     use {
         super::*,
         solana_ledger::{
-            shred::{Shred, ShredFlags},
+            blockstore::make_many_slot_entries,
+            get_tmp_ledger_path_auto_delete,
+            shred::ShredFlags,
             sigverify_shreds::{verify_shred_cpu, LruCache},
         },
         solana_sdk::{
@@ -101,4 +210,142 @@ mod test {
     fn test_sigverify_shred_cpu_repair() {
         run_test_sigverify_shred_cpu_repair(0xdead_c0de);
     }
+
+    #[test]
+    fn test_repair_response_packet_compressed() {
+        let mut shred = Shred::new_from_data(
+            0xdead_c0de,
+            0xc0de,
+            0xdead,
+            &[1u8; 1000],
+            ShredFlags::LAST_SHRED_IN_SLOT,
+            0,
+            0,
+            0xc0de,
+        );
+        shred.sign(&Keypair::new());
+        let payload = shred.into_payload();
+        let dest = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080);
+        let nonce = 9;
+        let plain = repair_response_packet_from_bytes(&payload, &dest, nonce).unwrap();
+
+        let compressed = repair_response_packet_compressed(&payload, &dest, nonce).unwrap();
+        assert!(compressed.meta().size < plain.meta().size);
+        assert_eq!(compressed.meta().socket_addr(), dest);
+        let compressed = compressed.data(..).unwrap();
+        assert!(compressed.starts_with(&COMPRESSED_REPAIR_RESPONSE_MAGIC));
+        assert_eq!(
+            decompress_repair_response(compressed).as_deref(),
+            plain.data(..)
+        );
+
+        // plain and corrupt responses are not decompressed
+        assert_eq!(decompress_repair_response(plain.data(..).unwrap()), None);
+        let mut corrupt = compressed.to_vec();
+        corrupt.truncate(COMPRESSED_REPAIR_RESPONSE_MAGIC.len() + 8);
+        assert_eq!(decompress_repair_response(&corrupt), None);
+        assert_eq!(
+            decompress_repair_response(&COMPRESSED_REPAIR_RESPONSE_MAGIC),
+            None
+        );
+
+        // incompressible bytes are sent as is
+        let random_bytes: Vec<u8> = (0..1000).map(|_| rand::random()).collect();
+        assert_eq!(
+            repair_response_packet_compressed(&random_bytes, &dest, nonce),
+            repair_response_packet_from_bytes(&random_bytes, &dest, nonce),
+        );
+    }
+
+    #[test]
+    fn test_repair_response_packet_with_meta() {
+        let mut shred = Shred::new_from_data(
+            0xdead_c0de,
+            0xc0de,
+            0xdead,
+            &[1, 2, 3, 4],
+            ShredFlags::LAST_SHRED_IN_SLOT,
+            0,
+            0,
+            0xc0de,
+        );
+        shred.sign(&Keypair::new());
+        let payload = shred.into_payload();
+        let dest = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080);
+        let nonce = 9;
+        let (packet, meta) =
+            repair_response_packet_from_bytes_with_meta(&payload, &dest, nonce).unwrap();
+        assert_eq!(
+            meta,
+            ShredMeta {
+                index: 0xdead,
+                is_last_in_slot: true,
+                is_coding: false,
+            }
+        );
+        assert_eq!(
+            Some(packet),
+            repair_response_packet_from_bytes(&payload, &dest, nonce)
+        );
+
+        // bytes which are not a shred have no metadata
+        assert_eq!(
+            repair_response_packet_from_bytes_with_meta([1u8; 100], &dest, nonce),
+            None
+        );
+
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let (shreds, _) = make_many_slot_entries(1, 1, 1);
+        let last_index = shreds.last().unwrap().index();
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        let (packet, meta) =
+            repair_response_packet_with_meta(&blockstore, 1, u64::from(last_index), &dest, nonce)
+                .unwrap();
+        assert_eq!(meta.index, last_index);
+        assert!(meta.is_last_in_slot);
+        assert!(!meta.is_coding);
+        assert_eq!(
+            Some(packet),
+            repair_response_packet(&blockstore, 1, u64::from(last_index), &dest, nonce)
+        );
+        assert!(repair_response_packet_with_meta(&blockstore, 2, 0, &dest, nonce).is_none());
+    }
+
+    #[test]
+    fn test_repair_response_packet_from_blockstores() {
+        let hot_ledger_path = get_tmp_ledger_path_auto_delete!();
+        let hot = Blockstore::open(hot_ledger_path.path()).unwrap();
+        let cold_ledger_path = get_tmp_ledger_path_auto_delete!();
+        let cold = Blockstore::open(cold_ledger_path.path()).unwrap();
+
+        // Slot 1 is only in the cold store, slot 2 only in the hot one.
+        let (shreds, _) = make_many_slot_entries(1, 2, 1);
+        let (cold_shreds, hot_shreds): (Vec<_>, Vec<_>) =
+            shreds.into_iter().partition(|shred| shred.slot() == 1);
+        cold.insert_shreds(cold_shreds, None, false).unwrap();
+        hot.insert_shreds(hot_shreds, None, false).unwrap();
+
+        let dest = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080);
+        let nonce = 42;
+        assert!(repair_response_packet(&hot, 1, 0, &dest, nonce).is_none());
+        assert_eq!(
+            repair_response_packet_from_blockstores([&hot, &cold], 1, 0, &dest, nonce),
+            repair_response_packet(&cold, 1, 0, &dest, nonce),
+        );
+        assert!(
+            repair_response_packet_from_blockstores([&hot, &cold], 1, 0, &dest, nonce).is_some()
+        );
+        assert_eq!(
+            repair_response_packet_from_blockstores([&hot, &cold], 2, 0, &dest, nonce),
+            repair_response_packet(&hot, 2, 0, &dest, nonce),
+        );
+        assert!(
+            repair_response_packet_from_blockstores([&hot, &cold], 3, 0, &dest, nonce).is_none()
+        );
+        assert!(
+            repair_response_packet_from_blockstores(std::iter::empty(), 1, 0, &dest, nonce)
+                .is_none()
+        );
+    }
 }
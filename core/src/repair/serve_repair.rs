@@ -339,6 +339,9 @@ pub struct ServeRepair {
     cluster_info: Arc<ClusterInfo>,
     root_bank_cache: RootBankCache,
     repair_whitelist: Arc<RwLock<HashSet<Pubkey>>>,
+    /// Looked up in order for the shreds of window requests missing from the
+    /// blockstore being served, e.g. a cold store of older shreds.
+    cold_blockstores: Vec<Arc<Blockstore>>,
 }
 
 // Cache entry for repair peers for a slot.
@@ -406,9 +409,17 @@ impl ServeRepair {
             cluster_info,
             root_bank_cache: RootBankCache::new(bank_forks),
             repair_whitelist,
+            cold_blockstores: Vec::default(),
         }
     }
 
+    /// Serve the window requests for shreds missing from the blockstore of
+    /// `listen()` from `cold_blockstores`, the first one having the shred.
+    pub fn with_cold_blockstores(mut self, cold_blockstores: Vec<Arc<Blockstore>>) -> Self {
+        self.cold_blockstores = cold_blockstores;
+        self
+    }
+
     pub(crate) fn my_id(&self) -> Pubkey {
         self.cluster_info.id()
     }
@@ -417,6 +428,7 @@ impl ServeRepair {
         recycler: &PacketBatchRecycler,
         from_addr: &SocketAddr,
         blockstore: &Blockstore,
+        cold_blockstores: &[Arc<Blockstore>],
         request: RepairProtocol,
         stats: &mut ServeRepairStats,
         ping_cache: &mut PingCache,
@@ -434,6 +446,7 @@ impl ServeRepair {
                         recycler,
                         from_addr,
                         blockstore,
+                        cold_blockstores,
                         *slot,
                         *shred_index,
                         *nonce,
@@ -1009,9 +1022,15 @@ impl ServeRepair {
                 }
             }
             stats.processed += 1;
-            let Some(rsp) =
-                Self::handle_repair(recycler, &from_addr, blockstore, request, stats, ping_cache)
-            else {
+            let Some(rsp) = Self::handle_repair(
+                recycler,
+                &from_addr,
+                blockstore,
+                &self.cold_blockstores,
+                request,
+                stats,
+                ping_cache,
+            ) else {
                 continue;
             };
             let num_response_packets = rsp.len();
@@ -1296,13 +1315,15 @@ impl ServeRepair {
         recycler: &PacketBatchRecycler,
         from_addr: &SocketAddr,
         blockstore: &Blockstore,
+        cold_blockstores: &[Arc<Blockstore>],
         slot: Slot,
         shred_index: u64,
         nonce: Nonce,
     ) -> Option<PacketBatch> {
-        // Try to find the requested index in one of the slots
-        let packet = repair_response::repair_response_packet(
-            blockstore,
+        // Try to find the requested index in one of the slots, falling back
+        // to the cold blockstores
+        let packet = repair_response::repair_response_packet_from_blockstores(
+            std::iter::once(blockstore).chain(cold_blockstores.iter().map(Arc::as_ref)),
             slot,
             shred_index,
             from_addr,
@@ -1957,6 +1978,7 @@ mod tests {
             &recycler,
             &socketaddr_any!(),
             &blockstore,
+            &[],
             slot,
             0,
             nonce,
@@ -1973,6 +1995,7 @@ mod tests {
             &recycler,
             &socketaddr_any!(),
             &blockstore,
+            &[],
             slot,
             index,
             nonce,
@@ -1994,6 +2017,44 @@ mod tests {
         assert_eq!(rv[0].slot(), slot);
     }
 
+    #[test]
+    fn test_run_window_request_cold_blockstore() {
+        let recycler = PacketBatchRecycler::default();
+        let (slot, index, nonce) = (2, 1, 9);
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let cold_ledger_path = get_tmp_ledger_path_auto_delete!();
+        let cold_blockstore = Arc::new(Blockstore::open(cold_ledger_path.path()).unwrap());
+        let shred = Shred::new_from_data(slot, 1, 1, &[], ShredFlags::empty(), 0, 2, 0);
+        cold_blockstore
+            .insert_shreds(vec![shred], None, false)
+            .expect("Expect successful ledger write");
+
+        // the shred is only served with the cold blockstore to fall back to
+        assert!(ServeRepair::run_window_request(
+            &recycler,
+            &socketaddr_any!(),
+            &blockstore,
+            &[],
+            slot,
+            index,
+            nonce,
+        )
+        .is_none());
+        let rv = ServeRepair::run_window_request(
+            &recycler,
+            &socketaddr_any!(),
+            &blockstore,
+            &[cold_blockstore],
+            slot,
+            index,
+            nonce,
+        )
+        .expect("packets");
+        verify_responses(&ShredRepairType::Shred(slot, index), rv.iter());
+        assert_eq!(rv.len(), 1);
+    }
+
     fn new_test_cluster_info() -> ClusterInfo {
         let keypair = Arc::new(Keypair::new());
         let contact_info = ContactInfo::new_localhost(&keypair.pubkey(), timestamp());
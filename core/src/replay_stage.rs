@@ -252,6 +252,19 @@ impl PartitionInfo {
     }
 }
 
+/// Identity the replay stage votes with, published once its tower is loaded
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VotingIdentity {
+    pub identity: Pubkey,
+    /// Identity voted with before the last identity change, if any
+    pub previous_identity: Option<Pubkey>,
+    /// Last slot voted on by the tower of `previous_identity` when it was
+    /// replaced, including votes not yet saved by the voting service
+    pub previous_last_vote_slot: Option<Slot>,
+}
+
+pub type SharedVotingIdentity = Arc<RwLock<VotingIdentity>>;
+
 pub struct ReplayStageConfig {
     pub vote_account: Pubkey,
     pub authorized_voter_keypairs: Arc<RwLock<Vec<Arc<Keypair>>>>,
@@ -275,6 +288,7 @@ pub struct ReplayStageConfig {
     pub log_messages_bytes_limit: Option<usize>,
     pub prioritization_fee_cache: Arc<PrioritizationFeeCache>,
     pub banking_tracer: Arc<BankingTracer>,
+    pub voting_identity: SharedVotingIdentity,
 }
 
 pub struct ReplaySenders {
@@ -566,6 +580,7 @@ impl ReplayStage {
             log_messages_bytes_limit,
             prioritization_fee_cache,
             banking_tracer,
+            voting_identity,
         } = config;
 
         let ReplaySenders {
@@ -634,6 +649,7 @@ impl ReplayStage {
                     my_old_pubkey, my_pubkey
                 );
             }
+            voting_identity.write().unwrap().identity = my_pubkey;
             let (mut progress, mut heaviest_subtree_fork_choice) =
                 Self::initialize_progress_and_fork_choice_with_locked_bank_forks(
                     &bank_forks,
@@ -1057,6 +1073,7 @@ impl ReplayStage {
                         if my_pubkey != cluster_info.id() {
                             identity_keypair = cluster_info.keypair().clone();
                             let my_old_pubkey = my_pubkey;
+                            let my_old_last_vote_slot = tower.last_voted_slot();
                             my_pubkey = identity_keypair.pubkey();
 
                             // Load the new identity's tower
@@ -1080,6 +1097,11 @@ impl ReplayStage {
                             // Ensure the validator can land votes with the new identity before
                             // becoming leader
                             has_new_vote_been_rooted = !wait_for_vote_to_start_leader;
+                            *voting_identity.write().unwrap() = VotingIdentity {
+                                identity: my_pubkey,
+                                previous_identity: Some(my_old_pubkey),
+                                previous_last_vote_slot: my_old_last_vote_slot,
+                            };
                             warn!("Identity changed from {} to {}", my_old_pubkey, my_pubkey);
                         }
 
@@ -10,7 +10,9 @@ pub use solana_sdk::net::DEFAULT_TPU_COALESCE;
 pub use solana_streamer::quic::DEFAULT_MAX_QUIC_CONNECTIONS_PER_PEER as MAX_QUIC_CONNECTIONS_PER_PEER;
 use {
     crate::{
-        banking_stage::BankingStage,
+        banking_stage::{
+            BankingStage, EstimateRequestSender, SharedAdmissionConfig, SharedSchedulerState,
+        },
         banking_trace::{Channels, TracerThread},
         cluster_info_vote_listener::{
             ClusterInfoVoteListener, DuplicateConfirmedSlotsSender, GossipVerifiedVoteHashSender,
@@ -126,6 +128,7 @@ impl Tpu {
         block_production_method: BlockProductionMethod,
         transaction_struct: TransactionStructure,
         enable_block_production_forwarding: bool,
+        banking_transaction_ttl: Option<Duration>,
         _generator_config: Option<GeneratorConfig>, /* vestigial code for replay invalidator */
     ) -> (Self, Vec<Arc<dyn NotifyKeyUpdate + Sync + Send>>) {
         let TpuSockets {
@@ -273,6 +276,7 @@ impl Tpu {
             log_messages_bytes_limit,
             bank_forks.clone(),
             prioritization_fee_cache,
+            banking_transaction_ttl,
         );
 
         let forwarding_stage = ForwardingStage::spawn(
@@ -329,6 +333,22 @@ impl Tpu {
         )
     }
 
+    /// Latest snapshot of the state of the banking stage scheduler.
+    pub fn scheduler_state(&self) -> SharedSchedulerState {
+        self.banking_stage.scheduler_state()
+    }
+
+    /// Admission floor of the banking stage scheduler container.
+    pub fn admission_config(&self) -> SharedAdmissionConfig {
+        self.banking_stage.admission_config()
+    }
+
+    /// Channel the banking stage scheduler answers scheduling estimate
+    /// requests on.
+    pub fn estimate_request_sender(&self) -> EstimateRequestSender {
+        self.banking_stage.estimate_request_sender()
+    }
+
     pub fn join(self) -> thread::Result<()> {
         let results = vec![
             self.fetch_stage.join(),
@@ -14,7 +14,9 @@ use {
         cost_update_service::CostUpdateService,
         drop_bank_service::DropBankService,
         repair::repair_service::{OutstandingShredRepairs, RepairInfo, RepairServiceChannels},
-        replay_stage::{ReplayReceivers, ReplaySenders, ReplayStage, ReplayStageConfig},
+        replay_stage::{
+            ReplayReceivers, ReplaySenders, ReplayStage, ReplayStageConfig, SharedVotingIdentity,
+        },
         shred_fetch_stage::ShredFetchStage,
         voting_service::VotingService,
         warm_quic_cache_service::WarmQuicCacheService,
@@ -69,6 +71,7 @@ pub struct Tvu {
     warm_quic_cache_service: Option<WarmQuicCacheService>,
     drop_bank_service: DropBankService,
     duplicate_shred_listener: DuplicateShredListener,
+    voting_identity: SharedVotingIdentity,
 }
 
 pub struct TvuSockets {
@@ -307,6 +310,7 @@ impl Tvu {
             popular_pruned_forks_receiver,
         };
 
+        let voting_identity = SharedVotingIdentity::default();
         let replay_stage_config = ReplayStageConfig {
             vote_account: *vote_account,
             authorized_voter_keypairs,
@@ -328,6 +332,7 @@ impl Tvu {
             log_messages_bytes_limit,
             prioritization_fee_cache: prioritization_fee_cache.clone(),
             banking_tracer,
+            voting_identity: voting_identity.clone(),
         };
 
         let voting_service = VotingService::new(
@@ -389,9 +394,15 @@ impl Tvu {
             warm_quic_cache_service,
             drop_bank_service,
             duplicate_shred_listener,
+            voting_identity,
         })
     }
 
+    /// Identity the replay stage votes with.
+    pub fn voting_identity(&self) -> SharedVotingIdentity {
+        self.voting_identity.clone()
+    }
+
     pub fn join(self) -> thread::Result<()> {
         self.retransmit_stage.join()?;
         self.window_service.join()?;
@@ -86,7 +86,7 @@ use {
         max_slots::MaxSlots,
         optimistically_confirmed_bank_tracker::{
             BankNotificationSenderConfig, OptimisticallyConfirmedBank,
-            OptimisticallyConfirmedBankTracker,
+            OptimisticallyConfirmedBankTracker, SlotNotificationSender,
         },
         rpc::JsonRpcConfig,
         rpc_completed_slots_service::RpcCompletedSlotsService,
@@ -256,6 +256,9 @@ pub struct ValidatorConfig {
     /// Specifies which plugins to start up with
     pub on_start_geyser_plugin_config_files: Option<Vec<PathBuf>>,
     pub geyser_plugin_always_enabled: bool,
+    /// Notified of the confirmed, frozen and rooted slots, whether or not the
+    /// RPC service is enabled
+    pub slot_notification_senders: Vec<SlotNotificationSender>,
     pub rpc_addrs: Option<(SocketAddr, SocketAddr)>, // (JsonRpc, JsonRpcPubSub)
     pub pubsub_config: PubSubConfig,
     pub snapshot_config: SnapshotConfig,
@@ -305,6 +308,7 @@ pub struct ValidatorConfig {
     pub block_production_method: BlockProductionMethod,
     pub transaction_struct: TransactionStructure,
     pub enable_block_production_forwarding: bool,
+    pub banking_transaction_ttl: Option<Duration>,
     pub generator_config: Option<GeneratorConfig>,
     pub use_snapshot_archives_at_startup: UseSnapshotArchivesAtStartup,
     pub wen_restart_proto_path: Option<PathBuf>,
@@ -334,6 +338,7 @@ impl Default for ValidatorConfig {
             rpc_config: JsonRpcConfig::default(),
             on_start_geyser_plugin_config_files: None,
             geyser_plugin_always_enabled: false,
+            slot_notification_senders: Vec::new(),
             rpc_addrs: None,
             pubsub_config: PubSubConfig::default(),
             snapshot_config: SnapshotConfig::new_load_only(),
@@ -379,6 +384,7 @@ impl Default for ValidatorConfig {
             block_production_method: BlockProductionMethod::default(),
             transaction_struct: TransactionStructure::default(),
             enable_block_production_forwarding: false,
+            banking_transaction_ttl: None,
             generator_config: None,
             use_snapshot_archives_at_startup: UseSnapshotArchivesAtStartup::default(),
             wen_restart_proto_path: None,
@@ -649,7 +655,7 @@ impl Validator {
             })?;
         }
 
-        let mut bank_notification_senders = Vec::new();
+        let mut bank_notification_senders = config.slot_notification_senders.clone();
 
         let exit = Arc::new(AtomicBool::new(false));
 
@@ -1142,6 +1148,10 @@ impl Validator {
 
         let rpc_override_health_check =
             Arc::new(AtomicBool::new(config.rpc_config.disable_health_check));
+        // The roots are only notified to the slot notification subscribers
+        // from the rooted chains, which include the parent slots.
+        let should_send_parents =
+            geyser_plugin_service.is_some() || !config.slot_notification_senders.is_empty();
         let (
             json_rpc_service,
             pubsub_service,
@@ -1254,7 +1264,7 @@ impl Validator {
                 ));
             let bank_notification_sender_config = Some(BankNotificationSenderConfig {
                 sender: bank_notification_sender,
-                should_send_parents: geyser_plugin_service.is_some(),
+                should_send_parents,
             });
             (
                 Some(json_rpc_service),
@@ -1265,6 +1275,32 @@ impl Validator {
                 optimistically_confirmed_bank_tracker,
                 bank_notification_sender_config,
             )
+        } else if !bank_notification_senders.is_empty() {
+            // Without RPC, the slots are still tracked for the slot
+            // notification subscribers, such as the admin RPC events.
+            let (bank_notification_sender, bank_notification_receiver) = unbounded();
+            let optimistically_confirmed_bank_tracker = OptimisticallyConfirmedBankTracker::new(
+                bank_notification_receiver,
+                exit.clone(),
+                bank_forks.clone(),
+                optimistically_confirmed_bank,
+                rpc_subscriptions.clone(),
+                Some(Arc::new(RwLock::new(bank_notification_senders))),
+                prioritization_fee_cache.clone(),
+            );
+            let bank_notification_sender_config = Some(BankNotificationSenderConfig {
+                sender: bank_notification_sender,
+                should_send_parents,
+            });
+            (
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(optimistically_confirmed_bank_tracker),
+                bank_notification_sender_config,
+            )
         } else {
             (None, None, None, None, None, None, None)
         };
@@ -1604,6 +1640,7 @@ impl Validator {
             config.block_production_method.clone(),
             config.transaction_struct.clone(),
             config.enable_block_production_forwarding,
+            config.banking_transaction_ttl,
             config.generator_config.clone(),
         );
 
@@ -1629,6 +1666,11 @@ impl Validator {
             repair_socket: Arc::new(node.sockets.repair),
             outstanding_repair_requests,
             cluster_slots,
+            blockstore: blockstore.clone(),
+            scheduler_state: tpu.scheduler_state(),
+            estimate_request_sender: tpu.estimate_request_sender(),
+            admission_config: tpu.admission_config(),
+            voting_identity: tvu.voting_identity(),
         });
 
         Ok(Self {
@@ -1,10 +1,66 @@
-use solana_pubkey::Pubkey;
+use {
+    serde::{Deserialize, Serialize},
+    solana_pubkey::Pubkey,
+    std::{
+        collections::{HashMap, HashSet},
+        fs::File,
+        io::{self, BufReader, BufWriter},
+        path::Path,
+        sync::{Arc, RwLock},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// On load, one in this many cached addresses is re-derived to detect a stale cache
+pub const DERIVATION_CACHE_SAMPLE_INTERVAL: usize = 1024;
+
+fn derive_address(base_pubkey: &Pubkey, program_id: &Pubkey, nth: usize) -> Pubkey {
+    Pubkey::create_with_seed(base_pubkey, &format!("{nth}"), program_id).unwrap()
+}
+
+/// The addresses of the (base, program_id) namespace, in the order
+/// `AddressGenerator` derives them, each derived only once it is reached.
+/// Skipping addresses with `nth()` or `skip()` does not derive them.
+pub fn addresses(base_pubkey: &Pubkey, program_id: &Pubkey) -> Addresses {
+    Addresses {
+        base_pubkey: *base_pubkey,
+        program_id: *program_id,
+        nth: 0,
+    }
+}
+
+/// Iterator returned by `addresses()`
+#[derive(Clone, Debug)]
+pub struct Addresses {
+    base_pubkey: Pubkey,
+    program_id: Pubkey,
+    nth: usize,
+}
+
+impl Iterator for Addresses {
+    type Item = Pubkey;
+
+    fn next(&mut self) -> Option<Pubkey> {
+        self.nth(0)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Pubkey> {
+        let nth = self.nth.checked_add(n)?;
+        self.nth = nth.checked_add(1)?;
+        Some(derive_address(&self.base_pubkey, &self.program_id, nth))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX - self.nth, Some(usize::MAX - self.nth))
+    }
+}
 
 #[derive(Default)]
 pub struct AddressGenerator {
     base_pubkey: Pubkey,
     program_id: Pubkey,
     nth: usize,
+    derivation_cache: Option<Arc<DerivationCache>>,
 }
 
 impl AddressGenerator {
@@ -13,11 +69,23 @@ impl AddressGenerator {
             base_pubkey: *base_pubkey,
             program_id: *program_id,
             nth: 0,
+            derivation_cache: None,
         }
     }
 
+    /// Consult (and fill) `derivation_cache` instead of always re-deriving addresses
+    pub fn with_derivation_cache(mut self, derivation_cache: Option<Arc<DerivationCache>>) -> Self {
+        self.derivation_cache = derivation_cache;
+        self
+    }
+
     pub fn nth(&self, nth: usize) -> Pubkey {
-        Pubkey::create_with_seed(&self.base_pubkey, &format!("{nth}"), &self.program_id).unwrap()
+        match &self.derivation_cache {
+            Some(derivation_cache) => {
+                derivation_cache.get_or_derive(&self.base_pubkey, &self.program_id, nth)
+            }
+            None => derive_address(&self.base_pubkey, &self.program_id, nth),
+        }
     }
 
     #[allow(clippy::should_implement_trait)]
@@ -26,4 +94,271 @@ impl AddressGenerator {
         self.nth += 1;
         self.nth(nth)
     }
+
+    /// Reserve the next `count` addresses, skipping any derived address found in
+    /// `blacklist`. Skipped indices are consumed, so the result only depends on
+    /// the generator's position and the blacklist.
+    /// Returns each reserved address with the index it was derived from.
+    pub fn reserve_avoiding(
+        &mut self,
+        count: usize,
+        blacklist: &HashSet<Pubkey>,
+    ) -> Vec<(usize, Pubkey)> {
+        let mut reserved = Vec::with_capacity(count);
+        while reserved.len() < count {
+            let nth = self.nth;
+            let address = self.next();
+            if !blacklist.contains(&address) {
+                reserved.push((nth, address));
+            }
+        }
+        reserved
+    }
+}
+
+/// A contiguous range of derived addresses, starting at index 0, for one
+/// (base, program_id) namespace
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+struct DerivationCacheEntry {
+    base_pubkey: Pubkey,
+    program_id: Pubkey,
+    addresses: Vec<Pubkey>,
+}
+
+/// Persistent cache of addresses derived by `AddressGenerator`.
+///
+/// The cache is strictly an optimization: a cached address is always the
+/// address that would have been derived without it.
+#[derive(Default, Debug)]
+pub struct DerivationCache {
+    entries: RwLock<HashMap<(Pubkey, Pubkey), Vec<Pubkey>>>,
+}
+
+impl DerivationCache {
+    /// Load a cache previously written by `save()`, verifying a sample of its entries
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let sample_offset = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos() as usize)
+            .unwrap_or_default();
+        Self::load_with_sample(path, DERIVATION_CACHE_SAMPLE_INTERVAL, sample_offset)
+    }
+
+    fn load_with_sample(path: &Path, interval: usize, offset: usize) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let entries: Vec<DerivationCacheEntry> = bincode::deserialize_from(BufReader::new(file))
+            .map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid derivation cache {}: {err:?}", path.display()),
+                )
+            })?;
+        let cache = Self {
+            entries: RwLock::new(
+                entries
+                    .into_iter()
+                    .map(|entry| ((entry.base_pubkey, entry.program_id), entry.addresses))
+                    .collect(),
+            ),
+        };
+        cache.verify_sample(interval, offset)?;
+        Ok(cache)
+    }
+
+    /// Load the cache at `path` if it exists, otherwise start with an empty cache
+    pub fn load_or_default(path: &Path) -> io::Result<Self> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut entries: Vec<_> = self
+            .entries
+            .read()
+            .unwrap()
+            .iter()
+            .map(
+                |((base_pubkey, program_id), addresses)| DerivationCacheEntry {
+                    base_pubkey: *base_pubkey,
+                    program_id: *program_id,
+                    addresses: addresses.clone(),
+                },
+            )
+            .collect();
+        entries.sort_by_key(|entry| (entry.base_pubkey, entry.program_id));
+
+        let file = File::create(path)?;
+        bincode::serialize_into(BufWriter::new(file), &entries)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{err:?}")))
+    }
+
+    /// Re-derive every `interval`th cached address (starting at `offset % interval`)
+    /// and fail if any of them does not match
+    pub fn verify_sample(&self, interval: usize, offset: usize) -> io::Result<()> {
+        let interval = interval.max(1);
+        for ((base_pubkey, program_id), addresses) in self.entries.read().unwrap().iter() {
+            for nth in (offset % interval..addresses.len()).step_by(interval) {
+                if addresses[nth] != derive_address(base_pubkey, program_id, nth) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "derivation cache mismatch for base {base_pubkey}, program {program_id}, \
+                             index {nth}; delete the cache and rebuild"
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get_or_derive(&self, base_pubkey: &Pubkey, program_id: &Pubkey, nth: usize) -> Pubkey {
+        let key = (*base_pubkey, *program_id);
+        if let Some(address) = self
+            .entries
+            .read()
+            .unwrap()
+            .get(&key)
+            .and_then(|addresses| addresses.get(nth))
+        {
+            return *address;
+        }
+
+        // keep each range contiguous by deriving any gap up to `nth`
+        let mut entries = self.entries.write().unwrap();
+        let addresses = entries.entry(key).or_default();
+        for index in addresses.len()..=nth {
+            addresses.push(derive_address(base_pubkey, program_id, index));
+        }
+        addresses[nth]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tempfile::TempDir};
+
+    #[test]
+    fn test_reserve_avoiding() {
+        let base_pubkey = solana_pubkey::new_rand();
+        let program_id = solana_pubkey::new_rand();
+        let uncached_generator = AddressGenerator::new(&base_pubkey, &program_id);
+        let blacklist = HashSet::from([uncached_generator.nth(1), solana_pubkey::new_rand()]);
+
+        let mut address_generator = AddressGenerator::new(&base_pubkey, &program_id);
+        let reserved = address_generator.reserve_avoiding(3, &blacklist);
+        assert_eq!(
+            reserved,
+            vec![
+                (0, uncached_generator.nth(0)),
+                (2, uncached_generator.nth(2)),
+                (3, uncached_generator.nth(3)),
+            ]
+        );
+        assert!(reserved.iter().all(|(nth, _)| *nth != 1));
+        assert_eq!(address_generator.next(), uncached_generator.nth(4));
+        assert!(address_generator.reserve_avoiding(0, &blacklist).is_empty());
+    }
+
+    #[test]
+    fn test_addresses() {
+        let base_pubkey = solana_pubkey::new_rand();
+        let program_id = solana_pubkey::new_rand();
+        let mut address_generator = AddressGenerator::new(&base_pubkey, &program_id);
+        let expected: Vec<_> = (0..10).map(|_| address_generator.next()).collect();
+
+        assert_eq!(
+            addresses(&base_pubkey, &program_id)
+                .take(10)
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(
+            addresses(&base_pubkey, &program_id)
+                .skip(3)
+                .take(4)
+                .collect::<Vec<_>>(),
+            expected[3..7]
+        );
+        let mut iter = addresses(&base_pubkey, &program_id);
+        assert_eq!(iter.nth(5), Some(expected[5]));
+        assert_eq!(iter.next(), Some(expected[6]));
+        assert_eq!(
+            addresses(&base_pubkey, &program_id).nth(1_000),
+            Some(address_generator.nth(1_000))
+        );
+
+        // another namespace derives other addresses
+        assert!(addresses(&program_id, &base_pubkey)
+            .take(10)
+            .all(|address| !expected.contains(&address)));
+    }
+
+    #[test]
+    fn test_derivation_cache_round_trip() {
+        let base_pubkey = solana_pubkey::new_rand();
+        let program_id = solana_pubkey::new_rand();
+        let cache = Arc::new(DerivationCache::default());
+
+        let mut address_generator = AddressGenerator::new(&base_pubkey, &program_id)
+            .with_derivation_cache(Some(cache.clone()));
+        let addresses: Vec<_> = (0..10).map(|_| address_generator.next()).collect();
+        assert_eq!(cache.len(), 10);
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("derivation_cache.bin");
+        cache.save(&path).unwrap();
+
+        let loaded = Arc::new(DerivationCache::load(&path).unwrap());
+        assert_eq!(loaded.len(), 10);
+        let mut uncached_generator = AddressGenerator::new(&base_pubkey, &program_id);
+        let mut cached_generator =
+            AddressGenerator::new(&base_pubkey, &program_id).with_derivation_cache(Some(loaded));
+        for address in addresses {
+            assert_eq!(address, cached_generator.next());
+            assert_eq!(address, uncached_generator.next());
+        }
+
+        // an index beyond the cached range is derived and appended
+        assert_eq!(
+            cached_generator.nth(20),
+            AddressGenerator::new(&base_pubkey, &program_id).nth(20)
+        );
+    }
+
+    #[test]
+    fn test_derivation_cache_detects_corruption() {
+        let base_pubkey = solana_pubkey::new_rand();
+        let program_id = solana_pubkey::new_rand();
+        let cache = DerivationCache::default();
+        cache.get_or_derive(&base_pubkey, &program_id, 4);
+        assert!(cache.verify_sample(1, 0).is_ok());
+
+        cache
+            .entries
+            .write()
+            .unwrap()
+            .get_mut(&(base_pubkey, program_id))
+            .unwrap()[3] = solana_pubkey::new_rand();
+        assert!(cache.verify_sample(1, 0).is_err());
+        // a sample that skips the corrupted entry doesn't notice it
+        assert!(cache.verify_sample(2, 0).is_ok());
+        assert!(cache.verify_sample(2, 1).is_err());
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("derivation_cache.bin");
+        cache.save(&path).unwrap();
+        assert!(DerivationCache::load_with_sample(&path, 1, 0).is_err());
+    }
 }
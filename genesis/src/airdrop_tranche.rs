@@ -0,0 +1,297 @@
+//! Pre-funded "airdrop tranche" accounts for faucet clusters
+//!
+//! A tranche is a set of small system accounts handed out to new developers
+//! by a distribution service, instead of airdropping each of them from the
+//! faucet. The keypairs are derived from a seed known to the service, which
+//! is given an index of the addresses along with the genesis.
+use {
+    crate::error::GenesisError,
+    serde::{Deserialize, Serialize},
+    solana_account::AccountSharedData,
+    solana_config_program::{create_config_account, ConfigKeys, ConfigState},
+    solana_genesis_config::GenesisConfig,
+    solana_keypair::{keypair_from_seed, Keypair},
+    solana_pubkey::Pubkey,
+    solana_sdk_ids::system_program,
+    solana_sha256_hasher::{hash, hashv},
+    solana_signer::Signer,
+    std::{fs::File, io, path::Path},
+};
+
+/// Default name of the index file, written into the ledger directory
+pub const AIRDROP_TRANCHE_INDEX_FILE: &str = "airdrop-tranche-index.json";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AirdropTrancheFile {
+    pub airdrop_tranche: AirdropTranche,
+}
+
+/// Parameters of a tranche, as read from the `airdrop_tranche` section of an
+/// airdrop tranche file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AirdropTranche {
+    /// Secret the keypairs of the tranche are derived from
+    pub seed: String,
+    pub count: u64,
+    pub lamports_each: u64,
+    /// Also create a config-program account holding the tranche parameters,
+    /// so that the tranche can be audited on-chain
+    #[serde(default)]
+    pub publish_parameters: bool,
+}
+
+impl AirdropTranche {
+    /// Keypair of the `index`th account of the tranche
+    pub fn keypair(&self, index: u64) -> Keypair {
+        derive_keypair(&self.seed, b"account", index)
+    }
+
+    /// Address of the config account holding the parameters of the tranche
+    pub fn parameters_address(&self) -> Pubkey {
+        derive_keypair(&self.seed, b"parameters", 0).pubkey()
+    }
+
+    /// Parameters published on-chain. The seed itself is kept secret and only
+    /// its hash is published.
+    pub fn parameters(&self) -> AirdropTrancheParameters {
+        AirdropTrancheParameters {
+            seed_commitment: hash(self.seed.as_bytes()).to_bytes(),
+            count: self.count,
+            lamports_each: self.lamports_each,
+        }
+    }
+}
+
+fn derive_keypair(seed: &str, role: &[u8], index: u64) -> Keypair {
+    let secret = hashv(&[
+        b"airdrop-tranche",
+        seed.as_bytes(),
+        role,
+        &index.to_le_bytes(),
+    ]);
+    keypair_from_seed(secret.as_ref()).unwrap()
+}
+
+/// Contents of the parameters config account
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct AirdropTrancheParameters {
+    /// SHA-256 of the seed of the tranche
+    pub seed_commitment: [u8; 32],
+    pub count: u64,
+    pub lamports_each: u64,
+}
+
+impl ConfigState for AirdropTrancheParameters {
+    fn max_space() -> u64 {
+        bincode::serialized_size(&Self::default()).unwrap()
+    }
+}
+
+/// Entry of the index file consumed by the distribution service
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct AirdropTrancheIndexEntry {
+    pub address: String,
+    pub index: u64,
+}
+
+/// Add the accounts of `tranche`, and its parameters account if requested, to
+/// `genesis_config`. Returns the index of the tranche accounts.
+pub fn add_airdrop_tranche(
+    genesis_config: &mut GenesisConfig,
+    tranche: &AirdropTranche,
+) -> Result<Vec<AirdropTrancheIndexEntry>, GenesisError> {
+    let conflict = |pubkey: Pubkey, what: &str| GenesisError::Conflict {
+        pubkey,
+        sources: vec![format!("airdrop tranche {what}"), "genesis".to_string()],
+    };
+    let rent_exempt_minimum = genesis_config.rent.minimum_balance(0);
+    if tranche.lamports_each < rent_exempt_minimum {
+        return Err(GenesisError::validation(
+            "airdrop tranche",
+            "lamports_each",
+            format_args!(
+                "{} is below the rent exempt minimum of {rent_exempt_minimum}",
+                tranche.lamports_each
+            ),
+        ));
+    }
+
+    let mut index = Vec::with_capacity(tranche.count as usize);
+    for nth in 0..tranche.count {
+        let address = tranche.keypair(nth).pubkey();
+        if genesis_config.accounts.contains_key(&address) {
+            return Err(conflict(address, "account"));
+        }
+        genesis_config.add_account(
+            address,
+            AccountSharedData::new(tranche.lamports_each, 0, &system_program::id()),
+        );
+        index.push(AirdropTrancheIndexEntry {
+            address: address.to_string(),
+            index: nth,
+        });
+    }
+
+    if tranche.publish_parameters {
+        let address = tranche.parameters_address();
+        if genesis_config.accounts.contains_key(&address) {
+            return Err(conflict(address, "parameters account"));
+        }
+        let space = ConfigKeys::serialized_size(vec![]) + AirdropTrancheParameters::max_space();
+        let lamports = genesis_config.rent.minimum_balance(space as usize);
+        genesis_config.add_account(
+            address,
+            create_config_account(vec![], &tranche.parameters(), lamports),
+        );
+    }
+
+    Ok(index)
+}
+
+/// Load the tranche described in the airdrop tranche file at `path`
+pub fn load_airdrop_tranche(path: &Path) -> Result<AirdropTranche, GenesisError> {
+    let file = File::open(path)?;
+    let tranche_file: AirdropTrancheFile =
+        serde_yaml::from_reader(file).map_err(|err| GenesisError::yaml(path.display(), &err))?;
+    Ok(tranche_file.airdrop_tranche)
+}
+
+pub fn write_airdrop_tranche_index(
+    path: &Path,
+    index: &[AirdropTrancheIndexEntry],
+) -> io::Result<()> {
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(file, index).map_err(io::Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, assert_matches::assert_matches, solana_account::ReadableAccount,
+        solana_config_program::get_config_data, solana_rent::Rent,
+    };
+
+    fn tranche(count: u64, publish_parameters: bool) -> AirdropTranche {
+        AirdropTranche {
+            seed: "devnet tranche 1".to_string(),
+            count,
+            lamports_each: 1_000_000_000,
+            publish_parameters,
+        }
+    }
+
+    #[test]
+    fn test_add_airdrop_tranche_index() {
+        let tranche = tranche(500, false);
+        let mut genesis_config = GenesisConfig::default();
+        let index = add_airdrop_tranche(&mut genesis_config, &tranche).unwrap();
+        assert_eq!(index.len(), 500);
+        assert_eq!(genesis_config.accounts.len(), 500);
+        assert_eq!(
+            genesis_config
+                .accounts
+                .values()
+                .map(|account| account.lamports)
+                .sum::<u64>(),
+            500 * tranche.lamports_each
+        );
+
+        let index_file = tempfile::NamedTempFile::new().unwrap();
+        write_airdrop_tranche_index(index_file.path(), &index).unwrap();
+        let index: Vec<AirdropTrancheIndexEntry> =
+            serde_json::from_reader(File::open(index_file.path()).unwrap()).unwrap();
+
+        for entry in index.iter().step_by(47) {
+            let address: Pubkey = entry.address.parse().unwrap();
+            assert_eq!(tranche.keypair(entry.index).pubkey(), address);
+            let account = &genesis_config.accounts[&address];
+            assert_eq!(account.lamports, tranche.lamports_each);
+            assert_eq!(account.owner, system_program::id());
+            assert!(account.data.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_add_airdrop_tranche_deterministic() {
+        let build = || {
+            let mut genesis_config = GenesisConfig::default();
+            let index = add_airdrop_tranche(&mut genesis_config, &tranche(100, true)).unwrap();
+            (genesis_config.accounts, index)
+        };
+        assert_eq!(build(), build());
+
+        let mut other_seed = tranche(100, true);
+        other_seed.seed.push('!');
+        let mut genesis_config = GenesisConfig::default();
+        let index = add_airdrop_tranche(&mut genesis_config, &other_seed).unwrap();
+        assert_ne!(index, build().1);
+    }
+
+    #[test]
+    fn test_add_airdrop_tranche_parameters_account() {
+        let tranche = tranche(10, true);
+        let mut genesis_config = GenesisConfig::default();
+        add_airdrop_tranche(&mut genesis_config, &tranche).unwrap();
+        assert_eq!(genesis_config.accounts.len(), 11);
+
+        let account =
+            AccountSharedData::from(genesis_config.accounts[&tranche.parameters_address()].clone());
+        assert_eq!(account.owner(), &solana_config_program::id());
+        assert_eq!(
+            account.lamports(),
+            Rent::default().minimum_balance(account.data().len())
+        );
+        let parameters: AirdropTrancheParameters =
+            bincode::deserialize(get_config_data(account.data()).unwrap()).unwrap();
+        assert_eq!(
+            parameters,
+            AirdropTrancheParameters {
+                seed_commitment: hash(b"devnet tranche 1").to_bytes(),
+                count: 10,
+                lamports_each: 1_000_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_add_airdrop_tranche_errors() {
+        let mut genesis_config = GenesisConfig::default();
+        let mut below_rent = tranche(1, false);
+        below_rent.lamports_each = 1;
+        assert_matches!(
+            add_airdrop_tranche(&mut genesis_config, &below_rent),
+            Err(GenesisError::Validation { field, .. }) if field == "lamports_each"
+        );
+
+        let tranche = tranche(2, false);
+        add_airdrop_tranche(&mut genesis_config, &tranche).unwrap();
+        assert_matches!(
+            add_airdrop_tranche(&mut genesis_config, &tranche),
+            Err(GenesisError::Conflict { pubkey, .. }) if pubkey == tranche.keypair(0).pubkey()
+        );
+    }
+
+    #[test]
+    fn test_load_airdrop_tranche() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        io::Write::write_all(
+            &mut file,
+            b"airdrop_tranche:\n  seed: devnet tranche 1\n  count: 10\n  lamports_each: 1000000000\n",
+        )
+        .unwrap();
+        assert_eq!(
+            load_airdrop_tranche(file.path()).unwrap(),
+            tranche(10, false)
+        );
+
+        io::Write::write_all(&mut file, b"  count: ten\n").unwrap();
+        assert_matches!(
+            load_airdrop_tranche(file.path()),
+            Err(GenesisError::Parse { .. })
+        );
+        assert_matches!(
+            load_airdrop_tranche(Path::new("unknownfile")),
+            Err(GenesisError::Io(_))
+        );
+    }
+}
@@ -0,0 +1,402 @@
+//! Amounts of lamports in the accounts files
+//!
+//! A balance or stake is either a plain integer of lamports, as it always
+//! was, or a string with a unit: `"1000 SOL"`, `"1.5 SOL"`, `"500000
+//! lamports"`. A stake may also be a percentage of the `total_stake_pool`
+//! declared by its file, e.g. `"2.5%"`.
+//!
+//! Amounts are converted to lamports with integer math, and must come to a
+//! whole number of lamports that fits in a `u64`.
+use {
+    serde::{de, Deserialize, Deserializer},
+    solana_native_token::LAMPORTS_PER_SOL,
+    std::fmt,
+    thiserror::Error,
+};
+
+const EXPECTED: &str = r#"an amount such as 1000, "1.5 SOL", "500000 lamports" or "2.5%""#;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AmountError {
+    #[error("invalid amount {0:?}, expected {EXPECTED}")]
+    Malformed(String),
+
+    #[error("{0:?} is not a whole number of lamports")]
+    FractionalLamports(String),
+
+    #[error("{0:?} exceeds the maximum of {max} lamports", max = u64::MAX)]
+    Overflow(String),
+
+    #[error("{0:?} is a percentage, which is only accepted for stakes of a total_stake_pool")]
+    UnexpectedPercentage(String),
+}
+
+/// An amount as written in an accounts file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Amount {
+    Lamports(u64),
+    /// Percentage of the stake pool of the file, still to be resolved by
+    /// `Amount::lamports()`
+    Percentage(Decimal),
+}
+
+impl Amount {
+    /// The lamports of the amount, resolving a percentage against
+    /// `total_stake_pool`
+    pub fn lamports(self, total_stake_pool: Option<u64>) -> Result<u64, AmountError> {
+        match (self, total_stake_pool) {
+            (Self::Lamports(lamports), _) => Ok(lamports),
+            (Self::Percentage(percentage), Some(total_stake_pool)) => {
+                percentage.mul_div_exact(u128::from(total_stake_pool), 100)
+            }
+            (Self::Percentage(percentage), None) => {
+                Err(AmountError::UnexpectedPercentage(format!("{percentage}%")))
+            }
+        }
+    }
+}
+
+/// A non-negative decimal number, `mantissa / 10^scale`, without trailing
+/// zeros in its fraction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Decimal {
+    mantissa: u128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Parse digits with an optional fraction, such as `12` or `0.25`
+    fn parse(number: &str) -> Option<Self> {
+        let (integer, fraction) = match number.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction.trim_end_matches('0')),
+            None => (number, ""),
+        };
+        let is_digits = |digits: &str| digits.bytes().all(|byte| byte.is_ascii_digit());
+        if integer.is_empty()
+            || !is_digits(integer)
+            || !is_digits(fraction)
+            || number.ends_with('.')
+        {
+            return None;
+        }
+        // Digits past the range of a u128 are a value too large or too
+        // precise for any amount, saturate and let the conversion reject it
+        let mantissa = integer
+            .bytes()
+            .chain(fraction.bytes())
+            .try_fold(0u128, |mantissa, digit| {
+                mantissa
+                    .checked_mul(10)?
+                    .checked_add(u128::from(digit - b'0'))
+            })
+            .unwrap_or(u128::MAX);
+        Some(Self {
+            mantissa,
+            scale: fraction.len() as u32,
+        })
+    }
+
+    /// `self * numerator / denominator`, as long as it is a whole number
+    /// fitting in a u64
+    fn mul_div_exact(self, numerator: u128, denominator: u128) -> Result<u64, AmountError> {
+        let overflow = || AmountError::Overflow(self.to_string());
+        let fractional = || AmountError::FractionalLamports(self.to_string());
+        if self.mantissa == u128::MAX {
+            return Err(overflow());
+        }
+        let dividend = self.mantissa.checked_mul(numerator).ok_or_else(overflow)?;
+        // 10^scale past the range of a u128 leaves a non-zero digit far below
+        // a lamport
+        let divisor = 10u128
+            .checked_pow(self.scale)
+            .and_then(|power| power.checked_mul(denominator))
+            .ok_or_else(fractional)?;
+        if dividend % divisor != 0 {
+            return Err(fractional());
+        }
+        u64::try_from(dividend / divisor).map_err(|_| overflow())
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.mantissa.to_string();
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{integer}.{fraction}")
+    }
+}
+
+/// Parse an amount of an accounts file: plain integer lamports, or a number
+/// followed by `SOL`, `lamports` or `%`
+pub fn parse_amount(amount: &str) -> Result<Amount, AmountError> {
+    let malformed = || AmountError::Malformed(amount.to_string());
+    let trimmed = amount.trim();
+    let unit_start = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(unit_start);
+    let decimal = Decimal::parse(number).ok_or_else(malformed)?;
+    let convert = |lamports_per_unit: u64| {
+        decimal
+            .mul_div_exact(u128::from(lamports_per_unit), 1)
+            .map(Amount::Lamports)
+            .map_err(|err| match err {
+                AmountError::FractionalLamports(_) => {
+                    AmountError::FractionalLamports(amount.to_string())
+                }
+                _ => AmountError::Overflow(amount.to_string()),
+            })
+    };
+    match unit.trim_start() {
+        // a plain number keeps meaning lamports
+        "" if decimal.scale == 0 && !number.contains('.') => convert(1),
+        "" => Err(malformed()),
+        unit if unit.eq_ignore_ascii_case("sol") => convert(LAMPORTS_PER_SOL),
+        unit if unit.eq_ignore_ascii_case("lamports") || unit.eq_ignore_ascii_case("lamport") => {
+            convert(1)
+        }
+        "%" => Ok(Amount::Percentage(decimal)),
+        _ => Err(malformed()),
+    }
+}
+
+/// Parse an amount that cannot be a percentage, into lamports
+pub fn parse_lamports(amount: &str) -> Result<u64, AmountError> {
+    parse_amount(amount)?.lamports(None)
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountVisitor;
+
+        impl de::Visitor<'_> for AmountVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(EXPECTED)
+            }
+
+            fn visit_u64<E: de::Error>(self, lamports: u64) -> Result<Amount, E> {
+                Ok(Amount::Lamports(lamports))
+            }
+
+            fn visit_i64<E: de::Error>(self, lamports: i64) -> Result<Amount, E> {
+                u64::try_from(lamports)
+                    .map(Amount::Lamports)
+                    .map_err(|_| E::custom(AmountError::Malformed(lamports.to_string())))
+            }
+
+            fn visit_str<E: de::Error>(self, amount: &str) -> Result<Amount, E> {
+                parse_amount(amount).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+/// Deserialize an amount that cannot be a percentage, into lamports
+pub fn deserialize_lamports<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    Amount::deserialize(deserializer)?
+        .lamports(None)
+        .map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, assert_matches::assert_matches};
+
+    fn lamports(amount: &str) -> Result<u64, AmountError> {
+        parse_lamports(amount)
+    }
+
+    fn percentage_of(amount: &str, total_stake_pool: u64) -> Result<u64, AmountError> {
+        let amount = parse_amount(amount)?;
+        assert_matches!(amount, Amount::Percentage(_));
+        amount.lamports(Some(total_stake_pool))
+    }
+
+    #[test]
+    fn test_plain_lamports() {
+        assert_eq!(lamports("0"), Ok(0));
+        assert_eq!(lamports("1000"), Ok(1000));
+        assert_eq!(lamports(" 42 "), Ok(42));
+        assert_eq!(lamports("007"), Ok(7));
+        assert_eq!(lamports("18446744073709551615"), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn test_sol() {
+        assert_eq!(lamports("1000 SOL"), Ok(1_000 * LAMPORTS_PER_SOL));
+        assert_eq!(lamports("1.5 SOL"), Ok(1_500_000_000));
+        assert_eq!(lamports("0.000000001 SOL"), Ok(1));
+        assert_eq!(lamports("0 SOL"), Ok(0));
+        // the unit is case insensitive, and the space optional
+        assert_eq!(lamports("2sol"), Ok(2 * LAMPORTS_PER_SOL));
+        assert_eq!(lamports("2 Sol"), Ok(2 * LAMPORTS_PER_SOL));
+        // trailing zeros do not make an amount fractional
+        assert_eq!(
+            lamports("1.000000000000000000000000000000000000000000 SOL"),
+            Ok(LAMPORTS_PER_SOL)
+        );
+        assert_eq!(lamports("0.1000000000 SOL"), Ok(100_000_000));
+    }
+
+    #[test]
+    fn test_lamports_unit() {
+        assert_eq!(lamports("500000 lamports"), Ok(500_000));
+        assert_eq!(lamports("1 lamport"), Ok(1));
+        assert_eq!(lamports("3 LAMPORTS"), Ok(3));
+        assert_eq!(lamports("12.0 lamports"), Ok(12));
+    }
+
+    #[test]
+    fn test_fractional_lamports() {
+        assert_eq!(
+            lamports("0.0000000001 SOL"),
+            Err(AmountError::FractionalLamports(
+                "0.0000000001 SOL".to_string()
+            ))
+        );
+        assert_matches!(
+            lamports("1.0000000015 SOL"),
+            Err(AmountError::FractionalLamports(_))
+        );
+        assert_matches!(
+            lamports("1.5 lamports"),
+            Err(AmountError::FractionalLamports(_))
+        );
+        // a non-zero digit beyond the precision of the math is still fractional
+        let tiny = format!("0.{}1 SOL", "0".repeat(60));
+        assert_matches!(lamports(&tiny), Err(AmountError::FractionalLamports(_)));
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(
+            lamports("18446744073709551616"),
+            Err(AmountError::Overflow("18446744073709551616".to_string()))
+        );
+        // u64::MAX lamports is a little over 18.4 billion SOL
+        assert_eq!(lamports("18446744073.709551615 SOL"), Ok(u64::MAX));
+        assert_matches!(
+            lamports("18446744073.709551616 SOL"),
+            Err(AmountError::Overflow(_))
+        );
+        assert_matches!(lamports("20000000000 SOL"), Err(AmountError::Overflow(_)));
+        // past the range of the intermediate math too
+        assert_matches!(lamports(&"9".repeat(50)), Err(AmountError::Overflow(_)));
+        assert_matches!(
+            lamports(&format!("{} SOL", "9".repeat(35))),
+            Err(AmountError::Overflow(_))
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        for amount in [
+            "",
+            " ",
+            "SOL",
+            "-5",
+            "-5 SOL",
+            "+5",
+            "1.5",
+            "1.",
+            ".5 SOL",
+            "1..5 SOL",
+            "1.5.0 SOL",
+            "1e9",
+            "1,000 SOL",
+            "1_000",
+            "10 sols",
+            "10 SOL SOL",
+            "10 BTC",
+            "SOL 10",
+            "5 %%",
+            "%",
+        ] {
+            assert_eq!(
+                parse_amount(amount),
+                Err(AmountError::Malformed(amount.to_string())),
+                "{amount:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_percentage() {
+        assert_eq!(percentage_of("2.5%", 1_000), Ok(25));
+        assert_eq!(percentage_of("2.5 %", 1_000), Ok(25));
+        assert_eq!(percentage_of("100%", 1_000), Ok(1_000));
+        assert_eq!(percentage_of("0%", 1_000), Ok(0));
+        assert_eq!(percentage_of("33.333%", 1_000_000), Ok(333_330));
+        assert_eq!(percentage_of("100%", u64::MAX), Ok(u64::MAX));
+        // a share that is not a whole number of lamports
+        assert_matches!(
+            percentage_of("33.3333%", 1_000),
+            Err(AmountError::FractionalLamports(_))
+        );
+        assert_matches!(
+            percentage_of("200%", u64::MAX),
+            Err(AmountError::Overflow(_))
+        );
+
+        // only accepted against a stake pool
+        assert_eq!(
+            lamports("2.5%"),
+            Err(AmountError::UnexpectedPercentage("2.5%".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let amounts: Vec<Amount> =
+            serde_yaml::from_str("- 1000\n- 1.5 SOL\n- \"500000 lamports\"\n- 2.5%\n").unwrap();
+        assert_eq!(
+            amounts[..3],
+            [
+                Amount::Lamports(1_000),
+                Amount::Lamports(1_500_000_000),
+                Amount::Lamports(500_000)
+            ]
+        );
+        assert_eq!(amounts[3].lamports(Some(1_000)), Ok(25));
+
+        // a float, a negative or a fractional amount are rejected
+        for yaml in ["1.5", "-1", "0.0000000001 SOL"] {
+            assert!(serde_yaml::from_str::<Amount>(yaml).is_err(), "{yaml}");
+        }
+
+        #[derive(Deserialize)]
+        struct Balance {
+            #[serde(deserialize_with = "deserialize_lamports")]
+            balance: u64,
+        }
+        let balance: Balance = serde_yaml::from_str("balance: 2 SOL").unwrap();
+        assert_eq!(balance.balance, 2 * LAMPORTS_PER_SOL);
+        let err = serde_yaml::from_str::<Balance>("balance: 10%").unwrap_err();
+        assert!(
+            err.to_string().contains("only accepted for stakes"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_decimal_display() {
+        for (decimal, expected) in [
+            ("2.5", "2.5"),
+            ("0.001", "0.001"),
+            ("12", "12"),
+            ("1.500", "1.5"),
+            ("0.0", "0"),
+        ] {
+            assert_eq!(Decimal::parse(decimal).unwrap().to_string(), expected);
+        }
+    }
+}
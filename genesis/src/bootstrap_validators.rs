@@ -0,0 +1,408 @@
+//! Generation of bootstrap validators for multi-node local clusters, and
+//! loading of bootstrap validator sets from stake CSVs
+use {
+    crate::{
+        address_generator::AddressGenerator, amount::parse_lamports, error::GenesisError,
+        StakedValidatorAccountInfo, ValidatorAccountsFile,
+    },
+    csv::{ReaderBuilder, Trim},
+    solana_keypair::{keypair_from_seed, write_keypair_file, Keypair},
+    solana_pubkey::Pubkey,
+    solana_sha256_hasher::hashv,
+    solana_signer::Signer,
+    solana_stake_interface as stake,
+    std::{
+        collections::HashSet,
+        fmt::Display,
+        fs::{self, File},
+        io,
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+};
+
+/// Keypair file names, as expected by the multinode-demo scripts
+pub const IDENTITY_KEYPAIR_FILE: &str = "identity.json";
+pub const VOTE_ACCOUNT_KEYPAIR_FILE: &str = "vote-account.json";
+pub const STAKE_ACCOUNT_KEYPAIR_FILE: &str = "stake-account.json";
+
+/// A bootstrap validator generated by `generate_bootstrap_validators()`
+pub struct GeneratedValidator {
+    pub identity: Keypair,
+    pub vote_account: Keypair,
+    pub stake_account: Keypair,
+    pub balance_lamports: u64,
+    pub stake_lamports: u64,
+}
+
+impl GeneratedValidator {
+    pub fn account_info(&self) -> StakedValidatorAccountInfo {
+        StakedValidatorAccountInfo {
+            balance_lamports: self.balance_lamports,
+            stake_lamports: self.stake_lamports,
+            identity_account: self.identity.pubkey().to_string(),
+            vote_account: self.vote_account.pubkey().to_string(),
+            stake_account: self.stake_account.pubkey().to_string(),
+            stake_accounts: vec![],
+        }
+    }
+
+    /// Write the identity, vote and stake keypairs into `dir`, creating it if needed
+    pub fn write_keypairs(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for (keypair, file_name) in [
+            (&self.identity, IDENTITY_KEYPAIR_FILE),
+            (&self.vote_account, VOTE_ACCOUNT_KEYPAIR_FILE),
+            (&self.stake_account, STAKE_ACCOUNT_KEYPAIR_FILE),
+        ] {
+            let path = dir.join(file_name);
+            write_keypair_file(keypair, &path).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Failed to write {}: {err}", path.display()),
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Directory holding the keypairs of the `nth` generated validator
+pub fn bootstrap_validator_keypairs_dir(output_dir: &Path, nth: usize) -> PathBuf {
+    output_dir.join(format!("bootstrap-validator-{nth}"))
+}
+
+/// Split `total_stake_lamports` evenly across `count` validators, giving the
+/// remainder to the first one
+pub fn split_stake(total_stake_lamports: u64, count: usize) -> Vec<u64> {
+    if count == 0 {
+        return vec![];
+    }
+    let share = total_stake_lamports / count as u64;
+    let remainder = total_stake_lamports % count as u64;
+    let mut stakes = vec![share; count];
+    stakes[0] += remainder;
+    stakes
+}
+
+pub(crate) fn derive_keypair(seed: u64, nth: usize, role: &str) -> Keypair {
+    let secret = hashv(&[
+        b"bootstrap-validator",
+        &seed.to_le_bytes(),
+        &(nth as u64).to_le_bytes(),
+        role.as_bytes(),
+    ]);
+    keypair_from_seed(secret.as_ref()).unwrap()
+}
+
+/// Deterministically generate `count` validators from `seed`, each funded with
+/// `balance_lamports` and staked with an even share of `total_stake_lamports`.
+///
+/// The keypairs are derived from `seed` alone, so they are only suitable for
+/// development clusters.
+pub fn generate_bootstrap_validators(
+    count: usize,
+    total_stake_lamports: u64,
+    balance_lamports: u64,
+    seed: u64,
+) -> Vec<GeneratedValidator> {
+    split_stake(total_stake_lamports, count)
+        .into_iter()
+        .enumerate()
+        .map(|(nth, stake_lamports)| GeneratedValidator {
+            identity: derive_keypair(seed, nth, "identity"),
+            vote_account: derive_keypair(seed, nth, "vote-account"),
+            stake_account: derive_keypair(seed, nth, "stake-account"),
+            balance_lamports,
+            stake_lamports,
+        })
+        .collect()
+}
+
+/// Write the keypairs of every validator into its own directory under `output_dir`
+pub fn write_bootstrap_validator_keypairs(
+    validators: &[GeneratedValidator],
+    output_dir: &Path,
+) -> io::Result<()> {
+    validators
+        .iter()
+        .enumerate()
+        .try_for_each(|(nth, validator)| {
+            validator.write_keypairs(&bootstrap_validator_keypairs_dir(output_dir, nth))
+        })
+}
+
+/// Columns of a validator stake CSV, which may be followed by a
+/// `stake_account` column
+pub const VALIDATOR_STAKE_CSV_COLUMNS: [&str; 4] = ["identity", "vote", "stake", "balance"];
+const STAKE_ACCOUNT_COLUMN: &str = "stake_account";
+
+/// Load the validator set of the stake CSV at `path`, see
+/// `parse_validator_stake_csv()`
+pub fn load_validator_stake_csv(path: &Path) -> Result<ValidatorAccountsFile, GenesisError> {
+    parse_validator_stake_csv(File::open(path)?, path.display())
+}
+
+/// Parse a CSV of `identity,vote,stake,balance` lines, the stake and balance
+/// being in lamports, into a validator set.
+///
+/// Validators without a `stake_account` are given the first address derived
+/// from their identity for the stake program, so that loading the same CSV
+/// always yields the same stake accounts.
+pub fn parse_validator_stake_csv(
+    reader: impl io::Read,
+    file: impl Display,
+) -> Result<ValidatorAccountsFile, GenesisError> {
+    let parse_error = |line: u64, cause: String| GenesisError::Parse {
+        file: file.to_string(),
+        entry: format!("line {line}"),
+        cause,
+    };
+    let mut reader = ReaderBuilder::new().trim(Trim::All).from_reader(reader);
+    let header = reader
+        .headers()
+        .map_err(|err| parse_error(1, err.to_string()))?
+        .clone();
+    let has_stake_account_column = match header.iter().collect::<Vec<_>>().as_slice() {
+        [columns @ .., STAKE_ACCOUNT_COLUMN] if columns == VALIDATOR_STAKE_CSV_COLUMNS => true,
+        columns if columns == VALIDATOR_STAKE_CSV_COLUMNS => false,
+        _ => {
+            return Err(parse_error(
+                1,
+                format!(
+                    "expected the header {}[,{STAKE_ACCOUNT_COLUMN}], found {}",
+                    VALIDATOR_STAKE_CSV_COLUMNS.join(","),
+                    header.iter().collect::<Vec<_>>().join(","),
+                ),
+            ))
+        }
+    };
+
+    let mut identities = HashSet::new();
+    let mut validator_accounts = vec![];
+    for record in reader.records() {
+        let record = record.map_err(|err| {
+            parse_error(
+                err.position()
+                    .map(|position| position.line())
+                    .unwrap_or_default(),
+                err.to_string(),
+            )
+        })?;
+        let line = record
+            .position()
+            .map(|position| position.line())
+            .unwrap_or_default();
+        let pubkey = |column: usize| {
+            Pubkey::from_str(&record[column]).map_err(|err| {
+                parse_error(
+                    line,
+                    format!("invalid {} {}: {err}", &header[column], &record[column]),
+                )
+            })
+        };
+        let lamports = |column: usize| {
+            parse_lamports(&record[column])
+                .map_err(|err| parse_error(line, format!("invalid {}: {err}", &header[column])))
+        };
+
+        let identity = pubkey(0)?;
+        let vote_account = pubkey(1)?;
+        let stake_lamports = lamports(2)?;
+        let balance_lamports = lamports(3)?;
+        if !identities.insert(identity) {
+            return Err(parse_error(line, format!("duplicate identity {identity}")));
+        }
+        let stake_account = if has_stake_account_column && !record[4].is_empty() {
+            pubkey(4)?
+        } else {
+            AddressGenerator::new(&identity, &stake::program::id()).nth(0)
+        };
+
+        validator_accounts.push(StakedValidatorAccountInfo {
+            balance_lamports,
+            stake_lamports,
+            identity_account: identity.to_string(),
+            vote_account: vote_account.to_string(),
+            stake_account: stake_account.to_string(),
+            stake_accounts: vec![],
+        });
+    }
+
+    Ok(ValidatorAccountsFile { validator_accounts })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, assert_matches::assert_matches, solana_keypair::read_keypair_file,
+        tempfile::TempDir,
+    };
+
+    #[test]
+    fn test_split_stake() {
+        assert!(split_stake(100, 0).is_empty());
+        assert_eq!(split_stake(100, 1), vec![100]);
+        assert_eq!(split_stake(100, 4), vec![25; 4]);
+        assert_eq!(split_stake(101, 3), vec![35, 33, 33]);
+        assert_eq!(split_stake(2, 3), vec![2, 0, 0]);
+    }
+
+    #[test]
+    fn test_generate_bootstrap_validators_is_deterministic() {
+        let pubkeys = |seed| -> Vec<_> {
+            generate_bootstrap_validators(3, 300, 10, seed)
+                .iter()
+                .map(GeneratedValidator::account_info)
+                .collect()
+        };
+        assert_eq!(pubkeys(7), pubkeys(7));
+        assert_ne!(pubkeys(7), pubkeys(8));
+
+        let unique: HashSet<_> = pubkeys(7)
+            .into_iter()
+            .flat_map(|info| [info.identity_account, info.vote_account, info.stake_account])
+            .collect();
+        assert_eq!(unique.len(), 9);
+    }
+
+    #[test]
+    fn test_write_bootstrap_validator_keypairs() {
+        let validators = generate_bootstrap_validators(2, 200, 10, 0);
+        let dir = TempDir::new().unwrap();
+        write_bootstrap_validator_keypairs(&validators, dir.path()).unwrap();
+
+        for (nth, validator) in validators.iter().enumerate() {
+            let keypairs_dir = bootstrap_validator_keypairs_dir(dir.path(), nth);
+            for (keypair, file_name) in [
+                (&validator.identity, IDENTITY_KEYPAIR_FILE),
+                (&validator.vote_account, VOTE_ACCOUNT_KEYPAIR_FILE),
+                (&validator.stake_account, STAKE_ACCOUNT_KEYPAIR_FILE),
+            ] {
+                let read = read_keypair_file(keypairs_dir.join(file_name)).unwrap();
+                assert_eq!(read.pubkey(), keypair.pubkey());
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_validator_stake_csv() {
+        let validators: Vec<_> = (0..3)
+            .map(|_| {
+                (
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                )
+            })
+            .collect();
+        let info = |nth: usize, stake_account: Pubkey, stake_lamports, balance_lamports| {
+            StakedValidatorAccountInfo {
+                balance_lamports,
+                stake_lamports,
+                identity_account: validators[nth].0.to_string(),
+                vote_account: validators[nth].1.to_string(),
+                stake_account: stake_account.to_string(),
+                stake_accounts: vec![],
+            }
+        };
+        let generated_stake_account =
+            |nth: usize| AddressGenerator::new(&validators[nth].0, &stake::program::id()).nth(0);
+
+        let csv = format!(
+            "identity,vote,stake,balance\n{},{},1000,500\n {} , {} , 2000 , 0 \n",
+            validators[0].0, validators[0].1, validators[1].0, validators[1].1,
+        );
+        assert_eq!(
+            parse_validator_stake_csv(csv.as_bytes(), "stakes.csv")
+                .unwrap()
+                .validator_accounts,
+            vec![
+                info(0, generated_stake_account(0), 1000, 500),
+                info(1, generated_stake_account(1), 2000, 0),
+            ]
+        );
+
+        let csv = format!(
+            "identity,vote,stake,balance,stake_account\n{},{},1000,500,{}\n{},{},3000,7,\n",
+            validators[0].0, validators[0].1, validators[0].2, validators[2].0, validators[2].1,
+        );
+        assert_eq!(
+            parse_validator_stake_csv(csv.as_bytes(), "stakes.csv")
+                .unwrap()
+                .validator_accounts,
+            vec![
+                info(0, validators[0].2, 1000, 500),
+                info(2, generated_stake_account(2), 3000, 7),
+            ]
+        );
+
+        // amounts with a unit
+        let csv = format!(
+            "identity,vote,stake,balance\n{},{},2.5 SOL,500 lamports\n",
+            validators[0].0, validators[0].1,
+        );
+        assert_eq!(
+            parse_validator_stake_csv(csv.as_bytes(), "stakes.csv")
+                .unwrap()
+                .validator_accounts,
+            vec![info(0, generated_stake_account(0), 2_500_000_000, 500)]
+        );
+    }
+
+    #[test]
+    fn test_parse_validator_stake_csv_errors() {
+        let identity = Pubkey::new_unique();
+        let vote = Pubkey::new_unique();
+        let parse_error_line = |csv: String| match parse_validator_stake_csv(csv.as_bytes(), "x") {
+            Err(GenesisError::Parse { entry, .. }) => entry,
+            result => panic!("expected a parse error, got {result:?}"),
+        };
+
+        assert_eq!(
+            parse_error_line(format!(
+                "identity,vote,balance,stake\n{identity},{vote},1,2\n"
+            )),
+            "line 1"
+        );
+        assert_eq!(
+            parse_error_line(format!(
+                "identity,vote,stake,balance\n{identity},{vote},1,2\n{identity},{vote},ten,2\n"
+            )),
+            "line 3"
+        );
+        assert_eq!(
+            parse_error_line(format!(
+                "identity,vote,stake,balance\n{identity},{vote},1,-2\n"
+            )),
+            "line 2"
+        );
+        // no pool to take a percentage of
+        assert_eq!(
+            parse_error_line(format!(
+                "identity,vote,stake,balance\n{identity},{vote},10%,2\n"
+            )),
+            "line 2"
+        );
+        assert_eq!(
+            parse_error_line(format!("identity,vote,stake,balance\nfoo,{vote},1,2\n")),
+            "line 2"
+        );
+        assert_eq!(
+            parse_error_line(format!(
+                "identity,vote,stake,balance\n{identity},{vote},1\n"
+            )),
+            "line 2"
+        );
+        assert_eq!(
+            parse_error_line(format!(
+                "identity,vote,stake,balance\n{identity},{vote},1,2\n{identity},{vote},3,4\n"
+            )),
+            "line 3"
+        );
+        assert_matches!(
+            load_validator_stake_csv(Path::new("unknownfile")),
+            Err(GenesisError::Io(_))
+        );
+    }
+}
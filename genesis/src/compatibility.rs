@@ -0,0 +1,345 @@
+//! Check an existing genesis config against what a validator version expects
+//!
+//! Each check is one entry of `CHECKS`, with an optional fix for the issues
+//! that can be patched without changing the meaning of the genesis config.
+use {
+    solana_epoch_schedule::{EpochSchedule, MINIMUM_SLOTS_PER_EPOCH},
+    solana_genesis_config::GenesisConfig,
+    solana_pubkey::Pubkey,
+    solana_runtime::genesis_utils::activate_feature,
+    std::fmt,
+};
+
+/// What the target validator expects from a genesis config
+#[derive(Debug, Default, Clone)]
+pub struct ValidatorTarget {
+    /// Features the validator requires to be activated at genesis
+    pub required_features: Vec<Pubkey>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The validator starts, but may not behave as expected
+    Warning,
+    /// The validator fails to start
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+type Detect = fn(&GenesisConfig, &ValidatorTarget) -> Option<String>;
+type Fix = fn(&mut GenesisConfig, &ValidatorTarget);
+
+struct Check {
+    name: &'static str,
+    severity: Severity,
+    detect: Detect,
+    fix: Option<Fix>,
+}
+
+const CHECKS: &[Check] = &[
+    Check {
+        name: "epoch-schedule-too-short",
+        severity: Severity::Error,
+        detect: detect_epoch_schedule_too_short,
+        fix: Some(fix_epoch_schedule_too_short),
+    },
+    Check {
+        name: "epoch-schedule-warmup",
+        severity: Severity::Error,
+        detect: detect_epoch_schedule_warmup,
+        fix: Some(fix_epoch_schedule_warmup),
+    },
+    Check {
+        name: "missing-feature-accounts",
+        severity: Severity::Error,
+        detect: detect_missing_feature_accounts,
+        fix: Some(fix_missing_feature_accounts),
+    },
+    Check {
+        name: "rent-parameters",
+        severity: Severity::Error,
+        detect: detect_rent_parameters,
+        fix: None,
+    },
+];
+
+/// An incompatibility found by `check()`
+pub struct CompatibilityIssue {
+    pub check: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    fix: Option<Fix>,
+}
+
+impl CompatibilityIssue {
+    pub fn is_fixable(&self) -> bool {
+        self.fix.is_some()
+    }
+
+    /// Patch `genesis_config` to resolve this issue.
+    /// Returns false if the issue has no safe fix.
+    pub fn fix(&self, genesis_config: &mut GenesisConfig, target: &ValidatorTarget) -> bool {
+        let Some(fix) = self.fix else {
+            return false;
+        };
+        fix(genesis_config, target);
+        true
+    }
+}
+
+impl fmt::Debug for CompatibilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CompatibilityIssue")
+            .field("check", &self.check)
+            .field("severity", &self.severity)
+            .field("message", &self.message)
+            .field("fixable", &self.is_fixable())
+            .finish()
+    }
+}
+
+impl fmt::Display for CompatibilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} [{}]: {}", self.severity, self.check, self.message)?;
+        if self.is_fixable() {
+            write!(f, " (fixable with --fix)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Detect the known incompatibilities of `genesis_config` with `target`
+pub fn check(genesis_config: &GenesisConfig, target: &ValidatorTarget) -> Vec<CompatibilityIssue> {
+    CHECKS
+        .iter()
+        .filter_map(|check| {
+            (check.detect)(genesis_config, target).map(|message| CompatibilityIssue {
+                check: check.name,
+                severity: check.severity,
+                message,
+                fix: check.fix,
+            })
+        })
+        .collect()
+}
+
+/// Apply the fixes of all fixable issues of `genesis_config`.
+/// Returns the issues remaining afterwards.
+pub fn fix(
+    genesis_config: &mut GenesisConfig,
+    target: &ValidatorTarget,
+) -> Vec<CompatibilityIssue> {
+    for issue in check(genesis_config, target) {
+        issue.fix(genesis_config, target);
+    }
+    check(genesis_config, target)
+}
+
+fn detect_epoch_schedule_too_short(
+    genesis_config: &GenesisConfig,
+    _target: &ValidatorTarget,
+) -> Option<String> {
+    let slots_per_epoch = genesis_config.epoch_schedule.slots_per_epoch;
+    (slots_per_epoch < MINIMUM_SLOTS_PER_EPOCH).then(|| {
+        format!(
+            "{slots_per_epoch} slots per epoch is below the minimum of {MINIMUM_SLOTS_PER_EPOCH}"
+        )
+    })
+}
+
+fn fix_epoch_schedule_too_short(genesis_config: &mut GenesisConfig, _target: &ValidatorTarget) {
+    genesis_config.epoch_schedule = EpochSchedule::custom(
+        MINIMUM_SLOTS_PER_EPOCH,
+        MINIMUM_SLOTS_PER_EPOCH,
+        genesis_config.epoch_schedule.warmup,
+    );
+}
+
+/// The epoch schedule as computed by the current code from the stored parameters
+fn expected_epoch_schedule(epoch_schedule: &EpochSchedule) -> Option<EpochSchedule> {
+    (epoch_schedule.slots_per_epoch >= MINIMUM_SLOTS_PER_EPOCH).then(|| {
+        EpochSchedule::custom(
+            epoch_schedule.slots_per_epoch,
+            epoch_schedule.leader_schedule_slot_offset,
+            epoch_schedule.warmup,
+        )
+    })
+}
+
+fn detect_epoch_schedule_warmup(
+    genesis_config: &GenesisConfig,
+    _target: &ValidatorTarget,
+) -> Option<String> {
+    let epoch_schedule = &genesis_config.epoch_schedule;
+    let expected = expected_epoch_schedule(epoch_schedule)?;
+    (expected != *epoch_schedule).then(|| {
+        format!(
+            "warmup ends at epoch {} (slot {}), expected epoch {} (slot {})",
+            epoch_schedule.first_normal_epoch,
+            epoch_schedule.first_normal_slot,
+            expected.first_normal_epoch,
+            expected.first_normal_slot,
+        )
+    })
+}
+
+fn fix_epoch_schedule_warmup(genesis_config: &mut GenesisConfig, _target: &ValidatorTarget) {
+    if let Some(expected) = expected_epoch_schedule(&genesis_config.epoch_schedule) {
+        genesis_config.epoch_schedule = expected;
+    }
+}
+
+fn missing_features<'a>(
+    genesis_config: &'a GenesisConfig,
+    target: &'a ValidatorTarget,
+) -> impl Iterator<Item = &'a Pubkey> {
+    target
+        .required_features
+        .iter()
+        .filter(|feature_id| !genesis_config.accounts.contains_key(feature_id))
+}
+
+fn detect_missing_feature_accounts(
+    genesis_config: &GenesisConfig,
+    target: &ValidatorTarget,
+) -> Option<String> {
+    let missing: Vec<_> = missing_features(genesis_config, target)
+        .map(ToString::to_string)
+        .collect();
+    (!missing.is_empty()).then(|| format!("missing feature accounts: {}", missing.join(", ")))
+}
+
+fn fix_missing_feature_accounts(genesis_config: &mut GenesisConfig, target: &ValidatorTarget) {
+    let missing: Vec<_> = missing_features(genesis_config, target).copied().collect();
+    for feature_id in missing {
+        activate_feature(genesis_config, feature_id);
+    }
+}
+
+fn detect_rent_parameters(
+    genesis_config: &GenesisConfig,
+    _target: &ValidatorTarget,
+) -> Option<String> {
+    let rent = &genesis_config.rent;
+    let mut problems = vec![];
+    if rent.lamports_per_byte_year == 0 {
+        problems.push("lamports per byte-year must be greater than 0".to_string());
+    }
+    if !rent.exemption_threshold.is_finite() || rent.exemption_threshold <= 0.0 {
+        problems.push(format!(
+            "exemption threshold {} must be a positive number",
+            rent.exemption_threshold
+        ));
+    }
+    if rent.burn_percent > 100 {
+        problems.push(format!("burn percentage {} exceeds 100", rent.burn_percent));
+    }
+    (!problems.is_empty()).then(|| problems.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_rent::Rent};
+
+    fn check_names(genesis_config: &GenesisConfig, target: &ValidatorTarget) -> Vec<&'static str> {
+        check(genesis_config, target)
+            .iter()
+            .map(|issue| issue.check)
+            .collect()
+    }
+
+    #[test]
+    fn test_default_genesis_is_compatible() {
+        assert!(check(&GenesisConfig::default(), &ValidatorTarget::default()).is_empty());
+    }
+
+    #[test]
+    fn test_epoch_schedule_too_short() {
+        let mut genesis_config = GenesisConfig::default();
+        genesis_config.epoch_schedule.slots_per_epoch = 8;
+        let target = ValidatorTarget::default();
+        assert_eq!(
+            check_names(&genesis_config, &target),
+            ["epoch-schedule-too-short"]
+        );
+
+        assert!(fix(&mut genesis_config, &target).is_empty());
+        assert_eq!(
+            genesis_config.epoch_schedule.slots_per_epoch,
+            MINIMUM_SLOTS_PER_EPOCH
+        );
+    }
+
+    #[test]
+    fn test_epoch_schedule_warmup() {
+        let mut genesis_config = GenesisConfig {
+            epoch_schedule: EpochSchedule::custom(8192, 8192, true),
+            ..GenesisConfig::default()
+        };
+        // as written by code that computed the end of warmup differently
+        genesis_config.epoch_schedule.first_normal_epoch += 1;
+        let target = ValidatorTarget::default();
+        assert_eq!(
+            check_names(&genesis_config, &target),
+            ["epoch-schedule-warmup"]
+        );
+
+        assert!(fix(&mut genesis_config, &target).is_empty());
+        assert_eq!(
+            genesis_config.epoch_schedule,
+            EpochSchedule::custom(8192, 8192, true)
+        );
+    }
+
+    #[test]
+    fn test_missing_feature_accounts() {
+        let mut genesis_config = GenesisConfig::default();
+        let present = Pubkey::new_unique();
+        activate_feature(&mut genesis_config, present);
+        let missing = Pubkey::new_unique();
+        let target = ValidatorTarget {
+            required_features: vec![present, missing],
+        };
+        let issues = check(&genesis_config, &target);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].check, "missing-feature-accounts");
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert!(issues[0].message.contains(&missing.to_string()));
+        assert!(!issues[0].message.contains(&present.to_string()));
+
+        assert!(fix(&mut genesis_config, &target).is_empty());
+        assert!(genesis_config.accounts.contains_key(&missing));
+    }
+
+    #[test]
+    fn test_rent_parameters_are_not_fixable() {
+        let mut genesis_config = GenesisConfig {
+            rent: Rent {
+                lamports_per_byte_year: 0,
+                exemption_threshold: f64::NAN,
+                burn_percent: 101,
+            },
+            ..GenesisConfig::default()
+        };
+        let target = ValidatorTarget::default();
+        let issues = check(&genesis_config, &target);
+        assert_eq!(issues.len(), 1);
+        assert!(!issues[0].is_fixable());
+        assert!(!issues[0].fix(&mut genesis_config, &target));
+        assert_eq!(
+            issues[0].to_string(),
+            "error [rent-parameters]: lamports per byte-year must be greater than 0, exemption \
+             threshold NaN must be a positive number, burn percentage 101 exceeds 100"
+        );
+
+        assert_eq!(fix(&mut genesis_config, &target).len(), 1);
+    }
+}
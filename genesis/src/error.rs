@@ -0,0 +1,159 @@
+//! Errors building a genesis config
+use {
+    solana_pubkey::Pubkey,
+    std::{fmt::Display, io},
+    thiserror::Error,
+};
+
+#[derive(Debug, Error)]
+pub enum GenesisError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// An input file could not be parsed, `entry` locating the failure within it
+    #[error("failed to parse {file} at {entry}: {cause}")]
+    Parse {
+        file: String,
+        entry: String,
+        cause: String,
+    },
+
+    /// Rows of the input `file` are invalid, each of `errors` locating its row.
+    /// `aborted` when reading stopped at the error limit
+    #[error("invalid rows in {file}{}:\n{}", aborted_suffix(.aborted), .errors.join("\n"))]
+    InvalidRows {
+        file: String,
+        errors: Vec<String>,
+        aborted: bool,
+    },
+
+    /// `field` of `entry` holds a value that cannot be used
+    #[error("invalid {field} of {entry}: {reason}")]
+    Validation {
+        entry: String,
+        field: String,
+        reason: String,
+    },
+
+    /// The account at `pubkey` would be created by each of `sources`
+    #[error("account {pubkey} is created by both {}", .sources.join(" and "))]
+    Conflict {
+        pubkey: Pubkey,
+        sources: Vec<String>,
+    },
+
+    #[error("{which}: {actual} exceeds the limit of {limit}")]
+    LimitExceeded {
+        which: String,
+        limit: u64,
+        actual: u64,
+    },
+
+    /// Neither a single validator nor the bootstrap validators hold enough of
+    /// the stake of `epoch` to root a slot, `breakdown` listing the stake of
+    /// each validator
+    #[error(
+        "the genesis stake cannot root slot 1: at most {percent:.2}% of the stake of epoch \
+         {epoch} votes at first, not more than the {threshold_percent:.2}% required\n{breakdown}"
+    )]
+    Unrootable {
+        epoch: u64,
+        percent: f64,
+        threshold_percent: f64,
+        breakdown: String,
+    },
+}
+
+fn aborted_suffix(aborted: &bool) -> &'static str {
+    if *aborted {
+        ", stopped after the first errors"
+    } else {
+        ""
+    }
+}
+
+impl GenesisError {
+    /// Failure to parse the YAML `file`, located at the line of `err` if known
+    pub fn yaml(file: impl Display, err: &serde_yaml::Error) -> Self {
+        Self::Parse {
+            file: file.to_string(),
+            entry: err
+                .location()
+                .map(|location| format!("line {}", location.line()))
+                .unwrap_or_else(|| "top level".to_string()),
+            cause: err.to_string(),
+        }
+    }
+
+    pub fn validation(entry: impl Display, field: impl Display, reason: impl Display) -> Self {
+        Self::Validation {
+            entry: entry.to_string(),
+            field: field.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl From<GenesisError> for String {
+    fn from(err: GenesisError) -> Self {
+        err.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genesis_error_display() {
+        let pubkey = Pubkey::new_unique();
+        assert_eq!(
+            String::from(GenesisError::Conflict {
+                pubkey,
+                sources: vec![
+                    "the airdrop tranche".to_string(),
+                    "the primordial accounts".to_string()
+                ],
+            }),
+            format!(
+                "account {pubkey} is created by both the airdrop tranche and the primordial \
+                 accounts"
+            )
+        );
+        assert_eq!(
+            GenesisError::validation("validator foo", "stake_lamports", "below 10").to_string(),
+            "invalid stake_lamports of validator foo: below 10"
+        );
+        assert_eq!(
+            GenesisError::LimitExceeded {
+                which: "fees of staker foo".to_string(),
+                limit: 1,
+                actual: 2,
+            }
+            .to_string(),
+            "fees of staker foo: 2 exceeds the limit of 1"
+        );
+
+        assert_eq!(
+            GenesisError::InvalidRows {
+                file: "stakes.csv".to_string(),
+                errors: vec![
+                    "row 2: invalid amount".to_string(),
+                    "row 5: invalid date".to_string()
+                ],
+                aborted: true,
+            }
+            .to_string(),
+            "invalid rows in stakes.csv, stopped after the first errors:\nrow 2: invalid \
+             amount\nrow 5: invalid date"
+        );
+
+        let err = serde_yaml::from_str::<Vec<u64>>("- 1\n- two\n").unwrap_err();
+        let GenesisError::Parse { file, entry, .. } = GenesisError::yaml("accounts.yml", &err)
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(file, "accounts.yml");
+        assert_eq!(entry, "line 2");
+    }
+}
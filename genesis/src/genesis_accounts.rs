@@ -1,10 +1,14 @@
 use {
     crate::{
-        stakes::{create_and_add_stakes, StakerInfo},
+        address_generator::DerivationCache,
+        error::GenesisError,
+        progress::{GenesisPhase, ProgressTracker},
+        stakes::{create_and_add_stakes_locked_until, StakerInfo},
         unlocks::UnlockInfo,
     },
     solana_genesis_config::{ClusterType, GenesisConfig},
     solana_native_token::LAMPORTS_PER_SOL,
+    std::sync::Arc,
 };
 
 // 9 month schedule is 100% after 9 months
@@ -40,90 +44,105 @@ pub const CREATOR_STAKER_INFOS: &[StakerInfo] = &[
         staker: "uE3TVEffRp69mrgknYr71M18GDqL7GxCNGYYRjb3oUt",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("59SLqk4ete5QttM1WmjfMA7uNJnJVFLQqXJSy9rvuj7c"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "nutritious examination",
         staker: "9noVEZreMmgQvE8iyKmxy7CGTJ2enELyuJ1qxFtXrfJB",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("ERnx3Csgu3LjrGGrCeCUZzuHguRu6XabT1kufSB1NDWi"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "tidy impression",
         staker: "BU7LA4kYvicfPCp22EM2Tth3eaeWAXYo6yCgWXQFJ42z",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("5eKcGy7ZCPJdQSQGVnfmT7kGz6MKPMKaNaMEYJbmwhuT"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "dramatic treatment",
         staker: "BrNFrFeuev8TosKhRe2kvVZTYrcUuYaqCfptWutxs17B",
         lamports: 1_205_602 * LAMPORTS_PER_SOL,
         withdrawer: Some("2pKqwFKfKj2nGrknPNDSP8vXGYrgAjd28fT6yLew8sT3"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "angry noise",
         staker: "34HCVh8Yx4jNkaeLUQEKibFKUZDPQMjWzkXy8qUfdhS4",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("Hw3sP6PreBtFCnwXbNvUypMhty62GXibjfiZ1zHBXFk6"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "hard cousin",
         staker: "AyZb3xrZE8wnS6gYBdsJg5v8CjyrX2ZGXU2zMakCFyYd",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("9j3WzBSZRHrD2DbzFTUVVi81QX6boVvUTpGWcSiMwD5W"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "lopsided skill",
         staker: "7SbpY8LmZUb5XRqDbyoreUrSVVV9c39wkpEz81kEAXu5",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("EJyZGbQ1PmpcWxfqGME6SUNHfurh1zggDqCT7rV9xLzL"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "red snake",
         staker: "C9CfFpmLDsQsz6wt7MrrZquNB5oS4QkpJkmDAiboVEZZ",
         lamports: 3_655_292 * LAMPORTS_PER_SOL,
         withdrawer: Some("JBGnGdLyo7V2z9hz51mnnbyDp9sBACtw5WYH9YRG8n7e"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "jolly year",
         staker: "5WbxKiW7bghkr8JN6ZAv2TQt4PfJFvtuqNaN8gyQ5UzU",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("43XAfG3AFiF1ockdh7xp91fpFyZkbWSZq9ZFBCGUVV41"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "typical initiative",
         staker: "Gc8XnHU6Nnriwt9RbEwi7PTosx4YanLyXak9GTbB8VaH",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("7s2GVwFo8VSrCwX9Tztt42ueiEaUtJ6zCEHU8XGvuf5E"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "deserted window",
         staker: "AMmYEynkd78uNTZDFFrMw6NKjWTgqW7M8EFjvajk23VR",
         lamports: 3_655_292 * LAMPORTS_PER_SOL,
         withdrawer: Some("23PJYLS1WFLqhXnXq2Hobc17DbvZaoinoTZYLyGRT8E2"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "eight nation",
         staker: "4qWoqt71p7h6siSDS6osu4oVWpw8R7E6uYYiY7Z6oJbH",
         lamports: 103_519 * LAMPORTS_PER_SOL,
         withdrawer: Some("6bFjx3egMjVsGKFb445564a4bwgibwbUB2tVFsJcdPv7"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "earsplitting meaning",
         staker: "GYitoBY53E9awc56NWHJ2kxMwj4do5GSmvTRowjGaRDw",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("jXMEkVQQpoqebVMGN7DfpvdRLwJDEkoVNrwPVphNm7i"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "alike cheese",
         staker: "Drg9uSvSEfjtn15jqmmrEQnA4pvU1ToYSGSa1Dv9C6Fk",
         lamports: 3_880_295 * LAMPORTS_PER_SOL,
         withdrawer: Some("BxmwgfnyAqZnqRCJGdsEea35pcc92GFTcyGeSj4RNfJJ"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "noisy honey",
         staker: "95HsPFFvwbWpk42MKzenauSoULNzk8Tg6fc6EiJhLsUZ",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("Aj3K933zdRQhYEJi2Yjz8hJWXN3Z3hrKJQtPtE8VmUnq"),
+        ..StakerInfo::DEFAULT
     },
 ];
 
@@ -133,36 +152,42 @@ pub const SERVICE_STAKER_INFOS: &[StakerInfo] = &[
         staker: "B1hegzthtfNQxyEPzkESySxRjMidNqaxrzbQ28GaEwn8",
         lamports: 225_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("HWzeqw1Yk5uiLgT2uGUim5ocFJNCwYUFbeCtDVpx9yUb"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "unbecoming silver",
         staker: "4AcoZa1P8fF5XK21RJsiuMRZPEScbbWNc75oakRFHiBz",
         lamports: 28_800 * LAMPORTS_PER_SOL,
         withdrawer: None,
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "inexpensive uncle",
         staker: "AkJ7yssRqS3X4UWLUsPTxbP6LfVgdPYBWH4Jgk5EETgZ",
         lamports: 300_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("6mudxxoe5VyXXNXsJ3NSGSTGESfG2t86PBCQGbouHpXX"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "hellish money",
         staker: "4DVkqvRP8y26JvzNwsnQEQuC7HASwpGs58GsAT9XJMVg",
         lamports: 200_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("ASJpWZAxY96kbciLqzb7sg45gsH32yPzGcxjn7HPcARn"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "full grape",
         staker: "B2EWnwgmNd3KMpD71yZMijhML1jd4TYp96zJdhMiWZ7b",
         lamports: 450_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("9oaCkokBBhgBsgyg4sL7fMJyQseaJb1TbADZeoPdpWdc"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "nice ghost",
         staker: "HtQS1CH3nsUHmnLpenj5W6KHzFWTf3mzCn1mTqK7LkB7",
         lamports: 650_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("4YnNnycEZXCkuVs2hDthdNxMD4E8wc7ZPgyAK7Lm1uZc"),
+        ..StakerInfo::DEFAULT
     },
 ];
 
@@ -172,12 +197,14 @@ pub const FOUNDATION_STAKER_INFOS: &[StakerInfo] = &[
         staker: "4xh7vtQCTim3vgpQ1dQQWjtKrBSkbtL3s15FimXVJAAP",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("C7WS9ic7KN9XNcLsNoMvzTvbzURM3rFGDEQN7qJMWNLn"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "frequent description",
         staker: "95Nf8XfoecteSXU9nbcvzkrFQdu6FqPaH3EvhwLaC83t",
         lamports: 57_500_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("FdGYQdiRky8NZzN9wZtczTBcWLYYRXrJ3LMDhqDPn5rM"),
+        ..StakerInfo::DEFAULT
     },
 ];
 
@@ -187,12 +214,14 @@ pub const GRANTS_STAKER_INFOS: &[StakerInfo] = &[
         staker: "8w5cgUQfXAZZWyVgenPHpQ1uABXUVLnymqXbuZPx7yqt",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("EDwSQShtUWQtmFfN9SpUUd6hgonL7tRdxngAsNKv9Pe6"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "tasty location",
         staker: "9eyXtP43dCp59oyvWG2R7WQCeJ2bA6TWoLzXg1KTDfQQ",
         lamports: 15_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("9BgvWHerNACjnx6ZpK51k2LEsnwBP3gFwWDzhKkHKH1m"),
+        ..StakerInfo::DEFAULT
     },
 ];
 
@@ -202,71 +231,137 @@ pub const COMMUNITY_STAKER_INFOS: &[StakerInfo] = &[
         staker: "Eo1iDtrZZiAkQFA8u431hedChaSUnPbU8MWg849MFvEZ",
         lamports: 5_000_000 * LAMPORTS_PER_SOL,
         withdrawer: Some("8CUUMKYNGxdgYio5CLHRHyzMEhhVRMcqefgE6dLqnVRK"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "legal gate",
         staker: "7KCzZCbZz6V1U1YXUpBNaqQzQCg2DKo8JsNhKASKtYxe",
         lamports: 30_301_032 * LAMPORTS_PER_SOL,
         withdrawer: Some("92viKFftk1dJjqJwreFqT2qHXxjSUuEE9VyHvTdY1mpY"),
+        ..StakerInfo::DEFAULT
     },
     StakerInfo {
         name: "cluttered complaint",
         staker: "2J8mJU6tWg78DdQVEqMfpN3rMeNbcRT9qGL3yLbmSXYL",
         lamports: 153_333_633 * LAMPORTS_PER_SOL + 41 * LAMPORTS_PER_SOL / 100,
         withdrawer: Some("7kgfDmgbEfypBujqn4tyApjf8H7ZWuaL3F6Ah9vQHzgR"),
+        ..StakerInfo::DEFAULT
     },
 ];
 
+/// Add the stakes of `staker_infos`, reported to `progress` as the accounts
+/// of `source`
 fn add_stakes(
     genesis_config: &mut GenesisConfig,
+    source: &str,
     staker_infos: &[StakerInfo],
     unlock_info: &UnlockInfo,
-) -> u64 {
-    staker_infos
+    derivation_cache: Option<&Arc<DerivationCache>>,
+    progress: &mut ProgressTracker,
+) -> Result<u64, GenesisError> {
+    progress.start_phase(GenesisPhase::CreateAccounts, source, None);
+    let lamports: Result<u64, GenesisError> = staker_infos
         .iter()
-        .map(|staker_info| create_and_add_stakes(genesis_config, staker_info, unlock_info, None))
-        .sum::<u64>()
+        .map(|staker_info| {
+            create_and_add_stakes_locked_until(
+                genesis_config,
+                staker_info,
+                unlock_info,
+                0,
+                None,
+                derivation_cache,
+                progress,
+            )
+        })
+        .sum();
+    progress.finish_phase();
+    lamports
 }
 
 /// Add acounts that should be present in genesis; skip for development clusters
-pub fn add_genesis_accounts(genesis_config: &mut GenesisConfig, mut issued_lamports: u64) {
+pub fn add_genesis_accounts(
+    genesis_config: &mut GenesisConfig,
+    issued_lamports: u64,
+    derivation_cache: Option<&Arc<DerivationCache>>,
+) -> Result<(), GenesisError> {
+    add_genesis_accounts_with_progress(
+        genesis_config,
+        issued_lamports,
+        derivation_cache,
+        &mut ProgressTracker::default(),
+    )
+}
+
+/// `add_genesis_accounts()`, the accounts of each group of stakes reported to
+/// `progress`
+pub fn add_genesis_accounts_with_progress(
+    genesis_config: &mut GenesisConfig,
+    mut issued_lamports: u64,
+    derivation_cache: Option<&Arc<DerivationCache>>,
+    progress: &mut ProgressTracker,
+) -> Result<(), GenesisError> {
     if genesis_config.cluster_type == ClusterType::Development {
-        return;
+        return Ok(());
     }
 
     // add_stakes() and add_validators() award tokens for rent exemption and
     //  to cover an initial transfer-free period of the network
     issued_lamports += add_stakes(
         genesis_config,
+        "creator stakes",
         CREATOR_STAKER_INFOS,
         &UNLOCKS_HALF_AT_9_MONTHS,
-    ) + add_stakes(
+        derivation_cache,
+        progress,
+    )? + add_stakes(
         genesis_config,
+        "service stakes",
         SERVICE_STAKER_INFOS,
         &UNLOCKS_ALL_AT_9_MONTHS,
-    ) + add_stakes(
+        derivation_cache,
+        progress,
+    )? + add_stakes(
         genesis_config,
+        "foundation stakes",
         FOUNDATION_STAKER_INFOS,
         &UNLOCKS_ALL_DAY_ZERO,
-    ) + add_stakes(genesis_config, GRANTS_STAKER_INFOS, &UNLOCKS_ALL_DAY_ZERO)
-        + add_stakes(
-            genesis_config,
-            COMMUNITY_STAKER_INFOS,
-            &UNLOCKS_ALL_DAY_ZERO,
-        );
+        derivation_cache,
+        progress,
+    )? + add_stakes(
+        genesis_config,
+        "grants stakes",
+        GRANTS_STAKER_INFOS,
+        &UNLOCKS_ALL_DAY_ZERO,
+        derivation_cache,
+        progress,
+    )? + add_stakes(
+        genesis_config,
+        "community stakes",
+        COMMUNITY_STAKER_INFOS,
+        &UNLOCKS_ALL_DAY_ZERO,
+        derivation_cache,
+        progress,
+    )?;
 
     // "one thanks" (community pool) gets 500_000_000SOL (total) - above distributions
-    create_and_add_stakes(
+    progress.start_phase(GenesisPhase::CreateAccounts, "community pool stakes", None);
+    create_and_add_stakes_locked_until(
         genesis_config,
         &StakerInfo {
             name: "one thanks",
             staker: "7vEAL3nS9CWmy1q6njUUyHE7Cf5RmyQpND6CsoHjzPiR",
             lamports: (500_000_000 * LAMPORTS_PER_SOL).saturating_sub(issued_lamports),
             withdrawer: Some("3FFaheyqtyAXZSYxDzsr5CVKvJuvZD1WE1VEsBtDbRqB"),
+            ..StakerInfo::DEFAULT
         },
         &UNLOCKS_ALL_DAY_ZERO,
+        0,
         None,
-    );
+        derivation_cache,
+        progress,
+    )?;
+    progress.finish_phase();
+    Ok(())
 }
 
 #[cfg(test)]
@@ -287,7 +382,7 @@ mod tests {
                 cluster_type: *cluster_type,
                 ..GenesisConfig::default()
             };
-            add_genesis_accounts(&mut genesis_config, 0);
+            add_genesis_accounts(&mut genesis_config, 0, None).unwrap();
 
             let lamports = genesis_config
                 .accounts
@@ -297,4 +392,67 @@ mod tests {
             assert_eq!(*expected_lamports, lamports);
         }
     }
+
+    #[test]
+    fn test_add_genesis_accounts_with_derivation_cache() {
+        let new_genesis_config = || GenesisConfig {
+            cluster_type: ClusterType::MainnetBeta,
+            ..GenesisConfig::default()
+        };
+
+        let mut uncached_genesis_config = new_genesis_config();
+        add_genesis_accounts(&mut uncached_genesis_config, 0, None).unwrap();
+
+        // cold cache
+        let derivation_cache = Arc::new(DerivationCache::default());
+        let mut cold_genesis_config = new_genesis_config();
+        add_genesis_accounts(&mut cold_genesis_config, 0, Some(&derivation_cache)).unwrap();
+        assert!(!derivation_cache.is_empty());
+
+        // warm cache, after a round trip through the sidecar file
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("derivation_cache.bin");
+        derivation_cache.save(&path).unwrap();
+        let derivation_cache = Arc::new(DerivationCache::load(&path).unwrap());
+        let mut warm_genesis_config = new_genesis_config();
+        add_genesis_accounts(&mut warm_genesis_config, 0, Some(&derivation_cache)).unwrap();
+
+        assert_eq!(uncached_genesis_config.hash(), cold_genesis_config.hash());
+        assert_eq!(uncached_genesis_config.hash(), warm_genesis_config.hash());
+    }
+
+    #[test]
+    fn test_add_stakes_invalid_staker() {
+        let mut genesis_config = GenesisConfig::default();
+        let staker_infos = [
+            StakerInfo {
+                name: "valid",
+                staker: "uE3TVEffRp69mrgknYr71M18GDqL7GxCNGYYRjb3oUt",
+                lamports: 1_000 * LAMPORTS_PER_SOL,
+                withdrawer: None,
+                ..StakerInfo::DEFAULT
+            },
+            StakerInfo {
+                name: "invalid",
+                staker: "not a pubkey",
+                lamports: 1_000 * LAMPORTS_PER_SOL,
+                withdrawer: None,
+                ..StakerInfo::DEFAULT
+            },
+        ];
+        match add_stakes(
+            &mut genesis_config,
+            "test stakes",
+            &staker_infos,
+            &UNLOCKS_ALL_DAY_ZERO,
+            None,
+            &mut ProgressTracker::default(),
+        ) {
+            Err(GenesisError::Validation { entry, field, .. }) => {
+                assert_eq!(entry, "invalid");
+                assert_eq!(field, "staker");
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+    }
 }
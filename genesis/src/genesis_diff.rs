@@ -0,0 +1,437 @@
+//! Compare two genesis configs, e.g. the one a cluster was started from
+//! against the one the current inputs would generate
+//!
+//! Account data is never shown in full, only its length and hash.
+use {
+    serde::Serialize, solana_account::Account, solana_genesis_config::GenesisConfig,
+    solana_pubkey::Pubkey, solana_sha256_hasher::hash, std::fmt,
+};
+
+/// A field whose value differs, rendered as strings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+/// An account only present in one of the genesis configs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccountSummary {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub owner: String,
+    pub data_len: usize,
+    pub data_hash: String,
+    pub executable: bool,
+}
+
+impl AccountSummary {
+    fn new(pubkey: &Pubkey, account: &Account) -> Self {
+        Self {
+            pubkey: pubkey.to_string(),
+            lamports: account.lamports,
+            owner: account.owner.to_string(),
+            data_len: account.data.len(),
+            data_hash: hash(&account.data).to_string(),
+            executable: account.executable,
+        }
+    }
+}
+
+/// An account present in both genesis configs whose contents differ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccountChange {
+    pub pubkey: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Differences between two genesis configs, as reported by `diff()`.
+/// Accounts are sorted by pubkey.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct GenesisDiff {
+    pub parameters: Vec<FieldChange>,
+    pub added: Vec<AccountSummary>,
+    pub removed: Vec<AccountSummary>,
+    pub modified: Vec<AccountChange>,
+}
+
+impl GenesisDiff {
+    pub fn is_empty(&self) -> bool {
+        self.parameters.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+    }
+}
+
+impl fmt::Display for GenesisDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No differences");
+        }
+        if !self.parameters.is_empty() {
+            writeln!(f, "Cluster parameters changed:")?;
+            for change in &self.parameters {
+                writeln!(f, "  {change}")?;
+            }
+        }
+        for (title, accounts) in [("added", &self.added), ("removed", &self.removed)] {
+            if accounts.is_empty() {
+                continue;
+            }
+            writeln!(f, "Accounts {title} ({}):", accounts.len())?;
+            writeln!(
+                f,
+                "  {:<44} {:>20} {:<44} {:>10} {:<44} EXECUTABLE",
+                "PUBKEY", "LAMPORTS", "OWNER", "DATA LEN", "DATA HASH"
+            )?;
+            for account in accounts {
+                writeln!(
+                    f,
+                    "  {:<44} {:>20} {:<44} {:>10} {:<44} {}",
+                    account.pubkey,
+                    account.lamports,
+                    account.owner,
+                    account.data_len,
+                    account.data_hash,
+                    account.executable
+                )?;
+            }
+        }
+        if !self.modified.is_empty() {
+            writeln!(f, "Accounts modified ({}):", self.modified.len())?;
+            for account in &self.modified {
+                writeln!(f, "  {}", account.pubkey)?;
+                for change in &account.changes {
+                    writeln!(f, "    {change}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<44} {} -> {}", self.field, self.before, self.after)
+    }
+}
+
+fn push_change(changes: &mut Vec<FieldChange>, field: &'static str, before: String, after: String) {
+    if before != after {
+        changes.push(FieldChange {
+            field,
+            before,
+            after,
+        });
+    }
+}
+
+/// Changes of the epoch schedule, rent, fee rate governor and ticks
+pub fn diff_parameters(before: &GenesisConfig, after: &GenesisConfig) -> Vec<FieldChange> {
+    let parameters: [(&'static str, fn(&GenesisConfig) -> String); 17] = [
+        ("epoch_schedule.slots_per_epoch", |config| {
+            config.epoch_schedule.slots_per_epoch.to_string()
+        }),
+        ("epoch_schedule.leader_schedule_slot_offset", |config| {
+            config
+                .epoch_schedule
+                .leader_schedule_slot_offset
+                .to_string()
+        }),
+        ("epoch_schedule.warmup", |config| {
+            config.epoch_schedule.warmup.to_string()
+        }),
+        ("epoch_schedule.first_normal_epoch", |config| {
+            config.epoch_schedule.first_normal_epoch.to_string()
+        }),
+        ("epoch_schedule.first_normal_slot", |config| {
+            config.epoch_schedule.first_normal_slot.to_string()
+        }),
+        ("rent.lamports_per_byte_year", |config| {
+            config.rent.lamports_per_byte_year.to_string()
+        }),
+        ("rent.exemption_threshold", |config| {
+            config.rent.exemption_threshold.to_string()
+        }),
+        ("rent.burn_percent", |config| {
+            config.rent.burn_percent.to_string()
+        }),
+        (
+            "fee_rate_governor.target_lamports_per_signature",
+            |config| {
+                config
+                    .fee_rate_governor
+                    .target_lamports_per_signature
+                    .to_string()
+            },
+        ),
+        ("fee_rate_governor.target_signatures_per_slot", |config| {
+            config
+                .fee_rate_governor
+                .target_signatures_per_slot
+                .to_string()
+        }),
+        ("fee_rate_governor.min_lamports_per_signature", |config| {
+            config
+                .fee_rate_governor
+                .min_lamports_per_signature
+                .to_string()
+        }),
+        ("fee_rate_governor.max_lamports_per_signature", |config| {
+            config
+                .fee_rate_governor
+                .max_lamports_per_signature
+                .to_string()
+        }),
+        ("fee_rate_governor.burn_percent", |config| {
+            config.fee_rate_governor.burn_percent.to_string()
+        }),
+        ("ticks_per_slot", |config| config.ticks_per_slot.to_string()),
+        ("poh_config.target_tick_duration", |config| {
+            format!("{:?}", config.poh_config.target_tick_duration)
+        }),
+        ("poh_config.hashes_per_tick", |config| {
+            format!("{:?}", config.poh_config.hashes_per_tick)
+        }),
+        ("cluster_type", |config| {
+            format!("{:?}", config.cluster_type)
+        }),
+    ];
+
+    let mut changes = vec![];
+    for (field, value) in parameters {
+        push_change(&mut changes, field, value(before), value(after));
+    }
+    changes
+}
+
+/// Changes of the lamports, owner, data and executable flag of an account
+pub fn diff_account(before: &Account, after: &Account) -> Vec<FieldChange> {
+    let mut changes = vec![];
+    push_change(
+        &mut changes,
+        "lamports",
+        before.lamports.to_string(),
+        after.lamports.to_string(),
+    );
+    push_change(
+        &mut changes,
+        "owner",
+        before.owner.to_string(),
+        after.owner.to_string(),
+    );
+    push_change(
+        &mut changes,
+        "data_len",
+        before.data.len().to_string(),
+        after.data.len().to_string(),
+    );
+    if before.data != after.data {
+        push_change(
+            &mut changes,
+            "data_hash",
+            hash(&before.data).to_string(),
+            hash(&after.data).to_string(),
+        );
+    }
+    push_change(
+        &mut changes,
+        "executable",
+        before.executable.to_string(),
+        after.executable.to_string(),
+    );
+    changes
+}
+
+/// Compare `before` against `after`
+pub fn diff(before: &GenesisConfig, after: &GenesisConfig) -> GenesisDiff {
+    let mut diff = GenesisDiff {
+        parameters: diff_parameters(before, after),
+        ..GenesisDiff::default()
+    };
+    for (pubkey, before_account) in &before.accounts {
+        match after.accounts.get(pubkey) {
+            None => diff
+                .removed
+                .push(AccountSummary::new(pubkey, before_account)),
+            Some(after_account) => {
+                let changes = diff_account(before_account, after_account);
+                if !changes.is_empty() {
+                    diff.modified.push(AccountChange {
+                        pubkey: pubkey.to_string(),
+                        changes,
+                    });
+                }
+            }
+        }
+    }
+    diff.added = after
+        .accounts
+        .iter()
+        .filter(|(pubkey, _)| !before.accounts.contains_key(pubkey))
+        .map(|(pubkey, account)| AccountSummary::new(pubkey, account))
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_epoch_schedule::EpochSchedule, solana_rent::Rent};
+
+    fn new_account(lamports: u64, data: Vec<u8>) -> Account {
+        Account {
+            lamports,
+            data,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn fields(changes: &[FieldChange]) -> Vec<&'static str> {
+        changes.iter().map(|change| change.field).collect()
+    }
+
+    #[test]
+    fn test_identical_configs() {
+        let mut genesis_config = GenesisConfig::default();
+        genesis_config.add_account(Pubkey::new_unique(), new_account(42, vec![1, 2, 3]).into());
+        let diff = diff(&genesis_config, &genesis_config.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff, GenesisDiff::default());
+        assert_eq!(diff.to_string(), "No differences\n");
+    }
+
+    #[test]
+    fn test_diff_parameters() {
+        let before = GenesisConfig::default();
+        let mut after = GenesisConfig {
+            epoch_schedule: EpochSchedule::custom(8192, 8192, false),
+            rent: Rent {
+                burn_percent: 100,
+                ..before.rent.clone()
+            },
+            ticks_per_slot: before.ticks_per_slot * 2,
+            ..before.clone()
+        };
+        after.fee_rate_governor.target_lamports_per_signature += 1;
+
+        let diff = diff(&before, &after);
+        assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.modified.is_empty());
+        assert_eq!(
+            fields(&diff.parameters),
+            [
+                "epoch_schedule.slots_per_epoch",
+                "epoch_schedule.leader_schedule_slot_offset",
+                "epoch_schedule.warmup",
+                "epoch_schedule.first_normal_epoch",
+                "epoch_schedule.first_normal_slot",
+                "rent.burn_percent",
+                "fee_rate_governor.target_lamports_per_signature",
+                "ticks_per_slot",
+            ]
+        );
+        assert_eq!(
+            diff.parameters[5],
+            FieldChange {
+                field: "rent.burn_percent",
+                before: before.rent.burn_percent.to_string(),
+                after: "100".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_diff_added_and_removed_accounts() {
+        let kept = Pubkey::new_unique();
+        let removed = Pubkey::new_unique();
+        let added = Pubkey::new_unique();
+        let mut before = GenesisConfig::default();
+        before.add_account(kept, new_account(1, vec![]).into());
+        let mut after = before.clone();
+        before.add_account(removed, new_account(2, vec![]).into());
+        let added_account = new_account(3, vec![7; 10]);
+        after.add_account(added, added_account.clone().into());
+
+        let diff = diff(&before, &after);
+        assert!(diff.parameters.is_empty() && diff.modified.is_empty());
+        assert_eq!(
+            diff.added,
+            [AccountSummary {
+                pubkey: added.to_string(),
+                lamports: 3,
+                owner: added_account.owner.to_string(),
+                data_len: 10,
+                data_hash: hash(&[7; 10]).to_string(),
+                executable: false,
+            }]
+        );
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].pubkey, removed.to_string());
+        assert_eq!(diff.removed[0].lamports, 2);
+    }
+
+    #[test]
+    fn test_diff_modified_accounts() {
+        let pubkey = Pubkey::new_unique();
+        let before_account = new_account(1, vec![0; 1_000_000]);
+        let mut after_account = before_account.clone();
+        after_account.lamports = 2;
+        after_account.owner = Pubkey::new_unique();
+        after_account.data = vec![1; 2_000_000];
+        after_account.executable = true;
+
+        let mut before = GenesisConfig::default();
+        before.add_account(pubkey, before_account.clone().into());
+        let mut after = GenesisConfig::default();
+        after.add_account(pubkey, after_account.clone().into());
+
+        let diff = diff(&before, &after);
+        assert!(diff.parameters.is_empty() && diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.modified.len(), 1);
+        let changes = &diff.modified[0].changes;
+        assert_eq!(
+            fields(changes),
+            ["lamports", "owner", "data_len", "data_hash", "executable"]
+        );
+        assert_eq!(changes[2].before, "1000000");
+        assert_eq!(changes[2].after, "2000000");
+        assert_eq!(changes[3].after, hash(&after_account.data).to_string());
+        // large data is only summarized
+        assert!(diff.to_string().len() < 1_000);
+
+        // same length, different contents
+        let mut after_account = before_account.clone();
+        after_account.data[0] = 1;
+        after.add_account(pubkey, after_account.into());
+        assert_eq!(
+            fields(&diff_account(&before_account, &after.accounts[&pubkey])),
+            ["data_hash"]
+        );
+    }
+
+    #[test]
+    fn test_diff_serialization() {
+        let before = GenesisConfig::default();
+        let after = GenesisConfig {
+            ticks_per_slot: 32,
+            ..before.clone()
+        };
+        let json = serde_json::to_value(diff(&before, &after)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "parameters": [{
+                    "field": "ticks_per_slot",
+                    "before": before.ticks_per_slot.to_string(),
+                    "after": "32",
+                }],
+                "added": [],
+                "removed": [],
+                "modified": [],
+            })
+        );
+    }
+}
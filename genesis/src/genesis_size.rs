@@ -0,0 +1,89 @@
+//! Estimate of the size of the genesis file a genesis config is written to,
+//! computed without serializing the accounts
+//!
+//! `genesis.bin` is the bincode serialization of the genesis config. An
+//! account takes a fixed number of bytes besides its data, so the size of the
+//! account set follows from the data lengths alone. The remaining parameters
+//! are small and are serialized in memory.
+//!
+//! The estimate is exact for `genesis.bin` as long as the bincode layout of
+//! `Account` matches `ACCOUNT_OVERHEAD`. It is an upper bound of the
+//! compressed genesis archive, which may be much smaller when accounts hold
+//! zeroed or repetitive data. It says nothing of the blockstore created along
+//! with the genesis file.
+use {
+    solana_account::Account,
+    solana_genesis_config::GenesisConfig,
+    solana_pubkey::Pubkey,
+    std::{collections::BTreeMap, mem::size_of},
+};
+
+/// Bytes taken by an account besides its data: its pubkey, lamports, data
+/// length, owner, executable flag and rent epoch.
+pub const ACCOUNT_OVERHEAD: u64 = (size_of::<Pubkey>()
+    + size_of::<u64>()
+    + size_of::<u64>()
+    + size_of::<Pubkey>()
+    + size_of::<u8>()
+    + size_of::<u64>()) as u64;
+
+/// Estimated size in bytes of `genesis.bin` for `genesis_config`
+pub fn estimate_genesis_size(genesis_config: &GenesisConfig) -> u64 {
+    let parameters = GenesisConfig {
+        accounts: BTreeMap::new(),
+        native_instruction_processors: genesis_config.native_instruction_processors.clone(),
+        rewards_pools: BTreeMap::new(),
+        poh_config: genesis_config.poh_config.clone(),
+        ..GenesisConfig::default()
+    };
+    bincode::serialized_size(&parameters).unwrap()
+        + estimate_accounts_size(&genesis_config.accounts)
+        + estimate_accounts_size(&genesis_config.rewards_pools)
+}
+
+fn estimate_accounts_size(accounts: &BTreeMap<Pubkey, Account>) -> u64 {
+    accounts
+        .values()
+        .map(|account| ACCOUNT_OVERHEAD + account.data.len() as u64)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_account::AccountSharedData, solana_poh_config::PohConfig};
+
+    #[test]
+    fn test_estimate_genesis_size() {
+        let mut genesis_config = GenesisConfig::default();
+        let default_size = bincode::serialized_size(&genesis_config).unwrap();
+        assert_eq!(estimate_genesis_size(&genesis_config), default_size);
+
+        for data_len in [0, 1, 200, 10 * 1024] {
+            genesis_config.add_account(
+                Pubkey::new_unique(),
+                AccountSharedData::new(1, data_len, &Pubkey::new_unique()),
+            );
+        }
+        genesis_config.rewards_pools.insert(
+            Pubkey::new_unique(),
+            Account::new(1, 16, &Pubkey::new_unique()),
+        );
+        genesis_config.add_native_instruction_processor(
+            "solana_bench_program".to_string(),
+            Pubkey::new_unique(),
+        );
+        genesis_config.poh_config = PohConfig {
+            hashes_per_tick: Some(12_500),
+            ..PohConfig::default()
+        };
+
+        let estimate = estimate_genesis_size(&genesis_config);
+        let actual = bincode::serialize(&genesis_config).unwrap().len() as u64;
+        assert!(estimate > default_size + 10 * 1024);
+        // within 1% of the serialized size
+        assert!(
+            estimate.abs_diff(actual) * 100 <= actual,
+            "estimate {estimate}, actual {actual}"
+        );
+    }
+}
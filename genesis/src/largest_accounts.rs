@@ -0,0 +1,120 @@
+//! Accounts of a genesis config ordered by balance, largest first, for
+//! reviewing the allocations
+//!
+//! Accounts of equal balance are ordered by pubkey, so that the report is the
+//! same for the same genesis config.
+use {solana_genesis_config::GenesisConfig, solana_pubkey::Pubkey, std::fmt};
+
+/// Balance of an account of the genesis config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountBalance {
+    pub pubkey: Pubkey,
+    pub lamports: u64,
+    pub owner: Pubkey,
+}
+
+/// Accounts of a genesis config by balance, as reported by
+/// `largest_accounts()`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LargestAccounts {
+    pub accounts: Vec<AccountBalance>,
+    /// Number of accounts of the genesis config, including those left out of
+    /// `accounts`
+    pub total_accounts: usize,
+    /// Lamports of the accounts of the genesis config, including those left
+    /// out of `accounts`
+    pub total_lamports: u64,
+}
+
+/// The accounts of `genesis_config` sorted by lamports, descending, limited
+/// to the `top` largest if given
+pub fn largest_accounts(genesis_config: &GenesisConfig, top: Option<usize>) -> LargestAccounts {
+    let mut accounts: Vec<_> = genesis_config
+        .accounts
+        .iter()
+        .map(|(pubkey, account)| AccountBalance {
+            pubkey: *pubkey,
+            lamports: account.lamports,
+            owner: account.owner,
+        })
+        .collect();
+    let total_accounts = accounts.len();
+    let total_lamports = accounts.iter().map(|account| account.lamports).sum();
+    accounts.sort_by(|a, b| b.lamports.cmp(&a.lamports).then(a.pubkey.cmp(&b.pubkey)));
+    if let Some(top) = top {
+        accounts.truncate(top);
+    }
+    LargestAccounts {
+        accounts,
+        total_accounts,
+        total_lamports,
+    }
+}
+
+impl fmt::Display for LargestAccounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Largest {} of {} accounts, {} lamports in total:",
+            self.accounts.len(),
+            self.total_accounts,
+            self.total_lamports
+        )?;
+        writeln!(f, "  {:<44} {:>20} OWNER", "PUBKEY", "LAMPORTS")?;
+        for account in &self.accounts {
+            writeln!(
+                f,
+                "  {:<44} {:>20} {}",
+                account.pubkey.to_string(),
+                account.lamports,
+                account.owner
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_account::AccountSharedData};
+
+    #[test]
+    fn test_largest_accounts() {
+        let mut genesis_config = GenesisConfig::default();
+        let owner = Pubkey::new_unique();
+        let pubkeys: Vec<_> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        for (pubkey, lamports) in pubkeys.iter().zip([10, 500, 20, 500, 1]) {
+            genesis_config.add_account(*pubkey, AccountSharedData::new(lamports, 0, &owner));
+        }
+
+        let largest = largest_accounts(&genesis_config, None);
+        assert_eq!(largest.total_accounts, 5);
+        assert_eq!(largest.total_lamports, 1_031);
+        assert_eq!(
+            largest
+                .accounts
+                .iter()
+                .map(|account| account.lamports)
+                .collect::<Vec<_>>(),
+            vec![500, 500, 20, 10, 1]
+        );
+        // equal balances are ordered by pubkey
+        assert!(largest.accounts[0].pubkey < largest.accounts[1].pubkey);
+        assert!(largest
+            .accounts
+            .iter()
+            .all(|account| account.owner == owner));
+
+        let top = largest_accounts(&genesis_config, Some(3));
+        assert_eq!(top.accounts, largest.accounts[..3]);
+        assert_eq!(top.total_accounts, 5);
+        assert_eq!(top.total_lamports, 1_031);
+        assert_eq!(
+            top.to_string().lines().next(),
+            Some("Largest 3 of 5 accounts, 1031 lamports in total:")
+        );
+        assert_eq!(top.to_string().lines().count(), 5);
+
+        assert_eq!(largest_accounts(&genesis_config, Some(10)), largest);
+    }
+}
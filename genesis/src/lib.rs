@@ -1,32 +1,443 @@
 #![allow(clippy::arithmetic_side_effects)]
 pub mod address_generator;
+pub mod airdrop_tranche;
+pub mod amount;
+pub mod bootstrap_validators;
+pub mod compatibility;
+pub mod error;
 pub mod genesis_accounts;
+pub mod genesis_diff;
+pub mod genesis_size;
+pub mod largest_accounts;
+pub mod lookup_tables;
+pub mod progress;
+pub mod rent_exemption;
+pub mod rootability;
+pub mod spl_tokens;
+pub mod stake_allocations;
 pub mod stakes;
+pub mod test_genesis;
 pub mod unlocks;
 
-use serde::{Deserialize, Serialize};
+use {
+    crate::{
+        amount::{deserialize_lamports, Amount},
+        error::GenesisError,
+        lookup_tables::LookupTableSpec,
+        spl_tokens::SplTokenMint,
+    },
+    serde::{Deserialize, Serialize},
+    std::collections::{BTreeMap, HashMap},
+};
 
 /// An account where the data is encoded as a Base64 string.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Base64Account {
+    #[serde(deserialize_with = "deserialize_lamports")]
     pub balance: u64,
     pub owner: String,
     pub data: String,
     pub executable: bool,
 }
 
+/// A primordial accounts file, mapping the pubkeys of the accounts to their
+/// details, along with the SPL token mints and address lookup tables to create
 #[derive(Serialize, Deserialize, Debug)]
+pub struct GenesisAccountsFile {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spl_tokens: Vec<SplTokenMint>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lookup_tables: Vec<LookupTableSpec>,
+    #[serde(flatten)]
+    pub accounts: HashMap<String, Base64Account>,
+}
+
+/// A validator accounts file, with the stakes given as percentages of
+/// `total_stake_pool` resolved to lamports
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(try_from = "ValidatorAccountsFileInput")]
 pub struct ValidatorAccountsFile {
     pub validator_accounts: Vec<StakedValidatorAccountInfo>,
 }
 
+/// A validator accounts file as written, its stakes possibly percentages
+#[derive(Deserialize)]
+struct ValidatorAccountsFileInput {
+    #[serde(default)]
+    total_stake_pool: Option<Amount>,
+    validator_accounts: Vec<StakedValidatorAccountInput>,
+}
+
+#[derive(Deserialize)]
+struct StakedValidatorAccountInput {
+    #[serde(deserialize_with = "deserialize_lamports")]
+    balance_lamports: u64,
+    #[serde(default)]
+    stake_lamports: Option<Amount>,
+    identity_account: String,
+    vote_account: String,
+    #[serde(default)]
+    stake_account: String,
+    #[serde(default)]
+    stake_accounts: Vec<ValidatorStakeAccountInput>,
+}
+
+#[derive(Deserialize)]
+struct ValidatorStakeAccountInput {
+    stake_account: String,
+    stake_lamports: Amount,
+}
+
+impl TryFrom<ValidatorAccountsFileInput> for ValidatorAccountsFile {
+    type Error = GenesisError;
+
+    fn try_from(input: ValidatorAccountsFileInput) -> Result<Self, Self::Error> {
+        let total_stake_pool = input
+            .total_stake_pool
+            .map(|amount| amount.lamports(None))
+            .transpose()
+            .map_err(|err| {
+                GenesisError::validation("the validator accounts", "total_stake_pool", err)
+            })?;
+        // lamports of the stakes given as percentages, each a whole number of
+        // lamports, so that they exceed the pool only past 100% of it
+        let mut percentage_stakes = 0u64;
+        let mut resolve = |identity_account: &str, stake: Amount| {
+            let lamports = stake.lamports(total_stake_pool).map_err(|err| {
+                GenesisError::validation(
+                    format_args!("validator {identity_account}"),
+                    "stake_lamports",
+                    err,
+                )
+            })?;
+            if let Amount::Percentage(_) = stake {
+                percentage_stakes = percentage_stakes.saturating_add(lamports);
+            }
+            Ok::<_, GenesisError>(lamports)
+        };
+
+        let mut validator_accounts = Vec::with_capacity(input.validator_accounts.len());
+        for info in input.validator_accounts {
+            let stake_lamports = match info.stake_lamports {
+                Some(stake) => resolve(&info.identity_account, stake)?,
+                None => 0,
+            };
+            let stake_accounts = info
+                .stake_accounts
+                .into_iter()
+                .map(|account| {
+                    Ok(ValidatorStakeAccount {
+                        stake_lamports: resolve(&info.identity_account, account.stake_lamports)?,
+                        stake_account: account.stake_account,
+                    })
+                })
+                .collect::<Result<_, GenesisError>>()?;
+            validator_accounts.push(StakedValidatorAccountInfo {
+                balance_lamports: info.balance_lamports,
+                stake_lamports,
+                identity_account: info.identity_account,
+                vote_account: info.vote_account,
+                stake_account: info.stake_account,
+                stake_accounts,
+            });
+        }
+
+        if let Some(total_stake_pool) = total_stake_pool {
+            if percentage_stakes > total_stake_pool {
+                return Err(GenesisError::validation(
+                    "the validator accounts",
+                    "stake_lamports",
+                    format_args!(
+                        "the percentage stakes add up to {percentage_stakes} lamports, more \
+                         than 100% of the total_stake_pool of {total_stake_pool} lamports"
+                    ),
+                ));
+            }
+        }
+        Ok(Self { validator_accounts })
+    }
+}
+
+impl ValidatorAccountsFile {
+    /// Compare the validator set in `self` (before) against `other` (after),
+    /// keyed by identity account. Each bucket is sorted by identity.
+    pub fn diff(&self, other: &Self) -> ValidatorSetDiff {
+        let by_identity = |file: &Self| -> BTreeMap<String, StakedValidatorAccountInfo> {
+            file.validator_accounts
+                .iter()
+                .map(|info| (info.identity_account.clone(), info.clone()))
+                .collect()
+        };
+        let before = by_identity(self);
+        let mut after = by_identity(other);
+
+        let mut diff = ValidatorSetDiff::default();
+        for (identity_account, before) in before {
+            match after.remove(&identity_account) {
+                None => diff.removed.push(before),
+                Some(after) if after != before => diff.changed.push(ValidatorAccountChange {
+                    identity_account,
+                    before,
+                    after,
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.added = after.into_values().collect();
+        diff
+    }
+}
+
 /// Info needed to create a staked validator account,
 /// including relevant balances and vote- and stake-account addresses
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct StakedValidatorAccountInfo {
+    #[serde(deserialize_with = "deserialize_lamports")]
     pub balance_lamports: u64,
+    #[serde(default, deserialize_with = "deserialize_lamports")]
     pub stake_lamports: u64,
     pub identity_account: String,
     pub vote_account: String,
+    #[serde(default)]
+    pub stake_account: String,
+    /// Stake accounts all delegated to `vote_account`, in place of the single
+    /// `stake_account` holding `stake_lamports`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stake_accounts: Vec<ValidatorStakeAccount>,
+}
+
+impl StakedValidatorAccountInfo {
+    /// The `(stake_account, stake_lamports)` of each stake account of the
+    /// validator. Fails unless exactly one of the two forms is used.
+    pub fn stake_accounts(&self) -> Result<Vec<(&str, u64)>, GenesisError> {
+        match (
+            self.stake_account.is_empty(),
+            self.stake_accounts.is_empty(),
+        ) {
+            (false, true) => Ok(vec![(self.stake_account.as_str(), self.stake_lamports)]),
+            (true, false) => Ok(self
+                .stake_accounts
+                .iter()
+                .map(|account| (account.stake_account.as_str(), account.stake_lamports))
+                .collect()),
+            (false, false) => Err(GenesisError::validation(
+                format_args!("validator {}", self.identity_account),
+                "stake_accounts",
+                "both stake_account and stake_accounts are set",
+            )),
+            (true, true) => Err(GenesisError::validation(
+                format_args!("validator {}", self.identity_account),
+                "stake_accounts",
+                "the validator has no stake account",
+            )),
+        }
+    }
+}
+
+/// One of several stake accounts of a validator
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorStakeAccount {
     pub stake_account: String,
+    #[serde(deserialize_with = "deserialize_lamports")]
+    pub stake_lamports: u64,
+}
+
+/// Differences between two validator sets, as reported by `ValidatorAccountsFile::diff()`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ValidatorSetDiff {
+    pub added: Vec<StakedValidatorAccountInfo>,
+    pub removed: Vec<StakedValidatorAccountInfo>,
+    pub changed: Vec<ValidatorAccountChange>,
+}
+
+impl ValidatorSetDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A validator present in both sets whose balances or accounts differ
+#[derive(Debug, PartialEq, Eq)]
+pub struct ValidatorAccountChange {
+    pub identity_account: String,
+    pub before: StakedValidatorAccountInfo,
+    pub after: StakedValidatorAccountInfo,
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, assert_matches::assert_matches, solana_native_token::LAMPORTS_PER_SOL};
+
+    fn new_validator_info(stake_lamports: u64) -> StakedValidatorAccountInfo {
+        StakedValidatorAccountInfo {
+            balance_lamports: 100_000_000_000,
+            stake_lamports,
+            identity_account: solana_pubkey::new_rand().to_string(),
+            vote_account: solana_pubkey::new_rand().to_string(),
+            stake_account: solana_pubkey::new_rand().to_string(),
+            stake_accounts: vec![],
+        }
+    }
+
+    #[test]
+    fn test_stake_accounts_forms() {
+        let single: StakedValidatorAccountInfo = serde_yaml::from_str(
+            "balance_lamports: 1000\n\
+             stake_lamports: 500\n\
+             identity_account: identity\n\
+             vote_account: vote\n\
+             stake_account: stake\n",
+        )
+        .unwrap();
+        assert_eq!(single.stake_accounts().unwrap(), [("stake", 500)]);
+        // written back in the same form
+        assert!(!serde_yaml::to_string(&single)
+            .unwrap()
+            .contains("stake_accounts"));
+
+        let multiple: StakedValidatorAccountInfo = serde_yaml::from_str(
+            "balance_lamports: 1000\n\
+             identity_account: identity\n\
+             vote_account: vote\n\
+             stake_accounts:\n\
+             - stake_account: stake1\n  \
+               stake_lamports: 100\n\
+             - stake_account: stake2\n  \
+               stake_lamports: 200\n",
+        )
+        .unwrap();
+        assert_eq!(
+            multiple.stake_accounts().unwrap(),
+            [("stake1", 100), ("stake2", 200)]
+        );
+
+        let both = StakedValidatorAccountInfo {
+            stake_account: "stake".to_string(),
+            ..multiple.clone()
+        };
+        assert_matches!(
+            both.stake_accounts(),
+            Err(GenesisError::Validation { field, .. }) if field == "stake_accounts"
+        );
+        let neither = StakedValidatorAccountInfo {
+            stake_accounts: vec![],
+            ..multiple
+        };
+        assert_matches!(
+            neither.stake_accounts(),
+            Err(GenesisError::Validation { .. })
+        );
+    }
+
+    #[test]
+    fn test_validator_accounts_file_diff() {
+        let unchanged = new_validator_info(10_000_000_000);
+        let restaked = new_validator_info(20_000_000_000);
+        let removed = new_validator_info(30_000_000_000);
+        let added = new_validator_info(40_000_000_000);
+
+        let baseline = ValidatorAccountsFile {
+            validator_accounts: vec![unchanged.clone(), restaked.clone(), removed.clone()],
+        };
+        assert!(baseline.diff(&baseline).is_empty());
+
+        let restaked_after = StakedValidatorAccountInfo {
+            stake_lamports: 25_000_000_000,
+            ..restaked.clone()
+        };
+        let updated = ValidatorAccountsFile {
+            validator_accounts: vec![added.clone(), restaked_after.clone(), unchanged],
+        };
+
+        assert_eq!(
+            baseline.diff(&updated),
+            ValidatorSetDiff {
+                added: vec![added.clone()],
+                removed: vec![removed.clone()],
+                changed: vec![ValidatorAccountChange {
+                    identity_account: restaked.identity_account.clone(),
+                    before: restaked.clone(),
+                    after: restaked_after.clone(),
+                }],
+            }
+        );
+
+        // diffing in the other direction swaps the buckets
+        assert_eq!(
+            updated.diff(&baseline),
+            ValidatorSetDiff {
+                added: vec![removed],
+                removed: vec![added],
+                changed: vec![ValidatorAccountChange {
+                    identity_account: restaked.identity_account.clone(),
+                    before: restaked_after,
+                    after: restaked,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_validator_accounts_file_amounts() {
+        let file: ValidatorAccountsFile = serde_yaml::from_str(
+            "total_stake_pool: 1000 SOL\n\
+             validator_accounts:\n\
+             - balance_lamports: 1.5 SOL\n  \
+               stake_lamports: 25%\n  \
+               identity_account: identity1\n  \
+               vote_account: vote1\n  \
+               stake_account: stake1\n\
+             - balance_lamports: 500000 lamports\n  \
+               identity_account: identity2\n  \
+               vote_account: vote2\n  \
+               stake_accounts:\n  \
+               - stake_account: stake2\n    \
+                 stake_lamports: 2.5%\n  \
+               - stake_account: stake3\n    \
+                 stake_lamports: 42\n",
+        )
+        .unwrap();
+        let [first, second] = &file.validator_accounts[..] else {
+            panic!("expected two validators");
+        };
+        assert_eq!(first.balance_lamports, 1_500_000_000);
+        assert_eq!(first.stake_lamports, 250 * LAMPORTS_PER_SOL);
+        assert_eq!(second.balance_lamports, 500_000);
+        assert_eq!(
+            second.stake_accounts().unwrap(),
+            [("stake2", 25 * LAMPORTS_PER_SOL), ("stake3", 42)]
+        );
+    }
+
+    #[test]
+    fn test_validator_accounts_file_invalid_percentages() {
+        let validators = |total_stake_pool: &str, stakes: &[&str]| {
+            let mut yaml = format!("{total_stake_pool}validator_accounts:\n");
+            for (i, stake) in stakes.iter().enumerate() {
+                yaml += &format!(
+                    "- balance_lamports: 1\n  \
+                     stake_lamports: {stake}\n  \
+                     identity_account: identity{i}\n  \
+                     vote_account: vote{i}\n  \
+                     stake_account: stake{i}\n"
+                );
+            }
+            serde_yaml::from_str::<ValidatorAccountsFile>(&yaml).map_err(|err| err.to_string())
+        };
+
+        assert!(validators("total_stake_pool: 1000\n", &["60%", "40%", "5000"]).is_ok());
+        let err = validators("total_stake_pool: 1000\n", &["60%", "40.1%"]).unwrap_err();
+        assert!(
+            err.contains("more than 100% of the total_stake_pool of 1000"),
+            "{err}"
+        );
+        // no pool to take a percentage of
+        let err = validators("", &["10%"]).unwrap_err();
+        assert!(err.contains("validator identity0"), "{err}");
+        // nor can the pool itself be a percentage
+        let err = validators("total_stake_pool: 10%\n", &["10%"]).unwrap_err();
+        assert!(err.contains("total_stake_pool"), "{err}");
+        let err = validators("total_stake_pool: 1000\n", &["0.01%"]).unwrap_err();
+        assert!(err.contains("not a whole number of lamports"), "{err}");
+    }
 }
@@ -0,0 +1,297 @@
+//! Address lookup tables, declared in the `lookup_tables` section of a
+//! primordial accounts file
+//!
+//! Each table is created at the address derived from its authority and
+//! `derivation_slot`, already extended with its addresses. The addresses
+//! count as extended in the genesis slot, so that they are active from slot 1
+//! on, with no transaction needed to extend the table.
+//! An address is either a pubkey, or the `label` of another table of the
+//! file, standing for the address of that table.
+use {
+    crate::error::GenesisError,
+    serde::{Deserialize, Serialize},
+    solana_account::AccountSharedData,
+    solana_address_lookup_table_interface::{
+        instruction::derive_lookup_table_address,
+        state::{
+            LookupTableMeta, ProgramState, LOOKUP_TABLE_MAX_ADDRESSES, LOOKUP_TABLE_META_SIZE,
+        },
+    },
+    solana_clock::Slot,
+    solana_genesis_config::GenesisConfig,
+    solana_pubkey::Pubkey,
+    std::{collections::HashMap, str::FromStr},
+};
+
+/// A lookup table, as read from the `lookup_tables` section of an accounts
+/// file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LookupTableSpec {
+    /// Name the other tables refer to this one by
+    #[serde(default)]
+    pub label: Option<String>,
+    pub authority: String,
+    /// Slot the table address is derived from, along with the authority.
+    /// Tables of the same authority need distinct slots.
+    #[serde(default)]
+    pub derivation_slot: Slot,
+    /// Slot the table was deactivated in, the table being active if `None`
+    #[serde(default)]
+    pub deactivation_slot: Option<Slot>,
+    /// Pubkeys, or labels of other tables
+    pub addresses: Vec<String>,
+}
+
+impl LookupTableSpec {
+    fn entry(&self) -> String {
+        match &self.label {
+            Some(label) => format!("lookup table {label}"),
+            None => format!(
+                "lookup table of {} at slot {}",
+                self.authority, self.derivation_slot
+            ),
+        }
+    }
+}
+
+/// Add the lookup tables of `tables` to `genesis_config`. Returns the
+/// lamports of the accounts added.
+pub fn add_lookup_tables(
+    genesis_config: &mut GenesisConfig,
+    tables: &[LookupTableSpec],
+) -> Result<u64, GenesisError> {
+    let mut authorities = Vec::with_capacity(tables.len());
+    let mut labels = HashMap::new();
+    for spec in tables {
+        let entry = spec.entry();
+        let authority = Pubkey::from_str(&spec.authority).map_err(|err| {
+            GenesisError::validation(
+                &entry,
+                "authority",
+                format_args!("{}: {err}", spec.authority),
+            )
+        })?;
+        let (address, _bump_seed) = derive_lookup_table_address(&authority, spec.derivation_slot);
+        if let Some(label) = &spec.label {
+            if Pubkey::from_str(label).is_ok() {
+                return Err(GenesisError::validation(
+                    &entry,
+                    "label",
+                    "a label cannot be a pubkey",
+                ));
+            }
+            if labels.insert(label.as_str(), address).is_some() {
+                return Err(GenesisError::validation(
+                    &entry,
+                    "label",
+                    format_args!("duplicate label {label}"),
+                ));
+            }
+        }
+        authorities.push((authority, address));
+    }
+
+    let mut accounts = Vec::with_capacity(tables.len());
+    for (spec, (authority, address)) in tables.iter().zip(authorities) {
+        let entry = spec.entry();
+        if spec.addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+            return Err(GenesisError::LimitExceeded {
+                which: format!("addresses of {entry}"),
+                limit: LOOKUP_TABLE_MAX_ADDRESSES as u64,
+                actual: spec.addresses.len() as u64,
+            });
+        }
+        let addresses = spec
+            .addresses
+            .iter()
+            .map(|address| {
+                Pubkey::from_str(address)
+                    .ok()
+                    .or_else(|| labels.get(address.as_str()).copied())
+                    .ok_or_else(|| {
+                        GenesisError::validation(
+                            &entry,
+                            "addresses",
+                            format_args!("{address} is neither a pubkey nor a label"),
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The metadata takes the first LOOKUP_TABLE_META_SIZE bytes, zero
+        // padded, and is followed by the addresses
+        let meta = LookupTableMeta {
+            deactivation_slot: spec.deactivation_slot.unwrap_or(Slot::MAX),
+            ..LookupTableMeta::new(authority)
+        };
+        let mut data = vec![0; LOOKUP_TABLE_META_SIZE];
+        bincode::serialize_into(data.as_mut_slice(), &ProgramState::LookupTable(meta))
+            .expect("lookup table metadata fits in LOOKUP_TABLE_META_SIZE bytes");
+        data.extend(addresses.iter().flat_map(|address| address.to_bytes()));
+        accounts.push((entry, address, data));
+    }
+
+    let mut lamports = 0;
+    for (entry, address, data) in accounts {
+        if genesis_config.accounts.contains_key(&address) {
+            return Err(GenesisError::Conflict {
+                pubkey: address,
+                sources: vec![entry, "genesis".to_string()],
+            });
+        }
+        let table_lamports = genesis_config.rent.minimum_balance(data.len());
+        let mut account = AccountSharedData::new(
+            table_lamports,
+            0,
+            &solana_sdk_ids::address_lookup_table::id(),
+        );
+        account.set_data_from_slice(&data);
+        genesis_config.add_account(address, account);
+        lamports += table_lamports;
+    }
+
+    Ok(lamports)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::GenesisAccountsFile, assert_matches::assert_matches,
+        solana_account::ReadableAccount,
+        solana_address_lookup_table_interface::state::AddressLookupTable, solana_rent::Rent,
+    };
+
+    fn table(label: Option<&str>, addresses: &[&str]) -> LookupTableSpec {
+        LookupTableSpec {
+            label: label.map(str::to_string),
+            authority: Pubkey::new_unique().to_string(),
+            derivation_slot: 0,
+            deactivation_slot: None,
+            addresses: addresses
+                .iter()
+                .map(|address| address.to_string())
+                .collect(),
+        }
+    }
+
+    fn table_address(spec: &LookupTableSpec) -> Pubkey {
+        derive_lookup_table_address(
+            &Pubkey::from_str(&spec.authority).unwrap(),
+            spec.derivation_slot,
+        )
+        .0
+    }
+
+    #[test]
+    fn test_add_lookup_tables() {
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut dex = table(Some("dex"), &[&alice.to_string(), &bob.to_string()]);
+        dex.derivation_slot = 7;
+        let mut routes = table(None, &["dex", &alice.to_string()]);
+        routes.deactivation_slot = Some(100);
+
+        let mut genesis_config = GenesisConfig::default();
+        let lamports =
+            add_lookup_tables(&mut genesis_config, &[dex.clone(), routes.clone()]).unwrap();
+        assert_eq!(genesis_config.accounts.len(), 2);
+
+        let dex_address = table_address(&dex);
+        assert_eq!(
+            dex_address,
+            Pubkey::find_program_address(
+                &[
+                    Pubkey::from_str(&dex.authority).unwrap().as_ref(),
+                    &7u64.to_le_bytes()
+                ],
+                &solana_sdk_ids::address_lookup_table::id(),
+            )
+            .0
+        );
+        let mut total_lamports = 0;
+        for (spec, addresses, deactivation_slot) in [
+            (&dex, vec![alice, bob], Slot::MAX),
+            (&routes, vec![dex_address, alice], 100),
+        ] {
+            let account =
+                AccountSharedData::from(genesis_config.accounts[&table_address(spec)].clone());
+            assert_eq!(account.owner(), &solana_sdk_ids::address_lookup_table::id());
+            assert_eq!(
+                account.lamports(),
+                Rent::default().minimum_balance(account.data().len())
+            );
+            total_lamports += account.lamports();
+
+            let table = AddressLookupTable::deserialize(account.data()).unwrap();
+            assert_eq!(table.addresses.as_ref(), addresses.as_slice());
+            assert_eq!(
+                table.meta.authority,
+                Some(Pubkey::from_str(&spec.authority).unwrap())
+            );
+            assert_eq!(table.meta.deactivation_slot, deactivation_slot);
+            // extended in the genesis slot, active from slot 1
+            assert_eq!(table.meta.last_extended_slot, 0);
+            assert_eq!(table.meta.last_extended_slot_start_index, 0);
+        }
+        assert_eq!(lamports, total_lamports);
+    }
+
+    #[test]
+    fn test_add_lookup_tables_errors() {
+        let alice = Pubkey::new_unique().to_string();
+        let mut genesis_config = GenesisConfig::default();
+        assert_matches!(
+            add_lookup_tables(&mut genesis_config, &[table(None, &["unknown"])]),
+            Err(GenesisError::Validation { field, .. }) if field == "addresses"
+        );
+        assert_matches!(
+            add_lookup_tables(
+                &mut genesis_config,
+                &[table(Some("dex"), &[]), table(Some("dex"), &[])]
+            ),
+            Err(GenesisError::Validation { field, reason, .. })
+                if field == "label" && reason.starts_with("duplicate")
+        );
+        assert_matches!(
+            add_lookup_tables(&mut genesis_config, &[table(Some(alice.as_str()), &[])]),
+            Err(GenesisError::Validation { field, .. }) if field == "label"
+        );
+        let mut bad_authority = table(None, &[]);
+        bad_authority.authority = "not a pubkey".to_string();
+        assert_matches!(
+            add_lookup_tables(&mut genesis_config, &[bad_authority]),
+            Err(GenesisError::Validation { field, .. }) if field == "authority"
+        );
+        let too_many = vec![alice.as_str(); LOOKUP_TABLE_MAX_ADDRESSES + 1];
+        assert_matches!(
+            add_lookup_tables(&mut genesis_config, &[table(None, &too_many)]),
+            Err(GenesisError::LimitExceeded { actual, .. })
+                if actual == LOOKUP_TABLE_MAX_ADDRESSES as u64 + 1
+        );
+        assert!(genesis_config.accounts.is_empty());
+
+        let full = table(None, &too_many[..LOOKUP_TABLE_MAX_ADDRESSES]);
+        add_lookup_tables(&mut genesis_config, &[full.clone()]).unwrap();
+        assert_matches!(
+            add_lookup_tables(&mut genesis_config, &[full.clone()]),
+            Err(GenesisError::Conflict { pubkey, .. }) if pubkey == table_address(&full)
+        );
+    }
+
+    #[test]
+    fn test_lookup_tables_section() {
+        let yaml = format!(
+            "lookup_tables:\n  - label: dex\n    authority: {authority}\n    addresses:\n      - \
+             {address}\n  - authority: {authority}\n    derivation_slot: 1\n    \
+             deactivation_slot: 10\n    addresses: [dex]\n",
+            authority = Pubkey::new_unique(),
+            address = Pubkey::new_unique(),
+        );
+        let accounts_file: GenesisAccountsFile = serde_yaml::from_str(&yaml).unwrap();
+        assert!(accounts_file.accounts.is_empty());
+        assert_eq!(accounts_file.lookup_tables.len(), 2);
+        assert_eq!(accounts_file.lookup_tables[0].label.as_deref(), Some("dex"));
+        assert_eq!(accounts_file.lookup_tables[1].derivation_slot, 1);
+        assert_eq!(accounts_file.lookup_tables[1].deactivation_slot, Some(10));
+        assert_eq!(accounts_file.lookup_tables[1].addresses, vec!["dex"]);
+    }
+}
@@ -12,8 +12,8 @@ use {
             cluster_type_of, pubkey_of, pubkeys_of, unix_timestamp_from_rfc3339_datetime,
         },
         input_validators::{
-            is_pubkey, is_pubkey_or_keypair, is_rfc3339_datetime, is_slot, is_url_or_moniker,
-            is_valid_percentage, normalize_to_url_if_moniker,
+            is_parsable, is_pubkey, is_pubkey_or_keypair, is_rfc3339_datetime, is_slot,
+            is_url_or_moniker, is_valid_percentage, normalize_to_url_if_moniker,
         },
     },
     solana_clock as clock,
@@ -24,8 +24,33 @@ use {
     solana_feature_set::FEATURE_NAMES,
     solana_fee_calculator::FeeRateGovernor,
     solana_genesis::{
-        genesis_accounts::add_genesis_accounts, Base64Account, StakedValidatorAccountInfo,
-        ValidatorAccountsFile,
+        address_generator::DerivationCache,
+        airdrop_tranche::{
+            add_airdrop_tranche, load_airdrop_tranche, write_airdrop_tranche_index,
+            AIRDROP_TRANCHE_INDEX_FILE,
+        },
+        bootstrap_validators::{
+            bootstrap_validator_keypairs_dir, generate_bootstrap_validators,
+            write_bootstrap_validator_keypairs, GeneratedValidator,
+        },
+        compatibility::{self, Severity, ValidatorTarget},
+        error::GenesisError,
+        genesis_accounts::add_genesis_accounts_with_progress,
+        genesis_diff,
+        genesis_size::estimate_genesis_size,
+        largest_accounts::largest_accounts,
+        lookup_tables::add_lookup_tables,
+        progress::{print_progress, GenesisPhase, ProgressTracker},
+        rent_exemption::RentExemptionCheck,
+        rootability::StakeDistribution,
+        spl_tokens::add_spl_tokens,
+        stake_allocations::{
+            add_stake_allocations, load_stake_allocations_csv, StakeAllocationsCsvConfig,
+            DEFAULT_MAX_ERRORS,
+        },
+        stakes::add_validator_stakes,
+        GenesisAccountsFile, StakedValidatorAccountInfo, ValidatorAccountsFile,
+        ValidatorStakeAccount,
     },
     solana_genesis_config::{ClusterType, GenesisConfig},
     solana_inflation::Inflation,
@@ -41,17 +66,16 @@ use {
     solana_sdk_ids::system_program,
     solana_signer::Signer,
     solana_stake_interface::state::StakeStateV2,
-    solana_stake_program::stake_state,
     solana_vote_program::vote_state::{self, VoteState},
     std::{
-        collections::HashMap,
         error,
-        fs::File,
-        io::{self, Read},
-        path::PathBuf,
+        fs::{self, File},
+        io::Read,
+        path::{Path, PathBuf},
         process,
         slice::Iter,
         str::FromStr,
+        sync::Arc,
         time::Duration,
     },
 };
@@ -70,26 +94,54 @@ fn pubkey_from_str(key_str: &str) -> Result<Pubkey, Box<dyn error::Error>> {
     })
 }
 
-pub fn load_genesis_accounts(file: &str, genesis_config: &mut GenesisConfig) -> io::Result<u64> {
+/// Add the accounts of the primordial accounts `file`, checking that each
+/// is rent exempt under the rent of `genesis_config` if `rent_exemption` is set
+pub fn load_genesis_accounts(
+    file: &str,
+    genesis_config: &mut GenesisConfig,
+    rent_exemption: Option<RentExemptionCheck>,
+) -> Result<u64, GenesisError> {
+    load_genesis_accounts_with_progress(
+        file,
+        genesis_config,
+        rent_exemption,
+        &mut ProgressTracker::default(),
+    )
+}
+
+/// `load_genesis_accounts()`, the loading, validation and addition of the
+/// accounts of `file` reported to `progress`
+pub fn load_genesis_accounts_with_progress(
+    file: &str,
+    genesis_config: &mut GenesisConfig,
+    rent_exemption: Option<RentExemptionCheck>,
+    progress: &mut ProgressTracker,
+) -> Result<u64, GenesisError> {
     let mut lamports = 0;
+    progress.start_phase(GenesisPhase::LoadFile, file, None);
     let accounts_file = File::open(file)?;
 
-    let genesis_accounts: HashMap<String, Base64Account> =
-        serde_yaml::from_reader(accounts_file)
-            .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{err:?}")))?;
+    let genesis_accounts: GenesisAccountsFile =
+        serde_yaml::from_reader(accounts_file).map_err(|err| GenesisError::yaml(file, &err))?;
+    let total_accounts = genesis_accounts.accounts.len();
+    progress.add(total_accounts);
 
-    for (key, account_details) in genesis_accounts {
+    progress.start_phase(GenesisPhase::Validation, file, Some(total_accounts));
+    let mut accounts = Vec::with_capacity(total_accounts);
+    for (key, account_details) in genesis_accounts.accounts {
         let pubkey = pubkey_from_str(key.as_str()).map_err(|err| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("Invalid pubkey/keypair {key}: {err:?}"),
+            GenesisError::validation(
+                format_args!("account {key}"),
+                "pubkey",
+                format_args!("invalid pubkey/keypair {key}: {err:?}"),
             )
         })?;
 
         let owner_program_id = Pubkey::from_str(account_details.owner.as_str()).map_err(|err| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("Invalid owner: {}: {:?}", account_details.owner, err),
+            GenesisError::validation(
+                format_args!("account {key}"),
+                "owner",
+                format_args!("{}: {err:?}", account_details.owner),
             )
         })?;
 
@@ -99,69 +151,131 @@ pub fn load_genesis_accounts(file: &str, genesis_config: &mut GenesisConfig) ->
                 &BASE64_STANDARD
                     .decode(account_details.data.as_str())
                     .map_err(|err| {
-                        io::Error::new(
-                            io::ErrorKind::Other,
-                            format!("Invalid account data: {}: {:?}", account_details.data, err),
+                        GenesisError::validation(
+                            format_args!("account {key}"),
+                            "data",
+                            format_args!("{}: {err:?}", account_details.data),
                         )
                     })?,
             );
         }
         account.set_executable(account_details.executable);
+        if let Some(rent_exemption) = rent_exemption {
+            rent_exemption.check(
+                &genesis_config.rent,
+                format_args!("account {key}"),
+                account.lamports(),
+                account.data().len(),
+            )?;
+        }
+        accounts.push((pubkey, account));
+        progress.inc();
+    }
+
+    progress.start_phase(GenesisPhase::CreateAccounts, file, Some(total_accounts));
+    for (pubkey, account) in accounts {
         lamports += account.lamports();
         genesis_config.add_account(pubkey, account);
+        progress.inc();
     }
+    lamports += add_spl_tokens(genesis_config, &genesis_accounts.spl_tokens)?;
+    lamports += add_lookup_tables(genesis_config, &genesis_accounts.lookup_tables)?;
+    progress.finish_phase();
 
     Ok(lamports)
 }
 
+/// Add the validators of the validator accounts `file`, checking that their
+/// identity accounts are rent exempt under `rent` if `rent_exemption` is set
 pub fn load_validator_accounts(
     file: &str,
     commission: u8,
     rent: &Rent,
     genesis_config: &mut GenesisConfig,
-) -> io::Result<()> {
+    rent_exemption: Option<RentExemptionCheck>,
+) -> Result<(), GenesisError> {
     let accounts_file = File::open(file)?;
     let validator_genesis_accounts: Vec<StakedValidatorAccountInfo> =
         serde_yaml::from_reader::<_, ValidatorAccountsFile>(accounts_file)
-            .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{err:?}")))?
+            .map_err(|err| GenesisError::yaml(file, &err))?
             .validator_accounts;
 
     for account_details in validator_genesis_accounts {
-        let pubkeys = [
-            pubkey_from_str(account_details.identity_account.as_str()).map_err(|err| {
-                io::Error::new(
-                    io::ErrorKind::Other,
-                    format!(
-                        "Invalid pubkey/keypair {}: {:?}",
-                        account_details.identity_account, err
-                    ),
-                )
-            })?,
-            pubkey_from_str(account_details.vote_account.as_str()).map_err(|err| {
-                io::Error::new(
-                    io::ErrorKind::Other,
-                    format!(
-                        "Invalid pubkey/keypair {}: {:?}",
-                        account_details.vote_account, err
-                    ),
-                )
-            })?,
-            pubkey_from_str(account_details.stake_account.as_str()).map_err(|err| {
-                io::Error::new(
-                    io::ErrorKind::Other,
-                    format!(
-                        "Invalid pubkey/keypair {}: {:?}",
-                        account_details.stake_account, err
-                    ),
+        let parse_pubkey = |field: &str, pubkey: &str| {
+            pubkey_from_str(pubkey).map_err(|err| {
+                GenesisError::validation(
+                    format_args!("validator {}", account_details.identity_account),
+                    field,
+                    format_args!("invalid pubkey/keypair {pubkey}: {err:?}"),
                 )
-            })?,
+            })
+        };
+        let identity_pubkey = parse_pubkey("identity_account", &account_details.identity_account)?;
+        if let Some(rent_exemption) = rent_exemption {
+            rent_exemption.check(
+                rent,
+                format_args!("validator {}", account_details.identity_account),
+                account_details.balance_lamports,
+                0,
+            )?;
+        }
+        let vote_pubkey = parse_pubkey("vote_account", &account_details.vote_account)?;
+        let stake_accounts = account_details
+            .stake_accounts()?
+            .into_iter()
+            .map(|(stake_account, stake_lamports)| {
+                parse_pubkey("stake_account", stake_account)
+                    .map(|stake_pubkey| (stake_pubkey, stake_lamports))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        add_validator(
+            genesis_config,
+            &identity_pubkey,
+            &vote_pubkey,
+            &stake_accounts,
+            account_details.balance_lamports,
+            commission,
+            rent,
+            None,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Add the accounts of generated bootstrap validators, failing if any of them
+/// collides with an account already in genesis
+pub fn add_generated_validator_accounts(
+    genesis_config: &mut GenesisConfig,
+    validators: &[GeneratedValidator],
+    commission: u8,
+    rent: &Rent,
+) -> Result<(), GenesisError> {
+    for validator in validators {
+        let pubkeys = [
+            validator.identity.pubkey(),
+            validator.vote_account.pubkey(),
+            validator.stake_account.pubkey(),
         ];
+        if let Some(pubkey) = pubkeys
+            .iter()
+            .find(|pubkey| genesis_config.accounts.contains_key(pubkey))
+        {
+            return Err(GenesisError::Conflict {
+                pubkey: *pubkey,
+                sources: vec![
+                    "a generated bootstrap validator".to_string(),
+                    "genesis".to_string(),
+                ],
+            });
+        }
 
         add_validator_accounts(
             genesis_config,
             &mut pubkeys.iter(),
-            account_details.balance_lamports,
-            account_details.stake_lamports,
+            validator.balance_lamports,
+            validator.stake_lamports,
             commission,
             rent,
             None,
@@ -171,6 +285,31 @@ pub fn load_validator_accounts(
     Ok(())
 }
 
+/// Write `genesis_config` to a new ledger at `ledger_path`, reported to
+/// `progress`
+fn write_genesis(
+    ledger_path: &Path,
+    genesis_config: &GenesisConfig,
+    max_genesis_archive_unpacked_size: u64,
+    progress: &mut ProgressTracker,
+) -> Result<(), Box<dyn error::Error>> {
+    let total_accounts = genesis_config.accounts.len();
+    progress.start_phase(
+        GenesisPhase::Serialization,
+        &ledger_path.display().to_string(),
+        Some(total_accounts),
+    );
+    create_new_ledger(
+        ledger_path,
+        genesis_config,
+        max_genesis_archive_unpacked_size,
+        LedgerColumnOptions::default(),
+    )?;
+    progress.add(total_accounts);
+    progress.finish_phase();
+    Ok(())
+}
+
 fn check_rpc_genesis_hash(
     cluster_type: &ClusterType,
     rpc_client: &RpcClient,
@@ -243,8 +382,9 @@ fn add_validator_accounts(
     commission: u8,
     rent: &Rent,
     authorized_pubkey: Option<&Pubkey>,
-) -> io::Result<()> {
+) -> Result<(), GenesisError> {
     rent_exempt_check(
+        "validator stakes",
         stake_lamports,
         rent.minimum_balance(StakeStateV2::size_of()),
     )?;
@@ -256,40 +396,86 @@ fn add_validator_accounts(
         let vote_pubkey = pubkeys_iter.next().unwrap();
         let stake_pubkey = pubkeys_iter.next().unwrap();
 
-        genesis_config.add_account(
-            *identity_pubkey,
-            AccountSharedData::new(lamports, 0, &system_program::id()),
-        );
-
-        let vote_account = vote_state::create_account_with_authorized(
-            identity_pubkey,
-            identity_pubkey,
+        add_validator(
+            genesis_config,
             identity_pubkey,
+            vote_pubkey,
+            &[(*stake_pubkey, stake_lamports)],
+            lamports,
             commission,
-            VoteState::get_rent_exempt_reserve(rent).max(1),
-        );
+            rent,
+            authorized_pubkey,
+        )?;
+    }
+    Ok(())
+}
 
-        genesis_config.add_account(
-            *stake_pubkey,
-            stake_state::create_account(
-                authorized_pubkey.unwrap_or(identity_pubkey),
-                vote_pubkey,
-                &vote_account,
-                rent,
-                stake_lamports,
-            ),
-        );
-        genesis_config.add_account(*vote_pubkey, vote_account);
+/// Add the identity and vote accounts of a validator, and its
+/// `(stake_pubkey, stake_lamports)` stake accounts delegated to the vote account
+#[allow(clippy::too_many_arguments)]
+fn add_validator(
+    genesis_config: &mut GenesisConfig,
+    identity_pubkey: &Pubkey,
+    vote_pubkey: &Pubkey,
+    stake_accounts: &[(Pubkey, u64)],
+    lamports: u64,
+    commission: u8,
+    rent: &Rent,
+    authorized_pubkey: Option<&Pubkey>,
+) -> Result<(), GenesisError> {
+    let stake_rent_exempt_reserve = rent.minimum_balance(StakeStateV2::size_of());
+    for (stake_pubkey, stake_lamports) in stake_accounts {
+        rent_exempt_check(
+            format_args!("stake account {stake_pubkey}"),
+            *stake_lamports,
+            stake_rent_exempt_reserve,
+        )?;
     }
+
+    genesis_config.add_account(
+        *identity_pubkey,
+        AccountSharedData::new(lamports, 0, &system_program::id()),
+    );
+
+    let vote_account = vote_state::create_account_with_authorized(
+        identity_pubkey,
+        identity_pubkey,
+        identity_pubkey,
+        commission,
+        VoteState::get_rent_exempt_reserve(rent).max(1),
+    );
+
+    add_validator_stakes(
+        genesis_config,
+        authorized_pubkey.unwrap_or(identity_pubkey),
+        vote_pubkey,
+        &vote_account,
+        rent,
+        stake_accounts,
+    );
+    genesis_config.add_account(*vote_pubkey, vote_account);
     Ok(())
 }
 
-fn rent_exempt_check(stake_lamports: u64, exempt: u64) -> io::Result<()> {
-    if stake_lamports < exempt {
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!(
-                "error: insufficient validator stake lamports: {stake_lamports} for rent exemption, requires {exempt}"
+fn rent_exempt_check(
+    entry: impl std::fmt::Display,
+    stake_lamports: u64,
+    exempt: u64,
+) -> Result<(), GenesisError> {
+    // a rent-free cluster would otherwise let a stake delegate nothing
+    if stake_lamports == 0 {
+        Err(GenesisError::validation(
+            entry,
+            "stake_lamports",
+            "validator stake lamports must be greater than 0",
+        ))
+    } else if stake_lamports < exempt {
+        Err(GenesisError::validation(
+            entry,
+            "stake_lamports",
+            format_args!(
+                "insufficient validator stake lamports: {stake_lamports} for rent exemption, \
+                 requires {exempt}"
             ),
         ))
     } else {
@@ -297,6 +483,72 @@ fn rent_exempt_check(stake_lamports: u64, exempt: u64) -> io::Result<()> {
     }
 }
 
+/// Report the incompatibilities of the genesis config in `ledger_path` with
+/// `target`. If `fix` is set, the genesis config is patched first and the
+/// ledger, genesis archive included, recreated from it. Fails if errors
+/// remain.
+fn check_genesis_compatibility(
+    ledger_path: &Path,
+    target: &ValidatorTarget,
+    fix: bool,
+    max_genesis_archive_unpacked_size: u64,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut genesis_config = GenesisConfig::load(ledger_path)?;
+    let issues = if fix {
+        let issues = compatibility::fix(&mut genesis_config, target);
+        create_new_ledger(
+            ledger_path,
+            &genesis_config,
+            max_genesis_archive_unpacked_size,
+            LedgerColumnOptions::default(),
+        )?;
+        println!(
+            "Recreated the ledger from the patched genesis config with hash {}",
+            genesis_config.hash()
+        );
+        issues
+    } else {
+        compatibility::check(&genesis_config, target)
+    };
+
+    if issues.is_empty() {
+        println!("No compatibility issues found");
+        return Ok(());
+    }
+    for issue in &issues {
+        println!("{issue}");
+    }
+    let num_errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    if num_errors > 0 {
+        return Err(format!("{num_errors} compatibility errors found").into());
+    }
+    Ok(())
+}
+
+/// Report the differences between the existing genesis config at `path`, a
+/// ledger directory or a genesis file, and `genesis_config`
+fn print_genesis_diff(
+    path: &Path,
+    genesis_config: &GenesisConfig,
+    json: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let existing = if path.is_dir() {
+        GenesisConfig::load(path)?
+    } else {
+        bincode::deserialize(&fs::read(path)?)?
+    };
+    let diff = genesis_diff::diff(&existing, genesis_config);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        print!("{diff}");
+    }
+    Ok(())
+}
+
 #[allow(clippy::cognitive_complexity)]
 fn main() -> Result<(), Box<dyn error::Error>> {
     let default_faucet_pubkey = solana_cli_config::Config::default().keypair_path;
@@ -339,6 +591,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     let default_ticks_per_slot = &clock::DEFAULT_TICKS_PER_SLOT.to_string();
     let default_cluster_type = "mainnet-beta";
     let default_genesis_archive_unpacked_size = MAX_GENESIS_ARCHIVE_UNPACKED_SIZE.to_string();
+    let default_stake_allocations_csv_max_errors = DEFAULT_MAX_ERRORS.to_string();
 
     let matches = App::new(crate_name!())
         .about(crate_description!())
@@ -360,9 +613,57 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 .validator(is_pubkey_or_keypair)
                 .number_of_values(3)
                 .multiple(true)
-                .required(true)
+                .required_unless_one(&["bootstrap_validator_count", "check_compatibility"])
                 .help("The bootstrap validator's identity, vote and stake pubkeys"),
         )
+        .arg(
+            Arg::with_name("bootstrap_validator_count")
+                .long("bootstrap-validator-count")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .requires("bootstrap_validator_keypairs_dir")
+                .help(
+                    "Generate this many bootstrap validators, funded with \
+                     --bootstrap-validator-lamports each and staked with an even share of \
+                     --bootstrap-validator-total-stake-lamports. Generated validators are \
+                     added after any --validator-accounts-file entries",
+                ),
+        )
+        .arg(
+            Arg::with_name("bootstrap_validator_total_stake_lamports")
+                .long("bootstrap-validator-total-stake-lamports")
+                .value_name("LAMPORTS")
+                .takes_value(true)
+                .requires("bootstrap_validator_count")
+                .help(
+                    "Total stake of the generated bootstrap validators, the remainder of an \
+                     even split going to the first one \
+                     [default: --bootstrap-validator-count * --bootstrap-validator-stake-lamports]",
+                ),
+        )
+        .arg(
+            Arg::with_name("bootstrap_validator_seed")
+                .long("bootstrap-validator-seed")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .requires("bootstrap_validator_count")
+                .help(
+                    "Seed the generated bootstrap validator keypairs are derived from. \
+                     The keypairs are predictable and only suitable for development clusters \
+                     [default: 0]",
+                ),
+        )
+        .arg(
+            Arg::with_name("bootstrap_validator_keypairs_dir")
+                .long("bootstrap-validator-keypairs-dir")
+                .value_name("DIR")
+                .takes_value(true)
+                .requires("bootstrap_validator_count")
+                .help(
+                    "Write the keypairs of each generated bootstrap validator into \
+                     DIR/bootstrap-validator-N/{identity,vote-account,stake-account}.json",
+                ),
+        )
         .arg(
             Arg::with_name("ledger_path")
                 .short("l")
@@ -372,6 +673,55 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 .required(true)
                 .help("Use directory as persistent ledger location"),
         )
+        .arg(
+            Arg::with_name("check_compatibility")
+                .long("check-compatibility")
+                .help(
+                    "Check the existing genesis config in the ledger directory for known \
+                     incompatibilities with this validator version instead of creating one",
+                ),
+        )
+        .arg(
+            Arg::with_name("fix")
+                .long("fix")
+                .requires("check_compatibility")
+                .help(
+                    "Apply the safe fixes of the issues found by --check-compatibility and \
+                     recreate the ledger, genesis archive included, from the patched genesis \
+                     config. The existing ledger is destroyed",
+                ),
+        )
+        .arg(
+            Arg::with_name("required_feature")
+                .long("required-feature")
+                .value_name("FEATURE_PUBKEY")
+                .takes_value(true)
+                .multiple(true)
+                .validator(is_pubkey)
+                .requires("check_compatibility")
+                .help("Feature the validator requires to be activated at genesis"),
+        )
+        .arg(
+            Arg::with_name("diff_against")
+                .long("diff-against")
+                .value_name("PATH")
+                .takes_value(true)
+                .conflicts_with_all(&["check_compatibility", "bootstrap_validator_keypairs_dir"])
+                .help(
+                    "Instead of creating the ledger, compare the genesis config the other \
+                     arguments would create against the existing one at PATH, a ledger \
+                     directory or a genesis.bin file",
+                ),
+        )
+        .arg(
+            Arg::with_name("diff_output")
+                .long("diff-output")
+                .value_name("FORMAT")
+                .takes_value(true)
+                .possible_values(&["table", "json"])
+                .requires("diff_against")
+                .help("Format of the --diff-against report [default: table]"),
+        )
         .arg(
             Arg::with_name("faucet_lamports")
                 .short("t")
@@ -551,6 +901,76 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 .multiple(true)
                 .help("The location of a file containing a list of identity, vote, and stake pubkeys and balances for validator accounts to bake into genesis")
         )
+        .arg(
+            Arg::with_name("check_rent_exemption")
+                .long("check-rent-exemption")
+                .value_name("error|warn")
+                .takes_value(true)
+                .possible_values(&["error", "warn"])
+                .help(
+                    "Check that the accounts of --primordial-accounts-file and the identity \
+                     accounts of --validator-accounts-file hold at least the rent-exempt \
+                     minimum for their data length, failing or warning on shortfalls",
+                ),
+        )
+        .arg(
+            Arg::with_name("airdrop_tranche_file")
+                .long("airdrop-tranche-file")
+                .value_name("FILENAME")
+                .takes_value(true)
+                .help(
+                    "Create the pre-funded accounts of the airdrop tranche described in the \
+                     airdrop_tranche section of this file: count system accounts funded with \
+                     lamports_each, whose keypairs are derived from seed",
+                ),
+        )
+        .arg(
+            Arg::with_name("airdrop_tranche_index_file")
+                .long("airdrop-tranche-index-file")
+                .value_name("FILENAME")
+                .takes_value(true)
+                .requires("airdrop_tranche_file")
+                .help(
+                    "Write the address and derivation index of each airdrop tranche account \
+                     to this file [default: airdrop-tranche-index.json in the ledger directory]",
+                ),
+        )
+        .arg(
+            Arg::with_name("stake_allocations_csv")
+                .long("stake-allocations-csv")
+                .value_name("FILENAME")
+                .takes_value(true)
+                .help(
+                    "Create the stake accounts allocated by the rows of this CSV, each \
+                     recipient staking and withdrawing its amount, locked up until the \
+                     lockup_date of its row if any, with custodian able to lift the lockup",
+                ),
+        )
+        .arg(
+            Arg::with_name("stake_allocations_csv_columns")
+                .long("stake-allocations-csv-columns")
+                .value_name("FIELD=HEADER,...")
+                .takes_value(true)
+                .requires("stake_allocations_csv")
+                .help(
+                    "Read the fields of --stake-allocations-csv from the columns with these \
+                     headers, e.g. recipient=Wallet,amount=SOL. The fields are recipient, \
+                     amount, lockup_date and custodian, by default read from the columns of \
+                     the same names",
+                ),
+        )
+        .arg(
+            Arg::with_name("stake_allocations_csv_max_errors")
+                .long("stake-allocations-csv-max-errors")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .requires("stake_allocations_csv")
+                .default_value(&default_stake_allocations_csv_max_errors)
+                .help(
+                    "Stop reading --stake-allocations-csv after this many invalid rows, \
+                     reporting them all at once",
+                ),
+        )
         .arg(
             Arg::with_name("cluster_type")
                 .long("cluster-type")
@@ -580,6 +1000,37 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                     "maximum total uncompressed file size of created genesis archive",
                 ),
         )
+        .arg(
+            Arg::with_name("print_largest_accounts")
+                .long("print-largest-accounts")
+                .value_name("TOP_N")
+                .takes_value(true)
+                .min_values(0)
+                .max_values(1)
+                .validator(is_parsable::<usize>)
+                .help(
+                    "Print the accounts of the genesis config by balance, largest first, \
+                     limited to the TOP_N largest if given",
+                ),
+        )
+        .arg(
+            Arg::with_name("progress")
+                .long("progress")
+                .takes_value(false)
+                .help(
+                    "Print the progress of the build to stderr, for genesis configs of many \
+                     accounts",
+                ),
+        )
+        .arg(
+            Arg::with_name("allow_unrootable_genesis")
+                .long("allow-unrootable-genesis")
+                .takes_value(false)
+                .help(
+                    "Only warn when neither a single validator nor the bootstrap validators \
+                     hold enough of the stake of the first epoch to root slot 1",
+                ),
+        )
         .arg(
             Arg::with_name("bpf_program")
                 .long("bpf-program")
@@ -606,6 +1057,16 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 .possible_values(&["pico", "full", "none"])
                 .help("Selects inflation"),
         )
+        .arg(
+            Arg::with_name("derivation_cache")
+                .long("derivation-cache")
+                .value_name("PATH")
+                .takes_value(true)
+                .help(
+                    "Cache derived stake account addresses in this file, reusing them on the \
+                     next build. The resulting genesis is identical with or without the cache",
+                ),
+        )
         .arg(
             Arg::with_name("json_rpc_url")
                 .short("u")
@@ -624,13 +1085,26 @@ fn main() -> Result<(), Box<dyn error::Error>> {
 
     let ledger_path = PathBuf::from(matches.value_of("ledger_path").unwrap());
 
+    if matches.is_present("check_compatibility") {
+        let target = ValidatorTarget {
+            required_features: pubkeys_of(&matches, "required_feature").unwrap_or_default(),
+        };
+        return check_genesis_compatibility(
+            &ledger_path,
+            &target,
+            matches.is_present("fix"),
+            value_t_or_exit!(matches, "max_genesis_archive_unpacked_size", u64),
+        );
+    }
+
     let rent = Rent {
         lamports_per_byte_year: value_t_or_exit!(matches, "lamports_per_byte_year", u64),
         exemption_threshold: value_t_or_exit!(matches, "rent_exemption_threshold", f64),
         burn_percent: value_t_or_exit!(matches, "rent_burn_percentage", u8),
     };
 
-    let bootstrap_validator_pubkeys = pubkeys_of(&matches, "bootstrap_validator").unwrap();
+    let bootstrap_validator_pubkeys =
+        pubkeys_of(&matches, "bootstrap_validator").unwrap_or_default();
     assert_eq!(bootstrap_validator_pubkeys.len() % 3, 0);
 
     // Ensure there are no duplicated pubkeys in the --bootstrap-validator list
@@ -650,6 +1124,12 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     let bootstrap_validator_stake_lamports =
         value_t_or_exit!(matches, "bootstrap_validator_stake_lamports", u64);
 
+    let bootstrap_validator_count = value_t!(matches, "bootstrap_validator_count", usize).ok();
+    if bootstrap_validator_count == Some(0) {
+        eprintln!("Error: --bootstrap-validator-count must be greater than 0");
+        process::exit(1);
+    }
+
     let bootstrap_stake_authorized_pubkey =
         pubkey_of(&matches, "bootstrap_stake_authorized_pubkey");
     let faucet_lamports = value_t!(matches, "faucet_lamports", u64).unwrap_or(0);
@@ -770,18 +1250,97 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         );
     }
 
+    let rent_exemption = match matches.value_of("check_rent_exemption") {
+        Some("error") => Some(RentExemptionCheck::Error),
+        Some("warn") => Some(RentExemptionCheck::Warn),
+        _ => None,
+    };
+    let mut progress_printer = print_progress;
+    let mut progress = if matches.is_present("progress") {
+        ProgressTracker::new(&mut progress_printer)
+    } else {
+        ProgressTracker::default()
+    };
     if let Some(files) = matches.values_of("primordial_accounts_file") {
         for file in files {
-            load_genesis_accounts(file, &mut genesis_config)?;
+            load_genesis_accounts_with_progress(
+                file,
+                &mut genesis_config,
+                rent_exemption,
+                &mut progress,
+            )?;
         }
     }
 
     if let Some(files) = matches.values_of("validator_accounts_file") {
         for file in files {
-            load_validator_accounts(file, commission, &rent, &mut genesis_config)?;
+            load_validator_accounts(file, commission, &rent, &mut genesis_config, rent_exemption)?;
         }
     }
 
+    // Added before the issued lamports are summed, so that the tranche counts
+    // towards the capitalization.
+    let airdrop_tranche_index = matches
+        .value_of("airdrop_tranche_file")
+        .map(|file| {
+            let tranche = load_airdrop_tranche(Path::new(file))?;
+            add_airdrop_tranche(&mut genesis_config, &tranche)
+        })
+        .transpose()?;
+
+    let derivation_cache_path = matches.value_of("derivation_cache").map(PathBuf::from);
+    let derivation_cache = derivation_cache_path
+        .as_ref()
+        .map(|path| DerivationCache::load_or_default(path).map(Arc::new))
+        .transpose()?;
+
+    if let Some(file) = matches.value_of("stake_allocations_csv") {
+        let mut config = StakeAllocationsCsvConfig {
+            max_errors: value_t_or_exit!(matches, "stake_allocations_csv_max_errors", usize),
+            ..StakeAllocationsCsvConfig::default()
+        };
+        if let Some(columns) = matches.value_of("stake_allocations_csv_columns") {
+            config = config
+                .with_columns(columns)
+                .map_err(|err| format!("invalid --stake-allocations-csv-columns: {err}"))?;
+        }
+        let allocations = load_stake_allocations_csv(Path::new(file), &config)?;
+        add_stake_allocations(
+            &mut genesis_config,
+            file,
+            &allocations,
+            derivation_cache.as_ref(),
+            &mut progress,
+        )?;
+    }
+
+    let generated_validators = if let Some(count) = bootstrap_validator_count {
+        let total_stake_lamports =
+            value_t!(matches, "bootstrap_validator_total_stake_lamports", u64)
+                .unwrap_or(bootstrap_validator_stake_lamports * count as u64);
+        let seed = if matches.is_present("bootstrap_validator_seed") {
+            value_t_or_exit!(matches, "bootstrap_validator_seed", u64)
+        } else {
+            0
+        };
+        let validators = generate_bootstrap_validators(
+            count,
+            total_stake_lamports,
+            bootstrap_validator_lamports,
+            seed,
+        );
+        add_generated_validator_accounts(&mut genesis_config, &validators, commission, &rent)?;
+        validators
+    } else {
+        vec![]
+    };
+    let bootstrap_validator_keypairs_dir_path = matches
+        .value_of("bootstrap_validator_keypairs_dir")
+        .map(PathBuf::from);
+    if let Some(dir) = &bootstrap_validator_keypairs_dir_path {
+        write_bootstrap_validator_keypairs(&generated_validators, dir)?;
+    }
+
     let max_genesis_archive_unpacked_size =
         value_t_or_exit!(matches, "max_genesis_archive_unpacked_size", u64);
 
@@ -791,7 +1350,16 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         .map(|account| account.lamports)
         .sum::<u64>();
 
-    add_genesis_accounts(&mut genesis_config, issued_lamports - faucet_lamports);
+    add_genesis_accounts_with_progress(
+        &mut genesis_config,
+        issued_lamports - faucet_lamports,
+        derivation_cache.as_ref(),
+        &mut progress,
+    )?;
+
+    if let (Some(path), Some(derivation_cache)) = (&derivation_cache_path, &derivation_cache) {
+        derivation_cache.save(path)?;
+    }
 
     let parse_address = |address: &str, input_type: &str| {
         address.parse::<Pubkey>().unwrap_or_else(|err| {
@@ -885,15 +1453,80 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         }
     }
 
+    if let Some(path) = matches.value_of("diff_against") {
+        return print_genesis_diff(
+            Path::new(path),
+            &genesis_config,
+            matches.value_of("diff_output") == Some("json"),
+        );
+    }
+
+    let bootstrap_vote_accounts: Vec<_> = bootstrap_validator_pubkeys
+        .chunks(3)
+        .map(|pubkeys| pubkeys[1])
+        .chain(
+            generated_validators
+                .iter()
+                .map(|validator| validator.vote_account.pubkey()),
+        )
+        .collect();
+    if let Err(err) = StakeDistribution::new(&genesis_config).check(&bootstrap_vote_accounts) {
+        if !matches.is_present("allow_unrootable_genesis") {
+            return Err(err.into());
+        }
+        eprintln!("Warning: {err}");
+    }
+
+    if matches.is_present("print_largest_accounts") {
+        let top = value_t!(matches, "print_largest_accounts", usize).ok();
+        print!("{}", largest_accounts(&genesis_config, top));
+    }
+
+    let estimated_genesis_size = estimate_genesis_size(&genesis_config);
+    println!("Estimated genesis size: {estimated_genesis_size} bytes");
+    if estimated_genesis_size > max_genesis_archive_unpacked_size {
+        eprintln!(
+            "Warning: the estimated genesis size exceeds --max-genesis-archive-unpacked-size of \
+             {max_genesis_archive_unpacked_size} bytes"
+        );
+    }
+
     solana_logger::setup();
-    create_new_ledger(
+    write_genesis(
         &ledger_path,
         &genesis_config,
         max_genesis_archive_unpacked_size,
-        LedgerColumnOptions::default(),
+        &mut progress,
     )?;
 
+    if let Some(index) = &airdrop_tranche_index {
+        let path = matches
+            .value_of("airdrop_tranche_index_file")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| ledger_path.join(AIRDROP_TRANCHE_INDEX_FILE));
+        write_airdrop_tranche_index(&path, index)?;
+        println!(
+            "Airdrop tranche of {} accounts indexed in {}",
+            index.len(),
+            path.display()
+        );
+    }
+
     println!("{genesis_config}");
+    if let Some(dir) = &bootstrap_validator_keypairs_dir_path {
+        for (nth, validator) in generated_validators.iter().enumerate() {
+            let info = validator.account_info();
+            println!(
+                "Generated bootstrap validator {nth}: identity {}, vote account {}, stake \
+                 account {}, stake {} lamports, keypairs in {}",
+                info.identity_account,
+                info.vote_account,
+                info.stake_account,
+                info.stake_lamports,
+                bootstrap_validator_keypairs_dir(dir, nth).display(),
+            );
+        }
+    }
     Ok(())
 }
 
@@ -901,8 +1534,16 @@ fn main() -> Result<(), Box<dyn error::Error>> {
 mod tests {
     use {
         super::*,
+        assert_matches::assert_matches,
         solana_borsh::v1 as borsh1,
-        solana_genesis_config::GenesisConfig,
+        solana_genesis::{
+            bootstrap_validators::{
+                IDENTITY_KEYPAIR_FILE, STAKE_ACCOUNT_KEYPAIR_FILE, VOTE_ACCOUNT_KEYPAIR_FILE,
+            },
+            progress::GenesisProgress,
+            Base64Account,
+        },
+        solana_genesis_config::{GenesisConfig, DEFAULT_GENESIS_ARCHIVE},
         solana_stake_interface as stake,
         std::{collections::HashMap, fs::remove_file, io::Write, path::Path},
     };
@@ -910,7 +1551,10 @@ mod tests {
     #[test]
     fn test_append_primordial_accounts_to_genesis() {
         // Test invalid file returns error
-        assert!(load_genesis_accounts("unknownfile", &mut GenesisConfig::default()).is_err());
+        assert_matches!(
+            load_genesis_accounts("unknownfile", &mut GenesisConfig::default(), None),
+            Err(GenesisError::Io(_))
+        );
 
         let mut genesis_config = GenesisConfig::default();
 
@@ -952,6 +1596,7 @@ mod tests {
         load_genesis_accounts(
             "test_append_primordial_accounts_to_genesis.yml",
             &mut genesis_config,
+            None,
         )
         .expect("test_append_primordial_accounts_to_genesis.yml");
         // Test valid file returns ok
@@ -1026,6 +1671,7 @@ mod tests {
         load_genesis_accounts(
             "test_append_primordial_accounts_to_genesis.yml",
             &mut genesis_config,
+            None,
         )
         .expect("test_append_primordial_accounts_to_genesis.yml");
 
@@ -1110,6 +1756,7 @@ mod tests {
         load_genesis_accounts(
             "test_append_primordial_accounts_to_genesis.yml",
             &mut genesis_config,
+            None,
         )
         .expect("genesis");
 
@@ -1210,7 +1857,7 @@ mod tests {
         file.write_all(yaml_string_pubkey.as_bytes()).unwrap();
 
         let mut genesis_config = GenesisConfig::default();
-        load_genesis_accounts(path.to_str().unwrap(), &mut genesis_config).expect("genesis");
+        load_genesis_accounts(path.to_str().unwrap(), &mut genesis_config, None).expect("genesis");
         remove_file(path).unwrap();
 
         assert_eq!(genesis_config.accounts.len(), 4);
@@ -1238,7 +1885,7 @@ mod tests {
         file.write_all(yaml_string_keypair.as_bytes()).unwrap();
 
         let mut genesis_config = GenesisConfig::default();
-        load_genesis_accounts(path.to_str().unwrap(), &mut genesis_config).expect("genesis");
+        load_genesis_accounts(path.to_str().unwrap(), &mut genesis_config, None).expect("genesis");
         remove_file(path).unwrap();
 
         assert_eq!(genesis_config.accounts.len(), 3);
@@ -1251,7 +1898,8 @@ mod tests {
             "unknownfile",
             100,
             &Rent::default(),
-            &mut GenesisConfig::default()
+            &mut GenesisConfig::default(),
+            None
         )
         .is_err());
 
@@ -1264,6 +1912,7 @@ mod tests {
                 stake_account: solana_pubkey::new_rand().to_string(),
                 balance_lamports: 100000000000,
                 stake_lamports: 10000000000,
+                stake_accounts: vec![],
             },
             StakedValidatorAccountInfo {
                 identity_account: solana_pubkey::new_rand().to_string(),
@@ -1271,6 +1920,7 @@ mod tests {
                 stake_account: solana_pubkey::new_rand().to_string(),
                 balance_lamports: 200000000000,
                 stake_lamports: 20000000000,
+                stake_accounts: vec![],
             },
             StakedValidatorAccountInfo {
                 identity_account: solana_pubkey::new_rand().to_string(),
@@ -1278,6 +1928,7 @@ mod tests {
                 stake_account: solana_pubkey::new_rand().to_string(),
                 balance_lamports: 300000000000,
                 stake_lamports: 30000000000,
+                stake_accounts: vec![],
             },
         ];
 
@@ -1294,6 +1945,7 @@ mod tests {
             100,
             &Rent::default(),
             &mut genesis_config,
+            None,
         )
         .expect("Failed to load validator accounts");
 
@@ -1363,4 +2015,479 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_load_validator_accounts_stake_accounts() {
+        let rent = Rent::default();
+        let stake_rent_exempt_reserve = rent.minimum_balance(StakeStateV2::size_of());
+        let single = StakedValidatorAccountInfo {
+            identity_account: solana_pubkey::new_rand().to_string(),
+            vote_account: solana_pubkey::new_rand().to_string(),
+            stake_account: solana_pubkey::new_rand().to_string(),
+            balance_lamports: 100_000_000_000,
+            stake_lamports: 10_000_000_000,
+            stake_accounts: vec![],
+        };
+        let stake_accounts: Vec<_> = [1_000_000_000, 2_000_000_000, 3_000_000_000]
+            .into_iter()
+            .map(|stake_lamports| ValidatorStakeAccount {
+                stake_account: solana_pubkey::new_rand().to_string(),
+                stake_lamports,
+            })
+            .collect();
+        let multiple = StakedValidatorAccountInfo {
+            identity_account: solana_pubkey::new_rand().to_string(),
+            vote_account: solana_pubkey::new_rand().to_string(),
+            stake_account: String::new(),
+            balance_lamports: 100_000_000_000,
+            stake_lamports: 0,
+            stake_accounts: stake_accounts.clone(),
+        };
+        let accounts_file = tempfile::NamedTempFile::new().unwrap();
+        serde_yaml::to_writer(
+            accounts_file.as_file(),
+            &ValidatorAccountsFile {
+                validator_accounts: vec![single.clone(), multiple.clone()],
+            },
+        )
+        .unwrap();
+        let mut genesis_config = GenesisConfig::default();
+        load_validator_accounts(
+            accounts_file.path().to_str().unwrap(),
+            100,
+            &rent,
+            &mut genesis_config,
+            None,
+        )
+        .unwrap();
+
+        // identity, vote and stake accounts
+        assert_eq!(
+            genesis_config.accounts.len(),
+            2 + 2 + 1 + stake_accounts.len()
+        );
+        let delegated_stake = |vote_account: &str| -> (usize, u64) {
+            let vote_pubkey: Pubkey = vote_account.parse().unwrap();
+            genesis_config
+                .accounts
+                .values()
+                .filter(|account| account.owner == solana_stake_program::id())
+                .filter_map(|account| {
+                    let stake_state =
+                        borsh1::try_from_slice_unchecked::<StakeStateV2>(&account.data).unwrap();
+                    let delegation = stake_state.delegation().unwrap();
+                    (delegation.voter_pubkey == vote_pubkey).then_some(delegation.stake)
+                })
+                .fold((0, 0), |(count, total), stake| (count + 1, total + stake))
+        };
+        assert_eq!(
+            delegated_stake(&single.vote_account),
+            (1, single.stake_lamports - stake_rent_exempt_reserve)
+        );
+        assert_eq!(
+            delegated_stake(&multiple.vote_account),
+            (3, 6_000_000_000 - 3 * stake_rent_exempt_reserve,)
+        );
+        for stake_account in &stake_accounts {
+            let stake_pubkey: Pubkey = stake_account.stake_account.parse().unwrap();
+            assert_eq!(
+                genesis_config.accounts[&stake_pubkey].lamports,
+                stake_account.stake_lamports
+            );
+        }
+
+        // both forms at once are rejected
+        let both = StakedValidatorAccountInfo {
+            stake_account: solana_pubkey::new_rand().to_string(),
+            ..multiple
+        };
+        let accounts_file = tempfile::NamedTempFile::new().unwrap();
+        serde_yaml::to_writer(
+            accounts_file.as_file(),
+            &ValidatorAccountsFile {
+                validator_accounts: vec![both],
+            },
+        )
+        .unwrap();
+        assert_matches!(
+            load_validator_accounts(
+                accounts_file.path().to_str().unwrap(),
+                100,
+                &rent,
+                &mut GenesisConfig::default(),
+                None,
+            ),
+            Err(GenesisError::Validation { field, .. }) if field == "stake_accounts"
+        );
+
+        // as is a file that is not a validator accounts file
+        let accounts_file = tempfile::NamedTempFile::new().unwrap();
+        accounts_file
+            .as_file()
+            .write_all(b"validator_accounts:\n- balance_lamports: many\n")
+            .unwrap();
+        assert_matches!(
+            load_validator_accounts(
+                accounts_file.path().to_str().unwrap(),
+                100,
+                &rent,
+                &mut GenesisConfig::default(),
+                None,
+            ),
+            Err(GenesisError::Parse { file, .. })
+                if file == accounts_file.path().to_str().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_load_accounts_rent_exemption() {
+        let rent = Rent::default();
+        let funded = solana_pubkey::new_rand().to_string();
+        let underfunded = solana_pubkey::new_rand().to_string();
+        let account = |balance| Base64Account {
+            owner: solana_pubkey::new_rand().to_string(),
+            balance,
+            executable: false,
+            data: BASE64_STANDARD.encode([0; 100]),
+        };
+        let accounts_file = tempfile::NamedTempFile::new().unwrap();
+        serde_yaml::to_writer(
+            accounts_file.as_file(),
+            &HashMap::from([
+                (funded.clone(), account(rent.minimum_balance(100))),
+                (underfunded.clone(), account(rent.minimum_balance(100) - 1)),
+            ]),
+        )
+        .unwrap();
+        let load_genesis_accounts = |rent_exemption| {
+            let mut genesis_config = GenesisConfig {
+                rent: rent.clone(),
+                ..GenesisConfig::default()
+            };
+            load_genesis_accounts(
+                accounts_file.path().to_str().unwrap(),
+                &mut genesis_config,
+                rent_exemption,
+            )
+            .map(|_| genesis_config.accounts.len())
+        };
+
+        // the underfunded account is named, the rent-exempt one is not
+        assert_matches!(
+            load_genesis_accounts(Some(RentExemptionCheck::Error)),
+            Err(GenesisError::Validation { entry, field, .. })
+                if entry == format!("account {underfunded}") && field == "balance_lamports"
+        );
+        assert_matches!(load_genesis_accounts(Some(RentExemptionCheck::Warn)), Ok(2));
+        assert_matches!(load_genesis_accounts(None), Ok(2));
+
+        let validator = |balance_lamports| StakedValidatorAccountInfo {
+            identity_account: solana_pubkey::new_rand().to_string(),
+            vote_account: solana_pubkey::new_rand().to_string(),
+            stake_account: solana_pubkey::new_rand().to_string(),
+            balance_lamports,
+            stake_lamports: 10_000_000_000,
+            stake_accounts: vec![],
+        };
+        let underfunded = validator(rent.minimum_balance(0) - 1);
+        let accounts_file = tempfile::NamedTempFile::new().unwrap();
+        serde_yaml::to_writer(
+            accounts_file.as_file(),
+            &ValidatorAccountsFile {
+                validator_accounts: vec![validator(rent.minimum_balance(0)), underfunded.clone()],
+            },
+        )
+        .unwrap();
+        assert_matches!(
+            load_validator_accounts(
+                accounts_file.path().to_str().unwrap(),
+                100,
+                &rent,
+                &mut GenesisConfig::default(),
+                Some(RentExemptionCheck::Error),
+            ),
+            Err(GenesisError::Validation { entry, .. })
+                if entry == format!("validator {}", underfunded.identity_account)
+        );
+    }
+
+    #[test]
+    fn test_add_generated_validator_accounts() {
+        let rent = Rent::default();
+        let mut genesis_config = GenesisConfig::default();
+
+        // an entry from a validator accounts file, added first
+        let file_validator = StakedValidatorAccountInfo {
+            identity_account: solana_pubkey::new_rand().to_string(),
+            vote_account: solana_pubkey::new_rand().to_string(),
+            stake_account: solana_pubkey::new_rand().to_string(),
+            balance_lamports: 100_000_000_000,
+            stake_lamports: 10_000_000_000,
+            stake_accounts: vec![],
+        };
+        let accounts_file = tempfile::NamedTempFile::new().unwrap();
+        serde_yaml::to_writer(
+            accounts_file.as_file(),
+            &ValidatorAccountsFile {
+                validator_accounts: vec![file_validator],
+            },
+        )
+        .unwrap();
+        load_validator_accounts(
+            accounts_file.path().to_str().unwrap(),
+            100,
+            &rent,
+            &mut genesis_config,
+            None,
+        )
+        .unwrap();
+
+        let count = 3;
+        let total_stake_lamports = 100_000_000_001;
+        let validators =
+            generate_bootstrap_validators(count, total_stake_lamports, 500_000_000_000, 42);
+        add_generated_validator_accounts(&mut genesis_config, &validators, 100, &rent).unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        write_bootstrap_validator_keypairs(&validators, dir.path()).unwrap();
+
+        let owned_by = |owner: &Pubkey| {
+            genesis_config
+                .accounts
+                .values()
+                .filter(|account| account.owner == *owner)
+                .count()
+        };
+        assert_eq!(owned_by(&system_program::id()), count + 1);
+        assert_eq!(owned_by(&solana_vote_program::id()), count + 1);
+        assert_eq!(owned_by(&solana_stake_program::id()), count + 1);
+
+        let generated_stake: u64 = validators
+            .iter()
+            .map(|validator| genesis_config.accounts[&validator.stake_account.pubkey()].lamports)
+            .sum();
+        assert_eq!(generated_stake, total_stake_lamports);
+
+        for (nth, validator) in validators.iter().enumerate() {
+            let keypairs_dir = bootstrap_validator_keypairs_dir(dir.path(), nth);
+            let identity = read_keypair_file(keypairs_dir.join(IDENTITY_KEYPAIR_FILE)).unwrap();
+            let vote_account =
+                read_keypair_file(keypairs_dir.join(VOTE_ACCOUNT_KEYPAIR_FILE)).unwrap();
+            let stake_account =
+                read_keypair_file(keypairs_dir.join(STAKE_ACCOUNT_KEYPAIR_FILE)).unwrap();
+
+            assert_eq!(
+                genesis_config.accounts[&identity.pubkey()].lamports,
+                500_000_000_000
+            );
+            let vote_state =
+                VoteState::deserialize(&genesis_config.accounts[&vote_account.pubkey()].data)
+                    .unwrap();
+            assert_eq!(vote_state.node_pubkey, identity.pubkey());
+            let stake_state = borsh1::try_from_slice_unchecked::<StakeStateV2>(
+                &genesis_config.accounts[&stake_account.pubkey()].data,
+            )
+            .unwrap();
+            assert_eq!(
+                stake_state.delegation().unwrap().voter_pubkey,
+                vote_account.pubkey()
+            );
+            assert_eq!(stake_account.pubkey(), validator.stake_account.pubkey());
+        }
+
+        // generating the same validators again collides with the existing accounts
+        assert_matches!(
+            add_generated_validator_accounts(
+                &mut genesis_config,
+                &generate_bootstrap_validators(1, total_stake_lamports, 500_000_000_000, 42),
+                100,
+                &rent,
+            ),
+            Err(GenesisError::Conflict { pubkey, .. })
+                if pubkey == validators[0].identity.pubkey()
+        );
+
+        // without rent, a validator staked with nothing is still rejected
+        assert_matches!(
+            add_generated_validator_accounts(
+                &mut GenesisConfig::default(),
+                &generate_bootstrap_validators(1, 0, 500_000_000_000, 7),
+                100,
+                &Rent::free(),
+            ),
+            Err(GenesisError::Validation { field, .. }) if field == "stake_lamports"
+        );
+    }
+
+    #[test]
+    fn test_check_genesis_compatibility_fix() {
+        let ledger_dir = tempfile::tempdir().unwrap();
+        let ledger_path = ledger_dir.path();
+        let max_genesis_archive_unpacked_size = 10 * 1024 * 1024;
+        create_new_ledger(
+            ledger_path,
+            &GenesisConfig::default(),
+            max_genesis_archive_unpacked_size,
+            LedgerColumnOptions::default(),
+        )
+        .unwrap();
+        let archive_path = ledger_path.join(DEFAULT_GENESIS_ARCHIVE);
+        remove_file(&archive_path).unwrap();
+
+        let feature_id = Pubkey::new_unique();
+        let target = ValidatorTarget {
+            required_features: vec![feature_id],
+        };
+        assert!(check_genesis_compatibility(
+            ledger_path,
+            &target,
+            false,
+            max_genesis_archive_unpacked_size
+        )
+        .is_err());
+        assert!(!archive_path.exists());
+
+        check_genesis_compatibility(
+            ledger_path,
+            &target,
+            true,
+            max_genesis_archive_unpacked_size,
+        )
+        .unwrap();
+        assert!(archive_path.exists());
+        let genesis_config = GenesisConfig::load(ledger_path).unwrap();
+        assert!(genesis_config.accounts.contains_key(&feature_id));
+        check_genesis_compatibility(
+            ledger_path,
+            &target,
+            false,
+            max_genesis_archive_unpacked_size,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_genesis_build_progress() {
+        let num_accounts = 25_000;
+        let accounts_file = tempfile::NamedTempFile::new().unwrap();
+        serde_yaml::to_writer(
+            accounts_file.as_file(),
+            &(0..num_accounts)
+                .map(|_| {
+                    (
+                        solana_pubkey::new_rand().to_string(),
+                        Base64Account {
+                            owner: system_program::id().to_string(),
+                            balance: 1_000_000_000,
+                            executable: false,
+                            data: "~".to_string(),
+                        },
+                    )
+                })
+                .collect::<HashMap<_, _>>(),
+        )
+        .unwrap();
+        let file = accounts_file.path().to_str().unwrap();
+        let ledger_dir = tempfile::TempDir::new().unwrap();
+        let ledger_path = ledger_dir.path().to_str().unwrap();
+
+        let mut reports = vec![];
+        let mut callback = |progress: GenesisProgress| {
+            reports.push((
+                progress.phase,
+                progress.source.to_string(),
+                progress.done,
+                progress.total,
+                progress.elapsed,
+            ))
+        };
+        let mut progress = ProgressTracker::new(&mut callback);
+        let mut genesis_config = GenesisConfig {
+            cluster_type: ClusterType::MainnetBeta,
+            ..GenesisConfig::default()
+        };
+        load_genesis_accounts_with_progress(file, &mut genesis_config, None, &mut progress)
+            .unwrap();
+        add_genesis_accounts_with_progress(&mut genesis_config, 0, None, &mut progress).unwrap();
+        write_genesis(
+            ledger_dir.path(),
+            &genesis_config,
+            MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
+            &mut progress,
+        )
+        .unwrap();
+        drop(progress);
+
+        // every declared phase is reported, in order
+        let phases: Vec<_> = reports
+            .iter()
+            .map(|(phase, source, ..)| (*phase, source.as_str()))
+            .dedup()
+            .collect();
+        assert_eq!(
+            phases,
+            vec![
+                (GenesisPhase::LoadFile, file),
+                (GenesisPhase::Validation, file),
+                (GenesisPhase::CreateAccounts, file),
+                (GenesisPhase::CreateAccounts, "creator stakes"),
+                (GenesisPhase::CreateAccounts, "service stakes"),
+                (GenesisPhase::CreateAccounts, "foundation stakes"),
+                (GenesisPhase::CreateAccounts, "grants stakes"),
+                (GenesisPhase::CreateAccounts, "community stakes"),
+                (GenesisPhase::CreateAccounts, "community pool stakes"),
+                (GenesisPhase::Serialization, ledger_path),
+            ]
+        );
+
+        // progress only moves forward
+        assert!(reports.windows(2).all(|reports| {
+            let (phase, source, done, _, elapsed) = &reports[0];
+            let (next_phase, next_source, next_done, _, next_elapsed) = &reports[1];
+            phase <= next_phase
+                && elapsed <= next_elapsed
+                && (phase != next_phase || source != next_source || done <= next_done)
+        }));
+
+        // each phase ends at its total, reported every PROGRESS_INTERVAL items
+        let reports_of = |phase| {
+            reports
+                .iter()
+                .filter(move |report| report.0 == phase)
+                .map(|(_, _, done, total, _)| (*done, *total))
+                .collect::<Vec<_>>()
+        };
+        let total = Some(num_accounts);
+        assert_eq!(
+            reports_of(GenesisPhase::Validation),
+            vec![
+                (0, total),
+                (10_000, total),
+                (20_000, total),
+                (num_accounts, total)
+            ]
+        );
+        assert_eq!(
+            reports_of(GenesisPhase::CreateAccounts).get(..4),
+            Some(
+                &[
+                    (0, total),
+                    (10_000, total),
+                    (20_000, total),
+                    (num_accounts, total)
+                ][..]
+            )
+        );
+        assert_eq!(
+            reports_of(GenesisPhase::LoadFile).last(),
+            Some(&(num_accounts, None))
+        );
+        let total_accounts = genesis_config.accounts.len();
+        assert_eq!(
+            reports_of(GenesisPhase::Serialization),
+            vec![
+                (0, Some(total_accounts)),
+                (total_accounts, Some(total_accounts))
+            ]
+        );
+    }
 }
@@ -0,0 +1,200 @@
+//! Progress of long running genesis builds
+//!
+//! Building a genesis config of millions of accounts takes minutes, so the
+//! builder reports what it is doing to a callback. The callback is invoked
+//! when a phase starts, every `PROGRESS_INTERVAL` items and when it finishes,
+//! so that reporting does not slow the build down.
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Number of items processed between two reports of a phase
+pub const PROGRESS_INTERVAL: usize = 10_000;
+
+/// Phases of a genesis build, in the order they run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GenesisPhase {
+    /// Reading and parsing an accounts file
+    LoadFile,
+    /// Validating the accounts of a file
+    Validation,
+    /// Adding the accounts of a source, e.g. a file or a group of stakes
+    CreateAccounts,
+    /// Writing the genesis config to the ledger
+    Serialization,
+}
+
+impl fmt::Display for GenesisPhase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::LoadFile => "loading",
+            Self::Validation => "validating",
+            Self::CreateAccounts => "creating accounts of",
+            Self::Serialization => "serializing",
+        })
+    }
+}
+
+/// Progress of a phase, as reported to the callback of a `ProgressTracker`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GenesisProgress<'a> {
+    pub phase: GenesisPhase,
+    /// What the phase works on, e.g. the file being loaded
+    pub source: &'a str,
+    pub done: usize,
+    /// Number of items of the phase, if known upfront
+    pub total: Option<usize>,
+    /// Time since the tracker was created
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for GenesisProgress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}: {}", self.phase, self.source, self.done)?;
+        if let Some(total) = self.total {
+            write!(f, "/{total}")?;
+        }
+        write!(f, " after {:.1}s", self.elapsed.as_secs_f64())
+    }
+}
+
+/// Prints each report to stderr, for `--progress`
+pub fn print_progress(progress: GenesisProgress) {
+    eprintln!("{progress}");
+}
+
+struct Phase {
+    phase: GenesisPhase,
+    source: String,
+    total: Option<usize>,
+    done: usize,
+    reported: usize,
+}
+
+/// Counts the items of the current phase of a build and reports them to a
+/// callback. The default tracker has no callback and does nothing.
+#[derive(Default)]
+pub struct ProgressTracker<'a> {
+    callback: Option<&'a mut dyn FnMut(GenesisProgress)>,
+    start: Option<Instant>,
+    phase: Option<Phase>,
+}
+
+impl<'a> ProgressTracker<'a> {
+    pub fn new(callback: &'a mut dyn FnMut(GenesisProgress)) -> Self {
+        Self {
+            callback: Some(callback),
+            start: Some(Instant::now()),
+            phase: None,
+        }
+    }
+
+    /// Finish the current phase, if any, and start `phase` of `source`
+    pub fn start_phase(&mut self, phase: GenesisPhase, source: &str, total: Option<usize>) {
+        if self.callback.is_none() {
+            return;
+        }
+        self.finish_phase();
+        self.phase = Some(Phase {
+            phase,
+            source: source.to_string(),
+            total,
+            done: 0,
+            reported: 0,
+        });
+        self.report();
+    }
+
+    /// Count an item of the current phase
+    pub fn inc(&mut self) {
+        self.add(1);
+    }
+
+    /// Count `count` items of the current phase
+    pub fn add(&mut self, count: usize) {
+        let Some(phase) = self.phase.as_mut() else {
+            return;
+        };
+        phase.done = phase.done.saturating_add(count);
+        if phase.done.saturating_sub(phase.reported) >= PROGRESS_INTERVAL {
+            self.report();
+        }
+    }
+
+    /// Report the final count of the current phase, unless it was just
+    /// reported
+    pub fn finish_phase(&mut self) {
+        if let Some(phase) = self.phase.as_ref() {
+            if phase.done != phase.reported {
+                self.report();
+            }
+        }
+        self.phase = None;
+    }
+
+    fn report(&mut self) {
+        let (Some(callback), Some(phase)) = (self.callback.as_mut(), self.phase.as_mut()) else {
+            return;
+        };
+        phase.reported = phase.done;
+        callback(GenesisProgress {
+            phase: phase.phase,
+            source: &phase.source,
+            done: phase.done,
+            total: phase.total,
+            elapsed: self.start.map(|start| start.elapsed()).unwrap_or_default(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_tracker() {
+        let mut reports = vec![];
+        let mut callback = |progress: GenesisProgress| {
+            reports.push((progress.phase, progress.source.to_string(), progress.done))
+        };
+        let mut progress = ProgressTracker::new(&mut callback);
+        progress.start_phase(GenesisPhase::Validation, "accounts.yml", Some(25_000));
+        for _ in 0..25_000 {
+            progress.inc();
+        }
+        progress.start_phase(GenesisPhase::CreateAccounts, "accounts.yml", Some(20_000));
+        progress.add(20_000);
+        progress.finish_phase();
+        // nothing to report outside of a phase
+        progress.inc();
+        progress.finish_phase();
+        drop(progress);
+
+        let validation = |done| (GenesisPhase::Validation, "accounts.yml".to_string(), done);
+        let creation = |done| {
+            (
+                GenesisPhase::CreateAccounts,
+                "accounts.yml".to_string(),
+                done,
+            )
+        };
+        assert_eq!(
+            reports,
+            vec![
+                validation(0),
+                validation(10_000),
+                validation(20_000),
+                validation(25_000),
+                creation(0),
+                creation(20_000),
+            ]
+        );
+
+        // the default tracker reports nothing
+        let mut progress = ProgressTracker::default();
+        progress.start_phase(GenesisPhase::LoadFile, "accounts.yml", None);
+        progress.add(PROGRESS_INTERVAL);
+        progress.finish_phase();
+    }
+}
@@ -0,0 +1,95 @@
+//! Rent exemption of the accounts loaded into genesis
+//!
+//! An account funded below the rent-exempt minimum of its data length may be
+//! collected or rejected by the first transactions touching it once the
+//! cluster runs. The check is optional, the accounts being created as given
+//! otherwise.
+use {crate::error::GenesisError, solana_rent::Rent, std::fmt::Display};
+
+/// How an account below the rent-exempt minimum is reported
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RentExemptionCheck {
+    Error,
+    Warn,
+}
+
+impl RentExemptionCheck {
+    /// Report `entry` if its `lamports` are below the minimum balance under
+    /// `rent` of an account holding `data_len` bytes
+    pub fn check(
+        self,
+        rent: &Rent,
+        entry: impl Display,
+        lamports: u64,
+        data_len: usize,
+    ) -> Result<(), GenesisError> {
+        let minimum_balance = rent.minimum_balance(data_len);
+        if lamports >= minimum_balance {
+            return Ok(());
+        }
+        let err = GenesisError::validation(
+            entry,
+            "balance_lamports",
+            format_args!(
+                "{lamports} lamports is below the rent-exempt minimum of {minimum_balance} for \
+                 {data_len} bytes of data"
+            ),
+        );
+        match self {
+            Self::Error => Err(err),
+            Self::Warn => {
+                eprintln!("Warning: {err}");
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, assert_matches::assert_matches};
+
+    #[test]
+    fn test_rent_exempt_account() {
+        let rent = Rent::default();
+        for data_len in [0, 200, 10 * 1024] {
+            let minimum_balance = rent.minimum_balance(data_len);
+            assert_matches!(
+                RentExemptionCheck::Error.check(&rent, "account foo", minimum_balance, data_len),
+                Ok(())
+            );
+            assert_matches!(
+                RentExemptionCheck::Error.check(&rent, "account foo", u64::MAX, data_len),
+                Ok(())
+            );
+        }
+        // nothing is exempt from a free rent
+        assert_matches!(
+            RentExemptionCheck::Error.check(&Rent::free(), "account foo", 0, 1024),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_underfunded_account() {
+        let rent = Rent::default();
+        let minimum_balance = rent.minimum_balance(200);
+        let err = RentExemptionCheck::Error
+            .check(&rent, "account foo", minimum_balance - 1, 200)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "invalid balance_lamports of account foo: {} lamports is below the rent-exempt \
+                 minimum of {minimum_balance} for 200 bytes of data",
+                minimum_balance - 1
+            )
+        );
+
+        // only reported as a warning
+        assert_matches!(
+            RentExemptionCheck::Warn.check(&rent, "account foo", 0, 200),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Check that the stake of a genesis config can root the first slots
+//!
+//! A slot is rooted once more than `VOTE_THRESHOLD_SIZE` of the stake of its
+//! epoch has voted on it and its descendants. At genesis, the only votes are
+//! those of the validators starting the cluster, so their delegated stake must
+//! exceed that share of the stake effective in the epoch of slot 1, or the
+//! cluster stalls at slot 0.
+//!
+//! The effective stake follows the activation of each delegation: the stake
+//! created by genesis is active from the first epoch, any other stake is
+//! still warming up and does not count yet.
+use {
+    crate::error::GenesisError,
+    solana_account::ReadableAccount,
+    solana_clock::{Epoch, Slot},
+    solana_genesis_config::GenesisConfig,
+    solana_pubkey::Pubkey,
+    solana_runtime::{commitment::VOTE_THRESHOLD_SIZE, stake_history::StakeHistory},
+    solana_sdk_ids::{stake, vote},
+    solana_stake_interface::state::StakeStateV2,
+    std::{
+        collections::{BTreeMap, HashSet},
+        fmt,
+    },
+};
+
+/// The first slot that must be rooted after genesis
+const FIRST_ROOTED_SLOT: Slot = 1;
+
+/// Stake effective in the epoch of the first rooted slot, per vote account
+#[derive(Debug, PartialEq)]
+pub struct StakeDistribution {
+    pub epoch: Epoch,
+    pub slots_in_epoch: u64,
+    pub total_stake: u64,
+    /// `(vote_pubkey, stake)`, largest stake first
+    pub validators: Vec<(Pubkey, u64)>,
+}
+
+impl StakeDistribution {
+    /// The stake delegated by the stake accounts of `genesis_config` to its
+    /// vote accounts
+    pub fn new(genesis_config: &GenesisConfig) -> Self {
+        let epoch = genesis_config.epoch_schedule.get_epoch(FIRST_ROOTED_SLOT);
+        let vote_accounts: HashSet<_> = genesis_config
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.owner() == &vote::id())
+            .map(|(pubkey, _)| *pubkey)
+            .collect();
+
+        let stake_history = StakeHistory::default();
+        let mut stakes = BTreeMap::<Pubkey, u64>::new();
+        for account in genesis_config.accounts.values() {
+            if account.owner() != &stake::id() {
+                continue;
+            }
+            let Ok(StakeStateV2::Stake(_, stake, _)) = bincode::deserialize(account.data()) else {
+                continue;
+            };
+            let delegation = stake.delegation;
+            // stake delegated to a missing vote account can never vote
+            if !vote_accounts.contains(&delegation.voter_pubkey) {
+                continue;
+            }
+            let effective_stake = delegation.stake(epoch, &stake_history, None);
+            if effective_stake > 0 {
+                *stakes.entry(delegation.voter_pubkey).or_default() += effective_stake;
+            }
+        }
+
+        let mut validators: Vec<_> = stakes.into_iter().collect();
+        validators.sort_by(|(pubkey, stake), (other_pubkey, other_stake)| {
+            other_stake.cmp(stake).then(pubkey.cmp(other_pubkey))
+        });
+        Self {
+            epoch,
+            slots_in_epoch: genesis_config.epoch_schedule.get_slots_in_epoch(epoch),
+            total_stake: validators.iter().map(|(_, stake)| stake).sum(),
+            validators,
+        }
+    }
+
+    fn share(&self, stake: u64) -> f64 {
+        if self.total_stake == 0 {
+            0.0
+        } else {
+            stake as f64 / self.total_stake as f64
+        }
+    }
+
+    /// Fails unless a single validator, or the validators of
+    /// `bootstrap_vote_accounts` together, hold more than the share of the
+    /// stake needed to root a slot
+    pub fn check(&self, bootstrap_vote_accounts: &[Pubkey]) -> Result<(), GenesisError> {
+        let largest_stake = self.validators.first().map_or(0, |(_, stake)| *stake);
+        let bootstrap_vote_accounts: HashSet<_> = bootstrap_vote_accounts.iter().collect();
+        let bootstrap_stake = self
+            .validators
+            .iter()
+            .filter(|(pubkey, _)| bootstrap_vote_accounts.contains(pubkey))
+            .map(|(_, stake)| stake)
+            .sum();
+        let share = self.share(largest_stake.max(bootstrap_stake));
+        if share > VOTE_THRESHOLD_SIZE {
+            Ok(())
+        } else {
+            Err(GenesisError::Unrootable {
+                epoch: self.epoch,
+                percent: share * 100.0,
+                threshold_percent: VOTE_THRESHOLD_SIZE * 100.0,
+                breakdown: self.to_string(),
+            })
+        }
+    }
+}
+
+impl fmt::Display for StakeDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Stake of epoch {} ({} slots): {} lamports over {} validators",
+            self.epoch,
+            self.slots_in_epoch,
+            self.total_stake,
+            self.validators.len(),
+        )?;
+        for (vote_pubkey, stake) in &self.validators {
+            write!(
+                f,
+                "\n  vote account {vote_pubkey}: {stake} lamports ({:.2}%)",
+                self.share(*stake) * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::stakes::add_validator_stakes, assert_matches::assert_matches,
+        solana_account::AccountSharedData, solana_epoch_schedule::EpochSchedule, solana_rent::Rent,
+        solana_stake_program::stake_state::create_account_with_activation_epoch,
+        solana_vote_program::vote_state,
+    };
+
+    /// Add a validator with a vote account delegated `stake` lamports, returning
+    /// its vote account
+    fn add_validator(genesis_config: &mut GenesisConfig, stake: u64) -> Pubkey {
+        let identity = Pubkey::new_unique();
+        let vote_pubkey = Pubkey::new_unique();
+        let vote_account = vote_state::create_account(&vote_pubkey, &identity, 0, 1);
+        add_validator_stakes(
+            genesis_config,
+            &identity,
+            &vote_pubkey,
+            &vote_account,
+            &Rent::free(),
+            &[(Pubkey::new_unique(), stake)],
+        );
+        genesis_config.add_account(vote_pubkey, vote_account);
+        vote_pubkey
+    }
+
+    fn genesis_config() -> GenesisConfig {
+        GenesisConfig {
+            epoch_schedule: EpochSchedule::custom(8192, 8192, true),
+            rent: Rent::free(),
+            ..GenesisConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_rootable_stake_distribution() {
+        let mut genesis_config = genesis_config();
+        let bootstrap = add_validator(&mut genesis_config, 700);
+        let other = add_validator(&mut genesis_config, 300);
+
+        let distribution = StakeDistribution::new(&genesis_config);
+        assert_eq!(
+            distribution,
+            StakeDistribution {
+                epoch: 0,
+                slots_in_epoch: 32,
+                total_stake: 1000,
+                validators: vec![(bootstrap, 700), (other, 300)],
+            }
+        );
+        assert!(distribution.check(&[bootstrap]).is_ok());
+        // the largest validator alone is enough
+        assert!(distribution.check(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_bootstrap_set_stake() {
+        let mut genesis_config = genesis_config();
+        let bootstrap: Vec<_> = [250, 250, 250]
+            .into_iter()
+            .map(|stake| add_validator(&mut genesis_config, stake))
+            .collect();
+        for _ in 0..5 {
+            add_validator(&mut genesis_config, 50);
+        }
+
+        // no validator holds a supermajority, the bootstrap set does
+        let distribution = StakeDistribution::new(&genesis_config);
+        assert_eq!(distribution.total_stake, 1000);
+        assert!(distribution.check(&bootstrap).is_ok());
+        assert_matches!(
+            distribution.check(&bootstrap[..2]),
+            Err(GenesisError::Unrootable { epoch: 0, percent, .. }) if percent == 50.0
+        );
+    }
+
+    #[test]
+    fn test_unrootable_stake_distribution() {
+        // zero-stake validators do not dilute the stake, many small ones do
+        let mut genesis_config = genesis_config();
+        let bootstrap = add_validator(&mut genesis_config, 600);
+        for _ in 0..10 {
+            add_validator(&mut genesis_config, 0);
+        }
+        for _ in 0..4 {
+            add_validator(&mut genesis_config, 100);
+        }
+
+        let distribution = StakeDistribution::new(&genesis_config);
+        assert_eq!(distribution.validators.len(), 5);
+        let err = distribution.check(&[bootstrap]).unwrap_err();
+        assert_matches!(
+            err,
+            GenesisError::Unrootable { percent, .. } if percent == 60.0
+        );
+        let message = err.to_string();
+        assert!(message.contains(&format!("vote account {bootstrap}: 600 lamports (60.00%)")));
+        assert_eq!(message.matches("lamports (10.00%)").count(), 4);
+
+        // nor can a genesis without stake root anything
+        assert_matches!(
+            StakeDistribution::new(&GenesisConfig::default()).check(&[]),
+            Err(GenesisError::Unrootable { .. })
+        );
+    }
+
+    #[test]
+    fn test_stake_distribution_at_threshold() {
+        // exactly at the threshold is not more than it
+        let mut genesis_config = genesis_config();
+        let bootstrap = add_validator(&mut genesis_config, 200);
+        add_validator(&mut genesis_config, 100);
+        let distribution = StakeDistribution::new(&genesis_config);
+        assert_eq!(
+            distribution.share(200),
+            VOTE_THRESHOLD_SIZE,
+            "the share must hit the threshold exactly"
+        );
+        assert_matches!(
+            distribution.check(&[bootstrap]),
+            Err(GenesisError::Unrootable { .. })
+        );
+
+        // a single lamport more is enough
+        let mut genesis_config = self::genesis_config();
+        let bootstrap = add_validator(&mut genesis_config, 201);
+        add_validator(&mut genesis_config, 100);
+        assert!(StakeDistribution::new(&genesis_config)
+            .check(&[bootstrap])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_warming_up_stake() {
+        // stake activated in the first epoch is not effective yet
+        let mut genesis_config = genesis_config();
+        let bootstrap = add_validator(&mut genesis_config, 100);
+        let other = add_validator(&mut genesis_config, 0);
+        let vote_account = AccountSharedData::from(genesis_config.accounts[&other].clone());
+        genesis_config.add_account(
+            Pubkey::new_unique(),
+            create_account_with_activation_epoch(
+                &Pubkey::new_unique(),
+                &other,
+                &vote_account,
+                &Rent::free(),
+                1_000,
+                0,
+            ),
+        );
+
+        let distribution = StakeDistribution::new(&genesis_config);
+        assert_eq!(distribution.validators, [(bootstrap, 100)]);
+        assert!(distribution.check(&[bootstrap]).is_ok());
+
+        // nor is stake delegated to a vote account missing from genesis
+        let mut genesis_config = self::genesis_config();
+        let bootstrap = add_validator(&mut genesis_config, 100);
+        let missing = add_validator(&mut genesis_config, 1_000);
+        genesis_config.accounts.remove(&missing);
+        assert_eq!(
+            StakeDistribution::new(&genesis_config).validators,
+            [(bootstrap, 100)]
+        );
+    }
+}
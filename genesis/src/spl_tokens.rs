@@ -0,0 +1,349 @@
+//! SPL token mints and their pre-minted balances, declared in the
+//! `spl_tokens` section of a primordial accounts file
+//!
+//! Each mint is created with the given supply, and each holder is given an
+//! associated token account holding its amount. Mints of either the token or
+//! the token-2022 program are supported, the latter without extensions.
+use {
+    crate::error::GenesisError,
+    serde::{Deserialize, Serialize},
+    solana_account::AccountSharedData,
+    solana_genesis_config::GenesisConfig,
+    solana_pubkey::Pubkey,
+    spl_token_2022::{
+        solana_program::{program_option::COption, program_pack::Pack},
+        state::{Account, AccountState, Mint},
+    },
+    std::{collections::HashSet, str::FromStr},
+};
+
+/// Program owning a mint and its token accounts
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SplTokenProgram {
+    #[default]
+    Token,
+    Token2022,
+}
+
+impl SplTokenProgram {
+    pub fn id(&self) -> Pubkey {
+        match self {
+            Self::Token => solana_inline_spl::token::id(),
+            Self::Token2022 => solana_inline_spl::token_2022::id(),
+        }
+    }
+}
+
+/// A mint, as read from the `spl_tokens` section of an accounts file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SplTokenMint {
+    pub mint: String,
+    #[serde(default)]
+    pub program: SplTokenProgram,
+    pub decimals: u8,
+    pub mint_authority: String,
+    #[serde(default)]
+    pub freeze_authority: Option<String>,
+    pub supply: u64,
+    #[serde(default)]
+    pub holders: Vec<SplTokenHolder>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SplTokenHolder {
+    /// Wallet owning the associated token account
+    pub owner: String,
+    pub amount: u64,
+}
+
+/// Address of the associated token account of `owner` for `mint`
+pub fn get_associated_token_address(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    program: SplTokenProgram,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), program.id().as_ref(), mint.as_ref()],
+        &solana_inline_spl::associated_token_account::id(),
+    )
+    .0
+}
+
+/// Add the mints of `mints` and the associated token accounts of their
+/// holders to `genesis_config`. Returns the lamports of the accounts added.
+pub fn add_spl_tokens(
+    genesis_config: &mut GenesisConfig,
+    mints: &[SplTokenMint],
+) -> Result<u64, GenesisError> {
+    let conflict = |pubkey: Pubkey, what: String| GenesisError::Conflict {
+        pubkey,
+        sources: vec![what, "genesis".to_string()],
+    };
+
+    let mut lamports = 0;
+    for spec in mints {
+        let entry = format!("spl token mint {}", spec.mint);
+        let parse_pubkey = |field: &str, value: &str| {
+            Pubkey::from_str(value).map_err(|err| {
+                GenesisError::validation(&entry, field, format_args!("{value}: {err}"))
+            })
+        };
+        let mint = parse_pubkey("mint", &spec.mint)?;
+        let mint_authority = parse_pubkey("mint_authority", &spec.mint_authority)?;
+        let freeze_authority = spec
+            .freeze_authority
+            .as_deref()
+            .map(|freeze_authority| parse_pubkey("freeze_authority", freeze_authority))
+            .transpose()?;
+
+        let mut owners = HashSet::with_capacity(spec.holders.len());
+        let mut holders = Vec::with_capacity(spec.holders.len());
+        let mut minted = 0u64;
+        for holder in &spec.holders {
+            let owner = parse_pubkey("holders", &holder.owner)?;
+            if !owners.insert(owner) {
+                return Err(GenesisError::validation(
+                    &entry,
+                    "holders",
+                    format_args!("duplicate holder {owner}"),
+                ));
+            }
+            minted = minted
+                .checked_add(holder.amount)
+                .filter(|minted| *minted <= spec.supply)
+                .ok_or_else(|| {
+                    GenesisError::validation(
+                        &entry,
+                        "holders",
+                        format_args!("amounts exceed the supply of {}", spec.supply),
+                    )
+                })?;
+            holders.push((owner, holder.amount));
+        }
+
+        if genesis_config.accounts.contains_key(&mint) {
+            return Err(conflict(mint, entry));
+        }
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            mint_authority: COption::Some(mint_authority),
+            supply: spec.supply,
+            decimals: spec.decimals,
+            is_initialized: true,
+            freeze_authority: freeze_authority.into(),
+        }
+        .pack_into_slice(&mut data);
+        lamports += add_token_program_account(genesis_config, mint, spec.program, data);
+
+        for (owner, amount) in holders {
+            let address = get_associated_token_address(&owner, &mint, spec.program);
+            if genesis_config.accounts.contains_key(&address) {
+                return Err(conflict(
+                    address,
+                    format!("token account of {owner} for {entry}"),
+                ));
+            }
+            let mut data = vec![0; Account::LEN];
+            Account {
+                mint,
+                owner,
+                amount,
+                delegate: COption::None,
+                state: AccountState::Initialized,
+                is_native: COption::None,
+                delegated_amount: 0,
+                close_authority: COption::None,
+            }
+            .pack_into_slice(&mut data);
+            lamports += add_token_program_account(genesis_config, address, spec.program, data);
+        }
+    }
+
+    Ok(lamports)
+}
+
+/// Add a rent exempt account holding `data`, owned by `program`
+fn add_token_program_account(
+    genesis_config: &mut GenesisConfig,
+    address: Pubkey,
+    program: SplTokenProgram,
+    data: Vec<u8>,
+) -> u64 {
+    let lamports = genesis_config.rent.minimum_balance(data.len());
+    let mut account = AccountSharedData::new(lamports, 0, &program.id());
+    account.set_data_from_slice(&data);
+    genesis_config.add_account(address, account);
+    lamports
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::GenesisAccountsFile, assert_matches::assert_matches,
+        solana_account::ReadableAccount, solana_rent::Rent,
+    };
+
+    fn mint(program: SplTokenProgram, holders: &[(Pubkey, u64)]) -> SplTokenMint {
+        SplTokenMint {
+            mint: Pubkey::new_unique().to_string(),
+            program,
+            decimals: 6,
+            mint_authority: Pubkey::new_unique().to_string(),
+            freeze_authority: None,
+            supply: 1_000_000,
+            holders: holders
+                .iter()
+                .map(|(owner, amount)| SplTokenHolder {
+                    owner: owner.to_string(),
+                    amount: *amount,
+                })
+                .collect(),
+        }
+    }
+
+    fn account(genesis_config: &GenesisConfig, address: &Pubkey) -> AccountSharedData {
+        AccountSharedData::from(genesis_config.accounts[address].clone())
+    }
+
+    #[test]
+    fn test_add_spl_tokens() {
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let mut token = mint(SplTokenProgram::Token, &[(alice, 600_000), (bob, 400_000)]);
+        token.freeze_authority = Some(Pubkey::new_unique().to_string());
+        let token_2022 = mint(SplTokenProgram::Token2022, &[(alice, 5)]);
+
+        let mut genesis_config = GenesisConfig::default();
+        let lamports =
+            add_spl_tokens(&mut genesis_config, &[token.clone(), token_2022.clone()]).unwrap();
+        assert_eq!(genesis_config.accounts.len(), 5);
+        assert_eq!(
+            lamports,
+            genesis_config
+                .accounts
+                .values()
+                .map(|account| account.lamports)
+                .sum::<u64>()
+        );
+
+        for (spec, holders) in [
+            (&token, vec![(alice, 600_000), (bob, 400_000)]),
+            (&token_2022, vec![(alice, 5)]),
+        ] {
+            let mint_address = Pubkey::from_str(&spec.mint).unwrap();
+            let mint_account = account(&genesis_config, &mint_address);
+            assert_eq!(mint_account.owner(), &spec.program.id());
+            assert_eq!(
+                mint_account.lamports(),
+                Rent::default().minimum_balance(Mint::LEN)
+            );
+            let mint = Mint::unpack(mint_account.data()).unwrap();
+            assert_eq!(
+                mint.mint_authority,
+                COption::Some(Pubkey::from_str(&spec.mint_authority).unwrap())
+            );
+            assert_eq!(
+                mint.freeze_authority,
+                spec.freeze_authority
+                    .as_deref()
+                    .map(|authority| Pubkey::from_str(authority).unwrap())
+                    .into()
+            );
+            assert_eq!(mint.supply, spec.supply);
+            assert_eq!(mint.decimals, 6);
+
+            for (owner, amount) in holders {
+                let address = get_associated_token_address(&owner, &mint_address, spec.program);
+                assert_eq!(
+                    address,
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &owner,
+                        &mint_address,
+                        &spec.program.id(),
+                    )
+                );
+                let token_account = account(&genesis_config, &address);
+                assert_eq!(token_account.owner(), &spec.program.id());
+                assert_eq!(
+                    token_account.lamports(),
+                    Rent::default().minimum_balance(Account::LEN)
+                );
+                let token_account = Account::unpack(token_account.data()).unwrap();
+                assert_eq!(token_account.mint, mint_address);
+                assert_eq!(token_account.owner, owner);
+                assert_eq!(token_account.amount, amount);
+                assert_eq!(token_account.state, AccountState::Initialized);
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_spl_tokens_errors() {
+        let alice = Pubkey::new_unique();
+        let mut genesis_config = GenesisConfig::default();
+        assert_matches!(
+            add_spl_tokens(
+                &mut genesis_config,
+                &[mint(SplTokenProgram::Token, &[(alice, 600_000), (alice, 1)])]
+            ),
+            Err(GenesisError::Validation { field, reason, .. })
+                if field == "holders" && reason.starts_with("duplicate")
+        );
+        assert_matches!(
+            add_spl_tokens(
+                &mut genesis_config,
+                &[mint(
+                    SplTokenProgram::Token,
+                    &[(alice, 600_000), (Pubkey::new_unique(), 400_001)]
+                )]
+            ),
+            Err(GenesisError::Validation { field, .. }) if field == "holders"
+        );
+        assert_matches!(
+            add_spl_tokens(
+                &mut genesis_config,
+                &[mint(SplTokenProgram::Token, &[(alice, u64::MAX), (Pubkey::new_unique(), 1)])]
+            ),
+            Err(GenesisError::Validation { field, .. }) if field == "holders"
+        );
+        let mut bad_authority = mint(SplTokenProgram::Token, &[]);
+        bad_authority.mint_authority = "not a pubkey".to_string();
+        assert_matches!(
+            add_spl_tokens(&mut genesis_config, &[bad_authority]),
+            Err(GenesisError::Validation { field, .. }) if field == "mint_authority"
+        );
+        assert!(genesis_config.accounts.is_empty());
+
+        let token = mint(SplTokenProgram::Token, &[(alice, 1)]);
+        add_spl_tokens(&mut genesis_config, &[token.clone()]).unwrap();
+        assert_matches!(
+            add_spl_tokens(&mut genesis_config, &[token.clone()]),
+            Err(GenesisError::Conflict { pubkey, .. }) if pubkey.to_string() == token.mint
+        );
+    }
+
+    #[test]
+    fn test_spl_tokens_section() {
+        let owner = Pubkey::new_unique();
+        let yaml = format!(
+            "{system}:\n  balance: 10\n  owner: {system}\n  data: ~\n  executable: false\n\
+             spl_tokens:\n  - mint: {mint}\n    program: token-2022\n    decimals: 9\n    \
+             mint_authority: {owner}\n    supply: 100\n    holders:\n      - owner: {owner}\n        \
+             amount: 100\n",
+            system = solana_sdk_ids::system_program::id(),
+            mint = Pubkey::new_unique(),
+        );
+        let accounts_file: GenesisAccountsFile = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(accounts_file.accounts.len(), 1);
+        assert_eq!(accounts_file.spl_tokens.len(), 1);
+        assert_eq!(
+            accounts_file.spl_tokens[0].program,
+            SplTokenProgram::Token2022
+        );
+        assert_eq!(accounts_file.spl_tokens[0].holders[0].amount, 100);
+
+        let accounts_file: GenesisAccountsFile = serde_yaml::from_str("{}").unwrap();
+        assert!(accounts_file.spl_tokens.is_empty());
+    }
+}
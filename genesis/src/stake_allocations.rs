@@ -0,0 +1,664 @@
+//! Stake allocations imported from CSV spreadsheets
+//!
+//! Each row allocates an amount to a recipient, which stakes it and may
+//! withdraw it, optionally locked up until a date and with the custodian
+//! allowed to lift the lockup. The rows are read one at a time and every
+//! invalid one is reported with its row number, up to an error limit.
+use {
+    crate::{
+        address_generator::DerivationCache,
+        amount::{deserialize_lamports, parse_lamports},
+        error::GenesisError,
+        progress::{GenesisPhase, ProgressTracker},
+        stakes::{create_and_add_stakes_locked_until, StakerInfo},
+        unlocks::UnlockInfo,
+    },
+    csv::{ReaderBuilder, StringRecord, Trim},
+    serde::Deserialize,
+    solana_clock::UnixTimestamp,
+    solana_genesis_config::GenesisConfig,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        fmt::{self, Display},
+        fs::File,
+        io,
+        path::Path,
+        str::FromStr,
+        sync::Arc,
+    },
+};
+
+/// Invalid rows reported before the import stops reading
+pub const DEFAULT_MAX_ERRORS: usize = 100;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// A date, in UTC, until which a stake is locked up
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "String")]
+pub struct LockupDate {
+    year: i64,
+    month: i64,
+    day: i64,
+}
+
+impl LockupDate {
+    /// The start of the day, in seconds since the Unix epoch
+    pub fn unix_timestamp(&self) -> UnixTimestamp {
+        // days since 0000-03-01, the leap day ending the year
+        let year = if self.month <= 2 {
+            self.year - 1
+        } else {
+            self.year
+        };
+        let day_of_year = (153 * ((self.month + 9) % 12) + 2) / 5 + self.day - 1;
+        let days = year * 365 + year / 4 - year / 100 + year / 400 + day_of_year;
+        // 1970-01-01 is day 719_468
+        (days - 719_468) * SECONDS_PER_DAY
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl FromStr for LockupDate {
+    type Err = String;
+
+    fn from_str(date: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{date:?} is not a date, expected YYYY-MM-DD");
+        let fields: Vec<_> = date.split('-').collect();
+        let [year, month, day] = fields.as_slice() else {
+            return Err(invalid());
+        };
+        let number = |field: &str, len: usize| {
+            (field.len() == len && field.bytes().all(|byte| byte.is_ascii_digit()))
+                .then(|| field.parse::<i64>().ok())
+                .flatten()
+                .ok_or_else(invalid)
+        };
+        let (year, month, day) = (number(year, 4)?, number(month, 2)?, number(day, 2)?);
+        if year < 1970 {
+            return Err(format!("{date} is before 1970"));
+        }
+        if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+            return Err(format!("{date} is not a day of the calendar"));
+        }
+        Ok(Self { year, month, day })
+    }
+}
+
+impl TryFrom<String> for LockupDate {
+    type Error = String;
+
+    fn try_from(date: String) -> Result<Self, Self::Error> {
+        date.parse()
+    }
+}
+
+impl Display for LockupDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// An amount staked by and for a recipient
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "StakeAllocationInput")]
+pub struct StakeAllocation {
+    pub recipient: Pubkey,
+    pub lamports: u64,
+    pub lockup_date: Option<LockupDate>,
+    /// May lift the lockup, the default pubkey if none is given
+    pub custodian: Option<Pubkey>,
+}
+
+/// A stake allocation as written in a YAML file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StakeAllocationInput {
+    recipient: String,
+    #[serde(deserialize_with = "deserialize_lamports")]
+    amount: u64,
+    #[serde(default)]
+    lockup_date: Option<LockupDate>,
+    #[serde(default)]
+    custodian: Option<String>,
+}
+
+impl TryFrom<StakeAllocationInput> for StakeAllocation {
+    type Error = String;
+
+    fn try_from(input: StakeAllocationInput) -> Result<Self, Self::Error> {
+        Ok(Self {
+            recipient: parse_pubkey("recipient", &input.recipient)?,
+            lamports: input.amount,
+            lockup_date: input.lockup_date,
+            custodian: input
+                .custodian
+                .map(|custodian| parse_pubkey("custodian", &custodian))
+                .transpose()?,
+        })
+    }
+}
+
+fn parse_pubkey(field: &str, pubkey: &str) -> Result<Pubkey, String> {
+    pubkey
+        .parse()
+        .map_err(|err| format!("invalid {field} {pubkey:?}: {err}"))
+}
+
+/// How a stake allocations CSV is read: the header of the column holding each
+/// field, and the number of invalid rows after which to stop
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StakeAllocationsCsvConfig {
+    pub recipient_column: String,
+    pub amount_column: String,
+    /// Optional column, the stakes are not locked up without it
+    pub lockup_date_column: String,
+    /// Optional column
+    pub custodian_column: String,
+    pub max_errors: usize,
+}
+
+impl Default for StakeAllocationsCsvConfig {
+    fn default() -> Self {
+        Self {
+            recipient_column: "recipient".to_string(),
+            amount_column: "amount".to_string(),
+            lockup_date_column: "lockup_date".to_string(),
+            custodian_column: "custodian".to_string(),
+            max_errors: DEFAULT_MAX_ERRORS,
+        }
+    }
+}
+
+impl StakeAllocationsCsvConfig {
+    /// Map fields to the columns of `mapping`, a comma separated list of
+    /// `FIELD=HEADER` such as `recipient=Wallet,amount=SOL`
+    pub fn with_columns(mut self, mapping: &str) -> Result<Self, String> {
+        for field_column in mapping.split(',') {
+            let Some((field, column)) = field_column.split_once('=') else {
+                return Err(format!("{field_column:?} is not FIELD=HEADER"));
+            };
+            let column = column.trim().to_string();
+            match field.trim() {
+                "recipient" => self.recipient_column = column,
+                "amount" => self.amount_column = column,
+                "lockup_date" => self.lockup_date_column = column,
+                "custodian" => self.custodian_column = column,
+                field => {
+                    return Err(format!(
+                        "unknown field {field:?}, expected recipient, amount, lockup_date or \
+                         custodian"
+                    ))
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Load the stake allocations of the CSV at `path`, see
+/// `parse_stake_allocations_csv()`
+pub fn load_stake_allocations_csv(
+    path: &Path,
+    config: &StakeAllocationsCsvConfig,
+) -> Result<Vec<StakeAllocation>, GenesisError> {
+    parse_stake_allocations_csv(File::open(path)?, path.display(), config)
+}
+
+/// Parse the stake allocations of a CSV whose columns are named by `config`.
+///
+/// Every row is validated and each problem recorded with its row number, the
+/// header being row 1. All of them are returned at once, once the CSV is read
+/// or `config.max_errors` have been found.
+pub fn parse_stake_allocations_csv(
+    reader: impl io::Read,
+    file: impl Display,
+    config: &StakeAllocationsCsvConfig,
+) -> Result<Vec<StakeAllocation>, GenesisError> {
+    let mut reader = ReaderBuilder::new().trim(Trim::All).from_reader(reader);
+    let header = reader
+        .headers()
+        .map_err(|err| GenesisError::Parse {
+            file: file.to_string(),
+            entry: "row 1".to_string(),
+            cause: err.to_string(),
+        })?
+        .clone();
+    let column = |name: &str| header.iter().position(|header| header == name);
+    let required_column = |name: &str| {
+        column(name).ok_or_else(|| GenesisError::Parse {
+            file: file.to_string(),
+            entry: "row 1".to_string(),
+            cause: format!(
+                "missing the column {name:?}, found {}",
+                header.iter().collect::<Vec<_>>().join(",")
+            ),
+        })
+    };
+    let columns = Columns {
+        recipient: required_column(&config.recipient_column)?,
+        amount: required_column(&config.amount_column)?,
+        lockup_date: column(&config.lockup_date_column),
+        custodian: column(&config.custodian_column),
+    };
+
+    let mut allocations = vec![];
+    let mut errors = vec![];
+    let mut first_rows = HashMap::new();
+    let mut aborted = false;
+    let max_errors = config.max_errors.max(1);
+    for record in reader.records() {
+        if errors.len() >= max_errors {
+            aborted = true;
+            break;
+        }
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                let row = err.position().map(|position| position.line());
+                errors.push(format!("row {}: {err}", row.unwrap_or_default()));
+                continue;
+            }
+        };
+        let row = record
+            .position()
+            .map(|position| position.line())
+            .unwrap_or_default();
+        match columns.parse(&record) {
+            Ok(allocation) => match first_rows.insert(allocation.recipient, row) {
+                Some(first_row) => errors.push(format!(
+                    "row {row}: recipient {} already has the allocation of row {first_row}",
+                    allocation.recipient
+                )),
+                None => allocations.push(allocation),
+            },
+            Err(row_errors) => {
+                errors.extend(
+                    row_errors
+                        .into_iter()
+                        .map(|row_error| format!("row {row}: {row_error}")),
+                );
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(allocations)
+    } else {
+        errors.truncate(max_errors);
+        Err(GenesisError::InvalidRows {
+            file: file.to_string(),
+            errors,
+            aborted,
+        })
+    }
+}
+
+/// Indices of the columns of the fields of a CSV
+struct Columns {
+    recipient: usize,
+    amount: usize,
+    lockup_date: Option<usize>,
+    custodian: Option<usize>,
+}
+
+impl Columns {
+    /// The allocation of `record`, or every problem with it
+    fn parse(&self, record: &StringRecord) -> Result<StakeAllocation, Vec<String>> {
+        let field = |column: usize| record.get(column).filter(|field| !field.is_empty());
+        let optional_field = |column: Option<usize>| column.and_then(field);
+
+        let recipient = field(self.recipient)
+            .ok_or_else(|| "missing recipient".to_string())
+            .and_then(|recipient| parse_pubkey("recipient", recipient));
+        let lamports = field(self.amount)
+            .ok_or_else(|| "missing amount".to_string())
+            .and_then(|amount| parse_lamports(amount).map_err(|err| err.to_string()))
+            .and_then(|lamports| match lamports {
+                0 => Err("the amount is zero".to_string()),
+                lamports => Ok(lamports),
+            });
+        let lockup_date = optional_field(self.lockup_date)
+            .map(|date| {
+                date.parse::<LockupDate>()
+                    .map_err(|err| format!("invalid lockup date: {err}"))
+            })
+            .transpose();
+        let custodian = optional_field(self.custodian)
+            .map(|custodian| parse_pubkey("custodian", custodian))
+            .transpose();
+
+        match (recipient, lamports, lockup_date, custodian) {
+            (Ok(recipient), Ok(lamports), Ok(lockup_date), Ok(custodian)) => Ok(StakeAllocation {
+                recipient,
+                lamports,
+                lockup_date,
+                custodian,
+            }),
+            (recipient, lamports, lockup_date, custodian) => Err([
+                recipient.err(),
+                lamports.err(),
+                lockup_date.err(),
+                custodian.err(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect()),
+        }
+    }
+}
+
+/// Create the stake accounts of `allocations`, each recipient being the
+/// staker and withdrawer of its stakes, reported to `progress` as the
+/// accounts of `source`. Returns the lamports allocated, including the fees
+/// the recipients are funded with.
+pub fn add_stake_allocations(
+    genesis_config: &mut GenesisConfig,
+    source: &str,
+    allocations: &[StakeAllocation],
+    derivation_cache: Option<&Arc<DerivationCache>>,
+    progress: &mut ProgressTracker,
+) -> Result<u64, GenesisError> {
+    progress.start_phase(GenesisPhase::CreateAccounts, source, None);
+    let lamports = allocations
+        .iter()
+        .map(|allocation| {
+            let name = format!("stake allocation of {}", allocation.recipient);
+            let recipient = allocation.recipient.to_string();
+            let custodian = allocation.custodian.unwrap_or_default().to_string();
+            create_and_add_stakes_locked_until(
+                genesis_config,
+                &StakerInfo {
+                    name: &name,
+                    staker: &recipient,
+                    withdrawer: None,
+                    lamports: allocation.lamports,
+                    ..StakerInfo::DEFAULT
+                },
+                // unlocked from genesis on, the lockup date aside
+                &UnlockInfo {
+                    cliff_fraction: 1.0,
+                    cliff_years: 0.0,
+                    unlocks: 0,
+                    unlock_years: 0.0,
+                    custodian: &custodian,
+                },
+                allocation
+                    .lockup_date
+                    .as_ref()
+                    .map_or(0, LockupDate::unix_timestamp),
+                None,
+                derivation_cache,
+                progress,
+            )
+        })
+        .sum();
+    progress.finish_phase();
+    lamports
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::progress::GenesisProgress,
+        assert_matches::assert_matches,
+        solana_stake_interface::state::{Lockup, StakeStateV2},
+        std::collections::BTreeMap,
+    };
+
+    const STAKE_ALLOCATIONS_CSV: &str = include_str!("../tests/fixtures/stake_allocations.csv");
+    const STAKE_ALLOCATIONS_YAML: &str = include_str!("../tests/fixtures/stake_allocations.yml");
+    const INVALID_STAKE_ALLOCATIONS_CSV: &str =
+        include_str!("../tests/fixtures/invalid_stake_allocations.csv");
+
+    fn fixture_config() -> StakeAllocationsCsvConfig {
+        StakeAllocationsCsvConfig::default()
+            .with_columns(
+                "recipient=Wallet,amount=Amount,lockup_date=Unlock Date,custodian=Custodian",
+            )
+            .unwrap()
+    }
+
+    fn date(date: &str) -> LockupDate {
+        date.parse().unwrap()
+    }
+
+    /// Every stake account of `genesis_config` by address, with its authorities,
+    /// lockup and lamports
+    fn stake_accounts(genesis_config: &GenesisConfig) -> BTreeMap<Pubkey, (Pubkey, Lockup, u64)> {
+        genesis_config
+            .accounts
+            .iter()
+            .filter_map(|(address, account)| {
+                match bincode::deserialize::<StakeStateV2>(&account.data) {
+                    Ok(StakeStateV2::Initialized(meta)) => Some((
+                        *address,
+                        (meta.authorized.withdrawer, meta.lockup, account.lamports),
+                    )),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lockup_date() {
+        assert_eq!(date("1970-01-01").unix_timestamp(), 0);
+        assert_eq!(date("2000-03-01").unix_timestamp(), 951_868_800);
+        assert_eq!(date("2024-02-29").unix_timestamp(), 1_709_164_800);
+        assert_eq!(date("2026-01-01").unix_timestamp(), 1_767_225_600);
+        assert_eq!(date("2026-01-01").to_string(), "2026-01-01");
+
+        for invalid in [
+            "2026-1-1",
+            "2026/01/01",
+            "01-01-2026",
+            "2026-01-01T00:00:00Z",
+            "2026-13-01",
+            "2026-00-10",
+            "2025-02-29",
+            "1900-02-29",
+            "2026-04-31",
+            "1969-12-31",
+            "",
+        ] {
+            assert!(invalid.parse::<LockupDate>().is_err(), "{invalid}");
+        }
+        assert!("2000-02-29".parse::<LockupDate>().is_ok());
+    }
+
+    #[test]
+    fn test_csv_config_columns() {
+        let config = fixture_config();
+        assert_eq!(config.recipient_column, "Wallet");
+        assert_eq!(config.lockup_date_column, "Unlock Date");
+        assert_eq!(config.max_errors, DEFAULT_MAX_ERRORS);
+        assert_eq!(
+            StakeAllocationsCsvConfig::default()
+                .with_columns("amount=SOL")
+                .unwrap(),
+            StakeAllocationsCsvConfig {
+                amount_column: "SOL".to_string(),
+                ..StakeAllocationsCsvConfig::default()
+            }
+        );
+        assert!(StakeAllocationsCsvConfig::default()
+            .with_columns("amount")
+            .is_err());
+        assert!(StakeAllocationsCsvConfig::default()
+            .with_columns("balance=SOL")
+            .is_err());
+    }
+
+    #[test]
+    fn test_csv_matches_yaml() {
+        let from_csv =
+            parse_stake_allocations_csv(STAKE_ALLOCATIONS_CSV.as_bytes(), "csv", &fixture_config())
+                .unwrap();
+        let from_yaml: Vec<StakeAllocation> = serde_yaml::from_str(STAKE_ALLOCATIONS_YAML).unwrap();
+        assert_eq!(from_csv.len(), 4);
+        assert_eq!(from_csv, from_yaml);
+        assert_eq!(from_csv[0].lamports, 1_000_000_000_000);
+        assert_eq!(from_csv[1].lamports, 2_500_000_000);
+        assert_eq!(from_csv[2].lockup_date, None);
+
+        let create = |allocations: &[StakeAllocation]| {
+            let mut genesis_config = GenesisConfig::default();
+            let mut reports = vec![];
+            let mut callback = |progress: GenesisProgress| {
+                reports.push((progress.phase, progress.source.to_string(), progress.done))
+            };
+            let lamports = add_stake_allocations(
+                &mut genesis_config,
+                "allocations",
+                allocations,
+                None,
+                &mut ProgressTracker::new(&mut callback),
+            )
+            .unwrap();
+            // every stake account is counted
+            assert_eq!(
+                reports.last(),
+                Some(&(
+                    GenesisPhase::CreateAccounts,
+                    "allocations".to_string(),
+                    allocations.len()
+                ))
+            );
+            (genesis_config, lamports)
+        };
+        let (csv_genesis_config, csv_lamports) = create(&from_csv);
+        let (yaml_genesis_config, yaml_lamports) = create(&from_yaml);
+        assert_eq!(csv_genesis_config.accounts, yaml_genesis_config.accounts);
+        assert_eq!(csv_lamports, yaml_lamports);
+        assert_eq!(
+            csv_lamports,
+            from_csv
+                .iter()
+                .map(|allocation| allocation.lamports)
+                .sum::<u64>()
+        );
+
+        // one stake account per allocation, locked up as the row says
+        let stake_accounts = stake_accounts(&csv_genesis_config);
+        assert_eq!(stake_accounts.len(), from_csv.len());
+        for allocation in &from_csv {
+            let (_, (_, lockup, _)) = stake_accounts
+                .iter()
+                .find(|(_, (withdrawer, _, _))| *withdrawer == allocation.recipient)
+                .unwrap();
+            assert_eq!(
+                lockup.unix_timestamp,
+                allocation
+                    .lockup_date
+                    .as_ref()
+                    .map_or(0, LockupDate::unix_timestamp)
+            );
+            assert_eq!(lockup.custodian, allocation.custodian.unwrap_or_default());
+        }
+    }
+
+    #[test]
+    fn test_csv_invalid_rows() {
+        let invalid_rows = |max_errors| {
+            let config = StakeAllocationsCsvConfig {
+                max_errors,
+                ..fixture_config()
+            };
+            match parse_stake_allocations_csv(
+                INVALID_STAKE_ALLOCATIONS_CSV.as_bytes(),
+                "invalid.csv",
+                &config,
+            ) {
+                Err(GenesisError::InvalidRows {
+                    file,
+                    errors,
+                    aborted,
+                }) => {
+                    assert_eq!(file, "invalid.csv");
+                    (errors, aborted)
+                }
+                result => panic!("unexpected result {result:?}"),
+            }
+        };
+
+        let (errors, aborted) = invalid_rows(DEFAULT_MAX_ERRORS);
+        assert!(!aborted);
+        let rows: Vec<_> = errors
+            .iter()
+            .map(|error| error.split(':').next().unwrap())
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "row 3", "row 4", "row 5", "row 6", "row 7", "row 8", "row 9", "row 10", "row 11",
+                "row 12", "row 12", "row 13",
+            ]
+        );
+        for (error, expected) in errors.iter().zip([
+            "invalid recipient",
+            "missing recipient",
+            "invalid amount",
+            "is not a whole number of lamports",
+            "is a percentage",
+            "the amount is zero",
+            "invalid lockup date",
+            "invalid custodian",
+            "already has the allocation of row 2",
+            "invalid recipient",
+            "invalid amount",
+            "found record with 3 fields",
+        ]) {
+            assert!(
+                error.contains(expected),
+                "{error} does not contain {expected}"
+            );
+        }
+
+        // reading stops at the error limit
+        let (errors, aborted) = invalid_rows(3);
+        assert!(aborted);
+        assert_eq!(errors.len(), 3);
+        assert!(errors[2].starts_with("row 5"));
+    }
+
+    #[test]
+    fn test_csv_missing_column() {
+        assert_matches!(
+            parse_stake_allocations_csv(
+                "Wallet,Unlock Date\n".as_bytes(),
+                "stakes.csv",
+                &fixture_config()
+            ),
+            Err(GenesisError::Parse { entry, cause, .. })
+                if entry == "row 1" && cause.contains("\"Amount\"")
+        );
+
+        // the lockup date and custodian columns may be left out
+        let pubkey = Pubkey::new_unique();
+        assert_eq!(
+            parse_stake_allocations_csv(
+                format!("recipient,amount\n{pubkey},5 SOL\n").as_bytes(),
+                "stakes.csv",
+                &StakeAllocationsCsvConfig::default(),
+            )
+            .unwrap(),
+            vec![StakeAllocation {
+                recipient: pubkey,
+                lamports: 5_000_000_000,
+                lockup_date: None,
+                custodian: None,
+            }]
+        );
+    }
+}
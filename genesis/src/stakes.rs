@@ -1,28 +1,138 @@
 //! stakes generator
 use {
     crate::{
-        address_generator::AddressGenerator,
+        address_generator::{AddressGenerator, DerivationCache},
+        error::GenesisError,
+        progress::ProgressTracker,
         unlocks::{UnlockInfo, Unlocks},
+        StakedValidatorAccountInfo,
     },
-    solana_account::Account,
-    solana_clock::Slot,
+    solana_account::{Account, AccountSharedData},
+    solana_clock::{Slot, UnixTimestamp},
     solana_genesis_config::GenesisConfig,
     solana_pubkey::Pubkey,
+    solana_rent::Rent,
     solana_sdk_ids::system_program,
+    solana_sha256_hasher::hashv,
     solana_stake_interface::{
         self as stake,
         state::{Authorized, Lockup, StakeStateV2},
     },
-    solana_stake_program::stake_state::create_lockup_stake_account,
+    solana_stake_program::stake_state::{create_account, create_lockup_stake_account},
     solana_time_utils::years_as_slots,
+    std::{
+        collections::{HashMap, HashSet},
+        fmt,
+        sync::Arc,
+    },
 };
 
 #[derive(Debug)]
-pub struct StakerInfo {
-    pub name: &'static str,
-    pub staker: &'static str,
-    pub withdrawer: Option<&'static str>,
+pub struct StakerInfo<'a> {
+    pub name: &'a str,
+    /// funded to cover the staking fees, and the default staker authority
+    pub staker: &'a str,
+    /// withdrawer authority of the stake accounts, defaults to `staker`
+    pub withdrawer: Option<&'a str>,
+    /// staker authority of the stake accounts, defaults to `staker`
+    pub staker_authority: Option<&'a str>,
     pub lamports: u64,
+    /// split the stake between several staker authorities; when empty, all the
+    ///  stake accounts use `staker_authority`
+    pub splits: &'a [StakerSplit<'a>],
+}
+
+impl StakerInfo<'static> {
+    /// No separate staker authority and no splits, for the other fields of an
+    ///  entry to be filled with `..StakerInfo::DEFAULT`
+    pub const DEFAULT: Self = Self {
+        name: "",
+        staker: "",
+        withdrawer: None,
+        staker_authority: None,
+        lamports: 0,
+        splits: &[],
+    };
+}
+
+/// A share of an allocation's stake, administered by its own staker authority
+#[derive(Debug)]
+pub struct StakerSplit<'a> {
+    pub staker: &'a str,
+    pub fraction: f64,
+}
+
+/// Largest difference from 1.0 tolerated in the sum of the split fractions,
+///  the last split absorbs the rounding
+const SPLIT_FRACTIONS_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, PartialEq)]
+pub enum StakeSplitError {
+    InvalidFraction { staker: String, fraction: f64 },
+    FractionsSum(f64),
+    DuplicateStaker(String),
+}
+
+impl fmt::Display for StakeSplitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidFraction { staker, fraction } => {
+                write!(
+                    f,
+                    "split fraction {fraction} of staker {staker} is not in (0, 1]"
+                )
+            }
+            Self::FractionsSum(sum) => write!(f, "split fractions sum to {sum} instead of 1.0"),
+            Self::DuplicateStaker(staker) => write!(f, "staker {staker} is split more than once"),
+        }
+    }
+}
+
+impl std::error::Error for StakeSplitError {}
+
+fn validate_splits(splits: &[StakerSplit]) -> Result<(), StakeSplitError> {
+    if splits.is_empty() {
+        return Ok(());
+    }
+    let mut stakers = HashSet::new();
+    for split in splits {
+        if !(split.fraction > 0.0 && split.fraction <= 1.0) {
+            return Err(StakeSplitError::InvalidFraction {
+                staker: split.staker.to_string(),
+                fraction: split.fraction,
+            });
+        }
+        if !stakers.insert(split.staker) {
+            return Err(StakeSplitError::DuplicateStaker(split.staker.to_string()));
+        }
+    }
+    let sum = splits.iter().map(|split| split.fraction).sum::<f64>();
+    if (sum - 1.0).abs() > SPLIT_FRACTIONS_TOLERANCE {
+        return Err(StakeSplitError::FractionsSum(sum));
+    }
+    Ok(())
+}
+
+/// Divide `lamports` according to the fractions of `splits`; the last split
+///  gets whatever rounding left over
+pub fn split_lamports(lamports: u64, splits: &[StakerSplit]) -> Result<Vec<u64>, StakeSplitError> {
+    validate_splits(splits)?;
+    if splits.is_empty() {
+        return Ok(vec![lamports]);
+    }
+
+    let mut remaining = lamports;
+    let mut split_lamports: Vec<_> = splits
+        .iter()
+        .take(splits.len().saturating_sub(1))
+        .map(|split| {
+            let share = ((lamports as f64 * split.fraction) as u64).min(remaining);
+            remaining -= share;
+            share
+        })
+        .collect();
+    split_lamports.push(remaining);
+    Ok(split_lamports)
 }
 
 // lamports required to run staking operations for one year
@@ -38,6 +148,37 @@ fn calculate_staker_fees(genesis_config: &GenesisConfig, years: f64) -> u64 {
         ) as Slot)
 }
 
+/// Base of the addresses of the stake accounts of the `nth` share of the
+///  stakes of `staker_info`. Stakes administered by their staker keep deriving
+///  from it, as they always have. Staker authorities may be shared between
+///  entries and splits, so the stakes they administer derive from a base of
+///  their own share instead.
+fn stake_address_base(
+    staker_info: &StakerInfo,
+    staker: &Pubkey,
+    staker_authority: &Pubkey,
+    nth: usize,
+) -> Pubkey {
+    if staker_info.staker_authority.is_none() && staker_info.splits.is_empty() {
+        return *staker;
+    }
+    Pubkey::new_from_array(
+        hashv(&[
+            b"stake-share",
+            staker_info.name.as_bytes(),
+            staker_authority.as_ref(),
+            &(nth as u64).to_le_bytes(),
+        ])
+        .to_bytes(),
+    )
+}
+
+fn parse_pubkey(entry: &str, field: &str, pubkey: &str) -> Result<Pubkey, GenesisError> {
+    pubkey
+        .parse()
+        .map_err(|err| GenesisError::validation(entry, field, format_args!("{pubkey}: {err}")))
+}
+
 /// create stake accounts for lamports with at most stake_granularity in each
 ///  account
 pub fn create_and_add_stakes(
@@ -48,25 +189,55 @@ pub fn create_and_add_stakes(
     unlock_info: &UnlockInfo,
     // the largest each stake account should be, in lamports
     granularity: Option<u64>,
-) -> u64 {
+    // previously derived stake account addresses, if any
+    derivation_cache: Option<&Arc<DerivationCache>>,
+) -> Result<u64, GenesisError> {
+    create_and_add_stakes_locked_until(
+        genesis_config,
+        staker_info,
+        unlock_info,
+        0,
+        granularity,
+        derivation_cache,
+        &mut ProgressTracker::default(),
+    )
+}
+
+/// `create_and_add_stakes()`, the lockups of the stakes also holding until
+///  `lockup_unix_timestamp`, each stake account counted by `progress`
+pub fn create_and_add_stakes_locked_until(
+    genesis_config: &mut GenesisConfig,
+    staker_info: &StakerInfo,
+    unlock_info: &UnlockInfo,
+    // the stakes stay locked up until this time, whatever their epoch
+    lockup_unix_timestamp: UnixTimestamp,
+    granularity: Option<u64>,
+    derivation_cache: Option<&Arc<DerivationCache>>,
+    progress: &mut ProgressTracker,
+) -> Result<u64, GenesisError> {
+    let name = staker_info.name;
+    validate_splits(staker_info.splits)
+        .map_err(|err| GenesisError::validation(name, "splits", err))?;
+    unlock_info.validate(name)?;
+
     let granularity = granularity.unwrap_or(u64::MAX);
-    let staker = &staker_info
-        .staker
-        .parse::<Pubkey>()
-        .expect("invalid staker");
-    let withdrawer = &staker_info
-        .withdrawer
-        .unwrap_or(staker_info.staker)
-        .parse::<Pubkey>()
-        .expect("invalid staker");
-    let authorized = Authorized {
-        staker: *staker,
-        withdrawer: *withdrawer,
-    };
-    let custodian = unlock_info
-        .custodian
-        .parse::<Pubkey>()
-        .expect("invalid custodian");
+    let staker = &parse_pubkey(name, "staker", staker_info.staker)?;
+    let withdrawer = &parse_pubkey(
+        name,
+        "withdrawer",
+        staker_info.withdrawer.unwrap_or(staker_info.staker),
+    )?;
+    let staker_authority = &parse_pubkey(
+        name,
+        "staker_authority",
+        staker_info.staker_authority.unwrap_or(staker_info.staker),
+    )?;
+    let split_stakers = staker_info
+        .splits
+        .iter()
+        .map(|split| parse_pubkey(name, "splits", split.staker))
+        .collect::<Result<Vec<_>, _>>()?;
+    let custodian = parse_pubkey(name, "custodian", unlock_info.custodian)?;
 
     let total_lamports = staker_info.lamports;
 
@@ -74,96 +245,586 @@ pub fn create_and_add_stakes(
     let staker_rent_reserve = genesis_config.rent.minimum_balance(0).max(1);
     let staker_fees = calculate_staker_fees(genesis_config, 1.0);
 
-    let mut stakes_lamports = total_lamports - staker_fees;
-
     // lamports required to run staking operations for one year
     //  the staker account needs to be rent exempt *and* carry enough
     //  lamports to cover TX fees (delegation) for one year,
     //  and we support one delegation per epoch
     // a single staker may administer any number of accounts
+    let staker_lamports = if genesis_config.accounts.contains_key(staker) {
+        staker_fees
+    } else {
+        staker_fees + staker_rent_reserve
+    };
+    let stakes_lamports =
+        total_lamports
+            .checked_sub(staker_lamports)
+            .ok_or_else(|| GenesisError::LimitExceeded {
+                which: format!("fees and rent reserve of staker {name}"),
+                limit: total_lamports,
+                actual: staker_lamports,
+            })?;
+
+    // each staker authority administers its share of the stake
+    let shares: Vec<(Pubkey, u64)> = if staker_info.splits.is_empty() {
+        vec![(*staker_authority, stakes_lamports)]
+    } else {
+        let split_lamports = split_lamports(stakes_lamports, staker_info.splits)
+            .map_err(|err| GenesisError::validation(name, "splits", err))?;
+        if let Some(split) = staker_info
+            .splits
+            .iter()
+            .zip(&split_lamports)
+            .find_map(|(split, lamports)| (*lamports == 0).then_some(split))
+        {
+            return Err(GenesisError::validation(
+                name,
+                "splits",
+                format_args!(
+                    "split of staker {} gets no lamports out of {stakes_lamports}",
+                    split.staker
+                ),
+            ));
+        }
+        split_stakers.into_iter().zip(split_lamports).collect()
+    };
+
     genesis_config
         .accounts
-        .entry(authorized.staker)
-        .or_insert_with(|| {
-            stakes_lamports -= staker_rent_reserve;
-            Account::new(staker_rent_reserve, 0, &system_program::id())
-        })
+        .entry(*staker)
+        .or_insert_with(|| Account::new(staker_rent_reserve, 0, &system_program::id()))
         .lamports += staker_fees;
 
-    // the staker account needs to be rent exempt *and* carry enough
-    //  lamports to cover TX fees (delegation) for one year
-    //  as we support one re-delegation per epoch
-    let unlocks = Unlocks::new(
-        unlock_info.cliff_fraction,
-        unlock_info.cliff_years,
-        unlock_info.unlocks,
-        unlock_info.unlock_years,
-        &genesis_config.epoch_schedule,
-        &genesis_config.poh_config.target_tick_duration,
-        genesis_config.ticks_per_slot,
-    );
-
-    let mut address_generator = AddressGenerator::new(&authorized.staker, &stake::program::id());
-
     let stake_rent_reserve = genesis_config.rent.minimum_balance(StakeStateV2::size_of());
+    // a stake account derived twice, e.g. by two entries sharing a staker,
+    //  must not silently replace the first one
+    let mut add_stake_account = |genesis_config: &mut GenesisConfig,
+                                 address: Pubkey,
+                                 authorized: &Authorized,
+                                 lockup: &Lockup,
+                                 lamports: u64| {
+        if genesis_config.accounts.contains_key(&address) {
+            return Err(GenesisError::Conflict {
+                pubkey: address,
+                sources: vec![format!("the stakes of {name}"), "genesis".to_string()],
+            });
+        }
+        let account =
+            create_lockup_stake_account(authorized, lockup, &genesis_config.rent, lamports);
+        genesis_config.add_account(address, account);
+        progress.inc();
+        Ok(())
+    };
 
-    for unlock in unlocks {
-        let lamports = unlock.amount(stakes_lamports);
-
-        let (granularity, remainder) = if granularity < lamports {
-            (granularity, lamports % granularity)
-        } else {
-            (lamports, 0)
+    for (nth, (staker_authority, stakes_lamports)) in shares.into_iter().enumerate() {
+        let authorized = Authorized {
+            staker: staker_authority,
+            withdrawer: *withdrawer,
         };
 
-        let lockup = Lockup {
-            epoch: unlock.epoch,
-            custodian,
-            unix_timestamp: 0,
-        };
-        for _ in 0..(lamports / granularity).saturating_sub(1) {
-            genesis_config.add_account(
-                address_generator.next(),
-                create_lockup_stake_account(
+        // the staker account needs to be rent exempt *and* carry enough
+        //  lamports to cover TX fees (delegation) for one year
+        //  as we support one re-delegation per epoch
+        let unlocks = Unlocks::new(
+            unlock_info.cliff_fraction,
+            unlock_info.cliff_years,
+            unlock_info.unlocks,
+            unlock_info.unlock_years,
+            &genesis_config.epoch_schedule,
+            &genesis_config.poh_config.target_tick_duration,
+            genesis_config.ticks_per_slot,
+        );
+
+        let mut address_generator = AddressGenerator::new(
+            &stake_address_base(staker_info, staker, &staker_authority, nth),
+            &stake::program::id(),
+        )
+        .with_derivation_cache(derivation_cache.cloned());
+
+        for unlock in unlocks {
+            let lamports = unlock.amount(stakes_lamports);
+
+            let (granularity, remainder) = if granularity < lamports {
+                (granularity, lamports % granularity)
+            } else {
+                (lamports, 0)
+            };
+
+            let lockup = Lockup {
+                epoch: unlock.epoch,
+                custodian,
+                unix_timestamp: lockup_unix_timestamp,
+            };
+            for _ in 0..(lamports / granularity).saturating_sub(1) {
+                add_stake_account(
+                    genesis_config,
+                    address_generator.next(),
                     &authorized,
                     &lockup,
-                    &genesis_config.rent,
                     granularity,
-                ),
-            );
-        }
-        if remainder <= stake_rent_reserve {
-            genesis_config.add_account(
-                address_generator.next(),
-                create_lockup_stake_account(
+                )?;
+            }
+            if remainder <= stake_rent_reserve {
+                add_stake_account(
+                    genesis_config,
+                    address_generator.next(),
                     &authorized,
                     &lockup,
-                    &genesis_config.rent,
                     granularity + remainder,
-                ),
-            );
-        } else {
-            genesis_config.add_account(
-                address_generator.next(),
-                create_lockup_stake_account(
+                )?;
+            } else {
+                add_stake_account(
+                    genesis_config,
+                    address_generator.next(),
                     &authorized,
                     &lockup,
-                    &genesis_config.rent,
                     granularity,
-                ),
-            );
-            genesis_config.add_account(
-                address_generator.next(),
-                create_lockup_stake_account(&authorized, &lockup, &genesis_config.rent, remainder),
-            );
+                )?;
+                add_stake_account(
+                    genesis_config,
+                    address_generator.next(),
+                    &authorized,
+                    &lockup,
+                    remainder,
+                )?;
+            }
         }
     }
-    total_lamports
+    Ok(total_lamports)
+}
+
+/// A transfer of stake between two validators, identified by identity account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StakeMove {
+    pub from: String,
+    pub to: String,
+    pub lamports: u64,
+}
+
+/// Add the `(stake_pubkey, stake_lamports)` stake accounts of a validator,
+/// each delegated to the vote account `vote_pubkey`
+pub fn add_validator_stakes(
+    genesis_config: &mut GenesisConfig,
+    authorized_pubkey: &Pubkey,
+    vote_pubkey: &Pubkey,
+    vote_account: &AccountSharedData,
+    rent: &Rent,
+    stake_accounts: &[(Pubkey, u64)],
+) {
+    for (stake_pubkey, stake_lamports) in stake_accounts {
+        genesis_config.add_account(
+            *stake_pubkey,
+            create_account(
+                authorized_pubkey,
+                vote_pubkey,
+                vote_account,
+                rent,
+                *stake_lamports,
+            ),
+        );
+    }
+}
+
+/// The total balance of the stake accounts of `info`
+fn validator_stake(info: &StakedValidatorAccountInfo) -> u64 {
+    if info.stake_accounts.is_empty() {
+        info.stake_lamports
+    } else {
+        info.stake_accounts.iter().fold(0u64, |stake, account| {
+            stake.saturating_add(account.stake_lamports)
+        })
+    }
+}
+
+/// Take `lamports` from the stake accounts of `info`, last first. Emptied
+/// accounts are removed, as long as another one is left. Returns the stake of
+/// the validator if it is less than `lamports`.
+fn withdraw_stake(info: &mut StakedValidatorAccountInfo, lamports: u64) -> Result<(), u64> {
+    let stake = validator_stake(info);
+    if stake < lamports {
+        return Err(stake);
+    }
+    if info.stake_accounts.is_empty() {
+        info.stake_lamports -= lamports;
+        return Ok(());
+    }
+    let mut remaining = lamports;
+    while remaining > 0 {
+        let account = info.stake_accounts.last_mut().expect("stake is left");
+        let taken = account.stake_lamports.min(remaining);
+        account.stake_lamports -= taken;
+        remaining -= taken;
+        if account.stake_lamports == 0 && info.stake_accounts.len() > 1 {
+            info.stake_accounts.pop();
+        }
+    }
+    Ok(())
+}
+
+/// Add `lamports` to the first stake account of `info`
+fn deposit_stake(info: &mut StakedValidatorAccountInfo, lamports: u64) -> Option<()> {
+    let balance = match info.stake_accounts.first_mut() {
+        Some(account) => &mut account.stake_lamports,
+        None => &mut info.stake_lamports,
+    };
+    *balance = balance.checked_add(lamports)?;
+    Some(())
+}
+
+/// Apply `moves`, in order, to the stake accounts of `validators`, returning
+/// the rebalanced validators without modifying them. Stake is taken from the
+/// last stake accounts of the source first, see [`withdraw_stake`], and added
+/// to the first stake account of the destination. Fails if any move names an
+/// unknown validator, or takes more stake from a validator than it has at that
+/// point.
+pub fn rebalance_stakes(
+    validators: &[StakedValidatorAccountInfo],
+    moves: &[StakeMove],
+) -> Result<Vec<StakedValidatorAccountInfo>, GenesisError> {
+    let mut rebalanced = validators.to_vec();
+    let index: HashMap<&str, usize> = validators
+        .iter()
+        .enumerate()
+        .map(|(i, info)| (info.identity_account.as_str(), i))
+        .collect();
+
+    for (i, stake_move) in moves.iter().enumerate() {
+        let entry = format!("stake move {i}");
+        let position = |field: &str, identity_account: &str| {
+            index.get(identity_account).copied().ok_or_else(|| {
+                GenesisError::validation(
+                    &entry,
+                    field,
+                    format_args!("unknown validator {identity_account}"),
+                )
+            })
+        };
+        let from = position("from", &stake_move.from)?;
+        let to = position("to", &stake_move.to)?;
+
+        withdraw_stake(&mut rebalanced[from], stake_move.lamports).map_err(|stake_lamports| {
+            GenesisError::LimitExceeded {
+                which: format!("stake moved from validator {}", stake_move.from),
+                limit: stake_lamports,
+                actual: stake_move.lamports,
+            }
+        })?;
+        deposit_stake(&mut rebalanced[to], stake_move.lamports).ok_or_else(|| {
+            GenesisError::validation(
+                &entry,
+                "lamports",
+                format_args!("overflows the stake of validator {}", stake_move.to),
+            )
+        })?;
+    }
+
+    Ok(rebalanced)
+}
+
+/// Check that each stake account of `validators` is delegated only to the vote
+/// account of its entry, and that no account plays more than one role: a stake
+/// account is neither a vote nor an identity account, and a vote account is no
+/// identity. A vote account may be shared by several identities only if
+/// `allow_shared_vote` is set.
+pub fn verify_linkage(
+    validators: &[StakedValidatorAccountInfo],
+    allow_shared_vote: bool,
+) -> Result<(), String> {
+    let identities: HashSet<&str> = validators
+        .iter()
+        .map(|info| info.identity_account.as_str())
+        .collect();
+
+    let mut vote_identities = HashMap::<&str, &str>::new();
+    for info in validators {
+        let (identity, vote) = (info.identity_account.as_str(), info.vote_account.as_str());
+        if identities.contains(vote) {
+            return Err(format!(
+                "vote account {vote} of validator {identity} is also an identity account"
+            ));
+        }
+        match vote_identities.insert(vote, identity) {
+            Some(other) if other != identity && !allow_shared_vote => {
+                return Err(format!(
+                    "vote account {vote} is shared by validators {other} and {identity}"
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    let mut stake_votes = HashMap::<&str, &str>::new();
+    for info in validators {
+        let (identity, vote) = (info.identity_account.as_str(), info.vote_account.as_str());
+        for (stake_account, _) in info.stake_accounts().map_err(|err| err.to_string())? {
+            if identities.contains(stake_account) || vote_identities.contains_key(stake_account) {
+                return Err(format!(
+                    "stake account {stake_account} of validator {identity} is also an identity \
+                     or vote account"
+                ));
+            }
+            match stake_votes.insert(stake_account, vote) {
+                Some(other) if other == vote => {
+                    return Err(format!(
+                        "stake account {stake_account} is listed more than once for vote account \
+                         {vote}"
+                    ));
+                }
+                Some(other) => {
+                    return Err(format!(
+                        "stake account {stake_account} is delegated to both vote accounts {other} \
+                         and {vote}"
+                    ));
+                }
+                None => {}
+            }
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use {super::*, solana_rent::Rent};
+    use {
+        super::*, crate::ValidatorStakeAccount, assert_matches::assert_matches, solana_rent::Rent,
+    };
+
+    // no lockups
+    const UNLOCKS_ALL_DAY_ZERO: UnlockInfo = UnlockInfo {
+        cliff_fraction: 1.0,
+        cliff_years: 0.0,
+        unlocks: 0,
+        unlock_years: 0.0,
+        custodian: "11111111111111111111111111111111",
+    };
+
+    fn new_validator_info(stake_lamports: u64) -> StakedValidatorAccountInfo {
+        StakedValidatorAccountInfo {
+            balance_lamports: 1_000,
+            stake_lamports,
+            identity_account: solana_pubkey::new_rand().to_string(),
+            vote_account: solana_pubkey::new_rand().to_string(),
+            stake_account: solana_pubkey::new_rand().to_string(),
+            stake_accounts: vec![],
+        }
+    }
+
+    fn total_stake(validators: &[StakedValidatorAccountInfo]) -> u64 {
+        validators.iter().map(validator_stake).sum()
+    }
+
+    #[test]
+    fn test_rebalance_stakes() {
+        let validators = vec![
+            new_validator_info(100),
+            new_validator_info(200),
+            new_validator_info(300),
+        ];
+        let (a, b, c) = (
+            validators[0].identity_account.clone(),
+            validators[1].identity_account.clone(),
+            validators[2].identity_account.clone(),
+        );
+        let moves = [
+            StakeMove {
+                from: a.clone(),
+                to: b.clone(),
+                lamports: 100,
+            },
+            // only possible because of the previous move
+            StakeMove {
+                from: b,
+                to: c,
+                lamports: 250,
+            },
+        ];
+
+        let rebalanced = rebalance_stakes(&validators, &moves).unwrap();
+        assert_eq!(
+            rebalanced
+                .iter()
+                .map(|info| info.stake_lamports)
+                .collect::<Vec<_>>(),
+            vec![0, 50, 550]
+        );
+        assert_eq!(total_stake(&rebalanced), total_stake(&validators));
+        // only the stakes change, and the source is left untouched
+        assert_eq!(rebalanced[0].vote_account, validators[0].vote_account);
+        assert_eq!(validators[0].stake_lamports, 100);
+
+        assert_matches!(
+            rebalance_stakes(
+                &validators,
+                &[StakeMove {
+                    from: a.clone(),
+                    to: "unknown".to_string(),
+                    lamports: 1,
+                }]
+            ),
+            Err(GenesisError::Validation { entry, field, reason })
+                if entry == "stake move 0" && field == "to" && reason == "unknown validator unknown"
+        );
+    }
+
+    #[test]
+    fn test_rebalance_stakes_accounts() {
+        let mut validators = vec![new_validator_info(0), new_validator_info(100)];
+        validators[0].stake_account.clear();
+        validators[0].stake_accounts = [100, 200, 300]
+            .into_iter()
+            .map(|stake_lamports| ValidatorStakeAccount {
+                stake_account: solana_pubkey::new_rand().to_string(),
+                stake_lamports,
+            })
+            .collect();
+        let (a, b) = (
+            validators[0].identity_account.clone(),
+            validators[1].identity_account.clone(),
+        );
+
+        // the last account is drained, and removed, first
+        let rebalanced = rebalance_stakes(
+            &validators,
+            &[StakeMove {
+                from: a.clone(),
+                to: b.clone(),
+                lamports: 350,
+            }],
+        )
+        .unwrap();
+        assert_eq!(
+            rebalanced[0].stake_accounts,
+            vec![
+                validators[0].stake_accounts[0].clone(),
+                ValidatorStakeAccount {
+                    stake_account: validators[0].stake_accounts[1].stake_account.clone(),
+                    stake_lamports: 150,
+                },
+            ]
+        );
+        assert_eq!(rebalanced[1].stake_lamports, 450);
+        assert_eq!(total_stake(&rebalanced), total_stake(&validators));
+
+        // the first account is kept, and credited
+        let rebalanced = rebalance_stakes(
+            &validators,
+            &[
+                StakeMove {
+                    from: a.clone(),
+                    to: b.clone(),
+                    lamports: 600,
+                },
+                StakeMove {
+                    from: b,
+                    to: a,
+                    lamports: 50,
+                },
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            rebalanced[0].stake_accounts,
+            vec![ValidatorStakeAccount {
+                stake_account: validators[0].stake_accounts[0].stake_account.clone(),
+                stake_lamports: 50,
+            }]
+        );
+        assert_eq!(rebalanced[1].stake_lamports, 650);
+    }
+
+    #[test]
+    fn test_rebalance_stakes_underflow() {
+        let validators = vec![new_validator_info(100), new_validator_info(200)];
+        let moves = [StakeMove {
+            from: validators[0].identity_account.clone(),
+            to: validators[1].identity_account.clone(),
+            lamports: 101,
+        }];
+        assert_matches!(
+            rebalance_stakes(&validators, &moves),
+            Err(GenesisError::LimitExceeded { which, limit: 100, actual: 101 })
+                if which == format!("stake moved from validator {}", validators[0].identity_account)
+        );
+    }
+
+    #[test]
+    fn test_rebalance_stakes_conserves_total() {
+        let validators: Vec<_> = (1..=5).map(|i| new_validator_info(i * 1_000)).collect();
+        let moves: Vec<_> = (0..20)
+            .map(|i| StakeMove {
+                from: validators[i % 5].identity_account.clone(),
+                to: validators[(i * 3 + 1) % 5].identity_account.clone(),
+                lamports: 100 + i as u64,
+            })
+            .collect();
+        let rebalanced = rebalance_stakes(&validators, &moves).unwrap();
+        assert_eq!(total_stake(&rebalanced), total_stake(&validators));
+    }
+
+    #[test]
+    fn test_verify_linkage() {
+        let mut validators = vec![new_validator_info(100), new_validator_info(200)];
+        validators[1].stake_account.clear();
+        validators[1].stake_accounts = (0..2)
+            .map(|_| ValidatorStakeAccount {
+                stake_account: solana_pubkey::new_rand().to_string(),
+                stake_lamports: 100,
+            })
+            .collect();
+        assert_eq!(verify_linkage(&validators, false), Ok(()));
+
+        // a stake account listed by two entries would be delegated twice
+        let mut delegated_twice = validators.clone();
+        delegated_twice[1].stake_accounts[1].stake_account = validators[0].stake_account.clone();
+        assert!(verify_linkage(&delegated_twice, true)
+            .unwrap_err()
+            .contains("is delegated to both vote accounts"));
+
+        let mut listed_twice = validators.clone();
+        listed_twice[1].stake_accounts[1].stake_account =
+            validators[1].stake_accounts[0].stake_account.clone();
+        assert!(verify_linkage(&listed_twice, true)
+            .unwrap_err()
+            .contains("is listed more than once"));
+    }
+
+    #[test]
+    fn test_verify_linkage_shared_vote() {
+        let mut validators = vec![new_validator_info(100), new_validator_info(200)];
+        validators[1].vote_account = validators[0].vote_account.clone();
+        assert_eq!(
+            verify_linkage(&validators, false),
+            Err(format!(
+                "vote account {} is shared by validators {} and {}",
+                validators[0].vote_account,
+                validators[0].identity_account,
+                validators[1].identity_account,
+            ))
+        );
+        assert_eq!(verify_linkage(&validators, true), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_linkage_self_linkage() {
+        let validators = vec![new_validator_info(100)];
+
+        let mut vote_is_identity = validators.clone();
+        vote_is_identity[0].vote_account = validators[0].identity_account.clone();
+        assert!(verify_linkage(&vote_is_identity, true)
+            .unwrap_err()
+            .contains("is also an identity account"));
+
+        let mut stake_is_vote = validators.clone();
+        stake_is_vote[0].stake_account = validators[0].vote_account.clone();
+        assert!(verify_linkage(&stake_is_vote, true)
+            .unwrap_err()
+            .contains("is also an identity or vote account"));
+
+        let mut stake_is_identity = validators;
+        stake_is_identity[0].stake_account = stake_is_identity[0].identity_account.clone();
+        assert!(verify_linkage(&stake_is_identity, true)
+            .unwrap_err()
+            .contains("is also an identity or vote account"));
+    }
 
     fn create_and_check_stakes(
         genesis_config: &mut GenesisConfig,
@@ -175,7 +836,14 @@ mod tests {
     ) {
         assert_eq!(
             total_lamports,
-            create_and_add_stakes(genesis_config, staker_info, unlock_info, Some(granularity))
+            create_and_add_stakes(
+                genesis_config,
+                staker_info,
+                unlock_info,
+                Some(granularity),
+                None
+            )
+            .unwrap()
         );
         assert_eq!(genesis_config.accounts.len(), len);
         assert_eq!(
@@ -252,6 +920,7 @@ mod tests {
                 staker: "P1aceHo1derPubkey11111111111111111111111111",
                 lamports: total_lamports,
                 withdrawer: None,
+                ..StakerInfo::DEFAULT
             },
             &UnlockInfo {
                 cliff_fraction: 0.5,
@@ -278,6 +947,7 @@ mod tests {
                 staker: "P1aceHo1derPubkey11111111111111111111111111",
                 lamports: total_lamports,
                 withdrawer: None,
+                ..StakerInfo::DEFAULT
             },
             &UnlockInfo {
                 cliff_fraction: 0.5,
@@ -304,6 +974,7 @@ mod tests {
                 staker: "P1aceHo1derPubkey11111111111111111111111111",
                 lamports: total_lamports,
                 withdrawer: None,
+                ..StakerInfo::DEFAULT
             },
             &UnlockInfo {
                 cliff_fraction: 0.5,
@@ -329,6 +1000,7 @@ mod tests {
                 staker: "P1aceHo1derPubkey11111111111111111111111111",
                 lamports: total_lamports,
                 withdrawer: None,
+                ..StakerInfo::DEFAULT
             },
             &UnlockInfo {
                 cliff_fraction: 0.5,
@@ -342,4 +1014,334 @@ mod tests {
             4 + 1,
         );
     }
+
+    #[test]
+    fn test_split_lamports() {
+        const SPLITS: &[StakerSplit] = &[
+            StakerSplit {
+                staker: "uE3TVEffRp69mrgknYr71M18GDqL7GxCNGYYRjb3oUt",
+                fraction: 1.0 / 3.0,
+            },
+            StakerSplit {
+                staker: "9noVEZreMmgQvE8iyKmxy7CGTJ2enELyuJ1qxFtXrfJB",
+                fraction: 1.0 / 3.0,
+            },
+            StakerSplit {
+                staker: "BU7LA4kYvicfPCp22EM2Tth3eaeWAXYo6yCgWXQFJ42z",
+                fraction: 1.0 / 3.0,
+            },
+        ];
+        // the last split absorbs the rounding
+        assert_eq!(split_lamports(100, SPLITS), Ok(vec![33, 33, 34]));
+        assert_eq!(split_lamports(2, SPLITS), Ok(vec![0, 0, 2]));
+        assert_eq!(split_lamports(100, &[]), Ok(vec![100]));
+
+        // fractions summing to 1.0 up to floating point error are accepted
+        const STAKERS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+        let tenths: Vec<_> = STAKERS
+            .iter()
+            .map(|&staker| StakerSplit {
+                staker,
+                fraction: 0.1,
+            })
+            .collect();
+        assert_eq!(
+            split_lamports(1_000, &tenths).unwrap().iter().sum::<u64>(),
+            1_000
+        );
+
+        assert_eq!(
+            split_lamports(100, &SPLITS[..2]),
+            Err(StakeSplitError::FractionsSum(2.0 / 3.0))
+        );
+        assert_eq!(
+            split_lamports(
+                100,
+                &[
+                    StakerSplit {
+                        staker: SPLITS[0].staker,
+                        fraction: 1.5,
+                    },
+                    StakerSplit {
+                        staker: SPLITS[1].staker,
+                        fraction: -0.5,
+                    },
+                ]
+            ),
+            Err(StakeSplitError::InvalidFraction {
+                staker: SPLITS[0].staker.to_string(),
+                fraction: 1.5,
+            })
+        );
+        assert_eq!(
+            split_lamports(
+                100,
+                &[
+                    StakerSplit {
+                        staker: SPLITS[0].staker,
+                        fraction: 0.5,
+                    },
+                    StakerSplit {
+                        staker: SPLITS[0].staker,
+                        fraction: 0.5,
+                    },
+                ]
+            ),
+            Err(StakeSplitError::DuplicateStaker(
+                SPLITS[0].staker.to_string()
+            ))
+        );
+    }
+
+    fn stake_authorities(genesis_config: &GenesisConfig) -> Vec<(Pubkey, Authorized, u64)> {
+        genesis_config
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.owner == stake::program::id())
+            .map(
+                |(address, account)| match bincode::deserialize(&account.data).unwrap() {
+                    StakeStateV2::Initialized(meta) => {
+                        (*address, meta.authorized, account.lamports)
+                    }
+                    stake_state => panic!("unexpected stake state {stake_state:?}"),
+                },
+            )
+            .collect()
+    }
+
+    #[test]
+    fn test_create_stakes_with_authorities() {
+        let staker: Pubkey = "P1aceHo1derPubkey11111111111111111111111111"
+            .parse()
+            .unwrap();
+        let withdrawer: Pubkey = "59SLqk4ete5QttM1WmjfMA7uNJnJVFLQqXJSy9rvuj7c"
+            .parse()
+            .unwrap();
+        let staker_authority: Pubkey = "ERnx3Csgu3LjrGGrCeCUZzuHguRu6XabT1kufSB1NDWi"
+            .parse()
+            .unwrap();
+        let staker_info = StakerInfo {
+            name: "fun",
+            staker: "P1aceHo1derPubkey11111111111111111111111111",
+            lamports: 1_000_000_000,
+            withdrawer: Some("59SLqk4ete5QttM1WmjfMA7uNJnJVFLQqXJSy9rvuj7c"),
+            staker_authority: Some("ERnx3Csgu3LjrGGrCeCUZzuHguRu6XabT1kufSB1NDWi"),
+            ..StakerInfo::DEFAULT
+        };
+        let mut genesis_config = GenesisConfig::default();
+        create_and_add_stakes(
+            &mut genesis_config,
+            &staker_info,
+            &UNLOCKS_ALL_DAY_ZERO,
+            None,
+            None,
+        )
+        .unwrap();
+
+        // the staker still pays the fees, the stake account is derived from
+        //  the share of its staker authority
+        assert!(genesis_config.accounts.contains_key(&staker));
+        let base = stake_address_base(&staker_info, &staker, &staker_authority, 0);
+        assert_ne!(base, staker_authority);
+        let stakes = stake_authorities(&genesis_config);
+        assert_eq!(stakes.len(), 1);
+        assert_eq!(
+            stakes[0].0,
+            AddressGenerator::new(&base, &stake::program::id()).nth(0)
+        );
+        assert_eq!(
+            stakes[0].1,
+            Authorized {
+                staker: staker_authority,
+                withdrawer,
+            }
+        );
+
+        // another entry sharing the staker authority gets stake accounts of
+        //  its own
+        create_and_add_stakes(
+            &mut genesis_config,
+            &StakerInfo {
+                name: "more fun",
+                ..staker_info
+            },
+            &UNLOCKS_ALL_DAY_ZERO,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(stake_authorities(&genesis_config).len(), 2);
+    }
+
+    #[test]
+    fn test_create_stakes_with_splits() {
+        const SPLITS: &[StakerSplit] = &[
+            StakerSplit {
+                staker: "uE3TVEffRp69mrgknYr71M18GDqL7GxCNGYYRjb3oUt",
+                fraction: 0.25,
+            },
+            StakerSplit {
+                staker: "9noVEZreMmgQvE8iyKmxy7CGTJ2enELyuJ1qxFtXrfJB",
+                fraction: 0.25,
+            },
+            StakerSplit {
+                staker: "BU7LA4kYvicfPCp22EM2Tth3eaeWAXYo6yCgWXQFJ42z",
+                fraction: 0.5,
+            },
+        ];
+        let staker: Pubkey = "P1aceHo1derPubkey11111111111111111111111111"
+            .parse()
+            .unwrap();
+        let total_lamports = 1_000_000_003;
+        let staker_info = StakerInfo {
+            name: "fun",
+            staker: "P1aceHo1derPubkey11111111111111111111111111",
+            lamports: total_lamports,
+            withdrawer: None,
+            splits: SPLITS,
+            ..StakerInfo::DEFAULT
+        };
+        let mut genesis_config = GenesisConfig::default();
+        assert_eq!(
+            create_and_add_stakes(
+                &mut genesis_config,
+                &staker_info,
+                &UNLOCKS_ALL_DAY_ZERO,
+                None,
+                None,
+            )
+            .unwrap(),
+            total_lamports
+        );
+
+        let stakes_lamports = total_lamports - genesis_config.accounts[&staker].lamports;
+        let expected_lamports = split_lamports(stakes_lamports, SPLITS).unwrap();
+        assert_eq!(expected_lamports.iter().sum::<u64>(), stakes_lamports);
+        assert_eq!(
+            expected_lamports[2],
+            stakes_lamports - 2 * expected_lamports[0]
+        );
+
+        let stakes = stake_authorities(&genesis_config);
+        assert_eq!(stakes.len(), SPLITS.len());
+        for (nth, (split, lamports)) in SPLITS.iter().zip(expected_lamports).enumerate() {
+            let split_staker = split.staker.parse().unwrap();
+            let base = stake_address_base(&staker_info, &staker, &split_staker, nth);
+            let stake_address = AddressGenerator::new(&base, &stake::program::id()).nth(0);
+            assert!(stakes.contains(&(
+                stake_address,
+                Authorized {
+                    staker: split_staker,
+                    withdrawer: staker,
+                },
+                lamports,
+            )));
+        }
+
+        // invalid splits are rejected before anything is added to genesis
+        let mut genesis_config = GenesisConfig::default();
+        assert_matches!(
+            create_and_add_stakes(
+                &mut genesis_config,
+                &StakerInfo {
+                    splits: &SPLITS[..2],
+                    ..staker_info
+                },
+                &UNLOCKS_ALL_DAY_ZERO,
+                None,
+                None,
+            ),
+            Err(GenesisError::Validation { field, .. }) if field == "splits"
+        );
+        // so are splits too small to get any lamports
+        assert_matches!(
+            create_and_add_stakes(
+                &mut genesis_config,
+                &StakerInfo {
+                    splits: &[
+                        StakerSplit {
+                            staker: SPLITS[0].staker,
+                            fraction: 1e-12,
+                        },
+                        StakerSplit {
+                            staker: SPLITS[1].staker,
+                            fraction: 1.0 - 1e-12,
+                        },
+                    ],
+                    ..staker_info
+                },
+                &UNLOCKS_ALL_DAY_ZERO,
+                None,
+                None,
+            ),
+            Err(GenesisError::Validation { field, reason, .. })
+                if field == "splits" && reason.contains("gets no lamports")
+        );
+        assert!(genesis_config.accounts.is_empty());
+    }
+
+    #[test]
+    fn test_create_stakes_errors() {
+        const STAKER_INFO: StakerInfo = StakerInfo {
+            name: "fun",
+            staker: "P1aceHo1derPubkey11111111111111111111111111",
+            lamports: 1_000_000_000,
+            withdrawer: None,
+            ..StakerInfo::DEFAULT
+        };
+        let create = |genesis_config: &mut GenesisConfig, staker_info: &StakerInfo| {
+            create_and_add_stakes(
+                genesis_config,
+                staker_info,
+                &UNLOCKS_ALL_DAY_ZERO,
+                None,
+                None,
+            )
+        };
+
+        let mut genesis_config = GenesisConfig::default();
+        assert_matches!(
+            create(
+                &mut genesis_config,
+                &StakerInfo {
+                    withdrawer: Some("withdrawer"),
+                    ..STAKER_INFO
+                }
+            ),
+            Err(GenesisError::Validation { entry, field, reason })
+                if entry == "fun" && field == "withdrawer" && reason.contains("withdrawer")
+        );
+        assert_matches!(
+            create(
+                &mut genesis_config,
+                &StakerInfo {
+                    lamports: 1,
+                    ..STAKER_INFO
+                }
+            ),
+            Err(GenesisError::LimitExceeded { limit: 1, .. })
+        );
+        assert!(genesis_config.accounts.is_empty());
+
+        let mut unlock_info = UNLOCKS_ALL_DAY_ZERO;
+        unlock_info.cliff_fraction = 2.0;
+        assert_matches!(
+            create_and_add_stakes(&mut genesis_config, &STAKER_INFO, &unlock_info, None, None),
+            Err(GenesisError::Validation { field, .. }) if field == "cliff_fraction"
+        );
+
+        // entries deriving the same stake accounts are rejected rather than
+        //  replacing one another
+        create(&mut genesis_config, &STAKER_INFO).unwrap();
+        assert_matches!(
+            create(
+                &mut genesis_config,
+                &StakerInfo {
+                    name: "more fun",
+                    ..STAKER_INFO
+                }
+            ),
+            Err(GenesisError::Conflict { sources, .. }) if sources[0] == "the stakes of more fun"
+        );
+    }
 }
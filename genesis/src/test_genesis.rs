@@ -0,0 +1,225 @@
+//! Minimal genesis for integration tests, built in memory
+use {
+    crate::bootstrap_validators::{
+        derive_keypair, generate_bootstrap_validators, GeneratedValidator,
+    },
+    solana_account::AccountSharedData,
+    solana_genesis_config::{ClusterType, GenesisConfig},
+    solana_keypair::Keypair,
+    solana_native_token::LAMPORTS_PER_SOL,
+    solana_pubkey::Pubkey,
+    solana_rent::Rent,
+    solana_sdk_ids::system_program,
+    solana_signer::Signer,
+    solana_stake_interface::state::StakeStateV2,
+    solana_stake_program::stake_state,
+    solana_vote_program::vote_state::{self, VoteState},
+    std::fmt,
+};
+
+/// Balance of the identity account of each validator of a minimal test genesis
+pub const MINIMAL_TEST_VALIDATOR_BALANCE_LAMPORTS: u64 = 10 * LAMPORTS_PER_SOL;
+
+/// Keypairs of a minimal test genesis are derived from this seed
+const MINIMAL_TEST_GENESIS_SEED: u64 = 0;
+
+/// A genesis built by `build_minimal_test_genesis()`, with the keypairs of its accounts
+pub struct MinimalTestGenesis {
+    pub genesis_config: GenesisConfig,
+    pub faucet_keypair: Keypair,
+    pub validators: Vec<GeneratedValidator>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MinimalTestGenesisError {
+    NoValidators,
+    InsufficientSupply { supply: u64, required: u64 },
+    DuplicateAccount(Pubkey),
+    NotRentExempt(Pubkey),
+    LamportsNotConserved { supply: u64, total: u64 },
+}
+
+impl fmt::Display for MinimalTestGenesisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoValidators => write!(f, "a test genesis needs at least one validator"),
+            Self::InsufficientSupply { supply, required } => write!(
+                f,
+                "supply of {supply} lamports is below the {required} lamports required"
+            ),
+            Self::DuplicateAccount(pubkey) => write!(f, "account {pubkey} is added twice"),
+            Self::NotRentExempt(pubkey) => write!(f, "account {pubkey} is not rent exempt"),
+            Self::LamportsNotConserved { supply, total } => write!(
+                f,
+                "genesis holds {total} lamports instead of the {supply} lamports supply"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MinimalTestGenesisError {}
+
+/// Build a development genesis holding exactly `supply` lamports, shared
+/// between `num_validators` staked validators and a faucet.
+///
+/// Each validator's identity is funded with `MINIMAL_TEST_VALIDATOR_BALANCE_LAMPORTS`
+/// and its vote account with the rent-exempt reserve; half of the rest is
+/// staked evenly across the validators and the faucet holds the remainder.
+/// The keypairs are deterministic, so the genesis is too.
+pub fn build_minimal_test_genesis(
+    num_validators: usize,
+    supply: u64,
+) -> Result<MinimalTestGenesis, MinimalTestGenesisError> {
+    if num_validators == 0 {
+        return Err(MinimalTestGenesisError::NoValidators);
+    }
+
+    let rent = Rent::default();
+    let vote_reserve = VoteState::get_rent_exempt_reserve(&rent).max(1);
+    let stake_reserve = rent.minimum_balance(StakeStateV2::size_of());
+    let num_validators_lamports = num_validators as u64;
+
+    // every validator must be left with a delegated stake, and the faucet with
+    //  at least as much as the stakes
+    let validators_lamports =
+        num_validators_lamports * (MINIMAL_TEST_VALIDATOR_BALANCE_LAMPORTS + vote_reserve);
+    let required = validators_lamports + 2 * num_validators_lamports * (stake_reserve + 1);
+    if supply < required {
+        return Err(MinimalTestGenesisError::InsufficientSupply { supply, required });
+    }
+    let total_stake_lamports = (supply - validators_lamports) / 2;
+
+    let validators = generate_bootstrap_validators(
+        num_validators,
+        total_stake_lamports,
+        MINIMAL_TEST_VALIDATOR_BALANCE_LAMPORTS,
+        MINIMAL_TEST_GENESIS_SEED,
+    );
+    let faucet_keypair = derive_keypair(MINIMAL_TEST_GENESIS_SEED, num_validators, "faucet");
+
+    let mut genesis_config = GenesisConfig {
+        cluster_type: ClusterType::Development,
+        rent,
+        ..GenesisConfig::default()
+    };
+    let mut add_account = |pubkey: Pubkey, account: AccountSharedData| {
+        if genesis_config.accounts.contains_key(&pubkey) {
+            return Err(MinimalTestGenesisError::DuplicateAccount(pubkey));
+        }
+        genesis_config.add_account(pubkey, account);
+        Ok(())
+    };
+
+    for validator in &validators {
+        let identity_pubkey = validator.identity.pubkey();
+        let vote_pubkey = validator.vote_account.pubkey();
+        let vote_account = vote_state::create_account_with_authorized(
+            &identity_pubkey,
+            &identity_pubkey,
+            &identity_pubkey,
+            0,
+            vote_reserve,
+        );
+        add_account(
+            identity_pubkey,
+            AccountSharedData::new(validator.balance_lamports, 0, &system_program::id()),
+        )?;
+        add_account(
+            validator.stake_account.pubkey(),
+            stake_state::create_account(
+                &identity_pubkey,
+                &vote_pubkey,
+                &vote_account,
+                &rent,
+                validator.stake_lamports,
+            ),
+        )?;
+        add_account(vote_pubkey, vote_account)?;
+    }
+    let faucet_lamports = supply - validators_lamports - total_stake_lamports;
+    add_account(
+        faucet_keypair.pubkey(),
+        AccountSharedData::new(faucet_lamports, 0, &system_program::id()),
+    )?;
+
+    if let Some(pubkey) = genesis_config
+        .accounts
+        .iter()
+        .find(|(_, account)| !rent.is_exempt(account.lamports, account.data.len()))
+        .map(|(pubkey, _)| *pubkey)
+    {
+        return Err(MinimalTestGenesisError::NotRentExempt(pubkey));
+    }
+    let total = genesis_config
+        .accounts
+        .values()
+        .map(|account| account.lamports)
+        .sum::<u64>();
+    if total != supply {
+        return Err(MinimalTestGenesisError::LamportsNotConserved { supply, total });
+    }
+
+    Ok(MinimalTestGenesis {
+        genesis_config,
+        faucet_keypair,
+        validators,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_account::ReadableAccount};
+
+    #[test]
+    fn test_build_minimal_test_genesis() {
+        let supply = 1_000_000 * LAMPORTS_PER_SOL + 1;
+        let MinimalTestGenesis {
+            genesis_config,
+            faucet_keypair,
+            validators,
+        } = build_minimal_test_genesis(3, supply).unwrap();
+
+        // an identity, a vote and a stake account per validator, and the faucet
+        assert_eq!(validators.len(), 3);
+        assert_eq!(genesis_config.accounts.len(), 3 * 3 + 1);
+        assert_eq!(
+            genesis_config
+                .accounts
+                .values()
+                .map(|account| account.lamports)
+                .sum::<u64>(),
+            supply
+        );
+        assert!(genesis_config
+            .accounts
+            .contains_key(&faucet_keypair.pubkey()));
+
+        for validator in &validators {
+            let stake_account = AccountSharedData::from(
+                genesis_config.accounts[&validator.stake_account.pubkey()].clone(),
+            );
+            let stake = stake_state::stake_from(&stake_account).unwrap();
+            assert_eq!(
+                stake.delegation.voter_pubkey,
+                validator.vote_account.pubkey()
+            );
+            assert_eq!(stake_account.lamports(), validator.stake_lamports,);
+        }
+
+        // the accounts are deterministic
+        let rebuilt = build_minimal_test_genesis(3, supply).unwrap();
+        assert_eq!(rebuilt.genesis_config.accounts, genesis_config.accounts);
+    }
+
+    #[test]
+    fn test_build_minimal_test_genesis_invalid() {
+        assert_eq!(
+            build_minimal_test_genesis(0, LAMPORTS_PER_SOL).err(),
+            Some(MinimalTestGenesisError::NoValidators)
+        );
+        assert!(matches!(
+            build_minimal_test_genesis(2, MINIMAL_TEST_VALIDATOR_BALANCE_LAMPORTS).err(),
+            Some(MinimalTestGenesisError::InsufficientSupply { .. })
+        ));
+    }
+}
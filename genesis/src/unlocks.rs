@@ -1,16 +1,54 @@
 //! lockups generator
 use {
-    solana_clock::Epoch, solana_epoch_schedule::EpochSchedule, solana_time_utils::years_as_slots,
-    std::time::Duration,
+    crate::error::GenesisError, solana_clock::Epoch, solana_epoch_schedule::EpochSchedule,
+    solana_pubkey::Pubkey, solana_time_utils::years_as_slots, std::time::Duration,
 };
 
 #[derive(Debug)]
-pub struct UnlockInfo {
+pub struct UnlockInfo<'a> {
     pub cliff_fraction: f64,
     pub cliff_years: f64,
     pub unlocks: usize,
     pub unlock_years: f64,
-    pub custodian: &'static str,
+    pub custodian: &'a str,
+}
+
+impl UnlockInfo<'_> {
+    /// Check that the schedule, used by `entry`, is well-formed
+    pub fn validate(&self, entry: &str) -> Result<(), GenesisError> {
+        if !(0.0..=1.0).contains(&self.cliff_fraction) {
+            return Err(GenesisError::validation(
+                entry,
+                "cliff_fraction",
+                format_args!("{} is not in [0, 1]", self.cliff_fraction),
+            ));
+        }
+        if !(self.cliff_years >= 0.0 && self.cliff_years.is_finite()) {
+            return Err(GenesisError::validation(
+                entry,
+                "cliff_years",
+                format_args!("{} is not a non-negative number of years", self.cliff_years),
+            ));
+        }
+        if self.unlocks > 0 && !(self.unlock_years > 0.0 && self.unlock_years.is_finite()) {
+            return Err(GenesisError::validation(
+                entry,
+                "unlock_years",
+                format_args!(
+                    "{} unlocks cannot be {} years apart",
+                    self.unlocks, self.unlock_years
+                ),
+            ));
+        }
+        self.custodian.parse::<Pubkey>().map_err(|err| {
+            GenesisError::validation(
+                entry,
+                "custodian",
+                format_args!("{}: {err}", self.custodian),
+            )
+        })?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -143,6 +181,54 @@ impl Unlock {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_validate_unlock_info() {
+        let unlock_info = UnlockInfo {
+            cliff_fraction: 0.5,
+            cliff_years: 0.75,
+            unlocks: 24,
+            unlock_years: 2.0,
+            custodian: "Mc5XB47H3DKJHym5RLa9mPzWv5snERsF3KNv5AauXK8",
+        };
+        assert!(unlock_info.validate("fun").is_ok());
+
+        let invalid_field = |unlock_info: UnlockInfo| match unlock_info.validate("fun") {
+            Err(GenesisError::Validation { entry, field, .. }) => {
+                assert_eq!(entry, "fun");
+                field
+            }
+            result => panic!("unexpected result {result:?}"),
+        };
+        assert_eq!(
+            invalid_field(UnlockInfo {
+                cliff_fraction: 1.5,
+                ..unlock_info
+            }),
+            "cliff_fraction"
+        );
+        assert_eq!(
+            invalid_field(UnlockInfo {
+                cliff_years: -1.0,
+                ..unlock_info
+            }),
+            "cliff_years"
+        );
+        assert_eq!(
+            invalid_field(UnlockInfo {
+                unlock_years: 0.0,
+                ..unlock_info
+            }),
+            "unlock_years"
+        );
+        assert_eq!(
+            invalid_field(UnlockInfo {
+                custodian: "custodian",
+                ..unlock_info
+            }),
+            "custodian"
+        );
+    }
+
     #[test]
     fn test_make_lockups() {
         // this number just a random val
@@ -1140,7 +1140,9 @@ impl ClusterInfo {
             .collect()
     }
 
-    fn refresh_my_gossip_contact_info(&self) {
+    /// Refresh the wallclock of the contact info of this node and insert it
+    /// into the crds table, so that the next push round sends it to peers.
+    pub fn refresh_my_gossip_contact_info(&self) {
         let keypair: Arc<Keypair> = self.keypair().clone();
         let node = {
             let mut node = self.my_contact_info.write().unwrap();
@@ -0,0 +1,74 @@
+use std::{
+    env,
+    path::Path,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|output| !output.is_empty())
+}
+
+// Build information reported by the `node-version` subcommand
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version =
+        command_output(&rustc, &["--version"]).unwrap_or_else(|| "unknown".to_string());
+
+    let ci_commit = env::var("CI_COMMIT").ok();
+    let commit = ci_commit
+        .clone()
+        .or_else(|| command_output("git", &["rev-parse", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Honor SOURCE_DATE_EPOCH so that reproducible builds stay reproducible
+    let build_timestamp = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default()
+        });
+
+    let mut cargo_features: Vec<_> = env::vars()
+        .filter_map(|(name, _)| {
+            name.strip_prefix("CARGO_FEATURE_")
+                .map(|feature| feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    cargo_features.sort();
+
+    println!("cargo:rustc-env=AGAVE_VALIDATOR_RUSTC_VERSION={rustc_version}");
+    println!("cargo:rustc-env=AGAVE_VALIDATOR_GIT_COMMIT={commit}");
+    println!("cargo:rustc-env=AGAVE_VALIDATOR_BUILD_TIMESTAMP={build_timestamp}");
+    println!(
+        "cargo:rustc-env=AGAVE_VALIDATOR_CARGO_FEATURES={}",
+        cargo_features.join(",")
+    );
+    println!("cargo:rerun-if-changed=.");
+    if ci_commit.is_none() {
+        // Rerun once HEAD moves, whether to another branch or to another
+        // commit of the current one, which is packed or not. Missing files
+        // would rerun every build.
+        let head_ref = command_output("git", &["symbolic-ref", "-q", "HEAD"]);
+        for git_file in ["HEAD", "packed-refs"]
+            .into_iter()
+            .chain(head_ref.as_deref())
+        {
+            if let Some(path) = command_output("git", &["rev-parse", "--git-path", git_file])
+                .filter(|path| Path::new(path).exists())
+            {
+                println!("cargo:rerun-if-changed={path}");
+            }
+        }
+    }
+    println!("cargo:rerun-if-env-changed=CI_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
@@ -0,0 +1,305 @@
+//! Push-based monitoring of a running validator
+//!
+//! Each `ValidatorEvent` is pushed to the subscribers of the admin RPC
+//! `eventsSubscribe` method, served on the same IPC path as the rest of the
+//! admin RPC.
+//!
+//! This replaces a WebSocket endpoint served with `tokio-tungstenite`:
+//! subscriptions use `jsonrpc-pubsub` over the admin RPC IPC path instead, so
+//! no other port is opened and the admin RPC access control applies as is.
+use {
+    crate::admin_rpc_service,
+    crossbeam_channel::{Receiver, RecvTimeoutError},
+    jsonrpc_core_client::TypedSubscriptionStream,
+    jsonrpc_pubsub::{
+        typed::{Sink, Subscriber},
+        SubscriptionId,
+    },
+    log::*,
+    serde::{Deserialize, Serialize},
+    solana_core::admin_rpc_post_init::AdminRpcRequestMetadataPostInit,
+    solana_rpc::optimistically_confirmed_bank_tracker::SlotNotification,
+    solana_runtime::{snapshot_archive_info::SnapshotArchiveInfoGetter, snapshot_utils},
+    solana_sdk::{clock::Slot, exit::Exit},
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, Mutex, RwLock,
+        },
+        thread::{Builder, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+/// Interval at which the snapshot archive directories are checked for new
+/// archives, and the exit flag for being set
+const EVENT_SOURCES_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ValidatorEvent {
+    /// A new root was set
+    SlotConfirmed { slot: Slot, bank_hash: String },
+    IdentityChanged {
+        old_pubkey: String,
+        new_pubkey: String,
+    },
+    /// A full or incremental snapshot archive was written
+    SnapshotComplete { slot: Slot, path: PathBuf },
+    /// A shred was requested with `repair-shred-from-peer`
+    RepairRequest { slot: Slot, shred_index: u64 },
+}
+
+/// Pushes events to the subscribers of the admin RPC `eventsSubscribe`
+/// method
+#[derive(Clone, Default)]
+pub struct AdminRpcEventSender {
+    next_subscription_id: Arc<AtomicU64>,
+    subscribers: Arc<Mutex<HashMap<u64, Sink<ValidatorEvent>>>>,
+}
+
+impl AdminRpcEventSender {
+    /// Push `event` to every subscriber, dropping the ones that disconnected
+    pub fn send(&self, event: &ValidatorEvent) {
+        self.subscribers.lock().unwrap().retain(|id, sink| {
+            let connected = sink.notify(Ok(event.clone())).is_ok();
+            if !connected {
+                debug!("admin events subscriber {id} disconnected");
+            }
+            connected
+        });
+    }
+
+    pub(crate) fn subscribe(&self, subscriber: Subscriber<ValidatorEvent>) {
+        let id = self.next_subscription_id.fetch_add(1, Ordering::Relaxed);
+        if let Ok(sink) = subscriber.assign_id(SubscriptionId::Number(id)) {
+            self.subscribers.lock().unwrap().insert(id, sink);
+        }
+    }
+
+    /// Returns false if `id` was not subscribed
+    pub(crate) fn unsubscribe(&self, id: &SubscriptionId) -> bool {
+        match id {
+            SubscriptionId::Number(id) => self.subscribers.lock().unwrap().remove(id).is_some(),
+            SubscriptionId::String(_) => false,
+        }
+    }
+}
+
+/// Subscribe to the events of the validator using `ledger_path`
+pub async fn subscribe_events(
+    ledger_path: &Path,
+) -> Result<TypedSubscriptionStream<ValidatorEvent>, String> {
+    let admin_client = admin_rpc_service::connect(ledger_path)
+        .await
+        .map_err(|err| format!("Unable to connect to validator: {err}"))?;
+    admin_client
+        .events_subscribe()
+        .map_err(|err| format!("Unable to subscribe to events: {err}"))
+}
+
+/// Parts of the validator state turned into events
+pub struct EventSources {
+    post_init: Arc<RwLock<Option<AdminRpcRequestMetadataPostInit>>>,
+    /// Notified by the validator of each new root
+    slot_notification_receiver: Receiver<SlotNotification>,
+    full_snapshot_archives_dir: PathBuf,
+    incremental_snapshot_archives_dir: PathBuf,
+    full_snapshot_slot: Option<Slot>,
+    incremental_snapshot_slot: Option<Slot>,
+}
+
+impl EventSources {
+    pub fn new(
+        post_init: Arc<RwLock<Option<AdminRpcRequestMetadataPostInit>>>,
+        slot_notification_receiver: Receiver<SlotNotification>,
+        full_snapshot_archives_dir: PathBuf,
+        incremental_snapshot_archives_dir: PathBuf,
+    ) -> Self {
+        Self {
+            post_init,
+            slot_notification_receiver,
+            full_snapshot_archives_dir,
+            incremental_snapshot_archives_dir,
+            full_snapshot_slot: None,
+            incremental_snapshot_slot: None,
+        }
+    }
+
+    /// Publish the roots notified until the poll interval elapses, then the
+    /// snapshot archives written since the previous call. Returns false once
+    /// the validator stopped sending notifications.
+    pub(crate) fn publish(&mut self, events: &AdminRpcEventSender) -> bool {
+        let deadline = Instant::now() + EVENT_SOURCES_POLL_INTERVAL;
+        loop {
+            match self.slot_notification_receiver.recv_deadline(deadline) {
+                Ok(SlotNotification::Root((slot, _parent))) => self.publish_root(slot, events),
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return false,
+            }
+        }
+        self.publish_snapshots(events);
+        true
+    }
+
+    fn publish_root(&self, slot: Slot, events: &AdminRpcEventSender) {
+        let bank_hash = self
+            .post_init
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|post_init| post_init.blockstore.get_bank_hash(slot));
+        match bank_hash {
+            Some(bank_hash) => events.send(&ValidatorEvent::SlotConfirmed {
+                slot,
+                bank_hash: bank_hash.to_string(),
+            }),
+            None => debug!("No bank hash for root {slot}"),
+        }
+    }
+
+    fn publish_snapshots(&mut self, events: &AdminRpcEventSender) {
+        let Some(full_snapshot) = snapshot_utils::get_highest_full_snapshot_archive_info(
+            &self.full_snapshot_archives_dir,
+        ) else {
+            return;
+        };
+        if self.full_snapshot_slot != Some(full_snapshot.slot()) {
+            self.full_snapshot_slot = Some(full_snapshot.slot());
+            events.send(&ValidatorEvent::SnapshotComplete {
+                slot: full_snapshot.slot(),
+                path: full_snapshot.path().clone(),
+            });
+        }
+        if let Some(incremental_snapshot) =
+            snapshot_utils::get_highest_incremental_snapshot_archive_info(
+                &self.incremental_snapshot_archives_dir,
+                full_snapshot.slot(),
+            )
+        {
+            if self.incremental_snapshot_slot != Some(incremental_snapshot.slot()) {
+                self.incremental_snapshot_slot = Some(incremental_snapshot.slot());
+                events.send(&ValidatorEvent::SnapshotComplete {
+                    slot: incremental_snapshot.slot(),
+                    path: incremental_snapshot.path().clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Publish the events of `sources` until the validator exits
+pub fn spawn_event_sources(
+    mut sources: EventSources,
+    events: AdminRpcEventSender,
+    validator_exit: &RwLock<Exit>,
+) -> JoinHandle<()> {
+    let exit = Arc::new(AtomicBool::new(false));
+    validator_exit.write().unwrap().register_exit(Box::new({
+        let exit = exit.clone();
+        move || exit.store(true, Ordering::Relaxed)
+    }));
+    Builder::new()
+        .name("solAdminEvents".to_string())
+        .spawn(
+            move || {
+                while !exit.load(Ordering::Relaxed) && sources.publish(&events) {}
+            },
+        )
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crossbeam_channel::unbounded,
+        serde_json::Value,
+        solana_runtime::{snapshot_hash::SnapshotHash, snapshot_utils::ArchiveFormat},
+        solana_sdk::hash::Hash,
+        std::fs,
+    };
+
+    /// Extract the event from an `eventNotification` sent to a test subscriber
+    fn notified_event(notification: String) -> ValidatorEvent {
+        let notification: Value = serde_json::from_str(&notification).unwrap();
+        assert_eq!(notification["method"], "eventNotification");
+        serde_json::from_value(notification["params"]["result"].clone()).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_events() {
+        let full_snapshot_archives_dir = tempfile::tempdir().unwrap();
+        let incremental_snapshot_archives_dir = tempfile::tempdir().unwrap();
+        let (slot_notification_sender, slot_notification_receiver) = unbounded();
+        let mut sources = EventSources::new(
+            Arc::default(),
+            slot_notification_receiver,
+            full_snapshot_archives_dir.path().to_path_buf(),
+            incremental_snapshot_archives_dir.path().to_path_buf(),
+        );
+        let events = AdminRpcEventSender::default();
+        let (subscriber, _id, mut notifications) = Subscriber::new_test("eventNotification");
+        events.subscribe(subscriber);
+
+        let hash = SnapshotHash(Hash::default());
+        let full_path = snapshot_utils::build_full_snapshot_archive_path(
+            full_snapshot_archives_dir.path(),
+            100,
+            &hash,
+            ArchiveFormat::TarZstd,
+        );
+        fs::write(&full_path, []).unwrap();
+        assert!(sources.publish(&events));
+        assert_eq!(
+            notified_event(notifications.try_next().unwrap().unwrap()),
+            ValidatorEvent::SnapshotComplete {
+                slot: 100,
+                path: full_path,
+            }
+        );
+
+        // nothing changed
+        assert!(sources.publish(&events));
+        assert!(notifications.try_next().is_err());
+
+        let incremental_path = snapshot_utils::build_incremental_snapshot_archive_path(
+            incremental_snapshot_archives_dir.path(),
+            100,
+            150,
+            &hash,
+            ArchiveFormat::TarZstd,
+        );
+        fs::write(&incremental_path, []).unwrap();
+        assert!(sources.publish(&events));
+        assert_eq!(
+            notified_event(notifications.try_next().unwrap().unwrap()),
+            ValidatorEvent::SnapshotComplete {
+                slot: 150,
+                path: incremental_path,
+            }
+        );
+
+        // stop once the validator is gone
+        drop(slot_notification_sender);
+        assert!(!sources.publish(&events));
+    }
+
+    #[test]
+    fn test_unsubscribe() {
+        let events = AdminRpcEventSender::default();
+        let (subscriber, _id, mut notifications) = Subscriber::new_test("eventNotification");
+        events.subscribe(subscriber);
+        assert!(events.unsubscribe(&SubscriptionId::Number(0)));
+        assert!(!events.unsubscribe(&SubscriptionId::Number(0)));
+
+        events.send(&ValidatorEvent::RepairRequest {
+            slot: 1,
+            shred_index: 2,
+        });
+        assert!(!matches!(notifications.try_next(), Ok(Some(_))));
+    }
+}
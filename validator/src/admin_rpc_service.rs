@@ -1,4 +1,8 @@
 use {
+    crate::{
+        admin_rpc_events::{AdminRpcEventSender, ValidatorEvent},
+        log_tail::{self, LogTail, LogTailChunk, LogTailCursor},
+    },
     crossbeam_channel::Sender,
     jsonrpc_core::{BoxFuture, ErrorCode, MetaIoHandler, Metadata, Result},
     jsonrpc_core_client::{transports::ipc, RpcError},
@@ -6,37 +10,52 @@ use {
     jsonrpc_ipc_server::{
         tokio::sync::oneshot::channel as oneshot_channel, RequestContext, ServerBuilder,
     },
+    jsonrpc_pubsub::{typed::Subscriber, PubSubMetadata, Session, SubscriptionId},
     log::*,
-    serde::{de::Deserializer, Deserialize, Serialize},
+    serde::{de::Deserializer, ser::Serializer, Deserialize, Serialize},
     solana_accounts_db::accounts_index::AccountIndex,
     solana_core::{
         admin_rpc_post_init::AdminRpcRequestMetadataPostInit,
+        banking_stage::{
+            AdmissionConfig, EstimateRequest, SchedulerStateSnapshot, SchedulingEstimate,
+        },
         consensus::{tower_storage::TowerStorage, Tower},
         repair::repair_service,
         validator::ValidatorStartProgress,
     },
     solana_geyser_plugin_manager::GeyserPluginManagerRequest,
     solana_gossip::contact_info::{ContactInfo, Protocol, SOCKET_ADDR_UNSPECIFIED},
+    solana_ledger::blockstore::Blockstore,
     solana_rpc::rpc::verify_pubkey,
     solana_rpc_client_api::{config::RpcAccountIndex, custom_error::RpcCustomError},
+    solana_runtime::bank::Bank,
     solana_sdk::{
+        clock::{Slot, UnixTimestamp},
         exit::Exit,
+        native_token::LAMPORTS_PER_SOL,
         pubkey::Pubkey,
         signature::{read_keypair_file, Keypair, Signer},
     },
     std::{
-        collections::{HashMap, HashSet},
+        collections::{BTreeMap, HashMap, HashSet},
         env, error,
         fmt::{self, Display},
         net::SocketAddr,
         path::{Path, PathBuf},
-        sync::{Arc, RwLock},
+        sync::{Arc, OnceLock, RwLock},
         thread::{self, Builder},
-        time::{Duration, SystemTime},
+        time::{Duration, SystemTime, UNIX_EPOCH},
     },
     tokio::runtime::Runtime,
 };
 
+/// Time at which the admin RPC server of this process started serving
+static ADMIN_RPC_SERVER_START_TIME: OnceLock<SystemTime> = OnceLock::new();
+
+/// Time allowed to the banking stage scheduler to answer an estimate request,
+/// which it does between scheduling passes
+const SCHEDULING_ESTIMATE_TIMEOUT: Duration = Duration::from_secs(1);
+
 #[derive(Clone)]
 pub struct AdminRpcRequestMetadata {
     pub rpc_addr: Option<SocketAddr>,
@@ -48,10 +67,22 @@ pub struct AdminRpcRequestMetadata {
     pub staked_nodes_overrides: Arc<RwLock<HashMap<Pubkey, u64>>>,
     pub post_init: Arc<RwLock<Option<AdminRpcRequestMetadataPostInit>>>,
     pub rpc_to_plugin_manager_sender: Option<Sender<GeyserPluginManagerRequest>>,
+    /// Events pushed to the subscribers of `eventsSubscribe`, if enabled
+    pub event_sender: Option<AdminRpcEventSender>,
+    /// Recent log lines, if kept
+    pub log_tail: Option<Arc<LogTail>>,
+    /// Connection the request was received on, set by the admin RPC server
+    pub session: Option<Arc<Session>>,
 }
 
 impl Metadata for AdminRpcRequestMetadata {}
 
+impl PubSubMetadata for AdminRpcRequestMetadata {
+    fn session(&self) -> Option<Arc<Session>> {
+        self.session.clone()
+    }
+}
+
 impl AdminRpcRequestMetadata {
     fn with_post_init<F, R>(&self, func: F) -> Result<R>
     where
@@ -65,6 +96,12 @@ impl AdminRpcRequestMetadata {
             ))
         }
     }
+
+    fn publish_event(&self, event: ValidatorEvent) {
+        if let Some(event_sender) = &self.event_sender {
+            event_sender.send(&event);
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -84,11 +121,90 @@ pub struct AdminRpcContactInfo {
     pub shred_version: u16,
 }
 
+/// Build information of the running validator binary
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AdminRpcVersionInfo {
+    pub version: String,
+    pub git_commit: String,
+    pub rustc_version: String,
+    pub feature_set: u32,
+    /// Unix timestamp of the build
+    pub build_timestamp: u64,
+    pub debug_assertions: bool,
+    pub cargo_features: Vec<String>,
+}
+
+impl AdminRpcVersionInfo {
+    /// Build information of this binary, captured at compile time by `build.rs`
+    pub fn current() -> Self {
+        let version = solana_version::Version::default();
+        Self {
+            version: version.as_semver_version().to_string(),
+            git_commit: env!("AGAVE_VALIDATOR_GIT_COMMIT").to_string(),
+            rustc_version: env!("AGAVE_VALIDATOR_RUSTC_VERSION").to_string(),
+            feature_set: version.feature_set,
+            build_timestamp: env!("AGAVE_VALIDATOR_BUILD_TIMESTAMP").parse().unwrap(),
+            debug_assertions: cfg!(debug_assertions),
+            cargo_features: env!("AGAVE_VALIDATOR_CARGO_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// Answer to `ping`, telling how long the validator and its admin RPC server
+/// have been up
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AdminRpcPing {
+    /// Unix timestamp of the start of the validator process
+    pub start_time: u64,
+    /// Seconds since the admin RPC server started serving, `None` if it was
+    /// not started by `run()`
+    pub rpc_server_uptime_secs: Option<u64>,
+}
+
+/// Answer to `votingIdentity`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AdminRpcVotingIdentity {
+    /// Identity the replay stage votes with, which it picks up at its next
+    /// reset after the identity of the validator changed
+    pub identity: String,
+    /// Identity it voted with before the last identity change, if any
+    pub previous_identity: Option<String>,
+    /// Last slot voted on with `previous_identity`, according to the tower of
+    /// the replay stage when it stopped voting with it, `None` if the tower
+    /// had no vote
+    pub previous_last_vote_slot: Option<Slot>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AdminRpcRepairWhitelist {
     pub whitelist: Vec<Pubkey>,
 }
 
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SlotRepairStatus {
+    pub slot: Slot,
+    pub total_shreds_expected: u64,
+    pub shreds_present: Vec<u64>,
+    pub shreds_missing: Vec<u64>,
+    pub is_complete: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BlockSummary {
+    pub slot: Slot,
+    pub block_time: UnixTimestamp,
+    pub parent_slot: Slot,
+    /// Transactions processed in this block, unknown for a root whose parent
+    /// is no longer in memory.
+    pub transaction_count: Option<u64>,
+    pub total_cus_used: u64,
+    pub block_hash: String,
+}
+
 impl From<ContactInfo> for AdminRpcContactInfo {
     fn from(node: ContactInfo) -> Self {
         macro_rules! unwrap_socket {
@@ -136,6 +252,31 @@ impl Display for AdminRpcContactInfo {
 impl solana_cli_output::VerboseDisplay for AdminRpcContactInfo {}
 impl solana_cli_output::QuietDisplay for AdminRpcContactInfo {}
 
+impl Display for AdminRpcVersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let build_time = chrono::DateTime::from_timestamp(self.build_timestamp as i64, 0)
+            .map(|build_time| build_time.to_rfc3339())
+            .unwrap_or_else(|| self.build_timestamp.to_string());
+        writeln!(f, "Version: {}", self.version)?;
+        writeln!(f, "Git Commit: {}", self.git_commit)?;
+        writeln!(f, "Rustc Version: {}", self.rustc_version)?;
+        writeln!(f, "Feature Set: {}", self.feature_set)?;
+        writeln!(f, "Build Time: {build_time}")?;
+        writeln!(f, "Debug Assertions: {}", self.debug_assertions)?;
+        writeln!(
+            f,
+            "Cargo Features: {}",
+            if self.cargo_features.is_empty() {
+                "none".to_string()
+            } else {
+                self.cargo_features.join(", ")
+            }
+        )
+    }
+}
+impl solana_cli_output::VerboseDisplay for AdminRpcVersionInfo {}
+impl solana_cli_output::QuietDisplay for AdminRpcVersionInfo {}
+
 impl Display for AdminRpcRepairWhitelist {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "Repair whitelist: {:?}", &self.whitelist)
@@ -144,6 +285,194 @@ impl Display for AdminRpcRepairWhitelist {
 impl solana_cli_output::VerboseDisplay for AdminRpcRepairWhitelist {}
 impl solana_cli_output::QuietDisplay for AdminRpcRepairWhitelist {}
 
+impl Display for SlotRepairStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Slot: {}", self.slot)?;
+        writeln!(f, "Complete: {}", self.is_complete)?;
+        writeln!(f, "Shreds Expected: {}", self.total_shreds_expected)?;
+        writeln!(f, "Shreds Present: {}", self.shreds_present.len())?;
+        writeln!(f, "Shreds Missing: {:?}", self.shreds_missing)
+    }
+}
+impl solana_cli_output::VerboseDisplay for SlotRepairStatus {}
+impl solana_cli_output::QuietDisplay for SlotRepairStatus {}
+
+impl Display for BlockSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Slot: {}", self.slot)?;
+        writeln!(f, "Parent Slot: {}", self.parent_slot)?;
+        writeln!(f, "Block Time: {}", self.block_time)?;
+        match self.transaction_count {
+            Some(transaction_count) => writeln!(f, "Transactions: {transaction_count}")?,
+            None => writeln!(f, "Transactions: unknown")?,
+        }
+        writeln!(f, "Compute Units Used: {}", self.total_cus_used)?;
+        writeln!(f, "Blockhash: {}", self.block_hash)
+    }
+}
+impl solana_cli_output::VerboseDisplay for BlockSummary {}
+impl solana_cli_output::QuietDisplay for BlockSummary {}
+
+impl BlockSummary {
+    /// Summarize `bank`, whose own transactions are counted from the
+    /// cumulative count of `parent`.
+    fn new(bank: &Bank, parent: Option<&Bank>) -> Self {
+        let transaction_count = match parent {
+            Some(parent) => Some(
+                bank.transaction_count()
+                    .saturating_sub(parent.transaction_count()),
+            ),
+            // The genesis block has no ancestors to count, while the parents
+            // of any other root have been squashed into it.
+            None if bank.slot() == 0 => Some(bank.transaction_count()),
+            None => None,
+        };
+        Self {
+            slot: bank.slot(),
+            block_time: bank.clock().unix_timestamp,
+            parent_slot: bank.parent_slot(),
+            transaction_count,
+            total_cus_used: bank.read_cost_tracker().unwrap().block_cost(),
+            block_hash: bank.last_blockhash().to_string(),
+        }
+    }
+}
+
+impl SlotRepairStatus {
+    /// Compare the data shreds of `slot` in `blockstore` against the shred count
+    /// expected from its slot meta. Until the last shred in the slot has been
+    /// received, the highest received shred index bounds the expected count.
+    fn from_blockstore(blockstore: &Blockstore, slot: Slot) -> Result<Self> {
+        let slot_meta = blockstore
+            .meta(slot)
+            .map_err(|err| {
+                jsonrpc_core::error::Error::invalid_params(format!(
+                    "Failed to read slot meta for slot {slot}: {err}"
+                ))
+            })?
+            .ok_or_else(|| {
+                jsonrpc_core::error::Error::invalid_params(format!(
+                    "Slot {slot} not found in blockstore"
+                ))
+            })?;
+        let total_shreds_expected = slot_meta
+            .last_index
+            .map(|last_index| last_index + 1)
+            .unwrap_or(slot_meta.received);
+
+        let shreds_present: Vec<u64> = blockstore
+            .get_data_shreds_for_slot(slot, 0)
+            .map_err(|err| {
+                jsonrpc_core::error::Error::invalid_params(format!(
+                    "Failed to read data shreds for slot {slot}: {err}"
+                ))
+            })?
+            .iter()
+            .map(|shred| u64::from(shred.index()))
+            .collect();
+        let present: HashSet<u64> = shreds_present.iter().copied().collect();
+        let shreds_missing: Vec<u64> = (0..total_shreds_expected)
+            .filter(|index| !present.contains(index))
+            .collect();
+
+        Ok(Self {
+            slot,
+            total_shreds_expected,
+            is_complete: slot_meta.is_full() && shreds_missing.is_empty(),
+            shreds_present,
+            shreds_missing,
+        })
+    }
+}
+
+/// A command changing the state of the validator, as accepted by `batchExecute`.
+///
+/// The plugin commands complete asynchronously and `exit` cannot be undone, so
+/// neither can be part of a batch.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "command", rename_all = "camelCase")]
+pub enum AdminCommand {
+    SetLogFilter {
+        filter: String,
+    },
+    AddAuthorizedVoter {
+        keypair_file: String,
+    },
+    RemoveAllAuthorizedVoters,
+    SetIdentity {
+        keypair_file: String,
+        #[serde(default)]
+        require_tower: bool,
+    },
+    SetStakedNodesOverrides {
+        path: String,
+    },
+    SetRepairWhitelist {
+        whitelist: Vec<String>,
+    },
+    SetPublicTpuAddress {
+        address: SocketAddr,
+    },
+    SetPublicTpuForwardsAddress {
+        address: SocketAddr,
+    },
+}
+
+impl AdminCommand {
+    /// Name of the equivalent `agave-validator` subcommand
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SetLogFilter { .. } => "set-log-filter",
+            Self::AddAuthorizedVoter { .. } => "authorized-voter add",
+            Self::RemoveAllAuthorizedVoters => "authorized-voter remove-all",
+            Self::SetIdentity { .. } => "set-identity",
+            Self::SetStakedNodesOverrides { .. } => "staked-nodes-overrides",
+            Self::SetRepairWhitelist { .. } => "repair-whitelist set",
+            Self::SetPublicTpuAddress { .. } => "set-public-address --tpu-addr",
+            Self::SetPublicTpuForwardsAddress { .. } => "set-public-address --tpu-forwards-addr",
+        }
+    }
+}
+
+/// Outcome of a single command of a batch
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CommandResult {
+    /// The command was applied
+    Applied,
+    /// The command was applied, then undone because a later command failed
+    RolledBack,
+    /// The command was applied, but could not be undone
+    RollbackFailed { error: String },
+    /// The command failed
+    Failed { error: String },
+    /// The command was not run because an earlier command failed
+    Skipped,
+}
+
+impl Display for CommandResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Applied => write!(f, "applied"),
+            Self::RolledBack => write!(f, "rolled back"),
+            Self::RollbackFailed { error } => write!(f, "applied, rollback failed: {error}"),
+            Self::Failed { error } => write!(f, "failed: {error}"),
+            Self::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+/// A batch stopped at its first failing command
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, thiserror::Error)]
+#[error("command {failed_index} of the batch failed: {error}")]
+pub struct BatchError {
+    /// Index of the failed command in the batch
+    pub failed_index: usize,
+    pub error: String,
+    /// Outcome of every command of the batch
+    pub results: Vec<CommandResult>,
+}
+
 #[rpc]
 pub trait AdminRpc {
     type Metadata;
@@ -174,6 +503,18 @@ pub trait AdminRpc {
     #[rpc(name = "setLogFilter")]
     fn set_log_filter(&self, filter: String) -> Result<()>;
 
+    #[rpc(meta, name = "tailLogs")]
+    fn tail_logs(
+        &self,
+        meta: Self::Metadata,
+        lines: usize,
+        follow: bool,
+        cursor: Option<LogTailCursor>,
+    ) -> Result<LogTailChunk>;
+
+    #[rpc(meta, name = "ping")]
+    fn ping(&self, meta: Self::Metadata) -> Result<AdminRpcPing>;
+
     #[rpc(meta, name = "startTime")]
     fn start_time(&self, meta: Self::Metadata) -> Result<SystemTime>;
 
@@ -206,12 +547,34 @@ pub trait AdminRpc {
         require_tower: bool,
     ) -> Result<()>;
 
+    #[rpc(meta, name = "setIdentityStandby")]
+    fn set_identity_standby(&self, meta: Self::Metadata) -> Result<String>;
+
+    #[rpc(meta, name = "votingIdentity")]
+    fn voting_identity(&self, meta: Self::Metadata) -> Result<AdminRpcVotingIdentity>;
+
+    #[rpc(meta, name = "towerLastVotedSlot")]
+    fn tower_last_voted_slot(&self, meta: Self::Metadata, identity: String)
+        -> Result<Option<Slot>>;
+
+    #[rpc(meta, name = "towerFound")]
+    fn tower_found(&self, meta: Self::Metadata, identity: String) -> Result<bool>;
+
     #[rpc(meta, name = "setStakedNodesOverrides")]
     fn set_staked_nodes_overrides(&self, meta: Self::Metadata, path: String) -> Result<()>;
 
+    #[rpc(meta, name = "getStakedNodesOverrides")]
+    fn get_staked_nodes_overrides(&self, meta: Self::Metadata) -> Result<HashMap<String, u64>>;
+
     #[rpc(meta, name = "contactInfo")]
     fn contact_info(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo>;
 
+    #[rpc(meta, name = "refreshGossip")]
+    fn refresh_gossip(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo>;
+
+    #[rpc(meta, name = "versionInfo")]
+    fn version_info(&self, meta: Self::Metadata) -> Result<AdminRpcVersionInfo>;
+
     #[rpc(meta, name = "repairShredFromPeer")]
     fn repair_shred_from_peer(
         &self,
@@ -221,9 +584,32 @@ pub trait AdminRpc {
         shred_index: u64,
     ) -> Result<()>;
 
+    #[rpc(meta, name = "getSlotRepairStatus")]
+    fn get_slot_repair_status(&self, meta: Self::Metadata, slot: Slot) -> Result<SlotRepairStatus>;
+
+    #[rpc(meta, name = "getRecentBlocks")]
+    fn get_recent_blocks(&self, meta: Self::Metadata, count: usize) -> Result<Vec<BlockSummary>>;
+
     #[rpc(meta, name = "repairWhitelist")]
     fn repair_whitelist(&self, meta: Self::Metadata) -> Result<AdminRpcRepairWhitelist>;
 
+    #[rpc(meta, name = "schedulerState")]
+    fn scheduler_state(&self, meta: Self::Metadata) -> Result<Option<SchedulerStateSnapshot>>;
+
+    #[rpc(meta, name = "schedulingEstimate")]
+    fn scheduling_estimate(
+        &self,
+        meta: Self::Metadata,
+        request: EstimateRequest,
+    ) -> Result<SchedulingEstimate>;
+
+    #[rpc(meta, name = "setAdmissionConfig")]
+    fn set_admission_config(
+        &self,
+        meta: Self::Metadata,
+        admission_config: Option<AdmissionConfig>,
+    ) -> Result<()>;
+
     #[rpc(meta, name = "setRepairWhitelist")]
     fn set_repair_whitelist(&self, meta: Self::Metadata, whitelist: Vec<Pubkey>) -> Result<()>;
 
@@ -247,6 +633,27 @@ pub trait AdminRpc {
         meta: Self::Metadata,
         public_tpu_forwards_addr: SocketAddr,
     ) -> Result<()>;
+
+    #[rpc(meta, name = "batchExecute")]
+    fn batch_execute(
+        &self,
+        meta: Self::Metadata,
+        commands: Vec<AdminCommand>,
+    ) -> Result<Vec<CommandResult>>;
+
+    #[pubsub(
+        subscription = "eventNotification",
+        subscribe,
+        name = "eventsSubscribe"
+    )]
+    fn events_subscribe(&self, meta: Self::Metadata, subscriber: Subscriber<ValidatorEvent>);
+
+    #[pubsub(
+        subscription = "eventNotification",
+        unsubscribe,
+        name = "eventsUnsubscribe"
+    )]
+    fn events_unsubscribe(&self, meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
 }
 
 pub struct AdminRpcImpl;
@@ -405,10 +812,44 @@ impl AdminRpc for AdminRpcImpl {
 
     fn set_log_filter(&self, filter: String) -> Result<()> {
         debug!("set_log_filter admin rpc request received");
-        solana_logger::setup_with(&filter);
+        log_tail::setup_with(&filter);
         Ok(())
     }
 
+    fn tail_logs(
+        &self,
+        meta: Self::Metadata,
+        lines: usize,
+        follow: bool,
+        cursor: Option<LogTailCursor>,
+    ) -> Result<LogTailChunk> {
+        debug!("tail_logs request received: {lines} lines, follow: {follow}");
+
+        let Some(log_tail) = &meta.log_tail else {
+            return Err(jsonrpc_core::error::Error::invalid_params(
+                "The log tail is disabled, see --log-tail-lines",
+            ));
+        };
+        // Without `follow`, the last lines are returned whatever the cursor
+        Ok(log_tail.read(lines, cursor.filter(|_| follow)))
+    }
+
+    fn ping(&self, meta: Self::Metadata) -> Result<AdminRpcPing> {
+        debug!("ping admin rpc request received");
+        let start_time = meta
+            .start_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let rpc_server_uptime_secs = ADMIN_RPC_SERVER_START_TIME
+            .get()
+            .map(|server_start_time| server_start_time.elapsed().unwrap_or_default().as_secs());
+        Ok(AdminRpcPing {
+            start_time,
+            rpc_server_uptime_secs,
+        })
+    }
+
     fn start_time(&self, meta: Self::Metadata) -> Result<SystemTime> {
         debug!("start_time admin rpc request received");
         Ok(meta.start_time)
@@ -484,6 +925,58 @@ impl AdminRpc for AdminRpcImpl {
         AdminRpcImpl::set_identity_keypair(meta, identity_keypair, require_tower)
     }
 
+    fn set_identity_standby(&self, meta: Self::Metadata) -> Result<String> {
+        debug!("set_identity_standby request received");
+
+        let standby_keypair = Keypair::new();
+        let standby_identity = standby_keypair.pubkey();
+        AdminRpcImpl::set_identity_keypair(meta.clone(), standby_keypair, false)?;
+        let identity = meta.with_post_init(|post_init| Ok(post_init.cluster_info.id()))?;
+        if identity != standby_identity {
+            return Err(jsonrpc_core::error::Error::invalid_params(format!(
+                "Identity changed to {identity} while switching to standby"
+            )));
+        }
+
+        // The replay stage picks up the new identity at its next reset, and
+        // may vote once more with the previous one until then: the caller
+        // waits for it with `votingIdentity`.
+        Ok(standby_identity.to_string())
+    }
+
+    fn voting_identity(&self, meta: Self::Metadata) -> Result<AdminRpcVotingIdentity> {
+        debug!("voting_identity request received");
+
+        meta.with_post_init(|post_init| {
+            let voting = *post_init.voting_identity.read().unwrap();
+            Ok(AdminRpcVotingIdentity {
+                identity: voting.identity.to_string(),
+                previous_identity: voting.previous_identity.map(|pubkey| pubkey.to_string()),
+                previous_last_vote_slot: voting.previous_last_vote_slot,
+            })
+        })
+    }
+
+    fn tower_last_voted_slot(
+        &self,
+        meta: Self::Metadata,
+        identity: String,
+    ) -> Result<Option<Slot>> {
+        debug!("tower_last_voted_slot request received");
+
+        let identity = verify_pubkey(&identity)?;
+        Ok(Tower::restore(meta.tower_storage.as_ref(), &identity)
+            .ok()
+            .and_then(|tower| tower.last_voted_slot()))
+    }
+
+    fn tower_found(&self, meta: Self::Metadata, identity: String) -> Result<bool> {
+        debug!("tower_found request received");
+
+        let identity = verify_pubkey(&identity)?;
+        Ok(Tower::restore(meta.tower_storage.as_ref(), &identity).is_ok())
+    }
+
     fn set_staked_nodes_overrides(&self, meta: Self::Metadata, path: String) -> Result<()> {
         let loaded_config = load_staked_nodes_overrides(&path)
             .map_err(|err| {
@@ -502,10 +995,33 @@ impl AdminRpc for AdminRpcImpl {
         Ok(())
     }
 
+    fn get_staked_nodes_overrides(&self, meta: Self::Metadata) -> Result<HashMap<String, u64>> {
+        Ok(meta
+            .staked_nodes_overrides
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(pubkey, stake)| (pubkey.to_string(), *stake))
+            .collect())
+    }
+
     fn contact_info(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo> {
         meta.with_post_init(|post_init| Ok(post_init.cluster_info.my_contact_info().into()))
     }
 
+    fn refresh_gossip(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo> {
+        debug!("refresh_gossip rpc request received");
+
+        meta.with_post_init(|post_init| {
+            post_init.cluster_info.refresh_my_gossip_contact_info();
+            Ok(post_init.cluster_info.my_contact_info().into())
+        })
+    }
+
+    fn version_info(&self, _meta: Self::Metadata) -> Result<AdminRpcVersionInfo> {
+        Ok(AdminRpcVersionInfo::current())
+    }
+
     fn repair_shred_from_peer(
         &self,
         meta: Self::Metadata,
@@ -525,10 +1041,117 @@ impl AdminRpc for AdminRpcImpl {
                 &post_init.repair_socket.clone(),
                 post_init.outstanding_repair_requests.clone(),
             );
+            meta.publish_event(ValidatorEvent::RepairRequest { slot, shred_index });
             Ok(())
         })
     }
 
+    fn get_slot_repair_status(&self, meta: Self::Metadata, slot: Slot) -> Result<SlotRepairStatus> {
+        debug!("get_slot_repair_status request received: {slot}");
+
+        meta.with_post_init(|post_init| {
+            SlotRepairStatus::from_blockstore(&post_init.blockstore, slot)
+        })
+    }
+
+    fn get_recent_blocks(&self, meta: Self::Metadata, count: usize) -> Result<Vec<BlockSummary>> {
+        debug!("get_recent_blocks request received: {count}");
+
+        meta.with_post_init(|post_init| {
+            // Only the blocks of the confirmed fork are listed: those from the
+            // latest optimistically confirmed bank still held in memory, or
+            // the root, down to the root.
+            let latest_confirmed = post_init
+                .blockstore
+                .get_latest_optimistic_slots(1)
+                .map_err(|err| jsonrpc_core::Error {
+                    code: ErrorCode::InternalError,
+                    message: format!("Unable to read the optimistically confirmed slots: {err}"),
+                    data: None,
+                })?;
+            let newest_bank = {
+                let bank_forks = post_init.bank_forks.read().unwrap();
+                latest_confirmed
+                    .first()
+                    .and_then(|(slot, hash, _timestamp)| {
+                        bank_forks
+                            .get(*slot)
+                            .filter(|bank| bank.is_frozen() && bank.hash() == *hash)
+                    })
+                    .unwrap_or_else(|| bank_forks.root_bank())
+            };
+            // One more bank than listed, to count the transactions of the
+            // oldest one listed from its parent.
+            let banks: Vec<_> = std::iter::successors(Some(newest_bank), |bank| bank.parent())
+                .take(count.saturating_add(1))
+                .collect();
+            Ok(banks
+                .iter()
+                .enumerate()
+                .take(count)
+                .map(|(index, bank)| {
+                    BlockSummary::new(bank, banks.get(index + 1).map(|parent| parent.as_ref()))
+                })
+                .collect())
+        })
+    }
+
+    fn scheduler_state(&self, meta: Self::Metadata) -> Result<Option<SchedulerStateSnapshot>> {
+        debug!("scheduler_state request received");
+
+        // Published by the scheduler thread, so the scheduler itself is not touched
+        meta.with_post_init(|post_init| Ok(post_init.scheduler_state.read().unwrap().clone()))
+    }
+
+    fn scheduling_estimate(
+        &self,
+        meta: Self::Metadata,
+        request: EstimateRequest,
+    ) -> Result<SchedulingEstimate> {
+        debug!("scheduling_estimate request received");
+
+        let estimate_request_sender =
+            meta.with_post_init(|post_init| Ok(post_init.estimate_request_sender.clone()))?;
+        let (response_sender, response_receiver) = crossbeam_channel::bounded(1);
+        estimate_request_sender
+            .send((request, response_sender))
+            .map_err(|_| jsonrpc_core::Error {
+                code: ErrorCode::InternalError,
+                message: "Banking stage scheduler is not running".to_string(),
+                data: None,
+            })?;
+        response_receiver
+            .recv_timeout(SCHEDULING_ESTIMATE_TIMEOUT)
+            .map_err(|err| jsonrpc_core::Error {
+                code: ErrorCode::InternalError,
+                message: format!("No estimate from the banking stage scheduler: {err}"),
+                data: None,
+            })
+    }
+
+    fn set_admission_config(
+        &self,
+        meta: Self::Metadata,
+        admission_config: Option<AdmissionConfig>,
+    ) -> Result<()> {
+        debug!("set_admission_config request received");
+
+        if let Some(admission_config) = &admission_config {
+            admission_config.validate().map_err(|err| {
+                jsonrpc_core::error::Error::invalid_params(format!(
+                    "Invalid admission config: {err}"
+                ))
+            })?;
+        }
+        // Applied by the scheduler thread on its next iteration
+        meta.with_post_init(|post_init| {
+            *post_init.admission_config.write().unwrap() = admission_config;
+            Ok(())
+        })?;
+        info!("Admission config set to {admission_config:?}");
+        Ok(())
+    }
+
     fn repair_whitelist(&self, meta: Self::Metadata) -> Result<AdminRpcRepairWhitelist> {
         debug!("repair_whitelist request received");
 
@@ -683,6 +1306,44 @@ impl AdminRpc for AdminRpcImpl {
             Ok(())
         })
     }
+
+    fn batch_execute(
+        &self,
+        meta: Self::Metadata,
+        commands: Vec<AdminCommand>,
+    ) -> Result<Vec<CommandResult>> {
+        debug!(
+            "batch_execute request received: {} commands",
+            commands.len()
+        );
+
+        batch_execute(&meta, commands).map_err(|err| jsonrpc_core::Error {
+            code: ErrorCode::InvalidParams,
+            message: err.to_string(),
+            data: serde_json::to_value(&err).ok(),
+        })
+    }
+
+    fn events_subscribe(&self, meta: Self::Metadata, subscriber: Subscriber<ValidatorEvent>) {
+        debug!("events_subscribe request received");
+
+        match &meta.event_sender {
+            Some(event_sender) => event_sender.subscribe(subscriber),
+            None => {
+                let _ = subscriber.reject(jsonrpc_core::error::Error::invalid_params(
+                    "Events are not published by this validator",
+                ));
+            }
+        }
+    }
+
+    fn events_unsubscribe(&self, meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        debug!("events_unsubscribe request received: {id:?}");
+
+        Ok(meta
+            .and_then(|meta| meta.event_sender)
+            .is_some_and(|event_sender| event_sender.unsubscribe(&id)))
+    }
 }
 
 impl AdminRpcImpl {
@@ -729,15 +1390,182 @@ impl AdminRpcImpl {
             }
 
             solana_metrics::set_host_id(identity_keypair.pubkey().to_string());
+            let old_pubkey = post_init.cluster_info.id();
             post_init
                 .cluster_info
                 .set_keypair(Arc::new(identity_keypair));
             warn!("Identity set to {}", post_init.cluster_info.id());
+            meta.publish_event(ValidatorEvent::IdentityChanged {
+                old_pubkey: old_pubkey.to_string(),
+                new_pubkey: post_init.cluster_info.id().to_string(),
+            });
             Ok(())
         })
     }
 }
 
+/// State changed by a command of a batch, captured before running it
+enum Undo {
+    /// The previous state cannot be read back from the validator
+    Unsupported(&'static str),
+    AuthorizedVoters(Vec<Arc<Keypair>>),
+    Identity(Arc<Keypair>),
+    StakedNodesOverrides(HashMap<Pubkey, u64>),
+    RepairWhitelist(HashSet<Pubkey>),
+    PublicTpuAddress(SocketAddr),
+    PublicTpuForwardsAddress(SocketAddr),
+}
+
+impl Undo {
+    fn capture(meta: &AdminRpcRequestMetadata, command: &AdminCommand) -> Result<Self> {
+        let published_address = |address: Option<SocketAddr>| {
+            address.ok_or_else(|| {
+                jsonrpc_core::error::Error::invalid_params(
+                    "The public TPU addresses aren't being published",
+                )
+            })
+        };
+        match command {
+            AdminCommand::SetLogFilter { .. } => {
+                Ok(Self::Unsupported("the previous log filter is unknown"))
+            }
+            AdminCommand::AddAuthorizedVoter { .. } | AdminCommand::RemoveAllAuthorizedVoters => {
+                Ok(Self::AuthorizedVoters(
+                    meta.authorized_voter_keypairs.read().unwrap().clone(),
+                ))
+            }
+            AdminCommand::SetIdentity { .. } => meta.with_post_init(|post_init| {
+                Ok(Self::Identity(post_init.cluster_info.keypair().clone()))
+            }),
+            AdminCommand::SetStakedNodesOverrides { .. } => Ok(Self::StakedNodesOverrides(
+                meta.staked_nodes_overrides.read().unwrap().clone(),
+            )),
+            AdminCommand::SetRepairWhitelist { .. } => meta.with_post_init(|post_init| {
+                Ok(Self::RepairWhitelist(
+                    post_init.repair_whitelist.read().unwrap().clone(),
+                ))
+            }),
+            AdminCommand::SetPublicTpuAddress { .. } => meta.with_post_init(|post_init| {
+                published_address(post_init.cluster_info.my_contact_info().tpu(Protocol::UDP))
+                    .map(Self::PublicTpuAddress)
+            }),
+            AdminCommand::SetPublicTpuForwardsAddress { .. } => meta.with_post_init(|post_init| {
+                published_address(
+                    post_init
+                        .cluster_info
+                        .my_contact_info()
+                        .tpu_forwards(Protocol::UDP),
+                )
+                .map(Self::PublicTpuForwardsAddress)
+            }),
+        }
+    }
+
+    fn apply(self, meta: &AdminRpcRequestMetadata) -> Result<()> {
+        match self {
+            Self::Unsupported(reason) => Err(jsonrpc_core::error::Error::invalid_params(reason)),
+            Self::AuthorizedVoters(authorized_voter_keypairs) => {
+                *meta.authorized_voter_keypairs.write().unwrap() = authorized_voter_keypairs;
+                Ok(())
+            }
+            Self::Identity(identity_keypair) => AdminRpcImpl::set_identity_keypair(
+                meta.clone(),
+                identity_keypair.insecure_clone(),
+                false,
+            ),
+            Self::StakedNodesOverrides(staked_nodes_overrides) => {
+                *meta.staked_nodes_overrides.write().unwrap() = staked_nodes_overrides;
+                Ok(())
+            }
+            Self::RepairWhitelist(whitelist) => meta.with_post_init(|post_init| {
+                *post_init.repair_whitelist.write().unwrap() = whitelist;
+                Ok(())
+            }),
+            Self::PublicTpuAddress(address) => {
+                AdminRpcImpl.set_public_tpu_address(meta.clone(), address)
+            }
+            Self::PublicTpuForwardsAddress(address) => {
+                AdminRpcImpl.set_public_tpu_forwards_address(meta.clone(), address)
+            }
+        }
+    }
+}
+
+fn execute_command(meta: &AdminRpcRequestMetadata, command: AdminCommand) -> Result<()> {
+    match command {
+        AdminCommand::SetLogFilter { filter } => AdminRpcImpl.set_log_filter(filter),
+        AdminCommand::AddAuthorizedVoter { keypair_file } => {
+            AdminRpcImpl.add_authorized_voter(meta.clone(), keypair_file)
+        }
+        AdminCommand::RemoveAllAuthorizedVoters => {
+            AdminRpcImpl.remove_all_authorized_voters(meta.clone())
+        }
+        AdminCommand::SetIdentity {
+            keypair_file,
+            require_tower,
+        } => AdminRpcImpl.set_identity(meta.clone(), keypair_file, require_tower),
+        AdminCommand::SetStakedNodesOverrides { path } => {
+            AdminRpcImpl.set_staked_nodes_overrides(meta.clone(), path)
+        }
+        AdminCommand::SetRepairWhitelist { whitelist } => {
+            let whitelist = whitelist
+                .iter()
+                .map(|pubkey| verify_pubkey(pubkey))
+                .collect::<Result<_>>()?;
+            AdminRpcImpl.set_repair_whitelist(meta.clone(), whitelist)
+        }
+        AdminCommand::SetPublicTpuAddress { address } => {
+            AdminRpcImpl.set_public_tpu_address(meta.clone(), address)
+        }
+        AdminCommand::SetPublicTpuForwardsAddress { address } => {
+            AdminRpcImpl.set_public_tpu_forwards_address(meta.clone(), address)
+        }
+    }
+}
+
+/// Run `commands` in order, stopping at the first failure.
+///
+/// When a command fails, the commands applied before it are undone in reverse
+/// order. The rollback is best-effort: a command whose previous state cannot be
+/// restored, such as `SetLogFilter`, is reported as `RollbackFailed`.
+pub fn batch_execute(
+    meta: &AdminRpcRequestMetadata,
+    commands: Vec<AdminCommand>,
+) -> std::result::Result<Vec<CommandResult>, BatchError> {
+    let num_commands = commands.len();
+    let mut undos = Vec::with_capacity(num_commands);
+    for (index, command) in commands.into_iter().enumerate() {
+        debug!("batch_execute: running {}", command.name());
+        let result = Undo::capture(meta, &command)
+            .and_then(|undo| execute_command(meta, command).map(|()| undo));
+        match result {
+            Ok(undo) => undos.push(undo),
+            Err(err) => {
+                let mut results = Vec::with_capacity(num_commands);
+                results.extend(undos.into_iter().rev().map(|undo| match undo.apply(meta) {
+                    Ok(()) => CommandResult::RolledBack,
+                    Err(err) => CommandResult::RollbackFailed { error: err.message },
+                }));
+                results.reverse();
+                results.push(CommandResult::Failed {
+                    error: err.message.clone(),
+                });
+                results.resize(num_commands, CommandResult::Skipped);
+                warn!(
+                    "Admin command batch failed at command {index}: {}",
+                    err.message
+                );
+                return Err(BatchError {
+                    failed_index: index,
+                    error: err.message,
+                    results,
+                });
+            }
+        }
+    }
+    Ok(vec![CommandResult::Applied; num_commands])
+}
+
 fn rpc_account_index_from_account_index(account_index: &AccountIndex) -> RpcAccountIndex {
     match account_index {
         AccountIndex::ProgramId => RpcAccountIndex::ProgramId,
@@ -764,8 +1592,11 @@ pub fn run(ledger_path: &Path, metadata: AdminRpcRequestMetadata) {
             io.extend_with(AdminRpcImpl.to_delegate());
 
             let validator_exit = metadata.validator_exit.clone();
-            let server = ServerBuilder::with_meta_extractor(io, move |_req: &RequestContext| {
-                metadata.clone()
+            let server = ServerBuilder::with_meta_extractor(io, move |req: &RequestContext| {
+                AdminRpcRequestMetadata {
+                    session: Some(Arc::new(Session::new(req.sender.clone()))),
+                    ..metadata.clone()
+                }
             })
             .event_loop_executor(event_loop.handle().clone())
             .start(&format!("{}", admin_rpc_path.display()));
@@ -776,6 +1607,7 @@ pub fn run(ledger_path: &Path, metadata: AdminRpcRequestMetadata) {
                 }
                 Ok(server) => {
                     info!("started admin rpc service!");
+                    let _ = ADMIN_RPC_SERVER_START_TIME.set(SystemTime::now());
                     let close_handle = server.close_handle();
                     validator_exit
                         .write()
@@ -791,7 +1623,7 @@ pub fn run(ledger_path: &Path, metadata: AdminRpcRequestMetadata) {
         .unwrap();
 }
 
-fn admin_rpc_path(ledger_path: &Path) -> PathBuf {
+pub fn admin_rpc_path(ledger_path: &Path) -> PathBuf {
     #[cfg(target_family = "windows")]
     {
         // More information about the wackiness of pipe names over at
@@ -813,7 +1645,14 @@ fn admin_rpc_path(ledger_path: &Path) -> PathBuf {
 
 // Connect to the Admin RPC interface
 pub async fn connect(ledger_path: &Path) -> std::result::Result<gen_client::Client, RpcError> {
-    let admin_rpc_path = admin_rpc_path(ledger_path);
+    connect_to(&admin_rpc_path(ledger_path)).await
+}
+
+/// Connect to the admin RPC server listening at `admin_rpc_path`, e.g. that of
+/// another validator
+pub async fn connect_to(
+    admin_rpc_path: &Path,
+) -> std::result::Result<gen_client::Client, RpcError> {
     if !admin_rpc_path.exists() {
         Err(RpcError::Client(format!(
             "{} does not exist",
@@ -832,22 +1671,52 @@ pub fn runtime() -> Runtime {
         .expect("new tokio runtime")
 }
 
-#[derive(Default, Deserialize, Clone)]
+/// Stake beyond which an override is rejected as absurd, above the total
+/// supply of any cluster
+pub const MAX_STAKED_NODES_OVERRIDE_STAKE: u64 = 1_000_000_000 * LAMPORTS_PER_SOL;
+
+#[derive(Default, Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct StakedNodesOverrides {
-    #[serde(deserialize_with = "deserialize_pubkey_map")]
+    #[serde(
+        deserialize_with = "deserialize_pubkey_map",
+        serialize_with = "serialize_pubkey_map"
+    )]
     pub staked_map_id: HashMap<Pubkey, u64>,
 }
 
+/// Serialize with base58 keys, sorted, in the format read by `deserialize_pubkey_map()`
+pub fn serialize_pubkey_map<S>(
+    map: &HashMap<Pubkey, u64>,
+    ser: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let container: BTreeMap<String, u64> = map
+        .iter()
+        .map(|(key, value)| (key.to_string(), *value))
+        .collect();
+    container.serialize(ser)
+}
+
 pub fn deserialize_pubkey_map<'de, D>(des: D) -> std::result::Result<HashMap<Pubkey, u64>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let container: HashMap<String, u64> = serde::Deserialize::deserialize(des)?;
+    // signed, so that a negative stake is reported as such
+    let container: HashMap<String, i64> = serde::Deserialize::deserialize(des)?;
     let mut container_typed: HashMap<Pubkey, u64> = HashMap::new();
     for (key, value) in container.iter() {
         let typed_key = Pubkey::try_from(key.as_str())
             .map_err(|_| serde::de::Error::invalid_type(serde::de::Unexpected::Map, &"PubKey"))?;
-        container_typed.insert(typed_key, *value);
+        let stake = u64::try_from(*value)
+            .map_err(|_| serde::de::Error::custom(format!("negative stake {value} for {key}")))?;
+        if stake > MAX_STAKED_NODES_OVERRIDE_STAKE {
+            return Err(serde::de::Error::custom(format!(
+                "stake {stake} for {key} exceeds the maximum of {MAX_STAKED_NODES_OVERRIDE_STAKE}"
+            )));
+        }
+        container_typed.insert(typed_key, stake);
     }
     Ok(container_typed)
 }
@@ -864,35 +1733,67 @@ pub fn load_staked_nodes_overrides(
     }
 }
 
+/// Get the staked nodes overrides currently applied by the validator behind
+/// `admin_client`
+pub async fn get_staked_nodes_overrides(
+    admin_client: &gen_client::Client,
+) -> std::result::Result<HashMap<Pubkey, u64>, RpcError> {
+    admin_client
+        .get_staked_nodes_overrides()
+        .await?
+        .into_iter()
+        .map(|(pubkey, stake)| {
+            Pubkey::try_from(pubkey.as_str())
+                .map(|pubkey| (pubkey, stake))
+                .map_err(|err| RpcError::Client(format!("invalid pubkey {pubkey}: {err}")))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use {
         super::*,
-        serde_json::Value,
+        crate::admin_rpc_events::EventSources,
+        crossbeam_channel::{unbounded, Receiver},
+        jsonrpc_core_client::transports::local,
+        serde_json::{json, Value},
         solana_accounts_db::{
             accounts_db::{AccountsDbConfig, ACCOUNTS_DB_CONFIG_FOR_TESTING},
             accounts_index::AccountSecondaryIndexes,
         },
         solana_core::{
+            banking_stage::{
+                FloorAction, SchedulingSummary, SharedAdmissionConfig, SharedSchedulerState,
+                ThreadLoad,
+            },
             consensus::tower_storage::NullTowerStorage,
+            replay_stage::{SharedVotingIdentity, VotingIdentity},
             validator::{Validator, ValidatorConfig, ValidatorTpuConfig},
         },
         solana_gossip::cluster_info::{ClusterInfo, Node},
         solana_inline_spl::token,
         solana_ledger::{
+            blockstore::make_slot_entries,
             create_new_tmp_ledger,
             genesis_utils::{
                 create_genesis_config, create_genesis_config_with_leader, GenesisConfigInfo,
             },
+            get_tmp_ledger_path_auto_delete,
         },
         solana_net_utils::bind_to_unspecified,
-        solana_rpc::rpc::create_validator_exit,
+        solana_rpc::{
+            optimistically_confirmed_bank_tracker::SlotNotification, rpc::create_validator_exit,
+        },
         solana_runtime::{
+            accounts_background_service::AbsRequestSender,
             bank::{Bank, BankTestConfig},
             bank_forks::BankForks,
         },
         solana_sdk::{
             account::{Account, AccountSharedData},
+            hash::Hash,
+            native_token::LAMPORTS_PER_SOL,
             pubkey::Pubkey,
             system_program,
         },
@@ -903,6 +1804,7 @@ mod tests {
             state::{Account as TokenAccount, AccountState as TokenAccountState, Mint},
         },
         std::{collections::HashSet, fs::remove_dir_all, sync::atomic::AtomicBool},
+        tempfile::TempDir,
     };
 
     #[derive(Default)]
@@ -914,6 +1816,12 @@ mod tests {
         io: MetaIoHandler<AdminRpcRequestMetadata>,
         meta: AdminRpcRequestMetadata,
         bank_forks: Arc<RwLock<BankForks>>,
+        blockstore: Arc<Blockstore>,
+        scheduler_state: SharedSchedulerState,
+        estimate_request_receiver: Receiver<(EstimateRequest, Sender<SchedulingEstimate>)>,
+        voting_identity: SharedVotingIdentity,
+        admission_config: SharedAdmissionConfig,
+        _ledger_path: TempDir,
     }
 
     impl RpcHandler {
@@ -943,6 +1851,12 @@ mod tests {
             let vote_account = vote_keypair.pubkey();
             let start_progress = Arc::new(RwLock::new(ValidatorStartProgress::default()));
             let repair_whitelist = Arc::new(RwLock::new(HashSet::new()));
+            let ledger_path = get_tmp_ledger_path_auto_delete!();
+            let blockstore = Arc::new(Blockstore::open(ledger_path.path()).unwrap());
+            let scheduler_state = SharedSchedulerState::default();
+            let (estimate_request_sender, estimate_request_receiver) = unbounded();
+            let voting_identity = SharedVotingIdentity::default();
+            let admission_config = SharedAdmissionConfig::default();
             let meta = AdminRpcRequestMetadata {
                 rpc_addr: None,
                 start_time: SystemTime::now(),
@@ -963,9 +1877,17 @@ mod tests {
                     cluster_slots: Arc::new(
                         solana_core::cluster_slots_service::cluster_slots::ClusterSlots::default(),
                     ),
+                    blockstore: blockstore.clone(),
+                    scheduler_state: scheduler_state.clone(),
+                    estimate_request_sender,
+                    voting_identity: voting_identity.clone(),
+                    admission_config: admission_config.clone(),
                 }))),
                 staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
                 rpc_to_plugin_manager_sender: None,
+                event_sender: None,
+                log_tail: None,
+                session: None,
             };
             let mut io = MetaIoHandler::default();
             io.extend_with(AdminRpcImpl.to_delegate());
@@ -974,6 +1896,12 @@ mod tests {
                 io,
                 meta,
                 bank_forks,
+                blockstore,
+                scheduler_state,
+                estimate_request_receiver,
+                voting_identity,
+                admission_config,
+                _ledger_path: ledger_path,
             }
         }
 
@@ -1301,6 +2229,678 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_slot_repair_status() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let get_slot_repair_status = |slot: Slot| {
+            let req = format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"getSlotRepairStatus","params":[{slot}]}}"#,
+            );
+            let res = rpc.io.handle_request_sync(&req, rpc.meta.clone());
+            let result: Value = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            serde_json::from_value::<SlotRepairStatus>(result["result"].clone())
+        };
+
+        // Unknown slots are an error
+        assert!(get_slot_repair_status(5).is_err());
+
+        // A complete slot
+        let (shreds, _) = make_slot_entries(1, 0, 10, true);
+        let num_shreds = shreds.len() as u64;
+        assert!(num_shreds > 3);
+        rpc.blockstore.insert_shreds(shreds, None, false).unwrap();
+        assert_eq!(
+            get_slot_repair_status(1).unwrap(),
+            SlotRepairStatus {
+                slot: 1,
+                total_shreds_expected: num_shreds,
+                shreds_present: (0..num_shreds).collect(),
+                shreds_missing: vec![],
+                is_complete: true,
+            }
+        );
+
+        // A slot with shreds deliberately left out
+        let (mut shreds, _) = make_slot_entries(2, 1, 10, true);
+        let num_shreds = shreds.len() as u64;
+        let missing = [0, num_shreds - 2];
+        shreds.retain(|shred| !missing.contains(&u64::from(shred.index())));
+        rpc.blockstore.insert_shreds(shreds, None, false).unwrap();
+        let status = get_slot_repair_status(2).unwrap();
+        assert_eq!(status.total_shreds_expected, num_shreds);
+        assert_eq!(status.shreds_missing, missing.to_vec());
+        assert_eq!(
+            status.shreds_present,
+            (0..num_shreds)
+                .filter(|index| !missing.contains(index))
+                .collect::<Vec<_>>()
+        );
+        assert!(!status.is_complete);
+    }
+
+    #[test]
+    fn test_get_recent_blocks() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let root_bank = rpc.root_bank();
+        let payer = Keypair::new();
+        root_bank.store_account(
+            &payer.pubkey(),
+            &AccountSharedData::new(100 * LAMPORTS_PER_SOL, 0, &system_program::id()),
+        );
+        root_bank.freeze();
+        let mut parent = root_bank;
+        // each block processes as many transactions as its slot
+        for slot in 1..=4 {
+            let bank = Bank::new_from_parent(parent, &Pubkey::default(), slot);
+            for _ in 0..slot {
+                bank.transfer(LAMPORTS_PER_SOL, &payer, &Pubkey::new_unique())
+                    .unwrap();
+            }
+            bank.freeze();
+            parent = rpc
+                .bank_forks
+                .write()
+                .unwrap()
+                .insert(bank)
+                .clone_without_scheduler();
+        }
+        let get_recent_blocks = |count: usize| {
+            let req = format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"getRecentBlocks","params":[{count}]}}"#,
+            );
+            let res = rpc.io.handle_request_sync(&req, rpc.meta.clone());
+            let result: Value = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            serde_json::from_value::<Vec<BlockSummary>>(result["result"].clone()).unwrap()
+        };
+
+        // a competing fork, never confirmed
+        let fork_parent = rpc.bank_forks.read().unwrap().get(2).unwrap();
+        let fork_bank = Bank::new_from_parent(fork_parent, &Pubkey::default(), 5);
+        fork_bank.freeze();
+        rpc.bank_forks.write().unwrap().insert(fork_bank);
+        let slots =
+            |blocks: &[BlockSummary]| blocks.iter().map(|block| block.slot).collect::<Vec<_>>();
+
+        let transaction_counts = |blocks: &[BlockSummary]| {
+            blocks
+                .iter()
+                .map(|block| block.transaction_count)
+                .collect::<Vec<_>>()
+        };
+
+        // nothing confirmed yet, only the genesis root is listed
+        let blocks = get_recent_blocks(3);
+        assert_eq!(slots(&blocks), vec![0]);
+        assert_eq!(transaction_counts(&blocks), vec![Some(0)]);
+
+        let confirmed_bank = rpc.bank_forks.read().unwrap().get(3).unwrap();
+        rpc.blockstore
+            .insert_optimistic_slot(3, &confirmed_bank.hash(), 0)
+            .unwrap();
+        let blocks = get_recent_blocks(2);
+        assert_eq!(slots(&blocks), vec![3, 2]);
+        assert_eq!(
+            blocks[0],
+            BlockSummary::new(&confirmed_bank, confirmed_bank.parent().as_deref())
+        );
+        assert_eq!(blocks[0].parent_slot, 2);
+        assert_eq!(transaction_counts(&blocks), vec![Some(3), Some(2)]);
+
+        // Asking for more blocks than are in memory returns all of them
+        let blocks = get_recent_blocks(10);
+        assert_eq!(slots(&blocks), vec![3, 2, 1, 0]);
+        assert_eq!(
+            transaction_counts(&blocks),
+            vec![Some(3), Some(2), Some(1), Some(0)]
+        );
+
+        // the newest bank confirmed, and not the highest frozen one, comes first
+        rpc.blockstore
+            .insert_optimistic_slot(4, &parent.hash(), 0)
+            .unwrap();
+        assert_eq!(slots(&get_recent_blocks(10)), vec![4, 3, 2, 1, 0]);
+
+        // the transactions of a root other than genesis are not told apart
+        // from those of its squashed parents
+        rpc.bank_forks
+            .write()
+            .unwrap()
+            .set_root(2, &AbsRequestSender::default(), None)
+            .unwrap();
+        let blocks = get_recent_blocks(10);
+        assert_eq!(slots(&blocks), vec![4, 3, 2]);
+        assert_eq!(transaction_counts(&blocks), vec![Some(4), Some(3), None]);
+
+        assert!(get_recent_blocks(0).is_empty());
+    }
+
+    #[test]
+    fn test_refresh_gossip() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let cluster_info = rpc
+            .meta
+            .post_init
+            .read()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .cluster_info
+            .clone();
+        let wallclock = cluster_info.my_contact_info().wallclock();
+        thread::sleep(Duration::from_millis(10));
+
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"refreshGossip"}"#;
+        let res = rpc.io.handle_request_sync(req, rpc.meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let contact_info: AdminRpcContactInfo =
+            serde_json::from_value(result["result"].clone()).unwrap();
+        assert_eq!(contact_info.id, cluster_info.id().to_string());
+        assert!(contact_info.last_updated_timestamp > wallclock);
+
+        // the refreshed contact info is in the crds table, ready to be pushed
+        assert_eq!(
+            cluster_info.lookup_contact_info(&cluster_info.id(), |node| node.wallclock()),
+            Some(contact_info.last_updated_timestamp)
+        );
+    }
+
+    #[test]
+    fn test_scheduler_state() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let scheduler_state = || {
+            let req = r#"{"jsonrpc":"2.0","id":1,"method":"schedulerState"}"#;
+            let res = rpc.io.handle_request_sync(req, rpc.meta.clone());
+            let result: Value = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            serde_json::from_value::<Option<SchedulerStateSnapshot>>(result["result"].clone())
+                .unwrap()
+        };
+
+        // nothing is published before the first pass of the scheduler
+        assert_eq!(scheduler_state(), None);
+
+        // `SchedulingSummary` is non-exhaustive outside of solana-core
+        let mut last_summary = SchedulingSummary::default();
+        last_summary.num_scheduled = 1;
+        let snapshot = SchedulerStateSnapshot {
+            queue_depth: 12,
+            queued_cus: 60_000,
+            thread_loads: vec![ThreadLoad {
+                in_flight_cus: 5_000,
+                in_flight_count: 1,
+            }],
+            num_locked_accounts: 3,
+            oldest_transaction_age_us: Some(1_500),
+            last_summary,
+            stats: None,
+        };
+        *rpc.scheduler_state.write().unwrap() = Some(snapshot.clone());
+        assert_eq!(scheduler_state(), Some(snapshot));
+    }
+
+    #[test]
+    fn test_scheduling_estimate() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let request = EstimateRequest {
+            write_accounts: vec![Pubkey::new_unique()],
+            read_accounts: vec![Pubkey::new_unique()],
+            cost: 5_000,
+        };
+        let estimate = SchedulingEstimate {
+            would_schedule_now: true,
+            min_conflicting_priority: Some(10),
+            num_conflicting: 2,
+            cu_headroom: 1_000_000,
+            truncated: false,
+        };
+
+        // stand in for the scheduler thread
+        let estimate_request_receiver = rpc.estimate_request_receiver.clone();
+        let scheduler = {
+            let request = request.clone();
+            let estimate = estimate.clone();
+            thread::spawn(move || {
+                let (received, response_sender) = estimate_request_receiver.recv().unwrap();
+                assert_eq!(received, request);
+                response_sender.send(estimate).unwrap();
+            })
+        };
+
+        let req = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "schedulingEstimate",
+            "params": [request],
+        })
+        .to_string();
+        let res = rpc.io.handle_request_sync(&req, rpc.meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        scheduler.join().unwrap();
+        assert_eq!(
+            serde_json::from_value::<SchedulingEstimate>(result["result"].clone()).unwrap(),
+            estimate
+        );
+
+        // no answer once the scheduler is gone
+        drop(rpc.estimate_request_receiver);
+        let res = rpc.io.handle_request_sync(&req, rpc.meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert!(result["error"].is_object());
+    }
+
+    #[test]
+    fn test_set_admission_config() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let set_admission_config = |admission_config: Option<AdmissionConfig>| {
+            let req = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "setAdmissionConfig",
+                "params": [admission_config],
+            })
+            .to_string();
+            let res = rpc.io.handle_request_sync(&req, rpc.meta.clone());
+            serde_json::from_str::<Value>(&res.expect("actual response"))
+                .expect("actual response deserialization")
+        };
+
+        let admission_config = AdmissionConfig {
+            floor_watermark: 0.8,
+            floor_action: FloorAction::EvictAndReplace,
+            eviction_watermark: 0.95,
+            eviction_fraction: 0.25,
+        };
+        assert_eq!(
+            set_admission_config(Some(admission_config))["result"],
+            Value::Null
+        );
+        assert_eq!(
+            *rpc.admission_config.read().unwrap(),
+            Some(admission_config)
+        );
+
+        // the floor watermark may not be above the eviction one
+        let result = set_admission_config(Some(AdmissionConfig {
+            floor_watermark: 0.99,
+            ..admission_config
+        }));
+        assert_eq!(result["error"]["code"], ErrorCode::InvalidParams.code());
+        assert_eq!(
+            *rpc.admission_config.read().unwrap(),
+            Some(admission_config)
+        );
+
+        assert_eq!(set_admission_config(None)["result"], Value::Null);
+        assert_eq!(*rpc.admission_config.read().unwrap(), None);
+    }
+
+    #[test]
+    fn test_root_events() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let snapshot_archives_dir = tempfile::tempdir().unwrap();
+        let (slot_notification_sender, slot_notification_receiver) = unbounded();
+        let mut sources = EventSources::new(
+            rpc.meta.post_init.clone(),
+            slot_notification_receiver,
+            snapshot_archives_dir.path().to_path_buf(),
+            snapshot_archives_dir.path().to_path_buf(),
+        );
+        let events = AdminRpcEventSender::default();
+        let (subscriber, _id, mut notifications) =
+            jsonrpc_pubsub::typed::Subscriber::new_test("eventNotification");
+        events.subscribe(subscriber);
+
+        let bank_hash = Hash::new_unique();
+        rpc.blockstore.insert_bank_hash(5, bank_hash, false);
+        slot_notification_sender
+            .send(SlotNotification::Frozen((5, 4)))
+            .unwrap();
+        slot_notification_sender
+            .send(SlotNotification::Root((5, 4)))
+            .unwrap();
+        // no bank hash was recorded for this root
+        slot_notification_sender
+            .send(SlotNotification::Root((6, 5)))
+            .unwrap();
+        assert!(sources.publish(&events));
+
+        let notification: Value =
+            serde_json::from_str(&notifications.try_next().unwrap().unwrap()).unwrap();
+        assert_eq!(
+            serde_json::from_value::<ValidatorEvent>(notification["params"]["result"].clone())
+                .unwrap(),
+            ValidatorEvent::SlotConfirmed {
+                slot: 5,
+                bank_hash: bank_hash.to_string(),
+            }
+        );
+        assert!(notifications.try_next().is_err());
+    }
+
+    #[test]
+    fn test_request_events() {
+        let mut rpc = RpcHandler::start_with_config(TestConfig::default());
+        let events = AdminRpcEventSender::default();
+        rpc.meta.event_sender = Some(events.clone());
+        let (subscriber, _id, mut notifications) =
+            jsonrpc_pubsub::typed::Subscriber::new_test("eventNotification");
+        events.subscribe(subscriber);
+        let mut handle_request = |method: &str, params: String| {
+            let req =
+                format!(r#"{{"jsonrpc":"2.0","id":1,"method":"{method}","params":{params}}}"#);
+            let res = rpc.io.handle_request_sync(&req, rpc.meta.clone());
+            let result: Value = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            assert_eq!(result["result"], Value::Null);
+            let notification: Value =
+                serde_json::from_str(&notifications.try_next().unwrap().unwrap()).unwrap();
+            serde_json::from_value::<ValidatorEvent>(notification["params"]["result"].clone())
+                .unwrap()
+        };
+
+        let old_pubkey = rpc
+            .meta
+            .post_init
+            .read()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .cluster_info
+            .id();
+        let new_identity = Keypair::new();
+        assert_eq!(
+            handle_request(
+                "setIdentityFromBytes",
+                format!("[{:?}, false]", new_identity.to_bytes()),
+            ),
+            ValidatorEvent::IdentityChanged {
+                old_pubkey: old_pubkey.to_string(),
+                new_pubkey: new_identity.pubkey().to_string(),
+            }
+        );
+
+        // no repair peer is known, but the request is still reported
+        assert_eq!(
+            handle_request("repairShredFromPeer", "[null, 5, 7]".to_string()),
+            ValidatorEvent::RepairRequest {
+                slot: 5,
+                shred_index: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn test_tail_logs() {
+        let mut rpc = RpcHandler::start_with_config(TestConfig::default());
+        let tail_logs = |rpc: &RpcHandler, params: &str| {
+            let req =
+                format!(r#"{{"jsonrpc":"2.0","id":1,"method":"tailLogs","params":[{params}]}}"#);
+            let res = rpc.io.handle_request_sync(&req, rpc.meta.clone());
+            let result: Value = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            serde_json::from_value::<LogTailChunk>(result["result"].clone())
+        };
+
+        // the log tail is disabled by default
+        assert!(tail_logs(&rpc, "10, false").is_err());
+
+        let log_tail = Arc::new(LogTail::new(16));
+        rpc.meta.log_tail = Some(log_tail.clone());
+        for line in 0..3 {
+            log_tail.push(format!("line {line}"));
+        }
+        let chunk = tail_logs(&rpc, "2, false").unwrap();
+        assert_eq!(chunk.lines, ["line 1", "line 2"]);
+        assert_eq!(chunk.cursor, 3);
+
+        // following from the cursor returns the new lines only
+        log_tail.push("line 3".to_string());
+        let chunk = tail_logs(&rpc, &format!("10, true, {}", chunk.cursor)).unwrap();
+        assert_eq!(chunk.lines, ["line 3"]);
+        assert_eq!(chunk.cursor, 4);
+        assert!(tail_logs(&rpc, "10, true, 4").unwrap().lines.is_empty());
+
+        // the cursor is ignored without follow
+        assert_eq!(
+            tail_logs(&rpc, "10, false, 4").unwrap().lines,
+            ["line 0", "line 1", "line 2", "line 3"]
+        );
+    }
+
+    #[test]
+    fn test_get_staked_nodes_overrides() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let get_staked_nodes_overrides = || {
+            runtime().block_on(async {
+                let (admin_client, server) = local::connect_with_metadata::<
+                    gen_client::Client,
+                    _,
+                    _,
+                >(&rpc.io, rpc.meta.clone());
+                // the server runs until the client is dropped
+                let overrides = async move { get_staked_nodes_overrides(&admin_client).await };
+                let (overrides, _) = futures_util::future::join(overrides, server).await;
+                overrides.unwrap()
+            })
+        };
+        assert!(get_staked_nodes_overrides().is_empty());
+
+        let applied = StakedNodesOverrides {
+            staked_map_id: HashMap::from([
+                (Pubkey::new_unique(), 1_000),
+                (Pubkey::new_unique(), 2_000),
+            ]),
+        };
+        let overrides_dir = TempDir::new().unwrap();
+        let path = overrides_dir.path().join("overrides.yml");
+        serde_yaml::to_writer(std::fs::File::create(&path).unwrap(), &applied).unwrap();
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"setStakedNodesOverrides","params":["{}"]}}"#,
+            path.display()
+        );
+        let res = rpc.io.handle_request_sync(&req, rpc.meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response")).unwrap();
+        assert!(result["error"].is_null(), "{result}");
+
+        let exported = StakedNodesOverrides {
+            staked_map_id: get_staked_nodes_overrides(),
+        };
+        assert_eq!(exported, applied);
+
+        // the export can be applied again as is
+        let path = overrides_dir.path().join("exported.yml");
+        serde_yaml::to_writer(std::fs::File::create(&path).unwrap(), &exported).unwrap();
+        assert_eq!(
+            load_staked_nodes_overrides(&path.display().to_string()).unwrap(),
+            applied
+        );
+    }
+
+    #[test]
+    fn test_batch_execute() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let whitelisted = Pubkey::new_unique();
+        let overrides = StakedNodesOverrides {
+            staked_map_id: HashMap::from([(Pubkey::new_unique(), 1_000)]),
+        };
+        let overrides_dir = TempDir::new().unwrap();
+        let path = overrides_dir.path().join("overrides.yml");
+        serde_yaml::to_writer(std::fs::File::create(&path).unwrap(), &overrides).unwrap();
+
+        let commands = serde_json::json!([
+            {"command": "setRepairWhitelist", "whitelist": [whitelisted.to_string()]},
+            {"command": "setStakedNodesOverrides", "path": path.display().to_string()},
+            {"command": "removeAllAuthorizedVoters"},
+        ]);
+        let req = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "batchExecute",
+            "params": [commands],
+        })
+        .to_string();
+        let res = rpc.io.handle_request_sync(&req, rpc.meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let results =
+            serde_json::from_value::<Vec<CommandResult>>(result["result"].clone()).unwrap();
+        assert_eq!(results, vec![CommandResult::Applied; 3]);
+
+        rpc.meta
+            .with_post_init(|post_init| {
+                assert_eq!(
+                    *post_init.repair_whitelist.read().unwrap(),
+                    HashSet::from([whitelisted])
+                );
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            *rpc.meta.staked_nodes_overrides.read().unwrap(),
+            overrides.staked_map_id
+        );
+        assert!(rpc
+            .meta
+            .authorized_voter_keypairs
+            .read()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_batch_execute_rollback() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let authorized_voters = rpc.meta.authorized_voter_keypairs.read().unwrap().clone();
+        assert_eq!(authorized_voters.len(), 1);
+
+        let commands = vec![
+            AdminCommand::RemoveAllAuthorizedVoters,
+            AdminCommand::SetRepairWhitelist {
+                whitelist: vec![Pubkey::new_unique().to_string()],
+            },
+            AdminCommand::SetStakedNodesOverrides {
+                path: "/nonexistent/overrides.yml".to_string(),
+            },
+            AdminCommand::SetLogFilter {
+                filter: "info".to_string(),
+            },
+        ];
+        let err = batch_execute(&rpc.meta, commands.clone()).unwrap_err();
+        assert_eq!(err.failed_index, 2);
+        assert_eq!(
+            err.results,
+            vec![
+                CommandResult::RolledBack,
+                CommandResult::RolledBack,
+                CommandResult::Failed {
+                    error: err.error.clone(),
+                },
+                CommandResult::Skipped,
+            ]
+        );
+
+        // The commands applied before the failure were undone
+        let restored = rpc.meta.authorized_voter_keypairs.read().unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].pubkey(), authorized_voters[0].pubkey());
+        drop(restored);
+        rpc.meta
+            .with_post_init(|post_init| {
+                assert!(post_init.repair_whitelist.read().unwrap().is_empty());
+                Ok(())
+            })
+            .unwrap();
+
+        // The batch error is returned as the data of the RPC error
+        let req = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "batchExecute",
+            "params": [commands],
+        })
+        .to_string();
+        let res = rpc.io.handle_request_sync(&req, rpc.meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert!(result["result"].is_null(), "{result}");
+        assert_eq!(
+            serde_json::from_value::<BatchError>(result["error"]["data"].clone()).unwrap(),
+            err
+        );
+    }
+
+    #[test]
+    fn test_version_info() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"versionInfo"}"#;
+        let res = rpc.io.handle_request_sync(req, rpc.meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let version_info =
+            serde_json::from_value::<AdminRpcVersionInfo>(result["result"].clone()).unwrap();
+        assert_eq!(version_info, AdminRpcVersionInfo::current());
+        assert_eq!(version_info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(version_info.debug_assertions, cfg!(debug_assertions));
+    }
+
+    #[test]
+    fn test_ping() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        let res = rpc.io.handle_request_sync(req, rpc.meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let ping = serde_json::from_value::<AdminRpcPing>(result["result"].clone()).unwrap();
+        assert_eq!(
+            ping.start_time,
+            rpc.meta
+                .start_time
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        );
+        // the test handler is not served by `run()`
+        assert_eq!(ping.rpc_server_uptime_secs, None);
+    }
+
+    #[test]
+    fn test_version_info_serialization() {
+        let version_info = AdminRpcVersionInfo {
+            version: "2.2.0".to_string(),
+            git_commit: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            rustc_version: "rustc 1.84.1 (e71f9a9a9 2025-01-27)".to_string(),
+            feature_set: 1234,
+            build_timestamp: 1_700_000_000,
+            debug_assertions: false,
+            cargo_features: vec![],
+        };
+        let json = serde_json::to_value(&version_info).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "version": "2.2.0",
+                "git_commit": "0123456789abcdef0123456789abcdef01234567",
+                "rustc_version": "rustc 1.84.1 (e71f9a9a9 2025-01-27)",
+                "feature_set": 1234,
+                "build_timestamp": 1_700_000_000,
+                "debug_assertions": false,
+                "cargo_features": [],
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<AdminRpcVersionInfo>(json).unwrap(),
+            version_info
+        );
+        assert!(version_info
+            .to_string()
+            .contains("Build Time: 2023-11-14T22:13:20+00:00"));
+    }
+
     // This test checks that the rpc call to `set_identity` works a expected with
     // Bank but without validator.
     #[test]
@@ -1344,6 +2944,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_set_identity_standby() {
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let RpcHandler {
+            io,
+            meta,
+            voting_identity,
+            ..
+        } = rpc;
+        let cluster_info = meta
+            .with_post_init(|post_init| Ok(post_init.cluster_info.clone()))
+            .unwrap();
+        let previous_identity = cluster_info.id();
+        voting_identity.write().unwrap().identity = previous_identity;
+        let get_voting_identity = || {
+            let request = r#"{"jsonrpc":"2.0","id":1,"method":"votingIdentity","params":[]}"#;
+            let response = io.handle_request_sync(request, meta.clone());
+            let response: Value = serde_json::from_str(&response.expect("actual response"))
+                .expect("actual response deserialization");
+            serde_json::from_value::<AdminRpcVotingIdentity>(response["result"].clone()).unwrap()
+        };
+
+        // answered as soon as the identity is switched, without waiting for
+        // the replay stage
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"setIdentityStandby","params":[]}"#;
+        let response = io.handle_request_sync(request, meta.clone());
+        let response: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        let standby_identity = cluster_info.id();
+        assert_ne!(standby_identity, previous_identity);
+        assert_eq!(response["result"], standby_identity.to_string());
+        assert_eq!(
+            get_voting_identity(),
+            AdminRpcVotingIdentity {
+                identity: previous_identity.to_string(),
+                previous_identity: None,
+                previous_last_vote_slot: None,
+            }
+        );
+
+        // the replay stage switches at its next reset, after a last vote
+        *voting_identity.write().unwrap() = VotingIdentity {
+            identity: standby_identity,
+            previous_identity: Some(previous_identity),
+            previous_last_vote_slot: Some(42),
+        };
+        assert_eq!(
+            get_voting_identity(),
+            AdminRpcVotingIdentity {
+                identity: standby_identity.to_string(),
+                previous_identity: Some(previous_identity.to_string()),
+                previous_last_vote_slot: Some(42),
+            }
+        );
+
+        let request = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"towerLastVotedSlot","params":["{previous_identity}"]}}"#,
+        );
+        let response = io.handle_request_sync(&request, meta.clone());
+        let response: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(response["result"], Value::Null);
+
+        let request = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"towerFound","params":["{previous_identity}"]}}"#,
+        );
+        let response = io.handle_request_sync(&request, meta);
+        let response: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(response["result"], Value::Bool(false));
+    }
+
     struct TestValidatorWithAdminRpc {
         meta: AdminRpcRequestMetadata,
         io: MetaIoHandler<AdminRpcRequestMetadata>,
@@ -1385,6 +3057,9 @@ mod tests {
                 post_init: post_init.clone(),
                 staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
                 rpc_to_plugin_manager_sender: None,
+                event_sender: None,
+                log_tail: None,
+                session: None,
             };
 
             let _validator = Validator::new(
@@ -126,7 +126,7 @@ fn main() {
     } else {
         None
     };
-    let _logger_thread = redirect_stderr_to_file(logfile);
+    let _logger_thread = redirect_stderr_to_file(logfile, None);
 
     info!("{} {}", crate_name!(), solana_version::version!());
     info!("Starting validator with: {:#?}", std::env::args_os());
@@ -407,6 +407,9 @@ fn main() {
             post_init: admin_service_post_init,
             tower_storage: tower_storage.clone(),
             rpc_to_plugin_manager_sender,
+            event_sender: None,
+            log_tail: None,
+            session: None,
         },
     );
     let dashboard = if output == Output::Dashboard {
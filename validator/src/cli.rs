@@ -1,5 +1,5 @@
 use {
-    crate::commands,
+    crate::{commands, log_tail::DEFAULT_LOG_TAIL_LINES},
     clap::{crate_description, crate_name, App, AppSettings, Arg, ArgMatches, SubCommand},
     log::warn,
     solana_accounts_db::{
@@ -42,7 +42,7 @@ use {
         DEFAULT_MAX_UNSTAKED_CONNECTIONS, DEFAULT_QUIC_ENDPOINTS,
     },
     solana_tpu_client::tpu_client::{DEFAULT_TPU_CONNECTION_POOL_SIZE, DEFAULT_VOTE_USE_QUIC},
-    std::{path::PathBuf, str::FromStr},
+    std::{path::PathBuf, str::FromStr, sync::LazyLock},
 };
 
 pub mod thread_args;
@@ -56,28 +56,43 @@ const MAX_SNAPSHOT_DOWNLOAD_ABORT: u32 = 5;
 // with less than 2 ticks per slot.
 const MINIMUM_TICKS_PER_SLOT: u64 = 2;
 
-pub fn app<'a>(version: &'a str, default_args: &'a DefaultArgs) -> App<'a, 'a> {
+static VERSION: LazyLock<String> = LazyLock::new(|| solana_version::version!().to_string());
+
+/// The complete command line of the validator, with every subcommand registered
+pub fn build_cli_app(default_args: &DefaultArgs) -> App<'_, '_> {
     let app = App::new(crate_name!())
         .about(crate_description!())
-        .version(version)
+        .version(VERSION.as_str())
         .global_setting(AppSettings::ColoredHelp)
         .global_setting(AppSettings::InferSubcommands)
         .global_setting(AppSettings::UnifiedHelpMessage)
         .global_setting(AppSettings::VersionlessSubcommands)
         .subcommand(commands::exit::command(default_args))
+        .subcommand(commands::admin_rpc::command(default_args))
         .subcommand(commands::authorized_voter::command(default_args))
+        .subcommand(commands::batch_execute::command(default_args))
+        .subcommand(commands::completions::command(default_args))
         .subcommand(commands::contact_info::command(default_args))
+        .subcommand(commands::get_recent_blocks::command(default_args))
+        .subcommand(commands::log_tail::command(default_args))
         .subcommand(commands::repair_shred_from_peer::command(default_args))
+        .subcommand(commands::repair_slot_status::command(default_args))
         .subcommand(commands::repair_whitelist::command(default_args))
         .subcommand(
             SubCommand::with_name("init").about("Initialize the ledger directory then exit"),
         )
         .subcommand(commands::monitor::command(default_args))
+        .subcommand(commands::node_version::command(default_args))
         .subcommand(SubCommand::with_name("run").about("Run the validator"))
         .subcommand(commands::plugin::command(default_args))
+        .subcommand(commands::refresh_gossip::command(default_args))
+        .subcommand(commands::scheduler_state::command(default_args))
+        .subcommand(commands::set_admission_config::command(default_args))
         .subcommand(commands::set_identity::command(default_args))
         .subcommand(commands::set_log_filter::command(default_args))
+        .subcommand(commands::snapshot_hashes::command(default_args))
         .subcommand(commands::staked_nodes_overrides::command(default_args))
+        .subcommand(commands::subscribe_events::command(default_args))
         .subcommand(commands::wait_for_restart_window::command())
         .subcommand(commands::set_public_address::command(default_args));
 
@@ -375,6 +390,7 @@ pub struct DefaultArgs {
     pub health_check_slot_distance: String,
     pub tower_storage: String,
     pub etcd_domain_name: String,
+    pub log_tail_lines: String,
     pub send_transaction_service_config: send_transaction_service::Config,
 
     pub rpc_max_multiple_accounts: String,
@@ -457,6 +473,7 @@ impl DefaultArgs {
             health_check_slot_distance: DELINQUENT_VALIDATOR_SLOT_DISTANCE.to_string(),
             tower_storage: "file".to_string(),
             etcd_domain_name: "localhost".to_string(),
+            log_tail_lines: DEFAULT_LOG_TAIL_LINES.to_string(),
             rpc_pubsub_max_active_subscriptions: PubSubConfig::default()
                 .max_active_subscriptions
                 .to_string(),
@@ -0,0 +1,456 @@
+use {
+    crate::{
+        admin_rpc_service::{self, gen_client, AdminRpcPing},
+        cli::DefaultArgs,
+        commands::FromClapArgMatches,
+    },
+    clap::{value_t, App, AppSettings, Arg, ArgMatches, SubCommand},
+    serde::Serialize,
+    solana_clap_utils::input_validators::is_parsable,
+    solana_cli_output::OutputFormat,
+    std::{
+        fmt::{self, Display},
+        path::{Path, PathBuf},
+        process::exit,
+        thread,
+        time::{Duration, Instant},
+    },
+    tokio::runtime::Runtime,
+};
+
+const COMMAND: &str = "admin-rpc";
+const PING_COMMAND: &str = "ping";
+
+const DEFAULT_COUNT: &str = "1";
+const DEFAULT_INTERVAL_MS: &str = "1000";
+/// Time given to the connection and to each request before giving up
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, PartialEq)]
+pub struct PingArgs {
+    pub count: usize,
+    pub interval: Duration,
+    pub output: OutputFormat,
+}
+
+impl FromClapArgMatches for PingArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        let count = value_t!(matches, "count", usize).map_err(|err| err.to_string())?;
+        if count == 0 {
+            return Err("--count must be greater than 0".to_string());
+        }
+        Ok(PingArgs {
+            count,
+            interval: Duration::from_millis(
+                value_t!(matches, "interval", u64).map_err(|err| err.to_string())?,
+            ),
+            output: OutputFormat::from_matches(matches, "output", false),
+        })
+    }
+}
+
+/// Failure to ping the admin RPC service, each with its own exit code
+#[derive(Debug, PartialEq, Eq)]
+pub enum PingError {
+    SocketMissing(PathBuf),
+    ConnectionRefused(String),
+    TimedOut,
+    RequestFailed(String),
+}
+
+impl PingError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::RequestFailed(_) => 1,
+            Self::SocketMissing(_) => 2,
+            Self::ConnectionRefused(_) => 3,
+            Self::TimedOut => 4,
+        }
+    }
+}
+
+impl Display for PingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SocketMissing(path) => write!(
+                f,
+                "admin rpc socket {} does not exist, is the validator running with this ledger?",
+                path.display()
+            ),
+            Self::ConnectionRefused(err) => write!(f, "admin rpc connection refused: {err}"),
+            Self::TimedOut => write!(f, "admin rpc request timed out after {PING_TIMEOUT:?}"),
+            Self::RequestFailed(err) => write!(f, "admin rpc ping request failed: {err}"),
+        }
+    }
+}
+
+/// Client of the admin RPC service, abstracted for testing
+pub trait PingClient {
+    fn connect(&mut self) -> Result<(), PingError>;
+    fn ping(&mut self) -> Result<AdminRpcPing, PingError>;
+}
+
+struct AdminRpcPingClient<'a> {
+    runtime: Runtime,
+    ledger_path: &'a Path,
+    client: Option<gen_client::Client>,
+}
+
+impl PingClient for AdminRpcPingClient<'_> {
+    fn connect(&mut self) -> Result<(), PingError> {
+        let socket_path = admin_rpc_service::admin_rpc_path(self.ledger_path);
+        if !socket_path.exists() {
+            return Err(PingError::SocketMissing(socket_path));
+        }
+        let client = self
+            .runtime
+            .block_on(async {
+                tokio::time::timeout(PING_TIMEOUT, admin_rpc_service::connect(self.ledger_path))
+                    .await
+            })
+            .map_err(|_| PingError::TimedOut)?
+            .map_err(|err| PingError::ConnectionRefused(err.to_string()))?;
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn ping(&mut self) -> Result<AdminRpcPing, PingError> {
+        let client = self.client.as_ref().expect("connected");
+        self.runtime
+            .block_on(async { tokio::time::timeout(PING_TIMEOUT, client.ping()).await })
+            .map_err(|_| PingError::TimedOut)?
+            .map_err(|err| PingError::RequestFailed(err.to_string()))
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct PingOutcome {
+    pub socket_path: String,
+    pub connect_time_us: u64,
+    pub count: usize,
+    pub average_round_trip_us: u64,
+    pub min_round_trip_us: u64,
+    pub max_round_trip_us: u64,
+    /// Unix timestamp of the start of the validator process
+    pub start_time: u64,
+    pub rpc_server_uptime_secs: Option<u64>,
+}
+
+impl PingOutcome {
+    fn new(
+        socket_path: &Path,
+        connect_time: Duration,
+        round_trips: &[Duration],
+        ping: AdminRpcPing,
+    ) -> Self {
+        let as_us = |duration: Duration| duration.as_micros() as u64;
+        let total: Duration = round_trips.iter().sum();
+        Self {
+            socket_path: socket_path.display().to_string(),
+            connect_time_us: as_us(connect_time),
+            count: round_trips.len(),
+            average_round_trip_us: as_us(total) / round_trips.len().max(1) as u64,
+            min_round_trip_us: round_trips.iter().copied().map(as_us).min().unwrap_or(0),
+            max_round_trip_us: round_trips.iter().copied().map(as_us).max().unwrap_or(0),
+            start_time: ping.start_time,
+            rpc_server_uptime_secs: ping.rpc_server_uptime_secs,
+        }
+    }
+}
+
+impl Display for PingOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Admin RPC socket: {}", self.socket_path)?;
+        writeln!(f, "Connected in {}us", self.connect_time_us)?;
+        writeln!(
+            f,
+            "{} pings, round trip avg {}us, min {}us, max {}us",
+            self.count, self.average_round_trip_us, self.min_round_trip_us, self.max_round_trip_us
+        )?;
+        write!(f, "Validator started at {}", self.start_time)?;
+        if let Some(rpc_server_uptime_secs) = self.rpc_server_uptime_secs {
+            write!(f, ", admin RPC up for {rpc_server_uptime_secs}s")?;
+        }
+        Ok(())
+    }
+}
+impl solana_cli_output::VerboseDisplay for PingOutcome {}
+impl solana_cli_output::QuietDisplay for PingOutcome {}
+
+/// Connect with `client`, then ping `count` times, `interval` apart
+pub fn run_pings(
+    client: &mut impl PingClient,
+    socket_path: &Path,
+    count: usize,
+    interval: Duration,
+) -> Result<PingOutcome, PingError> {
+    let connect_start = Instant::now();
+    client.connect()?;
+    let connect_time = connect_start.elapsed();
+
+    let mut round_trips = Vec::with_capacity(count);
+    let mut last_ping = None;
+    for nth in 0..count {
+        if nth > 0 {
+            thread::sleep(interval);
+        }
+        let ping_start = Instant::now();
+        last_ping = Some(client.ping()?);
+        round_trips.push(ping_start.elapsed());
+    }
+    let last_ping =
+        last_ping.ok_or_else(|| PingError::RequestFailed("no ping sent".to_string()))?;
+
+    Ok(PingOutcome::new(
+        socket_path,
+        connect_time,
+        &round_trips,
+        last_ping,
+    ))
+}
+
+fn ping_command<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(PING_COMMAND)
+        .about("Check that the admin RPC service answers, and how fast")
+        .arg(
+            Arg::with_name("count")
+                .long("count")
+                .value_name("N")
+                .takes_value(true)
+                .default_value(DEFAULT_COUNT)
+                .validator(is_parsable::<usize>)
+                .help("Number of pings, the round trip time is averaged over them"),
+        )
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .value_name("MS")
+                .takes_value(true)
+                .default_value(DEFAULT_INTERVAL_MS)
+                .validator(is_parsable::<u64>)
+                .help("Milliseconds between pings"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["json", "json-compact"])
+                .help("Output display mode"),
+        )
+}
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about("Diagnose the admin RPC service of the running validator")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(ping_command())
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    match matches.subcommand() {
+        (PING_COMMAND, Some(subcommand_matches)) => ping(subcommand_matches, ledger_path),
+        _ => unreachable!(),
+    }
+}
+
+fn ping(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let ping_args = PingArgs::from_clap_arg_match(matches)?;
+
+    let mut client = AdminRpcPingClient {
+        runtime: admin_rpc_service::runtime(),
+        ledger_path,
+        client: None,
+    };
+    let socket_path = admin_rpc_service::admin_rpc_path(ledger_path);
+    match run_pings(
+        &mut client,
+        &socket_path,
+        ping_args.count,
+        ping_args.interval,
+    ) {
+        Ok(outcome) => {
+            println!("{}", ping_args.output.formatted_string(&outcome));
+            Ok(())
+        }
+        Err(err) => {
+            println!("Validator command failed: {err}");
+            exit(err.exit_code());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+    };
+
+    #[derive(Default)]
+    struct TestClient {
+        connect_error: Option<PingError>,
+        /// Fail the ping of this index
+        fail_at: Option<usize>,
+        ping_delay: Duration,
+        pings: Vec<Instant>,
+    }
+
+    impl PingClient for TestClient {
+        fn connect(&mut self) -> Result<(), PingError> {
+            self.connect_error.take().map_or(Ok(()), Err)
+        }
+
+        fn ping(&mut self) -> Result<AdminRpcPing, PingError> {
+            if self.fail_at == Some(self.pings.len()) {
+                return Err(PingError::TimedOut);
+            }
+            self.pings.push(Instant::now());
+            thread::sleep(self.ping_delay);
+            Ok(AdminRpcPing {
+                start_time: 1_700_000_000,
+                rpc_server_uptime_secs: Some(self.pings.len() as u64),
+            })
+        }
+    }
+
+    #[test]
+    fn test_run_pings() {
+        let mut client = TestClient {
+            ping_delay: Duration::from_millis(5),
+            ..TestClient::default()
+        };
+        let interval = Duration::from_millis(20);
+        let outcome = run_pings(&mut client, Path::new("admin.rpc"), 3, interval).unwrap();
+
+        assert_eq!(client.pings.len(), 3);
+        for pings in client.pings.windows(2) {
+            assert!(pings[1] - pings[0] >= interval + client.ping_delay);
+        }
+        assert_eq!(outcome.socket_path, "admin.rpc");
+        assert_eq!(outcome.count, 3);
+        assert!(outcome.min_round_trip_us >= 5_000);
+        assert!(outcome.min_round_trip_us <= outcome.average_round_trip_us);
+        assert!(outcome.average_round_trip_us <= outcome.max_round_trip_us);
+        // the uptime reported is the one of the last ping
+        assert_eq!(outcome.start_time, 1_700_000_000);
+        assert_eq!(outcome.rpc_server_uptime_secs, Some(3));
+    }
+
+    #[test]
+    fn test_run_pings_errors() {
+        let mut client = TestClient {
+            connect_error: Some(PingError::SocketMissing(PathBuf::from("admin.rpc"))),
+            ..TestClient::default()
+        };
+        let err = run_pings(&mut client, Path::new("admin.rpc"), 3, Duration::ZERO).unwrap_err();
+        assert_eq!(err, PingError::SocketMissing(PathBuf::from("admin.rpc")));
+        assert!(client.pings.is_empty());
+
+        let mut client = TestClient {
+            fail_at: Some(1),
+            ..TestClient::default()
+        };
+        let err = run_pings(&mut client, Path::new("admin.rpc"), 3, Duration::ZERO).unwrap_err();
+        assert_eq!(err, PingError::TimedOut);
+        assert_eq!(client.pings.len(), 1);
+
+        let exit_codes = [
+            PingError::SocketMissing(PathBuf::new()),
+            PingError::ConnectionRefused(String::new()),
+            PingError::TimedOut,
+            PingError::RequestFailed(String::new()),
+        ]
+        .map(|err| err.exit_code());
+        assert_eq!(exit_codes, [2, 3, 4, 1]);
+    }
+
+    #[test]
+    fn test_ping_outcome_aggregation() {
+        let outcome = PingOutcome::new(
+            Path::new("admin.rpc"),
+            Duration::from_micros(250),
+            &[100, 300, 200].map(Duration::from_micros),
+            AdminRpcPing {
+                start_time: 1_700_000_000,
+                rpc_server_uptime_secs: None,
+            },
+        );
+        assert_eq!(
+            outcome,
+            PingOutcome {
+                socket_path: "admin.rpc".to_string(),
+                connect_time_us: 250,
+                count: 3,
+                average_round_trip_us: 200,
+                min_round_trip_us: 100,
+                max_round_trip_us: 300,
+                start_time: 1_700_000_000,
+                rpc_server_uptime_secs: None,
+            }
+        );
+        assert_eq!(
+            outcome.to_string(),
+            "Admin RPC socket: admin.rpc\nConnected in 250us\n3 pings, round trip avg 200us, min \
+             100us, max 300us\nValidator started at 1700000000"
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_ping_default() {
+        verify_args_struct_by_command(
+            ping_command(),
+            vec![PING_COMMAND],
+            PingArgs {
+                count: 1,
+                interval: Duration::from_secs(1),
+                output: OutputFormat::Display,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_ping_with_args() {
+        verify_args_struct_by_command(
+            ping_command(),
+            vec![
+                PING_COMMAND,
+                "--count",
+                "5",
+                "--interval",
+                "100",
+                "--output",
+                "json",
+            ],
+            PingArgs {
+                count: 5,
+                interval: Duration::from_millis(100),
+                output: OutputFormat::Json,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_ping_invalid() {
+        verify_args_struct_by_command_is_error::<PingArgs>(
+            ping_command(),
+            vec![PING_COMMAND, "--count", "-1"],
+        );
+        verify_args_struct_by_command_is_error::<PingArgs>(
+            ping_command(),
+            vec![PING_COMMAND, "--interval", "soon"],
+        );
+
+        let matches = ping_command().get_matches_from(vec![PING_COMMAND, "--count", "0"]);
+        assert!(PingArgs::from_clap_arg_match(&matches).is_err());
+    }
+
+    #[test]
+    fn test_command_requires_subcommand() {
+        assert!(command(&DefaultArgs::default())
+            .get_matches_from_safe(vec![COMMAND])
+            .is_err());
+    }
+}
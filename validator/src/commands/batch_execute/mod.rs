@@ -0,0 +1,142 @@
+use {
+    crate::{
+        admin_rpc_service::{self, AdminCommand, BatchError},
+        cli::DefaultArgs,
+        commands::FromClapArgMatches,
+    },
+    clap::{App, Arg, ArgMatches, SubCommand},
+    jsonrpc_core_client::RpcError,
+    std::{fs, path::Path},
+};
+
+const COMMAND: &str = "batch-execute";
+
+#[derive(Debug, PartialEq)]
+pub struct BatchExecuteArgs {
+    pub commands_file: String,
+}
+
+impl FromClapArgMatches for BatchExecuteArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        Ok(BatchExecuteArgs {
+            commands_file: matches
+                .value_of("commands_file")
+                .expect("commands_file is required")
+                .to_string(),
+        })
+    }
+}
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about("Run several admin commands, rolling them back if any of them fails")
+        .arg(
+            Arg::with_name("commands_file")
+                .long("commands-file")
+                .value_name("PATH")
+                .takes_value(true)
+                .required(true)
+                .help("JSON file with the array of commands to run, in order"),
+        )
+        .after_help(
+            "Each command is an object naming the command and its parameters, for example: \
+             [{\"command\": \"setLogFilter\", \"filter\": \"info\"}, \
+             {\"command\": \"setRepairWhitelist\", \"whitelist\": [\"PUBKEY\"]}]\n\n\
+             Note: the rollback is best-effort, a log filter change cannot be undone",
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let batch_execute_args = BatchExecuteArgs::from_clap_arg_match(matches)?;
+
+    let commands_file = &batch_execute_args.commands_file;
+    let commands = fs::read_to_string(commands_file)
+        .map_err(|err| format!("failed to read {commands_file}: {err}"))?;
+    let commands: Vec<AdminCommand> = serde_json::from_str(&commands)
+        .map_err(|err| format!("failed to parse {commands_file}: {err}"))?;
+    let names: Vec<_> = commands.iter().map(AdminCommand::name).collect();
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    let result = admin_rpc_service::runtime()
+        .block_on(async move { admin_client.await?.batch_execute(commands).await });
+
+    match result {
+        Ok(results) => {
+            for (name, result) in names.iter().zip(results) {
+                println!("{name}: {result}");
+            }
+            Ok(())
+        }
+        Err(RpcError::JsonRpcError(err)) => {
+            let Some(batch_error) = err
+                .data
+                .and_then(|data| serde_json::from_value::<BatchError>(data).ok())
+            else {
+                return Err(format!("batch execute request failed: {}", err.message));
+            };
+            for (name, result) in names.iter().zip(&batch_error.results) {
+                println!("{name}: {result}");
+            }
+            Err(format!("batch execute request failed: {batch_error}"))
+        }
+        Err(err) => Err(format!("batch execute request failed: {err}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+    };
+
+    #[test]
+    fn verify_args_struct_by_command_batch_execute_commands_file() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--commands-file", "commands.json"],
+            BatchExecuteArgs {
+                commands_file: "commands.json".to_string(),
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_batch_execute_without_commands_file() {
+        verify_args_struct_by_command_is_error::<BatchExecuteArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND],
+        );
+    }
+
+    #[test]
+    fn test_parse_commands_file() {
+        let commands: Vec<AdminCommand> = serde_json::from_str(
+            r#"[
+                {"command": "setLogFilter", "filter": "info"},
+                {"command": "removeAllAuthorizedVoters"},
+                {"command": "setIdentity", "keypair_file": "identity.json"},
+                {"command": "setPublicTpuAddress", "address": "127.0.0.1:8000"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                AdminCommand::SetLogFilter {
+                    filter: "info".to_string(),
+                },
+                AdminCommand::RemoveAllAuthorizedVoters,
+                AdminCommand::SetIdentity {
+                    keypair_file: "identity.json".to_string(),
+                    require_tower: false,
+                },
+                AdminCommand::SetPublicTpuAddress {
+                    address: "127.0.0.1:8000".parse().unwrap(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use {
+    crate::cli::{build_cli_app, DefaultArgs},
+    clap::{crate_name, value_t, App, Arg, ArgMatches, Shell, SubCommand},
+    std::{
+        fs::File,
+        io::{self, Write},
+        path::PathBuf,
+    },
+};
+
+const COMMAND: &str = "completions";
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about("Generate a completion script for the validator's command line")
+        .arg(
+            Arg::with_name("shell")
+                .long("shell")
+                .takes_value(true)
+                .value_name("SHELL")
+                .possible_values(&["bash", "zsh", "fish"])
+                .required(true)
+                .help("Shell to generate the completion script for"),
+        )
+        .arg(
+            Arg::with_name("output_file")
+                .long("output-file")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Write the completion script to PATH instead of stdout"),
+        )
+}
+
+/// Write the completion script of the complete command line for `shell`
+fn generate(default_args: &DefaultArgs, shell: Shell, out: &mut dyn Write) {
+    build_cli_app(default_args).gen_completions_to(crate_name!(), shell, out);
+}
+
+pub fn execute(matches: &ArgMatches) -> Result<(), String> {
+    let shell = value_t!(matches, "shell", Shell).map_err(|err| err.to_string())?;
+    let default_args = DefaultArgs::new();
+    match matches.value_of("output_file").map(PathBuf::from) {
+        Some(path) => {
+            let mut file = File::create(&path)
+                .map_err(|err| format!("failed to create {}: {err}", path.display()))?;
+            generate(&default_args, shell, &mut file);
+            file.flush()
+                .map_err(|err| format!("failed to write {}: {err}", path.display()))
+        }
+        None => {
+            generate(&default_args, shell, &mut io::stdout());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completions(shell: Shell) -> String {
+        let mut script = vec![];
+        generate(&DefaultArgs::default(), shell, &mut script);
+        String::from_utf8(script).unwrap()
+    }
+
+    #[test]
+    fn test_bash_completions_cover_subcommands() {
+        let script = completions(Shell::Bash);
+        for subcommand in ["contact-info", "set-identity", "monitor", COMMAND] {
+            assert!(
+                script.contains(subcommand),
+                "{subcommand} missing from the completions"
+            );
+        }
+    }
+
+    #[test]
+    fn test_completions_for_every_shell() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            assert!(completions(shell).contains("contact-info"));
+        }
+    }
+
+    #[test]
+    fn test_completions_args() {
+        let matches = command(&DefaultArgs::default()).get_matches_from(vec![
+            COMMAND,
+            "--shell",
+            "zsh",
+            "--output-file",
+            "agave-validator.zsh",
+        ]);
+        assert!(matches!(
+            value_t!(matches, "shell", Shell).unwrap(),
+            Shell::Zsh
+        ));
+        assert_eq!(matches.value_of("output_file"), Some("agave-validator.zsh"));
+
+        assert!(command(&DefaultArgs::default())
+            .get_matches_from_safe(vec![COMMAND, "--shell", "tcsh"])
+            .is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use {
+    crate::admin_rpc_service::AdminRpcContactInfo,
+    serde::Serialize,
+    serde_json::Value,
+    std::{fmt, fs, path::Path},
+};
+
+/// Fields that change on every update of the contact info, and so are not
+/// worth reporting
+const IGNORED_FIELDS: &[&str] = &["last_updated_timestamp"];
+
+/// A field of the contact info whose value differs between two snapshots
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FieldDifference {
+    pub field: String,
+    pub saved: Value,
+    pub current: Value,
+}
+
+impl fmt::Display for FieldDifference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<18} {} -> {}", self.field, self.saved, self.current)
+    }
+}
+
+/// Load a contact info saved with `contact-info --output json`
+pub fn load_contact_info(path: &Path) -> Result<AdminRpcContactInfo, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+    serde_json::from_str(&contents)
+        .map_err(|err| format!("failed to parse {}: {err}", path.display()))
+}
+
+/// Compare the JSON renderings of `saved` and `current`, field by field
+pub fn diff_contact_info(
+    saved: &AdminRpcContactInfo,
+    current: &AdminRpcContactInfo,
+) -> Result<Vec<FieldDifference>, String> {
+    let to_fields = |contact_info: &AdminRpcContactInfo| match serde_json::to_value(contact_info) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        Ok(value) => Err(format!("unexpected contact info rendering: {value}")),
+        Err(err) => Err(err.to_string()),
+    };
+    let saved = to_fields(saved)?;
+    let mut current = to_fields(current)?;
+    Ok(saved
+        .into_iter()
+        .filter(|(field, _)| !IGNORED_FIELDS.contains(&field.as_str()))
+        .filter_map(|(field, saved)| {
+            let current = current.remove(&field).unwrap_or(Value::Null);
+            (saved != current).then_some(FieldDifference {
+                field,
+                saved,
+                current,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::net::{Ipv4Addr, SocketAddr},
+    };
+
+    fn contact_info(tpu: SocketAddr, last_updated_timestamp: u64) -> AdminRpcContactInfo {
+        let addr = |port| SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), port));
+        AdminRpcContactInfo {
+            id: "identity".to_string(),
+            gossip: addr(8000),
+            tvu: addr(8001),
+            tvu_quic: addr(8002),
+            serve_repair_quic: addr(8003),
+            tpu,
+            tpu_forwards: addr(8005),
+            tpu_vote: addr(8006),
+            rpc: addr(8899),
+            rpc_pubsub: addr(8900),
+            serve_repair: addr(8007),
+            last_updated_timestamp,
+            shred_version: 42,
+        }
+    }
+
+    #[test]
+    fn test_diff_contact_info() {
+        let saved_tpu = SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), 8004));
+        let current_tpu = SocketAddr::from((Ipv4Addr::new(192, 168, 0, 1), 9004));
+        let saved = contact_info(saved_tpu, 1);
+        let current = contact_info(current_tpu, 2);
+
+        let differences = diff_contact_info(&saved, &current).unwrap();
+        assert_eq!(
+            differences,
+            vec![FieldDifference {
+                field: "tpu".to_string(),
+                saved: Value::from("10.0.0.1:8004"),
+                current: Value::from("192.168.0.1:9004"),
+            }]
+        );
+        assert_eq!(
+            differences[0].to_string(),
+            r#"tpu                "10.0.0.1:8004" -> "192.168.0.1:9004""#
+        );
+
+        assert!(diff_contact_info(&saved, &contact_info(saved_tpu, 3))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,39 +1,101 @@
 use {
     crate::{admin_rpc_service, cli::DefaultArgs, commands::FromClapArgMatches},
-    clap::{App, Arg, ArgMatches, SubCommand},
+    clap::{value_t, App, Arg, ArgMatches, SubCommand},
+    solana_clap_utils::input_validators::is_parsable,
     solana_cli_output::OutputFormat,
-    std::path::Path,
+    std::{
+        path::{Path, PathBuf},
+        time::Duration,
+    },
 };
 
+mod diff;
+mod verify;
+
 const COMMAND: &str = "contact-info";
+const VERIFY_COMMAND: &str = "verify";
+const DEFAULT_VERIFY_TIMEOUT_MS: &str = "1000";
 
 #[derive(Debug, PartialEq)]
 pub struct ContactInfoArgs {
     pub output: OutputFormat,
+    pub diff_against: Option<PathBuf>,
 }
 
 impl FromClapArgMatches for ContactInfoArgs {
     fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
         Ok(ContactInfoArgs {
             output: OutputFormat::from_matches(matches, "output", false),
+            diff_against: matches.value_of("diff_against").map(PathBuf::from),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ContactInfoVerifyArgs {
+    pub timeout: Duration,
+    pub output: OutputFormat,
+}
+
+impl FromClapArgMatches for ContactInfoVerifyArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        let timeout_ms = value_t!(matches, "timeout_ms", u64).map_err(|err| err.to_string())?;
+        Ok(ContactInfoVerifyArgs {
+            timeout: Duration::from_millis(timeout_ms),
+            output: OutputFormat::from_matches(matches, "output", false),
         })
     }
 }
 
+fn output_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("output")
+        .long("output")
+        .takes_value(true)
+        .value_name("MODE")
+        .possible_values(&["json", "json-compact"])
+        .help("Output display mode")
+}
+
 pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
     SubCommand::with_name(COMMAND)
         .about("Display the validator's contact info")
+        .arg(output_arg())
         .arg(
-            Arg::with_name("output")
-                .long("output")
+            Arg::with_name("diff_against")
+                .long("diff-against")
                 .takes_value(true)
-                .value_name("MODE")
-                .possible_values(&["json", "json-compact"])
-                .help("Output display mode"),
+                .value_name("FILE")
+                .help(
+                    "Display the fields that differ from the contact info saved in FILE with \
+                     --output json",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(VERIFY_COMMAND)
+                .about("Check that the advertised addresses are reachable from this machine")
+                .arg(
+                    Arg::with_name("timeout_ms")
+                        .long("timeout-ms")
+                        .takes_value(true)
+                        .value_name("MILLISECONDS")
+                        .validator(is_parsable::<u64>)
+                        .default_value(DEFAULT_VERIFY_TIMEOUT_MS)
+                        .help("How long to wait for each address to respond"),
+                )
+                .arg(output_arg())
+                .after_help(
+                    "The tpu, tpu_forwards and gossip addresses are sent a UDP probe and pass \
+                     if anything replies; the serve_repair address passes if it accepts a TCP \
+                     connection",
+                ),
         )
 }
 
 pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    if let (VERIFY_COMMAND, Some(subcommand_matches)) = matches.subcommand() {
+        return execute_verify(subcommand_matches, ledger_path);
+    }
+
     let contact_info_args = ContactInfoArgs::from_clap_arg_match(matches)?;
 
     let admin_client = admin_rpc_service::connect(ledger_path);
@@ -41,6 +103,26 @@ pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
         .block_on(async move { admin_client.await?.contact_info().await })
         .map_err(|err| format!("contact info request failed: {err}"))?;
 
+    if let Some(saved_path) = &contact_info_args.diff_against {
+        let saved = diff::load_contact_info(saved_path)?;
+        let differences = diff::diff_contact_info(&saved, &contact_info)?;
+        match contact_info_args.output {
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&differences).map_err(|err| err.to_string())?
+            ),
+            OutputFormat::JsonCompact => println!(
+                "{}",
+                serde_json::to_string(&differences).map_err(|err| err.to_string())?
+            ),
+            _ if differences.is_empty() => println!("No differences"),
+            _ => differences
+                .iter()
+                .for_each(|difference| println!("{difference}")),
+        }
+        return Ok(());
+    }
+
     println!(
         "{}",
         contact_info_args.output.formatted_string(&contact_info)
@@ -49,6 +131,30 @@ pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
     Ok(())
 }
 
+fn execute_verify(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let verify_args = ContactInfoVerifyArgs::from_clap_arg_match(matches)?;
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    let contact_info = admin_rpc_service::runtime()
+        .block_on(async move { admin_client.await?.contact_info().await })
+        .map_err(|err| format!("contact info request failed: {err}"))?;
+
+    let results = verify::verify_contact_info(&contact_info, verify_args.timeout);
+    match verify_args.output {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&results).map_err(|err| err.to_string())?
+        ),
+        OutputFormat::JsonCompact => println!(
+            "{}",
+            serde_json::to_string(&results).map_err(|err| err.to_string())?
+        ),
+        _ => results.iter().for_each(|result| println!("{result}")),
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use {
@@ -65,6 +171,7 @@ mod tests {
             vec![COMMAND, "--output", "json"],
             ContactInfoArgs {
                 output: OutputFormat::Json,
+                diff_against: None,
             },
         );
     }
@@ -76,6 +183,7 @@ mod tests {
             vec![COMMAND, "--output", "json-compact"],
             ContactInfoArgs {
                 output: OutputFormat::JsonCompact,
+                diff_against: None,
             },
         );
     }
@@ -87,10 +195,57 @@ mod tests {
             vec![COMMAND],
             ContactInfoArgs {
                 output: OutputFormat::Display,
+                diff_against: None,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_contact_info_diff_against() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--diff-against", "saved.json"],
+            ContactInfoArgs {
+                output: OutputFormat::Display,
+                diff_against: Some(PathBuf::from("saved.json")),
             },
         );
     }
 
+    #[test]
+    fn verify_args_struct_by_command_contact_info_verify_default() {
+        let matches =
+            command(&DefaultArgs::default()).get_matches_from(vec![COMMAND, VERIFY_COMMAND]);
+        let (_, verify_matches) = matches.subcommand();
+        assert_eq!(
+            ContactInfoVerifyArgs::from_clap_arg_match(verify_matches.unwrap()).unwrap(),
+            ContactInfoVerifyArgs {
+                timeout: Duration::from_millis(1000),
+                output: OutputFormat::Display,
+            }
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_contact_info_verify_with_args() {
+        let matches = command(&DefaultArgs::default()).get_matches_from(vec![
+            COMMAND,
+            VERIFY_COMMAND,
+            "--timeout-ms",
+            "250",
+            "--output",
+            "json",
+        ]);
+        let (_, verify_matches) = matches.subcommand();
+        assert_eq!(
+            ContactInfoVerifyArgs::from_clap_arg_match(verify_matches.unwrap()).unwrap(),
+            ContactInfoVerifyArgs {
+                timeout: Duration::from_millis(250),
+                output: OutputFormat::Json,
+            }
+        );
+    }
+
     #[test]
     fn verify_args_struct_by_command_contact_info_output_invalid() {
         verify_args_struct_by_command_is_error::<ContactInfoArgs>(
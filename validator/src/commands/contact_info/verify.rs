@@ -0,0 +1,205 @@
+use {
+    crate::admin_rpc_service::AdminRpcContactInfo,
+    serde::Serialize,
+    std::{
+        fmt,
+        io::ErrorKind,
+        net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket},
+        time::Duration,
+    },
+};
+
+const PROBE: &[u8] = b"agave-validator contact-info verify";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReachabilityStatus {
+    Pass,
+    Fail,
+    Timeout,
+}
+
+impl fmt::Display for ReachabilityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Pass => write!(f, "PASS"),
+            Self::Fail => write!(f, "FAIL"),
+            Self::Timeout => write!(f, "TIMEOUT"),
+        }
+    }
+}
+
+/// Outcome of probing one of the advertised addresses
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AddressReachabilityResult {
+    pub name: &'static str,
+    pub address: SocketAddr,
+    pub status: ReachabilityStatus,
+    pub error: Option<String>,
+}
+
+impl fmt::Display for AddressReachabilityResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<14} {:<22} {}", self.name, self.address, self.status)?;
+        if let Some(error) = &self.error {
+            write!(f, " ({error})")?;
+        }
+        Ok(())
+    }
+}
+
+fn status_of_error(kind: ErrorKind) -> ReachabilityStatus {
+    match kind {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => ReachabilityStatus::Timeout,
+        _ => ReachabilityStatus::Fail,
+    }
+}
+
+/// Send a probe to `address` and wait up to `timeout` for any reply
+pub fn check_udp(address: SocketAddr, timeout: Duration) -> Result<(), std::io::Error> {
+    let bind_address = if address.is_ipv4() {
+        SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))
+    } else {
+        SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0))
+    };
+    let socket = UdpSocket::bind(bind_address)?;
+    // connecting surfaces ICMP port unreachable as a receive error
+    socket.connect(address)?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.send(PROBE)?;
+    let mut buf = [0u8; 64];
+    socket.recv(&mut buf).map(|_| ())
+}
+
+/// Open a TCP connection to `address` within `timeout`
+pub fn check_tcp(address: SocketAddr, timeout: Duration) -> Result<(), std::io::Error> {
+    TcpStream::connect_timeout(&address, timeout).map(|_| ())
+}
+
+fn check(
+    name: &'static str,
+    address: SocketAddr,
+    timeout: Duration,
+    check: fn(SocketAddr, Duration) -> Result<(), std::io::Error>,
+) -> AddressReachabilityResult {
+    let (status, error) = if address.ip().is_unspecified() || address.port() == 0 {
+        (ReachabilityStatus::Fail, Some("not advertised".to_string()))
+    } else {
+        match check(address, timeout) {
+            Ok(()) => (ReachabilityStatus::Pass, None),
+            Err(err) => (status_of_error(err.kind()), Some(err.to_string())),
+        }
+    };
+    AddressReachabilityResult {
+        name,
+        address,
+        status,
+        error,
+    }
+}
+
+/// Probe the tpu, tpu_forwards and gossip addresses over UDP and the
+/// serve_repair address over TCP
+pub fn verify_contact_info(
+    contact_info: &AdminRpcContactInfo,
+    timeout: Duration,
+) -> Vec<AddressReachabilityResult> {
+    vec![
+        check("tpu", contact_info.tpu, timeout, check_udp),
+        check(
+            "tpu_forwards",
+            contact_info.tpu_forwards,
+            timeout,
+            check_udp,
+        ),
+        check("gossip", contact_info.gossip, timeout, check_udp),
+        check(
+            "serve_repair",
+            contact_info.serve_repair,
+            timeout,
+            check_tcp,
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{net::TcpListener, thread},
+    };
+
+    const TIMEOUT: Duration = Duration::from_millis(200);
+
+    fn localhost() -> SocketAddr {
+        SocketAddr::from((Ipv4Addr::LOCALHOST, 0))
+    }
+
+    #[test]
+    fn test_check_udp() {
+        let echo = UdpSocket::bind(localhost()).unwrap();
+        let echo_address = echo.local_addr().unwrap();
+        let echo_thread = thread::spawn(move || {
+            let mut buf = [0u8; 64];
+            let (len, from) = echo.recv_from(&mut buf).unwrap();
+            echo.send_to(&buf[..len], from).unwrap();
+        });
+        let result = check("gossip", echo_address, TIMEOUT, check_udp);
+        echo_thread.join().unwrap();
+        assert_eq!(
+            result,
+            AddressReachabilityResult {
+                name: "gossip",
+                address: echo_address,
+                status: ReachabilityStatus::Pass,
+                error: None,
+            }
+        );
+
+        // bound, but never replies
+        let silent = UdpSocket::bind(localhost()).unwrap();
+        let result = check("tpu", silent.local_addr().unwrap(), TIMEOUT, check_udp);
+        assert_eq!(result.status, ReachabilityStatus::Timeout);
+    }
+
+    #[test]
+    fn test_check_tcp() {
+        let listener = TcpListener::bind(localhost()).unwrap();
+        let address = listener.local_addr().unwrap();
+        assert_eq!(
+            check("serve_repair", address, TIMEOUT, check_tcp).status,
+            ReachabilityStatus::Pass
+        );
+
+        drop(listener);
+        let result = check("serve_repair", address, TIMEOUT, check_tcp);
+        assert_eq!(result.status, ReachabilityStatus::Fail);
+        assert!(result.error.is_some());
+    }
+
+    #[test]
+    fn test_check_not_advertised() {
+        let result = check(
+            "tpu",
+            SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+            TIMEOUT,
+            check_udp,
+        );
+        assert_eq!(result.status, ReachabilityStatus::Fail);
+        assert_eq!(result.error.as_deref(), Some("not advertised"));
+    }
+
+    #[test]
+    fn test_address_reachability_result_serialization() {
+        let result = AddressReachabilityResult {
+            name: "tpu",
+            address: SocketAddr::from((Ipv4Addr::LOCALHOST, 8003)),
+            status: ReachabilityStatus::Timeout,
+            error: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"name":"tpu","address":"127.0.0.1:8003","status":"TIMEOUT","error":null}"#
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use {
+    crate::{
+        admin_rpc_service::{self, BlockSummary},
+        cli::DefaultArgs,
+        commands::FromClapArgMatches,
+    },
+    clap::{value_t, App, Arg, ArgMatches, SubCommand},
+    serde::Serialize,
+    solana_clap_utils::input_validators::is_parsable,
+    solana_cli_output::OutputFormat,
+    std::{
+        fmt::{self, Display},
+        path::Path,
+    },
+};
+
+const COMMAND: &str = "get-recent-blocks";
+
+const DEFAULT_COUNT: &str = "10";
+
+#[derive(Debug, PartialEq)]
+pub struct GetRecentBlocksArgs {
+    pub count: usize,
+    pub output: OutputFormat,
+}
+
+impl FromClapArgMatches for GetRecentBlocksArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        Ok(GetRecentBlocksArgs {
+            count: value_t!(matches, "count", usize).map_err(|err| err.to_string())?,
+            output: OutputFormat::from_matches(matches, "output", false),
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(transparent)]
+struct RecentBlocks(Vec<BlockSummary>);
+
+impl Display for RecentBlocks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, block) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{block}")?;
+        }
+        Ok(())
+    }
+}
+impl solana_cli_output::VerboseDisplay for RecentBlocks {}
+impl solana_cli_output::QuietDisplay for RecentBlocks {}
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about("Display a summary of the most recent blocks of the confirmed fork held in memory")
+        .arg(
+            Arg::with_name("count")
+                .long("count")
+                .value_name("N")
+                .takes_value(true)
+                .default_value(DEFAULT_COUNT)
+                .validator(is_parsable::<usize>)
+                .help("Number of blocks to display, newest first"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["json", "json-compact"])
+                .help("Output display mode"),
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let get_recent_blocks_args = GetRecentBlocksArgs::from_clap_arg_match(matches)?;
+    let count = get_recent_blocks_args.count;
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    let recent_blocks = admin_rpc_service::runtime()
+        .block_on(async move { admin_client.await?.get_recent_blocks(count).await })
+        .map_err(|err| format!("get recent blocks request failed: {err}"))?;
+
+    println!(
+        "{}",
+        get_recent_blocks_args
+            .output
+            .formatted_string(&RecentBlocks(recent_blocks))
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+    };
+
+    #[test]
+    fn verify_args_struct_by_command_get_recent_blocks_default() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND],
+            GetRecentBlocksArgs {
+                count: 10,
+                output: OutputFormat::Display,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_get_recent_blocks_with_args() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--count", "3", "--output", "json"],
+            GetRecentBlocksArgs {
+                count: 3,
+                output: OutputFormat::Json,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_get_recent_blocks_output_json_compact() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--output", "json-compact"],
+            GetRecentBlocksArgs {
+                count: 10,
+                output: OutputFormat::JsonCompact,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_get_recent_blocks_invalid_count() {
+        verify_args_struct_by_command_is_error::<GetRecentBlocksArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--count", "-1"],
+        );
+    }
+}
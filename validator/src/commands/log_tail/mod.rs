@@ -0,0 +1,183 @@
+use {
+    crate::{
+        admin_rpc_service, cli::DefaultArgs, commands::FromClapArgMatches, log_tail::LogTailChunk,
+    },
+    clap::{value_t, App, Arg, ArgMatches, SubCommand},
+    solana_clap_utils::input_validators::is_parsable,
+    std::{path::Path, time::Duration},
+};
+
+const COMMAND: &str = "log-tail";
+
+const DEFAULT_LINES: &str = "100";
+/// Lines requested per poll with `--follow`
+const FOLLOW_CHUNK_LINES: usize = 1_000;
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, PartialEq)]
+pub struct LogTailArgs {
+    pub lines: usize,
+    pub follow: bool,
+    pub filter: Option<String>,
+}
+
+impl FromClapArgMatches for LogTailArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        Ok(LogTailArgs {
+            lines: value_t!(matches, "lines", usize).map_err(|err| err.to_string())?,
+            follow: matches.is_present("follow"),
+            filter: matches.value_of("filter").map(str::to_string),
+        })
+    }
+}
+
+impl LogTailArgs {
+    /// The lines of `chunk` passing the `--filter`, if any
+    fn filter_lines<'a>(&'a self, chunk: &'a LogTailChunk) -> impl Iterator<Item = &'a str> {
+        chunk.lines.iter().map(String::as_str).filter(|line| {
+            self.filter
+                .as_ref()
+                .is_none_or(|filter| line.contains(filter))
+        })
+    }
+}
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about("Print the most recent lines of the validator log, kept in memory by the validator")
+        .arg(
+            Arg::with_name("lines")
+                .long("lines")
+                .short("n")
+                .value_name("N")
+                .takes_value(true)
+                .default_value(DEFAULT_LINES)
+                .validator(is_parsable::<usize>)
+                .help("Number of lines to print"),
+        )
+        .arg(
+            Arg::with_name("follow")
+                .long("follow")
+                .short("f")
+                .takes_value(false)
+                .help("Keep printing the lines logged, until interrupted"),
+        )
+        .arg(
+            Arg::with_name("filter")
+                .long("filter")
+                .value_name("SUBSTR")
+                .takes_value(true)
+                .help("Only print the lines containing this string"),
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let log_tail_args = LogTailArgs::from_clap_arg_match(matches)?;
+
+    admin_rpc_service::runtime().block_on(async move {
+        let admin_client = admin_rpc_service::connect(ledger_path)
+            .await
+            .map_err(|err| format!("log tail request failed: {err}"))?;
+        let mut lines = log_tail_args.lines;
+        let mut cursor = None;
+        loop {
+            let chunk = admin_client
+                .tail_logs(lines, log_tail_args.follow, cursor)
+                .await
+                .map_err(|err| format!("log tail request failed: {err}"))?;
+            if chunk.missed > 0 {
+                eprintln!("... {} lines missed", chunk.missed);
+            }
+            for line in log_tail_args.filter_lines(&chunk) {
+                println!("{line}");
+            }
+            if !log_tail_args.follow {
+                return Ok(());
+            }
+
+            // Poll again right away while catching up
+            if cursor.is_none() || chunk.lines.len() < lines {
+                tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+            }
+            lines = FOLLOW_CHUNK_LINES;
+            cursor = Some(chunk.cursor);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+    };
+
+    #[test]
+    fn verify_args_struct_by_command_log_tail_default() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND],
+            LogTailArgs {
+                lines: 100,
+                follow: false,
+                filter: None,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_log_tail_with_args() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--lines", "20", "--follow", "--filter", "replay"],
+            LogTailArgs {
+                lines: 20,
+                follow: true,
+                filter: Some("replay".to_string()),
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_log_tail_invalid_lines() {
+        verify_args_struct_by_command_is_error::<LogTailArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--lines", "-1"],
+        );
+    }
+
+    #[test]
+    fn test_log_tail_filter() {
+        let chunk = LogTailChunk {
+            lines: vec![
+                "[INFO solana_core::replay_stage] new root 12".to_string(),
+                "[WARN solana_core::repair] repair request failed".to_string(),
+                "[INFO solana_core::replay_stage] new root 13".to_string(),
+            ],
+            cursor: 3,
+            missed: 0,
+        };
+        let filter_lines = |filter: Option<&str>| {
+            LogTailArgs {
+                lines: 10,
+                follow: false,
+                filter: filter.map(str::to_string),
+            }
+            .filter_lines(&chunk)
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(filter_lines(None), chunk.lines);
+        assert_eq!(
+            filter_lines(Some("replay")),
+            [chunk.lines[0].clone(), chunk.lines[2].clone()]
+        );
+        // the filter is a plain, case sensitive substring
+        assert_eq!(filter_lines(Some("WARN")), [chunk.lines[1].clone()]);
+        assert!(filter_lines(Some("warn")).is_empty());
+        assert!(filter_lines(Some("root 1*")).is_empty());
+    }
+}
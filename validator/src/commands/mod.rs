@@ -1,15 +1,27 @@
+pub mod admin_rpc;
 pub mod authorized_voter;
+pub mod batch_execute;
+pub mod completions;
 pub mod contact_info;
 pub mod exit;
+pub mod get_recent_blocks;
+pub mod log_tail;
 pub mod monitor;
+pub mod node_version;
 pub mod plugin;
+pub mod refresh_gossip;
 pub mod repair_shred_from_peer;
+pub mod repair_slot_status;
 pub mod repair_whitelist;
 pub mod run;
+pub mod scheduler_state;
+pub mod set_admission_config;
 pub mod set_identity;
 pub mod set_log_filter;
 pub mod set_public_address;
+pub mod snapshot_hashes;
 pub mod staked_nodes_overrides;
+pub mod subscribe_events;
 pub mod wait_for_restart_window;
 
 pub trait FromClapArgMatches {
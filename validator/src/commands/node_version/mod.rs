@@ -0,0 +1,90 @@
+use {
+    crate::{admin_rpc_service, cli::DefaultArgs, commands::FromClapArgMatches},
+    clap::{App, Arg, ArgMatches, SubCommand},
+    solana_cli_output::OutputFormat,
+    std::path::Path,
+};
+
+const COMMAND: &str = "node-version";
+
+#[derive(Debug, PartialEq)]
+pub struct NodeVersionArgs {
+    pub output: OutputFormat,
+}
+
+impl FromClapArgMatches for NodeVersionArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        Ok(NodeVersionArgs {
+            output: OutputFormat::from_matches(matches, "output", false),
+        })
+    }
+}
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about("Display the build information of the running validator")
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["json", "json-compact"])
+                .help("Output display mode"),
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let node_version_args = NodeVersionArgs::from_clap_arg_match(matches)?;
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    let version_info = admin_rpc_service::runtime()
+        .block_on(async move { admin_client.await?.version_info().await })
+        .map_err(|err| format!("node version request failed: {err}"))?;
+
+    println!(
+        "{}",
+        node_version_args.output.formatted_string(&version_info)
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+    };
+
+    #[test]
+    fn verify_args_struct_by_command_node_version_output_json() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--output", "json"],
+            NodeVersionArgs {
+                output: OutputFormat::Json,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_node_version_output_default() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND],
+            NodeVersionArgs {
+                output: OutputFormat::Display,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_node_version_output_invalid() {
+        verify_args_struct_by_command_is_error::<NodeVersionArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--output", "invalid_output_type"],
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use {
+    crate::{
+        admin_rpc_service::{self, gen_client, AdminRpcContactInfo},
+        cli::DefaultArgs,
+        commands::FromClapArgMatches,
+    },
+    clap::{App, Arg, ArgMatches, SubCommand},
+    jsonrpc_core_client::RpcError,
+    solana_cli_output::OutputFormat,
+    std::path::Path,
+};
+
+const COMMAND: &str = "refresh-gossip";
+
+#[derive(Debug, PartialEq)]
+pub struct RefreshGossipArgs {
+    pub output: OutputFormat,
+}
+
+impl FromClapArgMatches for RefreshGossipArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        Ok(RefreshGossipArgs {
+            output: OutputFormat::from_matches(matches, "output", false),
+        })
+    }
+}
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about(
+            "Push the contact info of the validator to gossip now, rather than at its next \
+             periodic refresh",
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["json", "json-compact"])
+                .help("Output display mode"),
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let refresh_gossip_args = RefreshGossipArgs::from_clap_arg_match(matches)?;
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    let report = admin_rpc_service::runtime()
+        .block_on(async move { refresh_gossip(admin_client.await?, &refresh_gossip_args).await })
+        .map_err(|err| format!("refresh gossip request failed: {err}"))?;
+    println!("{report}");
+
+    Ok(())
+}
+
+/// Refresh the contact info through `admin_client`, returning the report of
+/// the contact info pushed
+async fn refresh_gossip(
+    admin_client: gen_client::Client,
+    refresh_gossip_args: &RefreshGossipArgs,
+) -> Result<String, RpcError> {
+    let contact_info = admin_client.refresh_gossip().await?;
+    Ok(report(&refresh_gossip_args.output, &contact_info))
+}
+
+fn report(output: &OutputFormat, contact_info: &AdminRpcContactInfo) -> String {
+    match output {
+        OutputFormat::Json | OutputFormat::JsonCompact => output.formatted_string(contact_info),
+        _ => format!(
+            "Contact info refreshed at wallclock {}, pushed to gossip peers on the next push \
+             round\n{}",
+            contact_info.last_updated_timestamp,
+            output.formatted_string(contact_info)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+        jsonrpc_core::{IoHandler, Value},
+        jsonrpc_core_client::transports::local,
+        solana_gossip::contact_info::ContactInfo,
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    /// Run `refresh_gossip` against a mocked admin RPC server answering
+    /// `refreshGossip` with `response`
+    fn refresh_gossip_with_mock(
+        response: jsonrpc_core::Result<Value>,
+        refresh_gossip_args: &RefreshGossipArgs,
+    ) -> Result<String, RpcError> {
+        let mut io = IoHandler::new();
+        io.add_sync_method("refreshGossip", move |_params| response.clone());
+        admin_rpc_service::runtime().block_on(async move {
+            let (admin_client, server) = local::connect::<gen_client::Client, _, _>(io);
+            let (report, _) = futures_util::future::join(
+                refresh_gossip(admin_client, refresh_gossip_args),
+                server,
+            )
+            .await;
+            report
+        })
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_refresh_gossip_default() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND],
+            RefreshGossipArgs {
+                output: OutputFormat::Display,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_refresh_gossip_output_json() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--output", "json-compact"],
+            RefreshGossipArgs {
+                output: OutputFormat::JsonCompact,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_refresh_gossip_invalid() {
+        verify_args_struct_by_command_is_error::<RefreshGossipArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--output", "invalid_output_type"],
+        );
+        verify_args_struct_by_command_is_error::<RefreshGossipArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--force"],
+        );
+    }
+
+    #[test]
+    fn test_refresh_gossip() {
+        let pubkey = Pubkey::new_unique();
+        let contact_info = AdminRpcContactInfo::from(ContactInfo::new_localhost(&pubkey, 1_234));
+        let response = serde_json::to_value(&contact_info).unwrap();
+
+        let report = refresh_gossip_with_mock(
+            Ok(response.clone()),
+            &RefreshGossipArgs {
+                output: OutputFormat::Display,
+            },
+        )
+        .unwrap();
+        assert!(report.starts_with(
+            "Contact info refreshed at wallclock 1234, pushed to gossip peers on the next push \
+             round\n"
+        ));
+        assert!(report.contains(&format!("Identity: {pubkey}")));
+
+        // only the contact info is printed as json
+        let report = refresh_gossip_with_mock(
+            Ok(response.clone()),
+            &RefreshGossipArgs {
+                output: OutputFormat::JsonCompact,
+            },
+        )
+        .unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&report).unwrap(), response);
+    }
+
+    #[test]
+    fn test_refresh_gossip_error() {
+        let err = refresh_gossip_with_mock(
+            Err(jsonrpc_core::Error::internal_error()),
+            &RefreshGossipArgs {
+                output: OutputFormat::Display,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, RpcError::JsonRpcError(_)), "{err:?}");
+    }
+}
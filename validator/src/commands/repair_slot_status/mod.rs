@@ -0,0 +1,116 @@
+use {
+    crate::{admin_rpc_service, cli::DefaultArgs, commands::FromClapArgMatches},
+    clap::{value_t, App, Arg, ArgMatches, SubCommand},
+    solana_clap_utils::input_validators::is_parsable,
+    solana_cli_output::OutputFormat,
+    solana_sdk::clock::Slot,
+    std::path::Path,
+};
+
+const COMMAND: &str = "repair-slot-status";
+
+#[derive(Debug, PartialEq)]
+pub struct RepairSlotStatusArgs {
+    pub slot: Slot,
+    pub output: OutputFormat,
+}
+
+impl FromClapArgMatches for RepairSlotStatusArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        Ok(RepairSlotStatusArgs {
+            slot: value_t!(matches, "slot", Slot).map_err(|err| err.to_string())?,
+            output: OutputFormat::from_matches(matches, "output", false),
+        })
+    }
+}
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about("Display which data shreds of a slot are present or missing")
+        .arg(
+            Arg::with_name("slot")
+                .long("slot")
+                .value_name("SLOT")
+                .takes_value(true)
+                .required(true)
+                .validator(is_parsable::<Slot>)
+                .help("Slot to inspect"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["json", "json-compact"])
+                .help("Output display mode"),
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let repair_slot_status_args = RepairSlotStatusArgs::from_clap_arg_match(matches)?;
+    let slot = repair_slot_status_args.slot;
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    let slot_repair_status = admin_rpc_service::runtime()
+        .block_on(async move { admin_client.await?.get_slot_repair_status(slot).await })
+        .map_err(|err| format!("repair slot status request failed: {err}"))?;
+
+    println!(
+        "{}",
+        repair_slot_status_args
+            .output
+            .formatted_string(&slot_repair_status)
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+    };
+
+    #[test]
+    fn verify_args_struct_by_command_repair_slot_status_default() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--slot", "42"],
+            RepairSlotStatusArgs {
+                slot: 42,
+                output: OutputFormat::Display,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_repair_slot_status_output_json() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--slot", "42", "--output", "json"],
+            RepairSlotStatusArgs {
+                slot: 42,
+                output: OutputFormat::Json,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_repair_slot_status_missing_slot() {
+        verify_args_struct_by_command_is_error::<RepairSlotStatusArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND],
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_repair_slot_status_invalid_slot() {
+        verify_args_struct_by_command_is_error::<RepairSlotStatusArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--slot", "not-a-slot"],
+        );
+    }
+}
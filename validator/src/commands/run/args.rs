@@ -653,6 +653,18 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
                  SIGUSR1 signal to the validator process will cause it to re-open the log file",
             ),
     )
+    .arg(
+        Arg::with_name("log_tail_lines")
+            .long("log-tail-lines")
+            .value_name("LINES")
+            .takes_value(true)
+            .default_value(&default_args.log_tail_lines)
+            .validator(is_parsable::<usize>)
+            .help(
+                "Number of recent log lines kept in memory for the log-tail command, 0 to \
+                 disable",
+            ),
+    )
     .arg(
         Arg::with_name("wait_for_supermajority")
             .long("wait-for-supermajority")
@@ -1574,6 +1586,17 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
             .possible_values(TransactionStructure::cli_names())
             .help(TransactionStructure::cli_message()),
     )
+    .arg(
+        Arg::with_name("banking_transaction_ttl_ms")
+            .long("banking-transaction-ttl-ms")
+            .value_name("MILLISECONDS")
+            .takes_value(true)
+            .validator(|s| is_within_range(s, 1..))
+            .help(
+                "Drop transactions not scheduled by the central scheduler within this many \
+                 milliseconds of being received",
+            ),
+    )
     .arg(
         Arg::with_name("unified_scheduler_handler_threads")
             .long("unified-scheduler-handler-threads")
@@ -1,10 +1,12 @@
 use {
     crate::{
-        admin_rpc_service,
-        admin_rpc_service::{load_staked_nodes_overrides, StakedNodesOverrides},
+        admin_rpc_events::{self, AdminRpcEventSender, EventSources},
+        admin_rpc_service::{self, load_staked_nodes_overrides, StakedNodesOverrides},
         bootstrap,
         cli::{self},
-        ledger_lockfile, lock_ledger, redirect_stderr_to_file,
+        ledger_lockfile, lock_ledger,
+        log_tail::LogTail,
+        redirect_stderr_to_file,
     },
     clap::{crate_name, value_t, value_t_or_exit, values_t, values_t_or_exit, ArgMatches},
     crossbeam_channel::unbounded,
@@ -132,7 +134,9 @@ pub fn execute(
         }
     };
     let use_progress_bar = logfile.is_none();
-    let _logger_thread = redirect_stderr_to_file(logfile);
+    let log_tail_lines = value_t_or_exit!(matches, "log_tail_lines", usize);
+    let log_tail = (log_tail_lines > 0).then(|| Arc::new(LogTail::new(log_tail_lines)));
+    let _logger_thread = redirect_stderr_to_file(logfile, log_tail.clone());
 
     info!("{} {}", crate_name!(), solana_version);
     info!("Starting validator with: {:#?}", std::env::args_os());
@@ -1035,6 +1039,9 @@ pub fn execute(
     )
     .unwrap_or_default();
     validator_config.enable_block_production_forwarding = staked_nodes_overrides_path.is_some();
+    validator_config.banking_transaction_ttl = value_t!(matches, "banking_transaction_ttl_ms", u64)
+        .ok()
+        .map(Duration::from_millis);
     validator_config.unified_scheduler_handler_threads =
         value_t!(matches, "unified_scheduler_handler_threads", usize).ok();
 
@@ -1068,6 +1075,11 @@ pub fn execute(
         } else {
             (None, None)
         };
+    let admin_event_sender = AdminRpcEventSender::default();
+    let (slot_notification_sender, slot_notification_receiver) = unbounded();
+    validator_config
+        .slot_notification_senders
+        .push(slot_notification_sender);
     admin_rpc_service::run(
         &ledger_path,
         admin_rpc_service::AdminRpcRequestMetadata {
@@ -1080,8 +1092,21 @@ pub fn execute(
             tower_storage: validator_config.tower_storage.clone(),
             staked_nodes_overrides,
             rpc_to_plugin_manager_sender,
+            event_sender: Some(admin_event_sender.clone()),
+            log_tail,
+            session: None,
         },
     );
+    admin_rpc_events::spawn_event_sources(
+        EventSources::new(
+            admin_service_post_init.clone(),
+            slot_notification_receiver,
+            full_snapshot_archives_dir.clone(),
+            incremental_snapshot_archives_dir.clone(),
+        ),
+        admin_event_sender,
+        &validator_config.validator_exit,
+    );
 
     let gossip_host: IpAddr = matches
         .value_of("gossip_host")
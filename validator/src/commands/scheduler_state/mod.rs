@@ -0,0 +1,189 @@
+use {
+    crate::{admin_rpc_service, cli::DefaultArgs, commands::FromClapArgMatches},
+    clap::{App, Arg, ArgMatches, SubCommand},
+    serde::Serialize,
+    solana_cli_output::OutputFormat,
+    solana_core::banking_stage::SchedulerStateSnapshot,
+    std::{
+        fmt::{self, Display},
+        path::Path,
+        time::Duration,
+    },
+};
+
+const COMMAND: &str = "scheduler-state";
+
+#[derive(Debug, PartialEq)]
+pub struct SchedulerStateArgs {
+    pub output: OutputFormat,
+}
+
+impl FromClapArgMatches for SchedulerStateArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        Ok(SchedulerStateArgs {
+            output: OutputFormat::from_matches(matches, "output", false),
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(transparent)]
+struct SchedulerState(Option<SchedulerStateSnapshot>);
+
+impl Display for SchedulerState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some(snapshot) = &self.0 else {
+            return writeln!(f, "No scheduling pass yet");
+        };
+        writeln!(f, "Queue Depth: {}", snapshot.queue_depth)?;
+        writeln!(f, "Queued CUs: {}", snapshot.queued_cus)?;
+        writeln!(f, "Locked Accounts: {}", snapshot.num_locked_accounts)?;
+        match snapshot.oldest_transaction_age_us {
+            Some(age_us) => writeln!(
+                f,
+                "Oldest Transaction Age: {:?}",
+                Duration::from_micros(age_us)
+            )?,
+            None => writeln!(f, "Oldest Transaction Age: none")?,
+        }
+        for (thread_id, load) in snapshot.thread_loads.iter().enumerate() {
+            writeln!(
+                f,
+                "Thread {thread_id}: {} transactions, {} CUs in flight",
+                load.in_flight_count, load.in_flight_cus
+            )?;
+        }
+        let summary = &snapshot.last_summary;
+        writeln!(
+            f,
+            "Last Pass: {} scheduled, {} unschedulable, {} filtered out",
+            summary.num_scheduled, summary.num_unschedulable, summary.num_filtered_out
+        )
+    }
+}
+impl solana_cli_output::VerboseDisplay for SchedulerState {}
+impl solana_cli_output::QuietDisplay for SchedulerState {}
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about("Display the state of the banking stage scheduler")
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["json", "json-compact"])
+                .help("Output display mode"),
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let scheduler_state_args = SchedulerStateArgs::from_clap_arg_match(matches)?;
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    let scheduler_state = admin_rpc_service::runtime()
+        .block_on(async move { admin_client.await?.scheduler_state().await })
+        .map_err(|err| format!("scheduler state request failed: {err}"))?;
+
+    println!(
+        "{}",
+        scheduler_state_args
+            .output
+            .formatted_string(&SchedulerState(scheduler_state))
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+        solana_core::banking_stage::{SchedulingSummary, ThreadLoad},
+    };
+
+    fn snapshot() -> SchedulerStateSnapshot {
+        let mut last_summary = SchedulingSummary::default();
+        last_summary.num_scheduled = 2;
+        last_summary.num_unschedulable = 1;
+        SchedulerStateSnapshot {
+            queue_depth: 12,
+            queued_cus: 60_000,
+            thread_loads: vec![
+                ThreadLoad {
+                    in_flight_cus: 10_000,
+                    in_flight_count: 2,
+                },
+                ThreadLoad::default(),
+            ],
+            num_locked_accounts: 5,
+            oldest_transaction_age_us: Some(1_500),
+            last_summary,
+            stats: None,
+        }
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_scheduler_state_default() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND],
+            SchedulerStateArgs {
+                output: OutputFormat::Display,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_scheduler_state_output_json() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--output", "json"],
+            SchedulerStateArgs {
+                output: OutputFormat::Json,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_scheduler_state_output_invalid() {
+        verify_args_struct_by_command_is_error::<SchedulerStateArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--output", "invalid_output_type"],
+        );
+    }
+
+    #[test]
+    fn test_scheduler_state_display() {
+        assert_eq!(
+            SchedulerState(Some(snapshot())).to_string(),
+            "Queue Depth: 12\n\
+             Queued CUs: 60000\n\
+             Locked Accounts: 5\n\
+             Oldest Transaction Age: 1.5ms\n\
+             Thread 0: 2 transactions, 10000 CUs in flight\n\
+             Thread 1: 0 transactions, 0 CUs in flight\n\
+             Last Pass: 2 scheduled, 1 unschedulable, 0 filtered out\n"
+        );
+        assert_eq!(SchedulerState(None).to_string(), "No scheduling pass yet\n");
+    }
+
+    #[test]
+    fn test_scheduler_state_json() {
+        let json = OutputFormat::JsonCompact.formatted_string(&SchedulerState(Some(snapshot())));
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["queue_depth"], 12);
+        assert_eq!(value["thread_loads"][0]["in_flight_cus"], 10_000);
+        assert_eq!(value["last_summary"]["num_scheduled"], 2);
+        assert_eq!(
+            serde_json::from_value::<SchedulerStateSnapshot>(value).unwrap(),
+            snapshot()
+        );
+
+        let json = OutputFormat::JsonCompact.formatted_string(&SchedulerState(None));
+        assert_eq!(json, "null");
+    }
+}
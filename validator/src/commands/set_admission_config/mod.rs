@@ -0,0 +1,216 @@
+use {
+    crate::{admin_rpc_service, cli::DefaultArgs, commands::FromClapArgMatches},
+    clap::{value_t, App, Arg, ArgMatches, SubCommand},
+    solana_clap_utils::input_validators::is_parsable,
+    solana_core::banking_stage::{AdmissionConfig, FloorAction},
+    std::path::Path,
+};
+
+const COMMAND: &str = "set-admission-config";
+
+const FLOOR_ACTIONS: &[&str] = &["reject", "evict-and-replace"];
+
+#[derive(Debug, PartialEq)]
+pub struct SetAdmissionConfigArgs {
+    /// `None` disables the admission floor
+    pub admission_config: Option<AdmissionConfig>,
+}
+
+impl FromClapArgMatches for SetAdmissionConfigArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        if matches.is_present("disable") {
+            return Ok(SetAdmissionConfigArgs {
+                admission_config: None,
+            });
+        }
+        let fraction = |name: &str| value_t!(matches, name, f64).map_err(|err| err.to_string());
+        let floor_action = match matches.value_of("floor_action") {
+            Some("evict-and-replace") => FloorAction::EvictAndReplace,
+            _ => FloorAction::Reject,
+        };
+        let admission_config = AdmissionConfig {
+            floor_watermark: fraction("floor_watermark")?,
+            floor_action,
+            eviction_watermark: fraction("eviction_watermark")?,
+            eviction_fraction: fraction("eviction_fraction")?,
+        };
+        admission_config.validate()?;
+        Ok(SetAdmissionConfigArgs {
+            admission_config: Some(admission_config),
+        })
+    }
+}
+
+fn fill_arg<'a>(name: &'a str, long: &'a str, help: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name(name)
+        .long(long)
+        .value_name("FILL")
+        .takes_value(true)
+        .validator(is_parsable::<f64>)
+        .required_unless("disable")
+        .help(help)
+}
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about("Set the admission floor of the banking stage scheduler")
+        .arg(fill_arg(
+            "floor_watermark",
+            "floor-watermark",
+            "Fill of the scheduler container, as a fraction of its capacity, above which new \
+             transactions below the lowest queued priority are kept out",
+        ))
+        .arg(
+            Arg::with_name("floor_action")
+                .long("floor-action")
+                .value_name("ACTION")
+                .takes_value(true)
+                .possible_values(FLOOR_ACTIONS)
+                .help(
+                    "What happens to new transactions above the floor watermark: \"reject\" \
+                     rejects those below the lowest queued priority, \"evict-and-replace\" \
+                     rejects those not above it and admits the others in place of the lowest \
+                     queued transaction [default: reject]",
+                ),
+        )
+        .arg(fill_arg(
+            "eviction_watermark",
+            "eviction-watermark",
+            "Fill of the scheduler container above which the lowest priority queued \
+             transactions are evicted to make room. Must not be below --floor-watermark",
+        ))
+        .arg(
+            Arg::with_name("eviction_fraction")
+                .long("eviction-fraction")
+                .value_name("FRACTION")
+                .takes_value(true)
+                .validator(is_parsable::<f64>)
+                .required_unless("disable")
+                .help("Fraction of the queued transactions evicted above the eviction watermark"),
+        )
+        .arg(
+            Arg::with_name("disable")
+                .long("disable")
+                .takes_value(false)
+                .conflicts_with_all(&[
+                    "floor_watermark",
+                    "floor_action",
+                    "eviction_watermark",
+                    "eviction_fraction",
+                ])
+                .help("Disable the admission floor"),
+        )
+        .after_help(
+            "Note: the admission floor only applies to the currently running validator instance",
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let SetAdmissionConfigArgs { admission_config } =
+        SetAdmissionConfigArgs::from_clap_arg_match(matches)?;
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    admin_rpc_service::runtime()
+        .block_on(async move {
+            admin_client
+                .await?
+                .set_admission_config(admission_config)
+                .await
+        })
+        .map_err(|err| format!("set admission config request failed: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+    };
+
+    #[test]
+    fn verify_args_struct_by_command_set_admission_config() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![
+                COMMAND,
+                "--floor-watermark",
+                "0.8",
+                "--eviction-watermark",
+                "0.95",
+                "--eviction-fraction",
+                "0.25",
+            ],
+            SetAdmissionConfigArgs {
+                admission_config: Some(AdmissionConfig {
+                    floor_watermark: 0.8,
+                    floor_action: FloorAction::Reject,
+                    eviction_watermark: 0.95,
+                    eviction_fraction: 0.25,
+                }),
+            },
+        );
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![
+                COMMAND,
+                "--floor-watermark",
+                "0.9",
+                "--floor-action",
+                "evict-and-replace",
+                "--eviction-watermark",
+                "0.9",
+                "--eviction-fraction",
+                "0.1",
+            ],
+            SetAdmissionConfigArgs {
+                admission_config: Some(AdmissionConfig {
+                    floor_watermark: 0.9,
+                    floor_action: FloorAction::EvictAndReplace,
+                    eviction_watermark: 0.9,
+                    eviction_fraction: 0.1,
+                }),
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_set_admission_config_disable() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--disable"],
+            SetAdmissionConfigArgs {
+                admission_config: None,
+            },
+        );
+        verify_args_struct_by_command_is_error::<SetAdmissionConfigArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--disable", "--floor-watermark", "0.8"],
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_set_admission_config_invalid() {
+        // every fill is required
+        verify_args_struct_by_command_is_error::<SetAdmissionConfigArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--floor-watermark", "0.8"],
+        );
+
+        // the floor watermark may not be above the eviction one
+        let matches = command(&DefaultArgs::default()).get_matches_from(vec![
+            COMMAND,
+            "--floor-watermark",
+            "0.99",
+            "--eviction-watermark",
+            "0.95",
+            "--eviction-fraction",
+            "0.25",
+        ]);
+        assert_eq!(
+            SetAdmissionConfigArgs::from_clap_arg_match(&matches),
+            Err("floor watermark 0.99 is above eviction watermark 0.95".to_string())
+        );
+    }
+}
@@ -0,0 +1,642 @@
+//! Failover of an identity from a peer validator to this one, under
+//! `set-identity --coordinate-with`.
+//!
+//! The peer is switched to an unstaked standby identity before this node
+//! takes the identity over, so that the two never vote with it at once:
+//!
+//! 1. Preflight: the peer must run with the identity, and this node must not.
+//!    Both admin RPC servers must answer.
+//! 2. Tower check: the tower of this node for the identity must have voted
+//!    on the last slot of the saved tower of the peer or later, so that it
+//!    does not vote again on slots the peer already voted on. A peer without
+//!    a saved vote is refused unless forced, as its votes cannot be checked.
+//!    The tower is not copied from the peer: its tower file must be copied to
+//!    this node beforehand.
+//! 3. Standby: the peer switches to a standby identity once its replay stage
+//!    stopped voting with the identity, and reports the last slot it voted
+//!    on with it.
+//! 4. Tower check again, against that slot, which is later than the saved
+//!    one if the peer voted in the meantime.
+//! 5. Local switch: this node switches to the identity.
+//!
+//! A failure before the standby leaves both nodes unchanged. A failure after
+//! it leaves no node voting with the identity, and is reported along with how
+//! to recover.
+
+use {
+    crate::admin_rpc_service,
+    serde::Serialize,
+    solana_sdk::{
+        clock::{Slot, DEFAULT_MS_PER_SLOT},
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+    },
+    std::{
+        fmt,
+        path::{Path, PathBuf},
+        str::FromStr,
+        time::{Duration, Instant},
+    },
+    tokio::runtime::Runtime,
+};
+
+/// Time allowed to the replay stage of the peer to vote with the standby
+/// identity, which it picks up at its next reset
+const STANDBY_SWITCH_TIMEOUT: Duration = Duration::from_millis(25 * DEFAULT_MS_PER_SLOT);
+/// Interval at which the identity the replay stage of the peer votes with is
+/// checked while switching to standby
+const STANDBY_SWITCH_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The peer switched to standby
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StandbyIdentity {
+    /// Identity the peer ran with before switching to standby
+    pub previous_identity: String,
+    /// Unstaked identity generated for the standby
+    pub standby_identity: String,
+    /// Last slot voted on with the previous identity, according to the tower
+    /// of the replay stage when it stopped voting with it, `None` if the
+    /// tower had no vote
+    pub last_vote_slot: Option<Slot>,
+}
+
+/// The admin RPC calls the failover makes, to the peer or to this node
+pub trait FailoverAdminClient {
+    /// Identity the validator currently runs with
+    fn identity(&self) -> Result<Pubkey, String>;
+
+    /// Switch the validator to a standby identity, once its replay stage
+    /// stopped voting with the previous one
+    fn set_identity_standby(&self) -> Result<StandbyIdentity, String>;
+
+    /// Last slot voted on according to the saved tower of `identity`
+    fn tower_last_voted_slot(&self, identity: &Pubkey) -> Result<Option<Slot>, String>;
+
+    fn set_identity(&self, identity_keypair: &Keypair, require_tower: bool) -> Result<(), String>;
+}
+
+/// Admin RPC client of the validator listening at `admin_rpc_path`
+pub struct AdminRpcFailoverClient {
+    admin_rpc_path: PathBuf,
+    runtime: Runtime,
+}
+
+impl AdminRpcFailoverClient {
+    pub fn new(admin_rpc_path: &Path) -> Self {
+        Self {
+            admin_rpc_path: admin_rpc_path.to_path_buf(),
+            runtime: admin_rpc_service::runtime(),
+        }
+    }
+}
+
+impl FailoverAdminClient for AdminRpcFailoverClient {
+    fn identity(&self) -> Result<Pubkey, String> {
+        let contact_info = self
+            .runtime
+            .block_on(async {
+                admin_rpc_service::connect_to(&self.admin_rpc_path)
+                    .await?
+                    .contact_info()
+                    .await
+            })
+            .map_err(|err| format!("contact info request failed: {err}"))?;
+        Pubkey::from_str(&contact_info.id)
+            .map_err(|err| format!("invalid identity {}: {err}", contact_info.id))
+    }
+
+    fn set_identity_standby(&self) -> Result<StandbyIdentity, String> {
+        self.runtime.block_on(async {
+            let admin_client = admin_rpc_service::connect_to(&self.admin_rpc_path)
+                .await
+                .map_err(|err| format!("set identity standby request failed: {err}"))?;
+            let standby_identity = admin_client
+                .set_identity_standby()
+                .await
+                .map_err(|err| format!("set identity standby request failed: {err}"))?;
+
+            // The validator answers once it switched, while its replay stage
+            // picks the standby identity up at its next reset, and may vote
+            // once more with the previous one until then
+            let start = Instant::now();
+            loop {
+                let voting = admin_client
+                    .voting_identity()
+                    .await
+                    .map_err(|err| format!("voting identity request failed: {err}"))?;
+                if voting.identity == standby_identity {
+                    return Ok(StandbyIdentity {
+                        previous_identity: voting
+                            .previous_identity
+                            .unwrap_or_else(|| "no identity".to_string()),
+                        standby_identity,
+                        last_vote_slot: voting.previous_last_vote_slot,
+                    });
+                }
+                if start.elapsed() >= STANDBY_SWITCH_TIMEOUT {
+                    return Err(format!(
+                        "switched to standby identity {standby_identity}, but the replay stage \
+                         still votes as {} after {STANDBY_SWITCH_TIMEOUT:?}",
+                        voting.identity
+                    ));
+                }
+                tokio::time::sleep(STANDBY_SWITCH_POLL_INTERVAL).await;
+            }
+        })
+    }
+
+    fn tower_last_voted_slot(&self, identity: &Pubkey) -> Result<Option<Slot>, String> {
+        self.runtime
+            .block_on(async {
+                admin_rpc_service::connect_to(&self.admin_rpc_path)
+                    .await?
+                    .tower_last_voted_slot(identity.to_string())
+                    .await
+            })
+            .map_err(|err| format!("tower last voted slot request failed: {err}"))
+    }
+
+    fn set_identity(&self, identity_keypair: &Keypair, require_tower: bool) -> Result<(), String> {
+        let identity_keypair = Vec::from(identity_keypair.to_bytes());
+        self.runtime
+            .block_on(async {
+                admin_rpc_service::connect_to(&self.admin_rpc_path)
+                    .await?
+                    .set_identity_from_bytes(identity_keypair, require_tower)
+                    .await
+            })
+            .map_err(|err| format!("set identity request failed: {err}"))
+    }
+}
+
+/// Outcome of a successful failover
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct FailoverOutcome {
+    pub peer_standby_identity: String,
+    /// Last slot the peer voted on with the identity, `None` if it had no
+    /// vote, with `--force`
+    pub peer_last_vote_slot: Option<Slot>,
+    /// Last slot the tower of this node for the identity voted on
+    pub local_last_vote_slot: Option<Slot>,
+}
+
+/// Check that the tower of this node, last voting on `local_last_vote_slot`,
+/// is not behind the peer, last voting on `peer_last_vote_slot`
+fn check_towers(
+    peer_last_vote_slot: Option<Slot>,
+    local_last_vote_slot: Option<Slot>,
+) -> Result<(), String> {
+    match (peer_last_vote_slot, local_last_vote_slot) {
+        (Some(peer_slot), local_slot) if local_slot.map_or(true, |slot| slot < peer_slot) => {
+            Err(format!(
+                "the tower of this node last voted on {}, behind slot {peer_slot} of the peer",
+                local_slot.map_or_else(|| "no slot".to_string(), |slot| format!("slot {slot}")),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Step of the failover, see the module documentation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailoverStep {
+    Preflight,
+    Standby,
+    TowerCheck,
+    LocalSwitch,
+}
+
+impl fmt::Display for FailoverStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Preflight => write!(f, "preflight"),
+            Self::Standby => write!(f, "peer standby"),
+            Self::TowerCheck => write!(f, "tower check"),
+            Self::LocalSwitch => write!(f, "local switch"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FailoverError {
+    /// Neither node changed identity
+    Unchanged { step: FailoverStep, reason: String },
+    /// The standby request failed, possibly after the peer switched
+    StandbyUnknown { identity: Pubkey, reason: String },
+    /// The peer switched to standby, but this node did not take the identity
+    /// over, so no node votes with it
+    PeerOnStandby {
+        step: FailoverStep,
+        reason: String,
+        identity: Pubkey,
+        standby: StandbyIdentity,
+    },
+}
+
+impl FailoverError {
+    pub fn step(&self) -> FailoverStep {
+        match self {
+            Self::Unchanged { step, .. } | Self::PeerOnStandby { step, .. } => *step,
+            Self::StandbyUnknown { .. } => FailoverStep::Standby,
+        }
+    }
+}
+
+impl fmt::Display for FailoverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unchanged { step, reason } => write!(
+                f,
+                "failover aborted at {step}: {reason}. Neither node changed identity"
+            ),
+            Self::StandbyUnknown { identity, reason } => write!(
+                f,
+                "failover aborted at {}: {reason}. The peer may have switched to standby: \
+                 check its identity with `contact-info`, and if it no longer runs as \
+                 {identity}, set the identity of this node or of the peer with `set-identity`",
+                FailoverStep::Standby,
+            ),
+            Self::PeerOnStandby {
+                step,
+                reason,
+                identity,
+                standby,
+            } => {
+                write!(
+                    f,
+                    "failover aborted at {step}: {reason}. The peer switched to standby \
+                     identity {}, and NO NODE IS VOTING as {identity}. To recover, ",
+                    standby.standby_identity,
+                )?;
+                if *step == FailoverStep::TowerCheck {
+                    write!(
+                        f,
+                        "copy the tower file of {identity} from the peer to this node and run \
+                         `set-identity` again without --coordinate-with, or ",
+                    )?;
+                } else {
+                    write!(
+                        f,
+                        "fix the error and run `set-identity` again without \
+                         --coordinate-with, or ",
+                    )?;
+                }
+                write!(
+                    f,
+                    "give the identity back to the peer with `set-identity` on the peer"
+                )
+            }
+        }
+    }
+}
+
+/// Move `identity_keypair` from `peer` to `local`, see the module
+/// documentation. `force` takes the identity over from a peer without a saved
+/// vote.
+pub fn failover(
+    peer: &impl FailoverAdminClient,
+    local: &impl FailoverAdminClient,
+    identity_keypair: &Keypair,
+    require_tower: bool,
+    force: bool,
+) -> Result<FailoverOutcome, FailoverError> {
+    let identity = identity_keypair.pubkey();
+
+    let unchanged = |reason: String| FailoverError::Unchanged {
+        step: FailoverStep::Preflight,
+        reason,
+    };
+    let peer_identity = peer
+        .identity()
+        .map_err(|err| unchanged(format!("peer: {err}")))?;
+    if peer_identity != identity {
+        return Err(unchanged(format!(
+            "the peer runs as {peer_identity}, not {identity}"
+        )));
+    }
+    let local_identity = local
+        .identity()
+        .map_err(|err| unchanged(format!("this node: {err}")))?;
+    if local_identity == identity {
+        return Err(unchanged(format!("this node already runs as {identity}")));
+    }
+    let saved_peer_last_vote_slot = peer
+        .tower_last_voted_slot(&identity)
+        .map_err(|err| unchanged(format!("peer: {err}")))?;
+    let local_last_vote_slot = local
+        .tower_last_voted_slot(&identity)
+        .map_err(|err| unchanged(format!("this node: {err}")))?;
+
+    let unchanged = |reason: String| FailoverError::Unchanged {
+        step: FailoverStep::TowerCheck,
+        reason,
+    };
+    if saved_peer_last_vote_slot.is_none() && !force {
+        return Err(unchanged(format!(
+            "the peer has no saved vote for {identity}, so the tower of this node cannot be \
+             checked against it. Use --force to take the identity over anyway"
+        )));
+    }
+    check_towers(saved_peer_last_vote_slot, local_last_vote_slot).map_err(unchanged)?;
+
+    let standby = peer
+        .set_identity_standby()
+        .map_err(|reason| FailoverError::StandbyUnknown { identity, reason })?;
+    let peer_on_standby = |step, reason| FailoverError::PeerOnStandby {
+        step,
+        reason,
+        identity,
+        standby: standby.clone(),
+    };
+    if standby.previous_identity != identity.to_string() {
+        return Err(peer_on_standby(
+            FailoverStep::Standby,
+            format!(
+                "the peer switched from {}, not {identity}",
+                standby.previous_identity
+            ),
+        ));
+    }
+
+    let peer_last_vote_slot = standby.last_vote_slot.max(saved_peer_last_vote_slot);
+    let local_last_vote_slot = local
+        .tower_last_voted_slot(&identity)
+        .map_err(|err| peer_on_standby(FailoverStep::TowerCheck, format!("this node: {err}")))?;
+    check_towers(peer_last_vote_slot, local_last_vote_slot)
+        .map_err(|reason| peer_on_standby(FailoverStep::TowerCheck, reason))?;
+
+    local
+        .set_identity(identity_keypair, require_tower)
+        .map_err(|err| peer_on_standby(FailoverStep::LocalSwitch, format!("this node: {err}")))?;
+
+    Ok(FailoverOutcome {
+        peer_standby_identity: standby.standby_identity,
+        peer_last_vote_slot,
+        local_last_vote_slot,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::cell::RefCell};
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Call {
+        Identity,
+        SetIdentityStandby,
+        TowerLastVotedSlot,
+        SetIdentity,
+    }
+
+    /// A validator whose admin RPC fails on `fail_on`
+    struct MockValidator {
+        identity: RefCell<Pubkey>,
+        last_vote_slot: Option<Slot>,
+        /// Last vote reported on standby, voted after the tower was read
+        standby_last_vote_slot: Option<Slot>,
+        fail_on: Option<Call>,
+        calls: RefCell<Vec<Call>>,
+    }
+
+    impl MockValidator {
+        fn new(identity: Pubkey, last_vote_slot: Option<Slot>) -> Self {
+            Self {
+                identity: RefCell::new(identity),
+                last_vote_slot,
+                standby_last_vote_slot: last_vote_slot,
+                fail_on: None,
+                calls: RefCell::default(),
+            }
+        }
+
+        fn failing_on(self, call: Call) -> Self {
+            Self {
+                fail_on: Some(call),
+                ..self
+            }
+        }
+
+        fn voting_until_standby(self, slot: Slot) -> Self {
+            Self {
+                standby_last_vote_slot: Some(slot),
+                ..self
+            }
+        }
+
+        fn call(&self, call: Call) -> Result<(), String> {
+            self.calls.borrow_mut().push(call);
+            if self.fail_on == Some(call) {
+                Err(format!("{call:?} failed"))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn current_identity(&self) -> Pubkey {
+            *self.identity.borrow()
+        }
+    }
+
+    impl FailoverAdminClient for MockValidator {
+        fn identity(&self) -> Result<Pubkey, String> {
+            self.call(Call::Identity)?;
+            Ok(*self.identity.borrow())
+        }
+
+        fn set_identity_standby(&self) -> Result<StandbyIdentity, String> {
+            self.call(Call::SetIdentityStandby)?;
+            let standby_identity = Pubkey::new_unique();
+            let previous_identity = self.identity.replace(standby_identity);
+            Ok(StandbyIdentity {
+                previous_identity: previous_identity.to_string(),
+                standby_identity: standby_identity.to_string(),
+                last_vote_slot: self.standby_last_vote_slot,
+            })
+        }
+
+        fn tower_last_voted_slot(&self, _identity: &Pubkey) -> Result<Option<Slot>, String> {
+            self.call(Call::TowerLastVotedSlot)?;
+            Ok(self.last_vote_slot)
+        }
+
+        fn set_identity(
+            &self,
+            identity_keypair: &Keypair,
+            _require_tower: bool,
+        ) -> Result<(), String> {
+            self.call(Call::SetIdentity)?;
+            *self.identity.borrow_mut() = identity_keypair.pubkey();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_failover() {
+        let identity_keypair = Keypair::new();
+        let identity = identity_keypair.pubkey();
+        let peer = MockValidator::new(identity, Some(100));
+        let local = MockValidator::new(Pubkey::new_unique(), Some(101));
+
+        let outcome = failover(&peer, &local, &identity_keypair, false, false).unwrap();
+        assert_eq!(outcome.peer_last_vote_slot, Some(100));
+        assert_eq!(outcome.local_last_vote_slot, Some(101));
+        assert_eq!(
+            outcome.peer_standby_identity,
+            peer.current_identity().to_string()
+        );
+        assert_ne!(peer.current_identity(), identity);
+        assert_eq!(local.current_identity(), identity);
+        // the towers are compared before the peer goes on standby, and again
+        // before this node switches
+        assert_eq!(
+            *peer.calls.borrow(),
+            vec![
+                Call::Identity,
+                Call::TowerLastVotedSlot,
+                Call::SetIdentityStandby
+            ]
+        );
+        assert_eq!(
+            *local.calls.borrow(),
+            vec![
+                Call::Identity,
+                Call::TowerLastVotedSlot,
+                Call::TowerLastVotedSlot,
+                Call::SetIdentity
+            ]
+        );
+
+        // a vote of the peer while switching to standby is checked too
+        let peer = MockValidator::new(identity, Some(100)).voting_until_standby(101);
+        let local = MockValidator::new(Pubkey::new_unique(), Some(101));
+        let outcome = failover(&peer, &local, &identity_keypair, false, false).unwrap();
+        assert_eq!(outcome.peer_last_vote_slot, Some(101));
+
+        // a peer without a saved vote is only taken over with force
+        let peer = MockValidator::new(identity, None);
+        let local = MockValidator::new(Pubkey::new_unique(), None);
+        let outcome = failover(&peer, &local, &identity_keypair, false, true).unwrap();
+        assert_eq!(outcome.peer_last_vote_slot, None);
+        assert_eq!(local.current_identity(), identity);
+    }
+
+    #[test]
+    fn test_failover_preflight_failures() {
+        let identity_keypair = Keypair::new();
+        let identity = identity_keypair.pubkey();
+        let local_identity = Pubkey::new_unique();
+
+        let cases = [
+            (
+                MockValidator::new(identity, Some(100)).failing_on(Call::Identity),
+                MockValidator::new(local_identity, Some(100)),
+                FailoverStep::Preflight,
+            ),
+            (
+                MockValidator::new(Pubkey::new_unique(), Some(100)),
+                MockValidator::new(local_identity, Some(100)),
+                FailoverStep::Preflight,
+            ),
+            (
+                MockValidator::new(identity, Some(100)),
+                MockValidator::new(local_identity, Some(100)).failing_on(Call::Identity),
+                FailoverStep::Preflight,
+            ),
+            (
+                MockValidator::new(identity, Some(100)),
+                MockValidator::new(identity, Some(100)),
+                FailoverStep::Preflight,
+            ),
+            (
+                MockValidator::new(identity, Some(100)).failing_on(Call::TowerLastVotedSlot),
+                MockValidator::new(local_identity, Some(100)),
+                FailoverStep::Preflight,
+            ),
+            (
+                MockValidator::new(identity, Some(100)),
+                MockValidator::new(local_identity, Some(100)).failing_on(Call::TowerLastVotedSlot),
+                FailoverStep::Preflight,
+            ),
+            // the peer has no saved vote, and the failover is not forced
+            (
+                MockValidator::new(identity, None),
+                MockValidator::new(local_identity, Some(100)),
+                FailoverStep::TowerCheck,
+            ),
+            // the tower of this node is behind that of the peer
+            (
+                MockValidator::new(identity, Some(100)),
+                MockValidator::new(local_identity, Some(99)),
+                FailoverStep::TowerCheck,
+            ),
+            // this node has no tower for the identity
+            (
+                MockValidator::new(identity, Some(100)),
+                MockValidator::new(local_identity, None),
+                FailoverStep::TowerCheck,
+            ),
+        ];
+        for (peer, local, expected_step) in cases {
+            let peer_identity = peer.current_identity();
+            let local_identity = local.current_identity();
+            let err = failover(&peer, &local, &identity_keypair, false, false).unwrap_err();
+            assert!(matches!(err, FailoverError::Unchanged { .. }));
+            assert_eq!(err.step(), expected_step);
+            assert!(err.to_string().ends_with("Neither node changed identity"));
+            assert_eq!(peer.current_identity(), peer_identity);
+            assert_eq!(local.current_identity(), local_identity);
+            assert!(!peer.calls.borrow().contains(&Call::SetIdentityStandby));
+            assert!(!local.calls.borrow().contains(&Call::SetIdentity));
+        }
+    }
+
+    #[test]
+    fn test_failover_standby_failure() {
+        let identity_keypair = Keypair::new();
+        let identity = identity_keypair.pubkey();
+        let peer = MockValidator::new(identity, Some(100)).failing_on(Call::SetIdentityStandby);
+        let local = MockValidator::new(Pubkey::new_unique(), Some(100));
+
+        let err = failover(&peer, &local, &identity_keypair, false, false).unwrap_err();
+        assert!(matches!(err, FailoverError::StandbyUnknown { .. }));
+        assert_eq!(err.step(), FailoverStep::Standby);
+        assert!(err.to_string().contains("check its identity"));
+        assert!(!local.calls.borrow().contains(&Call::SetIdentity));
+    }
+
+    #[test]
+    fn test_failover_after_standby_failures() {
+        let identity_keypair = Keypair::new();
+        let identity = identity_keypair.pubkey();
+
+        let cases = [
+            // the peer voted past the tower of this node while switching
+            (
+                MockValidator::new(identity, Some(100)).voting_until_standby(101),
+                MockValidator::new(Pubkey::new_unique(), Some(100)),
+                FailoverStep::TowerCheck,
+            ),
+            (
+                MockValidator::new(identity, Some(100)),
+                MockValidator::new(Pubkey::new_unique(), Some(100)).failing_on(Call::SetIdentity),
+                FailoverStep::LocalSwitch,
+            ),
+        ];
+        for (peer, local, expected_step) in cases {
+            let local_identity = local.current_identity();
+            let err = failover(&peer, &local, &identity_keypair, false, false).unwrap_err();
+            assert!(matches!(err, FailoverError::PeerOnStandby { .. }));
+            assert_eq!(err.step(), expected_step);
+            // neither node votes with the identity, and the error says so
+            assert_ne!(peer.current_identity(), identity);
+            assert_eq!(local.current_identity(), local_identity);
+            let message = err.to_string();
+            assert!(message.contains(&format!("NO NODE IS VOTING as {identity}")));
+            assert!(message.contains(&peer.current_identity().to_string()));
+            assert_eq!(
+                message.contains("copy the tower file"),
+                expected_step == FailoverStep::TowerCheck
+            );
+        }
+    }
+}
@@ -1,11 +1,56 @@
 use {
     crate::{admin_rpc_service, cli::DefaultArgs},
     clap::{value_t, App, Arg, ArgMatches, SubCommand},
+    failover::{failover, AdminRpcFailoverClient, FailoverOutcome},
+    serde::Serialize,
     solana_clap_utils::input_validators::is_keypair,
-    solana_sdk::signature::{read_keypair, Signer},
-    std::{fs, path::Path},
+    solana_cli_output::OutputFormat,
+    solana_sdk::signature::{read_keypair, read_keypair_file, Keypair, Signer},
+    std::{
+        fmt::{self, Display},
+        fs,
+        path::{Path, PathBuf},
+    },
 };
 
+mod failover;
+
+/// Outcome of a successful identity change
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct SetIdentityOutcome {
+    pub new_identity: String,
+    pub required_tower: bool,
+    /// Whether the validator could load saved tower state for the new
+    /// identity, or `None` if it could not report it. With
+    /// `--coordinate-with`, the tower is found if it holds a vote.
+    pub tower_found: Option<bool>,
+    /// Failover from the peer, with `--coordinate-with`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failover: Option<FailoverOutcome>,
+}
+
+impl Display for SetIdentityOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Validator identity set to {}", self.new_identity)?;
+        if self.tower_found == Some(true) {
+            write!(f, ", saved tower state found")?;
+        }
+        if let Some(failover) = &self.failover {
+            write!(
+                f,
+                ", peer switched to standby identity {}",
+                failover.peer_standby_identity
+            )?;
+            if let Some(slot) = failover.peer_last_vote_slot {
+                write!(f, " after its last vote on slot {slot}")?;
+            }
+        }
+        Ok(())
+    }
+}
+impl solana_cli_output::VerboseDisplay for SetIdentityOutcome {}
+impl solana_cli_output::QuietDisplay for SetIdentityOutcome {}
+
 pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
     SubCommand::with_name("set-identity")
         .about("Set the validator identity")
@@ -24,22 +69,90 @@ pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
                 .takes_value(false)
                 .help("Refuse to set the validator identity if saved tower state is not found"),
         )
+        .arg(
+            Arg::with_name("coordinate_with")
+                .long("coordinate-with")
+                .value_name("PEER_ADMIN_SOCKET")
+                .takes_value(true)
+                .help(
+                    "Take the identity over from the validator whose admin RPC socket is \
+                     PEER_ADMIN_SOCKET, e.g. <PEER_LEDGER>/admin.rpc. The peer is switched to \
+                     an unstaked standby identity first, and the identity is only set once \
+                     the tower of this node is at least as recent as the last vote of the \
+                     peer. The tower is not copied between the nodes: first copy the tower \
+                     file of the identity, tower-1_9-<IDENTITY>.bin, from the tower directory \
+                     of the peer to that of this node, which is the ledger directory unless \
+                     the validator runs with --tower",
+                ),
+        )
+        .arg(
+            Arg::with_name("force")
+                .long("force")
+                .takes_value(false)
+                .requires("coordinate_with")
+                .help(
+                    "Take the identity over even if the peer has no saved vote for it, in which \
+                     case the tower of this node cannot be checked against the votes of the peer",
+                ),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["json", "json-compact"])
+                .help("Output display mode"),
+        )
         .after_help(
             "Note: the new identity only applies to the currently running validator instance",
         )
 }
 
 pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let output = OutputFormat::from_matches(matches, "output", false);
+    let outcome = set_identity(matches, ledger_path, output == OutputFormat::Display)?;
+    println!("{}", output.formatted_string(&outcome));
+    Ok(())
+}
+
+/// Set the identity of the validator running at `ledger_path`, printing its
+/// progress if `verbose`
+pub fn set_identity(
+    matches: &ArgMatches,
+    ledger_path: &Path,
+    verbose: bool,
+) -> Result<SetIdentityOutcome, String> {
     let require_tower = matches.is_present("require_tower");
 
-    if let Ok(identity_keypair) = value_t!(matches, "identity", String) {
+    if let Some(peer_admin_rpc_path) = matches.value_of("coordinate_with") {
+        return set_identity_with_failover(
+            matches,
+            ledger_path,
+            Path::new(peer_admin_rpc_path),
+            require_tower,
+            matches.is_present("force"),
+            verbose,
+        );
+    }
+
+    let new_identity = if let Ok(identity_keypair) = value_t!(matches, "identity", String) {
         let identity_keypair = fs::canonicalize(&identity_keypair)
             .map_err(|err| format!("unable to access path {identity_keypair}: {err:?}"))?;
+        let new_identity = read_keypair_file(&identity_keypair)
+            .map_err(|err| {
+                format!(
+                    "unable to read keypair {}: {err}",
+                    identity_keypair.display()
+                )
+            })?
+            .pubkey();
 
-        println!(
-            "New validator identity path: {}",
-            identity_keypair.display()
-        );
+        if verbose {
+            println!(
+                "New validator identity path: {}",
+                identity_keypair.display()
+            );
+        }
 
         let admin_client = admin_rpc_service::connect(ledger_path);
         admin_rpc_service::runtime()
@@ -49,13 +162,17 @@ pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
                     .set_identity(identity_keypair.display().to_string(), require_tower)
                     .await
             })
-            .map_err(|err| format!("set identity request failed: {err}"))
+            .map_err(|err| format!("set identity request failed: {err}"))?;
+        new_identity
     } else {
         let mut stdin = std::io::stdin();
         let identity_keypair = read_keypair(&mut stdin)
             .map_err(|err| format!("unable to read json keypair from stdin: {err:?}"))?;
+        let new_identity = identity_keypair.pubkey();
 
-        println!("New validator identity: {}", identity_keypair.pubkey());
+        if verbose {
+            println!("New validator identity: {new_identity}");
+        }
 
         let admin_client = admin_rpc_service::connect(ledger_path);
         admin_rpc_service::runtime()
@@ -65,6 +182,136 @@ pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
                     .set_identity_from_bytes(Vec::from(identity_keypair.to_bytes()), require_tower)
                     .await
             })
-            .map_err(|err| format!("set identity request failed: {err}"))
+            .map_err(|err| format!("set identity request failed: {err}"))?;
+        new_identity
+    };
+
+    // The identity is set by now, so a validator unable to report its tower
+    // does not fail the command
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    let tower_found = admin_rpc_service::runtime()
+        .block_on(async move {
+            admin_client
+                .await?
+                .tower_found(new_identity.to_string())
+                .await
+        })
+        .map_err(|err| {
+            if verbose {
+                println!("Unable to check the saved tower state: {err}");
+            }
+        })
+        .ok();
+
+    Ok(SetIdentityOutcome {
+        new_identity: new_identity.to_string(),
+        required_tower: require_tower,
+        tower_found,
+        failover: None,
+    })
+}
+
+/// Take the identity over from the validator listening at
+/// `peer_admin_rpc_path`, see [`failover`]
+fn set_identity_with_failover(
+    matches: &ArgMatches,
+    ledger_path: &Path,
+    peer_admin_rpc_path: &Path,
+    require_tower: bool,
+    force: bool,
+    verbose: bool,
+) -> Result<SetIdentityOutcome, String> {
+    let identity_keypair: Keypair =
+        if let Ok(identity_keypair) = value_t!(matches, "identity", PathBuf) {
+            read_keypair_file(&identity_keypair).map_err(|err| {
+                format!(
+                    "unable to read keypair {}: {err}",
+                    identity_keypair.display()
+                )
+            })?
+        } else {
+            read_keypair(&mut std::io::stdin())
+                .map_err(|err| format!("unable to read json keypair from stdin: {err:?}"))?
+        };
+    if verbose {
+        println!(
+            "Taking validator identity {} over from the validator at {}",
+            identity_keypair.pubkey(),
+            peer_admin_rpc_path.display()
+        );
+    }
+
+    let peer = AdminRpcFailoverClient::new(peer_admin_rpc_path);
+    let local = AdminRpcFailoverClient::new(&admin_rpc_service::admin_rpc_path(ledger_path));
+    let outcome = failover(&peer, &local, &identity_keypair, require_tower, force)
+        .map_err(|err| err.to_string())?;
+
+    Ok(SetIdentityOutcome {
+        new_identity: identity_keypair.pubkey().to_string(),
+        required_tower: require_tower,
+        tower_found: Some(outcome.local_last_vote_slot.is_some()),
+        failover: Some(outcome),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_identity_outcome_json() {
+        let outcome = SetIdentityOutcome {
+            new_identity: "7Np41oeYqPefeNQEHSv1UDhYrehxin3NStELsSKCT4K2".to_string(),
+            required_tower: true,
+            tower_found: Some(true),
+            failover: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&outcome).unwrap(),
+            serde_json::json!({
+                "new_identity": "7Np41oeYqPefeNQEHSv1UDhYrehxin3NStELsSKCT4K2",
+                "required_tower": true,
+                "tower_found": true,
+            })
+        );
+        assert_eq!(
+            outcome.to_string(),
+            "Validator identity set to 7Np41oeYqPefeNQEHSv1UDhYrehxin3NStELsSKCT4K2, saved \
+             tower state found"
+        );
+
+        let outcome = SetIdentityOutcome {
+            required_tower: false,
+            tower_found: None,
+            ..outcome
+        };
+        assert_eq!(
+            serde_json::to_string(&outcome).unwrap(),
+            "{\"new_identity\":\"7Np41oeYqPefeNQEHSv1UDhYrehxin3NStELsSKCT4K2\",\
+             \"required_tower\":false,\"tower_found\":null}"
+        );
+
+        let outcome = SetIdentityOutcome {
+            failover: Some(FailoverOutcome {
+                peer_standby_identity: "CiDwVBFgWV9E5MvXWoLgnEgn2hK7rJikbvfWavzAQz3".to_string(),
+                peer_last_vote_slot: Some(100),
+                local_last_vote_slot: Some(100),
+            }),
+            ..outcome
+        };
+        assert_eq!(
+            serde_json::to_value(&outcome).unwrap()["failover"],
+            serde_json::json!({
+                "peer_standby_identity": "CiDwVBFgWV9E5MvXWoLgnEgn2hK7rJikbvfWavzAQz3",
+                "peer_last_vote_slot": 100,
+                "local_last_vote_slot": 100,
+            })
+        );
+        assert_eq!(
+            outcome.to_string(),
+            "Validator identity set to 7Np41oeYqPefeNQEHSv1UDhYrehxin3NStELsSKCT4K2, peer \
+             switched to standby identity CiDwVBFgWV9E5MvXWoLgnEgn2hK7rJikbvfWavzAQz3 after its \
+             last vote on slot 100"
+        );
     }
 }
@@ -1,9 +1,12 @@
 use {
     crate::{admin_rpc_service, cli::DefaultArgs},
     clap::{App, Arg, ArgGroup, ArgMatches, SubCommand},
-    std::{net::SocketAddr, path::Path},
+    std::{fs, net::SocketAddr, path::Path},
 };
 
+const PUBLIC_TPU_ADDRESS_ARG: &str = "--public-tpu-address";
+const PUBLIC_TPU_FORWARDS_ADDRESS_ARG: &str = "--public-tpu-forwards-address";
+
 pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
     SubCommand::with_name("set-public-address")
         .about("Specify addresses to advertise in gossip")
@@ -29,7 +32,21 @@ pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
                 .required(true)
                 .multiple(true),
         )
-        .after_help("Note: At least one arg must be used. Using multiple is ok")
+        .arg(
+            Arg::with_name("persist")
+                .long("persist")
+                .value_name("ARGS_FILE")
+                .takes_value(true)
+                .help(
+                    "After updating the running validator, also write the addresses to this \
+                     file of validator arguments, one per line, so that a validator started \
+                     with it advertises them again",
+                ),
+        )
+        .after_help(
+            "Note: At least one arg must be used. Using multiple is ok. Without --persist, the \
+             addresses only apply to the currently running validator instance",
+        )
 }
 
 pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
@@ -65,5 +82,98 @@ pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
         tpu_forwards_addr,
         set_public_tpu_forwards_address,
         "set public tpu forwards address"
-    )
+    )?;
+
+    // The running validator is updated, only the args file remains
+    if let Some(args_file) = matches.value_of("persist") {
+        let contents = match fs::read_to_string(args_file) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(format!(
+                    "the running validator was updated, but reading {args_file} failed: {err}"
+                ))
+            }
+        };
+        let contents = persist_public_addresses(&contents, tpu_addr, tpu_forwards_addr);
+        fs::write(args_file, contents).map_err(|err| {
+            format!("the running validator was updated, but writing {args_file} failed: {err}")
+        })?;
+        println!("Persisted the public addresses to {args_file}");
+    }
+    Ok(())
+}
+
+/// Set the public address arguments of `contents`, a file of validator
+/// arguments, replacing any existing ones and keeping every other line.
+fn persist_public_addresses(
+    contents: &str,
+    tpu_addr: Option<SocketAddr>,
+    tpu_forwards_addr: Option<SocketAddr>,
+) -> String {
+    let updates: Vec<_> = [
+        (PUBLIC_TPU_ADDRESS_ARG, tpu_addr),
+        (PUBLIC_TPU_FORWARDS_ADDRESS_ARG, tpu_forwards_addr),
+    ]
+    .into_iter()
+    .filter_map(|(arg, addr)| addr.map(|addr| (arg, addr)))
+    .collect();
+
+    let is_updated = |line: &str| {
+        let arg = line.trim().split([' ', '=']).next().unwrap_or_default();
+        updates.iter().any(|(updated_arg, _)| *updated_arg == arg)
+    };
+    let mut lines: Vec<_> = contents
+        .lines()
+        .filter(|line| !is_updated(line))
+        .map(str::to_string)
+        .collect();
+    lines.extend(updates.iter().map(|(arg, addr)| format!("{arg} {addr}")));
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persist_public_addresses() {
+        let args_file = "\
+--identity /home/sol/validator-keypair.json
+# advertised addresses
+--public-tpu-address 10.0.0.1:8003
+--public-tpu-forwards-address=10.0.0.1:8004
+--rpc-port 8899
+";
+        let tpu_addr: SocketAddr = "192.168.0.1:9003".parse().unwrap();
+        let tpu_forwards_addr: SocketAddr = "192.168.0.1:9004".parse().unwrap();
+
+        assert_eq!(
+            persist_public_addresses(args_file, Some(tpu_addr), None),
+            "\
+--identity /home/sol/validator-keypair.json
+# advertised addresses
+--public-tpu-forwards-address=10.0.0.1:8004
+--rpc-port 8899
+--public-tpu-address 192.168.0.1:9003
+"
+        );
+        assert_eq!(
+            persist_public_addresses(args_file, Some(tpu_addr), Some(tpu_forwards_addr)),
+            "\
+--identity /home/sol/validator-keypair.json
+# advertised addresses
+--rpc-port 8899
+--public-tpu-address 192.168.0.1:9003
+--public-tpu-forwards-address 192.168.0.1:9004
+"
+        );
+        assert_eq!(
+            persist_public_addresses("", None, Some(tpu_forwards_addr)),
+            "--public-tpu-forwards-address 192.168.0.1:9004\n"
+        );
+    }
 }
@@ -0,0 +1,647 @@
+use {
+    crate::{cli::DefaultArgs, commands::FromClapArgMatches},
+    chrono::{DateTime, SecondsFormat, Utc},
+    clap::{value_t, App, Arg, ArgMatches, SubCommand},
+    serde::Serialize,
+    solana_cli_output::OutputFormat,
+    solana_runtime::{
+        snapshot_archive_info::{
+            FullSnapshotArchiveInfo, IncrementalSnapshotArchiveInfo, SnapshotArchiveInfoGetter,
+        },
+        snapshot_utils::{build_snapshot_archives_remote_dir, ArchiveFormat},
+    },
+    solana_sdk::clock::Slot,
+    std::{
+        fmt::{self, Display},
+        fs,
+        path::{Path, PathBuf},
+        time::SystemTime,
+    },
+    verify::{verify_archive, ArchiveStatus},
+};
+
+mod verify;
+
+const COMMAND: &str = "snapshot-hashes";
+
+const FULL_SNAPSHOT_ARCHIVE_PREFIX: &str = "snapshot-";
+const INCREMENTAL_SNAPSHOT_ARCHIVE_PREFIX: &str = "incremental-snapshot-";
+
+#[derive(Debug, PartialEq)]
+pub struct SnapshotHashesArgs {
+    pub full_snapshot_archives_dir: Option<PathBuf>,
+    pub incremental_snapshot_archives_dir: Option<PathBuf>,
+    pub verify: bool,
+    pub output: OutputFormat,
+}
+
+impl FromClapArgMatches for SnapshotHashesArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        let snapshots_dir = value_t!(matches, "snapshots", PathBuf).ok();
+        Ok(SnapshotHashesArgs {
+            full_snapshot_archives_dir: value_t!(matches, "full_snapshot_archive_path", PathBuf)
+                .ok()
+                .or_else(|| snapshots_dir.clone()),
+            incremental_snapshot_archives_dir: value_t!(
+                matches,
+                "incremental_snapshot_archive_path",
+                PathBuf
+            )
+            .ok()
+            .or(snapshots_dir),
+            verify: matches.is_present("verify"),
+            output: match matches.value_of("output") {
+                Some("json") => OutputFormat::Json,
+                _ => OutputFormat::Display,
+            },
+        })
+    }
+}
+
+/// What the name of a file in a snapshot archives directory describes
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArchiveName {
+    Full(FullSnapshotArchiveInfo),
+    Incremental(IncrementalSnapshotArchiveInfo),
+    /// Named like a snapshot archive, but not parsable as one
+    Malformed,
+    /// Not a snapshot archive
+    Other,
+}
+
+pub fn parse_archive_name(path: &Path) -> ArchiveName {
+    let Some(file_name) = path.file_name().and_then(|file_name| file_name.to_str()) else {
+        return ArchiveName::Other;
+    };
+    if file_name.starts_with(INCREMENTAL_SNAPSHOT_ARCHIVE_PREFIX) {
+        IncrementalSnapshotArchiveInfo::new_from_path(path.to_path_buf())
+            .map_or(ArchiveName::Malformed, ArchiveName::Incremental)
+    } else if file_name.starts_with(FULL_SNAPSHOT_ARCHIVE_PREFIX) {
+        FullSnapshotArchiveInfo::new_from_path(path.to_path_buf())
+            .map_or(ArchiveName::Malformed, ArchiveName::Full)
+    } else {
+        ArchiveName::Other
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveKind {
+    Full,
+    Incremental,
+    /// A malformed name, the kind could not be told
+    Unknown,
+}
+
+impl Display for ArchiveKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Full => write!(f, "full"),
+            Self::Incremental => write!(f, "incremental"),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// A snapshot archive found on disk
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SnapshotArchive {
+    pub path: PathBuf,
+    pub kind: ArchiveKind,
+    pub base_slot: Option<Slot>,
+    pub slot: Option<Slot>,
+    pub hash: Option<String>,
+    pub compression: Option<String>,
+    pub size: u64,
+    /// RFC 3339 time of the last modification
+    pub modified: Option<String>,
+    #[serde(flatten)]
+    pub status: ArchiveStatus,
+}
+
+impl SnapshotArchive {
+    /// Describe the file at `path` from its name, `None` if it is not a
+    /// snapshot archive. Malformed names are reported as misnamed.
+    pub fn new(path: &Path, size: u64, modified: Option<SystemTime>) -> Option<Self> {
+        let (kind, base_slot, parsed) = match parse_archive_name(path) {
+            ArchiveName::Full(info) => (
+                ArchiveKind::Full,
+                None,
+                Some((info.slot(), *info.hash(), info.archive_format())),
+            ),
+            ArchiveName::Incremental(info) => (
+                ArchiveKind::Incremental,
+                Some(info.base_slot()),
+                Some((info.slot(), *info.hash(), info.archive_format())),
+            ),
+            ArchiveName::Malformed => (ArchiveKind::Unknown, None, None),
+            ArchiveName::Other => return None,
+        };
+        Some(Self {
+            path: path.to_path_buf(),
+            kind,
+            base_slot,
+            slot: parsed.map(|(slot, _, _)| slot),
+            hash: parsed.map(|(_, hash, _)| hash.0.to_string()),
+            compression: parsed
+                .map(|(_, _, archive_format)| archive_format.extension().to_string()),
+            size,
+            modified: modified.map(|modified| {
+                DateTime::<Utc>::from(modified).to_rfc3339_opts(SecondsFormat::Secs, true)
+            }),
+            status: if parsed.is_some() {
+                ArchiveStatus::Unverified
+            } else {
+                ArchiveStatus::Misnamed {
+                    reason: "the name is not that of a snapshot archive".to_string(),
+                }
+            },
+        })
+    }
+
+    pub fn archive_format(&self) -> Option<ArchiveFormat> {
+        self.compression
+            .as_deref()
+            .and_then(|compression| compression.parse().ok())
+    }
+}
+
+impl Display for SnapshotArchive {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        write!(
+            f,
+            "{:<11} {:>12} {:>12} {:<44} {:<7} {:>15} {:<20} {}",
+            self.kind,
+            or_dash(self.slot.map(|slot| slot.to_string())),
+            or_dash(self.base_slot.map(|slot| slot.to_string())),
+            or_dash(self.hash.clone()),
+            or_dash(self.compression.clone()),
+            self.size,
+            or_dash(self.modified.clone()),
+            self.status,
+        )?;
+        if self.status.is_flagged() {
+            write!(f, "\n  {}", self.path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// A directory entry that could not be read
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UnreadableEntry {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SnapshotHashes {
+    pub archives: Vec<SnapshotArchive>,
+    pub unreadable: Vec<UnreadableEntry>,
+}
+
+impl SnapshotHashes {
+    /// The archives are ordered full first, then by slot
+    pub fn new(mut archives: Vec<SnapshotArchive>, unreadable: Vec<UnreadableEntry>) -> Self {
+        archives.sort_by(|a, b| {
+            (a.kind, a.slot, a.base_slot, &a.path).cmp(&(b.kind, b.slot, b.base_slot, &b.path))
+        });
+        Self {
+            archives,
+            unreadable,
+        }
+    }
+
+    pub fn num_flagged(&self) -> usize {
+        self.archives
+            .iter()
+            .filter(|archive| archive.status.is_flagged())
+            .count()
+    }
+}
+
+impl Display for SnapshotHashes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.archives.is_empty() {
+            writeln!(f, "No snapshot archives found")?;
+        } else {
+            writeln!(
+                f,
+                "{:<11} {:>12} {:>12} {:<44} {:<7} {:>15} {:<20} Status",
+                "Kind", "Slot", "Base Slot", "Hash", "Format", "Size", "Modified",
+            )?;
+            for archive in &self.archives {
+                writeln!(f, "{archive}")?;
+            }
+        }
+        for entry in &self.unreadable {
+            writeln!(
+                f,
+                "Warning: skipped {}: {}",
+                entry.path.display(),
+                entry.error
+            )?;
+        }
+        Ok(())
+    }
+}
+impl solana_cli_output::VerboseDisplay for SnapshotHashes {}
+impl solana_cli_output::QuietDisplay for SnapshotHashes {}
+
+/// Collect the snapshot archives of `dir`, recording the entries that cannot
+/// be read instead of failing on them
+fn scan_dir(
+    dir: &Path,
+    verify: bool,
+    archives: &mut Vec<SnapshotArchive>,
+    unreadable: &mut Vec<UnreadableEntry>,
+) {
+    let mut skip = |path: &Path, error: &dyn Display| {
+        unreadable.push(UnreadableEntry {
+            path: path.to_path_buf(),
+            error: error.to_string(),
+        })
+    };
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => return skip(dir, &err),
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                skip(dir, &err);
+                continue;
+            }
+        };
+        let path = entry.path();
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                skip(&path, &err);
+                continue;
+            }
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let Some(mut archive) =
+            SnapshotArchive::new(&path, metadata.len(), metadata.modified().ok())
+        else {
+            continue;
+        };
+        if verify {
+            if let Some(archive_format) = archive.archive_format() {
+                archive.status = verify_archive(&path, archive_format);
+            }
+        }
+        archives.push(archive);
+    }
+}
+
+/// Collect the snapshot archives of the full and incremental archives
+/// directories, and of their directories of downloaded archives
+pub fn scan_snapshot_archives(
+    full_snapshot_archives_dir: &Path,
+    incremental_snapshot_archives_dir: &Path,
+    verify: bool,
+) -> SnapshotHashes {
+    let mut dirs = vec![full_snapshot_archives_dir.to_path_buf()];
+    if incremental_snapshot_archives_dir != full_snapshot_archives_dir {
+        dirs.push(incremental_snapshot_archives_dir.to_path_buf());
+    }
+    let remote_dirs: Vec<_> = dirs
+        .iter()
+        .map(build_snapshot_archives_remote_dir)
+        .filter(|remote_dir| remote_dir.exists())
+        .collect();
+
+    let mut archives = vec![];
+    let mut unreadable = vec![];
+    for dir in dirs.iter().chain(&remote_dirs) {
+        scan_dir(dir, verify, &mut archives, &mut unreadable);
+    }
+    SnapshotHashes::new(archives, unreadable)
+}
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name(COMMAND)
+        .about("List the snapshot archives on disk")
+        .after_help(
+            "Note: the archives directories default to the ledger directory, as for the \
+             validator",
+        )
+        .arg(
+            Arg::with_name("snapshots")
+                .long("snapshots")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Directory of the snapshot archives, used for both kinds unless set apart"),
+        )
+        .arg(
+            Arg::with_name("full_snapshot_archive_path")
+                .long("full-snapshot-archive-path")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Directory of the full snapshot archives"),
+        )
+        .arg(
+            Arg::with_name("incremental_snapshot_archive_path")
+                .long("incremental-snapshot-archive-path")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Directory of the incremental snapshot archives"),
+        )
+        .arg(
+            Arg::with_name("verify")
+                .long("verify")
+                .takes_value(false)
+                .help(
+                    "Check the header and trailer of each archive against its name, flagging \
+                     the corrupt and misnamed archives. Only both ends of each archive are read, \
+                     so the trailers of the .tar.gz and .tar.zst archives, which can only be \
+                     checked by decompressing them, are not checked",
+                ),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["json", "display"])
+                .help("Output display mode"),
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let snapshot_hashes_args = SnapshotHashesArgs::from_clap_arg_match(matches)?;
+    let full_snapshot_archives_dir = snapshot_hashes_args
+        .full_snapshot_archives_dir
+        .as_deref()
+        .unwrap_or(ledger_path);
+    let incremental_snapshot_archives_dir = snapshot_hashes_args
+        .incremental_snapshot_archives_dir
+        .as_deref()
+        .unwrap_or(ledger_path);
+
+    let snapshot_hashes = scan_snapshot_archives(
+        full_snapshot_archives_dir,
+        incremental_snapshot_archives_dir,
+        snapshot_hashes_args.verify,
+    );
+    println!(
+        "{}",
+        snapshot_hashes_args
+            .output
+            .formatted_string(&snapshot_hashes)
+    );
+
+    match snapshot_hashes.num_flagged() {
+        0 => Ok(()),
+        num_flagged => Err(format!("{num_flagged} snapshot archives are flagged")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+        std::{fs::File, io::Write},
+    };
+
+    const HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+
+    fn archive(path: &str) -> Option<SnapshotArchive> {
+        SnapshotArchive::new(Path::new(path), 100, None)
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_snapshot_hashes_default() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![COMMAND],
+            SnapshotHashesArgs {
+                full_snapshot_archives_dir: None,
+                incremental_snapshot_archives_dir: None,
+                verify: false,
+                output: OutputFormat::Display,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_snapshot_hashes_with_args() {
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![
+                COMMAND,
+                "--snapshots",
+                "/snapshots",
+                "--verify",
+                "--output",
+                "json",
+            ],
+            SnapshotHashesArgs {
+                full_snapshot_archives_dir: Some(PathBuf::from("/snapshots")),
+                incremental_snapshot_archives_dir: Some(PathBuf::from("/snapshots")),
+                verify: true,
+                output: OutputFormat::Json,
+            },
+        );
+        verify_args_struct_by_command(
+            command(&DefaultArgs::default()),
+            vec![
+                COMMAND,
+                "--snapshots",
+                "/snapshots",
+                "--incremental-snapshot-archive-path",
+                "/incremental",
+            ],
+            SnapshotHashesArgs {
+                full_snapshot_archives_dir: Some(PathBuf::from("/snapshots")),
+                incremental_snapshot_archives_dir: Some(PathBuf::from("/incremental")),
+                verify: false,
+                output: OutputFormat::Display,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_snapshot_hashes_invalid_output() {
+        verify_args_struct_by_command_is_error::<SnapshotHashesArgs>(
+            command(&DefaultArgs::default()),
+            vec![COMMAND, "--output", "yaml"],
+        );
+    }
+
+    #[test]
+    fn test_parse_archive_name() {
+        let full = format!("/ledger/snapshot-100-{HASH}.tar.zst");
+        match parse_archive_name(Path::new(&full)) {
+            ArchiveName::Full(info) => {
+                assert_eq!(info.slot(), 100);
+                assert_eq!(info.hash().0.to_string(), HASH);
+                assert_eq!(info.archive_format().extension(), "tar.zst");
+            }
+            name => panic!("unexpected {name:?}"),
+        }
+        let incremental = format!("/ledger/remote/incremental-snapshot-100-150-{HASH}.tar.lz4");
+        match parse_archive_name(Path::new(&incremental)) {
+            ArchiveName::Incremental(info) => {
+                assert_eq!(info.base_slot(), 100);
+                assert_eq!(info.slot(), 150);
+                assert_eq!(info.archive_format().extension(), "tar.lz4");
+            }
+            name => panic!("unexpected {name:?}"),
+        }
+
+        for malformed in [
+            format!("snapshot-100-{HASH}.tar.xz"),
+            format!("snapshot-{HASH}.tar.zst"),
+            "snapshot-100-not-a-hash.tar.zst".to_string(),
+            format!("snapshot-100-{HASH}x.tar.zst"),
+            format!("snapshot-18446744073709551616-{HASH}.tar.zst"),
+            format!("incremental-snapshot-150-{HASH}.tar.zst"),
+            format!("incremental-snapshot-100-150-{HASH}.tar.zst.partial"),
+        ] {
+            assert_eq!(
+                parse_archive_name(Path::new(&malformed)),
+                ArchiveName::Malformed,
+                "{malformed}"
+            );
+        }
+        for other in [
+            "genesis.tar.bz2",
+            "rocksdb",
+            "tmp-snapshot-archive-100.tar.zst",
+            "snapshots",
+        ] {
+            assert_eq!(
+                parse_archive_name(Path::new(other)),
+                ArchiveName::Other,
+                "{other}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_snapshot_archive() {
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        assert_eq!(
+            SnapshotArchive::new(
+                Path::new(&format!(
+                    "/ledger/incremental-snapshot-100-150-{HASH}.tar.zst"
+                )),
+                1_000,
+                Some(modified),
+            )
+            .unwrap(),
+            SnapshotArchive {
+                path: PathBuf::from(format!(
+                    "/ledger/incremental-snapshot-100-150-{HASH}.tar.zst"
+                )),
+                kind: ArchiveKind::Incremental,
+                base_slot: Some(100),
+                slot: Some(150),
+                hash: Some(HASH.to_string()),
+                compression: Some("tar.zst".to_string()),
+                size: 1_000,
+                modified: Some("2023-11-14T22:13:20Z".to_string()),
+                status: ArchiveStatus::Unverified,
+            }
+        );
+
+        let malformed = archive("/ledger/snapshot-100.tar.zst").unwrap();
+        assert_eq!(malformed.kind, ArchiveKind::Unknown);
+        assert_eq!(malformed.slot, None);
+        assert_eq!(malformed.archive_format(), None);
+        assert!(malformed.status.is_flagged());
+        assert_eq!(archive("/ledger/genesis.tar.bz2"), None);
+
+        let json = serde_json::to_value(&malformed).unwrap();
+        assert_eq!(json["status"], "misnamed");
+        assert_eq!(json["kind"], "unknown");
+    }
+
+    #[test]
+    fn test_snapshot_hashes_order() {
+        let snapshot_hashes = SnapshotHashes::new(
+            vec![
+                archive(&format!("incremental-snapshot-200-250-{HASH}.tar.zst")).unwrap(),
+                archive("snapshot-bad.tar.zst").unwrap(),
+                archive(&format!("incremental-snapshot-100-150-{HASH}.tar.zst")).unwrap(),
+                archive(&format!("snapshot-200-{HASH}.tar.zst")).unwrap(),
+                archive(&format!("snapshot-100-{HASH}.tar.zst")).unwrap(),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            snapshot_hashes
+                .archives
+                .iter()
+                .map(|archive| (archive.kind, archive.slot))
+                .collect::<Vec<_>>(),
+            vec![
+                (ArchiveKind::Full, Some(100)),
+                (ArchiveKind::Full, Some(200)),
+                (ArchiveKind::Incremental, Some(150)),
+                (ArchiveKind::Incremental, Some(250)),
+                (ArchiveKind::Unknown, None),
+            ]
+        );
+        assert_eq!(snapshot_hashes.num_flagged(), 1);
+    }
+
+    #[test]
+    fn test_scan_snapshot_archives() {
+        let ledger_dir = tempfile::tempdir().unwrap();
+        let remote_dir = build_snapshot_archives_remote_dir(ledger_dir.path());
+        fs::create_dir_all(&remote_dir).unwrap();
+        let write = |path: PathBuf, content: &[u8]| {
+            File::create(path).unwrap().write_all(content).unwrap();
+        };
+        write(
+            ledger_dir
+                .path()
+                .join(format!("snapshot-100-{HASH}.tar.zst")),
+            &[0x28, 0xb5, 0x2f, 0xfd, 0x04, 0x58, 0x00],
+        );
+        // lz4 content named as zstd
+        write(
+            remote_dir.join(format!("incremental-snapshot-100-150-{HASH}.tar.zst")),
+            &[0x04, 0x22, 0x4d, 0x18, 0x60, 0x40, 0x82, 0, 0, 0, 0],
+        );
+        write(ledger_dir.path().join("genesis.bin"), b"genesis");
+        // a directory named like an archive is not one
+        fs::create_dir(
+            ledger_dir
+                .path()
+                .join(format!("snapshot-50-{HASH}.tar.zst")),
+        )
+        .unwrap();
+
+        let snapshot_hashes = scan_snapshot_archives(ledger_dir.path(), ledger_dir.path(), false);
+        assert_eq!(snapshot_hashes.archives.len(), 2);
+        assert_eq!(snapshot_hashes.num_flagged(), 0);
+        assert!(snapshot_hashes.unreadable.is_empty());
+
+        let snapshot_hashes = scan_snapshot_archives(ledger_dir.path(), ledger_dir.path(), true);
+        assert_eq!(snapshot_hashes.archives[0].status, ArchiveStatus::Intact);
+        assert!(matches!(
+            snapshot_hashes.archives[1].status,
+            ArchiveStatus::Misnamed { .. }
+        ));
+        assert_eq!(snapshot_hashes.num_flagged(), 1);
+
+        // a missing incremental archives directory is reported, not fatal
+        let missing_dir = ledger_dir.path().join("missing");
+        let snapshot_hashes = scan_snapshot_archives(ledger_dir.path(), &missing_dir, false);
+        assert_eq!(snapshot_hashes.archives.len(), 2);
+        assert_eq!(snapshot_hashes.unreadable.len(), 1);
+        assert_eq!(snapshot_hashes.unreadable[0].path, missing_dir);
+    }
+}
@@ -0,0 +1,426 @@
+use {
+    serde::Serialize,
+    solana_runtime::snapshot_utils::{
+        ArchiveFormat, TAR_BZIP2_EXTENSION, TAR_EXTENSION, TAR_GZIP_EXTENSION, TAR_LZ4_EXTENSION,
+        TAR_ZSTD_EXTENSION,
+    },
+    std::{
+        fmt,
+        fs::File,
+        io::{self, Read, Seek, SeekFrom},
+        path::Path,
+    },
+};
+
+/// Bytes read from each end of an archive, so that checking one does not
+/// depend on its size
+pub const CHECK_LEN: u64 = 1024;
+
+const TAR_BLOCK_SIZE: usize = 512;
+const TAR_MAGIC_OFFSET: usize = 257;
+const TAR_CHECKSUM_RANGE: std::ops::Range<usize> = 148..156;
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const LZ4_MAGIC: &[u8] = &[0x04, 0x22, 0x4d, 0x18];
+const LZ4_CONTENT_CHECKSUM_FLAG: u8 = 0x04;
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+// a gzip member is at least its 10 byte header and 8 byte trailer
+const GZIP_MIN_LEN: u64 = 18;
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const BZIP2_END_OF_STREAM_MAGIC: u128 = 0x1772_4538_5090;
+// the 48 bit end-of-stream magic, then the 32 bit CRC of the stream
+const BZIP2_END_OF_STREAM_BITS: usize = 80;
+
+/// Integrity of a snapshot archive
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ArchiveStatus {
+    /// Not checked, `--verify` was not set
+    Unverified,
+    /// The header of the archive matches its name, and so does its trailer
+    /// for the formats whose end can be checked
+    Intact,
+    /// The archive is damaged or truncated
+    Corrupt { reason: String },
+    /// The name does not describe the archive
+    Misnamed { reason: String },
+    /// The archive could not be read
+    Unreadable { error: String },
+}
+
+impl ArchiveStatus {
+    /// Whether the archive should not be trusted
+    pub fn is_flagged(&self) -> bool {
+        matches!(
+            self,
+            Self::Corrupt { .. } | Self::Misnamed { .. } | Self::Unreadable { .. }
+        )
+    }
+}
+
+impl fmt::Display for ArchiveStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unverified => write!(f, "unverified"),
+            Self::Intact => write!(f, "intact"),
+            Self::Corrupt { reason } => write!(f, "CORRUPT: {reason}"),
+            Self::Misnamed { reason } => write!(f, "MISNAMED: {reason}"),
+            Self::Unreadable { error } => write!(f, "UNREADABLE: {error}"),
+        }
+    }
+}
+
+/// Extension of the format the leading bytes of an archive are in
+pub fn detect_format(header: &[u8]) -> Option<&'static str> {
+    if header.starts_with(ZSTD_MAGIC) {
+        Some(TAR_ZSTD_EXTENSION)
+    } else if header.starts_with(LZ4_MAGIC) {
+        Some(TAR_LZ4_EXTENSION)
+    } else if header.starts_with(GZIP_MAGIC) {
+        Some(TAR_GZIP_EXTENSION)
+    } else if header.starts_with(BZIP2_MAGIC)
+        && header
+            .get(BZIP2_MAGIC.len())
+            .is_some_and(|block_size| (b'1'..=b'9').contains(block_size))
+    {
+        Some(TAR_BZIP2_EXTENSION)
+    } else if header.get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 5) == Some(&b"ustar"[..]) {
+        Some(TAR_EXTENSION)
+    } else {
+        None
+    }
+}
+
+/// Whether the checksum of a tar header block matches its content, the
+/// checksum field itself counting as spaces
+fn is_tar_checksum_valid(block: &[u8]) -> bool {
+    let Some(block) = block.get(..TAR_BLOCK_SIZE) else {
+        return false;
+    };
+    let Some(expected) = std::str::from_utf8(&block[TAR_CHECKSUM_RANGE])
+        .ok()
+        .map(|field| field.trim_matches(|c: char| c == '\0' || c == ' '))
+        .and_then(|field| u32::from_str_radix(field, 8).ok())
+    else {
+        return false;
+    };
+    let actual: u32 = block
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| {
+            if TAR_CHECKSUM_RANGE.contains(&i) {
+                u32::from(b' ')
+            } else {
+                u32::from(*byte)
+            }
+        })
+        .sum();
+    actual == expected
+}
+
+/// Whether `trailer` ends with a bzip2 end-of-stream marker. The marker is
+/// not byte aligned: it is followed by the CRC of the stream, then by up to 7
+/// bits of zero padding.
+fn has_bzip2_end_of_stream(trailer: &[u8]) -> bool {
+    let len = trailer.len().min(BZIP2_END_OF_STREAM_BITS / 8 + 1);
+    let bits = trailer[trailer.len() - len..]
+        .iter()
+        .fold(0u128, |bits, byte| (bits << 8) | u128::from(*byte));
+    (0..8).any(|padding| {
+        len * 8 >= BZIP2_END_OF_STREAM_BITS + padding
+            && bits & ((1 << padding) - 1) == 0
+            && (bits >> (32 + padding)) & ((1 << 48) - 1) == BZIP2_END_OF_STREAM_MAGIC
+    })
+}
+
+/// Check an archive of `size` bytes named as `archive_format` from its first
+/// and last `CHECK_LEN` bytes
+pub fn check_archive(
+    archive_format: ArchiveFormat,
+    size: u64,
+    header: &[u8],
+    trailer: &[u8],
+) -> ArchiveStatus {
+    let corrupt = |reason: &str| ArchiveStatus::Corrupt {
+        reason: reason.to_string(),
+    };
+    if size == 0 {
+        return corrupt("the file is empty");
+    }
+    let Some(detected) = detect_format(header) else {
+        return corrupt("the header is not of any snapshot archive format");
+    };
+    if detected != archive_format.extension() {
+        return ArchiveStatus::Misnamed {
+            reason: format!(
+                "named .{} but the content is .{detected}",
+                archive_format.extension()
+            ),
+        };
+    }
+
+    match archive_format {
+        ArchiveFormat::Tar => {
+            if size % TAR_BLOCK_SIZE as u64 != 0 {
+                corrupt("the size is not a multiple of the tar block size")
+            } else if !is_tar_checksum_valid(header) {
+                corrupt("the checksum of the first tar header does not match")
+            } else if trailer.len() < 2 * TAR_BLOCK_SIZE
+                || trailer[trailer.len() - 2 * TAR_BLOCK_SIZE..]
+                    .iter()
+                    .any(|byte| *byte != 0)
+            {
+                corrupt("the end-of-archive blocks are missing, the archive is likely truncated")
+            } else {
+                ArchiveStatus::Intact
+            }
+        }
+        ArchiveFormat::TarLz4 => {
+            // the last frame ends with a zero end mark, followed by the
+            // checksum of the content if the frame descriptor has its flag
+            let end_mark_len = match header.get(LZ4_MAGIC.len()) {
+                Some(flags) if flags & LZ4_CONTENT_CHECKSUM_FLAG != 0 => 8,
+                Some(_) => 4,
+                None => return corrupt("the lz4 frame descriptor is missing"),
+            };
+            let end_mark = trailer
+                .len()
+                .checked_sub(end_mark_len)
+                .map(|start| &trailer[start..start + 4]);
+            if end_mark == Some(&[0; 4][..]) {
+                ArchiveStatus::Intact
+            } else {
+                corrupt("the lz4 end mark is missing, the archive is likely truncated")
+            }
+        }
+        ArchiveFormat::TarGzip if size < GZIP_MIN_LEN => {
+            corrupt("the file is shorter than a gzip header and trailer")
+        }
+        ArchiveFormat::TarBzip2 => {
+            if has_bzip2_end_of_stream(trailer) {
+                ArchiveStatus::Intact
+            } else {
+                corrupt(
+                    "the bzip2 end-of-stream marker is missing, the archive is likely truncated",
+                )
+            }
+        }
+        // the gzip trailer is a checksum and the size of the content, and the
+        // last zstd block is only found by walking the frame, so neither can
+        // be checked without decompressing the archive
+        ArchiveFormat::TarGzip | ArchiveFormat::TarZstd { .. } => ArchiveStatus::Intact,
+    }
+}
+
+/// Read both ends of the archive at `path` and check them
+pub fn verify_archive(path: &Path, archive_format: ArchiveFormat) -> ArchiveStatus {
+    let read_ends = || -> io::Result<(u64, Vec<u8>, Vec<u8>)> {
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+        let mut header = vec![];
+        (&mut file).take(CHECK_LEN).read_to_end(&mut header)?;
+        let mut trailer = vec![];
+        file.seek(SeekFrom::Start(size.saturating_sub(CHECK_LEN)))?;
+        file.take(CHECK_LEN).read_to_end(&mut trailer)?;
+        Ok((size, header, trailer))
+    };
+    match read_ends() {
+        Ok((size, header, trailer)) => check_archive(archive_format, size, &header, &trailer),
+        Err(err) => ArchiveStatus::Unreadable {
+            error: err.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_runtime::snapshot_utils::ZstdConfig, std::io::Write};
+
+    fn tar_header(name: &str) -> Vec<u8> {
+        let mut block = vec![0; TAR_BLOCK_SIZE];
+        block[..name.len()].copy_from_slice(name.as_bytes());
+        block[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 6].copy_from_slice(b"ustar\0");
+        block[TAR_CHECKSUM_RANGE].copy_from_slice(b"        ");
+        let checksum: u32 = block.iter().map(|byte| u32::from(*byte)).sum();
+        block[TAR_CHECKSUM_RANGE].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+        block
+    }
+
+    fn tar_archive() -> Vec<u8> {
+        let mut archive = tar_header("version");
+        archive.extend_from_slice(&[b'1'; TAR_BLOCK_SIZE]);
+        archive.extend_from_slice(&[0; 2 * TAR_BLOCK_SIZE]);
+        archive
+    }
+
+    fn check(archive_format: ArchiveFormat, archive: &[u8]) -> ArchiveStatus {
+        let len = archive.len();
+        check_archive(
+            archive_format,
+            len as u64,
+            &archive[..len.min(CHECK_LEN as usize)],
+            &archive[len.saturating_sub(CHECK_LEN as usize)..],
+        )
+    }
+
+    fn assert_corrupt(status: ArchiveStatus, expected: &str) {
+        match status {
+            ArchiveStatus::Corrupt { reason } => assert!(reason.contains(expected), "{reason}"),
+            status => panic!("expected a corrupt archive, got {status:?}"),
+        }
+    }
+
+    const ZSTD: ArchiveFormat = ArchiveFormat::TarZstd {
+        config: ZstdConfig {
+            compression_level: 0,
+        },
+    };
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            detect_format(&[0x28, 0xb5, 0x2f, 0xfd, 0x04]),
+            Some(TAR_ZSTD_EXTENSION)
+        );
+        assert_eq!(
+            detect_format(&[0x04, 0x22, 0x4d, 0x18]),
+            Some(TAR_LZ4_EXTENSION)
+        );
+        assert_eq!(detect_format(&[0x1f, 0x8b, 0x08]), Some(TAR_GZIP_EXTENSION));
+        assert_eq!(detect_format(b"BZh9"), Some(TAR_BZIP2_EXTENSION));
+        assert_eq!(detect_format(&tar_archive()), Some(TAR_EXTENSION));
+
+        assert_eq!(detect_format(b"BZh0"), None);
+        assert_eq!(detect_format(b"BZh"), None);
+        assert_eq!(detect_format(&[]), None);
+        assert_eq!(detect_format(&[0; 1024]), None);
+    }
+
+    #[test]
+    fn test_check_tar() {
+        let archive = tar_archive();
+        assert_eq!(check(ArchiveFormat::Tar, &archive), ArchiveStatus::Intact);
+
+        let mut tampered = archive.clone();
+        tampered[0] = b'V';
+        assert_corrupt(check(ArchiveFormat::Tar, &tampered), "checksum");
+
+        assert_corrupt(
+            check(ArchiveFormat::Tar, &archive[..archive.len() - 1]),
+            "multiple of the tar block size",
+        );
+        assert_corrupt(
+            check(
+                ArchiveFormat::Tar,
+                &archive[..archive.len() - TAR_BLOCK_SIZE],
+            ),
+            "truncated",
+        );
+    }
+
+    #[test]
+    fn test_check_lz4() {
+        let mut archive = LZ4_MAGIC.to_vec();
+        archive.extend_from_slice(&[0x60, 0x40, 0x82]);
+        archive.extend_from_slice(&[0x55; 100]);
+        let mut complete = archive.clone();
+        complete.extend_from_slice(&[0; 4]);
+        assert_eq!(
+            check(ArchiveFormat::TarLz4, &complete),
+            ArchiveStatus::Intact
+        );
+        assert_corrupt(check(ArchiveFormat::TarLz4, &archive), "end mark");
+
+        // with a content checksum, which follows the end mark
+        archive[LZ4_MAGIC.len()] |= LZ4_CONTENT_CHECKSUM_FLAG;
+        let mut complete = archive.clone();
+        complete.extend_from_slice(&[0, 0, 0, 0, 0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(
+            check(ArchiveFormat::TarLz4, &complete),
+            ArchiveStatus::Intact
+        );
+        complete.truncate(complete.len() - 4);
+        assert_corrupt(check(ArchiveFormat::TarLz4, &complete), "end mark");
+        assert_corrupt(check(ArchiveFormat::TarLz4, LZ4_MAGIC), "frame descriptor");
+    }
+
+    #[test]
+    fn test_check_misnamed_and_corrupt() {
+        let zstd_archive = [0x28, 0xb5, 0x2f, 0xfd, 0x04, 0x58, 0x00];
+        assert_eq!(check(ZSTD, &zstd_archive), ArchiveStatus::Intact);
+        assert_eq!(
+            check(ArchiveFormat::TarLz4, &zstd_archive),
+            ArchiveStatus::Misnamed {
+                reason: "named .tar.lz4 but the content is .tar.zst".to_string()
+            }
+        );
+        assert_eq!(
+            check(ZSTD, &tar_archive()),
+            ArchiveStatus::Misnamed {
+                reason: "named .tar.zst but the content is .tar".to_string()
+            }
+        );
+
+        assert_corrupt(check(ZSTD, &[]), "empty");
+        assert_corrupt(check(ZSTD, b"<html>404</html>"), "not of any");
+        assert_corrupt(check(ArchiveFormat::TarGzip, &[0x1f, 0x8b, 0x08]), "gzip");
+        assert_eq!(
+            check(
+                ArchiveFormat::TarGzip,
+                &[0x1f, 0x8b, 0x08, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+            ),
+            ArchiveStatus::Intact
+        );
+    }
+
+    #[test]
+    fn test_check_bzip2() {
+        // whatever the padding, the marker and the CRC end the archive
+        for padding in 0..8 {
+            let end =
+                ((1 << BZIP2_END_OF_STREAM_BITS) | (BZIP2_END_OF_STREAM_MAGIC << 32) | 0xdead_beef)
+                    << padding;
+            let mut archive = b"BZh91AY&SY".to_vec();
+            archive.extend_from_slice(&u128::to_be_bytes(end)[5..]);
+            assert_eq!(
+                check(ArchiveFormat::TarBzip2, &archive),
+                ArchiveStatus::Intact,
+                "{padding}"
+            );
+            assert_corrupt(
+                check(ArchiveFormat::TarBzip2, &archive[..archive.len() - 1]),
+                "end-of-stream",
+            );
+        }
+
+        assert_corrupt(
+            check(ArchiveFormat::TarBzip2, b"BZh91AY&SY"),
+            "end-of-stream",
+        );
+    }
+
+    #[test]
+    fn test_verify_archive() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("snapshot.tar");
+        // bigger than twice CHECK_LEN, so that the ends are read apart
+        let mut archive = tar_header("version");
+        archive.extend_from_slice(&[b'1'; 8 * TAR_BLOCK_SIZE]);
+        archive.extend_from_slice(&[0; 2 * TAR_BLOCK_SIZE]);
+        File::create(&path).unwrap().write_all(&archive).unwrap();
+        assert_eq!(
+            verify_archive(&path, ArchiveFormat::Tar),
+            ArchiveStatus::Intact
+        );
+
+        File::create(&path)
+            .unwrap()
+            .write_all(&archive[..archive.len() - TAR_BLOCK_SIZE])
+            .unwrap();
+        assert_corrupt(verify_archive(&path, ArchiveFormat::Tar), "truncated");
+
+        assert!(matches!(
+            verify_archive(&temp_dir.path().join("missing.tar"), ArchiveFormat::Tar),
+            ArchiveStatus::Unreadable { .. }
+        ));
+    }
+}
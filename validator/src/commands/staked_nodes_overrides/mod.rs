@@ -1,12 +1,46 @@
 use {
-    crate::{admin_rpc_service, cli::DefaultArgs},
-    clap::{App, Arg, ArgMatches, SubCommand},
-    std::path::Path,
+    crate::{
+        admin_rpc_service::{self, load_staked_nodes_overrides, StakedNodesOverrides},
+        cli::DefaultArgs,
+        commands::FromClapArgMatches,
+    },
+    clap::{value_t, App, AppSettings, Arg, ArgMatches, SubCommand},
+    solana_clap_utils::input_validators::is_parsable,
+    std::{fs, path::Path},
 };
 
+const COMMAND: &str = "staked-nodes-overrides";
+const EXPORT_COMMAND: &str = "export";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Yaml,
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExportArgs {
+    pub output: Option<String>,
+    pub format: ExportFormat,
+}
+
+impl FromClapArgMatches for ExportArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self, String> {
+        let format = match matches.value_of("format") {
+            Some("json") => ExportFormat::Json,
+            _ => ExportFormat::Yaml,
+        };
+        Ok(ExportArgs {
+            output: matches.value_of("output").map(str::to_string),
+            format,
+        })
+    }
+}
+
 pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
-    SubCommand::with_name("staked-nodes-overrides")
+    SubCommand::with_name(COMMAND)
         .about("Overrides stakes of specific node identities.")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("path")
                 .value_name("PATH")
@@ -16,13 +50,51 @@ pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
                     "Provide path to a file with custom overrides for stakes of specific validator identities.",
                 ),
         )
+        .arg(
+            Arg::with_name("max_override_stake")
+                .long("max-override-stake")
+                .value_name("LAMPORTS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Reject the file if any override stake exceeds this many lamports"),
+        )
         .after_help(
             "Note: the new staked nodes overrides only applies to the currently running validator instance",
         )
+        .subcommand(
+            SubCommand::with_name(EXPORT_COMMAND)
+                .about("Display the staked nodes overrides currently applied by the validator")
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Write the overrides to this file instead of printing them"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .takes_value(true)
+                        .possible_values(&["yaml", "json"])
+                        .default_value("yaml")
+                        .help("Format of the exported overrides"),
+                )
+                .after_help(
+                    "Note: the exported overrides can be applied again with `staked-nodes-overrides PATH`",
+                ),
+        )
 }
 
 pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    if let (EXPORT_COMMAND, Some(subcommand_matches)) = matches.subcommand() {
+        return export(subcommand_matches, ledger_path);
+    }
+
     let path = matches.value_of("path").expect("path is required");
+    if let Ok(max_override_stake) = value_t!(matches, "max_override_stake", u64) {
+        check_max_override_stake(path, max_override_stake)?;
+    }
 
     let admin_client = admin_rpc_service::connect(ledger_path);
     admin_rpc_service::runtime()
@@ -34,3 +106,145 @@ pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
         })
         .map_err(|err| format!("set staked nodes override request failed: {err}"))
 }
+
+/// Load the overrides at `path` and check that none exceeds
+/// `max_override_stake`
+fn check_max_override_stake(
+    path: &str,
+    max_override_stake: u64,
+) -> Result<StakedNodesOverrides, String> {
+    let overrides = load_staked_nodes_overrides(&path.to_string())
+        .map_err(|err| format!("failed to load staked nodes overrides from {path}: {err}"))?;
+    if let Some((pubkey, stake)) = overrides
+        .staked_map_id
+        .iter()
+        .find(|(_, stake)| **stake > max_override_stake)
+    {
+        return Err(format!(
+            "staked nodes override of {stake} for {pubkey} exceeds --max-override-stake \
+             {max_override_stake}"
+        ));
+    }
+    Ok(overrides)
+}
+
+fn export(matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    let export_args = ExportArgs::from_clap_arg_match(matches)?;
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    let staked_map_id = admin_rpc_service::runtime()
+        .block_on(async move {
+            admin_rpc_service::get_staked_nodes_overrides(&admin_client.await?).await
+        })
+        .map_err(|err| format!("get staked nodes overrides request failed: {err}"))?;
+    // exported in the file format, so that it can be applied again
+    let staked_nodes_overrides = StakedNodesOverrides { staked_map_id };
+
+    let exported = match export_args.format {
+        ExportFormat::Json => serde_json::to_string_pretty(&staked_nodes_overrides)
+            .map_err(|err| format!("failed to serialize staked nodes overrides: {err}"))?,
+        ExportFormat::Yaml => serde_yaml::to_string(&staked_nodes_overrides)
+            .map_err(|err| format!("failed to serialize staked nodes overrides: {err}"))?,
+    };
+
+    match export_args.output {
+        Some(output) => fs::write(&output, exported)
+            .map_err(|err| format!("failed to write staked nodes overrides to {output}: {err}")),
+        None => {
+            println!("{exported}");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::admin_rpc_service::MAX_STAKED_NODES_OVERRIDE_STAKE,
+        solana_sdk::pubkey::Pubkey, tempfile::TempDir,
+    };
+
+    fn export_args(args: &[&str]) -> Result<ExportArgs, String> {
+        let matches = command(&DefaultArgs::default())
+            .get_matches_from_safe([COMMAND, EXPORT_COMMAND].iter().chain(args))
+            .map_err(|err| err.to_string())?;
+        ExportArgs::from_clap_arg_match(matches.subcommand_matches(EXPORT_COMMAND).unwrap())
+    }
+
+    #[test]
+    fn test_export_args() {
+        assert_eq!(
+            export_args(&[]).unwrap(),
+            ExportArgs {
+                output: None,
+                format: ExportFormat::Yaml,
+            }
+        );
+        assert_eq!(
+            export_args(&["--output", "overrides.json", "--format", "json"]).unwrap(),
+            ExportArgs {
+                output: Some("overrides.json".to_string()),
+                format: ExportFormat::Json,
+            }
+        );
+        assert!(export_args(&["--format", "toml"]).is_err());
+        assert!(export_args(&["--output"]).is_err());
+    }
+
+    #[test]
+    fn test_command_requires_path_unless_exporting() {
+        let app = || command(&DefaultArgs::default());
+        assert!(app().get_matches_from_safe([COMMAND]).is_err());
+        let matches = app()
+            .get_matches_from_safe([COMMAND, "overrides.yml"])
+            .unwrap();
+        assert_eq!(matches.value_of("path"), Some("overrides.yml"));
+        assert_eq!(matches.subcommand_name(), None);
+        let matches = app()
+            .get_matches_from_safe([COMMAND, EXPORT_COMMAND])
+            .unwrap();
+        assert_eq!(matches.subcommand_name(), Some(EXPORT_COMMAND));
+    }
+
+    fn write_overrides(dir: &TempDir, stakes: &[&str]) -> String {
+        let path = dir.path().join("overrides.yml");
+        let entries: String = stakes
+            .iter()
+            .map(|stake| format!("  {}: {stake}\n", Pubkey::new_unique()))
+            .collect();
+        fs::write(&path, format!("staked_map_id:\n{entries}")).unwrap();
+        path.display().to_string()
+    }
+
+    #[test]
+    fn test_check_max_override_stake() {
+        let dir = TempDir::new().unwrap();
+
+        let path = write_overrides(&dir, &["1000", "5000"]);
+        let overrides = check_max_override_stake(&path, 5000).unwrap();
+        assert_eq!(overrides.staked_map_id.values().sum::<u64>(), 6000);
+
+        let err = check_max_override_stake(&path, 4999).unwrap_err();
+        assert!(err.contains("exceeds --max-override-stake 4999"), "{err}");
+    }
+
+    #[test]
+    fn test_load_staked_nodes_overrides_rejects_bad_stakes() {
+        let dir = TempDir::new().unwrap();
+
+        let path = write_overrides(&dir, &["-1"]);
+        let err = check_max_override_stake(&path, u64::MAX).unwrap_err();
+        assert!(err.contains("negative stake -1"), "{err}");
+
+        let absurd = (MAX_STAKED_NODES_OVERRIDE_STAKE + 1).to_string();
+        let path = write_overrides(&dir, &[&absurd]);
+        let err = check_max_override_stake(&path, u64::MAX).unwrap_err();
+        assert!(err.contains("exceeds the maximum"), "{err}");
+
+        let path = write_overrides(&dir, &[&u64::MAX.to_string()]);
+        assert!(check_max_override_stake(&path, u64::MAX).is_err());
+
+        let path = write_overrides(&dir, &[&MAX_STAKED_NODES_OVERRIDE_STAKE.to_string()]);
+        assert!(check_max_override_stake(&path, u64::MAX).is_ok());
+    }
+}
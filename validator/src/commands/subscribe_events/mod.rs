@@ -0,0 +1,29 @@
+use {
+    crate::{admin_rpc_events, admin_rpc_service, cli::DefaultArgs},
+    clap::{App, ArgMatches, SubCommand},
+    futures_util::StreamExt,
+    std::path::Path,
+};
+
+pub fn command(_default_args: &DefaultArgs) -> App<'_, '_> {
+    SubCommand::with_name("subscribe-events")
+        .about("Print the events of the validator as newline-delimited JSON, until interrupted")
+        .after_help(
+            "Events are subscribed to over the admin RPC IPC path, with jsonrpc-pubsub, rather \
+             than over a WebSocket endpoint.",
+        )
+}
+
+pub fn execute(_matches: &ArgMatches, ledger_path: &Path) -> Result<(), String> {
+    admin_rpc_service::runtime().block_on(async move {
+        let mut subscription = admin_rpc_events::subscribe_events(ledger_path).await?;
+        while let Some(event) = subscription.next().await {
+            let event = event.map_err(|err| err.to_string())?;
+            println!(
+                "{}",
+                serde_json::to_string(&event).expect("events serialize to JSON")
+            );
+        }
+        Err("the validator closed the connection".to_string())
+    })
+}
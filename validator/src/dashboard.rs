@@ -6,9 +6,15 @@ use {
     console::style,
     solana_core::validator::ValidatorStartProgress,
     solana_rpc_client::rpc_client::RpcClient,
-    solana_rpc_client_api::{client_error, request, response::RpcContactInfo},
+    solana_rpc_client_api::{
+        client_error, config::RpcLeaderScheduleConfig, request, response::RpcContactInfo,
+    },
     solana_sdk::{
-        clock::Slot, commitment_config::CommitmentConfig, exit::Exit, native_token::Sol,
+        clock::{Epoch, Slot},
+        commitment_config::CommitmentConfig,
+        epoch_schedule::EpochSchedule,
+        exit::Exit,
+        native_token::Sol,
         pubkey::Pubkey,
     },
     std::{
@@ -115,6 +121,9 @@ impl Dashboard {
                 }
             }
 
+            let epoch_schedule = rpc_client.get_epoch_schedule().ok();
+            let mut leader_slots: Option<LeaderSlots> = None;
+
             let progress_bar = new_spinner_progress_bar();
             let mut snapshot_slot_info = None;
             for i in 0.. {
@@ -128,6 +137,7 @@ impl Dashboard {
                 let new_identity = rpc_client.get_identity().unwrap_or(identity);
                 if identity != new_identity {
                     identity = new_identity;
+                    leader_slots = None;
                     progress_bar.println(format_name_value("Identity:", &identity.to_string()));
                 }
 
@@ -140,6 +150,22 @@ impl Dashboard {
                         identity_balance,
                         health,
                     )) => {
+                        if let Some(epoch_schedule) = &epoch_schedule {
+                            let epoch = epoch_schedule.get_epoch(processed_slot);
+                            if leader_slots.as_ref().map(|slots| slots.epoch) != Some(epoch) {
+                                leader_slots = get_leader_slots(
+                                    &rpc_client,
+                                    epoch_schedule,
+                                    &identity,
+                                    processed_slot,
+                                )
+                                .ok();
+                            }
+                        }
+                        let is_leader = leader_slots
+                            .as_ref()
+                            .is_some_and(|slots| slots.is_leader(processed_slot));
+
                         let uptime = {
                             let uptime =
                                 chrono::Duration::from_std(start_time.elapsed().unwrap()).unwrap();
@@ -153,10 +179,15 @@ impl Dashboard {
                         };
 
                         progress_bar.set_message(format!(
-                            "{}{}| Processed Slot: {} | Confirmed Slot: {} | Finalized Slot: {} | \
-                             Full Snapshot Slot: {} | Incremental Snapshot Slot: {} | \
+                            "{}{}{}| Processed Slot: {} | Confirmed Slot: {} | Finalized Slot: \
+                             {} | Full Snapshot Slot: {} | Incremental Snapshot Slot: {} | \
                              Transactions: {} | {}",
                             uptime,
+                            if is_leader {
+                                format!("| {} ", style("LEADER").bold().green())
+                            } else {
+                                "".to_string()
+                            },
                             if health == "ok" {
                                 "".to_string()
                             } else {
@@ -254,6 +285,48 @@ fn get_contact_info(rpc_client: &RpcClient, identity: &Pubkey) -> Option<RpcCont
         .find(|node| node.pubkey == identity.to_string())
 }
 
+/// Leader slots of the node in an epoch
+struct LeaderSlots {
+    epoch: Epoch,
+    first_slot: Slot,
+    /// Indexes of the leader slots within the epoch, sorted
+    slot_indexes: Vec<usize>,
+}
+
+impl LeaderSlots {
+    /// Whether the node is leader of `slot`, a slot of `self.epoch`
+    fn is_leader(&self, slot: Slot) -> bool {
+        slot.checked_sub(self.first_slot)
+            .and_then(|index| usize::try_from(index).ok())
+            .is_some_and(|index| self.slot_indexes.binary_search(&index).is_ok())
+    }
+}
+
+fn get_leader_slots(
+    rpc_client: &RpcClient,
+    epoch_schedule: &EpochSchedule,
+    identity: &Pubkey,
+    slot: Slot,
+) -> client_error::Result<LeaderSlots> {
+    let epoch = epoch_schedule.get_epoch(slot);
+    let mut slot_indexes = rpc_client
+        .get_leader_schedule_with_config(
+            Some(slot),
+            RpcLeaderScheduleConfig {
+                identity: Some(identity.to_string()),
+                commitment: Some(CommitmentConfig::processed()),
+            },
+        )?
+        .and_then(|leader_schedule| leader_schedule.into_values().next())
+        .unwrap_or_default();
+    slot_indexes.sort_unstable();
+    Ok(LeaderSlots {
+        epoch,
+        first_slot: epoch_schedule.get_first_slot_in_epoch(epoch),
+        slot_indexes,
+    })
+}
+
 fn get_validator_stats(
     rpc_client: &RpcClient,
     identity: &Pubkey,
@@ -295,3 +368,31 @@ fn get_validator_stats(
         health,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leader_slots_is_leader() {
+        let epoch_schedule = EpochSchedule::custom(32, 32, false);
+        let epoch = 3;
+        let first_slot = epoch_schedule.get_first_slot_in_epoch(epoch);
+        let leader_slots = LeaderSlots {
+            epoch,
+            first_slot,
+            slot_indexes: vec![0, 1, 2, 3, 20, 21, 22, 23],
+        };
+
+        for index in [0, 3, 20, 23] {
+            assert!(leader_slots.is_leader(first_slot + index));
+        }
+        for index in [4, 19, 24, 31] {
+            assert!(!leader_slots.is_leader(first_slot + index));
+        }
+        // the slots of the previous epoch at the same indexes
+        assert!(!leader_slots.is_leader(first_slot - 32));
+        assert!(!leader_slots.is_leader(first_slot - 1));
+        assert_eq!(epoch_schedule.get_epoch(first_slot + 23), epoch);
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(clippy::arithmetic_side_effects)]
 pub use solana_test_validator as test_validator;
 use {
+    crate::log_tail::LogTail,
     console::style,
     fd_lock::{RwLock, RwLockWriteGuard},
     indicatif::{ProgressDrawTarget, ProgressStyle},
@@ -11,16 +12,19 @@ use {
         fs::{File, OpenOptions},
         path::Path,
         process::exit,
+        sync::Arc,
         thread::JoinHandle,
         time::Duration,
     },
 };
 
+pub mod admin_rpc_events;
 pub mod admin_rpc_service;
 pub mod bootstrap;
 pub mod cli;
 pub mod commands;
 pub mod dashboard;
+pub mod log_tail;
 
 #[cfg(unix)]
 fn redirect_stderr(filename: &str) {
@@ -36,7 +40,11 @@ fn redirect_stderr(filename: &str) {
 // Redirect stderr to a file with support for logrotate by sending a SIGUSR1 to the process.
 //
 // Upon success, future `log` macros and `eprintln!()` can be found in the specified log file.
-pub fn redirect_stderr_to_file(logfile: Option<String>) -> Option<JoinHandle<()>> {
+// The lines logged are also copied into `log_tail`, if any.
+pub fn redirect_stderr_to_file(
+    logfile: Option<String>,
+    log_tail: Option<Arc<LogTail>>,
+) -> Option<JoinHandle<()>> {
     // Default to RUST_BACKTRACE=1 for more informative validator logs
     if env::var_os("RUST_BACKTRACE").is_none() {
         env::set_var("RUST_BACKTRACE", "1")
@@ -44,7 +52,7 @@ pub fn redirect_stderr_to_file(logfile: Option<String>) -> Option<JoinHandle<()>
 
     match logfile {
         None => {
-            solana_logger::setup_with_default_filter();
+            log_tail::setup_with_default_filter(log_tail);
             None
         }
         Some(logfile) => {
@@ -58,7 +66,7 @@ pub fn redirect_stderr_to_file(logfile: Option<String>) -> Option<JoinHandle<()>
                             exit(1);
                         });
 
-                solana_logger::setup_with_default_filter();
+                log_tail::setup_with_default_filter(log_tail);
                 redirect_stderr(&logfile);
                 Some(
                     std::thread::Builder::new()
@@ -0,0 +1,379 @@
+//! Recent log lines of the validator, kept in memory for `log-tail`
+//!
+//! When enabled, the validator logs through `env_logger` as usual, to a pipe
+//! that copies every line to standard error and queues it on a bounded
+//! channel. A collector thread drains the channel into a ring buffer served
+//! by the admin RPC. Logging never waits on the ring buffer: lines that do not
+//! fit in the queue are dropped from the tail, not from the log file.
+//!
+//! Each line is numbered. A reader polls with the cursor returned by its
+//! previous read to receive only the lines logged since, and learns how many
+//! it missed to the ring buffer wrapping around or to dropped lines.
+use {
+    crossbeam_channel::{bounded, Receiver, Sender},
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::VecDeque,
+        io::{self, Write},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex, OnceLock, RwLock,
+        },
+        thread::Builder,
+    },
+};
+
+pub const DEFAULT_LOG_TAIL_LINES: usize = 10_000;
+/// Lines queued for the collector thread before new lines are dropped
+const LOG_TAIL_QUEUE_CAPACITY: usize = 4096;
+
+/// Position in the log, returned by a read to continue from
+pub type LogTailCursor = u64;
+
+/// Lines returned by `LogTail::read()`, oldest first
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogTailChunk {
+    pub lines: Vec<String>,
+    /// Cursor to read the lines logged after these
+    pub cursor: LogTailCursor,
+    /// Lines logged after the cursor of the read that are no longer held
+    pub missed: u64,
+}
+
+/// Ring buffer of the last log lines
+pub struct LogTail {
+    lines: Mutex<LogTailLines>,
+    /// Lines dropped by the writer since the last push
+    dropped: AtomicU64,
+}
+
+struct LogTailLines {
+    capacity: usize,
+    lines: VecDeque<(LogTailCursor, String)>,
+    next_cursor: LogTailCursor,
+}
+
+impl LogTail {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Mutex::new(LogTailLines {
+                capacity,
+                lines: VecDeque::with_capacity(capacity),
+                next_cursor: 0,
+            }),
+            dropped: AtomicU64::default(),
+        }
+    }
+
+    /// Append `line`, evicting the oldest line if the buffer is full
+    pub fn push(&self, line: String) {
+        let mut lines = self.lines.lock().unwrap();
+        lines.next_cursor += self.dropped.swap(0, Ordering::Relaxed);
+        if lines.capacity == 0 {
+            lines.next_cursor += 1;
+            return;
+        }
+        if lines.lines.len() == lines.capacity {
+            lines.lines.pop_front();
+        }
+        let cursor = lines.next_cursor;
+        lines.lines.push_back((cursor, line));
+        lines.next_cursor += 1;
+    }
+
+    /// Record a line that was logged but could not be queued
+    fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The last `max_lines` lines without a `cursor`, otherwise the lines
+    /// logged from `cursor` on, at most `max_lines` of them. A cursor ahead
+    /// of the log, such as one from before a restart, reads from the start.
+    pub fn read(&self, max_lines: usize, cursor: Option<LogTailCursor>) -> LogTailChunk {
+        let lines = self.lines.lock().unwrap();
+        let next_cursor = lines.next_cursor;
+        let cursor = match cursor {
+            Some(cursor) if cursor <= next_cursor => cursor,
+            Some(_) => 0,
+            None => {
+                let skip = lines.lines.len().saturating_sub(max_lines);
+                return LogTailChunk {
+                    lines: lines
+                        .lines
+                        .iter()
+                        .skip(skip)
+                        .map(|(_, line)| line.clone())
+                        .collect(),
+                    cursor: next_cursor,
+                    missed: 0,
+                };
+            }
+        };
+
+        let start = lines
+            .lines
+            .partition_point(|(line_cursor, _)| *line_cursor < cursor);
+        let chunk: Vec<_> = lines.lines.range(start..).take(max_lines).collect();
+        let end = if start + chunk.len() < lines.lines.len() {
+            chunk
+                .last()
+                .map_or(cursor, |(line_cursor, _)| *line_cursor + 1)
+        } else {
+            next_cursor
+        };
+        LogTailChunk {
+            missed: end - cursor - chunk.len() as u64,
+            lines: chunk.into_iter().map(|(_, line)| line.clone()).collect(),
+            cursor: end,
+        }
+    }
+}
+
+/// Target of `env_logger`, copying the lines logged to the queue of the
+/// collector thread
+struct LogTailPipe {
+    sender: Sender<String>,
+    log_tail: Arc<LogTail>,
+}
+
+impl Write for LogTailPipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write_all(buf)?;
+        for line in String::from_utf8_lossy(buf).lines() {
+            if self.sender.try_send(line.to_string()).is_err() {
+                self.log_tail.record_dropped();
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// The logger of the process when the log tail is enabled, swapping its
+/// `env_logger` like `solana_logger` does when the filter changes
+struct LogTailLogger {
+    logger: RwLock<env_logger::Logger>,
+    sender: Sender<String>,
+    log_tail: Arc<LogTail>,
+}
+
+static LOG_TAIL_LOGGER: OnceLock<LogTailLogger> = OnceLock::new();
+
+impl LogTailLogger {
+    fn build(&self, env: env_logger::Env) -> env_logger::Logger {
+        build_logger(env, self.sender.clone(), self.log_tail.clone())
+    }
+}
+
+impl log::Log for LogTailLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.logger.read().unwrap().enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.logger.read().unwrap().log(record);
+    }
+
+    fn flush(&self) {}
+}
+
+fn build_logger(
+    env: env_logger::Env,
+    sender: Sender<String>,
+    log_tail: Arc<LogTail>,
+) -> env_logger::Logger {
+    env_logger::Builder::from_env(env)
+        .format_timestamp_nanos()
+        .target(env_logger::Target::Pipe(Box::new(LogTailPipe {
+            sender,
+            log_tail,
+        })))
+        .build()
+}
+
+fn spawn_collector(receiver: Receiver<String>, log_tail: Arc<LogTail>) {
+    Builder::new()
+        .name("solLogTail".to_string())
+        .spawn(move || {
+            for line in receiver {
+                log_tail.push(line);
+            }
+        })
+        .unwrap();
+}
+
+/// Configure logging with `solana_logger::DEFAULT_FILTER` if RUST_LOG is not
+/// set, copying the lines logged into `log_tail` if any
+pub fn setup_with_default_filter(log_tail: Option<Arc<LogTail>>) {
+    let Some(log_tail) = log_tail else {
+        solana_logger::setup_with_default_filter();
+        return;
+    };
+    let (sender, receiver) = bounded(LOG_TAIL_QUEUE_CAPACITY);
+    spawn_collector(receiver, log_tail.clone());
+    let logger = LOG_TAIL_LOGGER.get_or_init(|| LogTailLogger {
+        logger: RwLock::new(build_logger(
+            env_logger::Env::new().default_filter_or(solana_logger::DEFAULT_FILTER),
+            sender.clone(),
+            log_tail.clone(),
+        )),
+        sender,
+        log_tail,
+    });
+    log::set_max_level(logger.logger.read().unwrap().filter());
+    let _ = log::set_logger(logger);
+}
+
+/// Replace the log filter, like `solana_logger::setup_with()`
+pub fn setup_with(filter: &str) {
+    let Some(log_tail_logger) = LOG_TAIL_LOGGER.get() else {
+        solana_logger::setup_with(filter);
+        return;
+    };
+    let logger = log_tail_logger.build(env_logger::Env::new().filter_or("_RUST_LOG", filter));
+    log::set_max_level(logger.filter());
+    *log_tail_logger.logger.write().unwrap() = logger;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled_log_tail(capacity: usize, lines: impl IntoIterator<Item = usize>) -> LogTail {
+        let log_tail = LogTail::new(capacity);
+        for line in lines {
+            log_tail.push(format!("line {line}"));
+        }
+        log_tail
+    }
+
+    fn lines(lines: impl IntoIterator<Item = usize>) -> Vec<String> {
+        lines
+            .into_iter()
+            .map(|line| format!("line {line}"))
+            .collect()
+    }
+
+    #[test]
+    fn test_log_tail_ring_buffer() {
+        let log_tail = filled_log_tail(4, 0..3);
+        assert_eq!(
+            log_tail.read(10, None),
+            LogTailChunk {
+                lines: lines(0..3),
+                cursor: 3,
+                missed: 0,
+            }
+        );
+        assert_eq!(log_tail.read(2, None).lines, lines(1..3));
+
+        // the oldest lines are evicted once full
+        let log_tail = filled_log_tail(4, 0..10);
+        assert_eq!(
+            log_tail.read(10, None),
+            LogTailChunk {
+                lines: lines(6..10),
+                cursor: 10,
+                missed: 0,
+            }
+        );
+        assert_eq!(log_tail.read(0, None).lines, Vec::<String>::new());
+
+        // nothing is held without a capacity, but lines are still counted
+        let log_tail = filled_log_tail(0, 0..3);
+        assert_eq!(
+            log_tail.read(10, Some(1)),
+            LogTailChunk {
+                lines: vec![],
+                cursor: 3,
+                missed: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_log_tail_cursor() {
+        let log_tail = filled_log_tail(8, 0..3);
+        let chunk = log_tail.read(10, None);
+        assert_eq!(chunk.cursor, 3);
+
+        // nothing new
+        assert_eq!(
+            log_tail.read(10, Some(chunk.cursor)),
+            LogTailChunk {
+                lines: vec![],
+                cursor: 3,
+                missed: 0,
+            }
+        );
+
+        // only the lines logged since the cursor
+        for line in 3..7 {
+            log_tail.push(format!("line {line}"));
+        }
+        let chunk = log_tail.read(10, Some(chunk.cursor));
+        assert_eq!(
+            chunk,
+            LogTailChunk {
+                lines: lines(3..7),
+                cursor: 7,
+                missed: 0,
+            }
+        );
+
+        // in chunks of at most `max_lines`, oldest first
+        for line in 7..12 {
+            log_tail.push(format!("line {line}"));
+        }
+        let first = log_tail.read(3, Some(chunk.cursor));
+        assert_eq!(
+            first,
+            LogTailChunk {
+                lines: lines(7..10),
+                cursor: 10,
+                missed: 0,
+            }
+        );
+        let second = log_tail.read(3, Some(first.cursor));
+        assert_eq!(
+            second,
+            LogTailChunk {
+                lines: lines(10..12),
+                cursor: 12,
+                missed: 0,
+            }
+        );
+
+        // the lines evicted since the cursor are reported as missed
+        for line in 12..22 {
+            log_tail.push(format!("line {line}"));
+        }
+        assert_eq!(
+            log_tail.read(100, Some(second.cursor)),
+            LogTailChunk {
+                lines: lines(14..22),
+                cursor: 22,
+                missed: 2,
+            }
+        );
+
+        // as are the lines dropped by the writer
+        log_tail.record_dropped();
+        log_tail.record_dropped();
+        log_tail.push("line 24".to_string());
+        assert_eq!(
+            log_tail.read(100, Some(22)),
+            LogTailChunk {
+                lines: lines([24]),
+                cursor: 25,
+                missed: 2,
+            }
+        );
+
+        // a cursor from a previous run of the validator reads from the start
+        assert_eq!(log_tail.read(2, Some(1_000)).lines, lines(15..17));
+    }
+}
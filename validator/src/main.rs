@@ -3,7 +3,7 @@
 use jemallocator::Jemalloc;
 use {
     agave_validator::{
-        cli::{app, warn_for_deprecated_arguments, DefaultArgs},
+        cli::{build_cli_app, warn_for_deprecated_arguments, DefaultArgs},
         commands,
     },
     log::error,
@@ -18,7 +18,7 @@ static GLOBAL: Jemalloc = Jemalloc;
 pub fn main() {
     let default_args = DefaultArgs::new();
     let solana_version = solana_version::version!();
-    let cli_app = app(solana_version, &default_args);
+    let cli_app = build_cli_app(&default_args);
     let matches = cli_app.get_matches();
     warn_for_deprecated_arguments(&matches);
 
@@ -42,25 +42,49 @@ pub fn main() {
             commands::run::execute::Operation::Run,
         )
         .inspect_err(|err| error!("Failed to start validator: {err}")),
+        ("admin-rpc", Some(subcommand_matches)) => {
+            commands::admin_rpc::execute(subcommand_matches, &ledger_path)
+        }
         ("authorized-voter", Some(authorized_voter_subcommand_matches)) => {
             commands::authorized_voter::execute(authorized_voter_subcommand_matches, &ledger_path)
         }
+        ("batch-execute", Some(subcommand_matches)) => {
+            commands::batch_execute::execute(subcommand_matches, &ledger_path)
+        }
         ("plugin", Some(plugin_subcommand_matches)) => {
             commands::plugin::execute(plugin_subcommand_matches, &ledger_path)
         }
+        ("completions", Some(subcommand_matches)) => {
+            commands::completions::execute(subcommand_matches)
+        }
         ("contact-info", Some(subcommand_matches)) => {
             commands::contact_info::execute(subcommand_matches, &ledger_path)
         }
+        ("node-version", Some(subcommand_matches)) => {
+            commands::node_version::execute(subcommand_matches, &ledger_path)
+        }
         ("exit", Some(subcommand_matches)) => {
             commands::exit::execute(subcommand_matches, &ledger_path)
         }
+        ("get-recent-blocks", Some(subcommand_matches)) => {
+            commands::get_recent_blocks::execute(subcommand_matches, &ledger_path)
+        }
         ("monitor", _) => commands::monitor::execute(&matches, &ledger_path),
         ("staked-nodes-overrides", Some(subcommand_matches)) => {
             commands::staked_nodes_overrides::execute(subcommand_matches, &ledger_path)
         }
+        ("subscribe-events", Some(subcommand_matches)) => {
+            commands::subscribe_events::execute(subcommand_matches, &ledger_path)
+        }
         ("set-identity", Some(subcommand_matches)) => {
             commands::set_identity::execute(subcommand_matches, &ledger_path)
         }
+        ("refresh-gossip", Some(subcommand_matches)) => {
+            commands::refresh_gossip::execute(subcommand_matches, &ledger_path)
+        }
+        ("log-tail", Some(subcommand_matches)) => {
+            commands::log_tail::execute(subcommand_matches, &ledger_path)
+        }
         ("set-log-filter", Some(subcommand_matches)) => {
             commands::set_log_filter::execute(subcommand_matches, &ledger_path)
         }
@@ -70,12 +94,24 @@ pub fn main() {
         ("repair-shred-from-peer", Some(subcommand_matches)) => {
             commands::repair_shred_from_peer::execute(subcommand_matches, &ledger_path)
         }
+        ("repair-slot-status", Some(subcommand_matches)) => {
+            commands::repair_slot_status::execute(subcommand_matches, &ledger_path)
+        }
         ("repair-whitelist", Some(repair_whitelist_subcommand_matches)) => {
             commands::repair_whitelist::execute(repair_whitelist_subcommand_matches, &ledger_path)
         }
+        ("scheduler-state", Some(subcommand_matches)) => {
+            commands::scheduler_state::execute(subcommand_matches, &ledger_path)
+        }
+        ("set-admission-config", Some(subcommand_matches)) => {
+            commands::set_admission_config::execute(subcommand_matches, &ledger_path)
+        }
         ("set-public-address", Some(subcommand_matches)) => {
             commands::set_public_address::execute(subcommand_matches, &ledger_path)
         }
+        ("snapshot-hashes", Some(subcommand_matches)) => {
+            commands::snapshot_hashes::execute(subcommand_matches, &ledger_path)
+        }
         _ => unreachable!(),
     }
     .unwrap_or_else(|err| {